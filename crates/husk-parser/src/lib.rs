@@ -4440,10 +4440,8 @@ impl<'src> Parser<'src> {
                     }
                 }
                 InTemplateString { template_depth } => match ch {
-                    '`' if prev_ch != '\\' => {
-                        if template_depth == 0 {
-                            state = Normal;
-                        }
+                    '`' if prev_ch != '\\' && template_depth == 0 => {
+                        state = Normal;
                     }
                     '$' if next_ch == '{' => {
                         pos += 1; // skip {
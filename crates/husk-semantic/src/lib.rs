@@ -4351,15 +4351,15 @@ impl<'a> FnContext<'a> {
                         // Numeric format specifiers require i32
                         if let Some(ty_char) = ph.spec.ty {
                             match ty_char {
-                                'x' | 'X' | 'b' | 'o' => {
-                                    if !matches!(arg_ty, Type::Primitive(PrimitiveType::I32)) {
-                                        self.tcx.errors.push(SemanticError {
-                                            message: format!(
-                                                "format specifier `:{ty_char}` requires numeric type, found `{arg_ty:?}`"
-                                            ),
-                                            span: ph.span.clone(),
-                                        });
-                                    }
+                                'x' | 'X' | 'b' | 'o'
+                                    if !matches!(arg_ty, Type::Primitive(PrimitiveType::I32)) =>
+                                {
+                                    self.tcx.errors.push(SemanticError {
+                                        message: format!(
+                                            "format specifier `:{ty_char}` requires numeric type, found `{arg_ty:?}`"
+                                        ),
+                                        span: ph.span.clone(),
+                                    });
                                 }
                                 '?' => {
                                     // Debug format works with any type
@@ -4438,15 +4438,15 @@ impl<'a> FnContext<'a> {
                         && let Some(ty_char) = ph.spec.ty
                     {
                         match ty_char {
-                            'x' | 'X' | 'b' | 'o' => {
-                                if !matches!(arg_ty, Type::Primitive(PrimitiveType::I32)) {
-                                    self.tcx.errors.push(SemanticError {
-                                            message: format!(
-                                                "format specifier `:{ty_char}` requires numeric type, found `{arg_ty:?}`"
-                                            ),
-                                            span: ph.span.clone(),
-                                        });
-                                }
+                            'x' | 'X' | 'b' | 'o'
+                                if !matches!(arg_ty, Type::Primitive(PrimitiveType::I32)) =>
+                            {
+                                self.tcx.errors.push(SemanticError {
+                                    message: format!(
+                                        "format specifier `:{ty_char}` requires numeric type, found `{arg_ty:?}`"
+                                    ),
+                                    span: ph.span.clone(),
+                                });
                             }
                             '?' => {}
                             _ => {}
@@ -24,6 +24,7 @@ use serde::{Deserialize, Serialize};
 use crate::undo::{TextPosition, TextRange, UndoHistory};
 use crate::unicode_utils::{char_to_column, column_to_char, display_width, trim_line_ending};
 use crate::utils::{expand_user_path, normalized_file_path};
+use crate::vim_regex;
 
 #[cfg(not(unix))]
 use crate::utils::same_file_path;
@@ -455,12 +456,11 @@ impl Buffer {
             (x, y)
         };
 
-        regex
-            .find_iter(&contents)
-            .filter(|match_| match_.start() != match_.end())
-            .map(|match_| {
-                let (start_x, start_y) = position_at(match_.start());
-                let (end_x, end_y) = position_at(match_.end());
+        vim_regex::find_spans(regex, &contents)
+            .filter(|span| !span.is_empty())
+            .map(|span| {
+                let (start_x, start_y) = position_at(span.start);
+                let (end_x, end_y) = position_at(span.end);
                 SearchMatch {
                     start_x,
                     start_y,
@@ -484,30 +484,25 @@ impl Buffer {
         let origin_byte = self.content.char_to_byte(origin_char);
 
         let matched = if backward {
-            regex
-                .find_iter(&contents)
-                .filter(|matched| matched.start() != matched.end())
-                .take_while(|matched| matched.start() < origin_byte)
+            vim_regex::find_spans(regex, &contents)
+                .filter(|matched| !matched.is_empty())
+                .take_while(|matched| matched.start < origin_byte)
                 .last()
                 .or_else(|| {
                     wrap.then(|| {
-                        regex
-                            .find_iter(&contents)
-                            .filter(|matched| matched.start() != matched.end())
+                        vim_regex::find_spans(regex, &contents)
+                            .filter(|matched| !matched.is_empty())
                             .last()
                     })
                     .flatten()
                 })
         } else {
-            regex
-                .find_iter(&contents)
-                .filter(|matched| matched.start() != matched.end())
-                .find(|matched| matched.start() > origin_byte)
+            vim_regex::find_spans(regex, &contents)
+                .filter(|matched| !matched.is_empty())
+                .find(|matched| matched.start > origin_byte)
                 .or_else(|| {
                     wrap.then(|| {
-                        regex
-                            .find_iter(&contents)
-                            .find(|matched| matched.start() != matched.end())
+                        vim_regex::find_spans(regex, &contents).find(|matched| !matched.is_empty())
                     })
                     .flatten()
                 })
@@ -518,8 +513,8 @@ impl Buffer {
             let line = self.content.char_to_line(character);
             (character - self.content.line_to_char(line), line)
         };
-        let (start_x, start_y) = position(matched.start());
-        let (end_x, end_y) = position(matched.end());
+        let (start_x, start_y) = position(matched.start);
+        let (end_x, end_y) = position(matched.end);
         Some(SearchMatch {
             start_x,
             start_y,
@@ -541,7 +536,7 @@ impl Buffer {
             let contents = self.contents();
             std::fs::write(&path, &contents)?;
             self.file = Some(file.clone());
-            self.mark_saved();
+            self.mark_written();
             let message = format!("{:?} {}L, {}B written", file, self.len(), contents.len());
             Ok(message)
         } else {
@@ -556,7 +551,7 @@ impl Buffer {
         let contents = self.contents();
         std::fs::write(&path, &contents)?;
         self.file = Some(file.clone());
-        self.mark_saved();
+        self.mark_written();
         let message = format!("{:?} {}L, {}B written", file, self.len(), contents.len());
         Ok(message)
     }
@@ -1234,6 +1229,12 @@ impl Buffer {
         self.dirty_revision = self.revision;
     }
 
+    /// Marks the current contents saved after writing them to the buffer's file.
+    pub fn mark_written(&mut self) {
+        self.mark_saved();
+        self.undo_history.record_write();
+    }
+
     // Helper method to convert (x,y) coordinates to character index in the rope
     fn xy_to_char_idx(&self, x: usize, y: usize) -> usize {
         if y >= self.content.len_lines() {
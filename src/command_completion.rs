@@ -6,7 +6,7 @@
 use std::{fs, ops::Range, path::PathBuf};
 
 use crate::{
-    command, command_palette, copilot::CopilotCommand, plugin::RegisteredPluginCommand,
+    command, command_palette, copilot::CopilotCommand, ex_range, plugin::RegisteredPluginCommand,
     utils::expand_user_path,
 };
 
@@ -50,6 +50,8 @@ struct ArgumentContext<'a> {
 impl<'a> ArgumentContext<'a> {
     fn parse(line: &'a str) -> Option<Self> {
         let start = line.find(|ch: char| !ch.is_whitespace())?;
+        // Complete the command after a line range, as in `:'<,'>w file`.
+        let start = start + ex_range::split(&line[start..]).0.len();
         let end = line[start..]
             .find(char::is_whitespace)
             .map_or(line.len(), |offset| start + offset);
@@ -111,6 +113,8 @@ fn builtin_source(name: &str, argument_index: usize) -> Option<Source> {
         | "sp"
         | "vsplit"
         | "vs"
+        | "r"
+        | "read"
         | "InlineHistoryExport" => Some(Source::Files),
         _ if argument_index != 0 => None,
         "syntax" | "syn" | "ft" => Some(Source::Syntax),
@@ -425,7 +429,7 @@ mod tests {
         fs::write(directory.path().join("afile"), "").unwrap();
         for command in [
             "e", "ed", "edit", "w", "wr", "wri", "write", "sav", "saveas", "fi", "file", "new",
-            "vne", "vnew", "sp", "spl", "split", "vs", "vsp", "vsplit", "e!",
+            "vne", "vnew", "sp", "spl", "split", "vs", "vsp", "vsplit", "e!", "r", "read",
         ] {
             let mut state = None;
             let mut line = format!("{command} {}/", directory.path().display());
@@ -444,6 +448,17 @@ mod tests {
         assert_eq!(completed("e .."), "e ../");
     }
 
+    #[test]
+    fn completion_skips_a_leading_line_range() {
+        let directory = tempfile::tempdir().unwrap();
+        fs::write(directory.path().join("afile"), "").unwrap();
+        let mut line = format!("'<,'>w {}/a", directory.path().display());
+        tab(&mut None, &mut line, CompletionDirection::Next, &[]);
+        assert_eq!(line, format!("'<,'>w {}/afile", directory.path().display()));
+        assert_eq!(completed("1,5dele"), "1,5delete");
+        assert_eq!(completed(".,/end/-1norma"), ".,/end/-1normal");
+    }
+
     #[test]
     fn file_completion_keeps_paths_containing_spaces() {
         let directory = tempfile::tempdir().unwrap();
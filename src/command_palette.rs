@@ -8,7 +8,7 @@ use serde_json::json;
 use crate::{
     command::{self, CommandSpec},
     config::{KeyAction, Keys},
-    editor::{Action, Mode, SearchDirection},
+    editor::{Action, ArgumentPosition, Mode, SearchDirection},
     plugin::RegisteredPluginCommand,
     ui::{PickerIcon, PickerItem},
     unicode_utils::{display_width, truncate_display_width},
@@ -19,6 +19,7 @@ pub(crate) const BUILTIN_COLON_COMMANDS: &[CommandSpec] = &[
     CommandSpec::exact("$"),
     CommandSpec::new("quit", 1),
     CommandSpec::new("write", 1),
+    CommandSpec::new("update", 2),
     CommandSpec::exact("buffer-next"),
     CommandSpec::new("bnext", 2),
     CommandSpec::exact("buffer-prev"),
@@ -44,6 +45,13 @@ pub(crate) const BUILTIN_COLON_COMMANDS: &[CommandSpec] = &[
     CommandSpec::exact("vs"),
     CommandSpec::new("close", 3),
     CommandSpec::new("only", 2),
+    CommandSpec::exact("tabnew"),
+    CommandSpec::new("tabedit", 4),
+    CommandSpec::new("tabclose", 4),
+    CommandSpec::new("tabonly", 4),
+    CommandSpec::new("tabnext", 4),
+    CommandSpec::new("tabprevious", 4),
+    CommandSpec::new("tabmove", 4),
     CommandSpec::exact("panel-layout-reset"),
     CommandSpec::exact("noh"),
     CommandSpec::new("nohlsearch", 3),
@@ -85,9 +93,105 @@ const SPECIAL_BUILTIN_COLON_COMMANDS: &[&str] = &[
     "dt",
     "registers",
     "undotree",
+    "u",
+    "undo",
+    "red",
+    "redo",
+    "ea",
+    "earlier",
+    "lat",
+    "later",
     "register",
+    "map",
+    "noremap",
+    "unmap",
+    "nmap",
+    "nnoremap",
+    "nunmap",
+    "vmap",
+    "vnoremap",
+    "vunmap",
+    "xmap",
+    "xnoremap",
+    "xunmap",
+    "imap",
+    "inoremap",
+    "iunmap",
+    "cmap",
+    "cnoremap",
+    "cunmap",
+    "abbreviate",
+    "noreabbrev",
+    "unabbreviate",
+    "iabbrev",
+    "inoreabbrev",
+    "iunabbrev",
+    "cabbrev",
+    "cnoreabbrev",
+    "cunabbrev",
+    "args",
+    "next",
+    "Next",
+    "previous",
+    "first",
+    "rewind",
+    "last",
+    "argadd",
+    "argdelete",
+    "argdo",
+    "bufdo",
+    "windo",
+    "cdo",
+    "cnext",
+    "cNext",
+    "cprevious",
+    "cfirst",
+    "crewind",
+    "clast",
+    "cc",
+    "colder",
+    "cnewer",
+    "clist",
+    "copen",
+    "cclose",
+    "lnext",
+    "lNext",
+    "lprevious",
+    "lfirst",
+    "lrewind",
+    "llast",
+    "ll",
+    "lolder",
+    "lnewer",
+    "llist",
+    "lopen",
+    "lclose",
+    "make",
+    "lmake",
     "j",
     "join",
+    "g",
+    "global",
+    "v",
+    "vglobal",
+    "norm",
+    "normal",
+    "d",
+    "delete",
+    "y",
+    "yank",
+    "m",
+    "move",
+    "t",
+    "co",
+    "copy",
+    ">",
+    "<",
+    "sor",
+    "sort",
+    "r",
+    "read",
+    "SelectMatches",
 ];
 
 #[derive(Debug, Clone, PartialEq)]
@@ -748,6 +852,24 @@ fn builtin_commands() -> Vec<BuiltinCommand> {
             &["text object", "syntax motion"],
             Action::MoveToPreviousClass,
         ),
+        builtin(
+            "syntax.next_parameter",
+            "Go to next parameter",
+            "Editor",
+            "Jump to the next Tree-sitter parameter or argument",
+            None,
+            &["argument", "text object", "syntax motion"],
+            Action::MoveToNextParameter,
+        ),
+        builtin(
+            "syntax.previous_parameter",
+            "Go to previous parameter",
+            "Editor",
+            "Jump to the previous Tree-sitter parameter or argument",
+            None,
+            &["argument", "text object", "syntax motion"],
+            Action::MoveToPreviousParameter,
+        ),
         builtin(
             "syntax.swap_next_parameter",
             "Swap with next parameter",
@@ -874,6 +996,33 @@ fn builtin_commands() -> Vec<BuiltinCommand> {
             &[":set nornu"],
             Action::SetRelativeLineNumbers(false),
         ),
+        builtin(
+            "view.enable_spell",
+            "Enable spell checking",
+            "View",
+            "Underline misspelled words in comments, strings, and prose",
+            Some(":set spell"),
+            &["spelling", "dictionary"],
+            Action::SetSpell(true),
+        ),
+        builtin(
+            "view.disable_spell",
+            "Disable spell checking",
+            "View",
+            "Stop underlining misspelled words",
+            Some(":set nospell"),
+            &["spelling"],
+            Action::SetSpell(false),
+        ),
+        builtin(
+            "edit.suggest_spelling",
+            "Suggest spelling",
+            "Edit",
+            "Pick a replacement for the misspelled word under the cursor",
+            None,
+            &["spelling", "correct word", "z="],
+            Action::SuggestSpelling,
+        ),
         builtin(
             "view.syntax",
             "Set syntax",
@@ -937,6 +1086,51 @@ fn builtin_commands() -> Vec<BuiltinCommand> {
             &[],
             Action::OnlyWindow,
         ),
+        builtin(
+            "tab.new",
+            "New tab page",
+            "Window",
+            "Open a tab page with its own window layout",
+            Some(":tabnew"),
+            &[":tabedit"],
+            Action::NewTabPage(None),
+        ),
+        builtin(
+            "tab.close",
+            "Close tab page",
+            "Window",
+            "Close the current tab page and its windows",
+            Some(":tabclose"),
+            &[],
+            Action::CloseTabPage,
+        ),
+        builtin(
+            "tab.only",
+            "Keep only current tab page",
+            "Window",
+            "Close every other tab page",
+            Some(":tabonly"),
+            &[],
+            Action::OnlyTabPage,
+        ),
+        builtin(
+            "tab.next",
+            "Next tab page",
+            "Window",
+            "Go to the next tab page",
+            Some(":tabnext"),
+            &[],
+            Action::NextTabPage,
+        ),
+        builtin(
+            "tab.previous",
+            "Previous tab page",
+            "Window",
+            "Go to the previous tab page",
+            Some(":tabprevious"),
+            &[],
+            Action::PreviousTabPage,
+        ),
         builtin(
             "window.next",
             "Next window",
@@ -1395,7 +1589,7 @@ fn key_action_is(action: &KeyAction, target: &Action) -> bool {
         )
 }
 
-fn key_action_label(action: &KeyAction) -> Option<String> {
+pub(crate) fn key_action_label(action: &KeyAction) -> Option<String> {
     match action {
         KeyAction::None => None,
         KeyAction::Single(action) => Some(action_label(action)),
@@ -1445,9 +1639,23 @@ fn action_label(action: &Action) -> String {
         Action::Save => "Save file".to_string(),
         Action::NewBuffer => "New buffer".to_string(),
         Action::ListBuffers => "List buffers".to_string(),
+        Action::ListArguments => "Show argument list".to_string(),
+        Action::EditArgument(position) => match position {
+            ArgumentPosition::Next => "Next argument",
+            ArgumentPosition::Previous => "Previous argument",
+            ArgumentPosition::First => "First argument",
+            ArgumentPosition::Last => "Last argument",
+        }
+        .to_string(),
+        Action::NextQuickfixEntry => "Next quickfix entry".to_string(),
+        Action::PreviousQuickfixEntry => "Previous quickfix entry".to_string(),
         Action::Quit(_) => "Quit".to_string(),
         Action::Undo => "Undo".to_string(),
+        Action::FeedKeys { keys, .. } => format!("Type {keys}"),
         Action::Redo => "Redo".to_string(),
+        Action::UndoEarlier(_) => "Earlier text state".to_string(),
+        Action::UndoLater(_) => "Later text state".to_string(),
+        Action::UndoToChange(change) => format!("Go to undo change {change}"),
         Action::StartCommentOperator(_) => "Comment with motion".to_string(),
         Action::ToggleCommentLines(_) => "Toggle line comments".to_string(),
         Action::ToggleCommentRange(_) => "Toggle range comments".to_string(),
@@ -1455,6 +1663,7 @@ fn action_label(action: &Action) -> String {
         Action::StartFormatOperator(_) => "Format with motion".to_string(),
         Action::FormatTextRange(_) => "Format text range".to_string(),
         Action::FormatSelection => "Format selected text".to_string(),
+        Action::StartFilterOperator(_) => "Filter with motion".to_string(),
         Action::RepeatLastChange => "Repeat last change".to_string(),
         Action::MoveToNextParagraph => "Go to next paragraph".to_string(),
         Action::MoveToPreviousParagraph => "Go to previous paragraph".to_string(),
@@ -1466,6 +1675,8 @@ fn action_label(action: &Action) -> String {
         Action::MoveToPreviousFunction => "Go to previous function".to_string(),
         Action::MoveToNextClass => "Go to next class".to_string(),
         Action::MoveToPreviousClass => "Go to previous class".to_string(),
+        Action::MoveToNextParameter => "Go to next parameter".to_string(),
+        Action::MoveToPreviousParameter => "Go to previous parameter".to_string(),
         Action::SwapNextParameter => "Swap with next parameter".to_string(),
         Action::SwapPreviousParameter => "Swap with previous parameter".to_string(),
         Action::SwapNextFunction => "Swap with next function".to_string(),
@@ -1482,6 +1693,11 @@ fn action_label(action: &Action) -> String {
         Action::ShowLineDiagnostics => "Show line diagnostics".to_string(),
         Action::NextDiagnostic => "Go to next diagnostic".to_string(),
         Action::PreviousDiagnostic => "Go to previous diagnostic".to_string(),
+        Action::NextMisspelledWord => "Go to next misspelled word".to_string(),
+        Action::PreviousMisspelledWord => "Go to previous misspelled word".to_string(),
+        Action::SuggestSpelling => "Suggest spelling".to_string(),
+        Action::MarkWordGood => "Add word to the good words".to_string(),
+        Action::MarkWordWrong => "Add word to the wrong words".to_string(),
         Action::SignatureHelp => "Show signature help".to_string(),
         Action::ClearSearchHighlight => "Clear search highlights".to_string(),
         Action::ToggleWrap => "Toggle line wrapping".to_string(),
@@ -1491,6 +1707,11 @@ fn action_label(action: &Action) -> String {
         Action::SplitVerticalNewBuffer => "New buffer in vertical split".to_string(),
         Action::CloseWindow => "Close window".to_string(),
         Action::OnlyWindow => "Keep only current window".to_string(),
+        Action::NewTabPage(_) => "New tab page".to_string(),
+        Action::CloseTabPage => "Close tab page".to_string(),
+        Action::OnlyTabPage => "Keep only current tab page".to_string(),
+        Action::NextTabPage => "Next tab page".to_string(),
+        Action::PreviousTabPage => "Previous tab page".to_string(),
         Action::BalanceWindows => "Balance windows".to_string(),
         Action::ResetPanelLayout(_) => "Reset panel layout".to_string(),
         Action::MaximizeWindow => "Maximize window".to_string(),
@@ -1877,6 +2098,12 @@ mod tests {
             ),
             ("syntax.next_class", "] c", Action::MoveToNextClass),
             ("syntax.previous_class", "[ c", Action::MoveToPreviousClass),
+            ("syntax.next_parameter", "] a", Action::MoveToNextParameter),
+            (
+                "syntax.previous_parameter",
+                "[ a",
+                Action::MoveToPreviousParameter,
+            ),
             (
                 "syntax.swap_next_parameter",
                 "Space ] a",
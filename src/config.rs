@@ -288,6 +288,24 @@ pub struct Config {
     /// Matching-token navigation.
     #[serde(default)]
     pub matchit: MatchitConfig,
+    /// Formats `Ctrl-a` and `Ctrl-x` recognize besides decimal, hex, and binary numbers.
+    #[serde(default)]
+    pub increment: IncrementConfig,
+    /// Per-file undo history kept across editor restarts.
+    #[serde(default)]
+    pub undofile: UndoFileConfig,
+    /// Spell checking of comments, strings, and prose.
+    #[serde(default)]
+    pub spell: SpellConfig,
+    /// Options for `:map` and `:abbreviate` commands run in the editor.
+    #[serde(default)]
+    pub mappings: MappingsConfig,
+    /// Build commands `:make` runs and the formats that read their output.
+    #[serde(default)]
+    pub make: MakeConfig,
+    /// Words replaced as they are typed, like Vim's `:iabbrev` and `:cabbrev`.
+    #[serde(default)]
+    pub abbreviations: AbbreviationsConfig,
     /// Disable every agent surface, adapter check, and process launch.
     #[serde(default = "default_false")]
     pub disable_ai: bool,
@@ -306,9 +324,10 @@ pub struct Config {
     /// Whether split borders use only ASCII characters.
     #[serde(default = "default_false")]
     pub window_borders_ascii: bool,
-    /// Number of files supplied at startup; runtime-only context.
+    /// Files named on the command line, which seed the argument list;
+    /// runtime-only context.
     #[serde(default, skip_serializing)]
-    pub startup_file_count: usize,
+    pub startup_files: Vec<String>,
     /// Whether startup already restored a core-owned recovery snapshot.
     #[serde(default, skip_serializing)]
     pub startup_session_resumed: bool,
@@ -755,6 +774,160 @@ pub struct PluginPermissions {
     pub process: Vec<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+/// Optional `Ctrl-a`/`Ctrl-x` targets.
+pub struct IncrementConfig {
+    /// Numbers with a leading zero are octal, like Vim's `nrformats+=octal`.
+    #[serde(default = "default_false")]
+    pub octal: bool,
+    /// Cycle `true` and `false`.
+    #[serde(default = "default_false")]
+    pub booleans: bool,
+    /// Cycle full and three-letter weekday names.
+    #[serde(default = "default_false")]
+    pub weekdays: bool,
+    /// Step ISO `YYYY-MM-DD` dates by the year, month, or day under the cursor.
+    #[serde(default = "default_false")]
+    pub dates: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+/// Per-file undo trees persisted on every write, like Vim's `'undofile'`.
+pub struct UndoFileConfig {
+    /// Store each file's undo tree when it is written and reload it on open.
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+    /// Largest undo file written; older history is dropped to fit.
+    #[serde(default = "default_undofile_max_bytes")]
+    pub max_bytes: u64,
+    /// Days after which an unused undo file is discarded.
+    #[serde(default = "default_undofile_max_age_days")]
+    pub max_age_days: u64,
+}
+
+impl Default for UndoFileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: default_undofile_max_bytes(),
+            max_age_days: default_undofile_max_age_days(),
+        }
+    }
+}
+
+fn default_undofile_max_bytes() -> u64 {
+    4 * 1024 * 1024
+}
+
+fn default_undofile_max_age_days() -> u64 {
+    90
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+/// Spell checking, like Vim's `'spell'` and `'spelllang'`.
+pub struct SpellConfig {
+    /// Underline misspelled words in comments, strings, Markdown, and plain text.
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+    /// Hunspell dictionaries to check against, loaded from `{name}.aff` and
+    /// `{name}.dic` in the `spell` config directory.
+    #[serde(default = "default_spell_languages")]
+    pub languages: Vec<String>,
+}
+
+impl Default for SpellConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            languages: default_spell_languages(),
+        }
+    }
+}
+
+fn default_spell_languages() -> Vec<String> {
+    vec!["en_US".to_string()]
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+/// `:make`, like Vim's `'makeprg'` and `'errorformat'`. Unset fields fall back to
+/// the built-in choice for the buffer's language.
+pub struct MakeConfig {
+    /// Command run from the workspace root; `$*` stands for `:make`'s arguments,
+    /// which are otherwise appended.
+    #[serde(default)]
+    pub makeprg: Option<String>,
+    /// Built-in format names or `%f:%l:%c: %m` patterns tried on each output line.
+    #[serde(default)]
+    pub errorformat: Vec<String>,
+    /// Overrides keyed by language id.
+    #[serde(default)]
+    pub languages: HashMap<String, MakeLanguageConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+/// A language's `:make` command and output formats.
+pub struct MakeLanguageConfig {
+    #[serde(default)]
+    pub makeprg: Option<String>,
+    #[serde(default)]
+    pub errorformat: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+/// Runtime mapping commands.
+pub struct MappingsConfig {
+    /// Key notation that `<leader>` stands for in `:map` commands.
+    #[serde(default = "default_mapping_leader")]
+    pub leader: String,
+    /// Write mappings and abbreviations made at runtime back to `config.toml`.
+    #[serde(default = "default_false")]
+    pub persist: bool,
+}
+
+impl Default for MappingsConfig {
+    fn default() -> Self {
+        Self {
+            leader: default_mapping_leader(),
+            persist: false,
+        }
+    }
+}
+
+fn default_mapping_leader() -> String {
+    "<Space>".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+/// Abbreviations expanded when a non-keyword character follows them.
+pub struct AbbreviationsConfig {
+    /// Insert-mode abbreviations.
+    #[serde(default)]
+    pub insert: HashMap<String, String>,
+    /// Command-line abbreviations.
+    #[serde(default)]
+    pub command: HashMap<String, String>,
+}
+
+impl AbbreviationsConfig {
+    /// Returns the abbreviations of Insert (`KeyMode::Insert`) or Command mode.
+    pub fn for_mode(&self, mode: KeyMode) -> Option<&HashMap<String, String>> {
+        match mode {
+            KeyMode::Insert => Some(&self.insert),
+            KeyMode::Command => Some(&self.command),
+            _ => None,
+        }
+    }
+
+    /// Mutable form of [`Self::for_mode`].
+    pub fn for_mode_mut(&mut self, mode: KeyMode) -> Option<&mut HashMap<String, String>> {
+        match mode {
+            KeyMode::Insert => Some(&mut self.insert),
+            KeyMode::Command => Some(&mut self.command),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 /// Matching-token navigation configuration.
 pub struct MatchitConfig {
@@ -813,6 +986,20 @@ pub struct SearchConfig {
     /// Restore case sensitivity when the query contains uppercase characters.
     #[serde(default = "default_false")]
     pub smartcase: bool,
+    /// Pattern syntax for search, `:s`, `:g` and `:sort`.
+    #[serde(default)]
+    pub dialect: SearchDialect,
+}
+
+/// Pattern syntax for search and substitute.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchDialect {
+    /// The `regex` crate's syntax.
+    #[default]
+    Rust,
+    /// Vim patterns, translated for the `regex` crate.
+    Vim,
 }
 
 impl Default for SearchConfig {
@@ -823,6 +1010,7 @@ impl Default for SearchConfig {
             wrapscan: true,
             ignorecase: false,
             smartcase: false,
+            dialect: SearchDialect::Rust,
         }
     }
 }
@@ -938,6 +1126,9 @@ pub struct CursorConfig {
     /// Insert mode.
     #[serde(default = "cursor_shape_steady_bar")]
     pub insert: CursorShape,
+    /// Replace and virtual replace modes.
+    #[serde(default = "cursor_shape_steady_underscore")]
+    pub replace: CursorShape,
     /// Command-line mode.
     #[serde(default)]
     pub command: CursorShape,
@@ -963,6 +1154,7 @@ impl Default for CursorConfig {
         Self {
             normal: CursorShape::Default,
             insert: CursorShape::SteadyBar,
+            replace: CursorShape::SteadyUnderscore,
             command: CursorShape::Default,
             search: CursorShape::Default,
             visual: CursorShape::Default,
@@ -1860,36 +2052,43 @@ impl Config {
 
     /// Saves Copilot consent without replacing unrelated settings or a config symlink.
     pub(crate) fn persist_copilot_enabled(path: &Path, enabled: bool) -> anyhow::Result<()> {
-        use std::io::Write;
+        update_config_file(path, |contents| {
+            update_copilot_config_contents(contents, enabled)
+        })
+    }
 
-        let target = match fs::symlink_metadata(path) {
-            Ok(_) => fs::canonicalize(path)?,
-            Err(error) if error.kind() == io::ErrorKind::NotFound => path.to_path_buf(),
-            Err(error) => return Err(error.into()),
-        };
-        let contents = match fs::read_to_string(&target) {
-            Ok(contents) => contents,
-            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
-            Err(error) => return Err(error.into()),
-        };
-        let updated = update_copilot_config_contents(&contents, enabled)?;
-        if updated == contents {
-            return Ok(());
-        }
-        let parent = target
-            .parent()
-            .filter(|path| !path.as_os_str().is_empty())
-            .unwrap_or_else(|| Path::new("."));
-        fs::create_dir_all(parent)?;
-        let mut temporary = tempfile::NamedTempFile::new_in(parent)?;
-        if let Ok(metadata) = fs::metadata(&target) {
-            temporary
-                .as_file()
-                .set_permissions(metadata.permissions())?;
-        }
-        temporary.write_all(updated.as_bytes())?;
-        temporary.persist(&target)?;
-        Ok(())
+    /// Saves a runtime key mapping under `[keys.{mode}]`, or removes it for `None`,
+    /// keeping the comments and layout of the rest of the file.
+    pub(crate) fn persist_key_mapping(
+        path: &Path,
+        mode: KeyMode,
+        keys: &[String],
+        action: Option<&KeyAction>,
+    ) -> anyhow::Result<()> {
+        update_config_file(path, |contents| {
+            update_key_mapping_contents(contents, mode, keys, action)
+        })
+    }
+
+    /// Saves an abbreviation under `[abbreviations]`, or removes it for `None`.
+    pub(crate) fn persist_abbreviation(
+        path: &Path,
+        mode: KeyMode,
+        abbreviation: &str,
+        expansion: Option<&str>,
+    ) -> anyhow::Result<()> {
+        update_config_file(path, |contents| {
+            update_abbreviation_contents(contents, mode, abbreviation, expansion)
+        })
+    }
+
+    /// The keymap of the bundled `default_config.toml`, without user changes.
+    pub fn bundled_keys() -> Keys {
+        toml::from_str::<toml::Value>(assets::DEFAULT_CONFIG)
+            .ok()
+            .and_then(|value| value.get("keys").cloned())
+            .and_then(|keys| keys.try_into().ok())
+            .unwrap_or_default()
     }
 
     /// Resolves a plugin path or bundled-plugin specifier for runtime loading.
@@ -2064,6 +2263,12 @@ fn known_top_level_field(field: &str) -> bool {
             | "diagnostics"
             | "show_diagnostics"
             | "window_borders_ascii"
+            | "increment"
+            | "undofile"
+            | "spell"
+            | "mappings"
+            | "abbreviations"
+            | "make"
     )
 }
 
@@ -2211,7 +2416,7 @@ fn known_schema_path(path: &[String]) -> bool {
         ),
         ["search", field] => matches!(
             *field,
-            "incsearch" | "hlsearch" | "wrapscan" | "ignorecase" | "smartcase"
+            "incsearch" | "hlsearch" | "wrapscan" | "ignorecase" | "smartcase" | "dialect"
         ),
         ["completion", field] => matches!(
             *field,
@@ -2325,6 +2530,15 @@ fn known_schema_path(path: &[String]) -> bool {
         ["commenting", "languages", _] => true,
         ["matchit", field] => matches!(*field, "enabled" | "pairs" | "languages"),
         ["matchit", "languages", _] | ["matchit", "languages", _, "groups"] => true,
+        ["increment", field] => matches!(*field, "octal" | "booleans" | "weekdays" | "dates"),
+        ["undofile", field] => matches!(*field, "enabled" | "max_bytes" | "max_age_days"),
+        ["spell", field] => matches!(*field, "enabled" | "languages"),
+        ["mappings", field] => matches!(*field, "leader" | "persist"),
+        ["abbreviations", field] => matches!(*field, "insert" | "command"),
+        ["abbreviations", _, _] => true,
+        ["make", field] => matches!(*field, "makeprg" | "errorformat" | "languages"),
+        ["make", "languages", _] => true,
+        ["make", "languages", _, field] => matches!(*field, "makeprg" | "errorformat"),
         _ => false,
     }
 }
@@ -2905,6 +3119,43 @@ fn update_statusline_config_contents(
     Ok(document.to_string())
 }
 
+/// Rewrites `path` through `update`, atomically and through a config symlink.
+fn update_config_file(
+    path: &Path,
+    update: impl FnOnce(&str) -> anyhow::Result<String>,
+) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let target = match fs::symlink_metadata(path) {
+        Ok(_) => fs::canonicalize(path)?,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => path.to_path_buf(),
+        Err(error) => return Err(error.into()),
+    };
+    let contents = match fs::read_to_string(&target) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error.into()),
+    };
+    let updated = update(&contents)?;
+    if updated == contents {
+        return Ok(());
+    }
+    let parent = target
+        .parent()
+        .filter(|path| !path.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent)?;
+    let mut temporary = tempfile::NamedTempFile::new_in(parent)?;
+    if let Ok(metadata) = fs::metadata(&target) {
+        temporary
+            .as_file()
+            .set_permissions(metadata.permissions())?;
+    }
+    temporary.write_all(updated.as_bytes())?;
+    temporary.persist(&target)?;
+    Ok(())
+}
+
 fn update_copilot_config_contents(contents: &str, enabled: bool) -> anyhow::Result<String> {
     use anyhow::Context;
     use toml_edit::{DocumentMut, Item, Table, Value};
@@ -2931,6 +3182,155 @@ fn update_copilot_config_contents(contents: &str, enabled: bool) -> anyhow::Resu
     Ok(document.to_string())
 }
 
+fn update_key_mapping_contents(
+    contents: &str,
+    mode: KeyMode,
+    keys: &[String],
+    action: Option<&KeyAction>,
+) -> anyhow::Result<String> {
+    use anyhow::Context;
+    use toml_edit::DocumentMut;
+
+    let mut document = contents
+        .parse::<DocumentMut>()
+        .context("could not update config.toml")?;
+    let mut path = vec!["keys".to_string(), mode.table_name().to_string()];
+    path.extend(keys.iter().cloned());
+    match action {
+        Some(action) => {
+            let value = toml::Value::try_from(action)
+                .context("could not update config.toml: the mapping has no TOML form")?;
+            set_config_path(
+                document.as_table_mut(),
+                false,
+                &path,
+                toml_edit_value(&value),
+            )?;
+        }
+        None => {
+            remove_config_path(document.as_table_mut(), &path);
+        }
+    }
+    Ok(document.to_string())
+}
+
+fn update_abbreviation_contents(
+    contents: &str,
+    mode: KeyMode,
+    abbreviation: &str,
+    expansion: Option<&str>,
+) -> anyhow::Result<String> {
+    use anyhow::Context;
+    use toml_edit::DocumentMut;
+
+    let mut document = contents
+        .parse::<DocumentMut>()
+        .context("could not update config.toml")?;
+    let path = [
+        "abbreviations".to_string(),
+        mode.table_name().to_string(),
+        abbreviation.to_string(),
+    ];
+    match expansion {
+        Some(expansion) => {
+            set_config_path(document.as_table_mut(), false, &path, expansion.into())?
+        }
+        None => {
+            remove_config_path(document.as_table_mut(), &path);
+        }
+    }
+    Ok(document.to_string())
+}
+
+/// Sets the dotted `path` below `table`, creating the tables on the way. Keys
+/// nested below an inline table stay inline.
+fn set_config_path(
+    table: &mut dyn toml_edit::TableLike,
+    inline: bool,
+    path: &[String],
+    value: toml_edit::Value,
+) -> anyhow::Result<()> {
+    use toml_edit::{InlineTable, Item, Table};
+
+    let [key, rest @ ..] = path else {
+        return Ok(());
+    };
+    if rest.is_empty() {
+        match table.get_mut(key).and_then(Item::as_value_mut) {
+            Some(existing) => {
+                let mut replacement = value;
+                *replacement.decor_mut() = existing.decor().clone();
+                *existing = replacement;
+            }
+            None => {
+                table.insert(key, Item::Value(value));
+            }
+        }
+        return Ok(());
+    }
+    if let Some(item) = table.get(key) {
+        anyhow::ensure!(
+            item.is_table_like(),
+            "could not update config.toml: {key} is not a table"
+        );
+    } else {
+        let child = if inline {
+            Item::Value(InlineTable::new().into())
+        } else {
+            let mut child = Table::new();
+            child.set_implicit(true);
+            Item::Table(child)
+        };
+        table.insert(key, child);
+    }
+    let child = table.get_mut(key).expect("the table was just ensured");
+    let inline = inline || child.is_inline_table();
+    let child = child
+        .as_table_like_mut()
+        .expect("the table was just ensured");
+    set_config_path(child, inline, rest, value)
+}
+
+/// Removes the dotted `path` below `table` along with the tables it empties.
+fn remove_config_path(table: &mut dyn toml_edit::TableLike, path: &[String]) -> bool {
+    let [key, rest @ ..] = path else {
+        return false;
+    };
+    if rest.is_empty() {
+        return table.remove(key).is_some();
+    }
+    let Some(child) = table
+        .get_mut(key)
+        .and_then(toml_edit::Item::as_table_like_mut)
+    else {
+        return false;
+    };
+    let removed = remove_config_path(child, rest);
+    if removed && child.is_empty() {
+        table.remove(key);
+    }
+    removed
+}
+
+fn toml_edit_value(value: &toml::Value) -> toml_edit::Value {
+    match value {
+        toml::Value::String(text) => text.as_str().into(),
+        toml::Value::Integer(number) => (*number).into(),
+        toml::Value::Float(number) => (*number).into(),
+        toml::Value::Boolean(flag) => (*flag).into(),
+        toml::Value::Datetime(datetime) => (*datetime).into(),
+        toml::Value::Array(items) => {
+            toml_edit::Value::Array(items.iter().map(toml_edit_value).collect())
+        }
+        toml::Value::Table(table) => toml_edit::Value::InlineTable(
+            table
+                .iter()
+                .map(|(key, value)| (key.as_str(), toml_edit_value(value)))
+                .collect(),
+        ),
+    }
+}
+
 fn is_theme_assignment(line: &str) -> bool {
     let line = line.trim_start();
     if line.starts_with('#') {
@@ -2972,7 +3372,7 @@ pub enum KeyAction {
     Repeating(u16, Box<KeyAction>),
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
 /// Key mappings grouped by editor mode.
 pub struct Keys {
     /// Normal-mode mappings.
@@ -2995,6 +3395,57 @@ pub struct Keys {
     pub visual_block: HashMap<String, KeyAction>,
 }
 
+/// One of the [`Keys`] tables.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyMode {
+    Normal,
+    Insert,
+    Command,
+    Visual,
+    VisualLine,
+    VisualBlock,
+}
+
+impl KeyMode {
+    /// The name of the mode's table under `[keys]`.
+    pub fn table_name(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Insert => "insert",
+            Self::Command => "command",
+            Self::Visual => "visual",
+            Self::VisualLine => "visual_line",
+            Self::VisualBlock => "visual_block",
+        }
+    }
+}
+
+impl Keys {
+    /// Returns the mappings of `mode`.
+    pub fn for_mode(&self, mode: KeyMode) -> &HashMap<String, KeyAction> {
+        match mode {
+            KeyMode::Normal => &self.normal,
+            KeyMode::Insert => &self.insert,
+            KeyMode::Command => &self.command,
+            KeyMode::Visual => &self.visual,
+            KeyMode::VisualLine => &self.visual_line,
+            KeyMode::VisualBlock => &self.visual_block,
+        }
+    }
+
+    /// Mutable form of [`Self::for_mode`].
+    pub fn for_mode_mut(&mut self, mode: KeyMode) -> &mut HashMap<String, KeyAction> {
+        match mode {
+            KeyMode::Normal => &mut self.normal,
+            KeyMode::Insert => &mut self.insert,
+            KeyMode::Command => &mut self.command,
+            KeyMode::Visual => &mut self.visual,
+            KeyMode::VisualLine => &mut self.visual_line,
+            KeyMode::VisualBlock => &mut self.visual_block,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::editor::{Action, Mode, SearchDirection};
@@ -3118,6 +3569,7 @@ wrap = false
 [search]
 ignorecase = true
 smartcase = false
+dialect = "vim"
 
 [completion]
 enabled = true
@@ -3141,6 +3593,7 @@ delay_ms = 75
         assert_eq!(loaded.config.wrap, Some(false));
         assert!(loaded.config.search.ignorecase);
         assert!(!loaded.config.search.smartcase);
+        assert_eq!(loaded.config.search.dialect, SearchDialect::Vim);
         assert!(!loaded.config.completion.auto_trigger);
         assert_eq!(loaded.config.completion.max_buffer_words, 4096);
         assert!(!loaded.config.formatting.on_save);
@@ -3562,6 +4015,55 @@ color = false
         }
     }
 
+    #[test]
+    fn update_key_mapping_config_keeps_comments_and_prunes_emptied_tables() {
+        let contents = "# my keys\n[keys.normal] # mine\nx = 'DeleteChar' # keep\n\n[keys.insert]\nj = { l = 'MoveRight' }\n";
+        let action = KeyAction::Single(Action::FeedKeys {
+            keys: "<Esc>".to_string(),
+            remap: false,
+        });
+        let keys = ["j".to_string(), "k".to_string()];
+        let updated =
+            update_key_mapping_contents(contents, KeyMode::Insert, &keys, Some(&action)).unwrap();
+        assert!(updated.starts_with("# my keys\n[keys.normal] # mine\nx = 'DeleteChar' # keep\n"));
+        let config = Config::from_user_toml_with_overrides(&updated, &[]).unwrap();
+        let Some(KeyAction::Nested(nested)) = config.keys.insert.get("j") else {
+            panic!("{updated}");
+        };
+        assert_eq!(nested.get("k"), Some(&action));
+        assert!(nested.contains_key("l"));
+
+        let updated = update_key_mapping_contents(&updated, KeyMode::Insert, &keys, None).unwrap();
+        assert_eq!(updated, contents);
+        let space = [" ".to_string(), "w".to_string()];
+        let updated =
+            update_key_mapping_contents(&updated, KeyMode::Normal, &space, Some(&action)).unwrap();
+        let updated = update_key_mapping_contents(&updated, KeyMode::Normal, &space, None).unwrap();
+        assert_eq!(updated, contents);
+        assert!(update_key_mapping_contents(
+            contents,
+            KeyMode::Normal,
+            &["x".to_string(), "y".to_string()],
+            Some(&action)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn update_abbreviation_config_round_trips() {
+        let contents = "[mappings]\nleader = ','\n";
+        let updated =
+            update_abbreviation_contents(contents, KeyMode::Insert, "teh", Some("the")).unwrap();
+        let config = Config::from_user_toml_with_overrides(&updated, &[]).unwrap();
+        assert_eq!(config.mappings.leader, ",");
+        assert_eq!(config.abbreviations.insert["teh"], "the");
+        assert!(config.abbreviations.command.is_empty());
+        assert_eq!(
+            update_abbreviation_contents(&updated, KeyMode::Insert, "teh", None).unwrap(),
+            contents
+        );
+    }
+
     #[test]
     fn persist_copilot_config_creates_and_reloads_user_setting() {
         let directory = tempfile::tempdir().unwrap();
@@ -4746,6 +5248,7 @@ theme = "theme/nightfox.json"
 
         assert_eq!(config.cursor.normal, CursorShape::Default);
         assert_eq!(config.cursor.insert, CursorShape::SteadyBar);
+        assert_eq!(config.cursor.replace, CursorShape::SteadyUnderscore);
         assert_eq!(config.cursor.command, CursorShape::Default);
         assert_eq!(config.cursor.search, CursorShape::Default);
         assert_eq!(config.cursor.visual, CursorShape::Default);
@@ -50,6 +50,24 @@ pub enum TextObjectKind {
     },
     /// A same-line, unescaped quoted string.
     Quote(char),
+    /// Lines at the cursor line's indentation or deeper.
+    Indentation {
+        /// Whether the around object also takes the line that closes the level.
+        below: bool,
+    },
+    /// The whole buffer, or its lines between leading and trailing blank lines.
+    Entire,
+}
+
+impl TextObjectKind {
+    /// Whether operators and Visual selections treat the object as whole lines.
+    #[must_use]
+    pub const fn is_linewise(self) -> bool {
+        matches!(
+            self,
+            Self::Paragraph | Self::Indentation { .. } | Self::Entire
+        )
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -673,6 +691,10 @@ impl<'buffer> MotionResolver<'buffer> {
                 self.delimited_text_object(scope, open, close)
             }
             TextObjectKind::Quote(quote) => self.quote_text_object(scope, quote),
+            TextObjectKind::Indentation { below } => {
+                self.indentation_text_object(scope, below, count)
+            }
+            TextObjectKind::Entire => self.entire_text_object(scope),
         }
     }
 
@@ -1052,6 +1074,107 @@ impl<'buffer> MotionResolver<'buffer> {
         (start != end).then(|| TextRange::new(start, end))
     }
 
+    /// Lines at the cursor line's indentation or deeper, with blank lines inside
+    /// the level but not at its edges. A blank cursor line uses the next
+    /// non-blank line. `Around` adds the nearest line above with less
+    /// indentation, and `below` also the nearest such line below. Each extra
+    /// count widens the object to the enclosing level.
+    fn indentation_text_object(
+        &self,
+        scope: TextObjectScope,
+        below: bool,
+        count: u16,
+    ) -> Option<TextRange> {
+        if self.buffer.is_empty() {
+            return None;
+        }
+
+        let last_line = self.buffer.last_navigable_line();
+        let indent = |line: usize| {
+            let contents = self.buffer.get(line)?;
+            let contents = trim_line_ending(&contents);
+            (!contents.trim().is_empty())
+                .then(|| contents.chars().take_while(|c| c.is_whitespace()).count())
+        };
+        let previous = |line: usize| (0..line).rev().find(|line| indent(*line).is_some());
+        let next = |line: usize| (line + 1..=last_line).find(|line| indent(*line).is_some());
+
+        let cursor_line = self.cursor.line.min(last_line);
+        let mut base = if indent(cursor_line).is_some() {
+            cursor_line
+        } else {
+            next(cursor_line).or_else(|| previous(cursor_line))?
+        };
+        let mut remaining = count.max(1);
+        let (first, last) = loop {
+            let level = indent(base)?;
+            let mut first = base;
+            while first > 0 && indent(first - 1).is_none_or(|indent| indent >= level) {
+                first -= 1;
+            }
+            let mut last = base;
+            while last < last_line && indent(last + 1).is_none_or(|indent| indent >= level) {
+                last += 1;
+            }
+            let first = if indent(first).is_some() {
+                first
+            } else {
+                next(first).unwrap_or(base)
+            };
+            let last = if indent(last).is_some() {
+                last
+            } else {
+                previous(last).unwrap_or(base)
+            };
+
+            remaining -= 1;
+            match previous(first) {
+                Some(above) if remaining > 0 => base = above,
+                _ => break (first, last),
+            }
+        };
+
+        let (first, last) = if scope == TextObjectScope::Around {
+            (
+                previous(first).unwrap_or(first),
+                below.then(|| next(last)).flatten().unwrap_or(last),
+            )
+        } else {
+            (first, last)
+        };
+        Some(self.line_span(first, last))
+    }
+
+    fn entire_text_object(&self, scope: TextObjectScope) -> Option<TextRange> {
+        if self.buffer.is_empty() {
+            return None;
+        }
+
+        let last_line = self.buffer.last_navigable_line();
+        if scope == TextObjectScope::Around {
+            return Some(self.line_span(0, last_line));
+        }
+        let blank = |line: usize| {
+            self.buffer
+                .get(line)
+                .is_none_or(|contents| trim_line_ending(&contents).trim().is_empty())
+        };
+        let first = (0..=last_line).find(|line| !blank(*line))?;
+        let last = (first..=last_line).rev().find(|line| !blank(*line))?;
+        Some(self.line_span(first, last))
+    }
+
+    /// Lines `first..=last`, including the final line ending unless `last` is the
+    /// buffer's last line.
+    fn line_span(&self, first: usize, last: usize) -> TextRange {
+        let end = if last < self.buffer.last_navigable_line() {
+            TextPosition::new(last + 1, 0)
+        } else {
+            TextPosition::new(last, self.line_character_len(last))
+        };
+        TextRange::new(TextPosition::new(first, 0), end)
+    }
+
     fn delimited_text_object(
         &self,
         scope: TextObjectScope,
@@ -1216,6 +1339,9 @@ pub(crate) fn text_object_kind_for_key(character: char) -> Option<TextObjectKind
         }),
         '"' | 'q' => Some(TextObjectKind::Quote('"')),
         '\'' | '`' => Some(TextObjectKind::Quote(character)),
+        'i' => Some(TextObjectKind::Indentation { below: false }),
+        'I' => Some(TextObjectKind::Indentation { below: true }),
+        'e' => Some(TextObjectKind::Entire),
         _ => None,
     }
 }
@@ -1889,6 +2015,71 @@ mod tests {
         assert_eq!(buffer.text_in_range(around), "first\nsecond\n\n");
     }
 
+    #[test]
+    fn indentation_objects_skip_inner_blank_lines_and_take_surrounding_lines() {
+        let buffer = Buffer::new(
+            None,
+            "root:\n    if ok:\n        one\n\n        two\n\n    done\nnext\n".to_string(),
+        );
+        let object = |line: usize, scope, below: bool, count: u16| {
+            let resolver = MotionResolver::new(&buffer, TextPosition::new(line, 0));
+            let range = resolver
+                .text_object_with_count(scope, TextObjectKind::Indentation { below }, count)
+                .unwrap();
+            buffer.text_in_range(range)
+        };
+
+        assert_eq!(
+            object(2, TextObjectScope::Inner, false, 1),
+            "        one\n\n        two\n"
+        );
+        assert_eq!(
+            object(3, TextObjectScope::Inner, true, 1),
+            "        one\n\n        two\n"
+        );
+        assert_eq!(
+            object(2, TextObjectScope::Around, false, 1),
+            "    if ok:\n        one\n\n        two\n"
+        );
+        assert_eq!(
+            object(2, TextObjectScope::Around, true, 1),
+            "    if ok:\n        one\n\n        two\n\n    done\n"
+        );
+        assert_eq!(
+            object(2, TextObjectScope::Inner, false, 2),
+            "    if ok:\n        one\n\n        two\n\n    done\n"
+        );
+        assert!(
+            MotionResolver::new(&Buffer::new(None, String::new()), TextPosition::new(0, 0))
+                .text_object(
+                    TextObjectScope::Inner,
+                    TextObjectKind::Indentation { below: false }
+                )
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn entire_objects_cover_the_buffer_or_its_non_blank_lines() {
+        let buffer = Buffer::new(None, "\n\nfirst\n\nlast\n\n".to_string());
+        let resolver = MotionResolver::new(&buffer, TextPosition::new(0, 0));
+        let around = resolver
+            .text_object(TextObjectScope::Around, TextObjectKind::Entire)
+            .unwrap();
+        let inner = resolver
+            .text_object(TextObjectScope::Inner, TextObjectKind::Entire)
+            .unwrap();
+
+        assert_eq!(around.start, TextPosition::new(0, 0));
+        assert_eq!(buffer.text_in_range(inner), "first\n\nlast\n");
+        assert!(MotionResolver::new(
+            &Buffer::new(None, "\n \n".to_string()),
+            TextPosition::new(0, 0)
+        )
+        .text_object(TextObjectScope::Inner, TextObjectKind::Entire)
+        .is_none());
+    }
+
     #[test]
     fn indexed_final_sentence_objects_preserve_punctuation_unicode_and_counts() {
         for text in [
@@ -7,7 +7,7 @@ use unicode_segmentation::UnicodeSegmentation;
 
 use super::{
     apply_transactional_replacement, plain_line, reflow_text, text_object_kind_for_key,
-    CharacterMotion, MotionResolver, TextObjectKind, TextObjectScope,
+    CharacterMotion, MotionResolver, TextObjectScope,
 };
 use crate::{
     buffer::Buffer,
@@ -819,14 +819,9 @@ impl TextArea {
                     if let Some(range) = self.resolver().text_object_with_count(scope, kind, count)
                     {
                         if let Some(operator) = operator {
-                            self.apply_operator(
-                                operator,
-                                range,
-                                kind == TextObjectKind::Paragraph,
-                                keys,
-                            );
+                            self.apply_operator(operator, range, kind.is_linewise(), keys);
                         } else {
-                            self.select_range(range, kind == TextObjectKind::Paragraph);
+                            self.select_range(range, kind.is_linewise());
                         }
                     }
                 } else if count > 0 {
@@ -30,6 +30,8 @@ pub(crate) fn apply_transactional_replacement(
         start_char,
         end_char,
         new_char_len: replacement.chars().count(),
+        range,
+        new_line_breaks: replacement.matches('\n').count(),
     };
     buffer.replace_char_range_raw(start_char, end_char, replacement);
     buffer
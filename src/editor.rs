@@ -18,6 +18,7 @@
 mod agent_annotations;
 mod agent_manager;
 mod agent_models;
+mod argument_list;
 mod buffer_actions;
 mod buffer_manager;
 mod command_mode;
@@ -27,6 +28,10 @@ mod diagnostics;
 mod diagnostics_picker;
 mod display_layout;
 mod edit_batch;
+mod filters;
+mod folds;
+mod global_command;
+mod increments;
 mod inline_actions;
 mod inline_agent_outcomes;
 mod inline_changes;
@@ -38,21 +43,36 @@ mod inline_expansion;
 mod inline_history;
 mod inline_jobs;
 mod inline_notifications;
+mod insert_completion;
+mod insert_keys;
 mod keyboard_shortcuts;
 mod learning;
+mod line_commands;
 mod lsp_coordinator;
+mod make;
+mod mappings;
+mod multi_cursor;
 #[cfg(test)]
 mod navigation_perf_tests;
 mod notifications;
 mod package_actions;
 pub(crate) mod perf;
+mod quickfix;
+mod registers;
 pub mod render_buffer;
 pub mod rendering;
+mod replace_mode;
 #[cfg(test)]
 mod resize_tests;
 mod session_manager;
 mod signature_help;
 mod snippet;
+mod spelling;
+mod substitute_repeat;
+mod surrounds;
+mod tab_pages;
+mod undo_files;
+mod visual_block;
 
 use std::{
     borrow::Cow,
@@ -116,7 +136,8 @@ use crate::{
     comment::CommentSyntax,
     config::{
         Config, ConfigDiagnostic, ConfigDiagnosticSource, ConfigRecovery, FormattingProvider,
-        KeyAction, LanguageFormatterConfig, PickerIconStyle, StatuslineConfig,
+        KeyAction, KeyMode, Keys, LanguageFormatterConfig, PickerIconStyle, SearchDialect,
+        StatuslineConfig,
     },
     dispatcher::Dispatcher,
     editing::{
@@ -124,9 +145,11 @@ use crate::{
         CharacterMotion as ForwardCharacterMotion, MotionResolver, TextObjectKind, TextObjectScope,
     },
     highlighter::{Highlighter, LanguageRegistry},
+    increment::{self, IncrementKind},
     indent::{self, IndentDecision},
     inline_assist::InlineAssistResult,
     inline_history::{InlineDisposition, InlineHistory, InlineTurnState},
+    line_sort::SortOptions,
     log,
     lsp::{
         apply_workspace_resource_operations, file_path as lsp_file_path, get_client_capabilities,
@@ -153,6 +176,8 @@ use crate::{
         SessionJump, SessionMark, SessionSnapshot, SessionStore, SessionVisualMode,
         SessionVisualSelection, SessionWindowJumps, SESSION_SCHEMA_VERSION,
     },
+    substitute_expression::SubstituteExpression,
+    tab_page::TabPages,
     textobjects::{ResolvedTextObject, SyntaxObjectKind, SyntaxTextObjectService},
     theme::{parse_vscode_theme, parse_vscode_theme_contents, Style, Theme},
     tutorial::{
@@ -167,8 +192,12 @@ use crate::{
         PickerUpdate, ScreenRect, StatuslineLayoutPanel, TutorialDemoKind, TutorialDemoPanel,
         WelcomePanel, WhatsNewPanel,
     },
-    undo::{AppliedTextEdit, CursorSnapshot, EditOrigin, RevertEdit, TextPosition, TextRange},
+    undo::{
+        relative_age, AppliedTextEdit, CursorSnapshot, EditOrigin, RevertEdit, SelectionRange,
+        TextPosition, TextRange, UndoDistance, UndoStep,
+    },
     utils::{expand_user_path, get_workspace_path, normalized_file_path, same_file_path},
+    vim_regex::{self, TranslatedPattern},
     whats_new::ReleaseNotes,
     window::{
         JumpEntry, JumpList, WindowDivider, WindowId, WindowManager, WindowManagerSnapshot,
@@ -177,9 +206,10 @@ use crate::{
 };
 
 use self::display_layout::{
-    layout_lines, leading_whitespace_display_width, wrap_line_segments, BreakIndentOptions,
-    DisplayLayout, LayoutConfig,
+    layout_lines_with_folds, leading_whitespace_display_width, wrap_line_segments,
+    BreakIndentOptions, DisplayLayout, LayoutConfig,
 };
+use self::visual_block::put_block_rows;
 
 thread_local! {
     static CURRENT_ACTION_DISPATCHER: RefCell<Option<Arc<Dispatcher<PluginRequest, PluginResponse>>>> =
@@ -949,6 +979,18 @@ struct KeySignature {
 #[derive(Debug, Clone)]
 struct SemanticChange {
     events: Vec<Event>,
+    /// Set instead of `events` for a confirmed substitute, whose answers replay
+    /// without asking again.
+    substitute: Option<ConfirmedSubstitute>,
+}
+
+/// Where Insert mode resumes after a `Ctrl-o` command. `normal_cursor` is where
+/// the command started, so a command that leaves the cursor there resumes at
+/// `insert_cursor`, which may be past the end of the line.
+#[derive(Debug, Clone, Copy)]
+struct InsertNormalCommand {
+    insert_cursor: TextPosition,
+    normal_cursor: TextPosition,
 }
 
 #[derive(Debug)]
@@ -956,6 +998,8 @@ struct PendingSemanticChange {
     buffer_id: BufferId,
     base_revision: u64,
     events: Vec<Event>,
+    /// Index in `events` of the first key of the sequence being resolved.
+    key_sequence_start: usize,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -992,6 +1036,19 @@ struct MacroRecording {
     events: Vec<Event>,
 }
 
+/// Terminal-derived render state saved while a hidden replay draws into a scratch frame.
+struct CommittedFrame {
+    deferred_render: MotionRender,
+    terminal_frame: Option<RenderBuffer>,
+    render_generation: u64,
+    viewport: Option<RenderedViewport>,
+    cursor_position: Option<(usize, usize)>,
+    bracket_rows: Vec<usize>,
+    cursor_surface: Option<Style>,
+    force_full_redraw: bool,
+    terminal_output_enabled: bool,
+}
+
 #[derive(Debug, Clone)]
 struct MacroReplayEvent {
     event: Event,
@@ -1001,6 +1058,8 @@ struct MacroReplayEvent {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// Parsed and line-resolved substitute command awaiting execution.
 pub struct SubstituteCommand {
+    /// The range as typed, which `.` resolves again after a confirmed substitute.
+    range: String,
     start_line: usize,
     end_line: usize,
     pattern: String,
@@ -1010,6 +1069,88 @@ pub struct SubstituteCommand {
     confirm: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Parsed and line-resolved `:global` or `:vglobal` command awaiting execution.
+pub struct GlobalCommand {
+    start_line: usize,
+    end_line: usize,
+    pattern: String,
+    /// Run on the lines that do not match (`:g!` and `:v`).
+    invert: bool,
+    command: String,
+}
+
+/// Argument-list entry `:next`, `:previous`, `:first` or `:last` edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArgumentPosition {
+    Next,
+    Previous,
+    First,
+    Last,
+}
+
+/// What `:argdo`, `:bufdo`, `:windo` and `:cdo` visit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BatchTarget {
+    Arguments,
+    Buffers,
+    Windows,
+    Quickfix,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Parsed batch command awaiting execution.
+pub struct BatchCommand {
+    target: BatchTarget,
+    command: String,
+    /// Write each modified buffer after the command (`| update`).
+    update: bool,
+}
+
+/// The list quickfix commands work on: the quickfix list or the current window's
+/// location list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuickfixScope {
+    Quickfix,
+    Location,
+}
+
+/// Entry `:cnext`, `:cprevious`, `:cfirst`, `:clast` and `:cc` jump to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuickfixMotion {
+    Next(usize),
+    Previous(usize),
+    First,
+    Last,
+    /// One-based entry number, or the current entry.
+    Entry(Option<usize>),
+}
+
+/// One location sent to a quickfix or location list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuickfixItem {
+    pub location: plugin::PluginLocation,
+    /// Message shown with the location; empty shows the text of its line.
+    #[serde(default)]
+    pub text: String,
+}
+
+/// A new quickfix or location list, pushed onto that list's history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuickfixListSpec {
+    #[serde(default)]
+    pub title: String,
+    pub items: Vec<QuickfixItem>,
+    /// Fill the current window's location list instead of the quickfix list.
+    #[serde(default)]
+    pub location: bool,
+    /// Show the list's panel once it is set.
+    #[serde(default)]
+    pub open: bool,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct ExLineRange {
     start_line: usize,
@@ -1031,6 +1172,26 @@ pub enum SubstituteDecision {
     Last,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Pattern, replacement and flags of the last substitute, which `&`, `g&` and `:&`
+/// repeat.
+pub struct LastSubstitute {
+    pattern: String,
+    replacement: String,
+    replace_all: bool,
+    case_insensitive: bool,
+    confirm: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ConfirmedSubstitute {
+    command: SubstituteCommand,
+    /// Whether each match was replaced, in order.
+    answers: Vec<bool>,
+    /// Whether matches past `answers` are replaced, as after `a`.
+    replace_rest: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct PlannedSubstitution {
     start_char: usize,
@@ -1040,9 +1201,11 @@ struct PlannedSubstitution {
 
 #[derive(Debug, Clone)]
 struct SubstituteConfirmation {
+    command: SubstituteCommand,
     substitutions: Vec<PlannedSubstitution>,
     current: usize,
     accepted: Vec<PlannedSubstitution>,
+    answers: Vec<bool>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -1907,6 +2070,10 @@ pub enum PluginRequest {
         request_id: RequestId,
         include_declaration: bool,
     },
+    SetQuickfixList {
+        request_id: RequestId,
+        list: QuickfixListSpec,
+    },
     GetTextDisplayWidth {
         request_id: RequestId,
         text: String,
@@ -2144,6 +2311,7 @@ impl PluginRequest {
             Self::ListRuntimeAssets { .. } => "ListRuntimeAssets",
             Self::WorkspaceSymbols { .. } => "WorkspaceSymbols",
             Self::References { .. } => "References",
+            Self::SetQuickfixList { .. } => "SetQuickfixList",
             Self::GetTextDisplayWidth { .. } => "GetTextDisplayWidth",
             Self::CharIndexToDisplayColumn { .. } => "CharIndexToDisplayColumn",
             Self::DisplayColumnToCharIndex { .. } => "DisplayColumnToCharIndex",
@@ -2227,8 +2395,24 @@ pub enum SearchDirection {
     Backward,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+/// Where an explicit Insert-mode completion takes its candidates from.
+pub enum InsertCompletionSource {
+    /// Keywords in the open buffers, searching forward from the cursor (`Ctrl-n`).
+    KeywordNext,
+    /// Keywords in the open buffers, searching backward from the cursor (`Ctrl-p`).
+    KeywordPrevious,
+    /// Whole lines starting with the text typed on the line (`Ctrl-x Ctrl-l`).
+    Line,
+    /// File names relative to the buffer's directory or the working directory
+    /// (`Ctrl-x Ctrl-f`).
+    FilePath,
+    /// The language server alone, without buffer words (`Ctrl-x Ctrl-o`).
+    Lsp,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
-/// Case transformation applied by an editor action.
+/// Case or increment transformation applied by an editor action.
 pub enum CaseTransform {
     /// Convert cased characters to lowercase.
     Lower,
@@ -2236,6 +2420,8 @@ pub enum CaseTransform {
     Upper,
     /// Invert the case of each cased character.
     Toggle,
+    /// Add `delta` to a number, boolean, weekday, or date target.
+    Increment { delta: i64, kind: IncrementKind },
 }
 
 impl SearchDirection {
@@ -2421,6 +2607,12 @@ pub enum Action {
 
     Undo,
     Redo,
+    /// `:earlier`
+    UndoEarlier(UndoDistance),
+    /// `:later`
+    UndoLater(UndoDistance),
+    /// `:undo {N}`: the state after change `N`, or before every change for zero.
+    UndoToChange(usize),
     SelectPreviousUndoBranch,
     SelectNextUndoBranch,
     RevertTransaction(String),
@@ -2433,11 +2625,81 @@ pub enum Action {
     },
     Substitute(SubstituteCommand),
     ConfirmSubstitute(SubstituteDecision),
+    /// Repeats the last substitute on the current line without its flags (`&`).
+    RepeatSubstitute,
+    /// Repeats the last substitute with its flags on every line (`g&`).
+    RepeatSubstituteAllLines,
+    Global(GlobalCommand),
+    /// Replays `keys` in Normal mode, at the cursor or on each line of `lines`.
+    ExecuteNormal {
+        lines: Option<(usize, usize)>,
+        keys: String,
+    },
     SetMacroRegister {
         register: char,
         keys: String,
     },
     PrintRegisters,
+    /// Types `keys` as if they were pressed; the target of a `:map` mapping.
+    /// Non-recursive keys skip the mappings made at runtime.
+    FeedKeys {
+        keys: String,
+        remap: bool,
+    },
+    /// `:map {lhs} {rhs}` and its mode variants.
+    MapKeys {
+        modes: Vec<KeyMode>,
+        keys: String,
+        rhs: String,
+        remap: bool,
+    },
+    /// `:unmap {lhs}` and its mode variants.
+    UnmapKeys {
+        modes: Vec<KeyMode>,
+        keys: String,
+    },
+    /// `:abbreviate {lhs} {rhs}` and its Insert and Command variants.
+    Abbreviate {
+        modes: Vec<KeyMode>,
+        abbreviation: String,
+        expansion: String,
+    },
+    Unabbreviate {
+        modes: Vec<KeyMode>,
+        abbreviation: String,
+    },
+    /// Replaces the abbreviation that ends at the Insert-mode cursor.
+    ExpandAbbreviation,
+    /// `:args {files}`: replaces the argument list and edits its first file.
+    SetArguments(Vec<String>),
+    /// `:args`
+    ListArguments,
+    /// `:argadd {files}`, or the current file without files.
+    AddArguments(Vec<String>),
+    /// `:argdelete {patterns}`
+    DeleteArguments(Vec<String>),
+    EditArgument(ArgumentPosition),
+    /// `:argdo`, `:bufdo`, `:windo` or `:cdo`.
+    Batch(BatchCommand),
+    /// Pushes a new quickfix or location list.
+    SetQuickfixList(QuickfixListSpec),
+    /// `:cnext`, `:cprevious`, `:cfirst`, `:clast`, `:cc` and their location-list forms.
+    JumpToQuickfixEntry(QuickfixScope, QuickfixMotion),
+    NextQuickfixEntry,
+    PreviousQuickfixEntry,
+    /// `:colder` moves back through a list's history and `:cnewer` forward.
+    QuickfixHistory(QuickfixScope, isize),
+    /// `:clist`
+    ListQuickfix(QuickfixScope),
+    /// `:copen`
+    OpenQuickfixPanel(QuickfixScope),
+    /// `:cclose`
+    CloseQuickfixPanel(QuickfixScope),
+    /// `:make [args]`, or `:lmake` for the location list.
+    Make {
+        arguments: String,
+        scope: QuickfixScope,
+    },
     InsertString(String),
     InsertPastedText(String),
 
@@ -2510,6 +2772,8 @@ pub enum Action {
     MoveToPreviousFunction,
     MoveToNextClass,
     MoveToPreviousClass,
+    MoveToNextParameter,
+    MoveToPreviousParameter,
     MoveToFilePercent(usize),
     MatchitForward,
     MatchitBackward,
@@ -2554,6 +2818,8 @@ pub enum Action {
     YankToLineEnd(u16),
     DeletePreviousChars(u16),
     ChangeCharsAtCursor(u16),
+    StartReplaceMode(u16),
+    StartVirtualReplaceMode(u16),
     JoinLines(u16),
     JoinLinesKeepSpaces(u16),
     JoinLinesInRange {
@@ -2561,7 +2827,103 @@ pub enum Action {
         end_line: usize,
         keep_spaces: bool,
     },
+    /// `:d`; lines are zero-based and inclusive.
+    DeleteLines {
+        start_line: usize,
+        end_line: usize,
+        register: Option<char>,
+    },
+    /// `:y`
+    YankLines {
+        start_line: usize,
+        end_line: usize,
+        register: Option<char>,
+    },
+    /// `:m`; `destination` is the one-based line the moved lines go below, or `0`
+    /// for the top of the buffer.
+    MoveLines {
+        start_line: usize,
+        end_line: usize,
+        destination: usize,
+    },
+    /// `:t` and `:co`, with `destination` as for [`Action::MoveLines`].
+    CopyLines {
+        start_line: usize,
+        end_line: usize,
+        destination: usize,
+    },
+    /// `:>`, shifting `count` times.
+    IndentLines {
+        start_line: usize,
+        end_line: usize,
+        count: u16,
+    },
+    /// `:<`, shifting `count` times.
+    UnindentLines {
+        start_line: usize,
+        end_line: usize,
+        count: u16,
+    },
+    /// `:{range}w {file}`
+    WriteLines {
+        start_line: usize,
+        end_line: usize,
+        file: String,
+        force: bool,
+    },
+    /// `:sort`; `columns` limits the keys to the inclusive columns of a Visual block.
+    SortLines {
+        start_line: usize,
+        end_line: usize,
+        options: SortOptions,
+        columns: Option<(usize, usize)>,
+    },
+    /// `:{range}!cmd`
+    FilterLines {
+        start_line: usize,
+        end_line: usize,
+        command: String,
+    },
+    /// `:!cmd` without a range.
+    RunShellCommand(String),
+    /// `:r !cmd`; `line` is as for [`Action::MoveLines`]'s `destination`.
+    ReadCommand {
+        line: usize,
+        command: String,
+    },
+    /// `:r {file}`, with `line` as for [`Action::ReadCommand`].
+    ReadFile {
+        line: usize,
+        file: String,
+    },
+    /// `:w !cmd`
+    WriteToCommand {
+        start_line: usize,
+        end_line: usize,
+        command: String,
+    },
     ToggleCharCase(u16),
+    Increment(u16),
+    Decrement(u16),
+    IncrementSequence(u16),
+    DecrementSequence(u16),
+    /// Wraps `range` in `open` and `close`, on lines of their own when `linewise`.
+    AddSurround {
+        range: TextRange,
+        linewise: bool,
+        open: String,
+        close: String,
+    },
+    /// Removes the delimiters the surround key names around the cursor.
+    DeleteSurround(char),
+    /// Replaces the delimiters `target` names around the cursor.
+    ChangeSurround {
+        target: char,
+        open: String,
+        close: String,
+    },
+    /// Visual `S`: waits for the delimiters to add around the selection.
+    SurroundSelection,
     SwapNextParameter,
     SwapPreviousParameter,
     SwapNextFunction,
@@ -2576,6 +2938,33 @@ pub enum Action {
     StartLowercaseOperator(u16),
     StartUppercaseOperator(u16),
     StartToggleCaseOperator(u16),
+    StartFoldOperator(u16),
+    /// `!{motion}`, which opens the command line with a filter range. In Visual
+    /// mode it uses the selected lines.
+    StartFilterOperator(u16),
+    /// Opens the command line with a filter range for the lines of `TextRange`.
+    OpenFilterPrompt(TextRange),
+    CreateFold(TextRange),
+    CreateFoldFromSelection,
+    OpenFold,
+    CloseFold,
+    ToggleFold,
+    OpenAllFolds,
+    CloseAllFolds,
+    DeleteFold,
+    DeleteAllFolds,
+    MoveToNextFold,
+    MoveToPreviousFold,
+    AddCursorAbove,
+    AddCursorBelow,
+    AddCursorAtNextMatch,
+    SplitSelectionIntoLines,
+    SelectAllMatches {
+        start_line: usize,
+        end_line: usize,
+        pattern: String,
+    },
+    RemoveSecondaryCursors,
     TransformTextRange {
         range: TextRange,
         transform: CaseTransform,
@@ -2589,6 +2978,25 @@ pub enum Action {
     InsertLineBelowCursor,
     InsertLineAtCursor,
     InsertTab,
+    /// Starts Insert-mode `Ctrl-r`; the next key names the register to insert.
+    StartInsertRegister,
+    /// Inserts a register as if its text were typed, or with `literally` as
+    /// pasted text.
+    InsertRegister {
+        register: char,
+        literally: bool,
+    },
+    /// Runs one Normal-mode command from Insert mode and then resumes inserting.
+    StartInsertNormalCommand,
+    /// Deletes the word before the cursor in Insert mode.
+    DeletePreviousWord,
+    /// Deletes the text entered before the cursor on this line, or without any,
+    /// the text between the indentation and the cursor.
+    DeleteToInsertStart,
+    /// Indents the current line by one shift width from Insert mode.
+    IncreaseInsertIndent,
+    /// Removes one shift width of indentation from the current line in Insert mode.
+    DecreaseInsertIndent,
 
     ReplaceCharsAtCursor {
         character: char,
@@ -2675,6 +3083,19 @@ pub enum Action {
     ShowLineDiagnostics,
     NextDiagnostic,
     PreviousDiagnostic,
+    /// `]s`: moves to the next misspelled word.
+    NextMisspelledWord,
+    /// `[s`: moves to the previous misspelled word.
+    PreviousMisspelledWord,
+    /// `z=`: picks a replacement for the word under the cursor.
+    SuggestSpelling,
+    ReplaceMisspelledWord(String),
+    /// `zg`: adds the word under the cursor to the good words.
+    MarkWordGood,
+    /// `zw`: adds the word under the cursor to the wrong words.
+    MarkWordWrong,
+    SetSpell(bool),
+    SetSpellLanguages(Vec<String>),
     Hover,
     ExecuteLspCommand(Box<LspCommand>),
     FormatDocument,
@@ -2761,6 +3182,8 @@ pub enum Action {
     Paste,
     PasteBefore,
     InsertBlock,
+    /// Visual-block `A`: appends after the block, or after each row's end after `$`.
+    AppendBlock,
 
     InsertText {
         x: usize,
@@ -2770,6 +3193,9 @@ pub enum Action {
     BufferText(Value),
 
     RequestCompletion,
+    /// Opens the completion menu for one Insert-mode source, as `Ctrl-n`, `Ctrl-p`
+    /// and the `Ctrl-x` keys do.
+    RequestInsertCompletion(InsertCompletionSource),
     Copilot(String),
     CopilotEnableAndSignIn,
     RequestInlineCompletion,
@@ -2874,6 +3300,13 @@ pub enum Action {
     MaximizeWindow,
     TogglePaneZoom,
     OnlyWindow,
+    NewTabPage(Option<String>),
+    CloseTabPage,
+    OnlyTabPage,
+    NextTabPage,
+    PreviousTabPage,
+    GoToTabPage(usize),
+    MoveTabPage(usize),
 }
 
 #[allow(unused)]
@@ -2906,6 +3339,10 @@ pub enum Mode {
     Normal,
     /// Direct text insertion mode.
     Insert,
+    /// Overtype mode where typed graphemes replace existing ones.
+    Replace,
+    /// Overtype mode that replaces screen cells, keeping tabs and wide characters aligned.
+    VirtualReplace,
     /// Colon-command prompt.
     Command,
     /// Incremental search prompt.
@@ -2991,6 +3428,7 @@ struct LayoutCacheKey {
     line_count_override: Option<usize>,
     break_indent: BreakIndentOptions,
     inline_prediction: Option<u64>,
+    closed_folds: Vec<(usize, usize)>,
 }
 
 #[derive(Debug)]
@@ -3102,6 +3540,8 @@ struct Rect {
 struct LastVisualSelection {
     mode: Mode,
     anchor_at_start: bool,
+    /// Whether a block extended to each row's end after `$`.
+    to_line_end: bool,
 }
 
 impl Rect {
@@ -3153,6 +3593,8 @@ pub struct ActionOnSelection {
     action: Action,
     selection: Rect,
     action_index: usize,
+    /// Right edge a Visual-block `A` appends at, rather than inserting at the left.
+    append: Option<visual_block::BlockEdge>,
 }
 
 impl ActionOnSelection {
@@ -3161,6 +3603,7 @@ impl ActionOnSelection {
             action,
             selection,
             action_index,
+            append: None,
         }
     }
 }
@@ -3276,6 +3719,9 @@ pub struct Editor {
     /// Window manager handling splits and layout
     window_manager: WindowManager,
 
+    /// Tab pages other than the current one, whose layout is `window_manager`
+    tab_pages: TabPages,
+
     /// Terminal output handle
     stdout: std::io::BufWriter<TerminalOutput>,
 
@@ -3358,6 +3804,12 @@ pub struct Editor {
 
     /// Nested synthetic/local edit publication boundary.
     edit_batch: edit_batch::EditBatch,
+    /// Secondary cursors and the command being typed for them.
+    multi_cursor: multi_cursor::MultiCursor,
+    /// Overwritten text and count of the active Replace or virtual Replace mode.
+    replace_session: Option<replace_mode::ReplaceSession>,
+    /// Lines still to visit for each running `:global` or ranged `:normal`.
+    ex_line_marks: Vec<global_command::LineMarks>,
 
     /// Repaint scope accumulated by deferred navigation.
     deferred_motion_render: MotionRender,
@@ -3416,6 +3868,13 @@ pub struct Editor {
     /// Cursor position where the current insert session began.
     insert_entry_cursor: Option<CursorSnapshot>,
 
+    /// Set by Insert-mode `Ctrl-r` until the register is named, to whether the
+    /// register is inserted literally.
+    pending_insert_register: Option<bool>,
+
+    /// Set by Insert-mode `Ctrl-o` while its Normal-mode command runs.
+    insert_normal_command: Option<InsertNormalCommand>,
+
     /// Whitespace created by auto-indent that is still removable as a blank line.
     generated_indent: Option<GeneratedIndent>,
 
@@ -3452,6 +3911,9 @@ pub struct Editor {
     /// Whether Normal-mode `r` is waiting for its replacement character.
     pending_replace: bool,
 
+    /// Surround command waiting for its delimiters, such as `ys` after `ysiw`.
+    pending_surround: Option<PendingSurround>,
+
     /// Executed actions
     actions: Vec<Action>,
 
@@ -3472,6 +3934,14 @@ pub struct Editor {
     macro_instructions_remaining: usize,
     macro_replay_queue: VecDeque<MacroReplayEvent>,
 
+    /// Text-register prefix state. `selected_register` holds a completed `"x` until
+    /// the command it prefixes resolves; `active_register` is what that command uses.
+    pending_register_prefix: bool,
+    selected_register: Option<char>,
+    active_register: Option<char>,
+    /// Text typed in the current insert session, recorded into the `.` register.
+    inserted_text: Option<String>,
+
     /// Buffer-local, file-global, and special Vim marks. Anchors use flat Unicode
     /// character coordinates and are transformed at the canonical edit boundary.
     pending_mark_action: Option<PendingMarkAction>,
@@ -3514,6 +3984,10 @@ pub struct Editor {
     /// Current search term
     search_term: String,
 
+    /// Replacement of the last substitute, which `~` stands for in Vim-dialect
+    /// patterns and replacements.
+    last_substitute: Option<LastSubstitute>,
+
     /// Direction of the most recently committed search.
     search_direction: SearchDirection,
 
@@ -3570,6 +4044,17 @@ pub struct Editor {
     diagnostics: HashMap<String, Vec<Diagnostic>>,
     diagnostic_reports: diagnostics::DiagnosticReports,
     diagnostic_cache: Option<diagnostic_cache::DiagnosticCache>,
+    /// Undo trees of written files, read while `undofile.enabled` is set
+    undo_files: Option<undo_files::UndoFileStore>,
+    /// Dictionaries and cached misspellings for `spell.enabled`
+    spell: spelling::SpellState,
+    /// Keys fed by `:map` mappings and the keymap non-recursive ones resolve in
+    key_mappings: mappings::KeyMappingState,
+    argument_list: argument_list::ArgumentList,
+    /// The quickfix list and every window's location list, each with its history
+    quickfix: quickfix::QuickfixState,
+    /// The `:make` build running in the background, if any
+    make: make::MakeState,
 
     /// Indentation rules per file type
     indentation: HashMap<String, Indentation>,
@@ -3601,6 +4086,8 @@ pub struct Editor {
     pending_plugin_references: HashMap<i64, RequestId>,
     pending_plugin_inlay_hints: HashMap<i64, RequestId>,
     pending_lsp_edit_requests: HashMap<i64, PendingLspEdit>,
+    /// Folding-range requests and the buffer revision each one describes.
+    pending_folding_ranges: HashMap<i64, (BufferId, u64)>,
     /// LSP request currently owning the visible, cancellable code-action picker.
     pending_code_action_request: Option<i64>,
     pending_lsp_format_saves: HashMap<i64, PendingLspFormatSave>,
@@ -4221,6 +4708,18 @@ struct CompletionSnapshot {
     cursor: Option<TextPosition>,
     original_range: Option<Range>,
     current_range: Option<Range>,
+    insert_completion: Option<InsertCompletionStart>,
+}
+
+/// Where a `Ctrl-n`, `Ctrl-p` or `Ctrl-x` completion started. An item accepted from
+/// its menu stays in the Insert transaction, and the change `.` repeats records the
+/// inserted text in place of the menu keys.
+#[derive(Debug, Clone, PartialEq)]
+struct InsertCompletionStart {
+    /// Dot-repeat events recorded before the keys that opened the menu.
+    events: usize,
+    cursor: TextPosition,
+    line: String,
 }
 
 #[derive(Debug, Clone)]
@@ -4241,6 +4740,7 @@ impl From<PendingLspEdit> for CompletionSnapshot {
             cursor: None,
             original_range: None,
             current_range: None,
+            insert_completion: None,
         }
     }
 }
@@ -4376,6 +4876,9 @@ enum EditOperator {
     Lowercase,
     Uppercase,
     ToggleCase,
+    Fold,
+    Filter,
+    Surround,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -4398,6 +4901,47 @@ struct PendingOperator {
     motion_count: Option<u16>,
 }
 
+/// A surround command waiting for keys; see `editor/surrounds.rs`.
+#[derive(Debug, Clone)]
+struct PendingSurround {
+    step: SurroundStep,
+    prompt: Option<SurroundPrompt>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SurroundStep {
+    /// `ys{motion}` or Visual `S`, waiting for the delimiters to add.
+    Add { range: TextRange, linewise: bool },
+    /// `ds`, waiting for the delimiters to remove.
+    Delete,
+    /// `cs`, waiting for the delimiters to replace.
+    Change,
+    /// `cs{target}`, waiting for the replacement delimiters.
+    ChangeTo { target: char },
+}
+
+/// A tag or function name being typed for a surround.
+#[derive(Debug, Clone)]
+struct SurroundPrompt {
+    kind: SurroundPromptKind,
+    text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SurroundPromptKind {
+    Tag,
+    Function { spaced: bool },
+}
+
+impl SurroundPrompt {
+    fn display(&self) -> String {
+        match self.kind {
+            SurroundPromptKind::Tag => format!("<{}", self.text),
+            SurroundPromptKind::Function { .. } => format!("{}(", self.text),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct PendingCharacterMotion {
     kind: ForwardCharacterMotion,
@@ -4425,6 +4969,7 @@ fn structural_motion_kind(key: char) -> Option<SyntaxObjectKind> {
         'm' => Some(SyntaxObjectKind::Call),
         'f' => Some(SyntaxObjectKind::Function),
         'c' => Some(SyntaxObjectKind::Class),
+        'a' => Some(SyntaxObjectKind::Parameter),
         _ => None,
     }
 }
@@ -4440,6 +4985,9 @@ impl EditOperator {
             EditOperator::Lowercase => "gu",
             EditOperator::Uppercase => "gU",
             EditOperator::ToggleCase => "g~",
+            EditOperator::Fold => "zf",
+            EditOperator::Filter => "!",
+            EditOperator::Surround => "ys",
         }
     }
 }
@@ -4619,6 +5167,7 @@ impl Editor {
         self.syntax_indentation.reset(Arc::clone(&registry));
         self.indentation = indentation;
         self.highlight_cache.clear();
+        self.forget_noremap_keys();
         self.bracket_match_cache = None;
         self.workspace_manager
             .update_theme_with_registry(&self.theme, &registry);
@@ -4737,9 +5286,12 @@ impl Editor {
             || self.pending_operator.is_some()
             || self.pending_character_motion.is_some()
             || self.pending_replace
+            || self.pending_surround.is_some()
             || self.pending_visual_text_object_scope.is_some()
             || self.pending_macro_action.is_some()
             || self.pending_mark_action.is_some()
+            || self.pending_register_prefix
+            || self.selected_register.is_some()
             || self.repeater.is_some()
     }
 
@@ -4863,6 +5415,7 @@ impl Editor {
 
         let plugin_registry = PluginRegistry::new();
         let indentation = Self::configured_indentation(&config);
+        let argument_list = argument_list::ArgumentList::new(config.startup_files.clone());
 
         let mut window_manager = WindowManager::new(0, (width, height));
         let wrap = config.wrap.unwrap_or(true);
@@ -4916,6 +5469,7 @@ impl Editor {
             bracket_match_cache: None,
             layout_cache: std::cell::RefCell::new(HashMap::new()),
             window_manager,
+            tab_pages: TabPages::default(),
             stdout,
             keyboard_protocol: crate::keyboard::KeyboardProtocol::default(),
             terminal_active: false,
@@ -4944,6 +5498,9 @@ impl Editor {
             last_rendered_cursor_surface: None,
             defer_motion_render: false,
             edit_batch: edit_batch::EditBatch::default(),
+            multi_cursor: multi_cursor::MultiCursor::default(),
+            replace_session: None,
+            ex_line_marks: Vec::new(),
             deferred_motion_render: MotionRender::None,
             deferred_plugin_event: None,
             block_replay_depth: 0,
@@ -4962,6 +5519,8 @@ impl Editor {
             pane_resize_mode: None,
             zoomed_pane: None,
             insert_entry_cursor: None,
+            pending_insert_register: None,
+            insert_normal_command: None,
             generated_indent: None,
             waiting_command: None,
             waiting_key_action: None,
@@ -4974,6 +5533,7 @@ impl Editor {
             pending_character_motion: None,
             last_character_motion: None,
             pending_replace: false,
+            pending_surround: None,
             actions: vec![],
             last_semantic_change: None,
             pending_semantic_change: None,
@@ -4984,6 +5544,10 @@ impl Editor {
             macro_replay_depth: 0,
             macro_instructions_remaining: 0,
             macro_replay_queue: VecDeque::new(),
+            pending_register_prefix: false,
+            selected_register: None,
+            active_register: None,
+            inserted_text: None,
             pending_mark_action: None,
             local_marks: HashMap::new(),
             global_marks: HashMap::new(),
@@ -5001,6 +5565,7 @@ impl Editor {
             command_history_navigation: None,
             command_completion: None,
             search_term: String::new(),
+            last_substitute: None,
             search_direction: SearchDirection::Forward,
             active_search: None,
             search_match_cache: None,
@@ -5030,6 +5595,12 @@ impl Editor {
             diagnostics: HashMap::new(),
             diagnostic_reports: diagnostics::DiagnosticReports::default(),
             diagnostic_cache: None,
+            undo_files: None,
+            spell: spelling::SpellState::default(),
+            key_mappings: mappings::KeyMappingState::default(),
+            argument_list,
+            quickfix: quickfix::QuickfixState::default(),
+            make: make::MakeState::default(),
             indentation,
             render_commands: VecDeque::new(),
             overlay_manager: plugin::OverlayManager::new(),
@@ -5041,6 +5612,7 @@ impl Editor {
             window_bar_manager: plugin::WindowBarManager::default(),
             directory_watchers: HashMap::new(),
             pending_plugin_document_symbols: HashMap::new(),
+            pending_folding_ranges: HashMap::new(),
             pending_plugin_workspace_symbols: HashMap::new(),
             pending_plugin_references: HashMap::new(),
             pending_plugin_inlay_hints: HashMap::new(),
@@ -5259,6 +5831,7 @@ impl Editor {
             &buffer_map,
         ) {
             self.window_manager = restored;
+            self.claim_restored_folds();
         }
         self.buffer_manager
             .set_active_index(tutorial.original_buffer_index);
@@ -5391,8 +5964,12 @@ impl Editor {
 
     /// Synchronizes the active window with the editor's state
     fn sync_to_window(&mut self) {
+        let buffer_id = self.buffer_manager.active_buffer().map(Buffer::id);
         if let Some(window) = self.window_manager.active_window_mut() {
             window.buffer_index = self.buffer_manager.active_index();
+            if let Some(buffer_id) = buffer_id {
+                window.folds.bind(buffer_id);
+            }
             window.vtop = self.vtop;
             window.vleft = self.vleft;
             window.skipcol = self.skipcol;
@@ -6188,11 +6765,15 @@ impl Editor {
             return;
         }
         self.sync_to_window();
+        // The tab line takes the first row from panels and windows alike.
+        let tab_line = self.tab_line_height();
+        self.panel_manager.set_top_inset(tab_line);
+        let terminal_size = (terminal_size.0, terminal_size.1.saturating_sub(tab_line));
         self.configure_pane_presentation(terminal_size);
         let (reserved_left, reserved_right) = self.reserved_panel_widths(terminal_size.0);
         let (reserved_top, reserved_bottom) = self.reserved_panel_heights(terminal_size.1);
         self.window_manager.resize_with_origin(
-            Point::new(reserved_left, reserved_top),
+            Point::new(reserved_left, reserved_top + tab_line),
             (
                 terminal_size
                     .0
@@ -6485,6 +7066,7 @@ impl Editor {
         let prediction = self
             .visible_inline_suggestion()
             .filter(|suggestion| window.active && suggestion.snapshot.buffer_id == buffer.id());
+        let closed_folds = window.folds.closed_ranges(buffer.id());
         let key = LayoutCacheKey {
             inline_selection: self.inline_comment_selection(window.id, buffer.id()),
             buffer_index: window.buffer_index,
@@ -6500,16 +7082,19 @@ impl Editor {
             line_count_override,
             break_indent,
             inline_prediction: prediction.map(|suggestion| suggestion.snapshot.generation),
+            closed_folds: closed_folds.clone(),
         };
         if let Some(layout) = self.layout_cache.borrow().get(&key) {
             return layout.clone();
         }
 
         let _span = perf::PerfSpan::start("layout_for_window:miss");
-        let end = window
-            .vtop
-            .saturating_add(self.window_content_height(window))
-            .min(line_count);
+        let end = folds::visible_lines_end(
+            &closed_folds,
+            window.vtop,
+            self.window_content_height(window),
+        )
+        .min(line_count);
         let lines = (window.vtop..end)
             .filter_map(|line| buffer.get(line))
             .collect::<Vec<_>>();
@@ -6528,11 +7113,12 @@ impl Editor {
             skipcol: window.skipcol,
             break_indent,
         };
-        let mut layout = layout_lines(&lines, line_count, layout_config).with_inline_comments(
-            &comments,
-            self.window_content_width(window),
-            self.window_content_height(window),
-        );
+        let mut layout = layout_lines_with_folds(&lines, line_count, layout_config, &closed_folds)
+            .with_inline_comments(
+                &comments,
+                self.window_content_width(window),
+                self.window_content_height(window),
+            );
         if let Some(suggestion) = prediction {
             if let Some(line) = buffer.get(suggestion.snapshot.cursor.line) {
                 let line = trim_line_ending(&line);
@@ -7250,7 +7836,10 @@ impl Editor {
     }
 
     fn is_insert(&self) -> bool {
-        matches!(self.mode, Mode::Insert)
+        matches!(
+            self.mode,
+            Mode::Insert | Mode::Replace | Mode::VirtualReplace
+        )
     }
 
     fn is_command(&self) -> bool {
@@ -7753,7 +8342,12 @@ impl Editor {
         let tab_widths = (0..buffers.len())
             .map(|buffer_index| self.tab_width_for_buffer_index(buffer_index))
             .collect::<Vec<_>>();
-        for window in self.window_manager.windows_mut() {
+        for window in self
+            .window_manager
+            .windows_mut()
+            .into_iter()
+            .chain(self.tab_pages.windows_mut())
+        {
             let buffer_y = window.vtop + window.cy;
             let tab_width = tab_widths.get(window.buffer_index).copied().unwrap_or(4);
             let display_col = buffers
@@ -7791,6 +8385,8 @@ impl Editor {
                 | Action::ShowLineDiagnostics
                 | Action::NextDiagnostic
                 | Action::PreviousDiagnostic
+                | Action::NextMisspelledWord
+                | Action::PreviousMisspelledWord
                 | Action::Hover
                 | Action::FormatDocument
                 | Action::CodeAction
@@ -8051,6 +8647,10 @@ impl Editor {
     }
 
     fn check_bounds(&mut self) -> bool {
+        let closed_folds = self.active_closed_folds();
+        if !closed_folds.is_empty() {
+            return self.check_bounds_with_folds(&closed_folds);
+        }
         let old_position = (self.cx, self.cy, self.vtop);
         let has_comments = self.has_inline_comments(self.current_buffer().id());
         let last_line = if self.is_insert() {
@@ -8926,7 +9526,9 @@ impl Editor {
 
         let buffer = Buffer::load_or_create(Some(normalized.clone())).await?;
         self.buffer_manager.push_buffer(buffer);
+        self.restore_undo_file(self.buffer_manager.len() - 1);
         self.rebind_inline_history_file(&normalized);
+        self.bind_quickfix_file(&normalized);
         Ok((self.buffer_manager.len() - 1, true, normalized))
     }
 
@@ -8956,7 +9558,7 @@ impl Editor {
             let contents = self.current_buffer().contents();
             crate::lsp::workspace_edit::secure_write_workspace_file(root, path, contents.as_bytes())
                 .map(|()| {
-                    self.current_buffer_mut().mark_saved();
+                    self.current_buffer_mut().mark_written();
                     format!(
                         "{:?} {}L, {}B written",
                         path.to_string_lossy(),
@@ -8980,6 +9582,7 @@ impl Editor {
         };
         match result {
             Ok(message) => {
+                self.write_undo_file();
                 self.notify_lsp_saved().await;
                 self.sync_inline_change_summaries();
                 if let Some(file) = self.current_buffer().file.clone() {
@@ -10058,6 +10661,8 @@ impl Editor {
                 .await?;
         }
 
+        self.poll_make_events();
+
         for (watch_id, payload) in self.poll_directory_watchers() {
             self.plugin_registry
                 .notify(runtime, &format!("filesystem:changed:{watch_id}"), payload)
@@ -11403,7 +12008,7 @@ impl Editor {
                             }
                             "diagnostics" => json!(self.config.diagnostics),
                             "show_diagnostics" => json!(self.config.show_diagnostics),
-                            "startup_file_count" => json!(self.config.startup_file_count),
+                            "startup_file_count" => json!(self.config.startup_files.len()),
                             "startup_session_resumed" => {
                                 json!(self.config.startup_session_resumed)
                             }
@@ -11428,7 +12033,7 @@ impl Editor {
                             "relative_line_numbers": self.config.relative_line_numbers,
                             "diagnostics": self.config.diagnostics,
                             "show_diagnostics": self.config.show_diagnostics,
-                            "startup_file_count": self.config.startup_file_count,
+                            "startup_file_count": self.config.startup_files.len(),
                             "startup_session_resumed": self.config.startup_session_resumed,
                             "cwd": std::env::current_dir().ok().map(|path| path.to_string_lossy().to_string()),
                             "executable": std::env::current_exe().ok().map(|path| path.to_string_lossy().to_string()),
@@ -11704,6 +12309,13 @@ impl Editor {
                         }
                     }
                 }
+                PluginRequest::SetQuickfixList { request_id, list } => {
+                    let count = self.set_quickfix_list(&list);
+                    self.plugin_registry
+                        .resolve_request(runtime, request_id, json!({ "ok": true, "count": count }))
+                        .await?;
+                    needs_render = true;
+                }
                 PluginRequest::InlayHints { request_id, range } => {
                     let Some(file) = self.current_buffer().file.clone() else {
                         self.plugin_registry
@@ -12325,11 +12937,14 @@ impl Editor {
         let mut navigation_deferred = false;
 
         let from_waiting_key_action = self.waiting_key_action.is_some();
+        let continues_key_sequence =
+            from_waiting_key_action || self.pending_insert_register.is_some();
         let had_repeater = self.repeater.is_some();
         let started_with_panel_focus = self.panel_manager.focused_panel_id().is_some();
         let started_in_normal = self.is_normal();
         let semantic_can_start = started_in_normal || self.is_visual();
         let was_recording_macro = self.macro_recording.is_some();
+        let multi_cursor_event = !sensitive_input && self.record_multi_cursor_event(&ev);
         let resolve_span = perf::PerfSpan::start("event:resolve_action");
         let mut action = self.handle_event_with_runtime(&ev, Some(runtime))?;
         if started_with_panel_focus {
@@ -12358,6 +12973,7 @@ impl Editor {
                 action.as_ref(),
                 started_in_normal,
                 semantic_can_start,
+                continues_key_sequence,
             );
         }
         drop(resolve_span);
@@ -12394,6 +13010,9 @@ impl Editor {
         let semantic_span = perf::PerfSpan::start("event:finish_semantic_change");
         self.finish_semantic_change_event();
         drop(semantic_span);
+        if multi_cursor_event {
+            self.finish_multi_cursor_event(buffer, runtime).await?;
+        }
 
         if render_mode != EventRenderMode::DeferredMotion
             && !navigation_deferred
@@ -12669,6 +13288,24 @@ impl Editor {
         action: &KeyAction,
         buffer: &mut RenderBuffer,
         runtime: &mut Runtime,
+    ) -> anyhow::Result<bool> {
+        let in_insert_normal_command = self.insert_normal_command.is_some();
+        let should_quit = self
+            .handle_key_action_with_render_boundary(ev, action, buffer, runtime)
+            .await?;
+        if in_insert_normal_command && !should_quit {
+            self.resume_insert_after_normal_command(buffer, runtime)
+                .await?;
+        }
+        Ok(should_quit)
+    }
+
+    async fn handle_key_action_with_render_boundary(
+        &mut self,
+        ev: &event::Event,
+        action: &KeyAction,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
     ) -> anyhow::Result<bool> {
         // A count is one input command even though KeyAction expands it internally.
         // Keep that render boundary for both direct input and replayed events.
@@ -12709,8 +13346,12 @@ impl Editor {
         action: Option<&KeyAction>,
         started_in_normal: bool,
         semantic_can_start: bool,
+        continues_key_sequence: bool,
     ) {
-        if self.replaying_semantic_change || !Self::event_is_replayable_input(event) {
+        if self.replaying_semantic_change
+            || self.substitute_confirmation.is_some()
+            || !Self::event_is_replayable_input(event)
+        {
             return;
         }
 
@@ -12727,10 +13368,14 @@ impl Editor {
                 buffer_id: self.current_buffer().id(),
                 base_revision: self.current_buffer().revision(),
                 events: Vec::new(),
+                key_sequence_start: 0,
             });
         }
 
         if let Some(change) = &mut self.pending_semantic_change {
+            if !continues_key_sequence {
+                change.key_sequence_start = change.events.len();
+            }
             change.events.push(event.clone());
         }
     }
@@ -12768,14 +13413,8 @@ impl Editor {
         }
 
         let pending_input = !self.is_normal()
-            || self.waiting_key_action.is_some()
-            || self.pending_operator.is_some()
-            || self.pending_character_motion.is_some()
-            || self.pending_replace
-            || self.pending_visual_text_object_scope.is_some()
-            || self.pending_macro_action.is_some()
-            || self.pending_mark_action.is_some()
-            || self.repeater.is_some()
+            || self.awaiting_key_input()
+            || self.insert_normal_command.is_some()
             || self.transaction_active();
         if pending_input {
             return;
@@ -12788,10 +13427,26 @@ impl Editor {
         if self.current_buffer().revision() != change.base_revision && !change.events.is_empty() {
             self.last_semantic_change = Some(SemanticChange {
                 events: change.events,
+                substitute: None,
             });
         }
     }
 
+    /// Returns whether a Normal-mode command is still waiting for more keys.
+    fn awaiting_key_input(&self) -> bool {
+        self.waiting_key_action.is_some()
+            || self.pending_operator.is_some()
+            || self.pending_character_motion.is_some()
+            || self.pending_replace
+            || self.pending_surround.is_some()
+            || self.pending_visual_text_object_scope.is_some()
+            || self.pending_macro_action.is_some()
+            || self.pending_mark_action.is_some()
+            || self.pending_register_prefix
+            || self.selected_register.is_some()
+            || self.repeater.is_some()
+    }
+
     fn event_is_replayable_input(event: &Event) -> bool {
         matches!(
             event,
@@ -12879,6 +13534,8 @@ impl Editor {
                 | Action::MoveToPreviousFunction
                 | Action::MoveToNextClass
                 | Action::MoveToPreviousClass
+                | Action::MoveToNextParameter
+                | Action::MoveToPreviousParameter
                 | Action::FindCharForward { .. }
                 | Action::TillCharForward { .. }
                 | Action::FindCharBackward { .. }
@@ -12937,6 +13594,8 @@ impl Editor {
                 | Action::MoveToPreviousFunction
                 | Action::MoveToNextClass
                 | Action::MoveToPreviousClass
+                | Action::MoveToNextParameter
+                | Action::MoveToPreviousParameter
                 | Action::FindCharForward { .. }
                 | Action::TillCharForward { .. }
                 | Action::FindCharBackward { .. }
@@ -12958,7 +13617,10 @@ impl Editor {
 
     fn action_is_visual_inherited(action: &Action) -> bool {
         Self::action_is_selection_motion(action)
-            || matches!(action, Action::EnterMode(Mode::Command))
+            || matches!(
+                action,
+                Action::EnterMode(Mode::Command) | Action::StartFilterOperator(_)
+            )
     }
 
     fn visual_inherited_subset(action: &KeyAction) -> Option<KeyAction> {
@@ -13006,9 +13668,8 @@ impl Editor {
     }
 
     fn visual_key_mappings_for_mode(&self, mode: Mode) -> HashMap<String, KeyAction> {
-        let mut mappings = self
-            .config
-            .keys
+        let keys = self.active_keys();
+        let mut mappings = keys
             .normal
             .iter()
             .filter_map(|(key, action)| {
@@ -13016,15 +13677,17 @@ impl Editor {
             })
             .collect::<HashMap<_, _>>();
 
-        Self::merge_key_mappings(&mut mappings, &self.config.keys.visual);
+        Self::merge_key_mappings(&mut mappings, &keys.visual);
         match mode {
-            Mode::VisualLine => {
-                Self::merge_key_mappings(&mut mappings, &self.config.keys.visual_line)
-            }
-            Mode::VisualBlock => {
-                Self::merge_key_mappings(&mut mappings, &self.config.keys.visual_block)
-            }
-            Mode::Visual | Mode::Normal | Mode::Insert | Mode::Command | Mode::Search => {}
+            Mode::VisualLine => Self::merge_key_mappings(&mut mappings, &keys.visual_line),
+            Mode::VisualBlock => Self::merge_key_mappings(&mut mappings, &keys.visual_block),
+            Mode::Visual
+            | Mode::Normal
+            | Mode::Insert
+            | Mode::Replace
+            | Mode::VirtualReplace
+            | Mode::Command
+            | Mode::Search => {}
         }
         mappings
     }
@@ -13095,10 +13758,17 @@ impl Editor {
         runtime: &'a mut Runtime,
     ) -> BoxFuture<'a, anyhow::Result<()>> {
         Box::pin(async move {
-            let Some(change) = self.last_semantic_change.clone() else {
+            let Some(mut change) = self.last_semantic_change.clone() else {
                 self.set_legacy_message(Some("no change to repeat".to_string()));
                 return Ok(());
             };
+            if let Some(substitute) = &change.substitute {
+                return self
+                    .replay_confirmed_substitute(substitute, buffer, runtime)
+                    .await;
+            }
+            Self::advance_numbered_register_in_change(&mut change.events);
+            self.last_semantic_change = Some(change.clone());
 
             self.pending_semantic_change = None;
             self.replaying_semantic_change = true;
@@ -13254,29 +13924,39 @@ impl Editor {
                     .find('>')
                     .ok_or_else(|| anyhow::anyhow!("macro notation has an unterminated key"))?;
                 let token = &suffix[1..end];
-                let (code, modifiers) = match token {
+                // Key names are case-insensitive, as in Vim: <esc>, <CR> and <c-w> all work.
+                let name = token.to_ascii_lowercase();
+                let (code, modifiers) = match name.as_str() {
                     "lt" => (KeyCode::Char('<'), KeyModifiers::NONE),
-                    "Esc" => (KeyCode::Esc, KeyModifiers::NONE),
-                    "CR" | "Enter" => (KeyCode::Enter, KeyModifiers::NONE),
-                    "BS" => (KeyCode::Backspace, KeyModifiers::NONE),
-                    "Tab" => (KeyCode::Tab, KeyModifiers::NONE),
-                    "S-Tab" => (KeyCode::BackTab, KeyModifiers::SHIFT),
-                    "Up" => (KeyCode::Up, KeyModifiers::NONE),
-                    "Down" => (KeyCode::Down, KeyModifiers::NONE),
-                    "Left" => (KeyCode::Left, KeyModifiers::NONE),
-                    "Right" => (KeyCode::Right, KeyModifiers::NONE),
-                    "Home" => (KeyCode::Home, KeyModifiers::NONE),
-                    "End" => (KeyCode::End, KeyModifiers::NONE),
-                    "PageUp" => (KeyCode::PageUp, KeyModifiers::NONE),
-                    "PageDown" => (KeyCode::PageDown, KeyModifiers::NONE),
-                    _ if token.starts_with("C-") => (
+                    "space" => (KeyCode::Char(' '), KeyModifiers::NONE),
+                    "bar" => (KeyCode::Char('|'), KeyModifiers::NONE),
+                    "bslash" => (KeyCode::Char('\\'), KeyModifiers::NONE),
+                    "esc" => (KeyCode::Esc, KeyModifiers::NONE),
+                    "cr" | "enter" | "return" => (KeyCode::Enter, KeyModifiers::NONE),
+                    "bs" => (KeyCode::Backspace, KeyModifiers::NONE),
+                    "del" => (KeyCode::Delete, KeyModifiers::NONE),
+                    "tab" => (KeyCode::Tab, KeyModifiers::NONE),
+                    "s-tab" => (KeyCode::BackTab, KeyModifiers::SHIFT),
+                    "up" => (KeyCode::Up, KeyModifiers::NONE),
+                    "down" => (KeyCode::Down, KeyModifiers::NONE),
+                    "left" => (KeyCode::Left, KeyModifiers::NONE),
+                    "right" => (KeyCode::Right, KeyModifiers::NONE),
+                    "home" => (KeyCode::Home, KeyModifiers::NONE),
+                    "end" => (KeyCode::End, KeyModifiers::NONE),
+                    "pageup" => (KeyCode::PageUp, KeyModifiers::NONE),
+                    "pagedown" => (KeyCode::PageDown, KeyModifiers::NONE),
+                    _ if name.starts_with("c-") => (
                         KeyCode::Char(single_macro_token_character(&token[2..])?),
                         KeyModifiers::CONTROL,
                     ),
-                    _ if token.starts_with("A-") => (
+                    _ if name.starts_with("a-") || name.starts_with("m-") => (
                         KeyCode::Char(single_macro_token_character(&token[2..])?),
                         KeyModifiers::ALT,
                     ),
+                    _ if name.starts_with('f') && name[1..].parse::<u8>().is_ok_and(|n| n > 0) => (
+                        KeyCode::F(name[1..].parse().expect("checked above")),
+                        KeyModifiers::NONE,
+                    ),
                     _ => anyhow::bail!("unsupported macro key notation <{token}>"),
                 };
                 events.push(Event::Key(KeyEvent::new(code, modifiers)));
@@ -14142,6 +14822,12 @@ impl Editor {
                         }
                     }
 
+                    if method == "textDocument/foldingRange" {
+                        if let Some(pending) = self.pending_folding_ranges.remove(&msg.id) {
+                            return self.apply_folding_ranges(pending, &msg.result);
+                        }
+                    }
+
                     if method == "textDocument/documentSymbol" {
                         if let Some(pending) = self.pending_plugin_document_symbols.remove(&msg.id)
                         {
@@ -14539,6 +15225,16 @@ impl Editor {
         &mut self,
         ev: &event::Event,
         runtime: Option<&Runtime>,
+    ) -> anyhow::Result<Option<KeyAction>> {
+        let action = self.resolve_event_with_runtime(ev, runtime)?;
+        self.bind_selected_register(action.as_ref());
+        Ok(action)
+    }
+
+    fn resolve_event_with_runtime(
+        &mut self,
+        ev: &event::Event,
+        runtime: Option<&Runtime>,
     ) -> anyhow::Result<Option<KeyAction>> {
         if let Some(action) = self.handle_keyboard_shortcuts_event(ev, runtime) {
             return Ok(Some(action));
@@ -14620,7 +15316,9 @@ impl Editor {
             self.waiting_command = None;
             self.keymap_hint_deadline = None;
             self.keymap_hints_visible = false;
-            let action = self.handle_waiting_command(ka, ev);
+            let action = self
+                .handle_waiting_command(ka, ev)
+                .or_else(|| self.unmapped_key_sequence(ev));
             if !matches!(action, Some(KeyAction::Nested(_))) {
                 self.keymap_hint_prefix.clear();
             }
@@ -14718,7 +15416,7 @@ impl Editor {
 
         Ok(match self.mode {
             Mode::Normal => self.handle_normal_event(ev),
-            Mode::Insert => self.handle_insert_event(ev)?,
+            Mode::Insert | Mode::Replace | Mode::VirtualReplace => self.handle_insert_event(ev)?,
             Mode::Command => self.handle_command_event(ev, runtime),
             Mode::Search => self.handle_search_event(ev),
             Mode::Visual | Mode::VisualLine | Mode::VisualBlock => self.handle_visual_event(ev),
@@ -15436,7 +16134,14 @@ impl Editor {
             | Action::ResetPanelLayout(_)
             | Action::MaximizeWindow
             | Action::TogglePaneZoom
-            | Action::OnlyWindow => true,
+            | Action::OnlyWindow
+            | Action::NewTabPage(_)
+            | Action::CloseTabPage
+            | Action::OnlyTabPage
+            | Action::NextTabPage
+            | Action::PreviousTabPage
+            | Action::GoToTabPage(_)
+            | Action::MoveTabPage(_) => true,
             Action::PluginCommand(command) => runtime.is_some_and(|runtime| {
                 runtime.command_scope(command).map_or_else(
                     || self.plugin_registry.has_pending_command(command),
@@ -15547,82 +16252,14 @@ impl Editor {
         false
     }
 
-    fn split_ex_line_range(command: &str) -> (&str, &str) {
-        if let Some(command) = command.strip_prefix('%') {
-            return ("%", command);
-        }
-        if let Some(command) = command.strip_prefix("'<,'>") {
-            return ("'<,'>", command);
-        }
-
-        let bytes = command.as_bytes();
-        let mut end = bytes
-            .iter()
-            .take_while(|byte| byte.is_ascii_digit())
-            .count();
-        if end == 0 {
-            return ("", command);
-        }
-        if bytes.get(end) == Some(&b',') {
-            let second_start = end + 1;
-            end = second_start
-                + bytes[second_start..]
-                    .iter()
-                    .take_while(|byte| byte.is_ascii_digit())
-                    .count();
-            if end == second_start {
-                return ("", command);
-            }
-        }
-
-        command.split_at(end)
-    }
-
-    fn resolve_ex_line_range(&self, range: &str, operation: &str) -> anyhow::Result<ExLineRange> {
-        let (start_line, end_line) = if range == "%" {
-            (0, self.last_navigable_line())
-        } else if range == "'<,'>" {
-            let buffer_id = self.current_buffer().id();
-            let start = self
-                .special_marks
-                .get(&(buffer_id, '<'))
-                .ok_or_else(|| anyhow::anyhow!("last visual range is not set"))?;
-            let end = self
-                .special_marks
-                .get(&(buffer_id, '>'))
-                .ok_or_else(|| anyhow::anyhow!("last visual range is not set"))?;
-            (
-                start.fallback.line.min(end.fallback.line),
-                start.fallback.line.max(end.fallback.line),
-            )
-        } else {
-            let lines = range
-                .split(',')
-                .map(str::parse::<usize>)
-                .collect::<Result<Vec<_>, _>>()?;
-            let start = lines[0];
-            let end = *lines.get(1).unwrap_or(&start);
-            anyhow::ensure!(
-                start > 0 && end > 0,
-                "{operation} line numbers are one-based"
-            );
-            anyhow::ensure!(start <= end, "{operation} range start exceeds its end");
-            (start - 1, end - 1)
-        };
-
-        anyhow::ensure!(
-            start_line <= self.last_navigable_line(),
-            "{operation} range starts past the end of the buffer"
-        );
-        Ok(ExLineRange {
-            start_line,
-            end_line: end_line.min(self.last_navigable_line()),
-        })
-    }
-
     fn parse_substitute_command(&self, command: &str) -> anyhow::Result<Option<SubstituteCommand>> {
         let (range, command) = Self::split_ex_line_range(command);
         let command = command.trim_start();
+        if let Some(arguments) = command.strip_prefix('&') {
+            return self
+                .parse_repeat_substitute_command(range, arguments)
+                .map(Some);
+        }
         let Some(body) = command.strip_prefix('s') else {
             return Ok(None);
         };
@@ -15641,19 +16278,11 @@ impl Editor {
             "unsupported substitute flags: {flags}"
         );
 
-        let range = if range.is_empty() {
-            let line = self.buffer_line();
-            ExLineRange {
-                start_line: line,
-                end_line: line,
-            }
-        } else {
-            self.resolve_ex_line_range(range, "substitute")?
-        };
-
+        let lines = self.substitute_lines(range)?;
         Ok(Some(SubstituteCommand {
-            start_line: range.start_line,
-            end_line: range.end_line,
+            range: range.to_string(),
+            start_line: lines.start_line,
+            end_line: lines.end_line,
             pattern,
             replacement,
             replace_all: flags.contains('g'),
@@ -15694,10 +16323,6 @@ impl Editor {
             }
         }
 
-        if let Ok(line) = cmd.parse::<usize>() {
-            return vec![Action::GoToLine(line)];
-        }
-
         match self.parse_substitute_command(cmd) {
             Ok(Some(command)) => return vec![Action::Substitute(command)],
             Ok(None) => {}
@@ -15706,6 +16331,14 @@ impl Editor {
                 return Vec::new();
             }
         }
+        match self.parse_global_command(cmd) {
+            Ok(Some(command)) => return vec![Action::Global(command)],
+            Ok(None) => {}
+            Err(error) => {
+                self.set_legacy_message(Some(error.to_string()));
+                return Vec::new();
+            }
+        }
 
         let (range, ranged_command) = Self::split_ex_line_range(cmd);
         let ranged_command = ranged_command.trim_start();
@@ -15714,6 +16347,31 @@ impl Editor {
             .unwrap_or((ranged_command, ""));
         let keep_spaces = name.ends_with('!');
         let name = name.strip_suffix('!').unwrap_or(name);
+        if !range.is_empty() && ranged_command.is_empty() {
+            return match self.parse_line_jump(range) {
+                Ok(action) => vec![action],
+                Err(error) => {
+                    self.set_legacy_message(Some(error.to_string()));
+                    Vec::new()
+                }
+            };
+        }
+        match self.parse_filter_command(range, ranged_command) {
+            Ok(Some(action)) => return vec![action],
+            Ok(None) => {}
+            Err(error) => {
+                self.set_legacy_message(Some(error.to_string()));
+                return Vec::new();
+            }
+        }
+        match self.parse_line_command(range, ranged_command) {
+            Ok(Some(action)) => return vec![action],
+            Ok(None) => {}
+            Err(error) => {
+                self.set_legacy_message(Some(error.to_string()));
+                return Vec::new();
+            }
+        }
         if matches!(name, "j" | "join") {
             if range.is_empty() {
                 let count = if arguments.trim().is_empty() {
@@ -15751,12 +16409,52 @@ impl Editor {
                 keep_spaces,
             }];
         }
-        if !range.is_empty() {
-            self.set_legacy_message(Some(if ranged_command.is_empty() {
-                "line range requires a command".to_string()
+        if matches!(name, "norm" | "normal") {
+            let keys = arguments.trim_start();
+            if keys.is_empty() {
+                self.set_legacy_message(Some("normal requires keys".to_string()));
+                return Vec::new();
+            }
+            let lines = if range.is_empty() {
+                None
             } else {
-                format!("command {name:?} does not support a line range")
-            }));
+                match self.resolve_ex_line_range(range, "normal") {
+                    Ok(range) => Some((range.start_line, range.end_line)),
+                    Err(error) => {
+                        self.set_legacy_message(Some(error.to_string()));
+                        return Vec::new();
+                    }
+                }
+            };
+            return vec![Action::ExecuteNormal {
+                lines,
+                keys: keys.to_string(),
+            }];
+        }
+        if name == "SelectMatches" {
+            let pattern = arguments.trim();
+            if pattern.is_empty() {
+                self.set_legacy_message(Some("SelectMatches requires a pattern".to_string()));
+                return Vec::new();
+            }
+            let range = if range.is_empty() { "%" } else { range };
+            let range = match self.resolve_ex_line_range(range, "SelectMatches") {
+                Ok(range) => range,
+                Err(error) => {
+                    self.set_legacy_message(Some(error.to_string()));
+                    return Vec::new();
+                }
+            };
+            return vec![Action::SelectAllMatches {
+                start_line: range.start_line,
+                end_line: range.end_line,
+                pattern: pattern.to_string(),
+            }];
+        }
+        if !range.is_empty() {
+            self.set_legacy_message(Some(format!(
+                "command {name:?} does not support a line range"
+            )));
             return Vec::new();
         }
 
@@ -15829,6 +16527,9 @@ impl Editor {
         if cmd == "registers" {
             return vec![Action::PrintRegisters];
         }
+        if let Some(actions) = self.parse_undo_navigation_command(cmd) {
+            return actions;
+        }
         if cmd == "undotree" {
             let items = self
                 .current_buffer()
@@ -15837,11 +16538,17 @@ impl Editor {
                 .into_iter()
                 .map(|entry| {
                     format!(
-                        "{}{} {} {:?}",
+                        "{}{} {} {} {:?} {}{}",
                         "  ".repeat(entry.parent.map_or(0, |_| 1)),
                         if entry.current { "*" } else { " " },
+                        entry.index + 1,
                         entry.label,
-                        entry.origin
+                        entry.origin,
+                        relative_age(entry.timestamp_ms),
+                        entry
+                            .write
+                            .map(|write| format!(" [saved {write}]"))
+                            .unwrap_or_default()
                     )
                 })
                 .collect();
@@ -15874,6 +16581,18 @@ impl Editor {
                 keys: keys.to_string(),
             }];
         }
+        if let Some(actions) = self.parse_mapping_command(cmd) {
+            return actions;
+        }
+        if let Some(actions) = self.parse_argument_command(cmd) {
+            return actions;
+        }
+        if let Some(actions) = self.parse_quickfix_command(cmd) {
+            return actions;
+        }
+        if let Some(actions) = self.parse_make_command(cmd) {
+            return actions;
+        }
 
         let (name, arguments) = cmd.split_once(' ').unwrap_or((cmd, ""));
         let name = name.strip_suffix('!').unwrap_or(name);
@@ -15921,27 +16640,43 @@ impl Editor {
         }
 
         if canonical_name == "set" {
-            let mut options = arguments.split_whitespace();
-            let Some(option) = options.next() else {
-                self.set_legacy_message(Some(
-                    "usage: set {relativenumber|norelativenumber}".to_string(),
-                ));
-                return Vec::new();
-            };
-            if options.next().is_some() {
-                self.set_legacy_message(Some(
-                    "usage: set {relativenumber|norelativenumber}".to_string(),
-                ));
+            const USAGE: &str =
+                "usage: set {relativenumber|norelativenumber|spell|nospell|spelllang=lang,...}";
+            if arguments.trim().is_empty() {
+                self.set_legacy_message(Some(USAGE.to_string()));
                 return Vec::new();
             }
-            return match option {
-                "relativenumber" | "rnu" => vec![Action::SetRelativeLineNumbers(true)],
-                "norelativenumber" | "nornu" => vec![Action::SetRelativeLineNumbers(false)],
-                _ => {
-                    self.set_legacy_message(Some(format!("unknown option {option:?}")));
-                    Vec::new()
-                }
-            };
+            let mut actions = Vec::new();
+            for option in arguments.split_whitespace() {
+                let action = match option.split_once('=') {
+                    Some(("spelllang" | "spl", languages)) => {
+                        let languages = languages
+                            .split(',')
+                            .filter(|language| !language.is_empty())
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>();
+                        if languages.is_empty() {
+                            self.set_legacy_message(Some(USAGE.to_string()));
+                            return Vec::new();
+                        }
+                        Action::SetSpellLanguages(languages)
+                    }
+                    _ => match option {
+                        "relativenumber" | "rnu" => Action::SetRelativeLineNumbers(true),
+                        "norelativenumber" | "nornu" => Action::SetRelativeLineNumbers(false),
+                        "spell" => Action::SetSpell(true),
+                        "nospell" => Action::SetSpell(false),
+                        _ => {
+                            self.set_legacy_message(Some(format!("unknown option {option:?}")));
+                            return Vec::new();
+                        }
+                    },
+                };
+                actions.push(action);
+            }
+            // Apply the languages before switching spelling on so only they load.
+            actions.sort_by_key(|action| !matches!(action, Action::SetSpellLanguages(_)));
+            return actions;
         }
 
         let Some(parsed) = parsed else {
@@ -15973,6 +16708,16 @@ impl Editor {
                 }
             }
 
+            if cmd == "update" {
+                if parsed.file_argument().is_some() {
+                    self.set_legacy_message(Some("usage: update".to_string()));
+                    return Vec::new();
+                }
+                if self.current_buffer().is_dirty() {
+                    actions.push(Action::Save);
+                }
+            }
+
             if cmd == "saveas" {
                 let Some(file) = parsed.file_argument() else {
                     self.set_legacy_message(Some("usage: saveas <file>".to_string()));
@@ -16087,6 +16832,49 @@ impl Editor {
                 actions.push(Action::OnlyWindow);
             }
 
+            if cmd == "tabnew" || cmd == "tabedit" {
+                actions.push(Action::NewTabPage(parsed.file_argument()));
+            }
+
+            if cmd == "tabclose" {
+                actions.push(Action::CloseTabPage);
+            }
+
+            if cmd == "tabonly" {
+                actions.push(Action::OnlyTabPage);
+            }
+
+            if cmd == "tabnext" || cmd == "tabprevious" {
+                let argument = parsed.file_argument();
+                let count = match argument.as_deref().map(str::parse::<usize>) {
+                    None => None,
+                    Some(Ok(count)) if count > 0 => Some(count),
+                    Some(_) => {
+                        self.set_legacy_message(Some(format!("usage: {cmd} [count]")));
+                        return Vec::new();
+                    }
+                };
+                match (cmd.as_str(), count) {
+                    ("tabnext", Some(number)) => actions.push(Action::GoToTabPage(number)),
+                    ("tabnext", None) => actions.push(Action::NextTabPage),
+                    (_, count) => actions.extend(std::iter::repeat_n(
+                        Action::PreviousTabPage,
+                        count.unwrap_or(1),
+                    )),
+                }
+            }
+
+            if cmd == "tabmove" {
+                let argument = parsed.file_argument().unwrap_or_default();
+                let Some(index) = self.tab_page_move_target(&argument) else {
+                    self.set_legacy_message(Some(format!(
+                        "invalid tab page position {argument:?}"
+                    )));
+                    return Vec::new();
+                };
+                actions.push(Action::MoveTabPage(index));
+            }
+
             if cmd == "panel-layout-reset" {
                 if parsed.args.len() > 1 {
                     self.set_legacy_message(Some(
@@ -16121,21 +16909,66 @@ impl Editor {
     }
 
     fn search_uses_case_insensitive(&self, pattern: &str) -> bool {
+        if self.config.search.dialect == SearchDialect::Vim {
+            if let Some(ignore_case) = vim_regex::case_override(pattern) {
+                return ignore_case;
+            }
+            return self.config.search.ignorecase
+                && !(self.config.search.smartcase && vim_regex::has_uppercase(pattern));
+        }
         self.config.search.ignorecase
             && !(self.config.search.smartcase && pattern.chars().any(char::is_uppercase))
     }
 
+    /// Returns `pattern` in the `regex` crate's syntax, translating it when
+    /// `search.dialect` is `"vim"`.
+    fn translate_search_pattern(&self, pattern: &str) -> anyhow::Result<TranslatedPattern> {
+        match self.config.search.dialect {
+            SearchDialect::Rust => Ok(TranslatedPattern::untranslated(pattern)),
+            SearchDialect::Vim => Ok(vim_regex::translate(
+                pattern,
+                self.last_substitute
+                    .as_ref()
+                    .map(|last| last.replacement.as_str()),
+            )?),
+        }
+    }
+
+    /// Finds the replacements `command` makes. `replacement` is its replacement with
+    /// `~` expanded, which Vim-dialect substitutes read instead of the `$` syntax. A
+    /// replacement starting with `\=` is a Husk expression evaluated for each match.
     fn plan_substitutions(
         &self,
         command: &SubstituteCommand,
+        replacement: &str,
     ) -> anyhow::Result<Vec<PlannedSubstitution>> {
         anyhow::ensure!(
             command.start_line <= self.last_navigable_line(),
             "substitute range starts past the end of the buffer"
         );
         let end_line = command.end_line.min(self.last_navigable_line());
-        let regex = RegexBuilder::new(&command.pattern)
-            .case_insensitive(command.case_insensitive)
+        let mut expression = replacement
+            .strip_prefix("\\=")
+            .map(SubstituteExpression::compile)
+            .transpose()
+            .map_err(|error| anyhow::anyhow!("invalid substitute expression: {error}"))?;
+        let (pattern, case_insensitive, vim_replacement) = match self.config.search.dialect {
+            SearchDialect::Rust => (
+                TranslatedPattern::untranslated(&command.pattern),
+                command.case_insensitive,
+                None,
+            ),
+            SearchDialect::Vim => (
+                self.translate_search_pattern(&command.pattern)
+                    .map_err(|error| anyhow::anyhow!("invalid substitute pattern: {error}"))?,
+                vim_regex::case_override(&command.pattern).unwrap_or(command.case_insensitive),
+                expression
+                    .is_none()
+                    .then(|| vim_regex::Replacement::parse(replacement)),
+            ),
+        };
+        let regex = RegexBuilder::new(&pattern.regex)
+            .case_insensitive(case_insensitive)
             .build()
             .map_err(|error| anyhow::anyhow!("invalid substitute pattern: {error}"))?;
         let mut substitutions = Vec::new();
@@ -16180,21 +17013,46 @@ impl Editor {
                 });
             };
 
-            if command.replacement.contains('$') {
+            if let Some(expression) = &mut expression {
+                for captures in vim_regex::captures_spans(&regex, line) {
+                    let replacement = expression
+                        .evaluate(&pattern.groups(&captures), line_index)
+                        .map_err(|error| {
+                            anyhow::anyhow!(
+                                "substitute expression failed on line {}: {error}",
+                                line_index + 1
+                            )
+                        })?;
+                    append(vim_regex::focus(&captures), replacement);
+                    if !command.replace_all {
+                        break;
+                    }
+                }
+            } else if let Some(vim_replacement) = &vim_replacement {
+                for captures in vim_regex::captures_spans(&regex, line) {
+                    append(
+                        vim_regex::focus(&captures),
+                        vim_replacement.expand(&captures, &pattern),
+                    );
+                    if !command.replace_all {
+                        break;
+                    }
+                }
+            } else if replacement.contains('$') {
                 for captures in regex.captures_iter(line) {
                     let matched = captures
                         .get(/*index*/ 0)
                         .expect("regex captures always include the full match");
-                    let mut replacement = String::new();
-                    captures.expand(&command.replacement, &mut replacement);
-                    append(matched, replacement);
+                    let mut expanded = String::new();
+                    captures.expand(replacement, &mut expanded);
+                    append(matched, expanded);
                     if !command.replace_all {
                         break;
                     }
                 }
             } else {
                 for matched in regex.find_iter(line) {
-                    append(matched, command.replacement.clone());
+                    append(matched, replacement.to_string());
                     if !command.replace_all {
                         break;
                     }
@@ -16222,6 +17080,41 @@ impl Editor {
         self.render(buffer)
     }
 
+    /// Plans `command` and applies it, or starts asking about each match with `c`.
+    async fn substitute(
+        &mut self,
+        command: &SubstituteCommand,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        let replacement = self.substitute_replacement(command);
+        let substitutions = match self.plan_substitutions(command, &replacement) {
+            Ok(substitutions) => {
+                self.remember_substitute(command, replacement);
+                substitutions
+            }
+            Err(error) => {
+                self.set_legacy_message(Some(error.to_string()));
+                return self.render(buffer);
+            }
+        };
+        if command.confirm && !substitutions.is_empty() {
+            let confirmation = SubstituteConfirmation {
+                command: command.clone(),
+                substitutions,
+                current: 0,
+                accepted: Vec::new(),
+                answers: Vec::new(),
+            };
+            self.show_substitute_confirmation(&confirmation, buffer)?;
+            self.substitute_confirmation = Some(confirmation);
+            Ok(())
+        } else {
+            self.apply_substitutions(substitutions, buffer, runtime)
+                .await
+        }
+    }
+
     async fn apply_substitutions(
         &mut self,
         mut substitutions: Vec<PlannedSubstitution>,
@@ -16265,7 +17158,10 @@ impl Editor {
     }
 
     fn compile_search_regex(&self, pattern: &str) -> anyhow::Result<Regex> {
-        RegexBuilder::new(pattern)
+        let translated = self
+            .translate_search_pattern(pattern)
+            .map_err(|err| anyhow::anyhow!("invalid search pattern: {err}"))?;
+        RegexBuilder::new(&translated.regex)
             .case_insensitive(self.search_uses_case_insensitive(pattern))
             .build()
             .map_err(|err| anyhow::anyhow!("invalid search pattern: {err}"))
@@ -16699,6 +17595,11 @@ impl Editor {
             return None;
         }
 
+        if let Some(action) = self.command_mode_mapping(ev) {
+            return Some(action);
+        }
+        self.expand_command_abbreviation(ev);
+
         if let Event::Key(ref event) = ev {
             let code = event.code;
             let _modifiers = event.modifiers;
@@ -16821,6 +17722,10 @@ impl Editor {
     }
 
     fn handle_visual_event(&mut self, ev: &event::Event) -> Option<KeyAction> {
+        if let Some(action) = self.handle_register_event(ev) {
+            return Some(action);
+        }
+
         if self.pending_replace {
             return self.handle_replace_event(ev);
         }
@@ -16905,7 +17810,7 @@ impl Editor {
                 return Some(KeyAction::None);
             };
 
-            if kind == TextObjectKind::Paragraph && self.select_linewise_text_range(range) {
+            if kind.is_linewise() && self.select_linewise_text_range(range) {
                 return Some(KeyAction::Single(Action::Refresh));
             }
             if self.select_text_range(range) {
@@ -16952,7 +17857,20 @@ impl Editor {
     }
 
     fn handle_insert_event(&mut self, ev: &event::Event) -> anyhow::Result<Option<KeyAction>> {
-        let insert = self.config.keys.insert.clone();
+        if let Some(action) = self.handle_insert_register_event(ev) {
+            return Ok(Some(action));
+        }
+        let expand_abbreviation = self.ends_insert_abbreviation(ev);
+        let action = self.insert_event_key_action(ev)?;
+        Ok(if expand_abbreviation {
+            action.map(mappings::with_abbreviation_expanded)
+        } else {
+            action
+        })
+    }
+
+    fn insert_event_key_action(&mut self, ev: &event::Event) -> anyhow::Result<Option<KeyAction>> {
+        let insert = self.active_keys().insert.clone();
         if let Some(ka) = self.event_to_key_action(&insert, ev) {
             return Ok(Some(ka));
         }
@@ -16989,6 +17907,14 @@ impl Editor {
     }
 
     fn handle_normal_event(&mut self, ev: &event::Event) -> Option<KeyAction> {
+        if self.pending_surround.is_some() {
+            return self.handle_surround_event(ev);
+        }
+
+        if let Some(action) = self.handle_register_event(ev) {
+            return Some(action);
+        }
+
         if self.pending_replace {
             return self.handle_replace_event(ev);
         }
@@ -17013,7 +17939,7 @@ impl Editor {
             return Some(action);
         }
 
-        let normal = self.config.keys.normal.clone();
+        let normal = self.active_keys().normal.clone();
         self.event_to_key_action(&normal, ev)
     }
 
@@ -17404,6 +18330,12 @@ impl Editor {
                         Some(self.current_line_range(pending.count(), false)),
                         "no line under cursor",
                     ),
+                '!' if pending.operator == EditOperator::Filter => self
+                    .operator_action_for_linewise_range(
+                        pending.operator,
+                        Some(self.current_line_range(pending.count(), false)),
+                        "no line under cursor",
+                    ),
                 'y' if pending.operator == EditOperator::Yank => {
                     Some(KeyAction::Single(Action::YankCurrentLines(pending.count())))
                 }
@@ -17425,6 +18357,25 @@ impl Editor {
                         Some(self.current_line_range(pending.count(), false)),
                         "no text under cursor",
                     ),
+                's' if pending.operator == EditOperator::Yank => {
+                    self.waiting_command = Some("ys".to_string());
+                    self.pending_operator = Some(PendingOperator {
+                        operator: EditOperator::Surround,
+                        ..pending
+                    });
+                    Some(KeyAction::None)
+                }
+                's' if pending.operator == EditOperator::Surround => {
+                    let range = self.surround_lines_range(pending.count());
+                    self.operator_action_for_range(pending.operator, range, "no line under cursor")
+                }
+                's' if matches!(
+                    pending.operator,
+                    EditOperator::Delete | EditOperator::Change
+                ) =>
+                {
+                    self.start_surround_search(pending.operator == EditOperator::Change)
+                }
                 'g' => {
                     self.waiting_command = Some(format!("{}g", pending.operator.as_str()));
                     self.pending_operator = Some(PendingOperator {
@@ -17708,11 +18659,18 @@ impl Editor {
                 let Some(kind) = text_object_kind_for_key(c) else {
                     return self.pending_operator_invalid();
                 };
-                self.operator_action_for_range(
-                    pending.operator,
-                    self.text_object_range_with_count(scope, kind, pending.count()),
-                    "text object not found",
-                )
+                let range = self.text_object_range_with_count(scope, kind, pending.count());
+                if matches!(
+                    kind,
+                    TextObjectKind::Indentation { .. } | TextObjectKind::Entire
+                ) {
+                    return self.operator_action_for_linewise_range(
+                        pending.operator,
+                        range.map(|range| self.linewise_text_object_range(range)),
+                        "text object not found",
+                    );
+                }
+                self.operator_action_for_range(pending.operator, range, "text object not found")
             }
         }
     }
@@ -17756,6 +18714,9 @@ impl Editor {
                 range,
                 transform: CaseTransform::Toggle,
             },
+            EditOperator::Fold => Action::CreateFold(range),
+            EditOperator::Filter => Action::OpenFilterPrompt(range),
+            EditOperator::Surround => return self.start_surround(range, false),
         };
         self.repeater = None;
         Some(KeyAction::Single(action))
@@ -17792,6 +18753,9 @@ impl Editor {
                 range,
                 transform: CaseTransform::Toggle,
             },
+            EditOperator::Fold => Action::CreateFold(range),
+            EditOperator::Filter => Action::OpenFilterPrompt(range),
+            EditOperator::Surround => return self.start_surround(range, true),
         };
         self.repeater = None;
         Some(KeyAction::Single(action))
@@ -18559,13 +19523,39 @@ impl Editor {
         };
         let first_line = selection.y0.min(selection.y1);
         let last_line = selection.y0.max(selection.y1);
+
+        self.begin_transaction("indent selection");
+        self.indent_lines(first_line, last_line, count);
+        self.move_to_text_position(TextPosition::new(first_line, 0));
+        self.selection = None;
+        self.selection_start = None;
+        self.commit_transaction(self.cursor_snapshot())
+    }
+
+    fn unindent_selection(&mut self, count: u16) -> bool {
+        let Some(selection) = self.selection else {
+            return false;
+        };
+        let first_line = selection.y0.min(selection.y1);
+        let last_line = selection.y0.max(selection.y1);
+
+        self.begin_transaction("unindent selection");
+        self.unindent_lines(first_line, last_line, count);
+        self.move_to_text_position(TextPosition::new(first_line, 0));
+        self.selection = None;
+        self.selection_start = None;
+        self.commit_transaction(self.cursor_snapshot())
+    }
+
+    /// Shifts nonblank lines right by `count` shift widths. Callers own the
+    /// transaction.
+    fn indent_lines(&mut self, first_line: usize, last_line: usize, count: u16) {
         let indentation = self.indentation();
         let columns = indentation
             .shift_width
             .saturating_mul(usize::from(count.max(1)));
         let prefix = indentation.whitespace_for_columns(columns);
 
-        self.begin_transaction("indent selection");
         for line in first_line..=last_line {
             let Some(contents) = self.current_buffer().get(line) else {
                 continue;
@@ -18575,24 +19565,15 @@ impl Editor {
             }
             self.replace_range(TextRange::insertion(TextPosition::new(line, 0)), &prefix);
         }
-        self.move_to_text_position(TextPosition::new(first_line, 0));
-        self.selection = None;
-        self.selection_start = None;
-        self.commit_transaction(self.cursor_snapshot())
     }
 
-    fn unindent_selection(&mut self, count: u16) -> bool {
-        let Some(selection) = self.selection else {
-            return false;
-        };
-        let first_line = selection.y0.min(selection.y1);
-        let last_line = selection.y0.max(selection.y1);
+    /// Shifts lines left by `count` shift widths. Callers own the transaction.
+    fn unindent_lines(&mut self, first_line: usize, last_line: usize, count: u16) {
         let indentation = self.indentation();
         let columns = indentation
             .shift_width
             .saturating_mul(usize::from(count.max(1)));
 
-        self.begin_transaction("unindent selection");
         for line in first_line..=last_line {
             let Some(contents) = self.current_buffer().get(line) else {
                 continue;
@@ -18613,10 +19594,6 @@ impl Editor {
                 &new_indentation,
             );
         }
-        self.move_to_text_position(TextPosition::new(first_line, 0));
-        self.selection = None;
-        self.selection_start = None;
-        self.commit_transaction(self.cursor_snapshot())
     }
 
     /// Executes a single editor action
@@ -18658,6 +19635,11 @@ impl Editor {
         if self.intercept_learn_action(action, buffer, runtime)? {
             return Ok(false);
         }
+        if self.reject_read_only_register_write(action) {
+            self.draw_commandline(buffer);
+            return Ok(false);
+        }
+        self.record_inserted_text(action);
         if matches!(action, Action::Save | Action::SaveAs(_))
             && self
                 .tutorial_controller
@@ -18723,6 +19705,12 @@ impl Editor {
                 | Action::ResetPanelLayout(_)
                 | Action::MaximizeWindow
                 | Action::OnlyWindow
+                | Action::NewTabPage(_)
+                | Action::CloseTabPage
+                | Action::OnlyTabPage
+                | Action::NextTabPage
+                | Action::PreviousTabPage
+                | Action::GoToTabPage(_)
         ) && self.clear_pane_zoom()
         {
             self.invalidate_terminal_render_state(buffer);
@@ -18769,6 +19757,18 @@ impl Editor {
                     return Ok(false);
                 }
             }
+            Action::MoveUp | Action::MoveDown if !self.active_closed_folds().is_empty() => {
+                // A closed fold is one row, so step over the lines it hides.
+                let target = if matches!(action, Action::MoveUp) {
+                    self.visible_line_above_cursor()
+                } else {
+                    self.visible_line_below_cursor()
+                };
+                if let Some(line) = target {
+                    self.move_cursor_to_line_keeping_goal(line);
+                }
+                self.finish_cursor_motion(buffer, true)?;
+            }
             Action::MoveUp => {
                 if self.cy == 0 {
                     // scroll up
@@ -19082,6 +20082,11 @@ impl Editor {
                 self.pending_visual_text_object_scope = None;
                 self.pending_operator = None;
                 self.pending_character_motion = None;
+                if matches!(old_mode, Mode::Command) && !matches!(new_mode, Mode::Command) {
+                    self.command.clear();
+                    self.reset_command_history_navigation();
+                    self.reset_command_completion();
+                }
 
                 // check for a pending action to be executed on the selection
                 let pending_select_action = self.pending_select_action.clone();
@@ -19089,12 +20094,27 @@ impl Editor {
                     self.execute(&select_action.action, buffer, runtime).await?;
                 }
 
-                if matches!(old_mode, Mode::Normal) && matches!(new_mode, Mode::Insert) {
+                if matches!(old_mode, Mode::Normal)
+                    && matches!(
+                        new_mode,
+                        Mode::Insert | Mode::Replace | Mode::VirtualReplace
+                    )
+                {
                     self.insert_entry_cursor = Some(self.cursor_snapshot());
                     self.begin_transaction("insert");
                 }
 
-                if matches!(old_mode, Mode::Insert) && matches!(new_mode, Mode::Normal) {
+                if matches!(old_mode, Mode::Replace | Mode::VirtualReplace)
+                    && self.finish_replace_session()
+                {
+                    self.notify_change(runtime).await?;
+                }
+
+                if matches!(
+                    old_mode,
+                    Mode::Insert | Mode::Replace | Mode::VirtualReplace
+                ) && matches!(new_mode, Mode::Normal)
+                {
                     let cleaned_generated_indent = self.cleanup_generated_indent();
                     if self.insert_entry_cursor.is_some_and(|entry| {
                         let y = self.buffer_line();
@@ -19106,6 +20126,7 @@ impl Editor {
                     // EnterMode renders before the generic post-action cursor-goal refresh.
                     self.refresh_cursor_goal();
                     self.insert_entry_cursor = None;
+                    self.finish_inserted_text();
                     let after_cursor = self.cursor_snapshot();
                     self.commit_transaction(after_cursor);
                     self.cancel_transaction_if_empty();
@@ -19152,6 +20173,29 @@ impl Editor {
 
                 self.draw_statusline(buffer);
             }
+            Action::InsertCharAtCursorPos(_)
+            | Action::InsertPastedText(_)
+            | Action::InsertNewLine
+            | Action::InsertTab
+            | Action::DeletePreviousChar
+                if self.replace_session.is_some() =>
+            {
+                if self.execute_replace_mode_action(action) {
+                    self.notify_change(runtime).await?;
+                }
+                self.refresh_cursor_goal();
+                self.render(buffer)?;
+            }
+            Action::StartReplaceMode(count) | Action::StartVirtualReplaceMode(count) => {
+                let mode = if matches!(action, Action::StartReplaceMode(_)) {
+                    Mode::Replace
+                } else {
+                    Mode::VirtualReplace
+                };
+                self.execute(&Action::EnterMode(mode), buffer, runtime)
+                    .await?;
+                self.start_replace_session(*count);
+            }
             Action::InsertCharAtCursorPos(c) => {
                 let completion_dialog_open = self
                     .current_dialog
@@ -19292,7 +20336,7 @@ impl Editor {
             Action::YankLinewiseRange(range) => {
                 let text = self.current_buffer().text_in_range(*range);
                 if !text.is_empty() {
-                    self.set_default_register(Content::linewise(text));
+                    self.store_yank(Content::linewise(text));
                     self.draw_commandline(buffer);
                 }
             }
@@ -19404,6 +20448,156 @@ impl Editor {
                 }
                 self.render(buffer)?;
             }
+            Action::DeleteLines {
+                start_line,
+                end_line,
+                register,
+            } => {
+                if self.delete_lines(*start_line, *end_line, *register) {
+                    self.notify_change(runtime).await?;
+                }
+                self.render(buffer)?;
+            }
+            Action::YankLines {
+                start_line,
+                end_line,
+                register,
+            } => {
+                self.yank_lines(*start_line, *end_line, *register);
+                self.draw_commandline(buffer);
+            }
+            Action::MoveLines {
+                start_line,
+                end_line,
+                destination,
+            } => {
+                if self.move_lines(*start_line, *end_line, *destination) {
+                    self.notify_change(runtime).await?;
+                }
+                self.render(buffer)?;
+            }
+            Action::CopyLines {
+                start_line,
+                end_line,
+                destination,
+            } => {
+                if self.copy_lines(*start_line, *end_line, *destination) {
+                    self.notify_change(runtime).await?;
+                }
+                self.render(buffer)?;
+            }
+            Action::IndentLines {
+                start_line,
+                end_line,
+                count,
+            } => {
+                if self.shift_lines(*start_line, *end_line, *count, false) {
+                    self.notify_change(runtime).await?;
+                }
+                self.render(buffer)?;
+            }
+            Action::UnindentLines {
+                start_line,
+                end_line,
+                count,
+            } => {
+                if self.shift_lines(*start_line, *end_line, *count, true) {
+                    self.notify_change(runtime).await?;
+                }
+                self.render(buffer)?;
+            }
+            Action::WriteLines {
+                start_line,
+                end_line,
+                file,
+                force,
+            } => {
+                if let Err(error) = self.write_lines(*start_line, *end_line, file, *force) {
+                    self.set_legacy_message(Some(error.to_string()));
+                }
+                self.draw_commandline(buffer);
+            }
+            Action::SortLines {
+                start_line,
+                end_line,
+                options,
+                columns,
+            } => match self.sort_lines(*start_line, *end_line, options, *columns) {
+                Ok(changed) => {
+                    if changed {
+                        self.notify_change(runtime).await?;
+                    }
+                    self.render(buffer)?;
+                }
+                Err(error) => {
+                    self.set_legacy_message(Some(error.to_string()));
+                    self.draw_commandline(buffer);
+                }
+            },
+            Action::FilterLines {
+                start_line,
+                end_line,
+                command,
+            } => match self.filter_lines(*start_line, *end_line, command).await {
+                Ok(changed) => {
+                    if changed {
+                        self.notify_change(runtime).await?;
+                    }
+                    self.render(buffer)?;
+                }
+                Err(error) => {
+                    self.set_legacy_message(Some(error.to_string()));
+                    self.draw_commandline(buffer);
+                }
+            },
+            Action::ReadCommand { line, command } => {
+                match self.read_command(*line, command).await {
+                    Ok(changed) => {
+                        if changed {
+                            self.notify_change(runtime).await?;
+                        }
+                        self.render(buffer)?;
+                    }
+                    Err(error) => {
+                        self.set_legacy_message(Some(error.to_string()));
+                        self.draw_commandline(buffer);
+                    }
+                }
+            }
+            Action::ReadFile { line, file } => match self.read_file(*line, file) {
+                Ok(changed) => {
+                    if changed {
+                        self.notify_change(runtime).await?;
+                    }
+                    self.render(buffer)?;
+                }
+                Err(error) => {
+                    self.set_legacy_message(Some(error.to_string()));
+                    self.draw_commandline(buffer);
+                }
+            },
+            Action::WriteToCommand {
+                start_line,
+                end_line,
+                command,
+            } => {
+                if let Err(error) = self.write_to_command(*start_line, *end_line, command).await {
+                    self.set_legacy_message(Some(error.to_string()));
+                }
+                self.draw_commandline(buffer);
+            }
+            Action::RunShellCommand(command) => {
+                if let Err(error) = self.run_shell_command(command).await {
+                    self.set_legacy_message(Some(error.to_string()));
+                }
+                self.draw_commandline(buffer);
+            }
+            Action::Make { arguments, scope } => {
+                if let Err(error) = self.start_make(arguments, *scope) {
+                    self.set_legacy_message(Some(error.to_string()));
+                }
+                self.draw_commandline(buffer);
+            }
             Action::ToggleCharCase(count) => {
                 let line = self.buffer_line();
                 let end_x = self
@@ -19438,22 +20632,61 @@ impl Editor {
                     self.set_legacy_message(Some("adjacent text object not found".to_string()));
                 }
             }
+            Action::StartFilterOperator(_) if self.is_visual() => {
+                self.open_filter_prompt(None);
+                self.render(buffer)?;
+            }
             Action::StartCommentOperator(count)
             | Action::StartFormatOperator(count)
             | Action::StartLowercaseOperator(count)
             | Action::StartUppercaseOperator(count)
-            | Action::StartToggleCaseOperator(count) => {
+            | Action::StartToggleCaseOperator(count)
+            | Action::StartFoldOperator(count)
+            | Action::StartFilterOperator(count) => {
                 let operator = match action {
                     Action::StartCommentOperator(_) => EditOperator::Comment,
                     Action::StartFormatOperator(_) => EditOperator::Format,
                     Action::StartLowercaseOperator(_) => EditOperator::Lowercase,
                     Action::StartUppercaseOperator(_) => EditOperator::Uppercase,
                     Action::StartToggleCaseOperator(_) => EditOperator::ToggleCase,
+                    Action::StartFoldOperator(_) => EditOperator::Fold,
+                    Action::StartFilterOperator(_) => EditOperator::Filter,
                     _ => unreachable!(),
                 };
                 self.pending_operator = Some(PendingOperator::new(operator, *count));
                 self.waiting_command = Some(operator.as_str().to_string());
             }
+            Action::AddSurround {
+                range,
+                linewise,
+                open,
+                close,
+            } => {
+                self.add_surround(*range, *linewise, open, close, runtime)
+                    .await?;
+                self.render(buffer)?;
+            }
+            Action::DeleteSurround(target) => {
+                self.replace_surround(*target, None, runtime).await?;
+                self.render(buffer)?;
+            }
+            Action::ChangeSurround {
+                target,
+                open,
+                close,
+            } => {
+                self.replace_surround(*target, Some((open, close)), runtime)
+                    .await?;
+                self.render(buffer)?;
+            }
+            Action::SurroundSelection => {
+                self.surround_selection(buffer, runtime).await?;
+                self.render(buffer)?;
+            }
+            Action::OpenFilterPrompt(range) => {
+                self.open_filter_prompt(Some(*range));
+                self.render(buffer)?;
+            }
             Action::ToggleCommentLines(count) => {
                 let start_line = self.buffer_line();
                 let last_line = start_line
@@ -19513,6 +20746,30 @@ impl Editor {
                 }
                 self.render(buffer)?;
             }
+            Action::Increment(_)
+            | Action::Decrement(_)
+            | Action::IncrementSequence(_)
+            | Action::DecrementSequence(_) => {
+                let (delta, sequence) = Self::increment_delta(action);
+                if self.is_visual() {
+                    if self.increment_selection(delta, sequence) {
+                        self.notify_change(runtime).await?;
+                    }
+                    self.execute(&Action::EnterMode(Mode::Normal), buffer, runtime)
+                        .await?;
+                    self.render(buffer)?;
+                } else {
+                    let changed =
+                        self.increment_at_cursor(delta)
+                            .is_some_and(|(range, transform)| {
+                                self.transform_text_range(range, transform, "increment")
+                            });
+                    if changed {
+                        self.notify_change(runtime).await?;
+                    }
+                    self.render(buffer)?;
+                }
+            }
             Action::TransformSelection(transform) => {
                 if self.transform_selection(*transform, None) {
                     self.notify_change(runtime).await?;
@@ -19553,7 +20810,7 @@ impl Editor {
                         ),
                         "",
                     );
-                    self.set_default_register(Content::charwise(deleted));
+                    self.store_delete(self.active_register, Content::charwise(deleted));
                     self.notify_change(runtime).await?;
                     if started_transaction {
                         self.commit_transaction(self.cursor_snapshot());
@@ -19716,7 +20973,7 @@ impl Editor {
                 };
                 let range = TextRange::new(TextPosition::new(line, 0), end);
                 let deleted_text = self.current_buffer().text_in_range(range);
-                self.set_default_register(Content::linewise(deleted_text));
+                self.store_delete(self.active_register, Content::linewise(deleted_text));
                 self.begin_transaction("delete line");
                 self.replace_range(range, "");
                 self.notify_change(runtime).await?;
@@ -19743,6 +21000,20 @@ impl Editor {
                 self.snippet_session = None;
                 self.redo_transaction(buffer, runtime).await?;
             }
+            Action::UndoEarlier(distance) => {
+                self.snippet_session = None;
+                let change = self.current_buffer().undo_history.earlier_change(*distance);
+                self.go_to_undo_change(change, buffer, runtime).await?;
+            }
+            Action::UndoLater(distance) => {
+                self.snippet_session = None;
+                let change = self.current_buffer().undo_history.later_change(*distance);
+                self.go_to_undo_change(change, buffer, runtime).await?;
+            }
+            Action::UndoToChange(change) => {
+                self.snippet_session = None;
+                self.go_to_undo_change(*change, buffer, runtime).await?;
+            }
             Action::SelectPreviousUndoBranch => {
                 add_to_history = false;
                 let message = self
@@ -19892,27 +21163,30 @@ impl Editor {
                 }
             }
             Action::Substitute(command) => {
-                let substitutions = match self.plan_substitutions(command) {
-                    Ok(substitutions) => substitutions,
+                self.substitute(command, buffer, runtime).await?;
+            }
+            Action::RepeatSubstitute | Action::RepeatSubstituteAllLines => {
+                let all_lines = matches!(action, Action::RepeatSubstituteAllLines);
+                match self.repeated_substitute_command(all_lines) {
+                    Ok(command) => self.substitute(&command, buffer, runtime).await?,
                     Err(error) => {
                         self.set_legacy_message(Some(error.to_string()));
                         self.render(buffer)?;
-                        return Ok(false);
                     }
-                };
-                if command.confirm && !substitutions.is_empty() {
-                    let confirmation = SubstituteConfirmation {
-                        substitutions,
-                        current: 0,
-                        accepted: Vec::new(),
-                    };
-                    self.show_substitute_confirmation(&confirmation, buffer)?;
-                    self.substitute_confirmation = Some(confirmation);
-                } else {
-                    self.apply_substitutions(substitutions, buffer, runtime)
-                        .await?;
                 }
             }
+            Action::Global(command) => {
+                if self.execute_global(command, buffer, runtime).await? {
+                    return Ok(true);
+                }
+                self.render(buffer)?;
+            }
+            Action::ExecuteNormal { lines, keys } => {
+                if self.execute_normal(*lines, keys, buffer, runtime).await? {
+                    return Ok(true);
+                }
+                self.render(buffer)?;
+            }
             Action::ConfirmSubstitute(decision) => {
                 add_to_history = false;
                 let Some(mut confirmation) = self.substitute_confirmation.take() else {
@@ -19930,9 +21204,13 @@ impl Editor {
                         {
                             confirmation.accepted.push(substitution);
                         }
+                        confirmation.answers.push(true);
+                        confirmation.current += 1;
+                    }
+                    SubstituteDecision::No => {
+                        confirmation.answers.push(false);
                         confirmation.current += 1;
                     }
-                    SubstituteDecision::No => confirmation.current += 1,
                     SubstituteDecision::All => {
                         confirmation.accepted.extend(
                             confirmation.substitutions[confirmation.current..]
@@ -19952,6 +21230,11 @@ impl Editor {
                         self.set_legacy_message(Some("0 substitutions".to_string()));
                         self.render(buffer)?;
                     } else {
+                        self.remember_confirmed_substitute(
+                            confirmation.command,
+                            confirmation.answers,
+                            matches!(decision, SubstituteDecision::All),
+                        );
                         self.apply_substitutions(confirmation.accepted, buffer, runtime)
                             .await?;
                     }
@@ -19960,6 +21243,82 @@ impl Editor {
                     self.substitute_confirmation = Some(confirmation);
                 }
             }
+            Action::FeedKeys { keys, remap } => {
+                add_to_history = false;
+                if self.feed_keys(keys, *remap, buffer, runtime).await? {
+                    return Ok(true);
+                }
+            }
+            Action::MapKeys {
+                modes,
+                keys,
+                rhs,
+                remap,
+            } => {
+                add_to_history = false;
+                if let Err(error) = self.map_keys(modes, keys, rhs, *remap) {
+                    self.set_legacy_message(Some(error.to_string()));
+                }
+            }
+            Action::UnmapKeys { modes, keys } => {
+                add_to_history = false;
+                if let Err(error) = self.unmap_keys(modes, keys) {
+                    self.set_legacy_message(Some(error.to_string()));
+                }
+            }
+            Action::Abbreviate {
+                modes,
+                abbreviation,
+                expansion,
+            } => {
+                add_to_history = false;
+                self.abbreviate(modes, abbreviation, expansion);
+            }
+            Action::Unabbreviate {
+                modes,
+                abbreviation,
+            } => {
+                add_to_history = false;
+                if let Err(error) = self.unabbreviate(modes, abbreviation) {
+                    self.set_legacy_message(Some(error.to_string()));
+                }
+            }
+            Action::ExpandAbbreviation => {
+                add_to_history = false;
+                self.expand_insert_abbreviation(runtime).await?;
+            }
+            Action::SetArguments(_)
+            | Action::ListArguments
+            | Action::AddArguments(_)
+            | Action::DeleteArguments(_)
+            | Action::EditArgument(_)
+            | Action::Batch(_) => {
+                // Edited arguments record their jumps through `:edit`.
+                add_to_history = false;
+                if self
+                    .execute_argument_action(action, buffer, runtime)
+                    .await?
+                {
+                    return Ok(true);
+                }
+            }
+            Action::SetQuickfixList(_)
+            | Action::JumpToQuickfixEntry(_, _)
+            | Action::NextQuickfixEntry
+            | Action::PreviousQuickfixEntry
+            | Action::QuickfixHistory(_, _)
+            | Action::ListQuickfix(_)
+            | Action::OpenQuickfixPanel(_)
+            | Action::CloseQuickfixPanel(_) => {
+                // Opening an entry records its own jump.
+                add_to_history = false;
+                if self
+                    .execute_quickfix_action(action, buffer, runtime)
+                    .await?
+                {
+                    return Ok(true);
+                }
+            }
             Action::SetMacroRegister { register, keys } => {
                 add_to_history = false;
                 let Some(register) = normalize_macro_register(*register) else {
@@ -19977,20 +21336,11 @@ impl Editor {
             }
             Action::PrintRegisters => {
                 add_to_history = false;
-                let mut registers = self
-                    .registers
-                    .iter()
-                    .map(|(register, content)| (*register, content.text.clone()))
-                    .collect::<Vec<_>>();
-                registers.sort_by_key(|(register, _)| *register);
+                let registers = self.register_listing();
                 self.set_legacy_message(Some(if registers.is_empty() {
                     "no registers".to_string()
                 } else {
-                    registers
-                        .into_iter()
-                        .map(|(register, text)| format!("{register}: {text}"))
-                        .collect::<Vec<_>>()
-                        .join("  ")
+                    registers.join("  ")
                 }));
                 self.draw_commandline(buffer);
             }
@@ -20342,10 +21692,11 @@ impl Editor {
                     self.record_command_history(cmd);
                     false
                 };
+                self.record_command_register(cmd);
                 let result: anyhow::Result<bool> = async {
                     for action in actions {
                         self.set_legacy_message(None);
-                        if self.execute(&action, buffer, runtime).await? {
+                        if self.execute_ex_action(&action, buffer, runtime).await? {
                             return Ok(true);
                         }
                     }
@@ -20425,6 +21776,39 @@ impl Editor {
                     .await?;
                 }
             }
+            Action::NextMisspelledWord | Action::PreviousMisspelledWord => {
+                let forward = matches!(action, Action::NextMisspelledWord);
+                match self.misspelling_navigation_target(forward) {
+                    Ok(Some(target)) => {
+                        self.execute_with_tracking(
+                            &Action::MoveTo(target.character, target.line + 1),
+                            buffer,
+                            runtime,
+                            false,
+                        )
+                        .await?;
+                    }
+                    Ok(None) => {
+                        self.set_legacy_message(Some("no misspelled words".to_string()));
+                    }
+                    Err(error) => {
+                        self.set_notification_message(Severity::Error, Some(error));
+                    }
+                }
+            }
+            Action::SuggestSpelling => {
+                self.release_current_dialog_callbacks(runtime);
+                self.open_spelling_suggestions();
+                self.render(buffer)?;
+            }
+            Action::ReplaceMisspelledWord(replacement) => {
+                self.replace_misspelled_word(replacement, runtime).await?;
+                self.render(buffer)?;
+            }
+            Action::MarkWordGood | Action::MarkWordWrong => {
+                self.mark_spelling(matches!(action, Action::MarkWordGood));
+                self.render(buffer)?;
+            }
             Action::ExecuteLspCommand(command) => {
                 self.execute_lsp_command(command, None).await?;
             }
@@ -20867,6 +22251,14 @@ impl Editor {
                 self.config.relative_line_numbers = Some(*enabled);
                 self.render(buffer)?;
             }
+            Action::SetSpell(enabled) => {
+                self.set_spell(*enabled);
+                self.render(buffer)?;
+            }
+            Action::SetSpellLanguages(languages) => {
+                self.set_spell_languages(languages.clone());
+                self.render(buffer)?;
+            }
             Action::MoveToNextWord => {
                 let line = self.buffer_line();
                 let char_cx = self.next_word_search_char_on_line(self.cx, line);
@@ -20942,7 +22334,9 @@ impl Editor {
             | Action::MoveToNextFunction
             | Action::MoveToPreviousFunction
             | Action::MoveToNextClass
-            | Action::MoveToPreviousClass => {
+            | Action::MoveToPreviousClass
+            | Action::MoveToNextParameter
+            | Action::MoveToPreviousParameter => {
                 let (kind, backward) = match action {
                     Action::MoveToNextCall => (SyntaxObjectKind::Call, false),
                     Action::MoveToPreviousCall => (SyntaxObjectKind::Call, true),
@@ -20950,6 +22344,8 @@ impl Editor {
                     Action::MoveToPreviousFunction => (SyntaxObjectKind::Function, true),
                     Action::MoveToNextClass => (SyntaxObjectKind::Class, false),
                     Action::MoveToPreviousClass => (SyntaxObjectKind::Class, true),
+                    Action::MoveToNextParameter => (SyntaxObjectKind::Parameter, false),
+                    Action::MoveToPreviousParameter => (SyntaxObjectKind::Parameter, true),
                     _ => unreachable!(),
                 };
                 if let Some(position) = self.syntax_motion_target(kind, backward, 1) {
@@ -20971,6 +22367,27 @@ impl Editor {
             Action::MatchitForward => {
                 self.move_to_matchit_motion(MatchDirection::Forward, buffer)?;
             }
+            Action::CreateFold(_)
+            | Action::CreateFoldFromSelection
+            | Action::OpenFold
+            | Action::CloseFold
+            | Action::ToggleFold
+            | Action::OpenAllFolds
+            | Action::CloseAllFolds
+            | Action::DeleteFold
+            | Action::DeleteAllFolds
+            | Action::MoveToNextFold
+            | Action::MoveToPreviousFold => {
+                self.execute_fold_action(action, buffer).await?;
+            }
+            Action::AddCursorAbove
+            | Action::AddCursorBelow
+            | Action::AddCursorAtNextMatch
+            | Action::SplitSelectionIntoLines
+            | Action::SelectAllMatches { .. }
+            | Action::RemoveSecondaryCursors => {
+                self.execute_multi_cursor_action(action, buffer)?;
+            }
             Action::MatchitBackward => {
                 self.move_to_matchit_motion(MatchDirection::Backward, buffer)?;
             }
@@ -21136,7 +22553,14 @@ impl Editor {
                 self.notify_search_cleared(runtime).await?;
             }
             Action::SearchWordUnderCursor => {
-                if let Some(search_term) = self.word_under_cursor() {
+                if let Some(word) = self.word_under_cursor() {
+                    let search_term = match self.config.search.dialect {
+                        SearchDialect::Rust => word,
+                        SearchDialect::Vim => format!(r"\<{}\>", vim_regex::escape(&word)),
+                    };
+                    if self.config.search.dialect == SearchDialect::Vim {
+                        self.record_search_history(&search_term);
+                    }
                     self.search_term = search_term;
                     self.search_direction = SearchDirection::Forward;
                     self.search_highlights_suppressed = false;
@@ -21842,7 +23266,7 @@ impl Editor {
                 }
             }
             Action::Yank => {
-                if self.selection.is_some() && self.yank(DEFAULT_REGISTER) {
+                if self.selection.is_some() && self.yank() {
                     // self.render(buffer)?;
                     self.draw_commandline(buffer);
                 }
@@ -21856,7 +23280,7 @@ impl Editor {
                 let range = self.current_line_range(*count, true);
                 let text = self.current_buffer().text_in_range(range);
                 if !text.is_empty() {
-                    self.set_default_register(Content::linewise(text));
+                    self.store_yank(Content::linewise(text));
                     self.draw_commandline(buffer);
                 }
             }
@@ -21900,7 +23324,14 @@ impl Editor {
                 self.buffer_text(value);
             }
             Action::InsertBlock => {
-                self.execute_block_action(buffer, runtime, Mode::Insert)
+                self.execute_block_action(buffer, runtime, Mode::Insert, None)
+                    .await?
+            }
+            Action::AppendBlock => {
+                let edge = self
+                    .selection
+                    .map(|selection| self.block_columns(selection).end);
+                self.execute_block_action(buffer, runtime, Mode::Insert, edge)
                     .await?
             }
             Action::ClearDiagnostics(uri, lines) => {
@@ -21959,6 +23390,38 @@ impl Editor {
                     self.render(buffer)?;
                 }
             }
+            Action::RequestInsertCompletion(source) => {
+                self.request_insert_completion(*source).await?;
+                self.render(buffer)?;
+            }
+            Action::StartInsertRegister => {
+                add_to_history = false;
+                self.start_insert_register();
+            }
+            Action::InsertRegister {
+                register,
+                literally,
+            } => {
+                self.insert_register(*register, *literally, buffer, runtime)
+                    .await?;
+                self.render(buffer)?;
+            }
+            Action::StartInsertNormalCommand => {
+                add_to_history = false;
+                self.start_insert_normal_command(buffer, runtime).await?;
+                self.render(buffer)?;
+            }
+            Action::DeletePreviousWord | Action::DeleteToInsertStart => {
+                let to_insert_start = matches!(action, Action::DeleteToInsertStart);
+                self.delete_before_insert_cursor(to_insert_start, buffer, runtime)
+                    .await?;
+                self.render(buffer)?;
+            }
+            Action::IncreaseInsertIndent | Action::DecreaseInsertIndent => {
+                let outdent = matches!(action, Action::DecreaseInsertIndent);
+                self.shift_insert_indent(outdent, runtime).await?;
+                self.render(buffer)?;
+            }
             Action::Copilot(command) => {
                 add_to_history = false;
                 self.handle_copilot_command(command);
@@ -22150,9 +23613,21 @@ impl Editor {
                 timer_stats::log_timer_stats();
             }
             Action::NotifyPlugins(method, params) => {
-                self.plugin_registry
-                    .notify(runtime, method, params.clone())
-                    .await?;
+                if let Some(scope) = method
+                    .strip_prefix("panel:event:")
+                    .and_then(QuickfixScope::from_panel_id)
+                {
+                    if self
+                        .handle_quickfix_panel_event(scope, params, buffer, runtime)
+                        .await?
+                    {
+                        return Ok(true);
+                    }
+                } else {
+                    self.plugin_registry
+                        .notify(runtime, method, params.clone())
+                        .await?;
+                }
             }
             Action::NotifyPlugin(plugin, method, params) => {
                 self.plugin_registry
@@ -22246,7 +23721,13 @@ impl Editor {
                 }
             }
             Action::CloseWindow => {
-                if self.update_window_layout(WindowManager::close_window) {
+                // The last window of a tab page closes the page, as in Vim.
+                let closed = if self.window_manager.window_count() == 1 {
+                    self.tab_pages.count() > 1 && self.close_tab_page()
+                } else {
+                    self.update_window_layout(WindowManager::close_window)
+                };
+                if closed {
                     self.render(buffer)?;
                 }
             }
@@ -22384,6 +23865,40 @@ impl Editor {
                     self.render(buffer)?;
                 }
             }
+            Action::NewTabPage(file) => {
+                self.open_tab_page(file.as_deref(), buffer).await?;
+            }
+            Action::CloseTabPage => {
+                if self.close_tab_page() {
+                    self.render(buffer)?;
+                } else {
+                    self.draw_commandline(buffer);
+                }
+            }
+            Action::OnlyTabPage => {
+                if self.close_other_tab_pages() {
+                    self.render(buffer)?;
+                }
+            }
+            Action::NextTabPage | Action::PreviousTabPage => {
+                let offset = if matches!(action, Action::NextTabPage) {
+                    1
+                } else {
+                    -1
+                };
+                if self.cycle_tab_page(offset) {
+                    self.render(buffer)?;
+                }
+            }
+            Action::GoToTabPage(number) => {
+                if self.select_tab_page(number.saturating_sub(1)) {
+                    self.render(buffer)?;
+                }
+            }
+            Action::MoveTabPage(index) => {
+                self.tab_pages.move_current(*index);
+                self.render(buffer)?;
+            }
         }
 
         if let Some(generated) = self.generated_indent {
@@ -22419,7 +23934,8 @@ impl Editor {
         }
 
         let bounds_span = perf::PerfSpan::start("edit:post_action_bounds");
-        let bounds_changed = self.check_bounds();
+        let folds_changed = self.settle_cursor_in_folds(action);
+        let bounds_changed = self.check_bounds() || folds_changed;
 
         if Self::should_refresh_cursor_goal_after(action) {
             self.refresh_cursor_goal();
@@ -22572,10 +24088,13 @@ impl Editor {
                 | Action::MoveToPreviousFunction
                 | Action::MoveToNextClass
                 | Action::MoveToPreviousClass
+                | Action::MoveToNextParameter
+                | Action::MoveToPreviousParameter
                 | Action::SwapNextParameter
                 | Action::SwapPreviousParameter
                 | Action::SwapNextFunction
                 | Action::SwapPreviousFunction
+                | Action::SortLines { .. }
                 | Action::NextDiagnostic
                 | Action::PreviousDiagnostic
                 | Action::NextInlineComment
@@ -22709,7 +24228,12 @@ impl Editor {
     }
 
     fn forget_jumps_for_buffer(&mut self, buffer_id: BufferId) {
-        for window in self.window_manager.windows_mut() {
+        for window in self
+            .window_manager
+            .windows_mut()
+            .into_iter()
+            .chain(self.tab_pages.windows_mut())
+        {
             let old_index = window.jump_list.index.min(window.jump_list.entries.len());
             let retained_before_index = window.jump_list.entries[..old_index]
                 .iter()
@@ -22878,8 +24402,9 @@ impl Editor {
         buffer: &'a mut RenderBuffer,
         runtime: &'a mut Runtime,
         mode: Mode,
+        append: Option<visual_block::BlockEdge>,
     ) -> BoxFuture<'a, anyhow::Result<()>> {
-        Box::pin(self.execute_block_action_impl(buffer, runtime, mode))
+        Box::pin(self.execute_block_action_impl(buffer, runtime, mode, append))
     }
 
     async fn execute_block_action_impl(
@@ -22887,6 +24412,7 @@ impl Editor {
         buffer: &mut RenderBuffer,
         runtime: &mut Runtime,
         mode: Mode,
+        append: Option<visual_block::BlockEdge>,
     ) -> anyhow::Result<()> {
         match self.pending_select_action.take() {
             Some(pending_action) => {
@@ -22900,10 +24426,13 @@ impl Editor {
                     self.move_to_first_selected_line(&selection);
 
                     if matches!(mode, Mode::Insert) {
-                        self.insert_entry_cursor = Some(self.cursor_snapshot());
                         if !self.transaction_active() {
                             self.begin_transaction("insert block");
                         }
+                        if let Some(edge) = append {
+                            self.cx = self.block_append_position(self.buffer_line(), edge);
+                        }
+                        self.insert_entry_cursor = Some(self.cursor_snapshot());
                     }
 
                     // allow user to work on the mode as per normal
@@ -22912,11 +24441,10 @@ impl Editor {
 
                     // and signal that when it is done, we should start the block
                     // insertion
-                    self.pending_select_action = Some(ActionOnSelection::new(
-                        Action::InsertBlock,
-                        selection,
-                        self.actions.len(),
-                    ));
+                    self.pending_select_action = Some(ActionOnSelection {
+                        append,
+                        ..ActionOnSelection::new(Action::InsertBlock, selection, self.actions.len())
+                    });
                 };
             }
         }
@@ -22967,7 +24495,7 @@ impl Editor {
 
         if matches!(mode, Mode::VisualBlock) {
             self.selection = Some(Rect::new(insertion_x, y0, insertion_x, y1));
-            self.execute_block_action(buffer, runtime, Mode::Insert)
+            self.execute_block_action(buffer, runtime, Mode::Insert, None)
                 .await?;
         } else {
             self.insert_entry_cursor = Some(self.cursor_snapshot());
@@ -23074,18 +24602,8 @@ impl Editor {
         };
 
         let primary_cursor = self.cursor_snapshot();
-        let committed_deferred_render = self.deferred_motion_render;
-        let committed_terminal_frame = self.previous_render_buffer.clone();
-        let committed_render_generation = self.render_generation;
-        let committed_viewport = self.last_rendered_viewport;
-        let committed_cursor_position = self.last_rendered_cursor_position;
-        let committed_bracket_rows = self.last_rendered_bracket_rows.clone();
-        let committed_cursor_surface = self.last_rendered_cursor_surface.clone();
-        let committed_force_full_redraw = self.force_full_redraw;
+        let committed_frame = self.begin_hidden_replay();
         let mut scratch_buffer = buffer.clone();
-        let previous_terminal_output_enabled = self.terminal_output_enabled;
-        self.terminal_output_enabled = false;
-        self.block_replay_depth += 1;
 
         let mut replay_result = Ok(());
         for y in y0 + 1..=y1 {
@@ -23093,7 +24611,10 @@ impl Editor {
                 self.vtop = y;
             }
             self.cy = y.saturating_sub(self.vtop);
-            self.cx = selection.x0;
+            self.cx = match pending_action.append {
+                Some(edge) => self.block_append_position(y, edge),
+                None => selection.x0,
+            };
             let batch = batched_action
                 .as_ref()
                 .filter(|_| self.can_batch_block_replay());
@@ -23128,34 +24649,58 @@ impl Editor {
             }
         }
 
-        self.block_replay_depth = self.block_replay_depth.saturating_sub(1);
-        self.terminal_output_enabled = previous_terminal_output_enabled;
-        // Replay renders into a scratch frame with terminal output suppressed. Keep
-        // terminal-derived caches tied to the last frame that was actually written,
-        // then return to the primary edit so Escape finishes at the block's first row.
-        self.deferred_motion_render = committed_deferred_render;
-        self.previous_render_buffer = committed_terminal_frame;
-        self.render_generation = committed_render_generation;
-        self.last_rendered_viewport = committed_viewport;
-        self.last_rendered_cursor_position = committed_cursor_position;
-        self.last_rendered_bracket_rows = committed_bracket_rows;
-        self.last_rendered_cursor_surface = committed_cursor_surface;
-        self.force_full_redraw = committed_force_full_redraw;
+        // Return to the primary edit so Escape finishes at the block's first row.
+        self.end_hidden_replay(committed_frame);
         self.restore_cursor_snapshot(primary_cursor);
 
         // A failed replay may already have changed earlier rows. Publish those
         // edits as well, while preserving the original replay error.
-        let notification_result = if self.block_replay_depth == 0
-            && std::mem::take(&mut self.block_replay_change_deferred)
-        {
+        let notification_result = self.publish_hidden_replay_changes(runtime).await;
+        replay_result.and(notification_result)
+    }
+
+    /// Suppresses terminal output while actions replay at positions other than the
+    /// primary cursor, rendering into a scratch frame instead.
+    fn begin_hidden_replay(&mut self) -> CommittedFrame {
+        let frame = CommittedFrame {
+            deferred_render: self.deferred_motion_render,
+            terminal_frame: self.previous_render_buffer.clone(),
+            render_generation: self.render_generation,
+            viewport: self.last_rendered_viewport,
+            cursor_position: self.last_rendered_cursor_position,
+            bracket_rows: self.last_rendered_bracket_rows.clone(),
+            cursor_surface: self.last_rendered_cursor_surface.clone(),
+            force_full_redraw: self.force_full_redraw,
+            terminal_output_enabled: self.terminal_output_enabled,
+        };
+        self.terminal_output_enabled = false;
+        self.block_replay_depth += 1;
+        frame
+    }
+
+    /// Keeps terminal-derived caches tied to the last frame that was actually written.
+    fn end_hidden_replay(&mut self, frame: CommittedFrame) {
+        self.block_replay_depth = self.block_replay_depth.saturating_sub(1);
+        self.terminal_output_enabled = frame.terminal_output_enabled;
+        self.deferred_motion_render = frame.deferred_render;
+        self.previous_render_buffer = frame.terminal_frame;
+        self.render_generation = frame.render_generation;
+        self.last_rendered_viewport = frame.viewport;
+        self.last_rendered_cursor_position = frame.cursor_position;
+        self.last_rendered_bracket_rows = frame.bracket_rows;
+        self.last_rendered_cursor_surface = frame.cursor_surface;
+        self.force_full_redraw = frame.force_full_redraw;
+    }
+
+    async fn publish_hidden_replay_changes(&mut self, runtime: &mut Runtime) -> anyhow::Result<()> {
+        if self.block_replay_depth == 0 && std::mem::take(&mut self.block_replay_change_deferred) {
             self.notify_change(runtime).await
         } else {
             Ok(())
-        };
-        replay_result.and(notification_result)
+        }
     }
 
-    fn yank(&mut self, register: char) -> bool {
+    fn yank(&mut self) -> bool {
         if let Some(content) = self.selected_content() {
             log!("selected_content: {content:#?}");
             let count = content.text.lines().count();
@@ -23171,7 +24716,7 @@ impl Editor {
                     needs_update = true;
                 }
             };
-            self.set_register(register, content);
+            self.store_yank(content);
 
             return needs_update;
         }
@@ -23231,7 +24776,7 @@ impl Editor {
             return false;
         };
 
-        self.set_default_register(Content::linewise(line));
+        self.store_yank(Content::linewise(line));
         true
     }
 
@@ -23241,7 +24786,7 @@ impl Editor {
             return false;
         }
 
-        self.set_default_register(Content::charwise(text));
+        self.store_yank(Content::charwise(text));
         true
     }
 
@@ -23255,7 +24800,7 @@ impl Editor {
                     text: selected_text.clone(),
                 };
 
-                self.set_default_register(content.clone());
+                self.store_delete(self.active_register, content.clone());
 
                 match self.mode {
                     Mode::VisualLine => {
@@ -23267,26 +24812,10 @@ impl Editor {
                         self.replace_range(TextRange::new(TextPosition::new(y0, 0), end), "");
                     }
                     Mode::VisualBlock => {
-                        let min_x = std::cmp::min(x0, x1);
-                        let max_x = std::cmp::max(x0, x1);
-
+                        let columns = self.block_columns(selection);
                         for y in y0..=y1 {
-                            if let Some(line) = self.current_buffer().get(y) {
-                                let line = line.trim_end_matches('\n');
-                                let line_len = grapheme_len(line);
-                                if min_x >= line_len {
-                                    continue;
-                                }
-                                let start = self.grapheme_to_char_on_line(min_x, y);
-                                let end =
-                                    self.grapheme_to_char_on_line((max_x + 1).min(line_len), y);
-                                self.replace_range(
-                                    TextRange::new(
-                                        TextPosition::new(y, start),
-                                        TextPosition::new(y, end),
-                                    ),
-                                    "",
-                                );
+                            if let Some(range) = self.block_row_range(y, columns) {
+                                self.replace_range(range, "");
                             }
                         }
                     }
@@ -23306,8 +24835,7 @@ impl Editor {
     }
 
     fn paste_default(&mut self, before: bool) -> bool {
-        self.refresh_default_register_from_system_clipboard();
-        let contents = self.registers.get(&DEFAULT_REGISTER).cloned();
+        let contents = self.register_content_for_put();
 
         if let Some(contents) = contents {
             self.paste(&contents, before);
@@ -23318,8 +24846,7 @@ impl Editor {
     }
 
     fn paste_over_selection(&mut self, preserve_default_register: bool) -> bool {
-        self.refresh_default_register_from_system_clipboard();
-        let Some(source) = self.registers.get(&DEFAULT_REGISTER).cloned() else {
+        let Some(source) = self.register_content_for_put() else {
             return false;
         };
         let Some(replaced) = self.selected_content() else {
@@ -23337,7 +24864,7 @@ impl Editor {
         self.move_to_text_position(plan.cursor);
         self.fix_cursor_pos();
         if !preserve_default_register {
-            self.set_default_register(replaced);
+            self.store_delete(None, replaced);
         }
         self.commit_transaction(self.cursor_snapshot());
         true
@@ -23443,10 +24970,14 @@ impl Editor {
             }
             ContentKind::Blockwise => {
                 lines.splice(y0..=y1, [format!("{prefix}{suffix}")]);
-                let paste_x = grapheme_len(&prefix);
-                for (offset, block_line) in source.text.lines().enumerate() {
-                    insert_at_grapheme_column(lines, y0 + offset, paste_x, block_line);
-                }
+                let tab_width = self.active_tab_width();
+                put_block_rows(
+                    lines,
+                    y0,
+                    display_width_with_tabs(&prefix, tab_width),
+                    &source.text.lines().collect::<Vec<_>>(),
+                    tab_width,
+                );
                 Some(TextPosition::new(y0, prefix.chars().count()))
             }
         }
@@ -23461,18 +24992,31 @@ impl Editor {
         y1: usize,
         source: &Content,
     ) -> Option<TextPosition> {
-        let min_x = x0.min(x1);
-        let max_x = x0.max(x1);
+        let columns = self.block_columns(Rect::new(x0, y0, x1, y1));
+        let tab_width = self.active_tab_width();
+        let rows = (y0..=y1)
+            .map(|y| self.block_row_graphemes(y, columns))
+            .collect::<Vec<_>>();
+        let remove_row = |lines: &mut Vec<String>, y: usize| {
+            if let Some((start, end)) = rows[y - y0].filter(|(start, end)| end > start) {
+                remove_grapheme_columns(lines, y, start, end - 1);
+            }
+        };
+        let block_start = |lines: &[String]| {
+            let line = lines.get(y0).map(String::as_str).unwrap_or_default();
+            let grapheme = column_to_grapheme_with_tabs(line, columns.start, tab_width);
+            TextPosition::new(y0, grapheme_to_char(line, grapheme))
+        };
         let top_line = lines.get(y0)?.clone();
-        let top_start = grapheme_to_byte(&top_line, min_x.min(grapheme_len(&top_line)));
-        let top_end = grapheme_to_byte(&top_line, (max_x + 1).min(grapheme_len(&top_line)));
-        let top_prefix = top_line[..top_start].to_string();
-        let top_suffix = top_line[top_end..].to_string();
+        let top_len = grapheme_len(&top_line);
+        let (top_start, top_end) = rows[0].unwrap_or((top_len, top_len));
+        let top_prefix = top_line[..grapheme_to_byte(&top_line, top_start)].to_string();
+        let top_suffix = top_line[grapheme_to_byte(&top_line, top_end)..].to_string();
 
         match source.kind {
             ContentKind::Charwise if source.text.contains('\n') => {
                 for y in (y0 + 1)..=y1 {
-                    remove_grapheme_columns(lines, y, min_x, max_x);
+                    remove_row(lines, y);
                 }
                 let source_lines = source.text.split('\n').collect::<Vec<_>>();
                 let mut replacement = Vec::with_capacity(source_lines.len());
@@ -23491,14 +25035,20 @@ impl Editor {
             }
             ContentKind::Charwise => {
                 for y in y0..=y1 {
-                    remove_grapheme_columns(lines, y, min_x, max_x);
-                    insert_at_grapheme_column(lines, y, min_x, &source.text);
-                }
-                Some(TextPosition::new(y0, min_x))
+                    remove_row(lines, y);
+                }
+                put_block_rows(
+                    lines,
+                    y0,
+                    columns.start,
+                    &vec![source.text.as_str(); y1 - y0 + 1],
+                    tab_width,
+                );
+                Some(block_start(lines))
             }
             ContentKind::Linewise => {
                 for y in y0..=y1 {
-                    remove_grapheme_columns(lines, y, min_x, max_x);
+                    remove_row(lines, y);
                 }
                 let insertion = source.text.lines().map(str::to_string).collect::<Vec<_>>();
                 lines.splice((y1 + 1)..(y1 + 1), insertion);
@@ -23506,12 +25056,16 @@ impl Editor {
             }
             ContentKind::Blockwise => {
                 for y in y0..=y1 {
-                    remove_grapheme_columns(lines, y, min_x, max_x);
-                }
-                for (offset, block_line) in source.text.lines().enumerate() {
-                    insert_at_grapheme_column(lines, y0 + offset, min_x, block_line);
-                }
-                Some(TextPosition::new(y0, min_x))
+                    remove_row(lines, y);
+                }
+                put_block_rows(
+                    lines,
+                    y0,
+                    columns.start,
+                    &source.text.lines().collect::<Vec<_>>(),
+                    tab_width,
+                );
+                Some(block_start(lines))
             }
         }
     }
@@ -23557,39 +25111,6 @@ impl Editor {
         self.move_to_first_non_blank_on_current_line();
     }
 
-    fn insert_blockwise(&mut self, x: usize, y: usize, contents: &Content, before: bool) {
-        let lines: Vec<&str> = contents.text.lines().collect();
-        let paste_x = if before { x } else { x + 1 };
-
-        for (dy, line) in lines.iter().enumerate() {
-            let y = y + dy;
-            // Extend the buffer with empty lines if needed
-            while self.current_buffer().len() <= y {
-                self.replace_range(TextRange::insertion(TextPosition::new(y, 0)), "\n");
-            }
-
-            let current_line = self.current_buffer().get(y).unwrap_or_default();
-            let current_line = current_line.trim_end_matches('\n');
-            let mut new_line = current_line.to_string();
-
-            // Extend the line with spaces if needed
-            while grapheme_len(&new_line) < paste_x {
-                new_line.push(' ');
-            }
-
-            // Insert the block text
-            let paste_byte = grapheme_to_byte(&new_line, paste_x);
-            new_line.insert_str(paste_byte, line);
-            self.replace_range(
-                TextRange::new(
-                    TextPosition::new(y, 0),
-                    TextPosition::new(y, current_line.chars().count()),
-                ),
-                &new_line,
-            );
-        }
-    }
-
     fn insert_charwise(&mut self, x: usize, y: usize, contents: &Content, before: bool) {
         let insert_x = self.grapheme_to_char_on_line(x, y);
         let insertion = if before {
@@ -23813,7 +25334,12 @@ impl Editor {
             self.vx = self.gutter_width() + 1;
             self.prev_highlight_y = None;
 
-            for window in self.window_manager.windows_mut() {
+            for window in self
+                .window_manager
+                .windows_mut()
+                .into_iter()
+                .chain(self.tab_pages.windows_mut())
+            {
                 window.buffer_index = 0;
                 window.cx = 0;
                 window.cy = 0;
@@ -23850,7 +25376,12 @@ impl Editor {
         let target_vtop = self.current_buffer().vtop;
         let target_vx = self.gutter_width() + 1;
 
-        for window in self.window_manager.windows_mut() {
+        for window in self
+            .window_manager
+            .windows_mut()
+            .into_iter()
+            .chain(self.tab_pages.windows_mut())
+        {
             if window.buffer_index == removed_index {
                 window.buffer_index = target_index;
                 window.cx = target_cx;
@@ -24688,9 +26219,27 @@ impl Editor {
                     KeyAction::Single(Action::ChangeCharsAtCursor(_)) => {
                         KeyAction::Single(Action::ChangeCharsAtCursor(count))
                     }
+                    KeyAction::Single(Action::StartReplaceMode(_)) => {
+                        KeyAction::Single(Action::StartReplaceMode(count))
+                    }
+                    KeyAction::Single(Action::StartVirtualReplaceMode(_)) => {
+                        KeyAction::Single(Action::StartVirtualReplaceMode(count))
+                    }
                     KeyAction::Single(Action::ToggleCharCase(_)) => {
                         KeyAction::Single(Action::ToggleCharCase(count))
                     }
+                    KeyAction::Single(Action::Increment(_)) => {
+                        KeyAction::Single(Action::Increment(count))
+                    }
+                    KeyAction::Single(Action::Decrement(_)) => {
+                        KeyAction::Single(Action::Decrement(count))
+                    }
+                    KeyAction::Single(Action::IncrementSequence(_)) => {
+                        KeyAction::Single(Action::IncrementSequence(count))
+                    }
+                    KeyAction::Single(Action::DecrementSequence(_)) => {
+                        KeyAction::Single(Action::DecrementSequence(count))
+                    }
                     KeyAction::Single(Action::RepeatCharSearch(_)) => {
                         KeyAction::Single(Action::RepeatCharSearch(count))
                     }
@@ -24706,6 +26255,9 @@ impl Editor {
                     KeyAction::Single(Action::MoveToBottom) => {
                         KeyAction::Single(Action::GoToLine(usize::from(count)))
                     }
+                    KeyAction::Single(Action::NextTabPage) => {
+                        KeyAction::Single(Action::GoToTabPage(usize::from(count)))
+                    }
                     KeyAction::Single(Action::HalfPageDown(_)) => {
                         KeyAction::Single(Action::HalfPageDown(count))
                     }
@@ -24721,6 +26273,12 @@ impl Editor {
                     KeyAction::Single(Action::StartFormatOperator(_)) => {
                         KeyAction::Single(Action::StartFormatOperator(count))
                     }
+                    KeyAction::Single(Action::StartFoldOperator(_)) => {
+                        KeyAction::Single(Action::StartFoldOperator(count))
+                    }
+                    KeyAction::Single(Action::StartFilterOperator(_)) => {
+                        KeyAction::Single(Action::StartFilterOperator(count))
+                    }
                     KeyAction::Single(Action::ToggleCommentLines(_)) => {
                         KeyAction::Single(Action::ToggleCommentLines(count))
                     }
@@ -24870,6 +26428,7 @@ impl Editor {
             LastVisualSelection {
                 mode: self.mode,
                 anchor_at_start: self.selection_start == Some(start_point),
+                to_line_end: self.block_extends_to_line_end(),
             },
         );
     }
@@ -24916,6 +26475,9 @@ impl Editor {
         self.pending_operator = None;
         self.pending_character_motion = None;
         self.refresh_cursor_goal();
+        if selection.to_line_end {
+            self.cursor_goal = CursorGoal::LineEnd;
+        }
         self.selection_start = Some(anchor);
         self.update_selection_end(cursor);
         true
@@ -24927,28 +26489,46 @@ impl Editor {
         end_char: usize,
         new_char_len: usize,
     ) {
+        anchor.char_index = Self::transform_char_index(
+            anchor.char_index,
+            anchor.affinity,
+            AppliedTextEdit {
+                start_char,
+                end_char,
+                new_char_len,
+                ..AppliedTextEdit::default()
+            },
+        );
+    }
+
+    fn transform_char_index(
+        char_index: usize,
+        affinity: AnchorAffinity,
+        edit: AppliedTextEdit,
+    ) -> usize {
+        let AppliedTextEdit {
+            start_char,
+            end_char,
+            new_char_len,
+            ..
+        } = edit;
         let replaced_len = end_char.saturating_sub(start_char);
-        anchor.char_index = if anchor.char_index < start_char {
-            anchor.char_index
-        } else if replaced_len == 0 && anchor.char_index == start_char {
-            match anchor.affinity {
-                AnchorAffinity::Left => start_char,
-                AnchorAffinity::Right => start_char.saturating_add(new_char_len),
-            }
-        } else if anchor.char_index >= end_char {
-            anchor
-                .char_index
+        if char_index < start_char {
+            char_index
+        } else if char_index >= end_char && (replaced_len > 0 || char_index > start_char) {
+            char_index
                 .saturating_sub(replaced_len)
                 .saturating_add(new_char_len)
         } else {
-            match anchor.affinity {
+            match affinity {
                 AnchorAffinity::Left => start_char,
                 AnchorAffinity::Right => start_char.saturating_add(new_char_len),
             }
-        };
+        }
     }
 
     fn update_anchors_for_edit(&mut self, edit: AppliedTextEdit) {
+        self.adjust_folds_for_edit(edit.range, edit.new_line_breaks);
         if !self.inline_history.conversations.is_empty()
             || !self.inline_jobs.is_empty()
             || self.inline_history_browser.is_some()
@@ -24956,6 +26536,8 @@ impl Editor {
             self.transform_inline_history_for_edit(edit);
         }
         self.transform_snippet_anchors(edit);
+        self.transform_secondary_cursors(edit);
+        self.transform_quickfix_anchors(edit);
         let buffer_id = self.current_buffer().id();
         if !self.inline_comments.is_empty() {
             let buffer = &self.buffer_manager[self.buffer_manager.active_index()];
@@ -25025,7 +26607,12 @@ impl Editor {
                 );
             }
         }
-        for window in self.window_manager.windows_mut() {
+        for window in self
+            .window_manager
+            .windows_mut()
+            .into_iter()
+            .chain(self.tab_pages.windows_mut())
+        {
             for entry in &mut window.jump_list.entries {
                 if entry.buffer_id == buffer_id {
                     let mut anchor = EditAnchor {
@@ -25093,7 +26680,12 @@ impl Editor {
             .filter(|((anchor_buffer_id, mark), _)| *anchor_buffer_id == buffer_id && *mark != '.')
             .map(|(_, anchor)| anchor)
             .for_each(update_fallback);
-        for window in self.window_manager.windows_mut() {
+        for window in self
+            .window_manager
+            .windows_mut()
+            .into_iter()
+            .chain(self.tab_pages.windows_mut())
+        {
             window
                 .jump_list
                 .entries
@@ -25180,6 +26772,9 @@ impl Editor {
             }
         }
         perf::increment("edit:replacements", 1);
+        if !self.ex_line_marks.is_empty() {
+            self.adjust_line_marks_for_edit(range, new_text);
+        }
         self.update_anchors_for_edit(edit);
         self.set_special_mark_at_char('.', edit.start_char, AnchorAffinity::Left);
     }
@@ -25193,7 +26788,10 @@ impl Editor {
             || (deletes_through_eof
                 && range.start.character > 0
                 && (deleted_text.starts_with('\n') || deleted_text.starts_with("\r\n")));
-        self.set_default_register(Content::charwise(deleted_text.clone()));
+        self.store_delete(
+            self.active_register,
+            Content::charwise(deleted_text.clone()),
+        );
         self.move_to_text_position(range.start);
 
         if deleted_text.is_empty() {
@@ -25211,6 +26809,9 @@ impl Editor {
     }
 
     fn transformed_text(text: &str, transform: CaseTransform) -> String {
+        if let CaseTransform::Increment { delta, kind } = transform {
+            return increment::apply(text, delta, kind).unwrap_or_else(|| text.to_string());
+        }
         let mut transformed = String::with_capacity(text.len());
         for character in text.chars() {
             match transform {
@@ -25222,7 +26823,9 @@ impl Editor {
                 CaseTransform::Toggle if character.is_uppercase() => {
                     transformed.extend(character.to_lowercase());
                 }
-                CaseTransform::Toggle => transformed.push(character),
+                CaseTransform::Toggle | CaseTransform::Increment { .. } => {
+                    transformed.push(character)
+                }
             }
         }
         transformed
@@ -25491,7 +27094,16 @@ impl Editor {
 
         self.begin_transaction(label);
         self.replace_range(range, &replacement);
-        self.move_to_text_position(range.start);
+        if matches!(transform, CaseTransform::Increment { .. }) {
+            // Like Vim, the cursor ends on the last character of the changed text.
+            let length = replacement.chars().count();
+            self.move_to_text_position(TextPosition::new(
+                range.start.line,
+                range.start.character + length.saturating_sub(1),
+            ));
+        } else {
+            self.move_to_text_position(range.start);
+        }
         self.commit_transaction(self.cursor_snapshot());
         true
     }
@@ -25510,17 +27122,19 @@ impl Editor {
                     )
                 })
                 .collect::<Vec<_>>(),
-            Mode::VisualBlock => (y0..=y1)
-                .map(|line| {
-                    let line_len = self.length_for_line(line);
-                    let start = x0.min(x1).min(line_len);
-                    let end = x0.max(x1).saturating_add(1).min(line_len);
-                    TextRange::new(
-                        TextPosition::new(line, self.grapheme_to_char_on_line(start, line)),
-                        TextPosition::new(line, self.grapheme_to_char_on_line(end, line)),
-                    )
-                })
-                .collect::<Vec<_>>(),
+            Mode::VisualBlock => {
+                let columns = self.block_columns(selection);
+                (y0..=y1)
+                    .map(|line| {
+                        self.block_row_range(line, columns).unwrap_or_else(|| {
+                            TextRange::insertion(TextPosition::new(
+                                line,
+                                self.line_character_len(line),
+                            ))
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            }
             Mode::Visual => {
                 if replacement.is_some() {
                     (y0..=y1)
@@ -25543,7 +27157,12 @@ impl Editor {
                     vec![TextRange::new(start, end)]
                 }
             }
-            Mode::Normal | Mode::Insert | Mode::Command | Mode::Search => return false,
+            Mode::Normal
+            | Mode::Insert
+            | Mode::Replace
+            | Mode::VirtualReplace
+            | Mode::Command
+            | Mode::Search => return false,
         };
 
         let edits = ranges
@@ -25648,7 +27267,10 @@ impl Editor {
 
     fn delete_linewise_range(&mut self, range: TextRange, label: &str) -> bool {
         let deleted_text = self.current_buffer().text_in_range(range);
-        self.set_default_register(Content::linewise(deleted_text.clone()));
+        self.store_delete(
+            self.active_register,
+            Content::linewise(deleted_text.clone()),
+        );
         self.move_to_text_position(range.start);
 
         if deleted_text.is_empty() {
@@ -25670,7 +27292,7 @@ impl Editor {
         } else {
             Content::charwise(deleted_text.clone())
         };
-        self.set_default_register(content);
+        self.store_delete(self.active_register, content);
         self.move_to_text_position(range.start);
 
         if deleted_text.is_empty() {
@@ -25843,11 +27465,31 @@ impl Editor {
         render_buffer: &mut RenderBuffer,
         runtime: &mut Runtime,
     ) -> anyhow::Result<()> {
-        let transaction = self
+        if !self.apply_undo_step() {
+            self.set_legacy_message(Some("already at oldest change".to_string()));
+            self.draw_commandline(render_buffer);
+            return Ok(());
+        }
+        self.notify_change(runtime).await?;
+        self.render(render_buffer)?;
+
+        Ok(())
+    }
+
+    /// Undoes the current change without notifying listeners or rendering.
+    /// Returns `false` at the oldest change.
+    fn apply_undo_step(&mut self) -> bool {
+        let (transaction, selections) = self
             .current_buffer()
             .undo_history
             .latest_transaction()
-            .map(|transaction| transaction.id.clone());
+            .map(|transaction| {
+                (
+                    Some(transaction.id.clone()),
+                    transaction.before_selections.clone(),
+                )
+            })
+            .unwrap_or_default();
         let buffer = self.current_buffer_mut();
         let mut history = std::mem::take(&mut buffer.undo_history);
         let outcome = history.undo(buffer);
@@ -25855,9 +27497,7 @@ impl Editor {
         buffer.refresh_dirty();
 
         let Some((cursor, edits)) = outcome else {
-            self.set_legacy_message(Some("already at oldest change".to_string()));
-            self.draw_commandline(render_buffer);
-            return Ok(());
+            return false;
         };
         self.clear_current_buffer_diagnostics();
         if let Some(transaction) = transaction {
@@ -25868,11 +27508,9 @@ impl Editor {
             self.set_special_mark_at_char('.', edit.start_char, AnchorAffinity::Left);
         }
         self.restore_cursor_snapshot(cursor);
+        self.restore_secondary_cursors(selections);
         self.restore_inline_history_comments();
-        self.notify_change(runtime).await?;
-        self.render(render_buffer)?;
-
-        Ok(())
+        true
     }
 
     async fn redo_transaction(
@@ -25880,6 +27518,20 @@ impl Editor {
         render_buffer: &mut RenderBuffer,
         runtime: &mut Runtime,
     ) -> anyhow::Result<()> {
+        if !self.apply_redo_step() {
+            self.set_legacy_message(Some("already at newest change".to_string()));
+            self.draw_commandline(render_buffer);
+            return Ok(());
+        }
+        self.notify_change(runtime).await?;
+        self.render(render_buffer)?;
+
+        Ok(())
+    }
+
+    /// Redoes the selected child change without notifying listeners or rendering.
+    /// Returns `false` at the newest change.
+    fn apply_redo_step(&mut self) -> bool {
         let buffer = self.current_buffer_mut();
         let mut history = std::mem::take(&mut buffer.undo_history);
         let outcome = history.redo(buffer);
@@ -25887,17 +27539,21 @@ impl Editor {
         buffer.refresh_dirty();
 
         let Some((cursor, edits)) = outcome else {
-            self.set_legacy_message(Some("already at newest change".to_string()));
-            self.draw_commandline(render_buffer);
-            return Ok(());
+            return false;
         };
         self.clear_current_buffer_diagnostics();
-        if let Some(transaction) = self
+        let (transaction, selections) = self
             .current_buffer()
             .undo_history
             .latest_transaction()
-            .map(|transaction| transaction.id.clone())
-        {
+            .map(|transaction| {
+                (
+                    Some(transaction.id.clone()),
+                    transaction.after_selections.clone(),
+                )
+            })
+            .unwrap_or_default();
+        if let Some(transaction) = transaction {
             self.set_inline_history_transaction_applied(&transaction, true);
         }
         for edit in edits {
@@ -25905,13 +27561,104 @@ impl Editor {
             self.set_special_mark_at_char('.', edit.start_char, AnchorAffinity::Left);
         }
         self.restore_cursor_snapshot(cursor);
+        self.restore_secondary_cursors(selections);
         self.restore_inline_history_comments();
+        true
+    }
+
+    /// Moves through the undo tree to the state numbered `change`, undoing up to
+    /// the nearest shared state and redoing down the target's branch.
+    async fn go_to_undo_change(
+        &mut self,
+        change: usize,
+        render_buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        let Some(steps) = self.current_buffer().undo_history.path_to_change(change) else {
+            self.set_legacy_message(Some(format!("undo number {change} not found")));
+            self.draw_commandline(render_buffer);
+            return Ok(());
+        };
+        if steps.is_empty() {
+            let message = if change == 0 {
+                "already at oldest change".to_string()
+            } else if change == self.current_buffer().undo_history.newest_change() {
+                "already at newest change".to_string()
+            } else {
+                format!("already at change {change}")
+            };
+            self.set_legacy_message(Some(message));
+            self.draw_commandline(render_buffer);
+            return Ok(());
+        }
+        for step in steps {
+            let applied = match step {
+                UndoStep::Undo => self.apply_undo_step(),
+                UndoStep::Redo(change) => {
+                    self.current_buffer_mut().undo_history.select_redo(change)
+                        && self.apply_redo_step()
+                }
+            };
+            if !applied {
+                break;
+            }
+        }
+        let history = &self.current_buffer().undo_history;
+        let message = match history.change_info(history.change_number()) {
+            Some((Some(timestamp_ms), write)) => format!(
+                "change {}; {}{}",
+                history.change_number(),
+                relative_age(timestamp_ms),
+                write
+                    .map(|write| format!(", write {write}"))
+                    .unwrap_or_default()
+            ),
+            _ => "original text".to_string(),
+        };
+        self.set_legacy_message(Some(message));
         self.notify_change(runtime).await?;
         self.render(render_buffer)?;
-
         Ok(())
     }
 
+    /// Parses `:u[ndo] [N]`, `:red[o]`, `:ea[rlier] [N]{s|m|h|d|f}` and
+    /// `:lat[er] [N]{s|m|h|d|f}`.
+    fn parse_undo_navigation_command(&mut self, command: &str) -> Option<Vec<Action>> {
+        let (name, argument) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+        let is = |full: &str, minimum: usize| name.len() >= minimum && full.starts_with(name);
+        if is("undo", 1) {
+            if argument.is_empty() {
+                return Some(vec![Action::Undo]);
+            }
+            let Ok(change) = argument.parse() else {
+                self.set_legacy_message(Some("usage: undo [N]".to_string()));
+                return Some(Vec::new());
+            };
+            return Some(vec![Action::UndoToChange(change)]);
+        }
+        if is("redo", 3) && argument.is_empty() {
+            return Some(vec![Action::Redo]);
+        }
+        let earlier = is("earlier", 2);
+        if !earlier && !is("later", 3) {
+            return None;
+        }
+        let Some(distance) = UndoDistance::parse(argument) else {
+            self.set_legacy_message(Some(format!(
+                "usage: {} [N][s|m|h|d|f]",
+                if earlier { "earlier" } else { "later" }
+            )));
+            return Some(Vec::new());
+        };
+        Some(vec![if earlier {
+            Action::UndoEarlier(distance)
+        } else {
+            Action::UndoLater(distance)
+        }])
+    }
+
     pub fn current_file_name(&self) -> Option<String> {
         self.current_buffer().file.clone()
     }
@@ -25987,6 +27734,55 @@ impl Editor {
         }
     }
 
+    /// Keeps the undo trees of written files in `directory` across launches.
+    pub fn enable_undo_files(&mut self, directory: PathBuf) {
+        let store = undo_files::UndoFileStore::new(directory);
+        if self.config.undofile.enabled {
+            store.prune(&self.config.undofile);
+        }
+        self.undo_files = Some(store);
+        for index in 0..self.buffer_manager.len() {
+            self.restore_undo_file(index);
+        }
+    }
+
+    fn restore_undo_file(&mut self, index: usize) {
+        if !self.config.undofile.enabled {
+            return;
+        }
+        let (Some(store), Some(buffer)) = (&self.undo_files, self.buffer_manager.get_mut(index))
+        else {
+            return;
+        };
+        // History recovered from a session snapshot is newer than the undo file.
+        if buffer.is_dirty() || buffer.undo_history.node_count() > 0 {
+            return;
+        }
+        match store.load(buffer, &self.config.undofile) {
+            Ok(Some(mut history)) => {
+                history.set_max_nodes(buffer.undo_history.max_nodes());
+                buffer.undo_history = history;
+            }
+            Ok(None) => {}
+            Err(error) => log!("ignoring undo file for {}: {error}", buffer.name()),
+        }
+    }
+
+    fn write_undo_file(&self) {
+        if !self.config.undofile.enabled {
+            return;
+        }
+        let Some(store) = &self.undo_files else {
+            return;
+        };
+        if let Err(error) = store.write(self.current_buffer(), &self.config.undofile) {
+            log!(
+                "failed to write undo file for {}: {error}",
+                self.current_buffer().name()
+            );
+        }
+    }
+
     #[doc(hidden)]
     pub fn test_persist_session_snapshot(&mut self, force: bool, due: bool) {
         if due {
@@ -26076,19 +27872,30 @@ impl Editor {
                 .copied()
                 .unwrap_or_default(),
         );
-        self.window_manager = WindowManager::from_snapshot(
-            &snapshot.window_layout,
-            (self.size.0 as usize, self.size.1 as usize),
-            &buffer_map,
-        )
-        .unwrap_or_else(|| {
-            WindowManager::new(
-                self.buffer_manager.active_index(),
-                (self.size.0 as usize, self.size.1 as usize),
-            )
-        });
+        let size = (self.size.0 as usize, self.size.1 as usize);
+        let pages = snapshot
+            .tab_layouts
+            .iter()
+            .filter_map(|layout| WindowManager::from_snapshot(layout, size, &buffer_map))
+            .collect::<Vec<_>>();
+        let restored_pages = (pages.len() == snapshot.tab_layouts.len())
+            .then(|| TabPages::from_pages(pages, snapshot.current_tab))
+            .flatten();
+        if let Some((current, pages)) = restored_pages {
+            self.window_manager = current;
+            self.tab_pages = pages;
+        } else {
+            self.window_manager =
+                WindowManager::from_snapshot(&snapshot.window_layout, size, &buffer_map)
+                    .unwrap_or_else(|| {
+                        WindowManager::new(self.buffer_manager.active_index(), size)
+                    });
+            self.tab_pages = TabPages::default();
+        }
+        self.claim_restored_folds();
         self.panel_manager.stage_restore(snapshot.panels.clone());
         self.registers = snapshot.registers.clone();
+        self.last_substitute = snapshot.last_substitute.clone();
         let saved_window_jumps = if snapshot.window_jumps.is_empty() {
             vec![SessionWindowJumps {
                 window_index: self.window_manager.active_window_id(),
@@ -26166,6 +27973,7 @@ impl Editor {
                 LastVisualSelection {
                     mode,
                     anchor_at_start: selection.anchor_at_start,
+                    to_line_end: selection.to_line_end,
                 },
             );
         }
@@ -26290,6 +28098,10 @@ impl Editor {
         }
         self.recompute_window_cursor_goals();
         self.sync_with_window();
+        if self.tab_pages.count() > 1 {
+            // Restored pages were laid out without the tab line.
+            self.apply_panel_layout();
+        }
         self.check_bounds();
         Ok(divergences)
     }
@@ -26487,12 +28299,18 @@ impl Editor {
                     Mode::Visual => SessionVisualMode::Character,
                     Mode::VisualLine => SessionVisualMode::Line,
                     Mode::VisualBlock => SessionVisualMode::Block,
-                    Mode::Normal | Mode::Insert | Mode::Command | Mode::Search => return None,
+                    Mode::Normal
+                    | Mode::Insert
+                    | Mode::Replace
+                    | Mode::VirtualReplace
+                    | Mode::Command
+                    | Mode::Search => return None,
                 };
                 Some(SessionVisualSelection {
                     buffer_index,
                     mode,
                     anchor_at_start: selection.anchor_at_start,
+                    to_line_end: selection.to_line_end,
                 })
             })
             .collect();
@@ -26513,8 +28331,18 @@ impl Editor {
                 buffers,
                 current_buffer_index: self.buffer_manager.active_index(),
                 window_layout: self.window_manager.snapshot(),
+                tab_layouts: if self.tab_pages.count() > 1 {
+                    self.tab_pages
+                        .pages(&self.window_manager)
+                        .map(WindowManager::snapshot)
+                        .collect()
+                } else {
+                    Vec::new()
+                },
+                current_tab: self.tab_pages.current_index(),
                 panels: self.panel_manager.snapshot(usize::from(self.size.0)),
                 registers: self.registers.clone(),
+                last_substitute: self.last_substitute.clone(),
                 jumps,
                 jump_index,
                 window_jumps,
@@ -26915,6 +28743,12 @@ impl Editor {
                 (self.size.0 as usize, self.size.1 as usize),
             )
         });
+        // Editor state files record a single layout.
+        self.tab_pages = TabPages::default();
+        self.claim_restored_folds();
+        for index in 0..self.buffer_manager.len() {
+            self.restore_undo_file(index);
+        }
 
         self.recompute_window_cursor_goals();
 
@@ -26974,20 +28808,15 @@ impl Editor {
             }
             Mode::VisualBlock => {
                 let mut text = String::new();
-                let min_x = std::cmp::min(x0, x1);
-                let max_x = std::cmp::max(x0, x1);
-
+                let columns = self.block_columns(selection);
                 for y in y0..=y1 {
-                    if let Some(line) = self.current_buffer().get(y) {
-                        let line = line.trim_end_matches('\n');
-                        let line_len = grapheme_len(line);
-                        if min_x <= line_len {
-                            let start = self.grapheme_to_char_on_line(min_x, y);
-                            let end = self.grapheme_to_char_on_line((max_x + 1).min(line_len), y);
-                            text.push_str(char_slice(line, start, end));
-                        }
-                        text.push('\n');
+                    if self.current_buffer().get(y).is_none() {
+                        break;
                     }
+                    if let Some(range) = self.block_row_range(y, columns) {
+                        text.push_str(&self.current_buffer().text_in_range(range));
+                    }
+                    text.push('\n');
                 }
                 Some(text)
             }
@@ -27356,6 +29185,7 @@ impl Editor {
             cursor: None,
             original_range: None,
             current_range: None,
+            insert_completion: None,
         }
     }
 
@@ -27486,6 +29316,18 @@ impl Editor {
         self.scheduled_completion = None;
         let buffer_items = self.buffer_completion_items();
         let snapshot = self.completion_snapshot();
+        self.request_completion_items(trigger_character, buffer_items, snapshot)
+            .await
+    }
+
+    /// Shows `buffer_items` at once and asks the language server for more, merging
+    /// them when the response arrives.
+    async fn request_completion_items(
+        &mut self,
+        trigger_character: Option<char>,
+        buffer_items: Vec<CompletionResponseItem>,
+        snapshot: CompletionSnapshot,
+    ) -> anyhow::Result<bool> {
         let displayed_immediately = !buffer_items.is_empty()
             && self.show_completion_items(buffer_items.clone(), snapshot.clone());
         let pending_snapshot = self
@@ -27611,12 +29453,19 @@ impl Editor {
             self.set_legacy_message(Some(format!("invalid LSP completion edit: {error}")));
             return Ok(());
         }
-        let resume_insert_transaction = self.transaction_active();
+        let insert_start = snapshot
+            .as_ref()
+            .and_then(|snapshot| snapshot.insert_completion.clone());
+        let keep_insert_transaction = insert_start.is_some() && self.transaction_active();
+        let resume_insert_transaction = !keep_insert_transaction && self.transaction_active();
         if resume_insert_transaction {
             self.commit_transaction(self.cursor_snapshot());
         }
 
-        self.begin_transaction("apply completion");
+        if !keep_insert_transaction {
+            self.begin_transaction("apply completion");
+        }
+        let cursor_before = self.cursor_text_position();
 
         let mut edits = vec![completion_edit_from_lsp(
             &contents,
@@ -27631,10 +29480,12 @@ impl Editor {
         edits.sort_by(|a, b| compare_text_positions_desc(a.range.start, b.range.start));
 
         let mut cursor_position = None;
+        let mut main_edit = None;
         for edit in edits {
             self.replace_range(edit.range, &edit.new_text);
 
             if edit.is_main {
+                main_edit = Some((edit.range, edit.new_text.clone()));
                 if let Some(snippet) = &edit.snippet {
                     self.activate_snippet_session(edit.range.start, snippet);
                 }
@@ -27677,8 +29528,16 @@ impl Editor {
             }
         }
 
+        if let (Some(start), Some((range, text)), None) =
+            (&insert_start, &main_edit, commit_character)
+        {
+            self.record_insert_completion(start, *range, text, cursor_before);
+        }
+
         self.notify_change(runtime).await?;
-        self.commit_transaction(self.cursor_snapshot());
+        if !keep_insert_transaction {
+            self.commit_transaction(self.cursor_snapshot());
+        }
 
         if resume_insert_transaction && self.is_insert() {
             self.begin_transaction("insert");
@@ -28076,6 +29935,7 @@ impl Editor {
                 );
                 self.sync_lsp_document_identity(previous_uri.as_deref(), index)
                     .await?;
+                self.write_undo_file();
                 self.notify_lsp_saved().await;
                 let file = self.current_buffer().file.clone();
                 self.plugin_registry
@@ -28331,6 +30191,7 @@ impl Editor {
                 };
                 self.set_notification_message(severity, Some(format_warning.unwrap_or(msg)));
 
+                self.write_undo_file();
                 self.notify_lsp_saved().await;
                 // Notify plugins about file save
                 if let Some(file) = &self.current_buffer().file {
@@ -28495,6 +30356,7 @@ impl Editor {
                         "format-on-save unavailable; saved unformatted: {error}"
                     )));
                 }
+                self.write_undo_file();
                 self.notify_lsp_saved().await;
                 let saved_file = self
                     .current_buffer()
@@ -29251,17 +31113,6 @@ fn trim_trailing_horizontal_whitespace(contents: &str) -> Option<String> {
     changed.then_some(trimmed_contents)
 }
 
-fn insert_at_grapheme_column(lines: &mut Vec<String>, y: usize, x: usize, text: &str) {
-    while lines.len() <= y {
-        lines.push(String::new());
-    }
-    while grapheme_len(&lines[y]) < x {
-        lines[y].push(' ');
-    }
-    let byte = grapheme_to_byte(&lines[y], x);
-    lines[y].insert_str(byte, text);
-}
-
 fn remove_grapheme_columns(lines: &mut [String], y: usize, min_x: usize, max_x: usize) {
     let Some(line) = lines.get_mut(y) else {
         return;
@@ -30419,6 +32270,16 @@ impl Editor {
         self.window_manager.windows().len()
     }
 
+    #[doc(hidden)]
+    pub fn test_tab_page_count(&self) -> usize {
+        self.tab_pages.count()
+    }
+
+    #[doc(hidden)]
+    pub fn test_current_tab_page(&self) -> usize {
+        self.tab_pages.current_index()
+    }
+
     #[doc(hidden)]
     pub fn test_active_window_bounds(&self) -> Option<(Point, (usize, usize))> {
         self.window_manager
@@ -30884,6 +32745,117 @@ mod test {
         assert_eq!(editor.buffer_manager.active_index(), 0);
     }
 
+    #[tokio::test]
+    async fn tab_pages_keep_their_layouts_through_session_snapshots() {
+        let mut editor = test_editor(80, 24);
+        let mut buffer = RenderBuffer::new(80, 24, &Style::default());
+        let mut runtime = Runtime::new();
+        editor
+            .execute(&Action::SplitVertical, &mut buffer, &mut runtime)
+            .await
+            .unwrap();
+        editor
+            .execute(&Action::NewTabPage(None), &mut buffer, &mut runtime)
+            .await
+            .unwrap();
+        editor
+            .execute(&Action::NewTabPage(None), &mut buffer, &mut runtime)
+            .await
+            .unwrap();
+        editor
+            .execute(&Action::GoToTabPage(2), &mut buffer, &mut runtime)
+            .await
+            .unwrap();
+        let snapshot = editor.test_session_snapshot();
+        assert_eq!(snapshot.tab_layouts.len(), 3);
+        assert_eq!(snapshot.current_tab, 1);
+        assert_eq!(snapshot.tab_layouts[1], snapshot.window_layout);
+
+        let encoded = serde_json::to_vec(&snapshot).unwrap();
+        let snapshot: SessionSnapshot = serde_json::from_slice(&encoded).unwrap();
+        let mut restored = test_editor(80, 24);
+        for _ in 1..snapshot.buffers.len() {
+            restored
+                .buffer_manager
+                .push_buffer(Buffer::new(None, String::new()));
+        }
+        restored.restore_session_snapshot(&snapshot).unwrap();
+        assert_eq!(restored.test_tab_page_count(), 3);
+        assert_eq!(restored.test_current_tab_page(), 1);
+        assert_eq!(restored.buffer_manager.active_index(), 1);
+        assert_eq!(
+            restored.window_manager.active_window().unwrap().position,
+            Point::new(0, 1)
+        );
+        restored
+            .execute(&Action::GoToTabPage(1), &mut buffer, &mut runtime)
+            .await
+            .unwrap();
+        assert_eq!(restored.window_manager.window_count(), 2);
+
+        // One page is saved without tab layouts, as before tab pages existed.
+        restored
+            .execute(&Action::OnlyTabPage, &mut buffer, &mut runtime)
+            .await
+            .unwrap();
+        let snapshot = restored.test_session_snapshot();
+        assert!(snapshot.tab_layouts.is_empty());
+        assert!(!serde_json::to_string(&snapshot)
+            .unwrap()
+            .contains("tab_layouts"));
+    }
+
+    #[tokio::test]
+    async fn tab_page_commands_resolve_counts_and_positions() {
+        let mut editor = test_editor(80, 24);
+        let mut buffer = RenderBuffer::new(80, 24, &Style::default());
+        let mut runtime = Runtime::new();
+        for _ in 0..3 {
+            editor
+                .execute(&Action::NewTabPage(None), &mut buffer, &mut runtime)
+                .await
+                .unwrap();
+        }
+        editor
+            .execute(&Action::GoToTabPage(2), &mut buffer, &mut runtime)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            editor.handle_command("tabn 3", &runtime),
+            vec![Action::GoToTabPage(3)]
+        );
+        assert_eq!(
+            editor.handle_command("tabnext", &runtime),
+            vec![Action::NextTabPage]
+        );
+        assert_eq!(
+            editor.handle_command("tabp 2", &runtime),
+            vec![Action::PreviousTabPage, Action::PreviousTabPage]
+        );
+        assert!(editor.handle_command("tabnext 0", &runtime).is_empty());
+        assert_eq!(editor.last_error.as_deref(), Some("usage: tabnext [count]"));
+
+        for (argument, target) in [
+            ("", 3),
+            ("$", 3),
+            ("0", 0),
+            ("1", 1),
+            ("3", 2),
+            ("+", 2),
+            ("+9", 3),
+            ("-1", 0),
+            ("-9", 0),
+        ] {
+            assert_eq!(
+                editor.handle_command(&format!("tabmove {argument}"), &runtime),
+                vec![Action::MoveTabPage(target)],
+                "tabmove {argument}"
+            );
+        }
+        assert!(editor.handle_command("tabmove x", &runtime).is_empty());
+    }
+
     #[tokio::test]
     async fn learn_red_restores_the_original_workspace_and_protects_files() {
         let mut editor = test_editor(120, 32);
@@ -33536,6 +35508,45 @@ builtin = "rust"
         drain_plugin_requests();
     }
 
+    #[tokio::test]
+    async fn detached_frames_draw_the_tab_line_above_the_windows() {
+        drain_plugin_requests();
+        let mut core = DetachedEditorCore::new(test_editor(/*width*/ 80, /*height*/ 24))
+            .await
+            .unwrap();
+        let first_row = core.snapshot(/*last_revision*/ None).lines[0].text.clone();
+        assert!(first_row.contains("hello"));
+
+        core.editor
+            .execute(
+                &Action::NewTabPage(None),
+                &mut core.render_buffer,
+                &mut core.runtime,
+            )
+            .await
+            .unwrap();
+        core.finish_render().unwrap();
+        let lines = core.snapshot(/*last_revision*/ None).lines;
+        assert!(lines[0].text.starts_with(" [No Name]  [No Name] "));
+        assert!(!lines[0].text.contains("hello"));
+        assert!(lines[1].text.contains('1'));
+
+        core.editor
+            .execute(
+                &Action::CloseTabPage,
+                &mut core.render_buffer,
+                &mut core.runtime,
+            )
+            .await
+            .unwrap();
+        core.finish_render().unwrap();
+        assert_eq!(
+            core.snapshot(/*last_revision*/ None).lines[0].text,
+            first_row
+        );
+        drain_plugin_requests();
+    }
+
     #[tokio::test]
     async fn detached_multiple_pending_frames_reserialize_every_changed_row() {
         drain_plugin_requests();
@@ -35518,7 +37529,7 @@ builtin = "rust"
         assert!(!rendered_dump(&mut editor, 100, 30).contains("red v"));
 
         let with_files = Config {
-            startup_file_count: 1,
+            startup_files: vec!["main.rs".to_string()],
             ..Config::default()
         };
         let mut editor = splash_test_editor(100, 30, with_files);
@@ -39901,6 +41912,577 @@ builtin = "rust"
         assert!(!editor.current_buffer().is_dirty());
     }
 
+    #[tokio::test]
+    async fn undo_files_restore_history_only_for_unchanged_text() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("notes.txt");
+        let undo_directory = root.path().join("undo");
+        std::fs::write(&path, "one\n").unwrap();
+        async fn open(path: &Path, undo_directory: &Path) -> Editor {
+            let mut config = Config::default();
+            config.lsp.enabled = false;
+            config.undofile.enabled = true;
+            let lsp = Box::new(crate::lsp::LspManager::new(config.lsp.clone()));
+            let buffer = Buffer::load_or_create(Some(path.to_string_lossy().into_owned()))
+                .await
+                .unwrap();
+            let mut editor =
+                Editor::with_size(lsp, 60, 12, config, Theme::default(), vec![buffer]).unwrap();
+            editor.test_disable_terminal_output();
+            editor.enable_undo_files(undo_directory.to_path_buf());
+            editor
+        }
+
+        let mut editor = open(&path, &undo_directory).await;
+        editor
+            .test_execute_production_action(Action::Command("s/one/two/".to_string()))
+            .await
+            .unwrap();
+        editor
+            .test_execute_production_action(Action::Save)
+            .await
+            .unwrap();
+        drop(editor);
+
+        let mut editor = open(&path, &undo_directory).await;
+        assert_eq!(editor.test_undo_tree().len(), 1);
+        assert!(!editor.current_buffer().is_dirty());
+        editor
+            .test_execute_production_action(Action::Undo)
+            .await
+            .unwrap();
+        assert_eq!(editor.current_buffer().contents(), "one\n");
+        assert!(editor.current_buffer().is_dirty());
+        drop(editor);
+
+        // A change made outside Red invalidates the stored tree.
+        std::fs::write(&path, "three\n").unwrap();
+        let editor = open(&path, &undo_directory).await;
+        assert!(editor.test_undo_tree().is_empty());
+        assert_eq!(std::fs::read_dir(&undo_directory).unwrap().count(), 0);
+        drop(editor);
+
+        let mut editor = open(&path, &undo_directory).await;
+        editor
+            .test_execute_production_action(Action::Command("s/three/four/".to_string()))
+            .await
+            .unwrap();
+        editor
+            .test_execute_production_action(Action::Save)
+            .await
+            .unwrap();
+        let undo_file = std::fs::read_dir(&undo_directory)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let mut persisted: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&undo_file).unwrap()).unwrap();
+        persisted["history"]["current"] = json!(7);
+        std::fs::write(&undo_file, serde_json::to_vec(&persisted).unwrap()).unwrap();
+        let editor = open(&path, &undo_directory).await;
+        assert!(editor.test_undo_tree().is_empty());
+        assert!(!undo_file.exists());
+    }
+
+    #[tokio::test]
+    async fn earlier_later_and_undo_number_walk_the_undo_tree() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("notes.txt");
+        std::fs::write(&path, "one\n").unwrap();
+        let mut config = Config::default();
+        config.lsp.enabled = false;
+        let lsp = Box::new(crate::lsp::LspManager::new(config.lsp.clone()));
+        let buffer = Buffer::load_or_create(Some(path.to_string_lossy().into_owned()))
+            .await
+            .unwrap();
+        let mut editor =
+            Editor::with_size(lsp, 60, 12, config, Theme::default(), vec![buffer]).unwrap();
+        editor.test_disable_terminal_output();
+        for action in [
+            Action::Command("s/one/two/".to_string()),
+            Action::Save,
+            Action::Command("s/two/three/".to_string()),
+        ] {
+            editor.test_execute_production_action(action).await.unwrap();
+        }
+        editor
+            .test_execute_production_action(Action::Command("earlier 2".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(editor.current_buffer().contents(), "one\n");
+        editor
+            .test_execute_production_action(Action::Command("s/one/four/".to_string()))
+            .await
+            .unwrap();
+
+        // Change 2 sits on the abandoned branch.
+        editor
+            .test_execute_production_action(Action::Command("undo 2".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(editor.current_buffer().contents(), "three\n");
+        editor
+            .test_execute_production_action(Action::Command("ea 1f".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(editor.current_buffer().contents(), "two\n");
+        assert!(!editor.current_buffer().is_dirty());
+        editor
+            .test_execute_production_action(Action::Command("later 10m".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(editor.current_buffer().contents(), "four\n");
+        editor
+            .test_execute_production_action(Action::Command("u 0".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(editor.current_buffer().contents(), "one\n");
+        assert!(editor.current_buffer().is_dirty());
+    }
+
+    async fn mapping_test_editor(contents: &str) -> (tempfile::TempDir, Editor) {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("notes.txt");
+        std::fs::write(&path, contents).unwrap();
+        let mut config = Config::default();
+        config.lsp.enabled = false;
+        config.keys = toml::from_str::<Config>(include_str!("../default_config.toml"))
+            .unwrap()
+            .keys;
+        let lsp = Box::new(crate::lsp::LspManager::new(config.lsp.clone()));
+        let buffer = Buffer::load_or_create(Some(path.to_string_lossy().into_owned()))
+            .await
+            .unwrap();
+        let mut editor =
+            Editor::with_size(lsp, 60, 12, config, Theme::default(), vec![buffer]).unwrap();
+        editor.test_disable_terminal_output();
+        (root, editor)
+    }
+
+    async fn type_keys(editor: &mut Editor, keys: &str) {
+        for event in Editor::macro_events_from_notation(keys).unwrap() {
+            editor.test_execute_event(event).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn runtime_mappings_feed_keys_with_and_without_remapping() {
+        let (_root, mut editor) = mapping_test_editor("1\n2\n3\n4\n5\n6\n7\n8\n").await;
+        for command in ["nnoremap Q jj", "nmap <leader>j Q", "nnoremap <C-k> Q"] {
+            editor
+                .test_execute_production_action(Action::Command(command.to_string()))
+                .await
+                .unwrap();
+        }
+
+        type_keys(&mut editor, "Q").await;
+        assert_eq!(editor.test_buffer_line(), 2);
+        type_keys(&mut editor, "<Space>j").await;
+        assert_eq!(editor.test_buffer_line(), 4);
+        type_keys(&mut editor, "<C-k>").await;
+        assert_eq!(editor.test_buffer_line(), 4);
+
+        let runtime = Runtime::new();
+        let Some(Action::OpenPicker(_, items, _)) =
+            editor.handle_command("nmap <leader>j", &runtime).pop()
+        else {
+            panic!("expected a mapping listing");
+        };
+        assert_eq!(items, vec!["n  <Space>j         Q".to_string()]);
+
+        editor
+            .test_execute_production_action(Action::Command("nunmap Q".to_string()))
+            .await
+            .unwrap();
+        type_keys(&mut editor, "<Space>j").await;
+        assert_eq!(editor.test_buffer_line(), 4);
+        assert!(editor.config.keys.normal.contains_key("j"));
+
+        editor
+            .test_execute_production_action(Action::Command("nmap Q gQ".to_string()))
+            .await
+            .unwrap();
+        editor
+            .test_execute_production_action(Action::Command("nmap gQ Q".to_string()))
+            .await
+            .unwrap();
+        type_keys(&mut editor, "Q").await;
+        assert_eq!(editor.test_last_error(), Some("recursive mapping"));
+    }
+
+    #[tokio::test]
+    async fn insert_mappings_and_abbreviations_expand_while_typing() {
+        let (_root, mut editor) = mapping_test_editor("\n").await;
+        for command in ["inoremap jk <Esc>", "iabbrev teh the", "cabbrev ww write"] {
+            editor
+                .test_execute_production_action(Action::Command(command.to_string()))
+                .await
+                .unwrap();
+        }
+
+        type_keys(&mut editor, "ijx teh jk").await;
+        assert!(editor.test_is_normal());
+        assert_eq!(editor.test_current_buffer().contents(), "jx the \n");
+
+        type_keys(&mut editor, ":ww ").await;
+        assert_eq!(editor.command, "write ");
+        type_keys(&mut editor, "<Esc>").await;
+        assert!(editor.command.is_empty());
+        assert!(editor.test_is_normal());
+    }
+
+    async fn run_command(editor: &mut Editor, command: impl Into<String>) {
+        editor
+            .test_execute_production_action(Action::Command(command.into()))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn argument_list_and_batch_commands_edit_every_target() {
+        let (root, mut editor) = mapping_test_editor("foo 0\n").await;
+        let dir = root.path().join("src");
+        std::fs::create_dir(&dir).unwrap();
+        for (name, contents) in [
+            ("a.txt", "foo 1\n"),
+            ("b.txt", "foo 2\n"),
+            ("c.rs", "foo 3\n"),
+        ] {
+            std::fs::write(dir.join(name), contents).unwrap();
+        }
+        let dir = dir.to_string_lossy().into_owned();
+
+        run_command(&mut editor, format!("args {dir}/*.txt")).await;
+        assert!(editor.current_buffer().name().ends_with("a.txt"));
+        run_command(&mut editor, "next").await;
+        assert!(editor.current_buffer().name().ends_with("b.txt"));
+        run_command(&mut editor, "n").await;
+        assert_eq!(editor.test_last_error(), Some("cannot go beyond last file"));
+        run_command(&mut editor, "first").await;
+        run_command(&mut editor, format!("argadd {dir}/c.rs")).await;
+        run_command(&mut editor, "args").await;
+        assert_eq!(
+            editor.test_last_error(),
+            Some(format!("[{dir}/a.txt] {dir}/c.rs {dir}/b.txt").as_str())
+        );
+
+        run_command(&mut editor, "argdo %s/foo/bar/ | update").await;
+        assert!(editor.current_buffer().name().ends_with("b.txt"));
+        assert_eq!(
+            editor.test_last_error(),
+            Some("argdo ran \"%s/foo/bar/\" in 3 files")
+        );
+        for name in ["a.txt", "b.txt", "c.rs"] {
+            let contents = std::fs::read_to_string(format!("{dir}/{name}")).unwrap();
+            assert!(contents.starts_with("bar "), "{name}: {contents}");
+        }
+        let details = editor
+            .notifications
+            .records()
+            .last()
+            .and_then(|record| record.content.details.clone())
+            .unwrap();
+        assert!(details.starts_with(&format!("{dir}/a.txt: ")), "{details}");
+        assert_eq!(details.lines().count(), 3);
+
+        run_command(&mut editor, format!("argdelete {dir}/*.rs")).await;
+        run_command(&mut editor, "args").await;
+        assert_eq!(
+            editor.test_last_error(),
+            Some(format!("{dir}/a.txt [{dir}/b.txt]").as_str())
+        );
+
+        run_command(&mut editor, "bufdo normal Ax").await;
+        assert_eq!(
+            editor
+                .buffer_manager
+                .iter()
+                .map(|source| source.contents())
+                .collect::<Vec<_>>(),
+            ["foo 0x\n", "bar 1x\n", "bar 2x\n", "bar 3x\n"]
+        );
+        assert!(editor.buffer_manager.iter().skip(1).all(Buffer::is_dirty));
+
+        run_command(&mut editor, "split").await;
+        run_command(&mut editor, "windo normal Iy").await;
+        assert_eq!(editor.current_buffer().contents(), "yybar 3x\n");
+        run_command(&mut editor, "cdo s/a/b/").await;
+        assert_eq!(
+            editor.test_last_error(),
+            Some("cdo: quickfix list is empty")
+        );
+    }
+
+    #[tokio::test]
+    async fn quickfix_list_navigates_follows_edits_and_drives_cdo() {
+        let (root, mut editor) = mapping_test_editor("notes\n").await;
+        let a = root.path().join("a.txt");
+        let b = root.path().join("b.txt");
+        std::fs::write(&a, "one\nfoo two\n").unwrap();
+        std::fs::write(&b, "x\nfoo\n").unwrap();
+        let item = |path: &Path, line, column, text: &str| QuickfixItem {
+            location: plugin::PluginLocation {
+                path: path.to_string_lossy().into_owned(),
+                line,
+                column,
+                column_encoding: plugin::LocationColumnEncoding::Utf8Byte,
+            },
+            text: text.to_string(),
+        };
+        editor
+            .test_execute_production_action(Action::SetQuickfixList(QuickfixListSpec {
+                title: "first".to_string(),
+                items: vec![item(&a, 1, 4, ""), item(&b, 1, 0, "in b")],
+                location: false,
+                open: false,
+            }))
+            .await
+            .unwrap();
+
+        run_command(&mut editor, "cc").await;
+        assert!(editor.current_buffer().name().ends_with("a.txt"));
+        assert_eq!(editor.cursor_text_position(), TextPosition::new(1, 4));
+        assert_eq!(editor.test_last_error(), Some("(1 of 2) foo two"));
+        type_keys(&mut editor, "<C-o>").await;
+        assert!(editor.current_buffer().name().ends_with("notes.txt"));
+        type_keys(&mut editor, "<C-i>]q").await;
+        assert!(editor.current_buffer().name().ends_with("b.txt"));
+        assert_eq!(editor.test_last_error(), Some("(2 of 2) in b"));
+        run_command(&mut editor, "cn").await;
+        assert_eq!(editor.test_last_error(), Some("no more items"));
+
+        run_command(&mut editor, "cfirst").await;
+        type_keys(&mut editor, "ggOnew<Esc>").await;
+        type_keys(&mut editor, "]q[q").await;
+        assert!(editor.current_buffer().name().ends_with("a.txt"));
+        assert_eq!(editor.cursor_text_position(), TextPosition::new(2, 4));
+
+        editor
+            .test_execute_production_action(Action::SetQuickfixList(QuickfixListSpec {
+                title: "second".to_string(),
+                items: vec![item(&b, 0, 0, "")],
+                location: false,
+                open: false,
+            }))
+            .await
+            .unwrap();
+        run_command(&mut editor, "cnewer").await;
+        assert_eq!(
+            editor.test_last_error(),
+            Some("at top of quickfix list stack")
+        );
+        run_command(&mut editor, "colder").await;
+        assert_eq!(
+            editor.test_last_error(),
+            Some("quickfix list 1 of 2; 2 entries: first")
+        );
+
+        run_command(&mut editor, "cdo s/foo/bar/").await;
+        assert_eq!(
+            editor.test_last_error(),
+            Some("cdo ran \"s/foo/bar/\" in 2 entries")
+        );
+        let contents = editor
+            .buffer_manager
+            .iter()
+            .skip(1)
+            .map(|source| source.contents())
+            .collect::<Vec<_>>();
+        assert_eq!(contents, ["new\none\nbar two\n", "x\nbar\n"]);
+
+        run_command(&mut editor, "copen").await;
+        assert!(editor.panel_manager.panel_layout("quickfix").is_some());
+        editor
+            .test_execute_production_action(Action::NotifyPlugins(
+                "panel:event:quickfix".to_string(),
+                json!({"panel_id": "quickfix", "action": "activate", "row": {"id": "0"}}),
+            ))
+            .await
+            .unwrap();
+        assert!(editor.current_buffer().name().ends_with("a.txt"));
+        assert_eq!(editor.cursor_text_position(), TextPosition::new(2, 4));
+        run_command(&mut editor, "ccl").await;
+        assert!(editor.panel_manager.panel_layout("quickfix").is_none());
+
+        run_command(&mut editor, "ll").await;
+        assert_eq!(editor.test_last_error(), Some("no location list"));
+    }
+
+    #[tokio::test]
+    async fn make_runs_in_the_background_and_fills_the_quickfix_list() {
+        let (root, mut editor) = mapping_test_editor("notes\n").await;
+        let source = root.path().join("main.c");
+        std::fs::write(&source, "int main() {\n  return x;\n}\n").unwrap();
+        editor.config.make.makeprg = Some(format!(
+            "printf '%s:2:10: error: undeclared x\\n%s:1:5: warning: unused\\nbuild failed\\n' {0} {0}",
+            source.display()
+        ));
+
+        run_command(&mut editor, "make").await;
+        run_command(&mut editor, "make").await;
+        assert_eq!(editor.test_last_error(), Some("make is already running"));
+        for _ in 0..500 {
+            editor.poll_make_events();
+            if !editor.make_running() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(!editor.make_running());
+        let record = editor
+            .notifications()
+            .records()
+            .find(|record| record.content.summary.starts_with("make: "))
+            .unwrap();
+        assert_eq!(record.content.summary, "make: 1 error, 1 warning");
+        assert!(record
+            .content
+            .details
+            .as_deref()
+            .is_some_and(|details| details.ends_with("\nbuild failed")));
+
+        run_command(&mut editor, "cc").await;
+        assert!(editor.current_buffer().name().ends_with("main.c"));
+        assert_eq!(editor.cursor_text_position(), TextPosition::new(1, 9));
+        assert_eq!(
+            editor.test_last_error(),
+            Some("(1 of 2) error: undeclared x")
+        );
+
+        editor.config.make.makeprg = Some("red-no-such-build-tool".to_string());
+        run_command(&mut editor, "lmake").await;
+        assert!(editor
+            .test_last_error()
+            .is_some_and(|error| error.contains("failed to spawn `red-no-such-build-tool`")));
+        assert!(!editor.make_running());
+    }
+
+    #[tokio::test]
+    async fn spell_checking_covers_comments_and_edits_word_lists() {
+        let root = tempfile::tempdir().unwrap();
+        let spell_directory = root.path().join("spell");
+        std::fs::create_dir(&spell_directory).unwrap();
+        std::fs::write(spell_directory.join("en_US.aff"), "SET UTF-8\nTRY oredw\n").unwrap();
+        std::fs::write(
+            spell_directory.join("en_US.dic"),
+            "4\nthe\nword\nis\nhere\n",
+        )
+        .unwrap();
+        let path = root.path().join("main.rs");
+        std::fs::write(&path, "// the wrod is here zorp\nfn wrod() {}\n").unwrap();
+        let mut config = Config::default();
+        config.lsp.enabled = false;
+        let lsp = Box::new(crate::lsp::LspManager::new(config.lsp.clone()));
+        let buffer = Buffer::load_or_create(Some(path.to_string_lossy().into_owned()))
+            .await
+            .unwrap();
+        let mut editor =
+            Editor::with_size(lsp, 60, 12, config, Theme::default(), vec![buffer]).unwrap();
+        editor.test_disable_terminal_output();
+        editor.set_spell_directory(spell_directory.clone());
+        editor
+            .test_execute_production_action(Action::Command("set spell spl=en_US".to_string()))
+            .await
+            .unwrap();
+
+        let mut render_buffer = RenderBuffer::new(60, 12, &Style::default());
+        editor.render(&mut render_buffer).unwrap();
+        let underlined = |row: usize| -> String {
+            render_buffer.cells[row * 60..(row + 1) * 60]
+                .iter()
+                .filter(|cell| cell.style.underline)
+                .map(|cell| cell.c)
+                .collect()
+        };
+        assert_eq!(underlined(0), "wrodzorp");
+        assert_eq!(underlined(1), "");
+
+        editor
+            .test_execute_production_action(Action::NextMisspelledWord)
+            .await
+            .unwrap();
+        assert_eq!(editor.cursor_text_position(), TextPosition::new(0, 7));
+        editor
+            .test_execute_production_action(Action::SuggestSpelling)
+            .await
+            .unwrap();
+        assert!(editor.current_dialog.is_some());
+        editor.current_dialog = None;
+        editor
+            .test_execute_production_action(Action::ReplaceMisspelledWord("word".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(
+            editor.current_buffer().contents(),
+            "// the word is here zorp\nfn wrod() {}\n"
+        );
+
+        for action in [Action::NextMisspelledWord, Action::MarkWordGood] {
+            editor.test_execute_production_action(action).await.unwrap();
+        }
+        assert_eq!(editor.cursor_text_position(), TextPosition::new(0, 20));
+        assert_eq!(
+            std::fs::read_to_string(spell_directory.join("en_US.add")).unwrap(),
+            "zorp\n"
+        );
+        editor
+            .test_execute_production_action(Action::PreviousMisspelledWord)
+            .await
+            .unwrap();
+        assert_eq!(editor.cursor_text_position(), TextPosition::new(0, 20));
+    }
+
+    #[tokio::test]
+    async fn spell_checking_sees_block_comments_opened_above_the_viewport() {
+        let root = tempfile::tempdir().unwrap();
+        let spell_directory = root.path().join("spell");
+        std::fs::create_dir(&spell_directory).unwrap();
+        std::fs::write(spell_directory.join("en_US.aff"), "SET UTF-8\n").unwrap();
+        std::fs::write(spell_directory.join("en_US.dic"), "1\nthe\n").unwrap();
+        let contents = format!("/* the\n{}*/ fn wrod() {{}}\n", "the wrod\n".repeat(60));
+        let mut config = Config::default();
+        config.lsp.enabled = false;
+        let lsp = Box::new(crate::lsp::LspManager::new(config.lsp.clone()));
+        let buffer = Buffer::new(
+            Some(root.path().join("main.rs").to_string_lossy().into_owned()),
+            contents,
+        );
+        let mut editor =
+            Editor::with_size(lsp, 60, 12, config, Theme::default(), vec![buffer]).unwrap();
+        editor.test_disable_terminal_output();
+        editor.set_spell_directory(spell_directory);
+        editor
+            .test_execute_production_action(Action::Command("set spell spl=en_US".to_string()))
+            .await
+            .unwrap();
+
+        let mut render_buffer = RenderBuffer::new(60, 12, &Style::default());
+        let underlined = |render_buffer: &RenderBuffer, row: usize| -> String {
+            render_buffer.cells[row * 60..(row + 1) * 60]
+                .iter()
+                .filter(|cell| cell.style.underline)
+                .map(|cell| cell.c)
+                .collect()
+        };
+        for vtop in [40, 55] {
+            editor.test_set_viewport_cursor(vtop, 0, 0);
+            editor.render(&mut render_buffer).unwrap();
+            assert_eq!(underlined(&render_buffer, 0), "wrod", "line {vtop}");
+        }
+        // The comment closes on line 61, so the function name is code.
+        assert_eq!(underlined(&render_buffer, 5), "wrod");
+        assert_eq!(underlined(&render_buffer, 6), "");
+
+        editor.test_set_viewport_cursor(30, 0, 0);
+        editor
+            .test_execute_production_action(Action::NextMisspelledWord)
+            .await
+            .unwrap();
+        assert_eq!(editor.cursor_text_position(), TextPosition::new(30, 4));
+    }
+
     #[cfg(unix)]
     #[tokio::test]
     async fn external_formatter_is_undoable_and_runs_before_save_by_default() {
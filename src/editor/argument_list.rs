@@ -0,0 +1,578 @@
+//! The argument list and batch commands: `:args`, `:next`, `:previous`, `:first`,
+//! `:last`, `:argadd` and `:argdelete`, and `:argdo`, `:bufdo`, `:windo` and `:cdo`.
+//!
+//! The argument list starts as the files named on the command line, which the editor
+//! receives as `Config::startup_files`. Entries keep the spelling they were given or
+//! expanded to, and editing one goes through `:edit`, so it reuses a buffer that
+//! already shows the file. Patterns with `*`, `?` or `**` expand against the files
+//! under their literal leading directories, skipping what `.gitignore` and
+//! hidden-file rules skip in the file picker.
+//!
+//! A batch command visits each target in turn and runs its Ex command there through
+//! `handle_command`, as `:global` does for lines, so `:s`, `:normal` and `:g` all
+//! work, and `:cdo` visits each quickfix entry as `:cc` would. Targets are collected
+//! before the first one runs; buffers and windows that the command closes are
+//! skipped. What each target printed is gathered into one
+//! message whose details the messages view shows, and a trailing `| update` writes
+//! every buffer the command modified.
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArgumentCommand {
+    Args,
+    Edit(ArgumentPosition),
+    Add,
+    Delete,
+    Batch(BatchTarget),
+}
+
+/// Argument-list and batch commands with the shortest prefix Vim accepts.
+const ARGUMENT_COMMANDS: &[(&str, usize, ArgumentCommand)] = &[
+    ("args", 2, ArgumentCommand::Args),
+    ("next", 1, ArgumentCommand::Edit(ArgumentPosition::Next)),
+    ("Next", 1, ArgumentCommand::Edit(ArgumentPosition::Previous)),
+    (
+        "previous",
+        4,
+        ArgumentCommand::Edit(ArgumentPosition::Previous),
+    ),
+    ("first", 3, ArgumentCommand::Edit(ArgumentPosition::First)),
+    ("rewind", 3, ArgumentCommand::Edit(ArgumentPosition::First)),
+    ("last", 2, ArgumentCommand::Edit(ArgumentPosition::Last)),
+    ("argadd", 4, ArgumentCommand::Add),
+    ("argdelete", 4, ArgumentCommand::Delete),
+    ("argdo", 5, ArgumentCommand::Batch(BatchTarget::Arguments)),
+    ("bufdo", 5, ArgumentCommand::Batch(BatchTarget::Buffers)),
+    ("windo", 5, ArgumentCommand::Batch(BatchTarget::Windows)),
+    ("cdo", 3, ArgumentCommand::Batch(BatchTarget::Quickfix)),
+];
+
+#[derive(Debug, Default)]
+pub(super) struct ArgumentList {
+    files: Vec<String>,
+    current: usize,
+    /// Set while a batch command runs, which cannot start another.
+    batch_running: bool,
+}
+
+/// Where a batch command runs its Ex command next.
+enum BatchStep {
+    Argument(usize),
+    Buffer(BufferId),
+    Window(WindowId),
+    Quickfix(usize),
+}
+
+impl ArgumentList {
+    pub(super) fn new(files: Vec<String>) -> Self {
+        Self {
+            files,
+            ..Self::default()
+        }
+    }
+}
+
+impl Editor {
+    /// Replaces the argument list without editing any of its files.
+    fn set_argument_list(&mut self, files: Vec<String>) {
+        self.argument_list.files = files;
+        self.argument_list.current = 0;
+    }
+
+    /// Parses the argument-list and batch commands, or returns `None` for any other
+    /// command.
+    pub(super) fn parse_argument_command(&mut self, cmd: &str) -> Option<Vec<Action>> {
+        let (name, arguments) = cmd.split_once(' ').unwrap_or((cmd, ""));
+        // Buffers stay loaded when another file is edited, so there are no changes
+        // for `!` to discard.
+        let name = name.strip_suffix('!').unwrap_or(name);
+        let (full, _, command) = ARGUMENT_COMMANDS
+            .iter()
+            .find(|(full, shortest, _)| name.len() >= *shortest && full.starts_with(name))?;
+        let arguments = arguments.trim();
+        let files = || {
+            arguments
+                .split_whitespace()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+        Some(match command {
+            ArgumentCommand::Args if arguments.is_empty() => vec![Action::ListArguments],
+            ArgumentCommand::Args => vec![Action::SetArguments(files())],
+            ArgumentCommand::Edit(position) => {
+                if !arguments.is_empty() {
+                    self.set_legacy_message(Some(format!("trailing characters: {arguments}")));
+                    return Some(Vec::new());
+                }
+                vec![Action::EditArgument(*position)]
+            }
+            ArgumentCommand::Add => vec![Action::AddArguments(files())],
+            ArgumentCommand::Delete if arguments.is_empty() => {
+                self.set_legacy_message(Some("argdelete requires a pattern".to_string()));
+                return Some(Vec::new());
+            }
+            ArgumentCommand::Delete => vec![Action::DeleteArguments(files())],
+            ArgumentCommand::Batch(target) => {
+                let (command, update) = split_update(arguments);
+                if command.is_empty() {
+                    self.set_legacy_message(Some(format!("{full} requires a command")));
+                    return Some(Vec::new());
+                }
+                vec![Action::Batch(BatchCommand {
+                    target: *target,
+                    command: command.to_string(),
+                    update,
+                })]
+            }
+        })
+    }
+
+    /// Keep the argument list's file and window switching off nested edit frames.
+    #[inline(never)]
+    pub(super) fn execute_argument_action<'a>(
+        &'a mut self,
+        action: &'a Action,
+        buffer: &'a mut RenderBuffer,
+        runtime: &'a mut Runtime,
+    ) -> BoxFuture<'a, anyhow::Result<bool>> {
+        Box::pin(async move {
+            let edit = match action {
+                Action::ListArguments => {
+                    self.set_legacy_message(Some(self.argument_listing()));
+                    None
+                }
+                Action::SetArguments(patterns) => match expand_file_patterns(patterns) {
+                    Ok(files) => {
+                        self.set_argument_list(files);
+                        Some(0)
+                    }
+                    Err(error) => {
+                        self.set_legacy_message(Some(error.to_string()));
+                        None
+                    }
+                },
+                Action::AddArguments(patterns) => {
+                    if let Err(error) = self.add_arguments(patterns) {
+                        self.set_legacy_message(Some(error.to_string()));
+                    }
+                    None
+                }
+                Action::DeleteArguments(patterns) => {
+                    if let Err(error) = self.delete_arguments(patterns) {
+                        self.set_legacy_message(Some(error.to_string()));
+                    }
+                    None
+                }
+                Action::EditArgument(position) => match self.argument_index(*position) {
+                    Ok(index) => Some(index),
+                    Err(error) => {
+                        self.set_legacy_message(Some(error.to_string()));
+                        None
+                    }
+                },
+                Action::Batch(command) => {
+                    return self.execute_batch(command, buffer, runtime).await;
+                }
+                _ => unreachable!("only argument-list actions are dispatched here"),
+            };
+            if let Some(index) = edit {
+                return self.edit_argument(index, buffer, runtime).await;
+            }
+            self.render(buffer)?;
+            Ok(false)
+        })
+    }
+
+    /// The argument list on one line with the current entry in brackets, like Vim.
+    fn argument_listing(&self) -> String {
+        if self.argument_list.files.is_empty() {
+            return "argument list is empty".to_string();
+        }
+        self.argument_list
+            .files
+            .iter()
+            .enumerate()
+            .map(|(index, file)| {
+                if index == self.argument_list.current {
+                    format!("[{file}]")
+                } else {
+                    file.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Adds files after the current entry, or the current buffer's file.
+    fn add_arguments(&mut self, patterns: &[String]) -> anyhow::Result<()> {
+        let files = if patterns.is_empty() {
+            let Some(file) = self.current_buffer().file.clone() else {
+                anyhow::bail!("no file name");
+            };
+            vec![file]
+        } else {
+            expand_file_patterns(patterns)?
+        };
+        let list = &mut self.argument_list;
+        let at = if list.files.is_empty() {
+            0
+        } else {
+            list.current + 1
+        };
+        list.files.splice(at..at, files);
+        Ok(())
+    }
+
+    /// Removes the entries that match any pattern. `%` is the current entry.
+    fn delete_arguments(&mut self, patterns: &[String]) -> anyhow::Result<()> {
+        let list = &mut self.argument_list;
+        let mut keep = vec![true; list.files.len()];
+        for pattern in patterns {
+            let matched = if pattern == "%" {
+                let current = keep.get_mut(list.current);
+                anyhow::ensure!(current.is_some(), "argument list is empty");
+                current.map(|keep| *keep = false).is_some()
+            } else {
+                let regex = glob_regex(pattern)?;
+                let mut matched = false;
+                for (file, keep) in list.files.iter().zip(&mut keep) {
+                    if regex.is_match(file) {
+                        *keep = false;
+                        matched = true;
+                    }
+                }
+                matched
+            };
+            anyhow::ensure!(matched, "no match: {pattern}");
+        }
+        let removed_before_current = keep[..list.current.min(keep.len())]
+            .iter()
+            .filter(|keep| !**keep)
+            .count();
+        let mut flags = keep.into_iter();
+        list.files.retain(|_| flags.next().unwrap_or(true));
+        list.current =
+            (list.current - removed_before_current).min(list.files.len().saturating_sub(1));
+        Ok(())
+    }
+
+    fn argument_index(&self, position: ArgumentPosition) -> anyhow::Result<usize> {
+        let list = &self.argument_list;
+        anyhow::ensure!(!list.files.is_empty(), "argument list is empty");
+        Ok(match position {
+            ArgumentPosition::Next => {
+                anyhow::ensure!(
+                    list.current + 1 < list.files.len(),
+                    "cannot go beyond last file"
+                );
+                list.current + 1
+            }
+            ArgumentPosition::Previous => {
+                anyhow::ensure!(list.current > 0, "cannot go before first file");
+                list.current - 1
+            }
+            ArgumentPosition::First => 0,
+            ArgumentPosition::Last => list.files.len() - 1,
+        })
+    }
+
+    /// Edits the argument at `index` and makes it the current entry.
+    async fn edit_argument(
+        &mut self,
+        index: usize,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<bool> {
+        let Some(file) = self.argument_list.files.get(index).cloned() else {
+            self.set_legacy_message(Some("argument list is empty".to_string()));
+            return Ok(false);
+        };
+        self.argument_list.current = index;
+        self.execute_ex_action(&Action::OpenFile(file), buffer, runtime)
+            .await
+    }
+
+    /// Runs `:argdo`, `:bufdo`, `:windo` or `:cdo`. Returns `true` when the command
+    /// quit the editor.
+    async fn execute_batch(
+        &mut self,
+        batch: &BatchCommand,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<bool> {
+        let name = batch.target.command_name();
+        if self.argument_list.batch_running {
+            self.set_legacy_message(Some(format!("{name} cannot run inside another batch")));
+            return Ok(false);
+        }
+        let steps = match batch.target {
+            BatchTarget::Arguments => (0..self.argument_list.files.len())
+                .map(BatchStep::Argument)
+                .collect::<Vec<_>>(),
+            BatchTarget::Buffers => self
+                .buffer_manager
+                .iter()
+                .map(|source| BatchStep::Buffer(source.id()))
+                .collect(),
+            BatchTarget::Windows => self
+                .window_manager
+                .windows()
+                .into_iter()
+                .map(|window| BatchStep::Window(window.id))
+                .collect(),
+            BatchTarget::Quickfix => (0..self.quickfix_entry_count())
+                .map(BatchStep::Quickfix)
+                .collect(),
+        };
+        if steps.is_empty() {
+            self.set_legacy_message(Some(format!("{name}: {} is empty", batch.target.noun())));
+            self.render(buffer)?;
+            return Ok(false);
+        }
+
+        self.argument_list.batch_running = true;
+        let mut report = Vec::new();
+        let result = async {
+            for step in steps {
+                if self.replay_is_cancelled() {
+                    break;
+                }
+                self.set_legacy_message(None);
+                let visited = match step {
+                    BatchStep::Argument(index) => {
+                        if index >= self.argument_list.files.len() {
+                            continue;
+                        }
+                        self.edit_argument(index, buffer, runtime).await?;
+                        // `:edit` reports why a file cannot be opened.
+                        self.last_error.is_none()
+                    }
+                    BatchStep::Buffer(id) => {
+                        match self
+                            .buffer_manager
+                            .iter()
+                            .position(|source| source.id() == id)
+                        {
+                            Some(index) => {
+                                self.set_current_buffer(buffer, index).await?;
+                                true
+                            }
+                            None => continue,
+                        }
+                    }
+                    BatchStep::Window(id) => match self.window_manager.window_index(id) {
+                        Some(index) => {
+                            self.set_active_window(index);
+                            true
+                        }
+                        None => continue,
+                    },
+                    BatchStep::Quickfix(index) => {
+                        if index >= self.quickfix_entry_count() {
+                            continue;
+                        }
+                        if self
+                            .visit_quickfix_entry(QuickfixScope::Quickfix, index, buffer, runtime)
+                            .await?
+                        {
+                            return Ok(true);
+                        }
+                        self.last_error.is_none()
+                    }
+                };
+                let label = match step {
+                    BatchStep::Argument(index) => self.argument_list.files[index].clone(),
+                    BatchStep::Window(id) => format!(
+                        "window {}: {}",
+                        self.window_manager.window_index(id).unwrap_or_default() + 1,
+                        self.current_buffer().name()
+                    ),
+                    BatchStep::Buffer(_) => self.current_buffer().name().to_string(),
+                    BatchStep::Quickfix(index) => {
+                        self.quickfix_entry_label(index).unwrap_or_default()
+                    }
+                };
+                if !visited {
+                    report.push(format!(
+                        "{label}: {}",
+                        self.last_error.take().unwrap_or_default()
+                    ));
+                    continue;
+                }
+
+                let mut messages = Vec::new();
+                let mut commands = vec![batch.command.as_str()];
+                if batch.update {
+                    commands.push("update");
+                }
+                for command in commands {
+                    for action in self.handle_command(command, runtime) {
+                        if self.execute_ex_action(&action, buffer, runtime).await? {
+                            return Ok(true);
+                        }
+                    }
+                    messages.extend(self.last_error.take());
+                }
+                report.push(if messages.is_empty() {
+                    label
+                } else {
+                    format!("{label}: {}", messages.join("; "))
+                });
+            }
+            Ok(false)
+        }
+        .await;
+        self.argument_list.batch_running = false;
+        if result.as_ref().is_ok_and(|quit| *quit) {
+            return result;
+        }
+
+        let summary = format!(
+            "{name} ran {:?} in {} {}",
+            batch.command,
+            report.len(),
+            batch.target.visited()
+        );
+        match &result {
+            Ok(_) => self.set_message_with_details(Severity::Info, summary, &report.join("\n")),
+            Err(error) => {
+                report.push(format!("stopped: {error}"));
+                self.set_message_with_details(Severity::Error, summary, &report.join("\n"));
+            }
+        }
+        self.render(buffer)?;
+        result
+    }
+}
+
+impl BatchTarget {
+    fn command_name(self) -> &'static str {
+        match self {
+            Self::Arguments => "argdo",
+            Self::Buffers => "bufdo",
+            Self::Windows => "windo",
+            Self::Quickfix => "cdo",
+        }
+    }
+
+    /// What the summary counts, such as "3 files".
+    fn visited(self) -> &'static str {
+        match self {
+            Self::Arguments => "files",
+            Self::Buffers => "buffers",
+            Self::Windows => "windows",
+            Self::Quickfix => "entries",
+        }
+    }
+
+    fn noun(self) -> &'static str {
+        match self {
+            Self::Arguments => "argument list",
+            Self::Buffers => "buffer list",
+            Self::Windows => "window list",
+            Self::Quickfix => "quickfix list",
+        }
+    }
+}
+
+/// Splits a trailing `| update` off a batch command.
+fn split_update(command: &str) -> (&str, bool) {
+    match command.rsplit_once('|') {
+        Some((command, update)) if matches!(update.trim(), "update" | "up") => {
+            (command.trim_end(), true)
+        }
+        _ => (command, false),
+    }
+}
+
+fn has_wildcard(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// Matches a whole path against a file pattern: `*` and `?` stay within one path
+/// component and `**` crosses any number of them.
+fn glob_regex(pattern: &str) -> anyhow::Result<Regex> {
+    let mut regex = String::from("^");
+    let mut rest = pattern;
+    while let Some(character) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("**/") {
+            regex.push_str("(?:.*/)?");
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("**") {
+            regex.push_str(".*");
+            rest = after;
+            continue;
+        }
+        match character {
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            _ => regex.push_str(&regex::escape(&character.to_string())),
+        }
+        rest = &rest[character.len_utf8()..];
+    }
+    regex.push('$');
+    Ok(Regex::new(&regex)?)
+}
+
+/// Expands file patterns in order. A pattern without wildcards is kept as given,
+/// since it may name a file that does not exist yet.
+fn expand_file_patterns(patterns: &[String]) -> anyhow::Result<Vec<String>> {
+    let mut files = Vec::new();
+    for pattern in patterns {
+        if !has_wildcard(pattern) {
+            files.push(pattern.clone());
+            continue;
+        }
+        let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+        let components = pattern.split('/').collect::<Vec<_>>();
+        let literal = components
+            .iter()
+            .take_while(|component| !has_wildcard(component))
+            .count();
+        let root = match components[..literal].join("/") {
+            root if root.is_empty() && pattern.starts_with('/') => "/".to_string(),
+            root if root.is_empty() => ".".to_string(),
+            root => root,
+        };
+        let mut walker = ignore::WalkBuilder::new(&root);
+        if !pattern.contains("**") {
+            walker.max_depth(Some(components.len() - literal));
+        }
+        let regex = glob_regex(pattern)?;
+        let mut matches = walker
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+            .filter_map(|entry| {
+                let path = entry.path().to_string_lossy();
+                let path = path.strip_prefix("./").unwrap_or(&path).to_string();
+                regex.is_match(&path).then_some(path)
+            })
+            .collect::<Vec<_>>();
+        anyhow::ensure!(!matches.is_empty(), "no match: {pattern}");
+        matches.sort();
+        files.extend(matches);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_patterns_match_within_and_across_directories() {
+        let regex = glob_regex("src/*.rs").unwrap();
+        assert!(regex.is_match("src/main.rs"));
+        assert!(!regex.is_match("src/editor/mod.rs"));
+        let regex = glob_regex("src/**/*.rs").unwrap();
+        assert!(regex.is_match("src/main.rs"));
+        assert!(regex.is_match("src/editor/mod.rs"));
+        assert!(!glob_regex("a?.txt").unwrap().is_match("a/.txt"));
+        assert_eq!(split_update("%s/a/b/ge | update"), ("%s/a/b/ge", true));
+        assert_eq!(split_update("s/a|b/c/"), ("s/a|b/c/", false));
+    }
+}
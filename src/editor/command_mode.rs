@@ -28,7 +28,9 @@ impl Editor {
                 visual = self.visual_key_mappings_for_mode(mode);
                 &visual
             }
-            Mode::Insert | Mode::Command | Mode::Search => return None,
+            Mode::Insert | Mode::Replace | Mode::VirtualReplace | Mode::Command | Mode::Search => {
+                return None
+            }
         };
         let action = mappings
             .get(&key)
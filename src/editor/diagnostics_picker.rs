@@ -13,12 +13,12 @@ use crate::{
     buffer::Buffer,
     lsp::{normalized_file_path, Diagnostic, DiagnosticSeverity},
     plugin::{LocationColumnEncoding, OpenLocationTarget, PluginLocation},
-    ui::{Picker, PickerItem, PickerPreview, MAX_UNFOCUSED_PREVIEW_BYTES},
+    ui::{Picker, PickerItem, PickerKeyAction, PickerPreview, MAX_UNFOCUSED_PREVIEW_BYTES},
     unicode_utils::grapheme_to_byte,
     utils::get_workspace_path,
 };
 
-use super::{utf16_to_grapheme, Action, Editor};
+use super::{utf16_to_grapheme, Action, Editor, QuickfixItem, QuickfixListSpec};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(super) enum DiagnosticFilter {
@@ -69,6 +69,7 @@ struct DiagnosticEntry {
 struct DiagnosticPickerModel {
     items: Vec<PickerItem>,
     actions: HashMap<String, Action>,
+    quickfix_items: HashMap<String, QuickfixItem>,
 }
 
 impl Editor {
@@ -82,6 +83,8 @@ impl Editor {
             &preview_contents,
         );
         let actions = model.actions;
+        let quickfix_items = model.quickfix_items;
+        let title = filter.title();
         let mut picker = Picker::builder()
             .title(filter.title())
             .structured_items(model.items)
@@ -97,6 +100,24 @@ impl Editor {
                     Action::Print("diagnostic is no longer available".to_string())
                 })
             })
+            .list_action(
+                PickerKeyAction {
+                    key: "Ctrl-q".to_string(),
+                    action: "quickfix".to_string(),
+                    label: Some("Send to quickfix list".to_string()),
+                },
+                move |ids| {
+                    Action::SetQuickfixList(QuickfixListSpec {
+                        title: title.to_string(),
+                        items: ids
+                            .iter()
+                            .filter_map(|id| quickfix_items.get(id).cloned())
+                            .collect(),
+                        location: false,
+                        open: true,
+                    })
+                },
+            )
             .build(self);
         picker.set_empty_message(Some(filter.empty_message().to_string()));
         self.current_dialog = Some(Box::new(picker));
@@ -187,6 +208,7 @@ fn diagnostic_picker_model(
 
     let mut items = Vec::with_capacity(entries.len());
     let mut actions = HashMap::with_capacity(entries.len());
+    let mut quickfix_items = HashMap::with_capacity(entries.len());
     for (index, entry) in entries.into_iter().enumerate() {
         let id = index.to_string();
         let path = entry.path.to_string_lossy().into_owned();
@@ -245,21 +267,30 @@ fn diagnostic_picker_model(
                 matches,
             }),
         });
+        let location = PluginLocation {
+            path,
+            line,
+            column,
+            column_encoding: LocationColumnEncoding::Utf16,
+        };
+        quickfix_items.insert(
+            id.clone(),
+            QuickfixItem {
+                location: location.clone(),
+                text: format!("{severity}: {message}"),
+            },
+        );
         actions.insert(
             id,
-            Action::OpenLocation(
-                PluginLocation {
-                    path,
-                    line,
-                    column,
-                    column_encoding: LocationColumnEncoding::Utf16,
-                },
-                OpenLocationTarget::Current,
-            ),
+            Action::OpenLocation(location, OpenLocationTarget::Current),
         );
     }
 
-    DiagnosticPickerModel { items, actions }
+    DiagnosticPickerModel {
+        items,
+        actions,
+        quickfix_items,
+    }
 }
 
 fn diagnostic_filter_score(item: &PickerItem, query: &str) -> Option<i64> {
@@ -12,8 +12,9 @@
 
 use unicode_segmentation::UnicodeSegmentation as _;
 
-use crate::unicode_utils::{
-    char_display_width, display_width, trim_line_ending, truncate_display_width,
+use crate::{
+    fold::closed_range_at,
+    unicode_utils::{char_display_width, display_width, trim_line_ending, truncate_display_width},
 };
 
 /// Minimum number of text columns kept on a wrapped row after applying
@@ -180,9 +181,19 @@ pub struct LineSegment {
     /// segments; on wrapped continuations it aligns the text with the
     /// line's indentation (vim's 'breakindent').
     pub visual_offset: usize,
+    /// Last line hidden behind this row when it is a closed fold's placeholder.
+    pub folded: Option<usize>,
 }
 
 impl LineSegment {
+    /// Whether `line` is drawn on this row, including lines hidden behind a fold.
+    pub fn shows_line(&self, line: usize) -> bool {
+        self.line == line
+            || self
+                .folded
+                .is_some_and(|end| (self.line..=end).contains(&line))
+    }
+
     pub fn contains_display_col(&self, col: usize) -> bool {
         if self.start_col == self.end_col {
             return col == self.start_col;
@@ -429,11 +440,17 @@ impl DisplayLayout {
         layout
     }
 
+    /// Lines hidden by a closed fold resolve to the fold's placeholder row.
     pub fn segment_for_cursor(&self, line: usize, display_col: usize) -> Option<&LineSegment> {
         self.rows
             .iter()
             .find(|segment| segment.line == line && segment.contains_cursor_col(display_col))
             .or_else(|| self.rows.iter().rev().find(|segment| segment.line == line))
+            .or_else(|| {
+                self.rows
+                    .iter()
+                    .find(|segment| segment.folded.is_some() && segment.shows_line(line))
+            })
     }
 }
 
@@ -449,6 +466,20 @@ pub struct LayoutConfig {
 }
 
 pub fn layout_lines(lines: &[String], line_count: usize, config: LayoutConfig) -> DisplayLayout {
+    layout_lines_with_folds(lines, line_count, config, &[])
+}
+
+/// Lays out `lines` with each closed fold collapsed into one placeholder row.
+///
+/// `closed_folds` holds the outermost closed ranges in line order. `lines` must
+/// include the lines hidden inside folds so source offsets stay aligned with
+/// highlight spans for the same viewport.
+pub fn layout_lines_with_folds(
+    lines: &[String],
+    line_count: usize,
+    config: LayoutConfig,
+    closed_folds: &[(usize, usize)],
+) -> DisplayLayout {
     if config.content_width == 0 || config.height == 0 {
         return DisplayLayout::default();
     }
@@ -469,6 +500,33 @@ pub fn layout_lines(lines: &[String], line_count: usize, config: LayoutConfig) -
         let source_offset = offset;
         offset += line_with_newline.len();
 
+        if let Some((_, end)) = closed_range_at(closed_folds, line_index) {
+            let end = end.min(line_count.saturating_sub(1)).max(line_index);
+            offset += (line_index + 1..=end)
+                .filter_map(|hidden| lines.get(hidden.saturating_sub(config.vtop)))
+                .map(String::len)
+                .sum::<usize>();
+            rows.push(LineSegment {
+                line: line_index,
+                row,
+                start_col: 0,
+                end_col: 0,
+                start_grapheme: 0,
+                end_grapheme: 0,
+                start_byte: 0,
+                end_byte: 0,
+                start_grapheme_col: 0,
+                source_offset,
+                first_segment: true,
+                last_segment: true,
+                visual_offset: 0,
+                folded: Some(end),
+            });
+            row += 1;
+            line_index = end + 1;
+            continue;
+        }
+
         let line_skipcol = if line_index == config.vtop {
             config.skipcol
         } else {
@@ -576,6 +634,7 @@ fn wrap_line_segments_with_limit(
                     first_segment,
                     last_segment: false,
                     visual_offset: if first_segment { 0 } else { indent },
+                    folded: None,
                 });
                 if segments.len() == max_segments {
                     return segments;
@@ -608,6 +667,7 @@ fn wrap_line_segments_with_limit(
             first_segment,
             last_segment: true,
             visual_offset: if first_segment { 0 } else { indent },
+            folded: None,
         });
     }
 
@@ -654,6 +714,7 @@ fn wrap_line_segments_with_limit(
             first_segment,
             last_segment: start_col == line_width,
             visual_offset: if first_segment { 0 } else { indent },
+            folded: None,
         });
     }
 
@@ -715,6 +776,7 @@ fn nowrap_line_segment(
         first_segment: true,
         last_segment: end_byte == line.len(),
         visual_offset: 0,
+        folded: None,
     }]
 }
 
@@ -67,12 +67,15 @@ impl Editor {
                 Action::EnterMode(
                     Mode::Normal
                         | Mode::Insert
+                        | Mode::Replace
+                        | Mode::VirtualReplace
                         | Mode::Visual
                         | Mode::VisualLine
                         | Mode::VisualBlock
                 ) | Action::RepeatLastChange
                     | Action::PlayMacro(_)
                     | Action::InsertBlock
+                    | Action::AppendBlock
                     | Action::MoveToLineEnd
                     | Action::MoveToLineStart
                     | Action::MoveToFirstLineChar
@@ -86,6 +89,9 @@ impl Editor {
                     | Action::SetMark(_)
                     | Action::Undo
                     | Action::Redo
+                    | Action::UndoEarlier(_)
+                    | Action::UndoLater(_)
+                    | Action::UndoToChange(_)
                     | Action::InsertCharAtCursorPos(_)
                     | Action::InsertString(_)
                     | Action::InsertPastedText(_)
@@ -96,6 +102,11 @@ impl Editor {
                     | Action::InsertTab
                     | Action::InsertText { .. }
                     | Action::DeletePreviousChar
+                    | Action::DeletePreviousWord
+                    | Action::DeleteToInsertStart
+                    | Action::IncreaseInsertIndent
+                    | Action::DecreaseInsertIndent
+                    | Action::InsertRegister { .. }
                     | Action::DeleteCharAtCursorPos
                     | Action::DeleteCharAt(_, _)
                     | Action::DeleteCurrentLine
@@ -113,6 +124,8 @@ impl Editor {
                     | Action::ChangeToLineEnd(_)
                     | Action::DeletePreviousChars(_)
                     | Action::ChangeCharsAtCursor(_)
+                    | Action::StartReplaceMode(_)
+                    | Action::StartVirtualReplaceMode(_)
                     | Action::Delete
                     | Action::ChangeSelection
                     | Action::Paste
@@ -122,10 +135,22 @@ impl Editor {
                     | Action::ReplaceSelection(_)
                     | Action::Substitute(_)
                     | Action::ConfirmSubstitute(_)
+                    | Action::RepeatSubstitute
+                    | Action::RepeatSubstituteAllLines
                     | Action::JoinLines(_)
                     | Action::JoinLinesKeepSpaces(_)
                     | Action::JoinLinesInRange { .. }
+                    | Action::DeleteLines { .. }
+                    | Action::MoveLines { .. }
+                    | Action::CopyLines { .. }
+                    | Action::IndentLines { .. }
+                    | Action::UnindentLines { .. }
+                    | Action::SortLines { .. }
                     | Action::ToggleCharCase(_)
+                    | Action::Increment(_)
+                    | Action::Decrement(_)
+                    | Action::IncrementSequence(_)
+                    | Action::DecrementSequence(_)
                     | Action::TransformTextRange { .. }
                     | Action::TransformSelection(_)
                     | Action::IndentLine
@@ -135,6 +160,10 @@ impl Editor {
                     | Action::ToggleCommentLines(_)
                     | Action::ToggleCommentRange(_)
                     | Action::ToggleCommentSelection
+                    | Action::AddSurround { .. }
+                    | Action::DeleteSurround(_)
+                    | Action::ChangeSurround { .. }
+                    | Action::SurroundSelection
                     | Action::StartCommentOperator(_)
                     | Action::StartFormatOperator(_)
                     | Action::FormatTextRange(_)
@@ -337,6 +366,8 @@ impl Editor {
         runtime: &mut Runtime,
         tracking: bool,
     ) -> anyhow::Result<bool> {
+        // A command typed for secondary cursors replays only if its actions are local.
+        self.note_multi_cursor_action(action);
         let local =
             Self::action_is_batch_local(action) || self.action_is_local_substitute_command(action);
         let barrier = self.edit_batch.is_active() && !local;
@@ -348,7 +379,7 @@ impl Editor {
         // its enclosing mode transition. Its hidden row replay has its own guard.
         let owner = local
             && !Self::action_is_navigation(action)
-            && !matches!(action, Action::InsertBlock)
+            && !matches!(action, Action::InsertBlock | Action::AppendBlock)
             && self.edit_batch.suspended == 0
             && self.block_replay_depth == 0;
         if owner {
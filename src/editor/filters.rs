@@ -0,0 +1,305 @@
+//! Ex filters: `:{range}!cmd`, `:!cmd`, `:r !cmd`, `:r file`, `:w !cmd` and the `!`
+//! operator.
+//!
+//! Commands run through [`crate::formatter::run_filter`], so they start without a
+//! shell and share the formatter's timeout and output caps. Output on stderr goes to
+//! the message history, and a command that exits unsuccessfully leaves the buffer
+//! alone. A filter or read is one undo transaction.
+
+use super::*;
+use crate::formatter::{self, FilterOutput};
+
+impl Editor {
+    /// Parses `:{range}!cmd`, `:!cmd`, `:[line]r[ead] !cmd`, `:[line]r[ead] {file}` and
+    /// `:[range]w[rite] !cmd`. Returns `None` for any other command.
+    pub(super) fn parse_filter_command(
+        &self,
+        range: &str,
+        command: &str,
+    ) -> anyhow::Result<Option<Action>> {
+        if let Some(shell_command) = command.strip_prefix('!') {
+            let shell_command = required_command("filter", shell_command)?;
+            if range.is_empty() {
+                return Ok(Some(Action::RunShellCommand(shell_command)));
+            }
+            let lines = self.resolve_ex_line_range(range, "filter")?;
+            return Ok(Some(Action::FilterLines {
+                start_line: lines.start_line,
+                end_line: lines.end_line,
+                command: shell_command,
+            }));
+        }
+
+        let name_end = command
+            .find(|character: char| !character.is_ascii_alphabetic())
+            .unwrap_or(command.len());
+        let (name, arguments) = command.split_at(name_end);
+        if matches!(name, "r" | "read") {
+            let line = if range.is_empty() {
+                self.buffer_line() + 1
+            } else {
+                let (_, line) = self.ex_range_line_numbers(range, "read")?;
+                anyhow::ensure!(
+                    line <= self.last_navigable_line() + 1,
+                    "read line is past the end of the buffer"
+                );
+                line
+            };
+            let arguments = arguments.trim();
+            if let Some(shell_command) = arguments.strip_prefix('!') {
+                return Ok(Some(Action::ReadCommand {
+                    line,
+                    command: required_command("read", shell_command)?,
+                }));
+            }
+            anyhow::ensure!(!arguments.is_empty(), "read requires a file or !command");
+            return Ok(Some(Action::ReadFile {
+                line,
+                file: arguments.to_string(),
+            }));
+        }
+
+        // `:w !cmd` pipes to a command, while `:w!cmd` still forces a write to a file.
+        if matches!(name, "w" | "write") && arguments.starts_with(char::is_whitespace) {
+            if let Some(shell_command) = arguments.trim_start().strip_prefix('!') {
+                let lines = if range.is_empty() {
+                    ExLineRange {
+                        start_line: 0,
+                        end_line: self.last_navigable_line(),
+                    }
+                } else {
+                    self.resolve_ex_line_range(range, "write")?
+                };
+                return Ok(Some(Action::WriteToCommand {
+                    start_line: lines.start_line,
+                    end_line: lines.end_line,
+                    command: required_command("write", shell_command)?,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Opens the command line with `{range}!` for the lines of an operator's range or
+    /// of the Visual selection.
+    pub(super) fn open_filter_prompt(&mut self, range: Option<TextRange>) {
+        let prefix = match range {
+            Some(range) => {
+                let start_line = range.start.line.min(self.last_navigable_line());
+                let end_line = if range.end.line > start_line && range.end.character == 0 {
+                    range.end.line - 1
+                } else {
+                    range.end.line
+                }
+                .min(self.last_navigable_line());
+                self.move_to_text_position(TextPosition::new(start_line, 0));
+                self.move_to_first_non_blank_on_current_line();
+                match end_line - start_line {
+                    0 => ".".to_string(),
+                    count => format!(".,.+{count}"),
+                }
+            }
+            None => {
+                self.capture_last_visual_selection();
+                "'<,'>".to_string()
+            }
+        };
+        self.selection = None;
+        self.pending_visual_text_object_scope = None;
+        self.pending_operator = None;
+        self.waiting_command = None;
+        self.mode = Mode::Command;
+        self.reset_command_history_navigation();
+        self.reset_command_completion();
+        self.command = format!("{prefix}!");
+    }
+
+    /// Replaces lines `start_line..=end_line` with the output of `command`, which
+    /// reads them on stdin.
+    #[inline(never)]
+    pub(super) fn filter_lines<'a>(
+        &'a mut self,
+        start_line: usize,
+        end_line: usize,
+        command: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<bool>> {
+        Box::pin(async move {
+            let (text, separator) = self.lines_text(start_line, end_line);
+            let revision = self.current_buffer().revision();
+            let output = run_command(command, &format!("{text}{separator}")).await?;
+            if !self.report_command_output(command, &output, false) {
+                return Ok(false);
+            }
+            anyhow::ensure!(
+                self.current_buffer().revision() == revision,
+                "filter result is stale because the document changed"
+            );
+
+            let keeps_ending = end_line < self.current_buffer().len();
+            let lines = output_lines(&output.stdout, &separator);
+            self.begin_transaction(format!("filter through {command}"));
+            if let Some(lines) = lines {
+                let replacement = if keeps_ending {
+                    format!("{lines}{separator}")
+                } else {
+                    lines
+                };
+                self.replace_range(self.whole_lines_range(start_line, end_line), &replacement);
+            } else {
+                let removal = self.lines_removal_range(start_line, end_line);
+                self.replace_range(removal, "");
+            }
+            self.move_to_text_position(TextPosition::new(
+                start_line.min(self.last_navigable_line()),
+                0,
+            ));
+            self.move_to_first_non_blank_on_current_line();
+            let count = end_line - start_line + 1;
+            if count > 2 {
+                self.set_quiet_message(Some(format!("{count} lines filtered")));
+            }
+            Ok(self.commit_transaction(self.cursor_snapshot()))
+        })
+    }
+
+    /// Inserts the output of `command` below the one-based line `line`.
+    #[inline(never)]
+    pub(super) fn read_command<'a>(
+        &'a mut self,
+        line: usize,
+        command: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<bool>> {
+        Box::pin(async move {
+            let revision = self.current_buffer().revision();
+            let output = run_command(command, "").await?;
+            if !self.report_command_output(command, &output, false) {
+                return Ok(false);
+            }
+            anyhow::ensure!(
+                self.current_buffer().revision() == revision,
+                "read result is stale because the document changed"
+            );
+            Ok(self.read_lines(line, &output.stdout, &format!("read from {command}")))
+        })
+    }
+
+    /// Inserts the contents of `file` below the one-based line `line`.
+    pub(super) fn read_file(&mut self, line: usize, file: &str) -> anyhow::Result<bool> {
+        let path = normalized_file_path(file)?;
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|error| anyhow::anyhow!("failed to read {file}: {error}"))?;
+        let changed = self.read_lines(line, &contents, &format!("read {file}"));
+        self.set_quiet_message(Some(format!(
+            "{file:?} {}L, {}B",
+            contents.lines().count(),
+            contents.len()
+        )));
+        Ok(changed)
+    }
+
+    fn read_lines(&mut self, line: usize, contents: &str, description: &str) -> bool {
+        let separator = self
+            .current_buffer()
+            .get(0)
+            .filter(|first| first.ends_with("\r\n"))
+            .map_or("\n", |_| "\r\n");
+        let Some(lines) = output_lines(contents, separator) else {
+            return false;
+        };
+        self.begin_transaction(description);
+        self.insert_lines_below(line, &lines, separator);
+        self.move_to_text_position(TextPosition::new(line, 0));
+        self.move_to_first_non_blank_on_current_line();
+        self.commit_transaction(self.cursor_snapshot())
+    }
+
+    /// Pipes lines `start_line..=end_line` to `command` and shows what it prints.
+    #[inline(never)]
+    pub(super) fn write_to_command<'a>(
+        &'a mut self,
+        start_line: usize,
+        end_line: usize,
+        command: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let mut input = self
+                .current_buffer()
+                .text_in_range(self.whole_lines_range(start_line, end_line));
+            if !input.ends_with('\n') {
+                input.push('\n');
+            }
+            let output = run_command(command, &input).await?;
+            self.report_command_output(command, &output, true);
+            Ok(())
+        })
+    }
+
+    /// Runs `command` without input and shows what it prints.
+    #[inline(never)]
+    pub(super) fn run_shell_command<'a>(
+        &'a mut self,
+        command: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let output = run_command(command, "").await?;
+            self.report_command_output(command, &output, true);
+            Ok(())
+        })
+    }
+
+    /// Puts stderr, and stdout with `show_stdout`, in the message history. Returns
+    /// whether the command succeeded; a failure becomes the current error.
+    fn report_command_output(
+        &mut self,
+        command: &str,
+        output: &FilterOutput,
+        show_stdout: bool,
+    ) -> bool {
+        let mut details = String::new();
+        if show_stdout {
+            details.push_str(&output.stdout);
+        }
+        details.push_str(&output.stderr);
+        let details = details.trim_end();
+        if !output.status.success() {
+            self.set_message_with_details(
+                Severity::Error,
+                format!("{command} exited with {}", output.status),
+                details,
+            );
+            return false;
+        }
+        if let Some(summary) = details.lines().rev().find(|line| !line.trim().is_empty()) {
+            self.set_message_with_details(Severity::Info, summary.to_string(), details);
+        }
+        true
+    }
+}
+
+async fn run_command(command: &str, input: &str) -> anyhow::Result<FilterOutput> {
+    let directory = std::env::current_dir()?;
+    formatter::run_filter(command, &directory, input).await
+}
+
+fn required_command(operation: &str, command: &str) -> anyhow::Result<String> {
+    let command = command.trim();
+    anyhow::ensure!(!command.is_empty(), "{operation} requires a command");
+    Ok(command.to_string())
+}
+
+/// Returns command output as lines joined by `separator`, without a final ending,
+/// or `None` when there is no output.
+fn output_lines(output: &str, separator: &str) -> Option<String> {
+    if output.is_empty() {
+        return None;
+    }
+    let output = output.strip_suffix('\n').unwrap_or(output);
+    let output = output.strip_suffix('\r').unwrap_or(output);
+    Some(
+        output
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .collect::<Vec<_>>()
+            .join(separator),
+    )
+}
@@ -0,0 +1,539 @@
+//! Vim folds: `zf` manual folds, the `zo`/`zc`/`za`/`zR`/`zM`/`zd`/`zE` commands, `zj`
+//! and `zk`, and the fold-aware viewport.
+//!
+//! Folds belong to windows (see [`crate::fold`]). Provider folds are refreshed lazily
+//! when a fold command runs after the buffer changed: tree-sitter ranges apply at once,
+//! and a `textDocument/foldingRange` response replaces them when the server answers.
+//! A motion may leave the cursor on a hidden line. After the action, searches, mark
+//! jumps, horizontal motions, undo, and insert open the folds around it; anything else
+//! moves it to the fold's first line, as Vim does.
+
+use super::{display_layout::LineSegment, *};
+use crate::fold::{closed_range_at, innermost_fold, outermost_fold, Fold, FoldSource};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LspFoldingRange {
+    start_line: usize,
+    end_line: usize,
+}
+
+/// First line of the row that displays `line`.
+fn visible_start(closed: &[(usize, usize)], line: usize) -> usize {
+    closed_range_at(closed, line).map_or(line, |(start, _)| start)
+}
+
+/// First line of the row after the one that displays `line`.
+fn next_visible_line(closed: &[(usize, usize)], line: usize) -> usize {
+    closed_range_at(closed, line).map_or(line, |(_, end)| end) + 1
+}
+
+/// First line of the row before the one that displays `line`.
+fn previous_visible_line(closed: &[(usize, usize)], line: usize) -> Option<usize> {
+    visible_start(closed, line)
+        .checked_sub(1)
+        .map(|line| visible_start(closed, line))
+}
+
+/// First line of the row `rows` rows above the one that displays `line`.
+fn rows_above(closed: &[(usize, usize)], mut line: usize, rows: usize) -> usize {
+    line = visible_start(closed, line);
+    for _ in 0..rows {
+        let Some(previous) = previous_visible_line(closed, line) else {
+            break;
+        };
+        line = previous;
+    }
+    line
+}
+
+/// Exclusive end of the lines shown by `height` rows from `vtop`, counting each
+/// closed fold as one row.
+pub(super) fn visible_lines_end(closed: &[(usize, usize)], vtop: usize, height: usize) -> usize {
+    if closed.is_empty() {
+        return vtop + height;
+    }
+    (0..height).fold(vtop, |line, _| next_visible_line(closed, line))
+}
+
+impl Editor {
+    /// Folds of the active window over the current buffer.
+    fn active_folds(&self) -> &[Fold] {
+        let buffer_id = self.current_buffer().id();
+        self.window_manager
+            .active_window()
+            .map_or(&[], |window| window.folds.folds(buffer_id))
+    }
+
+    fn active_folds_mut(&mut self) -> Option<&mut Vec<Fold>> {
+        let buffer_id = self.current_buffer().id();
+        self.window_manager
+            .active_window_mut()
+            .map(|window| window.folds.folds_mut(buffer_id))
+    }
+
+    pub(super) fn active_closed_folds(&self) -> Vec<(usize, usize)> {
+        crate::fold::closed_ranges(self.active_folds())
+    }
+
+    /// Binds folds restored from a session snapshot to the buffers their windows show.
+    pub(super) fn claim_restored_folds(&mut self) {
+        for window in self
+            .window_manager
+            .windows_mut()
+            .into_iter()
+            .chain(self.tab_pages.windows_mut())
+        {
+            if let Some(buffer) = self.buffer_manager.get(window.buffer_index) {
+                window.folds.bind(buffer.id());
+            }
+        }
+    }
+
+    /// Shifts the folds of every window showing the current buffer past an edit
+    /// that replaced `range` with text containing `inserted` line breaks.
+    pub(super) fn adjust_folds_for_edit(&mut self, range: TextRange, inserted: usize) {
+        let buffer_id = self.current_buffer().id();
+        for window in self
+            .window_manager
+            .windows_mut()
+            .into_iter()
+            .chain(self.tab_pages.windows_mut())
+        {
+            window.folds.adjust_for_edit(buffer_id, range, inserted);
+        }
+    }
+
+    /// Buffer lines the rows of `layout` cover, including lines hidden in closed folds.
+    pub(super) fn layout_source_height(
+        &self,
+        window: &crate::window::Window,
+        layout: &DisplayLayout,
+    ) -> usize {
+        layout
+            .rows
+            .last()
+            .map_or(0, |row| row.folded.unwrap_or(row.line) + 1)
+            .saturating_sub(window.vtop)
+            .max(self.window_content_height(window))
+    }
+
+    pub(super) fn fold_marker_for_segment(
+        &self,
+        window: &crate::window::Window,
+        segment: &LineSegment,
+    ) -> Option<&'static str> {
+        let ascii = self.config.window_borders_ascii;
+        if segment.folded.is_some() {
+            return Some(if ascii { "+" } else { "▸" });
+        }
+        let buffer = self.buffer_manager.get(window.buffer_index)?;
+        window
+            .folds
+            .folds(buffer.id())
+            .iter()
+            .any(|fold| fold.start == segment.line && fold.end > fold.start)
+            .then_some(if ascii { "-" } else { "▾" })
+    }
+
+    pub(super) fn visible_line_above_cursor(&self) -> Option<usize> {
+        previous_visible_line(&self.active_closed_folds(), self.buffer_line())
+    }
+
+    pub(super) fn visible_line_below_cursor(&self) -> Option<usize> {
+        Some(next_visible_line(
+            &self.active_closed_folds(),
+            self.buffer_line(),
+        ))
+        .filter(|line| *line <= self.last_navigable_line())
+    }
+
+    pub(super) fn move_cursor_to_line_keeping_goal(&mut self, line: usize) {
+        self.vtop = self.vtop.min(line);
+        self.cy = line - self.vtop;
+        self.apply_cursor_goal_to_current_line();
+    }
+
+    /// Scrolls so the cursor's row is visible when closed folds make buffer lines and
+    /// screen rows disagree. The cursor line itself may stay hidden inside a fold.
+    pub(super) fn check_bounds_with_folds(&mut self, closed: &[(usize, usize)]) -> bool {
+        let old_position = (self.cx, self.cy, self.vtop);
+        let last_line = if self.is_insert() {
+            self.current_buffer().len()
+        } else {
+            self.last_navigable_line()
+        };
+        let height = self.vheight().max(1);
+        let line = (self.vtop + self.cy).min(last_line);
+        let row_line = visible_start(closed, line);
+        let scrolloff = self
+            .config
+            .scrolloff
+            .unwrap_or(0)
+            .min(height.saturating_sub(1) / 2);
+
+        let mut rows_below = 0;
+        let mut next = row_line;
+        while rows_below < scrolloff {
+            next = next_visible_line(closed, next);
+            if next > last_line {
+                break;
+            }
+            rows_below += 1;
+        }
+        let highest_top = rows_above(closed, row_line, scrolloff);
+        let lowest_top = rows_above(closed, row_line, height - 1 - rows_below);
+        let mut vtop =
+            visible_start(closed, self.vtop.min(last_line)).clamp(lowest_top, highest_top);
+        let has_comments = self.has_inline_comments(self.current_buffer().id());
+        if self.wrap || has_comments {
+            while vtop < row_line && !self.buffer_line_visible_from(vtop, row_line) {
+                vtop = next_visible_line(closed, vtop);
+            }
+        } else {
+            vtop = vtop.min(rows_above(closed, last_line, height - 1));
+        }
+
+        self.vtop = vtop;
+        self.cy = line - vtop;
+        self.clamp_cursor_to_line();
+        old_position != (self.cx, self.cy, self.vtop)
+    }
+
+    /// Whether `action` reveals the cursor line the way Vim's default `foldopen` does.
+    pub(super) fn action_opens_folds(action: &Action) -> bool {
+        matches!(
+            action,
+            Action::FindNext
+                | Action::FindPrevious
+                | Action::RepeatSearch
+                | Action::RepeatSearchOpposite
+                | Action::SearchWordUnderCursor
+                | Action::MatchitForward
+                | Action::MatchitBackward
+                | Action::MatchitPreviousUnmatched
+                | Action::MatchitNextUnmatched
+                | Action::JumpToMark { .. }
+                | Action::JumpBack
+                | Action::JumpForward
+                | Action::MoveLeft
+                | Action::MoveRight
+                | Action::MoveToLineEnd
+                | Action::MoveToLineStart
+                | Action::MoveToFirstLineChar
+                | Action::MoveToLastLineChar
+                | Action::MoveToNextWord
+                | Action::MoveToPreviousWord
+                | Action::MoveToNextBigWord
+                | Action::MoveToNextWordEnd
+                | Action::MoveToPreviousWordEnd
+                | Action::MoveToNextBigWordEnd
+                | Action::MoveToPreviousBigWord
+                | Action::MoveToPreviousBigWordEnd
+                | Action::FindCharForward { .. }
+                | Action::TillCharForward { .. }
+                | Action::FindCharBackward { .. }
+                | Action::TillCharBackward { .. }
+                | Action::RepeatCharSearch(_)
+                | Action::RepeatCharSearchOpposite(_)
+                | Action::Undo
+                | Action::Redo
+                | Action::MoveTo(_, _)
+                | Action::MoveToFilePos(_, _, _)
+                | Action::NextDiagnostic
+                | Action::PreviousDiagnostic
+                | Action::NextMisspelledWord
+                | Action::PreviousMisspelledWord
+                | Action::JumpToQuickfixEntry(_, _)
+                | Action::NextQuickfixEntry
+                | Action::PreviousQuickfixEntry
+        )
+    }
+
+    /// Opens the folds hiding the cursor after actions that reveal it, otherwise moves
+    /// a cursor left on a hidden line to its fold's first line as Vim does.
+    pub(super) fn settle_cursor_in_folds(&mut self, action: &Action) -> bool {
+        if (Self::action_opens_folds(action) || self.is_insert()) && self.open_folds_at_cursor() {
+            return true;
+        }
+        let line = self.buffer_line();
+        let Some((start, _)) = closed_range_at(&self.active_closed_folds(), line) else {
+            return false;
+        };
+        if start == line {
+            return false;
+        }
+        self.move_cursor_to_line_keeping_goal(start);
+        true
+    }
+
+    /// Opens every closed fold hiding the cursor line.
+    fn open_folds_at_cursor(&mut self) -> bool {
+        let line = self.buffer_line();
+        let Some(folds) = self.active_folds_mut() else {
+            return false;
+        };
+        let mut opened = false;
+        for fold in folds
+            .iter_mut()
+            .filter(|fold| fold.closed && fold.contains(line))
+        {
+            fold.closed = false;
+            opened = true;
+        }
+        opened
+    }
+
+    pub(super) async fn execute_fold_action(
+        &mut self,
+        action: &Action,
+        buffer: &mut RenderBuffer,
+    ) -> anyhow::Result<()> {
+        let line = self.buffer_line();
+        match action {
+            Action::CreateFold(range) => {
+                let end = if range.end.character == 0 && range.end.line > range.start.line {
+                    range.end.line - 1
+                } else {
+                    range.end.line
+                };
+                self.create_fold(range.start.line, end);
+            }
+            Action::CreateFoldFromSelection => {
+                let Some(selection) = self.selection else {
+                    return Ok(());
+                };
+                let (_, y0, _, y1): (usize, usize, usize, usize) = selection.into();
+                self.create_fold(y0.min(y1), y0.max(y1));
+            }
+            Action::MoveToNextFold | Action::MoveToPreviousFold => {
+                self.refresh_fold_sources().await;
+                let closed = self.active_closed_folds();
+                let row_line = visible_start(&closed, line);
+                let target = if matches!(action, Action::MoveToNextFold) {
+                    self.active_folds()
+                        .iter()
+                        .map(|fold| fold.start)
+                        .filter(|start| {
+                            *start > row_line && visible_start(&closed, *start) == *start
+                        })
+                        .min()
+                } else {
+                    self.active_folds()
+                        .iter()
+                        .map(|fold| fold.end)
+                        .filter(|end| *end < row_line)
+                        .max()
+                };
+                if let Some(target) = target {
+                    self.move_cursor_to_line_keeping_goal(target);
+                    self.finish_cursor_motion(buffer, true)?;
+                }
+                return Ok(());
+            }
+            _ => {
+                self.refresh_fold_sources().await;
+                if !self.update_folds(action, line) {
+                    self.set_legacy_message(Some("no fold found".to_string()));
+                    return Ok(());
+                }
+            }
+        }
+        self.settle_cursor_in_folds(action);
+        self.check_bounds();
+        self.render(buffer)
+    }
+
+    fn create_fold(&mut self, start: usize, end: usize) {
+        let buffer_id = self.current_buffer().id();
+        if let Some(window) = self.window_manager.active_window_mut() {
+            window.folds.add(
+                buffer_id,
+                Fold {
+                    start,
+                    end,
+                    closed: true,
+                    source: FoldSource::Manual,
+                },
+            );
+        }
+        self.move_to_text_position(TextPosition::new(start, 0));
+        self.move_to_first_non_blank_on_current_line();
+    }
+
+    /// Applies an open, close, or delete command at `line`, reporting whether any
+    /// fold was found.
+    fn update_folds(&mut self, action: &Action, line: usize) -> bool {
+        let Some(folds) = self.active_folds_mut() else {
+            return false;
+        };
+        if folds.is_empty() {
+            return false;
+        }
+        let closed_at_line = outermost_fold(folds, line, |fold| fold.closed);
+        let open_above_closed = |folds: &[Fold]| {
+            let outer = closed_at_line.map(|index| folds[index]);
+            innermost_fold(folds, line, |fold| {
+                !fold.closed
+                    && outer.is_none_or(|outer| fold.start <= outer.start && outer.end <= fold.end)
+            })
+        };
+        match action {
+            Action::OpenFold => {
+                if let Some(index) = closed_at_line {
+                    folds[index].closed = false;
+                }
+            }
+            Action::CloseFold => {
+                if let Some(index) = open_above_closed(folds) {
+                    folds[index].closed = true;
+                }
+            }
+            Action::ToggleFold => {
+                if let Some(index) = closed_at_line {
+                    folds[index].closed = false;
+                } else if let Some(index) = open_above_closed(folds) {
+                    folds[index].closed = true;
+                }
+            }
+            Action::OpenAllFolds => folds.iter_mut().for_each(|fold| fold.closed = false),
+            Action::CloseAllFolds => folds.iter_mut().for_each(|fold| fold.closed = true),
+            Action::DeleteFold => {
+                match closed_at_line.or_else(|| innermost_fold(folds, line, |_| true)) {
+                    Some(index) => {
+                        folds.remove(index);
+                    }
+                    None => return false,
+                }
+            }
+            Action::DeleteAllFolds => folds.clear(),
+            _ => {}
+        }
+        matches!(
+            action,
+            Action::OpenAllFolds | Action::CloseAllFolds | Action::DeleteAllFolds
+        ) || folds.iter().any(|fold| fold.contains(line))
+    }
+
+    /// Recomputes provider folds for the active window when the buffer changed since
+    /// they were last computed.
+    async fn refresh_fold_sources(&mut self) {
+        let buffer_index = self.buffer_manager.active_index();
+        let buffer_id = self.current_buffer().id();
+        let revision = self.current_buffer().revision();
+        let current = self
+            .window_manager
+            .active_window()
+            .is_none_or(|window| window.folds.provided_revision(buffer_id) == Some(revision));
+        if current {
+            return;
+        }
+
+        let ranges = self
+            .highlight_language_id_for_buffer_index(buffer_index)
+            .and_then(|language_id| {
+                let buffer = self.buffer_manager.get(buffer_index)?;
+                match self.syntax_textobjects.fold_ranges(buffer, &language_id) {
+                    Ok(ranges) => Some(ranges),
+                    Err(error) => {
+                        log!("tree-sitter folds unavailable: {error}");
+                        None
+                    }
+                }
+            })
+            .unwrap_or_default();
+        if let Some(window) = self.window_manager.active_window_mut() {
+            window
+                .folds
+                .replace_provided(buffer_id, revision, FoldSource::Syntax, &ranges);
+        }
+
+        let Some(file) = self.current_buffer().file.clone() else {
+            return;
+        };
+        let supported = self.config.lsp.enabled
+            && self
+                .lsp
+                .server_capabilities_for_file(&file)
+                .and_then(|capabilities| capabilities.folding_range_provider.as_ref())
+                .is_some_and(|provider| {
+                    !matches!(
+                        provider,
+                        crate::lsp::FoldingRangeProviderCapability::Simple(false)
+                    )
+                });
+        if !supported {
+            return;
+        }
+        let request: anyhow::Result<i64> = async {
+            self.ensure_buffer_lsp_opened(buffer_index).await?;
+            Ok(self.lsp.folding_range(&file).await?)
+        }
+        .await;
+        match request {
+            Ok(id) if id > 0 => {
+                self.pending_folding_ranges
+                    .insert(id, (buffer_id, revision));
+            }
+            Ok(_) => {}
+            Err(error) => log!("folding range request failed: {error}"),
+        }
+    }
+
+    /// Replaces provider folds in every window showing the buffer a
+    /// `textDocument/foldingRange` response describes.
+    pub(super) fn apply_folding_ranges(
+        &mut self,
+        (buffer_id, revision): (BufferId, u64),
+        result: &Value,
+    ) -> Option<Action> {
+        let current = self
+            .buffer_manager
+            .iter()
+            .any(|buffer| buffer.id() == buffer_id && buffer.revision() == revision);
+        if !current {
+            return None;
+        }
+        let mut ranges = serde_json::from_value::<Vec<LspFoldingRange>>(result.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|range| (range.start_line, range.end_line))
+            .filter(|(start, end)| end > start)
+            .collect::<Vec<_>>();
+        ranges.sort_unstable();
+        ranges.dedup();
+        for window in self
+            .window_manager
+            .windows_mut()
+            .into_iter()
+            .chain(self.tab_pages.windows_mut())
+        {
+            let shows_buffer = self
+                .buffer_manager
+                .get(window.buffer_index)
+                .is_some_and(|buffer| buffer.id() == buffer_id);
+            if shows_buffer {
+                window
+                    .folds
+                    .replace_provided(buffer_id, revision, FoldSource::Lsp, &ranges);
+            }
+        }
+        Some(Action::Refresh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closed_folds_count_as_one_viewport_row() {
+        let closed = [(2, 5), (8, 20)];
+
+        assert_eq!(visible_lines_end(&closed, 0, 4), 7);
+        assert_eq!(visible_lines_end(&closed, 6, 3), 21);
+        assert_eq!(rows_above(&closed, 21, 2), 7);
+        assert_eq!(previous_visible_line(&closed, 4), Some(1));
+        assert_eq!(next_visible_line(&closed, 3), 6);
+    }
+}
@@ -0,0 +1,317 @@
+//! `:global`, `:vglobal` and `:normal`.
+//!
+//! `:g` marks every matching line before it runs anything, then runs its Ex command
+//! with the cursor on each marked line in turn. Marks follow the edits made for
+//! earlier lines and are dropped when their line is deleted or joined away, so the
+//! command never runs twice on one line or on a line that no longer exists. A ranged
+//! `:normal` visits its lines through the same marks and replays its keys through
+//! the key resolution macros use. Either command is one undo step.
+
+use super::*;
+
+/// Lines a `:global` or ranged `:normal` run has yet to visit.
+pub(super) struct LineMarks {
+    buffer_id: BufferId,
+    lines: Vec<Option<usize>>,
+    global: bool,
+}
+
+impl LineMarks {
+    /// Moves marks after `range` was replaced by `text` and drops marks on lines the
+    /// edit removed.
+    fn adjust_for_edit(&mut self, range: TextRange, text: &str) {
+        let inserted = text.matches('\n').count();
+        // Lines `first..removed_end` are gone. A replacement starting mid-line keeps
+        // its first line, and one starting at column 0 keeps its last.
+        let (first, removed_end) = if range.start.character == 0 && range.end.character == 0 {
+            (range.start.line, range.end.line)
+        } else {
+            (range.start.line + 1, range.end.line + 1)
+        };
+        for mark in &mut self.lines {
+            match *mark {
+                Some(line) if line >= removed_end => {
+                    *mark = Some(line - (removed_end - first) + inserted);
+                }
+                Some(line) if line >= first => *mark = None,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// What a marked-line run executes on each line.
+enum LineCommand<'a> {
+    Ex(&'a str),
+    Keys(&'a [Event]),
+}
+
+impl Editor {
+    pub(super) fn parse_global_command(
+        &self,
+        command: &str,
+    ) -> anyhow::Result<Option<GlobalCommand>> {
+        let (range, command) = Self::split_ex_line_range(command);
+        let command = command.trim_start();
+        let name_end = command
+            .find(|character: char| !character.is_ascii_alphabetic())
+            .unwrap_or(command.len());
+        let (name, body) = command.split_at(name_end);
+        if !matches!(name, "g" | "global" | "v" | "vglobal") {
+            return Ok(None);
+        }
+        let (bang, body) = match body.strip_prefix('!') {
+            Some(body) => (true, body),
+            None => (false, body),
+        };
+        let body = body.trim_start();
+        let Some(delimiter) = body.chars().next() else {
+            anyhow::bail!("{name} requires a pattern");
+        };
+        if delimiter.is_ascii_alphanumeric() || matches!(delimiter, '\\' | '"' | '|') {
+            anyhow::bail!("{name} pattern cannot be delimited by {delimiter:?}");
+        }
+        let (pattern, command) = parse_substitute_segment(&body[delimiter.len_utf8()..], delimiter)
+            .map_err(|_| anyhow::anyhow!("{name} pattern is missing a closing {delimiter}"))?;
+        let command = command.trim_start();
+        anyhow::ensure!(!command.is_empty(), "{name} requires a command");
+
+        let range = self.resolve_ex_line_range(if range.is_empty() { "%" } else { range }, name)?;
+        Ok(Some(GlobalCommand {
+            start_line: range.start_line,
+            end_line: range.end_line,
+            pattern,
+            invert: bang || name.starts_with('v'),
+            command: command.to_string(),
+        }))
+    }
+
+    pub(super) fn adjust_line_marks_for_edit(&mut self, range: TextRange, text: &str) {
+        let buffer_id = self.current_buffer().id();
+        for marks in &mut self.ex_line_marks {
+            if marks.buffer_id == buffer_id {
+                marks.adjust_for_edit(range, text);
+            }
+        }
+    }
+
+    /// Executes an action `handle_command` returned. `:global` and `:normal` run here
+    /// instead of through another level of action dispatch because both nest further
+    /// dispatch under them.
+    #[inline(never)]
+    pub(super) fn execute_ex_action<'a>(
+        &'a mut self,
+        action: &'a Action,
+        buffer: &'a mut RenderBuffer,
+        runtime: &'a mut Runtime,
+    ) -> BoxFuture<'a, anyhow::Result<bool>> {
+        Box::pin(async move {
+            match action {
+                Action::Global(command) => self.execute_global(command, buffer, runtime).await,
+                Action::ExecuteNormal { lines, keys } => {
+                    self.execute_normal(*lines, keys, buffer, runtime).await
+                }
+                _ => self.execute(action, buffer, runtime).await,
+            }
+        })
+    }
+
+    /// Runs `:g`/`:v`. Returns `true` when the command quit the editor.
+    #[inline(never)]
+    pub(super) fn execute_global<'a>(
+        &'a mut self,
+        command: &'a GlobalCommand,
+        buffer: &'a mut RenderBuffer,
+        runtime: &'a mut Runtime,
+    ) -> BoxFuture<'a, anyhow::Result<bool>> {
+        Box::pin(async move {
+            if self.ex_line_marks.iter().any(|marks| marks.global) {
+                self.set_legacy_message(Some("global commands cannot be nested".to_string()));
+                return Ok(false);
+            }
+            let pattern = if command.pattern.is_empty() {
+                self.search_term.clone()
+            } else {
+                command.pattern.clone()
+            };
+            if pattern.is_empty() {
+                self.set_legacy_message(Some("no previous search pattern".to_string()));
+                return Ok(false);
+            }
+            let regex = match self.compile_search_regex(&pattern) {
+                Ok(regex) => regex,
+                Err(error) => {
+                    self.set_legacy_message(Some(error.to_string()));
+                    return Ok(false);
+                }
+            };
+            let end_line = command.end_line.min(self.last_navigable_line());
+            let lines = (command.start_line..=end_line)
+                .filter(|&line| {
+                    self.current_buffer().get(line).is_some_and(|contents| {
+                        regex.is_match(trim_line_ending(&contents)) != command.invert
+                    })
+                })
+                .collect::<Vec<_>>();
+            if lines.is_empty() {
+                self.set_legacy_message(Some(if command.invert {
+                    format!("pattern found in every line: {pattern}")
+                } else {
+                    format!("pattern not found: {pattern}")
+                }));
+                return Ok(false);
+            }
+            self.run_on_marked_lines(
+                lines,
+                LineCommand::Ex(&command.command),
+                true,
+                buffer,
+                runtime,
+            )
+            .await
+        })
+    }
+
+    /// Runs `:normal`, once at the cursor or on each line of `lines`. Returns `true`
+    /// when the keys quit the editor.
+    #[inline(never)]
+    pub(super) fn execute_normal<'a>(
+        &'a mut self,
+        lines: Option<(usize, usize)>,
+        keys: &'a str,
+        buffer: &'a mut RenderBuffer,
+        runtime: &'a mut Runtime,
+    ) -> BoxFuture<'a, anyhow::Result<bool>> {
+        Box::pin(async move {
+            let events = match Self::macro_events_from_notation(keys) {
+                Ok(events) => events,
+                Err(error) => {
+                    self.set_legacy_message(Some(error.to_string()));
+                    return Ok(false);
+                }
+            };
+            let command = LineCommand::Keys(&events);
+            if let Some((start_line, end_line)) = lines {
+                return self
+                    .run_on_marked_lines(
+                        (start_line..=end_line).collect(),
+                        command,
+                        false,
+                        buffer,
+                        runtime,
+                    )
+                    .await;
+            }
+            let buffer_id = self.current_buffer().id();
+            let revision = self.current_buffer().undo_history.current_revision();
+            let selections = self.secondary_selections(buffer_id);
+            let result = self.run_line_command(&command, buffer, runtime).await;
+            self.merge_undo_since(buffer_id, revision, selections);
+            result
+        })
+    }
+
+    /// Runs `command` with the cursor at the start of each marked line.
+    async fn run_on_marked_lines(
+        &mut self,
+        lines: Vec<usize>,
+        command: LineCommand<'_>,
+        global: bool,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<bool> {
+        let buffer_id = self.current_buffer().id();
+        let revision = self.current_buffer().undo_history.current_revision();
+        let selections = self.secondary_selections(buffer_id);
+        self.ex_line_marks.push(LineMarks {
+            buffer_id,
+            lines: lines.into_iter().map(Some).collect(),
+            global,
+        });
+        let depth = self.ex_line_marks.len();
+        let result = async {
+            for index in 0.. {
+                if self.current_buffer().id() != buffer_id || self.replay_is_cancelled() {
+                    break;
+                }
+                let Some(mark) = self.ex_line_marks[depth - 1].lines.get(index).copied() else {
+                    break;
+                };
+                let Some(line) = mark else {
+                    continue;
+                };
+                self.move_to_text_position(TextPosition::new(line, 0));
+                if self.run_line_command(&command, buffer, runtime).await? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        .await;
+        self.ex_line_marks.truncate(depth - 1);
+        self.merge_undo_since(buffer_id, revision, selections);
+        result
+    }
+
+    async fn run_line_command(
+        &mut self,
+        command: &LineCommand<'_>,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<bool> {
+        match command {
+            LineCommand::Ex(command) => {
+                for action in self.handle_command(command, runtime) {
+                    if self.execute_ex_action(&action, buffer, runtime).await? {
+                        return Ok(true);
+                    }
+                }
+            }
+            LineCommand::Keys(events) => {
+                for event in *events {
+                    if let Some(action) = self.handle_event_with_runtime(event, Some(runtime))? {
+                        if self
+                            .handle_resolved_key_action(event, &action, buffer, runtime)
+                            .await?
+                        {
+                            anyhow::bail!("normal command attempted to quit the editor");
+                        }
+                    }
+                    if self.replay_checkpoint(buffer, runtime).await? {
+                        break;
+                    }
+                }
+                // Like Vim, an unfinished command is abandoned as if <Esc> was typed.
+                if self.is_waiting_for_key_sequence() || !self.is_normal() {
+                    let escape = Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+                    if let Some(action) = self.handle_event_with_runtime(&escape, Some(runtime))? {
+                        self.handle_resolved_key_action(&escape, &action, buffer, runtime)
+                            .await?;
+                    }
+                }
+                if !self.is_normal() {
+                    self.execute(&Action::EnterMode(Mode::Normal), buffer, runtime)
+                        .await?;
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Folds the transactions committed since `revision` into one undo step.
+    fn merge_undo_since(
+        &mut self,
+        buffer_id: BufferId,
+        revision: u64,
+        before_selections: Vec<SelectionRange>,
+    ) {
+        if self.current_buffer().id() != buffer_id || self.transaction_active() {
+            return;
+        }
+        let after_selections = self.secondary_selections(buffer_id);
+        let history = &mut self.current_buffer_mut().undo_history;
+        if history.current_revision() != revision {
+            history.merge_transactions_since(revision, before_selections, after_selections);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! `Ctrl-a`/`Ctrl-x` and the Visual `g Ctrl-a`/`g Ctrl-x` sequences.
+//!
+//! Targets come from [`crate::increment`]; the edit itself goes through the
+//! [`CaseTransform::Increment`] text transform shared with `TransformTextRange` and
+//! the case operators.
+
+use super::*;
+
+impl Editor {
+    /// Signed amount of an increment action and whether it forms a sequence.
+    pub(super) fn increment_delta(action: &Action) -> (i64, bool) {
+        let (count, sign, sequence) = match action {
+            Action::Increment(count) => (*count, 1, false),
+            Action::Decrement(count) => (*count, -1, false),
+            Action::IncrementSequence(count) => (*count, 1, true),
+            Action::DecrementSequence(count) => (*count, -1, true),
+            _ => (0, 0, false),
+        };
+        (i64::from(count.max(1)) * sign, sequence)
+    }
+
+    /// The range and transform changing the target under or after the cursor by
+    /// `delta`.
+    pub(super) fn increment_at_cursor(&self, delta: i64) -> Option<(TextRange, CaseTransform)> {
+        let line = self.buffer_line();
+        let contents = self.current_buffer().get(line)?;
+        let target = increment::find_target(
+            trim_line_ending(&contents),
+            self.cursor_text_position().character,
+            &self.config.increment,
+        )?;
+        Some((
+            TextRange::new(
+                TextPosition::new(line, target.start),
+                TextPosition::new(line, target.end),
+            ),
+            CaseTransform::Increment {
+                delta,
+                kind: target.kind,
+            },
+        ))
+    }
+
+    /// Changes the first target in the selected part of every line in one transaction.
+    /// A sequence multiplies `delta` by the number of lines changed so far.
+    pub(super) fn increment_selection(&mut self, delta: i64, sequence: bool) -> bool {
+        let Some(selection) = self.selection else {
+            return false;
+        };
+        let (x0, y0, x1, y1) = selection.into();
+        let mut edits = Vec::new();
+        let mut step = 0;
+        for line in y0..=y1 {
+            let Some(contents) = self.current_buffer().get(line) else {
+                continue;
+            };
+            let contents = trim_line_ending(&contents);
+            let line_length = grapheme_len(contents);
+            let (start, end) = match self.mode {
+                Mode::VisualLine => (0, line_length),
+                Mode::VisualBlock => (x0.min(x1), x0.max(x1).saturating_add(1)),
+                _ => (
+                    if line == y0 { x0 } else { 0 },
+                    if line == y1 {
+                        x1.saturating_add(1)
+                    } else {
+                        line_length
+                    },
+                ),
+            };
+            let start = grapheme_to_char(contents, start.min(line_length));
+            let end = grapheme_to_char(contents, end.min(line_length));
+            let selected = char_slice(contents, start, end);
+            let Some(target) = increment::find_target(selected, 0, &self.config.increment) else {
+                continue;
+            };
+            let text = char_slice(selected, target.start, target.end);
+            step += 1;
+            let delta = if sequence {
+                delta.saturating_mul(step)
+            } else {
+                delta
+            };
+            let replacement = Self::transformed_text(
+                text,
+                CaseTransform::Increment {
+                    delta,
+                    kind: target.kind,
+                },
+            );
+            if replacement != text {
+                edits.push((
+                    TextRange::new(
+                        TextPosition::new(line, start + target.start),
+                        TextPosition::new(line, start + target.end),
+                    ),
+                    replacement,
+                ));
+            }
+        }
+        if edits.is_empty() {
+            return false;
+        }
+
+        self.begin_transaction("increment selection");
+        for (range, replacement) in edits.into_iter().rev() {
+            self.replace_range(range, &replacement);
+        }
+        self.move_to_text_position(TextPosition::new(y0, self.grapheme_to_char_on_line(x0, y0)));
+        self.commit_transaction(self.cursor_snapshot());
+        true
+    }
+}
@@ -0,0 +1,429 @@
+//! Explicit Insert-mode completion: `Ctrl-n`, `Ctrl-p` and the `Ctrl-x` submodes.
+//!
+//! `Ctrl-n` and `Ctrl-p` offer keywords from the open buffers, nearest to the
+//! cursor first in their direction. `Ctrl-x Ctrl-l` offers whole lines that start
+//! with the text typed on the current line, `Ctrl-x Ctrl-f` file names relative to
+//! the buffer's directory or the working directory, and `Ctrl-x Ctrl-o` asks the
+//! language server alone. All of them use the ordinary completion menu, but the
+//! snapshot carries an [`InsertCompletionStart`], so an accepted item joins the
+//! current Insert transaction and `.` repeats the inserted text rather than the
+//! menu keys.
+
+use super::*;
+use ropey::Rope;
+
+const MAX_INSERT_COMPLETION_ITEMS: usize = 500;
+
+impl Editor {
+    pub(super) async fn request_insert_completion(
+        &mut self,
+        source: InsertCompletionSource,
+    ) -> anyhow::Result<()> {
+        if !self.config.completion.enabled || !self.is_insert() {
+            return Ok(());
+        }
+        self.scheduled_completion = None;
+        for pending in self.pending_completions.values_mut() {
+            pending.superseded = true;
+        }
+        self.close_completion_menu();
+
+        let mut snapshot = self.completion_snapshot();
+        snapshot.insert_completion = Some(self.insert_completion_start());
+        let (items, missing) = match source {
+            InsertCompletionSource::KeywordNext => {
+                (self.keyword_completion_items(false), "no matching keywords")
+            }
+            InsertCompletionSource::KeywordPrevious => {
+                (self.keyword_completion_items(true), "no matching keywords")
+            }
+            InsertCompletionSource::Line => (self.line_completion_items(), "no matching lines"),
+            InsertCompletionSource::FilePath => {
+                (self.file_path_completion_items(), "no matching files")
+            }
+            InsertCompletionSource::Lsp => {
+                let has_server = self
+                    .current_buffer()
+                    .file
+                    .as_deref()
+                    .and_then(|file| self.lsp.server_capabilities_for_file(file))
+                    .is_some_and(|capabilities| capabilities.completion_provider.is_some());
+                if !has_server {
+                    self.set_legacy_message(Some(
+                        "no language server provides completion for this buffer".to_string(),
+                    ));
+                    return Ok(());
+                }
+                self.request_completion_items(None, Vec::new(), snapshot)
+                    .await?;
+                return Ok(());
+            }
+        };
+        if items.is_empty() {
+            self.set_legacy_message(Some(missing.to_string()));
+            return Ok(());
+        }
+        let snapshot = self.activate_completion_snapshot(snapshot);
+        self.show_completion_items(items, snapshot);
+        Ok(())
+    }
+
+    /// Closes the completion menu that stays open while typing, if it is shown.
+    pub(super) fn close_completion_menu(&mut self) {
+        if self
+            .current_dialog
+            .as_ref()
+            .is_some_and(|dialog| dialog.allows_event_passthrough())
+        {
+            self.current_dialog = None;
+            self.completion_snapshot = None;
+        }
+    }
+
+    fn insert_completion_start(&self) -> InsertCompletionStart {
+        let cursor = self.cursor_text_position();
+        let line = self
+            .current_buffer()
+            .get(cursor.line)
+            .unwrap_or_default()
+            .trim_end_matches(['\r', '\n'])
+            .to_string();
+        InsertCompletionStart {
+            events: self
+                .pending_semantic_change
+                .as_ref()
+                .map_or(0, |change| change.key_sequence_start),
+            cursor,
+            line,
+        }
+    }
+
+    /// Replaces the keys recorded since `start` with the edit that accepting an item
+    /// made: backspaces over the typed text the item does not keep, then the rest of
+    /// the item as pasted text. An edit of any other shape keeps the keys.
+    pub(super) fn record_insert_completion(
+        &mut self,
+        start: &InsertCompletionStart,
+        range: TextRange,
+        inserted: &str,
+        cursor_before: TextPosition,
+    ) {
+        if self.replaying_semantic_change {
+            return;
+        }
+        let inserted_end = offset_text_position(range.start, inserted, inserted.chars().count());
+        let replays_as_typing = range.start.line == start.cursor.line
+            && range.end == cursor_before
+            && range.start.character <= start.cursor.character
+            && !inserted.contains('\n')
+            && self.cursor_text_position() == inserted_end
+            && self.selected_snippet_range().is_none();
+        let Some(change) = self
+            .pending_semantic_change
+            .as_mut()
+            .filter(|change| replays_as_typing && start.events <= change.events.len())
+        else {
+            return;
+        };
+
+        let typed = start
+            .line
+            .chars()
+            .skip(range.start.character)
+            .take(start.cursor.character - range.start.character)
+            .collect::<String>();
+        let kept = typed
+            .graphemes(true)
+            .zip(inserted.graphemes(true))
+            .take_while(|(typed, inserted)| typed == inserted)
+            .count();
+        change.events.truncate(start.events);
+        for _ in kept..typed.graphemes(true).count() {
+            change.events.push(Event::Key(KeyEvent::new(
+                KeyCode::Backspace,
+                KeyModifiers::NONE,
+            )));
+        }
+        let rest = inserted.graphemes(true).skip(kept).collect::<String>();
+        if !rest.is_empty() {
+            change.events.push(Event::Paste(rest));
+        }
+    }
+
+    /// Returns keywords starting with the one before the cursor, in the order
+    /// `Ctrl-n` (or with `backward`, `Ctrl-p`) visits them: the current buffer from
+    /// the cursor with wraparound, then the other open buffers.
+    fn keyword_completion_items(&self, backward: bool) -> Vec<CompletionResponseItem> {
+        let (prefix, range) = self
+            .completion_prefix()
+            .unwrap_or_else(|| (String::new(), self.completion_default_range()));
+        let cursor = self.cursor_text_position();
+        let contents = self.current_buffer().contents_snapshot();
+        let cursor_char =
+            contents.line_to_char(cursor.line.min(contents.len_lines() - 1)) + cursor.character;
+        let prefix_start = cursor_char - prefix.chars().count();
+
+        let mut remaining_scan_chars = MAX_BUFFER_COMPLETION_SCAN_CHARS;
+        let words = keyword_spans(&contents, &mut remaining_scan_chars);
+        let (before, after): (Vec<_>, Vec<_>) = words
+            .into_iter()
+            .filter(|(start, _)| *start != prefix_start)
+            .partition(|(start, _)| *start < prefix_start);
+        let mut ordered = if backward {
+            before
+                .into_iter()
+                .rev()
+                .chain(after.into_iter().rev())
+                .collect::<Vec<_>>()
+        } else {
+            after.into_iter().chain(before).collect::<Vec<_>>()
+        };
+        let active_buffer_id = self.current_buffer().id();
+        for buffer in self.buffer_manager.iter() {
+            if buffer.id() == active_buffer_id || remaining_scan_chars == 0 {
+                continue;
+            }
+            ordered.extend(keyword_spans(
+                &buffer.contents_snapshot(),
+                &mut remaining_scan_chars,
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        ordered
+            .into_iter()
+            .map(|(_, word)| word)
+            .filter(|word| word.len() > prefix.len() && word.starts_with(&prefix))
+            .filter(|word| seen.insert(word.clone()))
+            .take(MAX_INSERT_COMPLETION_ITEMS)
+            .enumerate()
+            .map(|(index, word)| {
+                insert_completion_item(word, "keyword", CompletionItemKind::Text, &range, index)
+            })
+            .collect()
+    }
+
+    /// Returns lines starting with the text typed after the indentation, searching
+    /// backward from the cursor with wraparound and then the other open buffers.
+    fn line_completion_items(&self) -> Vec<CompletionResponseItem> {
+        let cursor = self.cursor_text_position();
+        let characters = self
+            .current_buffer()
+            .get(cursor.line)
+            .unwrap_or_default()
+            .trim_end_matches(['\r', '\n'])
+            .chars()
+            .collect::<Vec<_>>();
+        let end = cursor.character.min(characters.len());
+        let start = characters[..end]
+            .iter()
+            .take_while(|character| character.is_whitespace())
+            .count();
+        let typed = characters[start..end].iter().collect::<String>();
+        let range = line_range(cursor.line, &characters, start, end);
+
+        let contents = self.current_buffer().contents_snapshot();
+        let lines = contents.lines().map(String::from).collect::<Vec<_>>();
+        let (before, after) = lines.split_at(cursor.line.min(lines.len()));
+        let mut candidates = before
+            .iter()
+            .rev()
+            .chain(after.iter().skip(1).rev())
+            .cloned()
+            .collect::<Vec<_>>();
+        let active_buffer_id = self.current_buffer().id();
+        for buffer in self.buffer_manager.iter() {
+            if buffer.id() != active_buffer_id {
+                candidates.extend(buffer.contents_snapshot().lines().map(String::from));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let mut remaining_scan_chars = MAX_BUFFER_COMPLETION_SCAN_CHARS;
+        candidates
+            .into_iter()
+            .take_while(|line| {
+                remaining_scan_chars = remaining_scan_chars.saturating_sub(line.len());
+                remaining_scan_chars > 0
+            })
+            .map(|line| line.trim_end_matches(['\r', '\n']).trim_start().to_string())
+            .filter(|line| line.len() > typed.len() && line.starts_with(&typed))
+            .filter(|line| seen.insert(line.clone()))
+            .take(MAX_INSERT_COMPLETION_ITEMS)
+            .enumerate()
+            .map(|(index, line)| {
+                insert_completion_item(line, "line", CompletionItemKind::Text, &range, index)
+            })
+            .collect()
+    }
+
+    /// Returns the entries of the directory typed before the cursor whose names
+    /// start with the typed name. A relative directory is looked up next to the
+    /// buffer's file and then in the working directory.
+    fn file_path_completion_items(&self) -> Vec<CompletionResponseItem> {
+        let cursor = self.cursor_text_position();
+        let characters = self
+            .current_buffer()
+            .get(cursor.line)
+            .unwrap_or_default()
+            .trim_end_matches(['\r', '\n'])
+            .chars()
+            .collect::<Vec<_>>();
+        let end = cursor.character.min(characters.len());
+        let mut start = end;
+        while start > 0 && is_file_name_char(characters[start - 1]) {
+            start -= 1;
+        }
+        let name_start = characters[start..end]
+            .iter()
+            .rposition(|character| *character == '/')
+            .map_or(start, |slash| start + slash + 1);
+        let directory = characters[start..name_start].iter().collect::<String>();
+        let name = characters[name_start..end].iter().collect::<String>();
+        let range = line_range(cursor.line, &characters, name_start, end);
+
+        let directories = if directory.starts_with('/') {
+            vec![PathBuf::from(&directory)]
+        } else if directory.starts_with('~') {
+            expand_user_path(&directory).into_iter().collect()
+        } else {
+            let buffer_directory = self
+                .current_buffer()
+                .file
+                .as_deref()
+                .and_then(|file| expand_user_path(file).ok())
+                .and_then(|file| file.parent().map(Path::to_path_buf))
+                .map(|parent| get_workspace_path().join(parent));
+            buffer_directory
+                .into_iter()
+                .chain(std::iter::once(get_workspace_path()))
+                .map(|root| root.join(&directory))
+                .collect()
+        };
+
+        let mut seen = HashSet::new();
+        let mut items = Vec::new();
+        for directory in directories {
+            let Ok(entries) = std::fs::read_dir(&directory) else {
+                continue;
+            };
+            let mut entries = entries
+                .filter_map(Result::ok)
+                .filter_map(|entry| {
+                    let file_name = entry.file_name().to_str()?.to_string();
+                    let visible = !file_name.starts_with('.') || name.starts_with('.');
+                    (visible && file_name.starts_with(&name)).then(|| {
+                        let is_dir = entry.path().is_dir();
+                        (file_name, is_dir)
+                    })
+                })
+                .collect::<Vec<_>>();
+            entries.sort();
+            for (file_name, is_dir) in entries {
+                if !seen.insert(file_name.clone()) || items.len() >= MAX_INSERT_COMPLETION_ITEMS {
+                    continue;
+                }
+                let (label, description, kind) = if is_dir {
+                    (
+                        format!("{file_name}/"),
+                        "directory",
+                        CompletionItemKind::Folder,
+                    )
+                } else {
+                    (file_name, "file", CompletionItemKind::File)
+                };
+                let index = items.len();
+                items.push(insert_completion_item(
+                    label,
+                    description,
+                    kind,
+                    &range,
+                    index,
+                ));
+            }
+        }
+        items
+    }
+}
+
+/// Returns the keywords of `contents` with their character offsets, scanning at most
+/// `remaining_scan_chars` characters and counting them off.
+fn keyword_spans(contents: &Rope, remaining_scan_chars: &mut usize) -> Vec<(usize, String)> {
+    let scan_chars = contents.len_chars().min(*remaining_scan_chars);
+    *remaining_scan_chars -= scan_chars;
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (offset, character) in contents
+        .chars()
+        .take(scan_chars)
+        .chain(std::iter::once(' '))
+        .enumerate()
+    {
+        if is_keyword_char(character) {
+            word.push(character);
+        } else if !word.is_empty() {
+            words.push((offset - word.chars().count(), std::mem::take(&mut word)));
+        }
+    }
+    words
+}
+
+/// Matches Vim's default `isfname` characters.
+fn is_file_name_char(character: char) -> bool {
+    character.is_alphanumeric() || "/.-_+,#$%~=".contains(character)
+}
+
+/// Returns the LSP range covering `characters[start..end]` of line `line`.
+fn line_range(line: usize, characters: &[char], start: usize, end: usize) -> Range {
+    let utf16_offset = |index: usize| {
+        characters[..index]
+            .iter()
+            .map(|character| character.len_utf16())
+            .sum()
+    };
+    Range {
+        start: crate::lsp::Position {
+            line,
+            character: utf16_offset(start),
+        },
+        end: crate::lsp::Position {
+            line,
+            character: utf16_offset(end),
+        },
+    }
+}
+
+/// Builds a menu item that replaces `range` with `label`. The sort text keeps the
+/// order the source found the candidates in.
+fn insert_completion_item(
+    label: String,
+    description: &str,
+    kind: CompletionItemKind,
+    range: &Range,
+    index: usize,
+) -> CompletionResponseItem {
+    CompletionResponseItem {
+        label: label.clone(),
+        label_details: Some(CompletionItemLabelDetails {
+            detail: None,
+            description: Some(description.to_string()),
+        }),
+        kind: Some(kind),
+        detail: None,
+        documentation: None,
+        deprecated: None,
+        preselect: None,
+        sort_text: Some(format!("{index:06}")),
+        filter_text: None,
+        insert_text: None,
+        insert_text_format: None,
+        text_edit: Some(LspTextEdit {
+            range: range.clone(),
+            new_text: label,
+        }),
+        additional_text_edits: None,
+        command: None,
+        data: None,
+        commit_characters: None,
+    }
+}
@@ -0,0 +1,277 @@
+//! Insert-mode command keys: `Ctrl-r`, `Ctrl-o`, `Ctrl-w`, `Ctrl-u`, `Ctrl-t` and
+//! `Ctrl-d`.
+//!
+//! All of them edit inside the current insert session, so `.` repeats them with
+//! the rest of the typed text. `Ctrl-r {register}` is recorded as the text it
+//! inserted rather than as the register name, which keeps the repeat stable when
+//! the register changes afterwards. `Ctrl-o` leaves Insert mode for one Normal
+//! command; the keys of that command join the recorded change and Insert mode
+//! resumes once it completes.
+
+use super::*;
+
+impl Editor {
+    /// Resolves the key after `Ctrl-r`. `Ctrl-r`, `Ctrl-o` or `Ctrl-p` there makes
+    /// the insert literal, and `Esc` cancels it.
+    pub(super) fn handle_insert_register_event(&mut self, ev: &Event) -> Option<KeyAction> {
+        let literally = self.pending_insert_register?;
+        let Event::Key(event) = ev else {
+            return None;
+        };
+        if event.kind == KeyEventKind::Release {
+            return None;
+        }
+        match event.code {
+            KeyCode::Char('r' | 'o' | 'p') if event.modifiers == KeyModifiers::CONTROL => {
+                self.pending_insert_register = Some(true);
+                Some(KeyAction::None)
+            }
+            KeyCode::Char(register) if !event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.pending_insert_register = None;
+                Some(KeyAction::Single(Action::InsertRegister {
+                    register,
+                    literally,
+                }))
+            }
+            _ => {
+                self.pending_insert_register = None;
+                Some(KeyAction::None)
+            }
+        }
+    }
+
+    pub(super) fn start_insert_register(&mut self) {
+        if !self.is_insert() {
+            return;
+        }
+        self.close_completion_menu();
+        self.pending_insert_register = Some(false);
+    }
+
+    /// Inserts `register` as typed text, or with `literally` as one paste that skips
+    /// autoindent and completion triggers.
+    pub(super) async fn insert_register(
+        &mut self,
+        register: char,
+        literally: bool,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        if !self.is_insert() {
+            return Ok(());
+        }
+        let Some(content) = self.read_register(register) else {
+            self.set_legacy_message(Some(format!("register {register} is empty")));
+            return Ok(());
+        };
+        let text = content.text;
+        let events = if literally {
+            self.execute(&Action::InsertPastedText(text.clone()), buffer, runtime)
+                .await?;
+            vec![Event::Paste(text)]
+        } else {
+            let mut events = Vec::new();
+            for character in text.chars() {
+                let (action, code) = match character {
+                    '\n' => (Action::InsertNewLine, KeyCode::Enter),
+                    '\t' => (Action::InsertTab, KeyCode::Tab),
+                    '\r' => continue,
+                    character => (
+                        Action::InsertCharAtCursorPos(character),
+                        KeyCode::Char(character),
+                    ),
+                };
+                self.execute(&action, buffer, runtime).await?;
+                events.push(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+            }
+            self.scheduled_completion = None;
+            events
+        };
+
+        if self.replaying_semantic_change {
+            return Ok(());
+        }
+        if let Some(change) = &mut self.pending_semantic_change {
+            change.events.truncate(change.key_sequence_start);
+            change.events.extend(events);
+        }
+        Ok(())
+    }
+
+    /// Leaves Insert mode for one Normal-mode command. The cursor stays where it
+    /// was unless that is past the end of the line.
+    pub(super) async fn start_insert_normal_command(
+        &mut self,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        if !matches!(self.mode, Mode::Insert) {
+            return Ok(());
+        }
+        let insert_cursor = self.cursor_text_position();
+        self.close_completion_menu();
+        self.execute(&Action::EnterMode(Mode::Normal), buffer, runtime)
+            .await?;
+        self.move_to_text_position(insert_cursor);
+        self.cx = self.cx.min(self.line_length().saturating_sub(1));
+        self.refresh_cursor_goal();
+        self.insert_normal_command = Some(InsertNormalCommand {
+            insert_cursor,
+            normal_cursor: self.cursor_text_position(),
+        });
+        Ok(())
+    }
+
+    /// Returns to Insert mode once the command started by `Ctrl-o` has finished.
+    /// A command that entered Insert mode itself ends the `Ctrl-o`.
+    pub(super) async fn resume_insert_after_normal_command(
+        &mut self,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        let Some(command) = self.insert_normal_command else {
+            return Ok(());
+        };
+        if self.is_insert() {
+            self.insert_normal_command = None;
+            return Ok(());
+        }
+        if !self.is_normal() || self.awaiting_key_input() {
+            return Ok(());
+        }
+        self.insert_normal_command = None;
+        let moved = self.cursor_text_position() != command.normal_cursor;
+        self.execute(&Action::EnterMode(Mode::Insert), buffer, runtime)
+            .await?;
+        if !moved {
+            self.move_to_insert_text_position(command.insert_cursor);
+            self.refresh_cursor_goal();
+            self.insert_entry_cursor = Some(self.cursor_snapshot());
+        }
+        self.render(buffer)
+    }
+
+    /// Deletes backwards from the cursor for `Ctrl-w`, or with `to_insert_start`
+    /// for `Ctrl-u`. Both stop once where the text typed on this line begins, and
+    /// join the line with the previous one when the cursor is in the first column.
+    pub(super) async fn delete_before_insert_cursor(
+        &mut self,
+        to_insert_start: bool,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        if !matches!(self.mode, Mode::Insert) {
+            return Ok(());
+        }
+        self.close_completion_menu();
+        if self.cx == 0 {
+            self.execute(&Action::DeletePreviousChar, buffer, runtime)
+                .await?;
+            return Ok(());
+        }
+
+        let line = self.buffer_line();
+        let cursor = self.grapheme_to_char_on_line(self.cx, line);
+        let before = self
+            .current_line_contents()
+            .unwrap_or_default()
+            .chars()
+            .take(cursor)
+            .collect::<String>();
+        // The text typed on this line since the insert started ends at the cursor
+        // unless the cursor has been moved away from it.
+        let typed = self
+            .inserted_text
+            .as_deref()
+            .and_then(|text| text.rsplit('\n').next())
+            .unwrap_or_default();
+        let insert_start = (!typed.is_empty() && before.ends_with(typed))
+            .then(|| before.chars().count() - typed.chars().count());
+        let before = before.chars().collect::<Vec<_>>();
+        let cursor = before.len();
+        let start = if to_insert_start {
+            insert_start.unwrap_or_else(|| {
+                let indent = before
+                    .iter()
+                    .take_while(|character| character.is_whitespace())
+                    .count();
+                if cursor > indent {
+                    indent
+                } else {
+                    0
+                }
+            })
+        } else {
+            let mut start = cursor;
+            while start > 0 && before[start - 1].is_whitespace() {
+                start -= 1;
+            }
+            if start > 0 {
+                let keyword = is_keyword_char(before[start - 1]);
+                while start > 0
+                    && !before[start - 1].is_whitespace()
+                    && is_keyword_char(before[start - 1]) == keyword
+                {
+                    start -= 1;
+                }
+            }
+            insert_start.map_or(start, |insert_start| start.max(insert_start))
+        };
+        if start == cursor {
+            return Ok(());
+        }
+
+        let started_transaction = !self.transaction_active();
+        if started_transaction {
+            self.begin_transaction("delete before cursor");
+        }
+        self.replace_range(
+            TextRange::new(
+                TextPosition::new(line, start),
+                TextPosition::new(line, cursor),
+            ),
+            "",
+        );
+        self.move_to_insert_text_position(TextPosition::new(line, start));
+        if started_transaction {
+            self.commit_transaction(self.cursor_snapshot());
+        }
+        if let Some(text) = &mut self.inserted_text {
+            for _ in start..cursor {
+                text.pop();
+            }
+        }
+        self.refresh_cursor_goal();
+        self.notify_change(runtime).await
+    }
+
+    /// Changes the indentation of the current line by one shift width for
+    /// `Ctrl-t`, or with `outdent` for `Ctrl-d`, keeping the cursor on its text.
+    pub(super) async fn shift_insert_indent(
+        &mut self,
+        outdent: bool,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        if !matches!(self.mode, Mode::Insert) {
+            return Ok(());
+        }
+        self.close_completion_menu();
+        let line = self.buffer_line();
+        let columns = indent::shift_indent_columns(
+            self.indentation_columns_for_line(line),
+            self.indentation().shift_width,
+            outdent,
+        );
+        let started_transaction = !self.transaction_active();
+        if started_transaction {
+            self.begin_transaction("shift indent");
+        }
+        self.apply_indentation_to_line(line, IndentDecision::Columns(columns));
+        if started_transaction {
+            self.commit_transaction(self.cursor_snapshot());
+        }
+        self.generated_indent = None;
+        self.refresh_cursor_goal();
+        self.notify_change(runtime).await
+    }
+}
@@ -163,7 +163,7 @@ impl Editor {
             && matches!(event, Event::Key(key) if key.code == KeyCode::Char('?') && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT));
         let editor_mapping = match self.mode {
             Mode::Normal => &self.config.keys.normal,
-            Mode::Insert => &self.config.keys.insert,
+            Mode::Insert | Mode::Replace | Mode::VirtualReplace => &self.config.keys.insert,
             Mode::Visual => &self.config.keys.visual,
             Mode::VisualLine => &self.config.keys.visual_line,
             Mode::VisualBlock => &self.config.keys.visual_block,
@@ -0,0 +1,661 @@
+//! Ex line addressing and the line-oriented commands that take a range.
+//!
+//! [`crate::ex_range`] parses the range syntax; this module resolves it against the
+//! current buffer. Marks resolve through their edit anchors, so a range like `'a,'b`
+//! follows text that moved since the marks were set, and `/pat/` and `?pat?` search
+//! from the line after or before the current one, wrapping when `wrapscan` is on.
+//!
+//! `:d`, `:y`, `:m`, `:t`/`:co`, `:>`, `:<` and a ranged `:w {file}` default to the
+//! current line, and `:sort` to the whole buffer. Each edit is one transaction, so a
+//! single `u` restores the lines.
+
+use super::*;
+use crate::ex_range::{self, Address, AddressBase, LineRange};
+use crate::line_sort::{self, SortOptions};
+
+impl Editor {
+    pub(super) fn split_ex_line_range(command: &str) -> (&str, &str) {
+        ex_range::split(command)
+    }
+
+    pub(super) fn resolve_ex_line_range(
+        &self,
+        range: &str,
+        operation: &str,
+    ) -> anyhow::Result<ExLineRange> {
+        let (start, end) = self.ex_range_line_numbers(range, operation)?;
+        anyhow::ensure!(
+            start > 0 && end > 0,
+            "{operation} line numbers are one-based"
+        );
+        anyhow::ensure!(start <= end, "{operation} range start exceeds its end");
+        let start_line = start - 1;
+        anyhow::ensure!(
+            start_line <= self.last_navigable_line(),
+            "{operation} range starts past the end of the buffer"
+        );
+        Ok(ExLineRange {
+            start_line,
+            end_line: (end - 1).min(self.last_navigable_line()),
+        })
+    }
+
+    /// Resolves `range` to one-based line numbers. Line `0` is returned as is for the
+    /// commands that accept it.
+    pub(super) fn ex_range_line_numbers(
+        &self,
+        range: &str,
+        operation: &str,
+    ) -> anyhow::Result<(usize, usize)> {
+        let current = self.buffer_line() + 1;
+        match ex_range::parse(range)? {
+            LineRange::Whole => Ok((1, self.last_navigable_line() + 1)),
+            LineRange::Addresses {
+                start,
+                end,
+                set_current,
+            } => {
+                let first = self.resolve_ex_address(&start, current, operation)?;
+                let Some(end) = end else {
+                    return Ok((first, first));
+                };
+                let current = if set_current { first } else { current };
+                Ok((first, self.resolve_ex_address(&end, current, operation)?))
+            }
+        }
+    }
+
+    fn resolve_ex_address(
+        &self,
+        address: &Address,
+        current: usize,
+        operation: &str,
+    ) -> anyhow::Result<usize> {
+        let base = match &address.base {
+            AddressBase::Line(line) => *line,
+            AddressBase::Current => current,
+            AddressBase::Last => self.last_navigable_line() + 1,
+            AddressBase::Mark(mark) => self.ex_mark_line(*mark)? + 1,
+            AddressBase::SearchForward(pattern) => {
+                self.ex_search_line(pattern, current.saturating_sub(1), true)? + 1
+            }
+            AddressBase::SearchBackward(pattern) => {
+                self.ex_search_line(pattern, current.saturating_sub(1), false)? + 1
+            }
+        };
+        let line = i64::try_from(base)
+            .unwrap_or(i64::MAX)
+            .saturating_add(address.offset);
+        usize::try_from(line)
+            .map_err(|_| anyhow::anyhow!("{operation} range is before the start of the buffer"))
+    }
+
+    fn ex_mark_line(&self, mark: char) -> anyhow::Result<usize> {
+        let buffer_id = self.current_buffer().id();
+        let anchor = if mark.is_ascii_lowercase() {
+            self.local_marks
+                .get(&buffer_id)
+                .and_then(|marks| marks.get(&mark))
+        } else if mark.is_ascii_uppercase() {
+            self.global_marks.get(&mark)
+        } else {
+            self.special_marks.get(&(buffer_id, mark))
+        };
+        let Some(anchor) = anchor else {
+            if matches!(mark, '<' | '>') {
+                anyhow::bail!("last visual range is not set");
+            }
+            anyhow::bail!("mark {mark} is not set");
+        };
+        anyhow::ensure!(
+            anchor.buffer_id == buffer_id,
+            "mark {mark} is in another buffer"
+        );
+        let buffer = self.current_buffer();
+        Ok(buffer
+            .char_idx_to_position(anchor.char_index.min(buffer.char_len()))
+            .line)
+    }
+
+    /// Finds the next line after `from`, or the previous one before it, that matches
+    /// `pattern`.
+    fn ex_search_line(&self, pattern: &str, from: usize, forward: bool) -> anyhow::Result<usize> {
+        let pattern = if pattern.is_empty() {
+            self.search_term.as_str()
+        } else {
+            pattern
+        };
+        anyhow::ensure!(!pattern.is_empty(), "no previous search pattern");
+        let regex = self.compile_search_regex(pattern)?;
+        let line_count = self.last_navigable_line() + 1;
+        let wrapscan = self.config.search.wrapscan;
+        (1..=line_count)
+            .filter_map(|step| {
+                if forward {
+                    let line = from + step;
+                    (wrapscan || line < line_count).then_some(line % line_count)
+                } else {
+                    (wrapscan || step <= from).then(|| (from + line_count - step) % line_count)
+                }
+            })
+            .find(|&line| {
+                self.current_buffer()
+                    .get(line)
+                    .is_some_and(|contents| regex.is_match(trim_line_ending(&contents)))
+            })
+            .ok_or_else(|| anyhow::anyhow!(Self::pattern_not_found_message(pattern)))
+    }
+
+    /// `:{range}` with no command moves to the last line of the range.
+    pub(super) fn parse_line_jump(&self, range: &str) -> anyhow::Result<Action> {
+        if range == "$" {
+            return Ok(Action::MoveToBottom);
+        }
+        let (_, line) = self.ex_range_line_numbers(range, "jump")?;
+        Ok(Action::GoToLine(
+            line.clamp(1, self.last_navigable_line() + 1),
+        ))
+    }
+
+    /// Parses `:d`, `:y`, `:m`, `:t`/`:co`, `:>`, `:<`, `:sort` and a ranged `:w {file}`.
+    /// Returns `None` for any other command.
+    pub(super) fn parse_line_command(
+        &self,
+        range: &str,
+        command: &str,
+    ) -> anyhow::Result<Option<Action>> {
+        let name_end = match command.chars().next() {
+            Some(shift @ ('>' | '<')) => command.len() - command.trim_start_matches(shift).len(),
+            _ => command
+                .find(|character: char| !character.is_ascii_alphabetic())
+                .unwrap_or(command.len()),
+        };
+        let (name, arguments) = command.split_at(name_end);
+        let (force, arguments) = match arguments.strip_prefix('!') {
+            Some(arguments) => (true, arguments),
+            None => (false, arguments),
+        };
+        let arguments = arguments.trim();
+        let operation = if is_abbreviation(name, "delete", 1) {
+            "delete"
+        } else if is_abbreviation(name, "yank", 1) {
+            "yank"
+        } else if is_abbreviation(name, "move", 1) {
+            "move"
+        } else if name == "t" || is_abbreviation(name, "copy", 2) {
+            "copy"
+        } else if name.starts_with('>') {
+            "indent"
+        } else if name.starts_with('<') {
+            "unindent"
+        } else if is_abbreviation(name, "sort", 3) {
+            "sort"
+        } else if matches!(name, "w" | "write") && !range.is_empty() && !arguments.is_empty() {
+            "write"
+        } else {
+            return Ok(None);
+        };
+
+        let lines = if range.is_empty() && operation == "sort" {
+            ExLineRange {
+                start_line: 0,
+                end_line: self.last_navigable_line(),
+            }
+        } else if range.is_empty() {
+            let line = self.buffer_line();
+            ExLineRange {
+                start_line: line,
+                end_line: line,
+            }
+        } else {
+            self.resolve_ex_line_range(range, operation)?
+        };
+        let action = match operation {
+            "delete" | "yank" => {
+                let (register, count) = parse_register_and_count(operation, arguments)?;
+                let lines = self.counted_ex_lines(lines, count);
+                if operation == "delete" {
+                    Action::DeleteLines {
+                        start_line: lines.start_line,
+                        end_line: lines.end_line,
+                        register,
+                    }
+                } else {
+                    Action::YankLines {
+                        start_line: lines.start_line,
+                        end_line: lines.end_line,
+                        register,
+                    }
+                }
+            }
+            "move" | "copy" => {
+                anyhow::ensure!(!arguments.is_empty(), "{operation} requires a destination");
+                let address = ex_range::parse_address(arguments)?;
+                let destination =
+                    self.resolve_ex_address(&address, self.buffer_line() + 1, operation)?;
+                anyhow::ensure!(
+                    destination <= self.last_navigable_line() + 1,
+                    "{operation} destination is past the end of the buffer"
+                );
+                if operation == "move" {
+                    anyhow::ensure!(
+                        destination < lines.start_line + 1 || destination > lines.end_line,
+                        "cannot move a range of lines into itself"
+                    );
+                    Action::MoveLines {
+                        start_line: lines.start_line,
+                        end_line: lines.end_line,
+                        destination,
+                    }
+                } else {
+                    Action::CopyLines {
+                        start_line: lines.start_line,
+                        end_line: lines.end_line,
+                        destination,
+                    }
+                }
+            }
+            "indent" | "unindent" => {
+                let count = if arguments.is_empty() {
+                    None
+                } else {
+                    Some(parse_line_count(operation, arguments)?)
+                };
+                let lines = self.counted_ex_lines(lines, count);
+                let count = u16::try_from(name.len()).unwrap_or(u16::MAX);
+                if operation == "indent" {
+                    Action::IndentLines {
+                        start_line: lines.start_line,
+                        end_line: lines.end_line,
+                        count,
+                    }
+                } else {
+                    Action::UnindentLines {
+                        start_line: lines.start_line,
+                        end_line: lines.end_line,
+                        count,
+                    }
+                }
+            }
+            "sort" => Action::SortLines {
+                start_line: lines.start_line,
+                end_line: lines.end_line,
+                options: SortOptions::parse(arguments, force)?,
+                columns: self.visual_block_columns(range),
+            },
+            _ => Action::WriteLines {
+                start_line: lines.start_line,
+                end_line: lines.end_line,
+                file: arguments.to_string(),
+                force,
+            },
+        };
+        Ok(Some(action))
+    }
+
+    /// Returns the columns of the last Visual block when `range` is exactly `'<,'>`
+    /// and that selection was blockwise.
+    fn visual_block_columns(&self, range: &str) -> Option<(usize, usize)> {
+        if range != "'<,'>" {
+            return None;
+        }
+        let buffer_id = self.current_buffer().id();
+        let selection = self.last_visual_selections.get(&buffer_id)?;
+        if selection.mode != Mode::VisualBlock {
+            return None;
+        }
+        let column = |mark| {
+            let anchor = self.special_marks.get(&(buffer_id, mark))?;
+            let position = self
+                .current_buffer()
+                .char_idx_to_position(anchor.char_index.min(self.current_buffer().char_len()));
+            Some(self.point_for_text_position(position).x)
+        };
+        let (start, end) = (column('<')?, column('>')?);
+        Some((start.min(end), start.max(end)))
+    }
+
+    /// Like Vim, a count after the command covers `count` lines starting at the last
+    /// line of the range.
+    fn counted_ex_lines(&self, lines: ExLineRange, count: Option<usize>) -> ExLineRange {
+        let Some(count) = count else {
+            return lines;
+        };
+        ExLineRange {
+            start_line: lines.end_line,
+            end_line: lines
+                .end_line
+                .saturating_add(count - 1)
+                .min(self.last_navigable_line()),
+        }
+    }
+
+    /// The range covering whole lines `start_line..=end_line`, including the last
+    /// line's ending when it has one.
+    pub(super) fn whole_lines_range(&self, start_line: usize, end_line: usize) -> TextRange {
+        let end = if end_line < self.current_buffer().len() {
+            TextPosition::new(end_line + 1, 0)
+        } else {
+            TextPosition::new(end_line, self.line_character_len(end_line))
+        };
+        TextRange::new(TextPosition::new(start_line, 0), end)
+    }
+
+    pub(super) fn delete_lines(
+        &mut self,
+        start_line: usize,
+        end_line: usize,
+        register: Option<char>,
+    ) -> bool {
+        let range = self.whole_lines_range(start_line, end_line);
+        let previous = std::mem::replace(&mut self.active_register, register);
+        let changed = self.delete_linewise_range(range, "delete lines");
+        self.active_register = previous;
+        changed
+    }
+
+    pub(super) fn yank_lines(
+        &mut self,
+        start_line: usize,
+        end_line: usize,
+        register: Option<char>,
+    ) {
+        let text = self
+            .current_buffer()
+            .text_in_range(self.whole_lines_range(start_line, end_line));
+        let previous = std::mem::replace(&mut self.active_register, register);
+        self.store_yank(Content::linewise(text));
+        self.active_register = previous;
+        let count = end_line - start_line + 1;
+        if count > 2 {
+            self.set_quiet_message(Some(format!("{count} lines yanked")));
+        }
+    }
+
+    /// Moves lines `start_line..=end_line` below the one-based line `destination`.
+    ///
+    /// The lines are deleted and then inserted rather than rotated in place, so line
+    /// marks of a running `:g` follow every other line; `:g/^/m0` reverses a buffer.
+    pub(super) fn move_lines(
+        &mut self,
+        start_line: usize,
+        end_line: usize,
+        destination: usize,
+    ) -> bool {
+        if (start_line..=end_line + 1).contains(&destination) {
+            return false;
+        }
+        let (text, separator) = self.lines_text(start_line, end_line);
+        let count = end_line - start_line + 1;
+
+        self.begin_transaction("move lines");
+        let removal = self.lines_removal_range(start_line, end_line);
+        self.replace_range(removal, "");
+        let destination = if destination > end_line {
+            destination - count
+        } else {
+            destination
+        };
+        self.insert_lines_below(destination, &text, &separator);
+        self.move_to_text_position(TextPosition::new(destination + count - 1, 0));
+        self.move_to_first_non_blank_on_current_line();
+        self.commit_transaction(self.cursor_snapshot())
+    }
+
+    /// Copies lines `start_line..=end_line` below the one-based line `destination`.
+    pub(super) fn copy_lines(
+        &mut self,
+        start_line: usize,
+        end_line: usize,
+        destination: usize,
+    ) -> bool {
+        let (text, separator) = self.lines_text(start_line, end_line);
+        let count = end_line - start_line + 1;
+
+        self.begin_transaction("copy lines");
+        self.insert_lines_below(destination, &text, &separator);
+        self.move_to_text_position(TextPosition::new(destination + count - 1, 0));
+        self.move_to_first_non_blank_on_current_line();
+        self.commit_transaction(self.cursor_snapshot())
+    }
+
+    /// Sorts lines `start_line..=end_line`. Only the lines that change are replaced,
+    /// so marks on lines that keep their text stay put.
+    pub(super) fn sort_lines(
+        &mut self,
+        start_line: usize,
+        end_line: usize,
+        options: &SortOptions,
+        columns: Option<(usize, usize)>,
+    ) -> anyhow::Result<bool> {
+        let pattern = match options.pattern.as_deref() {
+            Some(pattern) => {
+                let pattern = if pattern.is_empty() {
+                    self.search_term.as_str()
+                } else {
+                    pattern
+                };
+                anyhow::ensure!(!pattern.is_empty(), "no previous search pattern");
+                Some(self.compile_search_regex(pattern)?)
+            }
+            None => None,
+        };
+        let lines = (start_line..=end_line)
+            .map(|line| {
+                let contents = self
+                    .current_buffer()
+                    .get(line)
+                    .map(|contents| trim_line_ending(&contents).to_string())
+                    .unwrap_or_default();
+                let source = match columns {
+                    Some((first, last)) => self.block_columns_text(&contents, line, first, last),
+                    None => contents.clone(),
+                };
+                (contents, source)
+            })
+            .collect::<Vec<_>>();
+        let originals = lines
+            .iter()
+            .map(|(contents, _)| contents.clone())
+            .collect::<Vec<_>>();
+        let sorted = line_sort::sort_lines(lines, options, pattern.as_ref());
+
+        // Replacing a line moves the marks inside it to one of its ends, so the
+        // Visual marks in the range are put back on their columns afterwards.
+        let buffer_id = self.current_buffer().id();
+        let visual_marks = ['<', '>']
+            .into_iter()
+            .filter_map(|mark| {
+                let anchor = self.special_marks.get(&(buffer_id, mark))?;
+                let buffer = self.current_buffer();
+                let position =
+                    buffer.char_idx_to_position(anchor.char_index.min(buffer.char_len()));
+                (start_line..start_line + sorted.len())
+                    .contains(&position.line)
+                    .then_some((mark, position, anchor.affinity))
+            })
+            .collect::<Vec<_>>();
+
+        self.begin_transaction("sort lines");
+        for (offset, (line, original)) in sorted.iter().zip(&originals).enumerate() {
+            if line != original {
+                let line_number = start_line + offset;
+                self.replace_range(
+                    TextRange::new(
+                        TextPosition::new(line_number, 0),
+                        TextPosition::new(line_number, self.line_character_len(line_number)),
+                    ),
+                    line,
+                );
+            }
+        }
+        if sorted.len() < originals.len() {
+            let removal = self.lines_removal_range(start_line + sorted.len(), end_line);
+            self.replace_range(removal, "");
+        }
+        for (mark, position, affinity) in visual_marks {
+            let character = position
+                .character
+                .min(self.line_character_len(position.line));
+            let char_index = self
+                .current_buffer()
+                .position_to_char_idx(TextPosition::new(position.line, character));
+            self.set_special_mark_at_char(mark, char_index, affinity);
+        }
+        self.move_to_text_position(TextPosition::new(start_line, 0));
+        self.move_to_first_non_blank_on_current_line();
+        Ok(self.commit_transaction(self.cursor_snapshot()))
+    }
+
+    /// The part of `contents`, line `line` of the buffer, between the inclusive
+    /// grapheme columns `first` and `last`.
+    fn block_columns_text(&self, contents: &str, line: usize, first: usize, last: usize) -> String {
+        let start = self.grapheme_to_char_on_line(first, line);
+        let end = self.grapheme_to_char_on_line(last + 1, line);
+        contents
+            .chars()
+            .skip(start)
+            .take(end.saturating_sub(start))
+            .collect()
+    }
+
+    /// The range that removes lines `start_line..=end_line`. When the last of them is
+    /// the buffer's final line, which has no ending, the ending before them goes too.
+    pub(super) fn lines_removal_range(&self, start_line: usize, end_line: usize) -> TextRange {
+        if end_line < self.current_buffer().len() || start_line == 0 {
+            return self.whole_lines_range(start_line, end_line);
+        }
+        let previous = start_line - 1;
+        TextRange::new(
+            TextPosition::new(previous, self.line_character_len(previous)),
+            TextPosition::new(end_line, self.line_character_len(end_line)),
+        )
+    }
+
+    /// Returns the lines without their final ending, and the ending that separates
+    /// them.
+    pub(super) fn lines_text(&self, start_line: usize, end_line: usize) -> (String, String) {
+        let lines = (start_line..=end_line)
+            .filter_map(|line| self.current_buffer().get(line))
+            .collect::<Vec<_>>();
+        let separator = if lines.first().is_some_and(|line| line.ends_with("\r\n")) {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let text = lines
+            .iter()
+            .map(|line| trim_line_ending(line))
+            .collect::<Vec<_>>()
+            .join(separator);
+        (text, separator.to_string())
+    }
+
+    /// Inserts `text` as whole lines below the one-based line `destination`, or above
+    /// the first line when it is `0`.
+    pub(super) fn insert_lines_below(&mut self, destination: usize, text: &str, separator: &str) {
+        if destination <= self.current_buffer().len() {
+            self.replace_range(
+                TextRange::insertion(TextPosition::new(destination, 0)),
+                &format!("{text}{separator}"),
+            );
+        } else {
+            // The last line has no ending to insert after.
+            let line = destination - 1;
+            self.replace_range(
+                TextRange::insertion(TextPosition::new(line, self.line_character_len(line))),
+                &format!("{separator}{text}"),
+            );
+        }
+    }
+
+    pub(super) fn shift_lines(
+        &mut self,
+        start_line: usize,
+        end_line: usize,
+        count: u16,
+        unindent: bool,
+    ) -> bool {
+        if unindent {
+            self.begin_transaction("unindent lines");
+            self.unindent_lines(start_line, end_line, count);
+        } else {
+            self.begin_transaction("indent lines");
+            self.indent_lines(start_line, end_line, count);
+        }
+        self.move_to_text_position(TextPosition::new(end_line, 0));
+        self.move_to_first_non_blank_on_current_line();
+        self.commit_transaction(self.cursor_snapshot())
+    }
+
+    /// Writes lines `start_line..=end_line` to `file`. An existing file is only
+    /// replaced with `force`.
+    pub(super) fn write_lines(
+        &mut self,
+        start_line: usize,
+        end_line: usize,
+        file: &str,
+        force: bool,
+    ) -> anyhow::Result<()> {
+        let path = normalized_file_path(file)?;
+        anyhow::ensure!(
+            force || !path.exists(),
+            "{file} already exists (add ! to override)"
+        );
+        let mut text = self
+            .current_buffer()
+            .text_in_range(self.whole_lines_range(start_line, end_line));
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        std::fs::write(&path, &text)
+            .map_err(|error| anyhow::anyhow!("failed to write {file}: {error}"))?;
+        self.set_quiet_message(Some(format!(
+            "{file:?} {}L, {}B written",
+            end_line - start_line + 1,
+            text.len()
+        )));
+        Ok(())
+    }
+}
+
+/// Whether `name` is `full` shortened to at least `minimum` characters.
+fn is_abbreviation(name: &str, full: &str, minimum: usize) -> bool {
+    name.len() >= minimum && full.starts_with(name)
+}
+
+/// Parses `[x] [count]` after `:d` or `:y`.
+fn parse_register_and_count(
+    operation: &str,
+    arguments: &str,
+) -> anyhow::Result<(Option<char>, Option<usize>)> {
+    let mut arguments = arguments;
+    let register = match arguments.chars().next() {
+        Some(register) if !register.is_ascii_digit() => {
+            anyhow::ensure!(
+                registers::is_valid_text_register(register),
+                "invalid register: {register}"
+            );
+            anyhow::ensure!(
+                !registers::is_read_only_register(register),
+                "register {register} is read-only"
+            );
+            arguments = arguments[register.len_utf8()..].trim_start();
+            Some(register)
+        }
+        _ => None,
+    };
+    let count = if arguments.is_empty() {
+        None
+    } else {
+        Some(parse_line_count(operation, arguments)?)
+    };
+    Ok((register, count))
+}
+
+fn parse_line_count(operation: &str, text: &str) -> anyhow::Result<usize> {
+    text.parse::<usize>()
+        .ok()
+        .filter(|count| *count > 0)
+        .ok_or_else(|| anyhow::anyhow!("{operation} count must be a positive integer"))
+}
@@ -0,0 +1,341 @@
+//! `:make [args]` and `:lmake [args]`.
+//!
+//! The command is the buffer language's `makeprg`, run without a shell from the
+//! workspace root through an editor-owned [`ProcessManager`], so it gets the same
+//! output caps, environment filtering and polling as plugin processes and never
+//! blocks input. Its output streams into a progress notification while it runs,
+//! and each line is read with the language's errorformats. When it exits, what they
+//! found becomes a new quickfix list, or a location list for the window `:lmake`
+//! ran in. Unlike Vim, `:make` does not jump to the first entry, since the build may
+//! finish while something else is being edited. One build runs at a time.
+
+use super::*;
+use crate::{
+    errorformat::{CompilerMessage, ErrorFormat, MessageKind, OutputParser},
+    formatter::split_command_line,
+    notification::{MessageContent, ProgressOutcome, ProgressPriority},
+    plugin::process::{ProcessEvent, ProcessManager, ProcessSpawnOptions},
+};
+
+/// Owner of `:make` processes in their process manager.
+const MAKE_OWNER: &str = "make";
+/// Output lines read per background tick, so a chatty build cannot stall input.
+const MAKE_EVENTS_PER_TICK: usize = 512;
+/// Output lines kept for the finished notification's details.
+const MAKE_OUTPUT_LINES: usize = 200;
+
+const MAKE_COMMANDS: &[(&str, usize, QuickfixScope)] = &[
+    ("make", 3, QuickfixScope::Quickfix),
+    ("lmake", 4, QuickfixScope::Location),
+];
+
+pub(super) struct MakeState {
+    processes: ProcessManager,
+    job: Option<MakeJob>,
+}
+
+impl Default for MakeState {
+    fn default() -> Self {
+        Self {
+            processes: ProcessManager::new(HashMap::new()),
+            job: None,
+        }
+    }
+}
+
+struct MakeJob {
+    process_id: String,
+    command: String,
+    scope: QuickfixScope,
+    /// Window whose location list `:lmake` fills.
+    window: Option<WindowId>,
+    directory: PathBuf,
+    parser: OutputParser,
+    items: Vec<QuickfixItem>,
+    errors: usize,
+    warnings: usize,
+    output: VecDeque<String>,
+    notification: Option<NotificationId>,
+}
+
+impl MakeJob {
+    fn record(&mut self, line: &str) -> Option<String> {
+        if let Some(message) = self.parser.parse_line(line) {
+            match message.kind {
+                Some(MessageKind::Error) => self.errors += 1,
+                Some(MessageKind::Warning) => self.warnings += 1,
+                _ => {}
+            }
+            self.items.push(quickfix_item(&self.directory, &message));
+            return None;
+        }
+        // JSON records the formats skipped, such as cargo's artifacts, are noise.
+        let line = line.trim_end();
+        if line.trim().is_empty() || line.starts_with('{') {
+            return None;
+        }
+        self.keep_output(line.to_string());
+        Some(line.trim().to_string())
+    }
+
+    fn keep_output(&mut self, line: String) {
+        if self.output.len() == MAKE_OUTPUT_LINES {
+            self.output.pop_front();
+        }
+        self.output.push_back(line);
+    }
+
+    /// "2 errors, 1 warning", or how many entries were found without a kind.
+    fn counts(&self) -> String {
+        let plural = |count: usize, one: &str, many: &str| match count {
+            1 => format!("1 {one}"),
+            count => format!("{count} {many}"),
+        };
+        let mut counts = Vec::new();
+        if self.errors > 0 {
+            counts.push(plural(self.errors, "error", "errors"));
+        }
+        if self.warnings > 0 {
+            counts.push(plural(self.warnings, "warning", "warnings"));
+        }
+        let others = self.items.len() - self.errors - self.warnings;
+        if others > 0 || counts.is_empty() {
+            counts.push(plural(others, "entry", "entries"));
+        }
+        counts.join(", ")
+    }
+}
+
+/// Built-in `makeprg` and errorformats for a language.
+fn builtin_make(language_id: Option<&str>) -> (&'static str, &'static [&'static str]) {
+    match language_id {
+        Some("rust") => ("cargo build --message-format=json", &["cargo"]),
+        Some("go") => ("go build ./...", &["generic"]),
+        Some("typescript" | "tsx") => ("npx tsc --noEmit --pretty false", &["tsc", "generic"]),
+        Some("javascript" | "jsx") => ("npx eslint .", &["eslint", "generic"]),
+        Some("python") => ("pytest -q --tb=line", &["generic"]),
+        _ => ("make", &["gcc", "generic"]),
+    }
+}
+
+impl Editor {
+    /// Parses `:make` and `:lmake`, or returns `None` for any other command.
+    pub(super) fn parse_make_command(&mut self, cmd: &str) -> Option<Vec<Action>> {
+        let (name, arguments) = cmd.split_once(' ').unwrap_or((cmd, ""));
+        // `!` would skip the jump to the first entry, which `:make` never makes.
+        let name = name.strip_suffix('!').unwrap_or(name);
+        let (_, _, scope) = MAKE_COMMANDS
+            .iter()
+            .find(|(full, shortest, _)| name.len() >= *shortest && full.starts_with(name))?;
+        Some(vec![Action::Make {
+            arguments: arguments.trim().to_string(),
+            scope: *scope,
+        }])
+    }
+
+    /// The current buffer's `makeprg` and errorformats, configured ones first.
+    fn make_program(&self) -> (String, Vec<String>) {
+        let language = self.current_language_id();
+        let configured = language
+            .as_ref()
+            .and_then(|language| self.config.make.languages.get(language));
+        let (program, formats) = builtin_make(language.as_deref());
+        let makeprg = configured
+            .and_then(|language| language.makeprg.clone())
+            .or_else(|| self.config.make.makeprg.clone())
+            .unwrap_or_else(|| program.to_string());
+        let errorformat = [
+            configured.map(|language| language.errorformat.clone()),
+            Some(self.config.make.errorformat.clone()),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|formats| !formats.is_empty())
+        .unwrap_or_else(|| formats.iter().map(|format| format.to_string()).collect());
+        (makeprg, errorformat)
+    }
+
+    /// Starts the build in the background.
+    pub(super) fn start_make(
+        &mut self,
+        arguments: &str,
+        scope: QuickfixScope,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(!self.make_running(), "make is already running");
+        let (makeprg, errorformat) = self.make_program();
+        let formats = errorformat
+            .iter()
+            .map(|format| ErrorFormat::parse(format))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let command = if makeprg.contains("$*") {
+            makeprg.replace("$*", arguments)
+        } else if arguments.is_empty() {
+            makeprg
+        } else {
+            format!("{makeprg} {arguments}")
+        };
+        let words = split_command_line(&command)?;
+        let Some((program, args)) = words.split_first() else {
+            anyhow::bail!("makeprg is empty");
+        };
+        let directory = get_workspace_path();
+        self.make.processes.allow_command(MAKE_OWNER, program);
+        let process_id = self.make.processes.spawn(
+            MAKE_OWNER,
+            ProcessSpawnOptions {
+                command: program.clone(),
+                args: args.to_vec(),
+                cwd: Some(directory.clone()),
+                ..Default::default()
+            },
+        )?;
+        let notification = self
+            .notifications
+            .begin_progress(
+                NotificationSource::Editor,
+                MAKE_OWNER,
+                MessageContent::new(format!("make: {command}")),
+                ProgressPriority::UserInitiated,
+                NotificationTime::now(),
+            )
+            .ok();
+        self.make.job = Some(MakeJob {
+            process_id,
+            command,
+            scope,
+            window: self.window_manager.active_stable_window_id(),
+            directory,
+            parser: OutputParser::new(formats),
+            items: Vec::new(),
+            errors: 0,
+            warnings: 0,
+            output: VecDeque::new(),
+            notification,
+        });
+        Ok(())
+    }
+
+    pub(super) fn make_running(&self) -> bool {
+        self.make.job.is_some()
+    }
+
+    /// Reads the running build's output, and sets its list once it exits.
+    pub(super) fn poll_make_events(&mut self) {
+        let Some(job) = self.make.job.as_mut() else {
+            return;
+        };
+        let mut latest = None;
+        let mut exit = None;
+        let mut read = 0;
+        while read < MAKE_EVENTS_PER_TICK && exit.is_none() {
+            let events = self.make.processes.poll_events();
+            if events.is_empty() {
+                break;
+            }
+            for event in events {
+                read += 1;
+                match event {
+                    ProcessEvent::Stdout { line, .. } | ProcessEvent::Stderr { line, .. } => {
+                        latest = job.record(&line).or(latest);
+                    }
+                    ProcessEvent::Error { message, .. } => {
+                        job.keep_output(message.clone());
+                        latest = Some(message);
+                    }
+                    ProcessEvent::Exit {
+                        process_id, code, ..
+                    } if process_id == job.process_id => exit = Some(code),
+                    ProcessEvent::Exit { .. } => {}
+                }
+            }
+        }
+
+        if let Some(code) = exit {
+            self.finish_make(code);
+            return;
+        }
+        if let (Some(id), Some(latest)) = (job.notification, latest) {
+            let summary = if job.items.is_empty() {
+                format!("make: {latest}")
+            } else {
+                format!("make ({}): {latest}", job.counts())
+            };
+            let _ = self.notifications.update_progress(
+                id,
+                MessageContent::new(summary),
+                None,
+                NotificationTime::now(),
+            );
+        }
+    }
+
+    fn finish_make(&mut self, code: Option<i32>) {
+        let Some(job) = self.make.job.take() else {
+            return;
+        };
+        let succeeded = code == Some(0);
+        let summary = match (succeeded, code) {
+            (true, _) => format!("make: {}", job.counts()),
+            (false, Some(code)) => format!("make exited with {code}: {}", job.counts()),
+            (false, None) => format!("make was stopped: {}", job.counts()),
+        };
+        let details = std::iter::once(job.command.clone())
+            .chain(job.output.iter().cloned())
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.set_window_quickfix_list(
+            &QuickfixListSpec {
+                title: job.command,
+                items: job.items,
+                location: job.scope == QuickfixScope::Location,
+                open: false,
+            },
+            job.window,
+        );
+        let outcome = if succeeded {
+            ProgressOutcome::Succeeded
+        } else {
+            ProgressOutcome::Failed
+        };
+        let finished = job.notification.is_some_and(|id| {
+            self.notifications
+                .finish_progress(
+                    id,
+                    outcome,
+                    MessageContent::new(summary.clone()).with_details(details.clone()),
+                    NotificationTime::now(),
+                )
+                .is_ok()
+        });
+        if !finished {
+            let severity = match outcome {
+                ProgressOutcome::Failed => Severity::Error,
+                _ => Severity::Info,
+            };
+            self.set_message_with_details(severity, summary, &details);
+        }
+    }
+}
+
+/// The quickfix item for a message, with its file resolved against `directory`.
+fn quickfix_item(directory: &Path, message: &CompilerMessage) -> QuickfixItem {
+    let path = Path::new(&message.file);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        directory.join(path)
+    };
+    QuickfixItem {
+        location: plugin::PluginLocation {
+            path: path.to_string_lossy().into_owned(),
+            line: message.line.saturating_sub(1),
+            column: message.column.unwrap_or(1).saturating_sub(1),
+            column_encoding: if message.utf16_columns {
+                plugin::LocationColumnEncoding::Utf16
+            } else {
+                plugin::LocationColumnEncoding::Utf8Byte
+            },
+        },
+        text: message.summary(),
+    }
+}
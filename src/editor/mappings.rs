@@ -0,0 +1,1141 @@
+//! Runtime key mappings and abbreviations: `:map`, `:noremap`, `:unmap` with
+//! their `n`, `v`/`x`, `i` and `c` variants, and `:abbreviate` with its Insert
+//! and Command-line forms.
+//!
+//! A mapping binds its left-hand side to [`Action::FeedKeys`] in the same
+//! [`Keys`] tables as the configured keymap, so it shows up in key hints, the
+//! command palette and `:map` listings like any other binding. Fed keys are
+//! queued and resolved one at a time, like a macro. Recursive mappings resolve
+//! them against the live tables; non-recursive ones use a copy of the keymap
+//! with every `FeedKeys` binding put back to its bundled default, so
+//! `:nnoremap n nzz` does not expand its own `n`. The copy is rebuilt after the
+//! keymap changes.
+
+use super::*;
+
+/// Nesting limit for mappings that feed other mappings, like Vim's `maxmapdepth`.
+const MAPPING_MAX_DEPTH: usize = 100;
+/// Keys one typed key may expand to before the expansion is abandoned.
+const MAPPING_MAX_EVENTS: usize = 10_000;
+
+const NORMAL_AND_VISUAL: &[KeyMode] = &[
+    KeyMode::Normal,
+    KeyMode::Visual,
+    KeyMode::VisualLine,
+    KeyMode::VisualBlock,
+];
+const VISUAL: &[KeyMode] = &[KeyMode::Visual, KeyMode::VisualLine, KeyMode::VisualBlock];
+const INSERT_AND_COMMAND: &[KeyMode] = &[KeyMode::Insert, KeyMode::Command];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MappingCommand {
+    Map { remap: bool },
+    Unmap,
+    Abbreviate,
+    Unabbreviate,
+}
+
+/// Mapping commands with the shortest prefix Vim accepts and the modes they
+/// cover. `:map!`, `:noremap!` and `:unmap!` cover Insert and Command mode.
+const MAPPING_COMMANDS: &[(&str, usize, MappingCommand, &[KeyMode])] = &[
+    (
+        "map",
+        3,
+        MappingCommand::Map { remap: true },
+        NORMAL_AND_VISUAL,
+    ),
+    (
+        "nmap",
+        2,
+        MappingCommand::Map { remap: true },
+        &[KeyMode::Normal],
+    ),
+    ("vmap", 2, MappingCommand::Map { remap: true }, VISUAL),
+    ("xmap", 2, MappingCommand::Map { remap: true }, VISUAL),
+    (
+        "imap",
+        2,
+        MappingCommand::Map { remap: true },
+        &[KeyMode::Insert],
+    ),
+    (
+        "cmap",
+        2,
+        MappingCommand::Map { remap: true },
+        &[KeyMode::Command],
+    ),
+    (
+        "noremap",
+        2,
+        MappingCommand::Map { remap: false },
+        NORMAL_AND_VISUAL,
+    ),
+    (
+        "nnoremap",
+        2,
+        MappingCommand::Map { remap: false },
+        &[KeyMode::Normal],
+    ),
+    ("vnoremap", 2, MappingCommand::Map { remap: false }, VISUAL),
+    ("xnoremap", 2, MappingCommand::Map { remap: false }, VISUAL),
+    (
+        "inoremap",
+        3,
+        MappingCommand::Map { remap: false },
+        &[KeyMode::Insert],
+    ),
+    (
+        "cnoremap",
+        3,
+        MappingCommand::Map { remap: false },
+        &[KeyMode::Command],
+    ),
+    ("unmap", 3, MappingCommand::Unmap, NORMAL_AND_VISUAL),
+    ("nunmap", 3, MappingCommand::Unmap, &[KeyMode::Normal]),
+    ("vunmap", 2, MappingCommand::Unmap, VISUAL),
+    ("xunmap", 2, MappingCommand::Unmap, VISUAL),
+    ("iunmap", 2, MappingCommand::Unmap, &[KeyMode::Insert]),
+    ("cunmap", 2, MappingCommand::Unmap, &[KeyMode::Command]),
+    (
+        "abbreviate",
+        2,
+        MappingCommand::Abbreviate,
+        INSERT_AND_COMMAND,
+    ),
+    (
+        "noreabbrev",
+        5,
+        MappingCommand::Abbreviate,
+        INSERT_AND_COMMAND,
+    ),
+    ("iabbrev", 2, MappingCommand::Abbreviate, &[KeyMode::Insert]),
+    (
+        "inoreabbrev",
+        6,
+        MappingCommand::Abbreviate,
+        &[KeyMode::Insert],
+    ),
+    (
+        "cabbrev",
+        2,
+        MappingCommand::Abbreviate,
+        &[KeyMode::Command],
+    ),
+    (
+        "cnoreabbrev",
+        6,
+        MappingCommand::Abbreviate,
+        &[KeyMode::Command],
+    ),
+    (
+        "unabbreviate",
+        3,
+        MappingCommand::Unabbreviate,
+        INSERT_AND_COMMAND,
+    ),
+    (
+        "iunabbrev",
+        4,
+        MappingCommand::Unabbreviate,
+        &[KeyMode::Insert],
+    ),
+    (
+        "cunabbrev",
+        4,
+        MappingCommand::Unabbreviate,
+        &[KeyMode::Command],
+    ),
+];
+
+#[derive(Debug, Default)]
+pub(super) struct KeyMappingState {
+    /// The keymap without mappings, built on demand and dropped when the keymap
+    /// changes.
+    noremap_keys: Option<Keys>,
+    /// Set while a key fed by a non-recursive mapping is being resolved.
+    noremap: bool,
+    queue: VecDeque<FedKey>,
+    /// Where the next expansion is queued, so the keys fed for one event stay
+    /// in order ahead of the keys that were already waiting.
+    insert_at: usize,
+    feeding: bool,
+    /// Mapping depth of the key being resolved; zero for typed keys.
+    depth: usize,
+}
+
+#[derive(Debug)]
+struct FedKey {
+    event: Event,
+    remap: bool,
+    depth: usize,
+}
+
+impl Editor {
+    /// The keymap the key being resolved is looked up in.
+    pub(super) fn active_keys(&self) -> &Keys {
+        match &self.key_mappings.noremap_keys {
+            Some(keys) if self.key_mappings.noremap => keys,
+            _ => &self.config.keys,
+        }
+    }
+
+    fn ensure_noremap_keys(&mut self) {
+        if self.key_mappings.noremap_keys.is_some() {
+            return;
+        }
+        let defaults = Config::bundled_keys();
+        let mut keys = self.config.keys.clone();
+        for mode in [
+            KeyMode::Normal,
+            KeyMode::Insert,
+            KeyMode::Command,
+            KeyMode::Visual,
+            KeyMode::VisualLine,
+            KeyMode::VisualBlock,
+        ] {
+            *keys.for_mode_mut(mode) = without_fed_keys(
+                self.config.keys.for_mode(mode),
+                Some(defaults.for_mode(mode)),
+            );
+        }
+        self.key_mappings.noremap_keys = Some(keys);
+    }
+
+    /// Drops the keymap non-recursive mappings resolve against, so the next one
+    /// sees the current bindings.
+    pub(super) fn forget_noremap_keys(&mut self) {
+        self.key_mappings.noremap_keys = None;
+    }
+
+    /// Parses the mapping and abbreviation commands, or returns `None` for any
+    /// other command.
+    pub(super) fn parse_mapping_command(&mut self, cmd: &str) -> Option<Vec<Action>> {
+        let (name, arguments) = cmd.split_once(' ').unwrap_or((cmd, ""));
+        let (name, bang) = match name.strip_suffix('!') {
+            Some(name) => (name, true),
+            None => (name, false),
+        };
+        let (_, _, command, modes) = MAPPING_COMMANDS
+            .iter()
+            .find(|(full, shortest, _, _)| name.len() >= *shortest && full.starts_with(name))?;
+        let modes = if bang {
+            if *modes != NORMAL_AND_VISUAL
+                || !matches!(command, MappingCommand::Map { .. } | MappingCommand::Unmap)
+            {
+                self.set_legacy_message(Some(format!("{name} does not accept !")));
+                return Some(Vec::new());
+            }
+            INSERT_AND_COMMAND.to_vec()
+        } else {
+            modes.to_vec()
+        };
+        match self.mapping_command_actions(*command, modes, arguments.trim_start()) {
+            Ok(actions) => Some(actions),
+            Err(error) => {
+                self.set_legacy_message(Some(error.to_string()));
+                Some(Vec::new())
+            }
+        }
+    }
+
+    fn mapping_command_actions(
+        &mut self,
+        command: MappingCommand,
+        modes: Vec<KeyMode>,
+        mut arguments: &str,
+    ) -> anyhow::Result<Vec<Action>> {
+        // Vim's special arguments come first. Mappings never echo their keys, so
+        // <silent> changes nothing; the rest have no equivalent.
+        while let Some(end) = arguments
+            .starts_with('<')
+            .then(|| arguments.find('>'))
+            .flatten()
+        {
+            match arguments[1..end].to_ascii_lowercase().as_str() {
+                "silent" => arguments = arguments[end + 1..].trim_start(),
+                argument @ ("buffer" | "expr" | "nowait" | "script" | "unique" | "special") => {
+                    anyhow::bail!("<{argument}> mappings are not supported")
+                }
+                _ => break,
+            }
+        }
+        let (lhs, rhs) = arguments
+            .split_once(char::is_whitespace)
+            .map_or((arguments, ""), |(lhs, rhs)| (lhs, rhs.trim_start()));
+        match command {
+            MappingCommand::Map { remap } => {
+                if rhs.is_empty() {
+                    return self.key_mapping_listing(&modes, lhs);
+                }
+                let leader = &self.config.mappings.leader;
+                Ok(vec![Action::MapKeys {
+                    modes,
+                    keys: expand_leader(lhs, leader),
+                    rhs: expand_leader(rhs, leader),
+                    remap,
+                }])
+            }
+            MappingCommand::Unmap => {
+                anyhow::ensure!(!lhs.is_empty(), "unmap requires keys");
+                anyhow::ensure!(rhs.is_empty(), "trailing characters: {rhs}");
+                Ok(vec![Action::UnmapKeys {
+                    modes,
+                    keys: expand_leader(lhs, &self.config.mappings.leader),
+                }])
+            }
+            MappingCommand::Abbreviate => {
+                if rhs.is_empty() {
+                    return self.abbreviation_listing(&modes, lhs);
+                }
+                anyhow::ensure!(
+                    lhs.chars().all(is_keyword_char),
+                    "invalid abbreviation {lhs:?}: use keyword characters only"
+                );
+                Ok(vec![Action::Abbreviate {
+                    modes,
+                    abbreviation: lhs.to_string(),
+                    expansion: rhs.to_string(),
+                }])
+            }
+            MappingCommand::Unabbreviate => {
+                anyhow::ensure!(!lhs.is_empty(), "unabbreviate requires an abbreviation");
+                Ok(vec![Action::Unabbreviate {
+                    modes,
+                    abbreviation: lhs.to_string(),
+                }])
+            }
+        }
+    }
+
+    /// Key names of the [`Keys`] tables for the keys in Vim notation.
+    fn mapping_key_names(keys: &str) -> anyhow::Result<Vec<String>> {
+        let names = Self::macro_events_from_notation(keys)?
+            .iter()
+            .map(|event| {
+                Self::key_string_for_event(event)
+                    .ok_or_else(|| anyhow::anyhow!("{keys} cannot be mapped"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        anyhow::ensure!(!names.is_empty(), "map requires keys");
+        Ok(names)
+    }
+
+    pub(super) fn map_keys(
+        &mut self,
+        modes: &[KeyMode],
+        keys: &str,
+        rhs: &str,
+        remap: bool,
+    ) -> anyhow::Result<()> {
+        let names = Self::mapping_key_names(keys)?;
+        let action = if rhs.eq_ignore_ascii_case("<Nop>") {
+            KeyAction::Multiple(Vec::new())
+        } else {
+            Self::macro_events_from_notation(rhs)?;
+            KeyAction::Single(Action::FeedKeys {
+                keys: rhs.to_string(),
+                remap,
+            })
+        };
+        self.forget_noremap_keys();
+        let mut paths = Vec::with_capacity(modes.len());
+        for mode in modes {
+            let path = insert_key_mapping(self.config.keys.for_mode_mut(*mode), &names, &action)?;
+            paths.push((*mode, path));
+        }
+        if self.config.mappings.persist {
+            self.persist_mapping_change(|path| {
+                paths.iter().try_for_each(|(mode, keys)| {
+                    Config::persist_key_mapping(path, *mode, keys, Some(&action))
+                })
+            });
+        }
+        Ok(())
+    }
+
+    pub(super) fn unmap_keys(&mut self, modes: &[KeyMode], keys: &str) -> anyhow::Result<()> {
+        let names = Self::mapping_key_names(keys)?;
+        self.forget_noremap_keys();
+        let paths = modes
+            .iter()
+            .filter_map(|mode| {
+                remove_key_mapping(self.config.keys.for_mode_mut(*mode), &names)
+                    .map(|path| (*mode, path))
+            })
+            .collect::<Vec<_>>();
+        anyhow::ensure!(!paths.is_empty(), "no such mapping: {keys}");
+        if self.config.mappings.persist {
+            self.persist_mapping_change(|path| {
+                paths.iter().try_for_each(|(mode, keys)| {
+                    Config::persist_key_mapping(path, *mode, keys, None)
+                })
+            });
+        }
+        Ok(())
+    }
+
+    pub(super) fn abbreviate(&mut self, modes: &[KeyMode], abbreviation: &str, expansion: &str) {
+        for mode in modes {
+            if let Some(abbreviations) = self.config.abbreviations.for_mode_mut(*mode) {
+                abbreviations.insert(abbreviation.to_string(), expansion.to_string());
+            }
+        }
+        if self.config.mappings.persist {
+            self.persist_mapping_change(|path| {
+                modes.iter().try_for_each(|mode| {
+                    Config::persist_abbreviation(path, *mode, abbreviation, Some(expansion))
+                })
+            });
+        }
+    }
+
+    pub(super) fn unabbreviate(
+        &mut self,
+        modes: &[KeyMode],
+        abbreviation: &str,
+    ) -> anyhow::Result<()> {
+        let mut removed = Vec::new();
+        for mode in modes {
+            if self
+                .config
+                .abbreviations
+                .for_mode_mut(*mode)
+                .and_then(|abbreviations| abbreviations.remove(abbreviation))
+                .is_some()
+            {
+                removed.push(*mode);
+            }
+        }
+        anyhow::ensure!(!removed.is_empty(), "no such abbreviation: {abbreviation}");
+        if self.config.mappings.persist {
+            self.persist_mapping_change(|path| {
+                removed.iter().try_for_each(|mode| {
+                    Config::persist_abbreviation(path, *mode, abbreviation, None)
+                })
+            });
+        }
+        Ok(())
+    }
+
+    /// Writes a runtime change back to the user configuration for
+    /// `mappings.persist`, keeping the change for this session when that fails.
+    fn persist_mapping_change(&mut self, persist: impl FnOnce(&Path) -> anyhow::Result<()>) {
+        let saved = if self.preferences.is_persistent() {
+            persist(&self.language_config_path)
+        } else {
+            Err(anyhow::anyhow!("no persistent user configuration"))
+        };
+        if let Err(error) = saved {
+            self.set_notification_message(
+                Severity::Warning,
+                Some(format!("mapping not saved to config.toml: {error}")),
+            );
+        }
+    }
+
+    fn key_mapping_listing(&self, modes: &[KeyMode], keys: &str) -> anyhow::Result<Vec<Action>> {
+        let prefix = if keys.is_empty() {
+            Vec::new()
+        } else {
+            Self::mapping_key_names(&expand_leader(keys, &self.config.mappings.leader))?
+                .iter()
+                .map(|name| key_name_notation(name))
+                .collect()
+        };
+        let mut items = Vec::new();
+        for mode in modes {
+            let mut entries = Vec::new();
+            flatten_key_mappings(
+                self.config.keys.for_mode(*mode),
+                &mut Vec::new(),
+                &mut entries,
+            );
+            let mut entries = entries
+                .into_iter()
+                .filter(|(lhs, _)| lhs.starts_with(&prefix))
+                .filter(|(lhs, action)| {
+                    // `:vmap` fills all three Visual tables; list the copies once.
+                    !matches!(mode, KeyMode::VisualLine | KeyMode::VisualBlock)
+                        || !modes.contains(&KeyMode::Visual)
+                        || lookup_key_mapping(&self.config.keys.visual, lhs) != Some(*action)
+                })
+                .map(|(lhs, action)| (lhs.concat(), action))
+                .collect::<Vec<_>>();
+            entries.sort_by(|(left, _), (right, _)| left.cmp(right));
+            items.extend(entries.into_iter().map(|(lhs, action)| {
+                format!(
+                    "{:<3}{lhs:<15} {}",
+                    key_mode_label(*mode),
+                    key_action_description(action)
+                )
+            }));
+        }
+        anyhow::ensure!(!items.is_empty(), "no mapping found");
+        Ok(vec![Action::OpenPicker(
+            Some("Key mappings".to_string()),
+            items,
+            /*id*/ None,
+        )])
+    }
+
+    fn abbreviation_listing(
+        &self,
+        modes: &[KeyMode],
+        abbreviation: &str,
+    ) -> anyhow::Result<Vec<Action>> {
+        let mut items = Vec::new();
+        for mode in modes {
+            let Some(abbreviations) = self.config.abbreviations.for_mode(*mode) else {
+                continue;
+            };
+            let mut entries = abbreviations
+                .iter()
+                .filter(|(lhs, _)| lhs.starts_with(abbreviation))
+                .collect::<Vec<_>>();
+            entries.sort();
+            items.extend(entries.into_iter().map(|(lhs, expansion)| {
+                format!("{:<3}{lhs:<15} {expansion}", key_mode_label(*mode))
+            }));
+        }
+        anyhow::ensure!(!items.is_empty(), "no abbreviation found");
+        Ok(vec![Action::OpenPicker(
+            Some("Abbreviations".to_string()),
+            items,
+            /*id*/ None,
+        )])
+    }
+
+    /// Types `keys` through the active mode. A nested feed only queues its keys
+    /// ahead of the rest; the outermost one drives the queue, so a mapping that
+    /// feeds itself hits the depth limit instead of the async call stack.
+    /// Returns `true` when the keys quit the editor.
+    #[inline(never)]
+    pub(super) fn feed_keys<'a>(
+        &'a mut self,
+        keys: &'a str,
+        remap: bool,
+        buffer: &'a mut RenderBuffer,
+        runtime: &'a mut Runtime,
+    ) -> BoxFuture<'a, anyhow::Result<bool>> {
+        Box::pin(async move {
+            let events = match Self::macro_events_from_notation(keys) {
+                Ok(events) => events,
+                Err(error) => {
+                    self.set_legacy_message(Some(error.to_string()));
+                    return Ok(false);
+                }
+            };
+            let depth = self.key_mappings.depth + 1;
+            if depth > MAPPING_MAX_DEPTH {
+                self.key_mappings.queue.clear();
+                self.set_legacy_message(Some("recursive mapping".to_string()));
+                return Ok(false);
+            }
+            let state = &mut self.key_mappings;
+            for event in events {
+                state.queue.insert(
+                    state.insert_at,
+                    FedKey {
+                        event,
+                        remap,
+                        depth,
+                    },
+                );
+                state.insert_at += 1;
+            }
+            if state.feeding {
+                return Ok(false);
+            }
+
+            state.feeding = true;
+            let result = async {
+                let mut remaining = MAPPING_MAX_EVENTS;
+                while let Some(key) = self.key_mappings.queue.pop_front() {
+                    if remaining == 0 {
+                        self.set_legacy_message(Some(format!(
+                            "mapping expanded to more than {MAPPING_MAX_EVENTS} keys"
+                        )));
+                        break;
+                    }
+                    remaining -= 1;
+                    self.key_mappings.insert_at = 0;
+                    self.key_mappings.depth = key.depth;
+                    if !key.remap {
+                        self.ensure_noremap_keys();
+                    }
+                    self.key_mappings.noremap = !key.remap;
+                    let action = self.handle_event_with_runtime(&key.event, Some(runtime));
+                    self.key_mappings.noremap = false;
+                    if let Some(action) = action? {
+                        if self
+                            .handle_resolved_key_action(&key.event, &action, buffer, runtime)
+                            .await?
+                        {
+                            return Ok(true);
+                        }
+                    }
+                    if self.replay_checkpoint(buffer, runtime).await? {
+                        break;
+                    }
+                }
+                Ok(false)
+            }
+            .await;
+            self.key_mappings.queue.clear();
+            self.key_mappings.insert_at = 0;
+            self.key_mappings.depth = 0;
+            self.key_mappings.noremap = false;
+            self.key_mappings.feeding = false;
+            result
+        })
+    }
+
+    /// Vim gives up on a key sequence that matches no mapping by typing its keys
+    /// unmapped. Normal mode has nothing to type, but in Insert and Command mode
+    /// the `j` of an `inoremap jk <Esc>` must still reach the text.
+    pub(super) fn unmapped_key_sequence(&self, ev: &Event) -> Option<KeyAction> {
+        if !matches!(
+            self.mode,
+            Mode::Insert | Mode::Replace | Mode::VirtualReplace | Mode::Command
+        ) {
+            return None;
+        }
+        let prefix = self
+            .keymap_hint_prefix
+            .iter()
+            .map(|name| key_name_notation(name))
+            .collect::<String>();
+        let current = Self::macro_event_notation(ev)?;
+        if prefix.is_empty() || Self::macro_events_from_notation(&prefix).is_err() {
+            return None;
+        }
+        Some(KeyAction::Multiple(vec![
+            Action::FeedKeys {
+                keys: prefix,
+                remap: false,
+            },
+            Action::FeedKeys {
+                keys: current,
+                remap: true,
+            },
+        ]))
+    }
+
+    /// A command-line mapping for `ev`, if one is set.
+    pub(super) fn command_mode_mapping(&self, ev: &Event) -> Option<KeyAction> {
+        let key = Self::key_string_for_event(ev)?;
+        let mappings = &self.active_keys().command;
+        mappings
+            .get(&key)
+            .or_else(|| (key == "Space").then(|| mappings.get(" ")).flatten())
+            .cloned()
+    }
+
+    /// Whether `ev` ends an Insert-mode abbreviation that is before the cursor.
+    pub(super) fn ends_insert_abbreviation(&self, ev: &Event) -> bool {
+        ends_abbreviation(ev) && self.insert_abbreviation().is_some()
+    }
+
+    fn insert_abbreviation(&self) -> Option<(usize, String)> {
+        let line = self.buffer_line();
+        let cursor = self.grapheme_to_char_on_line(self.cx, line);
+        let before = self
+            .current_line_contents()
+            .unwrap_or_default()
+            .chars()
+            .take(cursor)
+            .collect::<String>();
+        let start = abbreviation_start(&before)?;
+        let expansion = self.config.abbreviations.insert.get(&before[start..])?;
+        Some((before[..start].chars().count(), expansion.clone()))
+    }
+
+    /// Replaces the abbreviation before the cursor inside the insert session, so
+    /// `.` and the `.` register see the expansion.
+    pub(super) async fn expand_insert_abbreviation(
+        &mut self,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        let Some((start, expansion)) = self.insert_abbreviation() else {
+            return Ok(());
+        };
+        let line = self.buffer_line();
+        let cursor = self.grapheme_to_char_on_line(self.cx, line);
+        let started_transaction = !self.transaction_active();
+        if started_transaction {
+            self.begin_transaction("expand abbreviation");
+        }
+        self.replace_range(
+            TextRange::new(
+                TextPosition::new(line, start),
+                TextPosition::new(line, cursor),
+            ),
+            &expansion,
+        );
+        self.move_to_insert_text_position(TextPosition::new(
+            line,
+            start + expansion.chars().count(),
+        ));
+        if started_transaction {
+            self.commit_transaction(self.cursor_snapshot());
+        }
+        if let Some(text) = &mut self.inserted_text {
+            for _ in start..cursor {
+                text.pop();
+            }
+            text.push_str(&expansion);
+        }
+        self.refresh_cursor_goal();
+        self.notify_change(runtime).await
+    }
+
+    /// Expands a Command-line abbreviation at the end of the command line when
+    /// `ev` ends it.
+    pub(super) fn expand_command_abbreviation(&mut self, ev: &Event) {
+        if !ends_abbreviation(ev) {
+            return;
+        }
+        let Some(start) = abbreviation_start(&self.command) else {
+            return;
+        };
+        if let Some(expansion) = self
+            .config
+            .abbreviations
+            .command
+            .get(&self.command[start..])
+        {
+            self.command.replace_range(start.., expansion);
+        }
+    }
+}
+
+/// Prepends [`Action::ExpandAbbreviation`] to what the key that ends the
+/// abbreviation does.
+pub(super) fn with_abbreviation_expanded(action: KeyAction) -> KeyAction {
+    match action {
+        KeyAction::Single(action) => KeyAction::Multiple(vec![Action::ExpandAbbreviation, action]),
+        KeyAction::Multiple(mut actions) => {
+            actions.insert(0, Action::ExpandAbbreviation);
+            KeyAction::Multiple(actions)
+        }
+        action => action,
+    }
+}
+
+/// Like Vim, an abbreviation expands on a non-keyword character, `Enter`, `Tab`
+/// or `Esc`.
+fn ends_abbreviation(ev: &Event) -> bool {
+    let Event::Key(KeyEvent {
+        code,
+        modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+        kind: KeyEventKind::Press | KeyEventKind::Repeat,
+        ..
+    }) = ev
+    else {
+        return false;
+    };
+    match code {
+        KeyCode::Char(character) => !is_keyword_char(*character),
+        KeyCode::Enter | KeyCode::Tab | KeyCode::Esc => true,
+        _ => false,
+    }
+}
+
+/// Byte offset of the keyword that ends `text`, if there is one.
+fn abbreviation_start(text: &str) -> Option<usize> {
+    text.char_indices()
+        .rev()
+        .take_while(|(_, character)| is_keyword_char(*character))
+        .last()
+        .map(|(index, _)| index)
+}
+
+/// Replaces `<leader>` in `keys`, in any letter case.
+fn expand_leader(keys: &str, leader: &str) -> String {
+    const LEADER: &str = "<leader>";
+    let mut expanded = String::with_capacity(keys.len());
+    let mut rest = keys;
+    while let Some(index) = rest.to_ascii_lowercase().find(LEADER) {
+        expanded.push_str(&rest[..index]);
+        expanded.push_str(leader);
+        rest = &rest[index + LEADER.len()..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// The table key for `name`: the keymap spells Space both as `Space` and `" "`.
+fn table_key(mappings: &HashMap<String, KeyAction>, name: &str) -> String {
+    if name == "Space" && !mappings.contains_key(name) && mappings.contains_key(" ") {
+        " ".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Binds `names` to `action` and returns the table keys of the binding.
+fn insert_key_mapping(
+    mut mappings: &mut HashMap<String, KeyAction>,
+    names: &[String],
+    action: &KeyAction,
+) -> anyhow::Result<Vec<String>> {
+    let (last, prefix) = names.split_last().expect("mapping keys are not empty");
+    let mut path = Vec::with_capacity(names.len());
+    for name in prefix {
+        let key = table_key(mappings, name);
+        path.push(key.clone());
+        let entry = mappings
+            .entry(key)
+            .or_insert_with(|| KeyAction::Nested(HashMap::new()));
+        let KeyAction::Nested(nested) = entry else {
+            anyhow::bail!("{} is already mapped", key_names_notation(&path));
+        };
+        mappings = nested;
+    }
+    let key = table_key(mappings, last);
+    path.push(key.clone());
+    if matches!(mappings.get(&key), Some(KeyAction::Nested(_))) {
+        anyhow::bail!("{} starts longer mappings", key_names_notation(&path));
+    }
+    mappings.insert(key, action.clone());
+    Ok(path)
+}
+
+/// Removes the binding of `names` with the prefix tables it empties, and returns
+/// its table keys.
+fn remove_key_mapping(
+    mappings: &mut HashMap<String, KeyAction>,
+    names: &[String],
+) -> Option<Vec<String>> {
+    let (name, rest) = names.split_first()?;
+    let key = table_key(mappings, name);
+    if rest.is_empty() {
+        mappings.remove(&key)?;
+        return Some(vec![key]);
+    }
+    let KeyAction::Nested(nested) = mappings.get_mut(&key)? else {
+        return None;
+    };
+    let mut path = remove_key_mapping(nested, rest)?;
+    if nested.is_empty() {
+        mappings.remove(&key);
+    }
+    path.insert(0, key);
+    Some(path)
+}
+
+fn lookup_key_mapping<'a>(
+    mut mappings: &'a HashMap<String, KeyAction>,
+    lhs: &[String],
+) -> Option<&'a KeyAction> {
+    let (last, prefix) = lhs.split_last()?;
+    for notation in prefix {
+        let Some((_, KeyAction::Nested(nested))) = mappings
+            .iter()
+            .find(|(name, _)| key_name_notation(name) == *notation)
+        else {
+            return None;
+        };
+        mappings = nested;
+    }
+    mappings
+        .iter()
+        .find(|(name, _)| key_name_notation(name) == *last)
+        .map(|(_, action)| action)
+}
+
+/// Collects every binding below `mappings` with its keys in Vim notation.
+fn flatten_key_mappings<'a>(
+    mappings: &'a HashMap<String, KeyAction>,
+    prefix: &mut Vec<String>,
+    entries: &mut Vec<(Vec<String>, &'a KeyAction)>,
+) {
+    for (name, action) in mappings {
+        prefix.push(key_name_notation(name));
+        match action {
+            KeyAction::Nested(nested) => flatten_key_mappings(nested, prefix, entries),
+            action => entries.push((prefix.clone(), action)),
+        }
+        prefix.pop();
+    }
+}
+
+fn feeds_keys(action: &KeyAction) -> bool {
+    match action {
+        KeyAction::Single(action) => matches!(action, Action::FeedKeys { .. }),
+        KeyAction::Multiple(actions) => actions
+            .iter()
+            .any(|action| matches!(action, Action::FeedKeys { .. })),
+        KeyAction::Repeating(_, action) => feeds_keys(action),
+        KeyAction::None | KeyAction::Nested(_) => false,
+    }
+}
+
+/// `mappings` with each binding that feeds keys replaced by its default.
+fn without_fed_keys(
+    mappings: &HashMap<String, KeyAction>,
+    defaults: Option<&HashMap<String, KeyAction>>,
+) -> HashMap<String, KeyAction> {
+    mappings
+        .iter()
+        .filter_map(|(name, action)| {
+            let default = defaults.and_then(|defaults| defaults.get(name));
+            let action = match action {
+                KeyAction::Nested(nested) => {
+                    let defaults = match default {
+                        Some(KeyAction::Nested(defaults)) => Some(defaults),
+                        _ => None,
+                    };
+                    let nested = without_fed_keys(nested, defaults);
+                    (!nested.is_empty()).then_some(KeyAction::Nested(nested))?
+                }
+                action if feeds_keys(action) => default?.clone(),
+                action => action.clone(),
+            };
+            Some((name.clone(), action))
+        })
+        .collect()
+}
+
+fn key_mode_label(mode: KeyMode) -> &'static str {
+    match mode {
+        KeyMode::Normal => "n",
+        KeyMode::Insert => "i",
+        KeyMode::Command => "c",
+        KeyMode::Visual => "v",
+        KeyMode::VisualLine => "V",
+        KeyMode::VisualBlock => "^V",
+    }
+}
+
+/// The right-hand side of a listed binding; `*` marks a non-recursive mapping,
+/// as in Vim.
+fn key_action_description(action: &KeyAction) -> String {
+    match action {
+        KeyAction::Single(Action::FeedKeys { keys, remap }) => {
+            format!("{}{keys}", if *remap { " " } else { "*" })
+        }
+        KeyAction::Multiple(actions) if actions.is_empty() => " <Nop>".to_string(),
+        action => format!(
+            " {}",
+            command_palette::key_action_label(action).unwrap_or_default()
+        ),
+    }
+}
+
+fn key_names_notation(names: &[String]) -> String {
+    names.iter().map(|name| key_name_notation(name)).collect()
+}
+
+/// Vim notation for a key name of the [`Keys`] tables, such as `<C-w>` for
+/// `Ctrl-w`.
+fn key_name_notation(name: &str) -> String {
+    match name {
+        " " | "Space" => return "<Space>".to_string(),
+        "<" => return "<lt>".to_string(),
+        "|" => return "<Bar>".to_string(),
+        "Enter" => return "<CR>".to_string(),
+        "Backspace" => return "<BS>".to_string(),
+        "Delete" => return "<Del>".to_string(),
+        "Shift-BackTab" | "BackTab" => return "<S-Tab>".to_string(),
+        _ if name.chars().count() == 1 => return name.to_string(),
+        _ => {}
+    }
+    let mut modifiers = String::new();
+    let mut key = name;
+    loop {
+        let (rest, modifier) = if let Some(rest) = key.strip_prefix("Ctrl-") {
+            (rest, "C-")
+        } else if let Some(rest) = key.strip_prefix("Alt-") {
+            (rest, "A-")
+        } else if let Some(rest) = key.strip_prefix("Shift-") {
+            (rest, "S-")
+        } else {
+            break;
+        };
+        modifiers.push_str(modifier);
+        key = rest;
+    }
+    format!("<{modifiers}{key}>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn mapping_editor(
+        contents: &str,
+        preferences: PreferencesStore,
+    ) -> (tempfile::TempDir, Editor) {
+        let root = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.lsp.enabled = false;
+        config.keys = Config::bundled_keys();
+        let lsp = Box::new(crate::lsp::LspManager::new(config.lsp.clone()));
+        let buffer = Buffer::new(
+            Some(root.path().join("notes.txt").to_string_lossy().into_owned()),
+            contents.to_string(),
+        );
+        let mut editor = Editor::test_with_size_and_preferences(
+            lsp,
+            60,
+            12,
+            config,
+            Theme::default(),
+            vec![buffer],
+            preferences,
+        )
+        .unwrap();
+        editor.test_disable_terminal_output();
+        editor.set_language_reload_source(root.path().join("config.toml"), Vec::new());
+        (root, editor)
+    }
+
+    async fn run(editor: &mut Editor, command: &str) {
+        editor
+            .test_execute_production_action(Action::Command(command.to_string()))
+            .await
+            .unwrap();
+    }
+
+    async fn type_keys(editor: &mut Editor, keys: &str) {
+        for event in Editor::macro_events_from_notation(keys).unwrap() {
+            editor.test_execute_event(event).await.unwrap();
+        }
+    }
+
+    fn lines(count: usize) -> String {
+        (0..count).map(|line| format!("{line}\n")).collect()
+    }
+
+    #[tokio::test]
+    async fn recursive_mappings_expand_fed_keys_and_noremap_ones_do_not() {
+        let (_root, mut editor) = mapping_editor(&lines(8), PreferencesStore::in_memory()).await;
+        run(&mut editor, "nnoremap j k").await;
+        run(&mut editor, "nmap R jj").await;
+        run(&mut editor, "nnoremap Q jj").await;
+
+        editor.test_set_viewport_cursor(0, 0, 4);
+        type_keys(&mut editor, "R").await;
+        assert_eq!(editor.test_buffer_line(), 2);
+        type_keys(&mut editor, "Q").await;
+        assert_eq!(editor.test_buffer_line(), 4);
+        type_keys(&mut editor, "j").await;
+        assert_eq!(editor.test_buffer_line(), 3);
+    }
+
+    #[tokio::test]
+    async fn noremap_resolution_follows_later_keymap_changes() {
+        let (_root, mut editor) = mapping_editor(&lines(8), PreferencesStore::in_memory()).await;
+        run(&mut editor, "nnoremap Q jj").await;
+        type_keys(&mut editor, "Q").await;
+        assert_eq!(editor.test_buffer_line(), 2);
+
+        run(&mut editor, "nunmap j").await;
+        type_keys(&mut editor, "Q").await;
+        assert_eq!(editor.test_buffer_line(), 2);
+
+        type_keys(&mut editor, "Q").await;
+        assert!(editor.key_mappings.noremap_keys.is_some());
+        editor.reload_languages().await.unwrap();
+        assert!(editor.key_mappings.noremap_keys.is_none());
+    }
+
+    #[tokio::test]
+    async fn mapping_chains_stop_at_the_depth_limit() {
+        let (_root, mut editor) = mapping_editor(&lines(4), PreferencesStore::in_memory()).await;
+        let key = |index: usize| {
+            let letter = |value: usize| char::from(b'a' + u8::try_from(value % 26).unwrap());
+            format!("\\{}{}", letter(index / 26), letter(index))
+        };
+        // Typing the first key of a chain of `n` mappings feeds `j` at depth `n`.
+        for index in 0..MAPPING_MAX_DEPTH - 1 {
+            run(
+                &mut editor,
+                &format!("nmap {} {}", key(index), key(index + 1)),
+            )
+            .await;
+        }
+        run(
+            &mut editor,
+            &format!("nmap {} j", key(MAPPING_MAX_DEPTH - 1)),
+        )
+        .await;
+        type_keys(&mut editor, &key(0)).await;
+        assert_eq!(editor.test_buffer_line(), 1);
+        assert_eq!(editor.test_last_error(), None);
+
+        run(&mut editor, &format!("nmap \\zz {}", key(0))).await;
+        type_keys(&mut editor, "\\zz").await;
+        assert_eq!(editor.test_buffer_line(), 1);
+        assert_eq!(editor.test_last_error(), Some("recursive mapping"));
+    }
+
+    #[tokio::test]
+    async fn one_key_stops_expanding_at_the_event_limit() {
+        let (_root, mut editor) = mapping_editor(&lines(4), PreferencesStore::in_memory()).await;
+        run(
+            &mut editor,
+            &format!("nnoremap Q {}j", "<Esc>".repeat(MAPPING_MAX_EVENTS - 1)),
+        )
+        .await;
+        type_keys(&mut editor, "Q").await;
+        assert_eq!(editor.test_buffer_line(), 1);
+        assert_eq!(editor.test_last_error(), None);
+
+        run(
+            &mut editor,
+            &format!("nnoremap Q {}j", "<Esc>".repeat(MAPPING_MAX_EVENTS)),
+        )
+        .await;
+        type_keys(&mut editor, "Q").await;
+        assert_eq!(editor.test_buffer_line(), 1);
+        assert_eq!(
+            editor.test_last_error(),
+            Some(format!("mapping expanded to more than {MAPPING_MAX_EVENTS} keys").as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn persisted_mappings_round_trip_through_the_user_configuration() {
+        let preferences_root = tempfile::tempdir().unwrap();
+        let preferences = PreferencesStore::load(preferences_root.path().join("preferences.json"));
+        let (root, mut editor) = mapping_editor("\n", preferences).await;
+        editor.config.mappings.persist = true;
+        let config_path = root.path().join("config.toml");
+        let load = || Config::load_user_file(&config_path, &[]).unwrap().config;
+
+        run(&mut editor, "nnoremap <leader>q jj").await;
+        run(&mut editor, "iabbrev teh the").await;
+        let loaded = load();
+        let Some(KeyAction::Nested(leader)) = loaded.keys.normal.get(" ") else {
+            panic!("expected the leader table");
+        };
+        assert_eq!(
+            leader.get("q"),
+            Some(&KeyAction::Single(Action::FeedKeys {
+                keys: "jj".to_string(),
+                remap: false,
+            }))
+        );
+        assert_eq!(
+            loaded
+                .abbreviations
+                .for_mode(KeyMode::Insert)
+                .and_then(|abbreviations| abbreviations.get("teh")),
+            Some(&"the".to_string())
+        );
+
+        run(&mut editor, "nunmap <leader>q").await;
+        run(&mut editor, "iunabbrev teh").await;
+        let loaded = load();
+        let Some(KeyAction::Nested(leader)) = loaded.keys.normal.get(" ") else {
+            panic!("expected the leader table");
+        };
+        assert!(!leader.contains_key("q"));
+        assert!(loaded
+            .abbreviations
+            .for_mode(KeyMode::Insert)
+            .is_none_or(|abbreviations| !abbreviations.contains_key("teh")));
+    }
+}
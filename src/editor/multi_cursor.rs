@@ -0,0 +1,577 @@
+//! Multiple cursors and selections.
+//!
+//! The primary cursor is the editor's own cursor. Secondary cursors are stored as
+//! [`SelectionRange`]s in absolute character indexes and move through every applied
+//! edit with the other anchors (see `update_anchors_for_edit`).
+//!
+//! Commands are not reimplemented per cursor. The keys of one complete command run at
+//! the primary cursor as usual, then replay at each secondary cursor in a hidden
+//! replay, starting from the mode the command started in. Commands that execute
+//! anything outside the batch-local action set (saves, dialogs, window and buffer
+//! changes, undo) stay primary-only. Transactions committed while a command replays
+//! merge into one undo step that records the secondary cursors on both sides.
+
+use super::*;
+use crate::clipboard::MemoryClipboardProvider;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct SecondaryCursor {
+    pub(super) range: SelectionRange,
+    goal: CursorGoal,
+    insert_entry: Option<usize>,
+    generated_indent: Option<usize>,
+}
+
+impl SecondaryCursor {
+    fn collapsed(head: usize) -> Self {
+        Self {
+            range: SelectionRange::cursor(head),
+            goal: CursorGoal::default(),
+            insert_entry: None,
+            generated_indent: None,
+        }
+    }
+
+    fn transform(&mut self, edit: AppliedTextEdit) {
+        let transform = |index| Editor::transform_char_index(index, AnchorAffinity::Left, edit);
+        self.range.anchor = transform(self.range.anchor);
+        self.range.head = transform(self.range.head);
+        self.insert_entry = self.insert_entry.map(transform);
+        self.generated_indent = self.generated_indent.map(transform);
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct MultiCursor {
+    buffer_id: Option<BufferId>,
+    cursors: Vec<SecondaryCursor>,
+    /// Primary cursor while the command replays at the secondary cursors.
+    primary: Option<SecondaryCursor>,
+    /// Keys of the command being typed, replayed once it is complete.
+    command: Vec<Event>,
+    command_mode: Option<Mode>,
+    executed: bool,
+    primary_only: bool,
+    replaying: bool,
+    /// Selection made by `AddCursorAtNextMatch` from the word under the cursor; later
+    /// matches then only stop at whole words.
+    word_selection: Option<SelectionRange>,
+    /// History revision and secondary cursors before the command being typed.
+    undo_group: Option<(u64, Vec<SelectionRange>)>,
+}
+
+/// Selection covering a non-empty search match.
+fn match_selection(buffer: &Buffer, found: SearchMatch) -> SelectionRange {
+    SelectionRange {
+        anchor: buffer.position_to_char_idx(TextPosition::new(found.start_y, found.start_x)),
+        head: buffer
+            .position_to_char_idx(TextPosition::new(found.end_y, found.end_x))
+            .saturating_sub(1),
+    }
+}
+
+impl Editor {
+    pub(crate) fn has_secondary_cursors(&self) -> bool {
+        !self.multi_cursor.cursors.is_empty()
+    }
+
+    /// Secondary cursors of `buffer_id`, for the renderer.
+    pub(crate) fn secondary_selections(&self, buffer_id: BufferId) -> Vec<SelectionRange> {
+        if self.multi_cursor.buffer_id != Some(buffer_id) {
+            return Vec::new();
+        }
+        self.multi_cursor
+            .cursors
+            .iter()
+            .map(|cursor| cursor.range)
+            .collect()
+    }
+
+    pub(super) fn transform_secondary_cursors(&mut self, edit: AppliedTextEdit) {
+        if self.multi_cursor.buffer_id != Some(self.current_buffer().id()) {
+            return;
+        }
+        for cursor in self
+            .multi_cursor
+            .cursors
+            .iter_mut()
+            .chain(self.multi_cursor.primary.as_mut())
+        {
+            cursor.transform(edit);
+        }
+    }
+
+    fn char_index_at(&self, point: Point) -> usize {
+        let character = self.grapheme_to_char_on_line(point.x, point.y);
+        self.current_buffer()
+            .position_to_char_idx(TextPosition::new(point.y, character))
+    }
+
+    fn point_at_char_index(&self, index: usize) -> Point {
+        self.point_for_text_position(self.current_buffer().char_idx_to_position(index))
+    }
+
+    fn cursor_state(&self) -> SecondaryCursor {
+        let head = self.char_index_at(Point::new(self.cx, self.buffer_line()));
+        let anchor = match self.selection_start {
+            Some(start) if self.is_visual() => self.char_index_at(start),
+            _ => head,
+        };
+        let buffer_id = self.current_buffer().id();
+        SecondaryCursor {
+            range: SelectionRange { anchor, head },
+            goal: self.cursor_goal,
+            insert_entry: self
+                .insert_entry_cursor
+                .map(|entry| self.char_index_at(Point::new(entry.x, entry.y))),
+            generated_indent: self
+                .generated_indent
+                .filter(|generated| generated.buffer_id == buffer_id)
+                .map(|generated| {
+                    self.current_buffer()
+                        .position_to_char_idx(TextPosition::new(generated.line, 0))
+                }),
+        }
+    }
+
+    fn load_cursor_state(&mut self, cursor: SecondaryCursor) {
+        let head = self
+            .current_buffer()
+            .char_idx_to_position(cursor.range.head);
+        if self.is_insert() {
+            self.move_to_insert_text_position(head);
+        } else {
+            self.move_to_text_position(head);
+        }
+        self.cursor_goal = cursor.goal;
+        self.insert_entry_cursor = cursor.insert_entry.map(|entry| {
+            let point = self.point_at_char_index(entry);
+            CursorSnapshot::new(point.x, point.y, self.vtop)
+        });
+        let buffer_id = self.current_buffer().id();
+        self.generated_indent = cursor.generated_indent.map(|start| GeneratedIndent {
+            buffer_id,
+            line: self.current_buffer().char_idx_to_position(start).line,
+        });
+        if self.is_visual() {
+            self.selection_start = Some(self.point_at_char_index(cursor.range.anchor));
+            self.update_selection_end(Point::new(self.cx, self.buffer_line()));
+        }
+    }
+
+    fn add_secondary_cursor(&mut self, cursor: SecondaryCursor) {
+        let buffer_id = self.current_buffer().id();
+        if self.multi_cursor.buffer_id != Some(buffer_id) {
+            self.multi_cursor.cursors.clear();
+            self.multi_cursor.buffer_id = Some(buffer_id);
+        }
+        self.multi_cursor.cursors.push(cursor);
+    }
+
+    /// Keeps one cursor per position, excluding the primary one.
+    fn normalize_secondary_cursors(&mut self) {
+        if !self.has_secondary_cursors() {
+            return;
+        }
+        if self.multi_cursor.buffer_id != Some(self.current_buffer().id()) {
+            self.remove_secondary_cursors();
+            return;
+        }
+        let primary = self.cursor_state().range.head;
+        let cursors = &mut self.multi_cursor.cursors;
+        cursors.retain(|cursor| cursor.range.head != primary);
+        cursors.sort_by_key(|cursor| cursor.range.head);
+        cursors.dedup_by_key(|cursor| cursor.range.head);
+        if cursors.is_empty() {
+            self.remove_secondary_cursors();
+        }
+    }
+
+    pub(super) fn remove_secondary_cursors(&mut self) {
+        self.multi_cursor.buffer_id = None;
+        self.multi_cursor.cursors.clear();
+        self.multi_cursor.word_selection = None;
+    }
+
+    /// Brings back the secondary cursors recorded by an undone or redone step.
+    pub(super) fn restore_secondary_cursors(&mut self, selections: Vec<SelectionRange>) {
+        if selections.is_empty() {
+            return;
+        }
+        self.remove_secondary_cursors();
+        for selection in selections {
+            self.add_secondary_cursor(SecondaryCursor::collapsed(selection.head));
+        }
+        self.normalize_secondary_cursors();
+    }
+
+    /// Records an input event of the command typed for the secondary cursors.
+    /// Returns whether [`Self::finish_multi_cursor_event`] must run after it.
+    pub(super) fn record_multi_cursor_event(&mut self, event: &Event) -> bool {
+        if !self.has_secondary_cursors()
+            || self.block_replay_depth > 0
+            || self.current_dialog.is_some()
+            || self.panel_manager.has_focused_panel()
+            || !matches!(event, Event::Key(_) | Event::Paste(_))
+        {
+            return false;
+        }
+        if !matches!(
+            self.mode,
+            Mode::Normal | Mode::Insert | Mode::Visual | Mode::VisualLine | Mode::VisualBlock
+        ) {
+            return false;
+        }
+        if self.multi_cursor.command.is_empty() {
+            if self.is_normal()
+                && !self.is_waiting_for_key_sequence()
+                && matches!(
+                    event,
+                    Event::Key(KeyEvent {
+                        code: KeyCode::Esc,
+                        ..
+                    })
+                )
+            {
+                self.remove_secondary_cursors();
+                return false;
+            }
+            self.multi_cursor.command_mode = Some(self.mode);
+            self.multi_cursor.executed = false;
+            self.multi_cursor.primary_only = false;
+            if self.multi_cursor.undo_group.is_none() {
+                let revision = self.current_buffer().undo_history.current_revision();
+                let selections = self.secondary_selections(self.current_buffer().id());
+                self.multi_cursor.undo_group = Some((revision, selections));
+            }
+        }
+        self.multi_cursor.command.push(event.clone());
+        true
+    }
+
+    /// Classifies an action of the command typed for the secondary cursors.
+    pub(super) fn note_multi_cursor_action(&mut self, action: &Action) {
+        if self.multi_cursor.command_mode.is_none() || self.multi_cursor.replaying {
+            return;
+        }
+        self.multi_cursor.executed = true;
+        let repeats = !matches!(action, Action::Undo | Action::Redo)
+            && (Self::action_is_batch_local(action)
+                || matches!(
+                    action,
+                    Action::Refresh
+                        | Action::Yank
+                        | Action::YankCurrentLine
+                        | Action::YankCurrentLines(_)
+                        | Action::YankToLineEnd(_)
+                        | Action::YankTextRange(_)
+                        | Action::YankLinewiseRange(_)
+                ));
+        self.multi_cursor.primary_only |= !repeats;
+    }
+
+    /// Replays a completed command at the secondary cursors and closes its undo step.
+    pub(super) async fn finish_multi_cursor_event(
+        &mut self,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        if self.is_waiting_for_key_sequence() {
+            return Ok(());
+        }
+        let events = std::mem::take(&mut self.multi_cursor.command);
+        let Some(mode) = self.multi_cursor.command_mode.take() else {
+            return Ok(());
+        };
+        let replay = self.multi_cursor.executed
+            && !self.multi_cursor.primary_only
+            && self.multi_cursor.buffer_id == Some(self.current_buffer().id());
+        if replay {
+            self.replay_at_secondary_cursors(events, mode, buffer, runtime)
+                .await?;
+        }
+        if !self.transaction_active() {
+            if let Some((revision, before)) = self.multi_cursor.undo_group.take() {
+                let after = self.secondary_selections(self.current_buffer().id());
+                let history = &mut self.current_buffer_mut().undo_history;
+                if replay && history.current_revision() != revision {
+                    history.merge_transactions_since(revision, before, after);
+                }
+            }
+        }
+        self.normalize_secondary_cursors();
+        self.render(buffer)
+    }
+
+    /// Runs `events` at every secondary cursor, publishing one change afterwards.
+    async fn replay_at_secondary_cursors(
+        &mut self,
+        events: Vec<Event>,
+        mode: Mode,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        let primary_mode = self.mode;
+        let vtop = self.vtop;
+        self.multi_cursor.primary = Some(self.cursor_state());
+        self.multi_cursor.replaying = true;
+        let committed_frame = self.begin_hidden_replay();
+        let mut scratch_buffer = buffer.clone();
+        let registers = self.registers.clone();
+        let last_semantic_change = self.last_semantic_change.clone();
+        let dialog = self.current_dialog.take();
+        let clipboard_text = self.clipboard.get_text().ok().flatten();
+        let clipboard: Box<dyn ClipboardProvider> = if self.clipboard.is_available() {
+            Box::new(MemoryClipboardProvider::with_text(
+                clipboard_text.unwrap_or_default(),
+            ))
+        } else {
+            Box::new(DisabledClipboardProvider)
+        };
+        let clipboard = std::mem::replace(&mut self.clipboard, clipboard);
+
+        let mut result = Ok(());
+        for index in 0..self.multi_cursor.cursors.len() {
+            self.mode = mode;
+            self.selection = None;
+            self.load_cursor_state(self.multi_cursor.cursors[index]);
+            result = async {
+                for event in &events {
+                    if let Some(action) = self.handle_event_with_runtime(event, Some(runtime))? {
+                        if self
+                            .handle_resolved_key_action(
+                                event,
+                                &action,
+                                &mut scratch_buffer,
+                                runtime,
+                            )
+                            .await?
+                        {
+                            anyhow::bail!("multi-cursor command attempted to quit the editor");
+                        }
+                    }
+                }
+                Ok(())
+            }
+            .await;
+            self.waiting_key_action = None;
+            self.pending_operator = None;
+            self.pending_character_motion = None;
+            self.pending_replace = false;
+            self.pending_visual_text_object_scope = None;
+            self.pending_register_prefix = false;
+            self.selected_register = None;
+            self.repeater = None;
+            let state = self.cursor_state();
+            if let Some(cursor) = self.multi_cursor.cursors.get_mut(index) {
+                *cursor = state;
+            }
+            if result.is_err() {
+                break;
+            }
+        }
+
+        self.clipboard = clipboard;
+        self.current_dialog = dialog;
+        self.last_semantic_change = last_semantic_change;
+        self.registers = registers;
+        self.mode = primary_mode;
+        self.selection = None;
+        if let Some(primary) = self.multi_cursor.primary.take() {
+            self.load_cursor_state(primary);
+        }
+        self.vtop = vtop;
+        self.end_hidden_replay(committed_frame);
+        self.multi_cursor.replaying = false;
+        self.check_bounds();
+
+        let notification_result = self.publish_hidden_replay_changes(runtime).await;
+        result.and(notification_result)
+    }
+
+    pub(super) fn execute_multi_cursor_action(
+        &mut self,
+        action: &Action,
+        buffer: &mut RenderBuffer,
+    ) -> anyhow::Result<()> {
+        match action {
+            Action::AddCursorAbove | Action::AddCursorBelow => {
+                let target = if matches!(action, Action::AddCursorBelow) {
+                    self.visible_line_below_cursor()
+                } else {
+                    self.visible_line_above_cursor()
+                };
+                let Some(target) = target else {
+                    return Ok(());
+                };
+                let cursor = self.cursor_state();
+                self.add_secondary_cursor(cursor);
+                self.move_cursor_to_line_keeping_goal(target);
+                self.check_bounds();
+            }
+            Action::AddCursorAtNextMatch => {
+                if !self.add_cursor_at_next_match() {
+                    return self.render(buffer);
+                }
+            }
+            Action::SplitSelectionIntoLines => {
+                let Some(selection) = self.selection.filter(|_| self.is_visual()) else {
+                    return Ok(());
+                };
+                let (x0, y0, x1, y1): (usize, usize, usize, usize) = selection.into();
+                let mut ranges = Vec::new();
+                for line in y0..=y1 {
+                    let length = self.line_character_len(line);
+                    let last = length.saturating_sub(1);
+                    let (start, end) = match self.mode {
+                        Mode::VisualLine => (0, last),
+                        Mode::VisualBlock => {
+                            let start = self.grapheme_to_char_on_line(x0.min(x1), line);
+                            if start >= length && length > 0 {
+                                continue;
+                            }
+                            let end = self.grapheme_to_char_on_line(x0.max(x1), line);
+                            (start, end.min(last))
+                        }
+                        _ => (
+                            if line == y0 {
+                                self.grapheme_to_char_on_line(x0, line)
+                            } else {
+                                0
+                            },
+                            if line == y1 {
+                                self.grapheme_to_char_on_line(x1, line).min(last)
+                            } else {
+                                last
+                            },
+                        ),
+                    };
+                    let buffer = self.current_buffer();
+                    ranges.push(SelectionRange {
+                        anchor: buffer.position_to_char_idx(TextPosition::new(line, start)),
+                        head: buffer.position_to_char_idx(TextPosition::new(line, end)),
+                    });
+                }
+                if ranges.len() < 2 {
+                    self.set_legacy_message(Some("selection spans one line".into()));
+                    return Ok(());
+                }
+                self.select_ranges(ranges);
+            }
+            Action::SelectAllMatches {
+                start_line,
+                end_line,
+                pattern,
+            } => {
+                let regex = self.compile_search_regex(pattern)?;
+                let buffer = self.current_buffer();
+                let ranges = buffer
+                    .regex_matches(&regex)
+                    .into_iter()
+                    .filter(|found| (*start_line..=*end_line).contains(&found.start_y))
+                    .map(|found| match_selection(buffer, found))
+                    .collect::<Vec<_>>();
+                let Some(first) = ranges.first().copied() else {
+                    self.set_legacy_message(Some(format!("pattern not found: {pattern}")));
+                    return Ok(());
+                };
+                self.remove_secondary_cursors();
+                let mut ranges = ranges;
+                ranges.remove(0);
+                ranges.push(first);
+                self.select_ranges(ranges);
+            }
+            Action::RemoveSecondaryCursors => self.remove_secondary_cursors(),
+            _ => return Ok(()),
+        }
+        self.normalize_secondary_cursors();
+        let count = self.multi_cursor.cursors.len() + 1;
+        self.set_quiet_message(Some(if count > 1 {
+            format!("{count} cursors")
+        } else {
+            "1 cursor".to_string()
+        }));
+        self.render(buffer)
+    }
+
+    /// Selects every range characterwise; the last one becomes the primary selection.
+    fn select_ranges(&mut self, mut ranges: Vec<SelectionRange>) {
+        let Some(primary) = ranges.pop() else {
+            return;
+        };
+        for range in ranges {
+            self.add_secondary_cursor(SecondaryCursor {
+                range,
+                ..SecondaryCursor::collapsed(range.head)
+            });
+        }
+        self.mode = Mode::Visual;
+        self.selection = None;
+        self.insert_entry_cursor = None;
+        self.load_cursor_state(SecondaryCursor::collapsed(primary.head));
+        self.selection_start = Some(self.point_at_char_index(primary.anchor));
+        self.update_selection_end(Point::new(self.cx, self.buffer_line()));
+        self.refresh_cursor_goal();
+    }
+
+    /// Selects the word under the cursor, or adds a cursor at the next occurrence of
+    /// the Visual selection. Returns whether the selection set changed.
+    fn add_cursor_at_next_match(&mut self) -> bool {
+        if !self.is_visual() {
+            let Some(range) =
+                self.text_object_range_with_count(TextObjectScope::Inner, TextObjectKind::Word, 1)
+            else {
+                return false;
+            };
+            self.mode = Mode::Visual;
+            self.select_text_range(range);
+            self.refresh_cursor_goal();
+            self.multi_cursor.word_selection = Some(self.cursor_state().range);
+            return true;
+        }
+
+        let Some(text) = self.selected_text().filter(|text| !text.is_empty()) else {
+            return false;
+        };
+        let primary = self.cursor_state().range;
+        let whole_word = if self.has_secondary_cursors() {
+            self.multi_cursor.word_selection.is_some()
+        } else {
+            self.multi_cursor.word_selection == Some(primary)
+        };
+        let escaped = regex::escape(&text);
+        let pattern = if whole_word {
+            format!(r"\b{escaped}\b")
+        } else {
+            escaped
+        };
+        let Ok(regex) = Regex::new(&pattern) else {
+            return false;
+        };
+        let buffer = self.current_buffer();
+        let origin = buffer.char_idx_to_position(primary.span().1);
+        let found = buffer
+            .regex_match_from(&regex, (origin.character, origin.line), false, true)
+            .map(|found| match_selection(buffer, found));
+        let selected = |range: SelectionRange| {
+            range.span().0 == primary.span().0
+                || self
+                    .multi_cursor
+                    .cursors
+                    .iter()
+                    .any(|cursor| cursor.range.span().0 == range.span().0)
+        };
+        let Some(found) = found.filter(|found| !selected(*found)) else {
+            self.set_legacy_message(Some("no more matches".into()));
+            return false;
+        };
+
+        let word_selection = self.multi_cursor.word_selection;
+        let mut cursor = self.cursor_state();
+        cursor.range = primary;
+        self.add_secondary_cursor(cursor);
+        self.multi_cursor.word_selection = word_selection.filter(|_| whole_word);
+        self.select_ranges(vec![found]);
+        true
+    }
+}
@@ -135,16 +135,48 @@ impl Editor {
         self.set_message_with_attention(severity, AttentionPolicy::for_severity(severity), message);
     }
 
+    /// Shows `message` with longer `details`, such as a command's output, that the
+    /// message history keeps.
+    pub(super) fn set_message_with_details(
+        &mut self,
+        severity: Severity,
+        message: String,
+        details: &str,
+    ) {
+        let details = if details.is_empty() {
+            message.clone()
+        } else {
+            details.to_string()
+        };
+        self.publish_message(
+            severity,
+            AttentionPolicy::for_severity(severity),
+            Some(message),
+            details,
+        );
+    }
+
     fn set_message_with_attention(
         &mut self,
         severity: Severity,
         attention: AttentionPolicy,
         message: Option<String>,
+    ) {
+        let details = message.clone().unwrap_or_default();
+        self.publish_message(severity, attention, message, details);
+    }
+
+    fn publish_message(
+        &mut self,
+        severity: Severity,
+        attention: AttentionPolicy,
+        message: Option<String>,
+        details: String,
     ) {
         if let Some(message) = message.as_ref().filter(|message| !message.is_empty()) {
             let notice = Notice::new(NotificationSource::Editor, severity, message)
                 .with_attention(attention)
-                .with_details(message);
+                .with_details(details);
             if let Err(error) = self.publish_notification(notice) {
                 self.notification_fallback = Some(crate::notification::single_line(
                     truncate_chars(message, 4_096),
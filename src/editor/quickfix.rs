@@ -0,0 +1,1083 @@
+//! Quickfix and location lists: `:cnext`, `:cprevious`, `:cfirst`, `:clast`, `:cc`,
+//! `:colder`, `:cnewer`, `:clist`, `:copen` and `:cclose`, their `:l` forms, and `]q`
+//! and `[q`.
+//!
+//! There is one quickfix list and a location list per window, and setting either
+//! pushes a new list onto a history of the last ten, as Vim does. Lists come from the
+//! diagnostics pickers, from plugins through the `SetQuickfixList` request, and from
+//! anything else that builds a [`QuickfixListSpec`].
+//!
+//! An entry whose file has a buffer holds an edit anchor in it, so its position moves
+//! with edits like a mark does. Entries for files that are not loaded keep the
+//! location they were given and bind when the file is opened; when a buffer goes
+//! away its entries keep the anchor's last position for the next one. Jumping to an
+//! entry records a jump, as opening a location does.
+
+use super::*;
+
+/// Lists each history keeps, as in Vim.
+const QUICKFIX_HISTORY: usize = 10;
+const QUICKFIX_PANEL: &str = "quickfix";
+const LOCATION_PANEL: &str = "location-list";
+const QUICKFIX_PANEL_HEIGHT: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QuickfixCommand {
+    Jump(QuickfixMotion),
+    History(isize),
+    List,
+    Open,
+    Close,
+}
+
+/// Quickfix commands with the shortest prefix Vim accepts. Counts come after the
+/// name, as in `:3cnext`'s equivalent `:cnext 3`.
+const QUICKFIX_COMMANDS: &[(&str, usize, QuickfixScope, QuickfixCommand)] = &[
+    (
+        "cnext",
+        2,
+        QuickfixScope::Quickfix,
+        QuickfixCommand::Jump(QuickfixMotion::Next(1)),
+    ),
+    (
+        "cNext",
+        2,
+        QuickfixScope::Quickfix,
+        QuickfixCommand::Jump(QuickfixMotion::Previous(1)),
+    ),
+    (
+        "cprevious",
+        2,
+        QuickfixScope::Quickfix,
+        QuickfixCommand::Jump(QuickfixMotion::Previous(1)),
+    ),
+    (
+        "cfirst",
+        4,
+        QuickfixScope::Quickfix,
+        QuickfixCommand::Jump(QuickfixMotion::First),
+    ),
+    (
+        "crewind",
+        2,
+        QuickfixScope::Quickfix,
+        QuickfixCommand::Jump(QuickfixMotion::First),
+    ),
+    (
+        "clast",
+        3,
+        QuickfixScope::Quickfix,
+        QuickfixCommand::Jump(QuickfixMotion::Last),
+    ),
+    (
+        "cc",
+        2,
+        QuickfixScope::Quickfix,
+        QuickfixCommand::Jump(QuickfixMotion::Entry(None)),
+    ),
+    (
+        "colder",
+        3,
+        QuickfixScope::Quickfix,
+        QuickfixCommand::History(-1),
+    ),
+    (
+        "cnewer",
+        4,
+        QuickfixScope::Quickfix,
+        QuickfixCommand::History(1),
+    ),
+    ("clist", 2, QuickfixScope::Quickfix, QuickfixCommand::List),
+    ("copen", 4, QuickfixScope::Quickfix, QuickfixCommand::Open),
+    ("cclose", 3, QuickfixScope::Quickfix, QuickfixCommand::Close),
+    (
+        "lnext",
+        3,
+        QuickfixScope::Location,
+        QuickfixCommand::Jump(QuickfixMotion::Next(1)),
+    ),
+    (
+        "lNext",
+        2,
+        QuickfixScope::Location,
+        QuickfixCommand::Jump(QuickfixMotion::Previous(1)),
+    ),
+    (
+        "lprevious",
+        2,
+        QuickfixScope::Location,
+        QuickfixCommand::Jump(QuickfixMotion::Previous(1)),
+    ),
+    (
+        "lfirst",
+        4,
+        QuickfixScope::Location,
+        QuickfixCommand::Jump(QuickfixMotion::First),
+    ),
+    (
+        "lrewind",
+        2,
+        QuickfixScope::Location,
+        QuickfixCommand::Jump(QuickfixMotion::First),
+    ),
+    (
+        "llast",
+        3,
+        QuickfixScope::Location,
+        QuickfixCommand::Jump(QuickfixMotion::Last),
+    ),
+    (
+        "ll",
+        2,
+        QuickfixScope::Location,
+        QuickfixCommand::Jump(QuickfixMotion::Entry(None)),
+    ),
+    (
+        "lolder",
+        3,
+        QuickfixScope::Location,
+        QuickfixCommand::History(-1),
+    ),
+    (
+        "lnewer",
+        4,
+        QuickfixScope::Location,
+        QuickfixCommand::History(1),
+    ),
+    ("llist", 3, QuickfixScope::Location, QuickfixCommand::List),
+    ("lopen", 3, QuickfixScope::Location, QuickfixCommand::Open),
+    ("lclose", 3, QuickfixScope::Location, QuickfixCommand::Close),
+];
+
+#[derive(Debug, Default)]
+pub(super) struct QuickfixState {
+    quickfix: QuickfixStack,
+    locations: HashMap<WindowId, QuickfixStack>,
+    /// Window whose location list the location-list panel shows.
+    location_panel_window: Option<WindowId>,
+}
+
+#[derive(Debug, Default)]
+struct QuickfixStack {
+    lists: Vec<QuickfixList>,
+    current: usize,
+}
+
+#[derive(Debug)]
+struct QuickfixList {
+    title: String,
+    entries: Vec<QuickfixEntry>,
+    current: usize,
+}
+
+#[derive(Debug)]
+struct QuickfixEntry {
+    /// Normalized path of the entry's file.
+    file: String,
+    /// Where the entry was given, used until its file has a buffer.
+    location: plugin::PluginLocation,
+    anchor: Option<EditAnchor>,
+    text: String,
+}
+
+impl QuickfixState {
+    fn stacks_mut(&mut self) -> impl Iterator<Item = &mut QuickfixStack> {
+        std::iter::once(&mut self.quickfix).chain(self.locations.values_mut())
+    }
+
+    fn entries_mut(&mut self) -> impl Iterator<Item = &mut QuickfixEntry> {
+        self.stacks_mut()
+            .flat_map(|stack| &mut stack.lists)
+            .flat_map(|list| &mut list.entries)
+    }
+}
+
+impl QuickfixStack {
+    /// Adds a list after the current one, dropping any newer lists and the oldest
+    /// beyond the history limit.
+    fn push(&mut self, list: QuickfixList) {
+        self.lists.truncate(self.current + 1);
+        self.lists.push(list);
+        if self.lists.len() > QUICKFIX_HISTORY {
+            self.lists.remove(0);
+        }
+        self.current = self.lists.len() - 1;
+    }
+
+    fn list(&self) -> Option<&QuickfixList> {
+        self.lists.get(self.current)
+    }
+
+    fn list_mut(&mut self) -> Option<&mut QuickfixList> {
+        self.lists.get_mut(self.current)
+    }
+
+    /// Moves `delta` lists toward newer (positive) or older (negative) ones.
+    fn move_history(&mut self, delta: isize, scope: QuickfixScope) -> anyhow::Result<()> {
+        let noun = scope.noun();
+        anyhow::ensure!(!self.lists.is_empty(), "no {noun}");
+        if delta < 0 {
+            anyhow::ensure!(self.current > 0, "at bottom of {noun} stack");
+            self.current = self.current.saturating_sub(delta.unsigned_abs());
+        } else {
+            anyhow::ensure!(
+                self.current + 1 < self.lists.len(),
+                "at top of {noun} stack"
+            );
+            self.current = (self.current + delta.unsigned_abs()).min(self.lists.len() - 1);
+        }
+        Ok(())
+    }
+}
+
+impl QuickfixList {
+    fn index(&self, motion: QuickfixMotion) -> anyhow::Result<usize> {
+        anyhow::ensure!(!self.entries.is_empty(), "no more items");
+        let last = self.entries.len() - 1;
+        Ok(match motion {
+            QuickfixMotion::Next(count) => {
+                anyhow::ensure!(self.current < last, "no more items");
+                (self.current + count.max(1)).min(last)
+            }
+            QuickfixMotion::Previous(count) => {
+                anyhow::ensure!(self.current > 0, "no more items");
+                self.current.saturating_sub(count.max(1))
+            }
+            QuickfixMotion::First => 0,
+            QuickfixMotion::Last => last,
+            QuickfixMotion::Entry(Some(number)) => number.saturating_sub(1).min(last),
+            QuickfixMotion::Entry(None) => self.current,
+        })
+    }
+
+    /// "3 of 5", as the summary of a history move shows it.
+    fn summary(&self, scope: QuickfixScope, number: usize, count: usize) -> String {
+        let entries = match self.entries.len() {
+            1 => "1 entry".to_string(),
+            count => format!("{count} entries"),
+        };
+        if self.title.is_empty() {
+            format!("{} {number} of {count}; {entries}", scope.noun())
+        } else {
+            format!(
+                "{} {number} of {count}; {entries}: {}",
+                scope.noun(),
+                self.title
+            )
+        }
+    }
+}
+
+impl QuickfixEntry {
+    /// The entry's zero-based line and column: the anchor's while it is bound, else
+    /// the given location's.
+    fn position(&self) -> (usize, usize) {
+        match &self.anchor {
+            Some(anchor) => (anchor.fallback.line, anchor.fallback.character),
+            None => (self.location.line, self.location.column),
+        }
+    }
+
+    fn display_file(&self) -> String {
+        Path::new(&self.file)
+            .strip_prefix(get_workspace_path())
+            .map_or_else(|_| self.file.clone(), |path| path.display().to_string())
+    }
+
+    /// `file:line:col`, one-based like compiler output.
+    fn label(&self) -> String {
+        let (line, column) = self.position();
+        format!("{}:{}:{}", self.display_file(), line + 1, column + 1)
+    }
+}
+
+impl QuickfixScope {
+    pub(super) fn from_panel_id(id: &str) -> Option<Self> {
+        match id {
+            QUICKFIX_PANEL => Some(Self::Quickfix),
+            LOCATION_PANEL => Some(Self::Location),
+            _ => None,
+        }
+    }
+
+    fn panel_id(self) -> &'static str {
+        match self {
+            Self::Quickfix => QUICKFIX_PANEL,
+            Self::Location => LOCATION_PANEL,
+        }
+    }
+
+    fn noun(self) -> &'static str {
+        match self {
+            Self::Quickfix => "quickfix list",
+            Self::Location => "location list",
+        }
+    }
+
+    fn panel_title(self) -> &'static str {
+        match self {
+            Self::Quickfix => "Quickfix List",
+            Self::Location => "Location List",
+        }
+    }
+}
+
+impl Editor {
+    /// Parses the quickfix and location-list commands, or returns `None` for any
+    /// other command.
+    pub(super) fn parse_quickfix_command(&mut self, cmd: &str) -> Option<Vec<Action>> {
+        let (name, argument) = cmd.split_once(' ').unwrap_or((cmd, ""));
+        let name = name.strip_suffix('!').unwrap_or(name);
+        let (full, _, scope, command) = QUICKFIX_COMMANDS
+            .iter()
+            .find(|(full, shortest, ..)| name.len() >= *shortest && full.starts_with(name))?;
+        let argument = argument.trim();
+        let count = if argument.is_empty() {
+            None
+        } else if let Ok(count) = argument.parse::<usize>() {
+            Some(count)
+        } else {
+            self.set_legacy_message(Some(format!("trailing characters: {argument}")));
+            return Some(Vec::new());
+        };
+        let takes_count = matches!(
+            command,
+            QuickfixCommand::Jump(_) | QuickfixCommand::History(_)
+        );
+        if count.is_some() && !takes_count {
+            self.set_legacy_message(Some(format!("{full} takes no argument")));
+            return Some(Vec::new());
+        }
+        let action = match *command {
+            QuickfixCommand::Jump(motion) => {
+                let motion = match (motion, count) {
+                    (QuickfixMotion::Next(_), Some(count)) => QuickfixMotion::Next(count),
+                    (QuickfixMotion::Previous(_), Some(count)) => QuickfixMotion::Previous(count),
+                    // `:cfirst N`, `:clast N` and `:cc N` all go to entry N.
+                    (_, Some(number)) => QuickfixMotion::Entry(Some(number)),
+                    (motion, None) => motion,
+                };
+                Action::JumpToQuickfixEntry(*scope, motion)
+            }
+            QuickfixCommand::History(direction) => {
+                let count = isize::try_from(count.unwrap_or(1)).unwrap_or(isize::MAX);
+                Action::QuickfixHistory(*scope, direction * count)
+            }
+            QuickfixCommand::List => Action::ListQuickfix(*scope),
+            QuickfixCommand::Open => Action::OpenQuickfixPanel(*scope),
+            QuickfixCommand::Close => Action::CloseQuickfixPanel(*scope),
+        };
+        Some(vec![action])
+    }
+
+    /// Keep quickfix jumps' file loading off nested edit frames.
+    #[inline(never)]
+    pub(super) fn execute_quickfix_action<'a>(
+        &'a mut self,
+        action: &'a Action,
+        buffer: &'a mut RenderBuffer,
+        runtime: &'a mut Runtime,
+    ) -> BoxFuture<'a, anyhow::Result<bool>> {
+        Box::pin(async move {
+            let result = match action {
+                Action::SetQuickfixList(list) => {
+                    self.set_quickfix_list(list);
+                    Ok(())
+                }
+                Action::JumpToQuickfixEntry(scope, motion) => {
+                    return self
+                        .jump_to_quickfix_entry(*scope, *motion, buffer, runtime)
+                        .await;
+                }
+                Action::NextQuickfixEntry => {
+                    return self
+                        .jump_to_quickfix_entry(
+                            QuickfixScope::Quickfix,
+                            QuickfixMotion::Next(1),
+                            buffer,
+                            runtime,
+                        )
+                        .await;
+                }
+                Action::PreviousQuickfixEntry => {
+                    return self
+                        .jump_to_quickfix_entry(
+                            QuickfixScope::Quickfix,
+                            QuickfixMotion::Previous(1),
+                            buffer,
+                            runtime,
+                        )
+                        .await;
+                }
+                Action::QuickfixHistory(scope, delta) => self.move_quickfix_history(*scope, *delta),
+                Action::ListQuickfix(scope) => self.list_quickfix(*scope),
+                Action::OpenQuickfixPanel(scope) => self.open_quickfix_panel(*scope),
+                Action::CloseQuickfixPanel(scope) => {
+                    self.close_quickfix_panel(*scope);
+                    Ok(())
+                }
+                _ => unreachable!("only quickfix actions are dispatched here"),
+            };
+            if let Err(error) = result {
+                self.set_legacy_message(Some(error.to_string()));
+            }
+            self.render(buffer)?;
+            Ok(false)
+        })
+    }
+
+    /// Pushes a new quickfix or location list and returns its entry count.
+    pub(super) fn set_quickfix_list(&mut self, spec: &QuickfixListSpec) -> usize {
+        let window = self.window_manager.active_stable_window_id();
+        self.set_window_quickfix_list(spec, window)
+    }
+
+    /// [`Self::set_quickfix_list`], giving a location list to `window` rather than
+    /// the current window.
+    pub(super) fn set_window_quickfix_list(
+        &mut self,
+        spec: &QuickfixListSpec,
+        window: Option<WindowId>,
+    ) -> usize {
+        let mut file_lines = HashMap::<String, Option<Vec<String>>>::new();
+        let mut entries = Vec::with_capacity(spec.items.len());
+        for item in &spec.items {
+            let file = normalized_file_path(&item.location.path).map_or_else(
+                |_| item.location.path.clone(),
+                |path| path.to_string_lossy().into_owned(),
+            );
+            let index = self.file_buffer_index(Path::new(&file));
+            let text = if item.text.is_empty() {
+                let line = match index {
+                    Some(index) => self.buffer_manager[index].get(item.location.line),
+                    None => file_lines
+                        .entry(file.clone())
+                        .or_insert_with(|| {
+                            std::fs::read_to_string(&file)
+                                .ok()
+                                .map(|contents| contents.lines().map(str::to_string).collect())
+                        })
+                        .as_ref()
+                        .and_then(|lines| lines.get(item.location.line).cloned()),
+                };
+                line.unwrap_or_default().trim().to_string()
+            } else {
+                item.text.clone()
+            };
+            let mut entry = QuickfixEntry {
+                file,
+                location: item.location.clone(),
+                anchor: None,
+                text,
+            };
+            if let Some(index) = index {
+                entry.anchor = Some(quickfix_anchor(&self.buffer_manager[index], &entry));
+            }
+            entries.push(entry);
+        }
+        let count = entries.len();
+        let list = QuickfixList {
+            title: spec.title.clone(),
+            entries,
+            current: 0,
+        };
+        let scope = if spec.location {
+            QuickfixScope::Location
+        } else {
+            QuickfixScope::Quickfix
+        };
+        match scope {
+            QuickfixScope::Quickfix => self.quickfix.quickfix.push(list),
+            QuickfixScope::Location => {
+                let Some(window) = window else {
+                    return 0;
+                };
+                self.quickfix
+                    .locations
+                    .entry(window)
+                    .or_default()
+                    .push(list);
+            }
+        }
+        if spec.open {
+            if let Err(error) = self.open_quickfix_panel(scope) {
+                self.set_legacy_message(Some(error.to_string()));
+            }
+        } else {
+            self.refresh_quickfix_panel(scope);
+        }
+        count
+    }
+
+    /// Binds the entries for a file that just got a buffer.
+    pub(super) fn bind_quickfix_file(&mut self, file: &str) {
+        let Some(index) = self.file_buffer_index(Path::new(file)) else {
+            return;
+        };
+        let buffer_ids = self
+            .buffer_manager
+            .iter()
+            .map(Buffer::id)
+            .collect::<HashSet<_>>();
+        let source = &self.buffer_manager[index];
+        let mut bound = false;
+        for entry in self.quickfix.entries_mut() {
+            let unbound = entry
+                .anchor
+                .as_ref()
+                .is_none_or(|anchor| !buffer_ids.contains(&anchor.buffer_id));
+            if unbound && same_file_path(Path::new(&entry.file), Path::new(file)) {
+                entry.anchor = Some(quickfix_anchor(source, entry));
+                bound = true;
+            }
+        }
+        if bound {
+            self.refresh_quickfix_panels();
+        }
+    }
+
+    /// Moves the entries in the edited buffer with the edit.
+    pub(super) fn transform_quickfix_anchors(&mut self, edit: AppliedTextEdit) {
+        if self.quickfix.quickfix.lists.is_empty() && self.quickfix.locations.is_empty() {
+            return;
+        }
+        let buffer_index = self.buffer_manager.active_index();
+        let source = &self.buffer_manager[buffer_index];
+        let buffer_id = source.id();
+        let mut moved = false;
+        for entry in self.quickfix.entries_mut() {
+            let Some(anchor) = entry.anchor.as_mut() else {
+                continue;
+            };
+            if anchor.buffer_id != buffer_id {
+                continue;
+            }
+            Self::transform_anchor_for_edit(
+                anchor,
+                edit.start_char,
+                edit.end_char,
+                edit.new_char_len,
+            );
+            let position = source.char_idx_to_position(anchor.char_index);
+            if position != anchor.fallback {
+                anchor.fallback = position;
+                moved = true;
+            }
+        }
+        if moved {
+            self.refresh_quickfix_panels();
+        }
+    }
+
+    fn quickfix_stack(&self, scope: QuickfixScope) -> Option<&QuickfixStack> {
+        match scope {
+            QuickfixScope::Quickfix => Some(&self.quickfix.quickfix),
+            QuickfixScope::Location => self
+                .quickfix
+                .locations
+                .get(&self.quickfix_location_window()?),
+        }
+    }
+
+    fn quickfix_stack_mut(&mut self, scope: QuickfixScope) -> Option<&mut QuickfixStack> {
+        match scope {
+            QuickfixScope::Quickfix => Some(&mut self.quickfix.quickfix),
+            QuickfixScope::Location => {
+                let window = self.quickfix_location_window()?;
+                self.quickfix.locations.get_mut(&window)
+            }
+        }
+    }
+
+    /// The window whose location list the `:l` commands use: the current one.
+    fn quickfix_location_window(&self) -> Option<WindowId> {
+        self.window_manager.active_stable_window_id()
+    }
+
+    fn quickfix_list(&self, scope: QuickfixScope) -> anyhow::Result<&QuickfixList> {
+        self.quickfix_stack(scope)
+            .and_then(QuickfixStack::list)
+            .ok_or_else(|| anyhow::anyhow!("no {}", scope.noun()))
+    }
+
+    async fn jump_to_quickfix_entry(
+        &mut self,
+        scope: QuickfixScope,
+        motion: QuickfixMotion,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<bool> {
+        let index = match self
+            .quickfix_list(scope)
+            .and_then(|list| list.index(motion))
+        {
+            Ok(index) => index,
+            Err(error) => {
+                self.set_legacy_message(Some(error.to_string()));
+                self.render(buffer)?;
+                return Ok(false);
+            }
+        };
+        self.open_quickfix_entry(scope, index, buffer, runtime)
+            .await
+    }
+
+    /// Opens entry `index` and shows its text. Returns `true` when opening it quit
+    /// the editor.
+    async fn open_quickfix_entry(
+        &mut self,
+        scope: QuickfixScope,
+        index: usize,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<bool> {
+        self.set_legacy_message(None);
+        if self
+            .visit_quickfix_entry(scope, index, buffer, runtime)
+            .await?
+        {
+            return Ok(true);
+        }
+        // `OpenLocation` reports a file it cannot open.
+        if self.last_error.is_none() {
+            if let Some(list) = self.quickfix_stack(scope).and_then(QuickfixStack::list) {
+                let text = list.entries[index].text.clone();
+                let count = list.entries.len();
+                self.set_legacy_message(Some(format!("({} of {count}) {text}", index + 1)));
+            }
+        }
+        self.render(buffer)?;
+        Ok(false)
+    }
+
+    /// Makes entry `index` current and opens it, leaving any error in the message.
+    /// Returns `true` when opening it quit the editor.
+    pub(super) async fn visit_quickfix_entry(
+        &mut self,
+        scope: QuickfixScope,
+        index: usize,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<bool> {
+        let Some(list) = self
+            .quickfix_stack_mut(scope)
+            .and_then(QuickfixStack::list_mut)
+        else {
+            return Ok(false);
+        };
+        if index >= list.entries.len() {
+            return Ok(false);
+        }
+        list.current = index;
+        let Some(entry) = self
+            .quickfix_stack(scope)
+            .and_then(QuickfixStack::list)
+            .and_then(|list| list.entries.get(index))
+        else {
+            return Ok(false);
+        };
+        let live_buffer = entry.anchor.as_ref().and_then(|anchor| {
+            self.buffer_manager
+                .iter()
+                .find(|source| source.id() == anchor.buffer_id)
+                .map(|source| (source, anchor.char_index))
+        });
+        let location = match live_buffer {
+            Some((source, char_index)) => {
+                let position = source.char_idx_to_position(char_index);
+                let line = source.get(position.line).unwrap_or_default();
+                plugin::PluginLocation {
+                    path: source.file.clone().unwrap_or_else(|| entry.file.clone()),
+                    line: position.line,
+                    column: crate::unicode_utils::char_to_byte(&line, position.character),
+                    column_encoding: plugin::LocationColumnEncoding::Utf8Byte,
+                }
+            }
+            None => plugin::PluginLocation {
+                path: entry.file.clone(),
+                ..entry.location.clone()
+            },
+        };
+
+        // Open the file and place the cursor here rather than through `OpenLocation`,
+        // which nests another action under this one.
+        let origin = self.current_jump_entry();
+        if self
+            .execute_ex_action(&Action::OpenFile(location.path.clone()), buffer, runtime)
+            .await?
+        {
+            return Ok(true);
+        }
+        if self.last_error.is_some() {
+            return Ok(false);
+        }
+        let line = location
+            .line
+            .min(self.current_buffer().len().saturating_sub(1));
+        let text = self.current_buffer().get(line).unwrap_or_default();
+        let text = text.trim_end_matches(['\n', '\r']);
+        let column = match location.column_encoding {
+            plugin::LocationColumnEncoding::Utf8Byte => {
+                crate::unicode_utils::byte_to_grapheme(text, location.column)
+            }
+            plugin::LocationColumnEncoding::Utf16 => utf16_to_grapheme(text, location.column),
+        };
+        self.go_to_line(line + 1, buffer, runtime, GoToLinePosition::Center)
+            .await?;
+        self.cx = column.min(self.line_length().saturating_sub(1));
+        self.check_bounds();
+        self.save_jump_after_action(
+            origin,
+            &Action::OpenLocation(location, plugin::OpenLocationTarget::Current),
+        );
+        self.refresh_quickfix_panel(scope);
+        Ok(false)
+    }
+
+    fn move_quickfix_history(&mut self, scope: QuickfixScope, delta: isize) -> anyhow::Result<()> {
+        let stack = self
+            .quickfix_stack_mut(scope)
+            .ok_or_else(|| anyhow::anyhow!("no {}", scope.noun()))?;
+        stack.move_history(delta, scope)?;
+        let summary = stack
+            .list()
+            .map(|list| list.summary(scope, stack.current + 1, stack.lists.len()))
+            .unwrap_or_default();
+        self.set_legacy_message(Some(summary));
+        self.refresh_quickfix_panel(scope);
+        Ok(())
+    }
+
+    /// Shows every entry of the list in the messages view.
+    fn list_quickfix(&mut self, scope: QuickfixScope) -> anyhow::Result<()> {
+        let list = self.quickfix_list(scope)?;
+        let stack = self.quickfix_stack(scope).expect("list has a stack");
+        let summary = list.summary(scope, stack.current + 1, stack.lists.len());
+        let details = list
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let marker = if index == list.current { '>' } else { ' ' };
+                format!("{marker}{:>3} {}: {}", index + 1, entry.label(), entry.text)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.set_message_with_details(Severity::Info, summary, &details);
+        Ok(())
+    }
+
+    fn open_quickfix_panel(&mut self, scope: QuickfixScope) -> anyhow::Result<()> {
+        self.quickfix_list(scope)?;
+        let id = scope.panel_id();
+        if scope == QuickfixScope::Location {
+            self.quickfix.location_panel_window = self.quickfix_location_window();
+        }
+        if self.panel_manager.panel_layout(id).is_none() {
+            self.clear_replaced_panel_zoom(id);
+            self.panel_manager.create_panel(
+                id.to_string(),
+                plugin::PanelConfig {
+                    side: plugin::PanelSide::Bottom,
+                    width: QUICKFIX_PANEL_HEIGHT,
+                    ..plugin::PanelConfig::default()
+                },
+            );
+            self.restore_panel_layout(id);
+            self.panel_manager.apply_pending_shell_restore(id);
+            self.apply_panel_layout();
+        }
+        self.refresh_quickfix_panel(scope);
+        self.panel_manager.focus_panel(id);
+        Ok(())
+    }
+
+    fn close_quickfix_panel(&mut self, scope: QuickfixScope) {
+        let id = scope.panel_id();
+        if self.panel_manager.panel_layout(id).is_some() {
+            self.panel_manager.close_panel(id);
+            self.apply_panel_layout();
+        }
+        if scope == QuickfixScope::Location {
+            self.quickfix.location_panel_window = None;
+        }
+    }
+
+    fn refresh_quickfix_panels(&mut self) {
+        self.refresh_quickfix_panel(QuickfixScope::Quickfix);
+        self.refresh_quickfix_panel(QuickfixScope::Location);
+    }
+
+    /// Redraws an open list panel from its list, selecting the current entry.
+    fn refresh_quickfix_panel(&mut self, scope: QuickfixScope) {
+        let id = scope.panel_id();
+        if self.panel_manager.panel_layout(id).is_none() {
+            return;
+        }
+        let list = match scope {
+            QuickfixScope::Quickfix => self.quickfix.quickfix.list(),
+            QuickfixScope::Location => self
+                .quickfix
+                .location_panel_window
+                .and_then(|window| self.quickfix.locations.get(&window))
+                .and_then(QuickfixStack::list),
+        };
+        let (title, rows, current) = match list {
+            Some(list) if !list.title.is_empty() => (
+                format!("{}: {}", scope.panel_title(), list.title),
+                quickfix_panel_rows(list),
+                list.current,
+            ),
+            Some(list) => (
+                scope.panel_title().to_string(),
+                quickfix_panel_rows(list),
+                list.current,
+            ),
+            None => (scope.panel_title().to_string(), Vec::new(), 0),
+        };
+        self.panel_manager.set_panel_title(id, Some(title));
+        self.panel_manager.update_panel(id, rows);
+        self.panel_manager.select_row_by_id(
+            id,
+            &current.to_string(),
+            usize::from(self.size.1.saturating_sub(2)),
+        );
+    }
+
+    /// Opens the entry activated in a list panel, or closes the panel. Returns `true`
+    /// when opening the entry quit the editor. Boxed because plugin notifications
+    /// arrive on the main action frame.
+    #[inline(never)]
+    pub(super) fn handle_quickfix_panel_event<'a>(
+        &'a mut self,
+        scope: QuickfixScope,
+        params: &'a Value,
+        buffer: &'a mut RenderBuffer,
+        runtime: &'a mut Runtime,
+    ) -> BoxFuture<'a, anyhow::Result<bool>> {
+        Box::pin(async move {
+            let Ok(event) = serde_json::from_value::<QuickfixPanelEvent>(params.clone()) else {
+                return Ok(false);
+            };
+            match event.action.as_str() {
+                "close" => {
+                    self.close_quickfix_panel(scope);
+                    self.render(buffer)?;
+                }
+                "activate" => {
+                    let Some(index) = event.row.and_then(|row| row.id.parse::<usize>().ok()) else {
+                        return Ok(false);
+                    };
+                    self.panel_manager.focus_editor();
+                    if scope == QuickfixScope::Location {
+                        // Jump in the window the panel lists, which may not be current.
+                        let owner = self
+                            .quickfix
+                            .location_panel_window
+                            .and_then(|window| self.window_manager.window_index(window));
+                        match owner {
+                            Some(owner) => {
+                                self.set_active_window(owner);
+                            }
+                            None => {
+                                self.set_legacy_message(Some(
+                                    "the window of this location list is closed".to_string(),
+                                ));
+                                self.render(buffer)?;
+                                return Ok(false);
+                            }
+                        }
+                    }
+                    return self
+                        .open_quickfix_entry(scope, index, buffer, runtime)
+                        .await;
+                }
+                _ => {}
+            }
+            Ok(false)
+        })
+    }
+
+    /// Number of entries in the quickfix list, for `:cdo`.
+    pub(super) fn quickfix_entry_count(&self) -> usize {
+        self.quickfix
+            .quickfix
+            .list()
+            .map_or(0, |list| list.entries.len())
+    }
+
+    /// `file:line:col` of quickfix entry `index`, for `:cdo`'s report.
+    pub(super) fn quickfix_entry_label(&self, index: usize) -> Option<String> {
+        self.quickfix
+            .quickfix
+            .list()
+            .and_then(|list| list.entries.get(index))
+            .map(QuickfixEntry::label)
+    }
+}
+
+#[derive(Deserialize)]
+struct QuickfixPanelEvent {
+    action: String,
+    row: Option<QuickfixPanelRow>,
+}
+
+#[derive(Deserialize)]
+struct QuickfixPanelRow {
+    id: String,
+}
+
+/// An anchor in `source` at the entry's last known position.
+fn quickfix_anchor(source: &Buffer, entry: &QuickfixEntry) -> EditAnchor {
+    let position = match &entry.anchor {
+        Some(anchor) => anchor.fallback,
+        None => {
+            let location = &entry.location;
+            let line = location.line.min(source.len().saturating_sub(1));
+            let text = source.get(line).unwrap_or_default();
+            let text = text.trim_end_matches(['\n', '\r']);
+            let character = match location.column_encoding {
+                plugin::LocationColumnEncoding::Utf8Byte => {
+                    crate::unicode_utils::byte_to_char(text, location.column)
+                }
+                plugin::LocationColumnEncoding::Utf16 => utf16_to_char(text, location.column),
+            };
+            TextPosition::new(line, character)
+        }
+    };
+    let char_index = source.position_to_char_idx(position);
+    EditAnchor {
+        buffer_id: source.id(),
+        file: source.file.clone(),
+        char_index,
+        fallback: source.char_idx_to_position(char_index),
+        // Text inserted at an entry lands after it, so the entry keeps pointing at
+        // the start of what it reported.
+        affinity: AnchorAffinity::Left,
+    }
+}
+
+fn utf16_to_char(line: &str, utf16_offset: usize) -> usize {
+    let mut units = 0;
+    line.chars()
+        .take_while(|character| {
+            units += character.len_utf16();
+            units <= utf16_offset
+        })
+        .count()
+}
+
+fn quickfix_panel_rows(list: &QuickfixList) -> Vec<plugin::PanelRow> {
+    let semantic = |keys: &[&str]| {
+        Some(crate::theme::ThemeStyleSpec {
+            foreground: keys.iter().map(ToString::to_string).collect(),
+            ..crate::theme::ThemeStyleSpec::default()
+        })
+    };
+    list.entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let (line, column) = entry.position();
+            plugin::PanelRow {
+                id: index.to_string(),
+                path: Some(entry.file.clone()),
+                expanded: None,
+                kind: plugin::PanelRowKind::File,
+                segments: vec![
+                    plugin::PanelSegment {
+                        text: entry.display_file(),
+                        style: None,
+                        semantic: semantic(&["textLink.foreground"]),
+                    },
+                    plugin::PanelSegment {
+                        text: format!(":{}:{} ", line + 1, column + 1),
+                        style: None,
+                        semantic: semantic(&["descriptionForeground"]),
+                    },
+                    plugin::PanelSegment {
+                        text: entry.text.clone(),
+                        style: None,
+                        semantic: None,
+                    },
+                ],
+                right_segments: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(title: &str, entries: usize) -> QuickfixList {
+        QuickfixList {
+            title: title.to_string(),
+            entries: (0..entries)
+                .map(|line| QuickfixEntry {
+                    file: "a.rs".to_string(),
+                    location: plugin::PluginLocation {
+                        path: "a.rs".to_string(),
+                        line,
+                        column: 0,
+                        column_encoding: plugin::LocationColumnEncoding::Utf8Byte,
+                    },
+                    anchor: None,
+                    text: String::new(),
+                })
+                .collect(),
+            current: 0,
+        }
+    }
+
+    #[test]
+    fn history_keeps_ten_lists_and_drops_newer_ones_on_push() {
+        let mut stack = QuickfixStack::default();
+        for index in 0..12 {
+            stack.push(list(&index.to_string(), 1));
+        }
+        assert_eq!(stack.lists.len(), QUICKFIX_HISTORY);
+        assert_eq!(stack.list().unwrap().title, "11");
+        assert_eq!(
+            stack
+                .move_history(1, QuickfixScope::Quickfix)
+                .unwrap_err()
+                .to_string(),
+            "at top of quickfix list stack"
+        );
+
+        stack.move_history(-3, QuickfixScope::Quickfix).unwrap();
+        assert_eq!(stack.list().unwrap().title, "8");
+        stack.push(list("new", 1));
+        assert_eq!(
+            stack
+                .lists
+                .iter()
+                .map(|list| list.title.as_str())
+                .collect::<Vec<_>>(),
+            ["2", "3", "4", "5", "6", "7", "8", "new"]
+        );
+        stack.move_history(-20, QuickfixScope::Quickfix).unwrap();
+        assert_eq!(stack.list().unwrap().title, "2");
+    }
+
+    #[test]
+    fn motions_stop_at_the_ends_of_the_list() {
+        let mut list = list("", 5);
+        assert_eq!(list.index(QuickfixMotion::Next(2)).unwrap(), 2);
+        assert_eq!(
+            list.index(QuickfixMotion::Previous(1))
+                .unwrap_err()
+                .to_string(),
+            "no more items"
+        );
+        list.current = 3;
+        assert_eq!(list.index(QuickfixMotion::Next(9)).unwrap(), 4);
+        assert_eq!(list.index(QuickfixMotion::Entry(Some(9))).unwrap(), 4);
+        assert_eq!(list.index(QuickfixMotion::Entry(None)).unwrap(), 3);
+        list.current = 4;
+        assert!(list.index(QuickfixMotion::Next(1)).is_err());
+        assert_eq!(list.index(QuickfixMotion::First).unwrap(), 0);
+    }
+}
@@ -0,0 +1,388 @@
+//! Vim text registers selected with the `"{register}` prefix.
+//!
+//! Storage is the editor's register map, shared with macros so `qa` and `"ay` address
+//! the same slot. Yanks and deletes route through [`Editor::store_yank`] and
+//! [`Editor::store_delete`], which apply Vim's numbered history, small-delete, black
+//! hole, append, and clipboard rules. The read-only registers are either derived from
+//! live editor state (`%`, `/`) or recorded at their producing boundary (`.`, `:`).
+
+use super::*;
+
+pub(super) const BLACK_HOLE_REGISTER: char = '_';
+pub(super) const SMALL_DELETE_REGISTER: char = '-';
+pub(super) const LAST_YANK_REGISTER: char = '0';
+pub(super) const LAST_INSERT_REGISTER: char = '.';
+pub(super) const LAST_COMMAND_REGISTER: char = ':';
+pub(super) const LAST_SEARCH_REGISTER: char = '/';
+pub(super) const FILE_NAME_REGISTER: char = '%';
+
+/// Whether `register` may follow the `"` prefix.
+pub(super) fn is_valid_text_register(register: char) -> bool {
+    register.is_ascii_alphanumeric()
+        || matches!(
+            register,
+            DEFAULT_REGISTER
+                | BLACK_HOLE_REGISTER
+                | SMALL_DELETE_REGISTER
+                | '+'
+                | '*'
+                | LAST_INSERT_REGISTER
+                | LAST_COMMAND_REGISTER
+                | LAST_SEARCH_REGISTER
+                | FILE_NAME_REGISTER
+        )
+}
+
+pub(super) fn is_read_only_register(register: char) -> bool {
+    matches!(
+        register,
+        LAST_INSERT_REGISTER | LAST_COMMAND_REGISTER | LAST_SEARCH_REGISTER | FILE_NAME_REGISTER
+    )
+}
+
+fn is_clipboard_register(register: char) -> bool {
+    matches!(register, '+' | '*')
+}
+
+/// Appends `addition` the way Vim's uppercase registers do: linewise text on either
+/// side keeps the result linewise and starts the new text on its own line.
+fn appended_content(existing: Option<&Content>, addition: Content) -> Content {
+    let Some(existing) = existing else {
+        return addition;
+    };
+    if existing.kind == ContentKind::Linewise || addition.kind == ContentKind::Linewise {
+        let mut text = existing.text.clone();
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&addition.text);
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        Content::linewise(text)
+    } else {
+        Content {
+            kind: existing.kind.clone(),
+            text: format!("{}{}", existing.text, addition.text),
+        }
+    }
+}
+
+impl Editor {
+    /// Consumes `"` and its register name in Normal and Visual modes.
+    pub(super) fn handle_register_event(&mut self, event: &Event) -> Option<KeyAction> {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press | KeyEventKind::Repeat,
+            ..
+        }) = event
+        else {
+            return None;
+        };
+
+        if self.pending_register_prefix {
+            self.pending_register_prefix = false;
+            self.waiting_command = None;
+            if *code == KeyCode::Esc {
+                self.repeater = None;
+                self.selected_register = None;
+                return Some(KeyAction::None);
+            }
+            let KeyCode::Char(register) = code else {
+                return self.invalid_text_register();
+            };
+            if !matches!(*modifiers, KeyModifiers::NONE | KeyModifiers::SHIFT)
+                || !is_valid_text_register(*register)
+            {
+                return self.invalid_text_register();
+            }
+            self.selected_register = Some(*register);
+            return Some(KeyAction::None);
+        }
+
+        if self.pending_operator.is_some()
+            || self.pending_replace
+            || self.pending_character_motion.is_some()
+            || self.pending_mark_action.is_some()
+            || self.pending_macro_action.is_some()
+            || self.pending_visual_text_object_scope.is_some()
+            || self.waiting_key_action.is_some()
+            || *code != KeyCode::Char('"')
+            || !matches!(*modifiers, KeyModifiers::NONE | KeyModifiers::SHIFT)
+        {
+            return None;
+        }
+        self.pending_register_prefix = true;
+        self.waiting_command = Some("\"".to_string());
+        Some(KeyAction::None)
+    }
+
+    fn invalid_text_register(&mut self) -> Option<KeyAction> {
+        self.pending_register_prefix = false;
+        self.selected_register = None;
+        self.waiting_command = None;
+        self.repeater = None;
+        self.set_legacy_message(Some("invalid register name".to_string()));
+        Some(KeyAction::None)
+    }
+
+    /// Hands a register chosen with `"x` to the command it prefixes.
+    ///
+    /// The register stays active for every repetition of a counted command and is
+    /// replaced once the next complete command resolves.
+    pub(super) fn bind_selected_register(&mut self, action: Option<&KeyAction>) {
+        if matches!(action, None | Some(KeyAction::None | KeyAction::Nested(_))) {
+            return;
+        }
+        self.active_register = self.selected_register.take();
+    }
+
+    pub(super) fn action_writes_register(action: &Action) -> bool {
+        matches!(
+            action,
+            Action::Yank
+                | Action::YankCurrentLine
+                | Action::YankCurrentLines(_)
+                | Action::YankTextRange(_)
+                | Action::YankLinewiseRange(_)
+                | Action::YankToLineEnd(_)
+                | Action::Delete
+                | Action::DeleteCurrentLine
+                | Action::DeleteCurrentLines(_)
+                | Action::DeleteCharAtCursorPos
+                | Action::DeletePreviousChars(_)
+                | Action::DeleteTextRange(_)
+                | Action::DeleteLinewiseRange(_)
+                | Action::DeleteToLineEnd(_)
+                | Action::ChangeTextRange(_)
+                | Action::ChangeLinewiseRange(_)
+                | Action::ChangeCurrentLine
+                | Action::ChangeCurrentLines(_)
+                | Action::ChangeToLineEnd(_)
+                | Action::ChangeCharsAtCursor(_)
+                | Action::ChangeSelection
+        )
+    }
+
+    /// Rejects a yank, delete, or change into a read-only register before it edits.
+    pub(super) fn reject_read_only_register_write(&mut self, action: &Action) -> bool {
+        let Some(register) = self.active_register else {
+            return false;
+        };
+        if !is_read_only_register(register) || !Self::action_writes_register(action) {
+            return false;
+        }
+        self.active_register = None;
+        self.set_legacy_message(Some(format!("register {register} is read-only")));
+        true
+    }
+
+    fn write_clipboard_register(&mut self, register: char, content: Content) {
+        if let Err(error) = self.clipboard.set_text(&content.text) {
+            self.set_legacy_message(Some(format!("clipboard register {register}: {error}")));
+        }
+        self.registers.insert(register, content.clone());
+        // The clipboard already holds this text, so skip the default-register sync.
+        self.registers.insert(DEFAULT_REGISTER, content);
+    }
+
+    /// Stores yanked text in the active register, or in `"0` and the unnamed register.
+    pub(super) fn store_yank(&mut self, content: Content) {
+        match self.active_register {
+            None | Some(DEFAULT_REGISTER) => {
+                self.registers.insert(LAST_YANK_REGISTER, content.clone());
+                self.set_default_register(content);
+            }
+            Some(register) => self.store_in_named_register(register, content),
+        }
+    }
+
+    /// Stores deleted or changed text using Vim's numbered and small-delete history.
+    pub(super) fn store_delete(&mut self, register: Option<char>, content: Content) {
+        if content.text.is_empty() {
+            return;
+        }
+        match register {
+            None | Some(DEFAULT_REGISTER) => {
+                if content.kind == ContentKind::Linewise || content.text.contains('\n') {
+                    for number in (1..9).rev() {
+                        let from = char::from_digit(number, 10).expect("single digit");
+                        let to = char::from_digit(number + 1, 10).expect("single digit");
+                        if let Some(shifted) = self.registers.remove(&from) {
+                            self.registers.insert(to, shifted);
+                        }
+                    }
+                    self.registers.insert('1', content.clone());
+                } else {
+                    self.registers
+                        .insert(SMALL_DELETE_REGISTER, content.clone());
+                }
+                self.set_default_register(content);
+            }
+            Some(register) => self.store_in_named_register(register, content),
+        }
+    }
+
+    fn store_in_named_register(&mut self, register: char, content: Content) {
+        match register {
+            BLACK_HOLE_REGISTER => {}
+            register if is_clipboard_register(register) => {
+                self.write_clipboard_register(register, content);
+            }
+            register if register.is_ascii_uppercase() => {
+                let name = register.to_ascii_lowercase();
+                let combined = appended_content(self.registers.get(&name), content);
+                self.registers.insert(name, combined.clone());
+                self.set_default_register(combined);
+            }
+            register if is_read_only_register(register) => {
+                self.set_legacy_message(Some(format!("register {register} is read-only")));
+            }
+            register => {
+                self.registers.insert(register, content.clone());
+                self.set_default_register(content);
+            }
+        }
+    }
+
+    /// Resolves a register for reading, including derived read-only registers.
+    pub(super) fn read_register(&mut self, register: char) -> Option<Content> {
+        match register {
+            DEFAULT_REGISTER => {
+                self.refresh_default_register_from_system_clipboard();
+                self.registers.get(&DEFAULT_REGISTER).cloned()
+            }
+            BLACK_HOLE_REGISTER => None,
+            register if is_clipboard_register(register) => {
+                let text = match self.clipboard.get_text() {
+                    Ok(text) => text?,
+                    Err(error) => {
+                        log!("failed to read clipboard register {register}: {error}");
+                        return None;
+                    }
+                };
+                // Keep the register kind when the clipboard still holds our own yank.
+                Some(
+                    self.registers
+                        .get(&register)
+                        .filter(|content| content.text == text)
+                        .cloned()
+                        .unwrap_or_else(|| Content::charwise(text)),
+                )
+            }
+            LAST_SEARCH_REGISTER => {
+                (!self.search_term.is_empty()).then(|| Content::charwise(self.search_term.clone()))
+            }
+            FILE_NAME_REGISTER => self.current_file_name().map(Content::charwise),
+            register => self.registers.get(&register.to_ascii_lowercase()).cloned(),
+        }
+    }
+
+    /// Returns the content for a put command, reporting an empty register.
+    pub(super) fn register_content_for_put(&mut self) -> Option<Content> {
+        let register = self.active_register.unwrap_or(DEFAULT_REGISTER);
+        let content = self.read_register(register);
+        if content.is_none() && register != DEFAULT_REGISTER && register != BLACK_HOLE_REGISTER {
+            self.set_legacy_message(Some(format!("register {register} is empty")));
+        }
+        content
+    }
+
+    /// Accumulates the text typed during one insert session for the `.` register.
+    pub(super) fn record_inserted_text(&mut self, action: &Action) {
+        if !self.is_insert() {
+            return;
+        }
+        let text = self.inserted_text.get_or_insert_with(String::new);
+        match action {
+            Action::InsertCharAtCursorPos(character) => text.push(*character),
+            Action::InsertNewLine => text.push('\n'),
+            Action::InsertTab => text.push('\t'),
+            Action::InsertString(inserted) | Action::InsertPastedText(inserted) => {
+                text.push_str(inserted)
+            }
+            Action::DeletePreviousChar => {
+                text.pop();
+            }
+            _ => {}
+        }
+    }
+
+    pub(super) fn finish_inserted_text(&mut self) {
+        if let Some(text) = self.inserted_text.take().filter(|text| !text.is_empty()) {
+            self.registers
+                .insert(LAST_INSERT_REGISTER, Content::charwise(text));
+        }
+    }
+
+    pub(super) fn record_command_register(&mut self, command: &str) {
+        if !command.trim().is_empty() {
+            self.registers.insert(
+                LAST_COMMAND_REGISTER,
+                Content::charwise(command.to_string()),
+            );
+        }
+    }
+
+    /// Advances `"1p` to `"2p` on each dot-repeat, as Vim does for numbered puts.
+    pub(super) fn advance_numbered_register_in_change(events: &mut [Event]) {
+        let Some(position) = events.windows(2).position(|pair| {
+            matches!(
+                pair,
+                [
+                    Event::Key(KeyEvent {
+                        code: KeyCode::Char('"'),
+                        ..
+                    }),
+                    Event::Key(KeyEvent {
+                        code: KeyCode::Char('1'..='8'),
+                        ..
+                    })
+                ]
+            )
+        }) else {
+            return;
+        };
+        if let Event::Key(KeyEvent {
+            code: KeyCode::Char(digit),
+            ..
+        }) = &mut events[position + 1]
+        {
+            *digit = char::from_u32(*digit as u32 + 1).unwrap_or(*digit);
+        }
+    }
+
+    /// Formats `:registers` entries in Vim's register order, each with Vim's `c`, `l`
+    /// or `b` type for characterwise, linewise or blockwise text.
+    pub(super) fn register_listing(&mut self) -> Vec<String> {
+        let mut names = vec![DEFAULT_REGISTER];
+        names.extend('0'..='9');
+        names.extend('a'..='z');
+        names.extend([
+            SMALL_DELETE_REGISTER,
+            '+',
+            '*',
+            LAST_INSERT_REGISTER,
+            LAST_COMMAND_REGISTER,
+            FILE_NAME_REGISTER,
+            LAST_SEARCH_REGISTER,
+        ]);
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let content = match name {
+                    LAST_SEARCH_REGISTER | FILE_NAME_REGISTER => self.read_register(name),
+                    name => self.registers.get(&name).cloned(),
+                }?;
+                let kind = match content.kind {
+                    ContentKind::Charwise => 'c',
+                    ContentKind::Linewise => 'l',
+                    ContentKind::Blockwise => 'b',
+                };
+                let text = content.text.replace('\n', "^J").replace('\t', "^I");
+                Some(format!("{kind} {name}: {text}"))
+            })
+            .collect()
+    }
+}
@@ -1226,6 +1226,14 @@ struct SourceSegmentGeometry {
     tab_width: usize,
 }
 
+/// Vim-style summary drawn in place of a closed fold, padded to `width` cells.
+fn fold_placeholder_text(first_line: &str, line_count: usize, width: usize, fill: char) -> String {
+    let summary = first_line.trim().replace('\t', " ");
+    let text = truncate_display_width(&format!("+--{line_count:>3} lines: {summary}"), width);
+    let padding = width.saturating_sub(display_width(&text));
+    format!("{text}{}", fill.to_string().repeat(padding))
+}
+
 fn render_source_segment(
     buffer: &mut RenderBuffer,
     segment: &LineSegment,
@@ -1513,6 +1521,7 @@ impl Editor {
 
         // Render global UI elements
         let chrome_span = super::perf::PerfSpan::start("render:chrome");
+        self.draw_tab_line(buffer);
         self.draw_statusline(buffer);
         // A modal workspace replaces editor chrome but remains below dialogs
         // and overlays so prompts and transient menus stay interactive.
@@ -1706,6 +1715,7 @@ impl Editor {
             && self.current_dialog.is_none()
             && !self.panel_manager.has_focused_panel()
             && !self.is_visual()
+            && !self.has_secondary_cursors()
             && self.active_search.is_none()
             && (self.search_term.is_empty()
                 || !self.config.search.hlsearch
@@ -1833,6 +1843,7 @@ impl Editor {
         self.render_gutter_rows_in_window(buffer, &window, window_id, &local_rows);
         self.render_main_content_rows_in_window(buffer, &window, &local_rows)?;
         self.render_line_highlight_rows_in_window(buffer, &window, &local_rows);
+        self.render_misspellings_in_window(buffer, &window, Some(terminal_rows));
         self.render_matching_brackets_in_window(buffer, &window, Some(terminal_rows));
 
         Ok(())
@@ -1903,8 +1914,8 @@ impl Editor {
         let gutter_style = self.theme.gutter_style.fallback_bg(&self.theme.style);
         let segment = layout.row(row).filter(|segment| segment.first_segment);
         let cursor_line = window.vtop + window.cy;
-        let is_cursor_line =
-            segment.is_some_and(|segment| segment.line < line_count && segment.line == cursor_line);
+        let is_cursor_line = segment
+            .is_some_and(|segment| segment.line < line_count && segment.shows_line(cursor_line));
         let line_number = segment
             .filter(|segment| segment.line < line_count)
             .map(|segment| {
@@ -1965,18 +1976,19 @@ impl Editor {
         let Some(segment) = segment else {
             return;
         };
-        let Some(sign) = self
+        if let Some(sign) = self
             .gutter_sign_manager
             .visible_sign(window.buffer_index, segment.line)
-        else {
-            return;
-        };
-        buffer.set_text(
-            term_x,
-            term_y,
-            &sign.text,
-            &sign.style.fallback_bg(&gutter_style),
-        );
+        {
+            buffer.set_text(
+                term_x,
+                term_y,
+                &sign.text,
+                &sign.style.fallback_bg(&gutter_style),
+            );
+        } else if let Some(marker) = self.fold_marker_for_segment(window, segment) {
+            buffer.set_text(term_x, term_y, marker, &self.theme.fold_marker_style());
+        }
     }
 
     fn render_inline_comment_connector(
@@ -2032,7 +2044,7 @@ impl Editor {
         let style_info = self.viewport_highlight_spans(
             window.buffer_index,
             window.vtop,
-            self.window_content_height(window),
+            self.layout_source_height(window, &layout),
         )?;
         let theme_style = self.theme.style.clone();
         let mut style_cursor = StyleCursor::new(&style_info);
@@ -2057,6 +2069,10 @@ impl Editor {
                 self.fill_line_in_window(buffer, term_x, term_y, content_width, &theme_style);
                 continue;
             };
+            if segment.folded.is_some() {
+                self.render_fold_placeholder_in_window(buffer, window, segment, term_x, term_y);
+                continue;
+            }
             if cached_line.as_ref().map(|(line, _)| *line) != Some(segment.line) {
                 cached_line = self.buffer_manager[window.buffer_index]
                     .get(segment.line)
@@ -2119,7 +2135,7 @@ impl Editor {
         for segment in layout
             .rows
             .iter()
-            .filter(|segment| segment.line == buffer_y && local_rows.contains(&segment.row))
+            .filter(|segment| segment.shows_line(buffer_y) && local_rows.contains(&segment.row))
         {
             let term_y = self.window_to_terminal_y(window, segment.row);
             buffer.set_bg_for_range(
@@ -2142,7 +2158,7 @@ impl Editor {
         if self.splash_dismissed {
             return false;
         }
-        if !self.config.splash.unwrap_or(true) || self.config.startup_file_count != 0 {
+        if !self.config.splash.unwrap_or(true) || !self.config.startup_files.is_empty() {
             return false;
         }
         let pristine = self.window_manager.windows().len() == 1
@@ -2210,6 +2226,7 @@ impl Editor {
 
             // Render overlays within window bounds
             self.render_overlays_in_window(buffer, &window)?;
+            self.render_secondary_cursors_in_window(buffer, &window);
             self.render_inline_prediction_in_window(buffer, &window)?;
         }
 
@@ -2228,7 +2245,7 @@ impl Editor {
         let spans = self.viewport_highlight_spans(
             window.buffer_index,
             window.vtop,
-            self.window_content_height(window),
+            self.layout_source_height(window, &layout),
         )?;
         let mut styles = StyleCursor::new(&spans);
         let normal = self.theme.style.clone();
@@ -2580,7 +2597,7 @@ impl Editor {
         let style_info = self.viewport_highlight_spans(
             window.buffer_index,
             window.vtop,
-            self.window_content_height(window),
+            self.layout_source_height(window, &layout),
         )?;
         let theme_style = self.theme.style.clone();
         let mut style_cursor = StyleCursor::new(&style_info);
@@ -2597,6 +2614,10 @@ impl Editor {
         for segment in &layout.rows {
             let term_y = self.window_to_terminal_y(window, segment.row);
             let term_x = self.window_to_terminal_x(window, gutter_width + 1);
+            if segment.folded.is_some() {
+                self.render_fold_placeholder_in_window(buffer, window, segment, term_x, term_y);
+                continue;
+            }
             if cached_line.as_ref().map(|(line, _)| *line) != Some(segment.line) {
                 cached_line = self.buffer_manager[window.buffer_index]
                     .get(segment.line)
@@ -2759,6 +2780,33 @@ impl Editor {
     }
 
     /// Fill a line with the given style within window bounds
+    fn render_fold_placeholder_in_window(
+        &self,
+        buffer: &mut RenderBuffer,
+        window: &crate::window::Window,
+        segment: &LineSegment,
+        x: usize,
+        y: usize,
+    ) {
+        let Some(end) = segment.folded else {
+            return;
+        };
+        let first_line = self.buffer_manager[window.buffer_index]
+            .get(segment.line)
+            .unwrap_or_default();
+        let text = fold_placeholder_text(
+            trim_line_ending(&first_line),
+            end + 1 - segment.line,
+            self.window_content_width(window),
+            if self.config.window_borders_ascii {
+                '-'
+            } else {
+                '·'
+            },
+        );
+        buffer.set_text(x, y, &text, &self.theme.folded_style());
+    }
+
     fn fill_line_in_window(
         &mut self,
         buffer: &mut RenderBuffer,
@@ -2810,6 +2858,7 @@ impl Editor {
             }
         }
 
+        self.render_misspellings_in_window(buffer, window, None);
         self.render_search_highlights_in_window(buffer, window)?;
         self.render_matching_brackets_in_window(buffer, window, None);
 
@@ -2865,10 +2914,91 @@ impl Editor {
         Ok(())
     }
 
+    /// Draws the secondary cursors, and their selections in Visual modes, in every
+    /// window showing their buffer.
+    fn render_secondary_cursors_in_window(
+        &self,
+        buffer: &mut RenderBuffer,
+        window: &crate::window::Window,
+    ) {
+        let text = &self.buffer_manager[window.buffer_index];
+        let selections = self.secondary_selections(text.id());
+        if selections.is_empty() {
+            return;
+        }
+        let tab_width = self.tab_width_for_buffer_index(window.buffer_index);
+        let selection_style = self.theme.editor_selection_style();
+        let display_col = |line: &str, character: usize| {
+            display_width_with_tabs(char_prefix(line, character), tab_width)
+        };
+        for selection in selections {
+            if self.is_visual() {
+                let (start, end) = selection.span();
+                let start = text.char_idx_to_position(start);
+                let end = text.char_idx_to_position(end);
+                for line_index in start.line..=end.line {
+                    let line = text.get(line_index).unwrap_or_default();
+                    let line = trim_line_ending(&line);
+                    let length = line.chars().count();
+                    let (from, to) = match self.mode {
+                        Mode::VisualLine => (0, length),
+                        _ => (
+                            if line_index == start.line {
+                                start.character
+                            } else {
+                                0
+                            },
+                            if line_index == end.line {
+                                end.character.saturating_add(1).min(length)
+                            } else {
+                                length
+                            },
+                        ),
+                    };
+                    let start_col = display_col(line, from);
+                    let end_col = display_col(line, to).max(start_col + 1);
+                    let points = self
+                        .display_col_range_points_in_window(window, line_index, start_col, end_col);
+                    buffer.apply_selection_for_points(
+                        points,
+                        &selection_style,
+                        &self.theme,
+                        SelectionForegroundPriority::Selection,
+                    );
+                }
+            }
+
+            let head = text.char_idx_to_position(selection.head);
+            let line = text.get(head.line).unwrap_or_default();
+            let col = display_col(trim_line_ending(&line), head.character);
+            let mut points =
+                self.display_col_range_points_in_window(window, head.line, col, col + 1);
+            if points.is_empty() && col > 0 {
+                // A cursor after the last character sits one cell past the text.
+                points = self
+                    .display_col_range_points_in_window(window, head.line, col - 1, col)
+                    .into_iter()
+                    .map(|point| Point::new(point.x + 1, point.y))
+                    .collect();
+            }
+            for point in points {
+                if let Some(cell) = buffer.cells.get_mut(point.y * buffer.width + point.x) {
+                    cell.style = self.theme.synthetic_cursor_style(&cell.style);
+                }
+            }
+        }
+    }
+
     pub(crate) fn matching_bracket_positions(&mut self) -> Option<[TextPosition; 2]> {
         if !matches!(
             self.mode,
-            Mode::Normal | Mode::Insert | Mode::Visual | Mode::VisualLine | Mode::VisualBlock
+            Mode::Normal
+                | Mode::Insert
+                | Mode::Replace
+                | Mode::VirtualReplace
+                | Mode::Visual
+                | Mode::VisualLine
+                | Mode::VisualBlock
         ) {
             return None;
         }
@@ -3168,6 +3298,19 @@ impl Editor {
         }
 
         let layout = self.layout_for_window(window);
+        self.display_col_range_points_in_layout(window, &layout, line_index, start_col, end_col)
+    }
+
+    /// Like [`Self::display_col_range_points_in_window`] for a layout the caller
+    /// already holds, so decorating many ranges lays the window out once.
+    pub(super) fn display_col_range_points_in_layout(
+        &self,
+        window: &crate::window::Window,
+        layout: &DisplayLayout,
+        line_index: usize,
+        start_col: usize,
+        end_col: usize,
+    ) -> Vec<Point> {
         let gutter_width = self.gutter_width_for_window(window);
         let content_start = gutter_width + 1;
         let content_width = self.window_content_width(window);
@@ -3326,6 +3469,7 @@ impl Editor {
         };
 
         let mut cells = Vec::new();
+        let block = (self.mode == Mode::VisualBlock).then(|| self.block_columns(*selection));
 
         for y in selection.y0..=selection.y1 {
             let (start_x, end_x) = match self.mode {
@@ -3341,7 +3485,14 @@ impl Editor {
                     }
                 }
                 Mode::VisualLine => (0, self.last_cell_for_line(y)),
-                Mode::VisualBlock => (selection.x0, selection.x1),
+                Mode::VisualBlock => {
+                    let columns = block.expect("block selections have columns");
+                    match self.block_row_graphemes(y, columns) {
+                        Some((start, end)) => (start, end.saturating_sub(1)),
+                        None if columns.start == 0 => (0, 0),
+                        None => continue,
+                    }
+                }
                 _ => unreachable!(),
             };
 
@@ -3366,6 +3517,8 @@ impl Editor {
         // Don't render global gutter - each window renders its own gutter
         // self.render_gutter(buffer)?;
 
+        self.draw_tab_line(buffer);
+
         // Render status line
         self.draw_statusline(buffer);
 
@@ -4082,11 +4235,13 @@ impl Editor {
                 let layout = self.layout_for_window(window);
                 let segment = layout.segment_for_cursor(buffer_y, display_col)?;
                 let gutter_width = self.gutter_width_for_window(window);
-                let term_x = window.position.x
-                    + gutter_width
-                    + 1
-                    + segment
-                        .screen_col_for_display_col(display_col, self.window_content_width(window));
+                let cursor_col = if segment.folded.is_some() {
+                    0
+                } else {
+                    segment
+                        .screen_col_for_display_col(display_col, self.window_content_width(window))
+                };
+                let term_x = window.position.x + gutter_width + 1 + cursor_col;
                 let term_y = self.window_to_terminal_y(window, segment.row);
                 Some((term_x, term_y))
             } else {
@@ -4147,6 +4302,7 @@ impl Editor {
             Mode::Normal => self.config.cursor.normal,
             Mode::Command => self.config.cursor.command,
             Mode::Insert => self.config.cursor.insert,
+            Mode::Replace | Mode::VirtualReplace => self.config.cursor.replace,
             Mode::Search => self.config.cursor.search,
             Mode::Visual => self.config.cursor.visual,
             Mode::VisualLine => self.config.cursor.visual_line,
@@ -4163,6 +4319,8 @@ fn format_mode_name(mode: &Mode) -> String {
     match mode {
         Mode::Normal => "NORMAL".to_string(),
         Mode::Insert => "INSERT".to_string(),
+        Mode::Replace => "REPLACE".to_string(),
+        Mode::VirtualReplace => "V-REPLACE".to_string(),
         Mode::Command => "COMMAND".to_string(),
         Mode::Search => "SEARCH".to_string(),
         Mode::Visual => "VISUAL".to_string(),
@@ -4193,6 +4351,18 @@ mod tests {
         editor
     }
 
+    #[test]
+    fn fold_placeholder_summarizes_the_first_line_and_fills_the_row() {
+        assert_eq!(
+            fold_placeholder_text("\tfn main() {", 12, 30, '-'),
+            "+-- 12 lines: fn main() {-----"
+        );
+        assert_eq!(
+            fold_placeholder_text("let value = 1;", 3, 12, '·'),
+            "+--  3 lines"
+        );
+    }
+
     #[test]
     fn empty_overlays_defer_positioning_until_content_becomes_visible() {
         let mut editor = rendering_test_editor(Buffer::new(None, "source\n".into()));
@@ -4562,6 +4732,7 @@ mod tests {
             first_segment,
             last_segment: true,
             visual_offset: 0,
+            folded: None,
         }
     }
 
@@ -0,0 +1,231 @@
+//! Replace (`R`) and virtual Replace (`gR`) modes.
+//!
+//! Both are insert-family modes: they share the insert keymap and the insert
+//! transaction, so the whole session is one undo step and dot-repeat and macros see
+//! ordinary key events. Typed text overwrites instead of inserting, and every typed
+//! grapheme records the text it displaced so Backspace can put the original back.
+//! Virtual Replace overwrites screen cells rather than graphemes: a tab is only
+//! replaced once typed text fills it, and a partly covered wide character leaves
+//! spaces behind so later text keeps its columns. A count replays the typed text when
+//! the mode ends.
+
+use super::*;
+
+/// One typed grapheme and the text it displaced.
+#[derive(Debug, Clone)]
+struct Overwrite {
+    start: TextPosition,
+    /// End of the typed text in the buffer.
+    end: TextPosition,
+    /// Cursor after typing; Backspace only restores when the cursor is still here.
+    cursor: TextPosition,
+    typed: String,
+    original: String,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct ReplaceSession {
+    count: u16,
+    overwritten: Vec<Overwrite>,
+}
+
+impl Editor {
+    pub(super) fn start_replace_session(&mut self, count: u16) {
+        self.replace_session = Some(ReplaceSession {
+            count: count.max(1),
+            overwritten: Vec::new(),
+        });
+    }
+
+    /// Ends the session, replaying the typed text for a count. Returns whether the
+    /// replay edited the buffer.
+    pub(super) fn finish_replace_session(&mut self) -> bool {
+        let Some(session) = self.replace_session.take() else {
+            return false;
+        };
+        let typed = session
+            .overwritten
+            .iter()
+            .map(|overwrite| overwrite.typed.as_str())
+            .collect::<String>();
+        if typed.is_empty() || session.count <= 1 {
+            return false;
+        }
+        for _ in 1..session.count {
+            for grapheme in typed.graphemes(true) {
+                self.replace_mode_type(grapheme);
+            }
+        }
+        true
+    }
+
+    /// Runs an insert action in Replace mode. Returns whether the buffer changed.
+    pub(super) fn execute_replace_mode_action(&mut self, action: &Action) -> bool {
+        let text = match action {
+            Action::InsertCharAtCursorPos(c) => c.to_string(),
+            Action::InsertPastedText(text) => text.clone(),
+            Action::InsertNewLine => "\n".to_string(),
+            Action::InsertTab => self.replace_mode_tab(),
+            Action::DeletePreviousChar => return self.replace_mode_backspace(),
+            _ => return false,
+        };
+        let mut edited = false;
+        for grapheme in text.graphemes(true) {
+            let overwrite = self.replace_mode_type(grapheme);
+            edited = true;
+            if let Some(session) = self.replace_session.as_mut() {
+                session.overwritten.push(overwrite);
+            }
+        }
+        edited
+    }
+
+    /// Tab text as `InsertTab` would insert it at the cursor.
+    fn replace_mode_tab(&self) -> String {
+        let indentation = self.indentation();
+        if !indentation.expand_tab {
+            return "\t".to_string();
+        }
+        let contents = self.current_line_contents().unwrap_or_default();
+        let contents = trim_line_ending(&contents);
+        let column = grapheme_to_column_with_tabs(contents, self.cx, indentation.tab_width.max(1));
+        let stop = indentation.soft_tab_stop.max(1);
+        " ".repeat((column / stop + 1) * stop - column)
+    }
+
+    /// Overwrites text at the cursor with one typed grapheme and moves past it.
+    fn replace_mode_type(&mut self, grapheme: &str) -> Overwrite {
+        let line = self.buffer_line();
+        let contents = self.current_line_contents().unwrap_or_default();
+        let contents = trim_line_ending(&contents);
+        let cx = self.cx.min(grapheme_len(contents));
+        let start_char = grapheme_to_char(contents, cx);
+        let start = TextPosition::new(line, start_char);
+        let virtual_replace = matches!(self.mode, Mode::VirtualReplace);
+
+        let (replaced_chars, inserted) = if grapheme == "\n" || grapheme == "\r\n" {
+            // Virtual Replace keeps later lines in place: the line break replaces the
+            // rest of the line and replacing continues on the next one.
+            if virtual_replace && line + 1 < self.current_buffer().len() {
+                let rest = contents.chars().count() - start_char;
+                let original = char_suffix(contents, start_char).to_string();
+                self.replace_range(
+                    TextRange::new(start, TextPosition::new(line, start_char + rest)),
+                    "",
+                );
+                let cursor = TextPosition::new(line + 1, 0);
+                self.move_to_insert_text_position(cursor);
+                return Overwrite {
+                    start,
+                    end: start,
+                    cursor,
+                    typed: "\n".to_string(),
+                    original,
+                };
+            }
+            let replaced = if virtual_replace {
+                contents.chars().count() - start_char
+            } else {
+                0
+            };
+            (replaced, "\n".to_string())
+        } else if virtual_replace {
+            self.virtual_replace_span(contents, cx, grapheme)
+        } else {
+            let replaced = contents
+                .graphemes(true)
+                .nth(cx)
+                .map_or(0, |existing| existing.chars().count());
+            (replaced, grapheme.to_string())
+        };
+
+        let original = char_slice(contents, start_char, start_char + replaced_chars).to_string();
+        self.replace_range(
+            TextRange::new(start, TextPosition::new(line, start_char + replaced_chars)),
+            &inserted,
+        );
+        let end = if inserted == "\n" {
+            TextPosition::new(line + 1, 0)
+        } else {
+            TextPosition::new(line, start_char + inserted.chars().count())
+        };
+        let cursor = if inserted == "\n" {
+            end
+        } else {
+            TextPosition::new(line, start_char + grapheme.chars().count())
+        };
+        self.move_to_insert_text_position(cursor);
+        Overwrite {
+            start,
+            end,
+            cursor,
+            typed: grapheme.to_string(),
+            original,
+        }
+    }
+
+    /// Characters virtual Replace overwrites at grapheme `cx` and the text replacing
+    /// them, padded with spaces where a wide character is only partly covered.
+    fn virtual_replace_span(&self, contents: &str, cx: usize, grapheme: &str) -> (usize, String) {
+        let tab_width = self.indentation().tab_width.max(1);
+        let cell_width = |text: &str, column: usize| {
+            if text == "\t" {
+                tab_width - column % tab_width
+            } else {
+                display_width(text)
+            }
+        };
+        let start_column = grapheme_to_column_with_tabs(contents, cx, tab_width);
+        let target = start_column + cell_width(grapheme, start_column);
+        let mut column = start_column;
+        let mut replaced = 0;
+        let mut inserted = grapheme.to_string();
+        for existing in contents.graphemes(true).skip(cx) {
+            if column >= target {
+                break;
+            }
+            let end = column + cell_width(existing, column);
+            if existing == "\t" && end > target {
+                // The tab shrinks to the next tab stop instead of being replaced.
+                break;
+            }
+            replaced += existing.chars().count();
+            if end > target {
+                inserted.push_str(&" ".repeat(end - target));
+                break;
+            }
+            column = end;
+        }
+        (replaced, inserted)
+    }
+
+    /// Restores the text displaced by the last typed grapheme, or moves left past text
+    /// that was not typed in this session.
+    fn replace_mode_backspace(&mut self) -> bool {
+        let cursor = self.cursor_text_position();
+        let overwrite = self.replace_session.as_mut().and_then(|session| {
+            if session
+                .overwritten
+                .last()
+                .is_some_and(|overwrite| overwrite.cursor == cursor)
+            {
+                session.overwritten.pop()
+            } else {
+                // The cursor moved away; what was typed before can no longer be undone
+                // character by character.
+                session.overwritten.clear();
+                None
+            }
+        });
+        let Some(overwrite) = overwrite else {
+            self.cx = self.cx.saturating_sub(1);
+            return false;
+        };
+        self.replace_range(
+            TextRange::new(overwrite.start, overwrite.end),
+            &overwrite.original,
+        );
+        self.move_to_insert_text_position(overwrite.start);
+        true
+    }
+}
@@ -0,0 +1,511 @@
+//! Spell checking: the misspelled-word underline, `]s`/`[s`, `z=`, `zg`, `zw`,
+//! and `:set spell spelllang=…`.
+//!
+//! Dictionaries are Hunspell `.aff`/`.dic` pairs read from the spell directory;
+//! none ship with Red. Source code is checked only inside the comment and string
+//! captures of its highlight query, while Markdown, commit messages, and plain
+//! text are checked throughout. Those captures come from parsing the whole buffer,
+//! so a comment or string opening above the checked lines is still seen. The
+//! misspellings around a window's viewport are cached per buffer until the
+//! buffer, the dictionaries, or a word list changes.
+
+use super::*;
+use crate::spell::{spell_words, SpellChecker, MAX_SUGGESTIONS};
+use std::ops::Range;
+
+/// Lines checked at a time when `]s`/`[s` search a buffer too large to check in
+/// one pass.
+const SPELL_SCAN_LINES: usize = 1000;
+
+/// Largest source buffer parsed for the comments and strings spell checking
+/// looks at; larger ones are not checked.
+const SPELL_PARSE_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Default)]
+pub(super) struct SpellState {
+    /// Holds the dictionaries and word lists; unset until startup names it.
+    directory: Option<PathBuf>,
+    /// The checker for the `spelllang` it was loaded for, or why loading failed.
+    checker: Option<(Vec<String>, Result<SpellChecker, String>)>,
+    /// Bumped whenever the checker or a word list changes.
+    generation: u64,
+    viewport: HashMap<usize, SpellViewportEntry>,
+    scopes: HashMap<usize, SpellScopesEntry>,
+}
+
+/// The byte ranges of a whole buffer that spell checking looks at.
+#[derive(Debug)]
+struct SpellScopesEntry {
+    buffer_id: BufferId,
+    revision: u64,
+    language_id: String,
+    ranges: Vec<Range<usize>>,
+}
+
+#[derive(Debug)]
+struct SpellViewportEntry {
+    buffer_id: BufferId,
+    revision: u64,
+    generation: u64,
+    language_id: Option<String>,
+    start_line: usize,
+    end_line: usize,
+    misspellings: Vec<Misspelling>,
+}
+
+/// A misspelled word as a character range of one buffer line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Misspelling {
+    line: usize,
+    start: usize,
+    end: usize,
+}
+
+impl Editor {
+    /// Reads dictionaries and word lists from `directory`.
+    pub fn set_spell_directory(&mut self, directory: PathBuf) {
+        self.spell.directory = Some(directory);
+        self.spell.checker = None;
+        self.spell.viewport.clear();
+    }
+
+    /// Loads the dictionaries named by `spelllang` unless they already are, and
+    /// returns why they could not be. A failed load is retried only once
+    /// `spelllang` changes or spell checking is switched on again.
+    fn load_spell_checker(&mut self) -> Result<(), String> {
+        let languages = &self.config.spell.languages;
+        if self
+            .spell
+            .checker
+            .as_ref()
+            .is_none_or(|(loaded, _)| loaded != languages)
+        {
+            let checker = match &self.spell.directory {
+                Some(directory) => {
+                    SpellChecker::load(directory, languages).map_err(|error| error.to_string())
+                }
+                None => Err("no spell directory".to_string()),
+            };
+            self.spell.checker = Some((languages.clone(), checker));
+            self.spell.generation = self.spell.generation.wrapping_add(1);
+            self.spell.viewport.clear();
+        }
+        match &self.spell.checker {
+            Some((_, Err(error))) => Err(error.clone()),
+            _ => Ok(()),
+        }
+    }
+
+    fn spell_checker(&self) -> Option<&SpellChecker> {
+        self.spell
+            .checker
+            .as_ref()
+            .and_then(|(_, checker)| checker.as_ref().ok())
+    }
+
+    /// Finds the misspelled words on lines `start..end` of a buffer.
+    fn misspellings_in_lines(
+        &mut self,
+        buffer_index: usize,
+        start: usize,
+        end: usize,
+    ) -> Vec<Misspelling> {
+        let language_id = self.highlight_language_id_for_buffer_index(buffer_index);
+        let Some(buffer) = self.buffer_manager.get(buffer_index) else {
+            return Vec::new();
+        };
+        let end = end.min(buffer.len());
+        if start >= end || buffer.line_range_byte_len(start, end) > MAX_HIGHLIGHT_SLICE_BYTES {
+            return Vec::new();
+        }
+        let (text, line_offsets) = buffer.line_range_contents_with_offsets(start, end);
+        let offset = buffer.line_range_byte_len(0, start);
+        let scopes = match language_id.as_deref() {
+            None | Some("gitcommit") => std::iter::once(0..text.len()).collect(),
+            Some(language_id) => {
+                self.buffer_spell_scopes(buffer_index, language_id, offset..offset + text.len())
+            }
+        };
+        let Some(checker) = self.spell_checker() else {
+            return Vec::new();
+        };
+
+        let mut misspellings = Vec::new();
+        for scope in scopes {
+            for word in spell_words(&text[scope.clone()]) {
+                let word = scope.start + word.start..scope.start + word.end;
+                if checker.check(&text[word.clone()]) {
+                    continue;
+                }
+                let line = line_offsets.partition_point(|offset| *offset <= word.start) - 1;
+                let character = text[line_offsets[line]..word.start].chars().count();
+                misspellings.push(Misspelling {
+                    line: start + line,
+                    start: character,
+                    end: character + text[word].chars().count(),
+                });
+            }
+        }
+        misspellings
+    }
+
+    /// Returns the parts of `range`, a byte range of a buffer, that spell checking
+    /// looks at, relative to its start. The whole buffer is parsed once per
+    /// revision.
+    fn buffer_spell_scopes(
+        &mut self,
+        buffer_index: usize,
+        language_id: &str,
+        range: Range<usize>,
+    ) -> Vec<Range<usize>> {
+        let Some(buffer) = self.buffer_manager.get(buffer_index) else {
+            return Vec::new();
+        };
+        let buffer_id = buffer.id();
+        let revision = buffer.revision();
+        let cached = self.spell.scopes.get(&buffer_index).is_some_and(|entry| {
+            entry.buffer_id == buffer_id
+                && entry.revision == revision
+                && entry.language_id == language_id
+        });
+        if !cached {
+            let text = (buffer.line_range_byte_len(0, buffer.len()) <= SPELL_PARSE_MAX_BYTES)
+                .then(|| buffer.contents());
+            let ranges = match text {
+                Some(text) => self
+                    .highlighter
+                    .spell_ranges(language_id, &text)
+                    .unwrap_or_else(|error| {
+                        log!("spell: failed to find comments and strings: {error}");
+                        Vec::new()
+                    }),
+                None => Vec::new(),
+            };
+            if self.spell.scopes.len() >= 32 {
+                self.spell.scopes.clear();
+            }
+            self.spell.scopes.insert(
+                buffer_index,
+                SpellScopesEntry {
+                    buffer_id,
+                    revision,
+                    language_id: language_id.to_string(),
+                    ranges,
+                },
+            );
+        }
+
+        let ranges = &self.spell.scopes[&buffer_index].ranges;
+        let first = ranges.partition_point(|scope| scope.end <= range.start);
+        ranges[first..]
+            .iter()
+            .take_while(|scope| scope.start < range.end)
+            .map(|scope| {
+                scope.start.max(range.start) - range.start..scope.end.min(range.end) - range.start
+            })
+            .collect()
+    }
+
+    /// Returns the misspellings on lines `start..end`, checking a screenful
+    /// around them when the cached ones are stale or do not cover them.
+    fn viewport_misspellings(
+        &mut self,
+        buffer_index: usize,
+        start: usize,
+        end: usize,
+    ) -> Vec<Misspelling> {
+        if self.load_spell_checker().is_err() {
+            return Vec::new();
+        }
+        let Some(buffer) = self.buffer_manager.get(buffer_index) else {
+            return Vec::new();
+        };
+        let buffer_id = buffer.id();
+        let revision = buffer.revision();
+        let language_id = self.highlight_language_id_for_buffer_index(buffer_index);
+        let generation = self.spell.generation;
+        let covered = self.spell.viewport.get(&buffer_index).is_some_and(|entry| {
+            entry.buffer_id == buffer_id
+                && entry.revision == revision
+                && entry.generation == generation
+                && entry.language_id == language_id
+                && entry.start_line <= start
+                && entry.end_line >= end
+        });
+
+        if !covered {
+            let margin = end - start;
+            let start_line = start.saturating_sub(margin);
+            let end_line = end + margin;
+            let misspellings = self.misspellings_in_lines(buffer_index, start_line, end_line);
+            if self.spell.viewport.len() >= 32 {
+                self.spell.viewport.clear();
+            }
+            self.spell.viewport.insert(
+                buffer_index,
+                SpellViewportEntry {
+                    buffer_id,
+                    revision,
+                    generation,
+                    language_id,
+                    start_line,
+                    end_line,
+                    misspellings,
+                },
+            );
+        }
+
+        self.spell.viewport[&buffer_index]
+            .misspellings
+            .iter()
+            .filter(|misspelling| (start..end).contains(&misspelling.line))
+            .copied()
+            .collect()
+    }
+
+    /// Underlines the misspelled words visible in `window`, limited to
+    /// `terminal_rows` when only those rows are repainted.
+    pub(super) fn render_misspellings_in_window(
+        &mut self,
+        buffer: &mut RenderBuffer,
+        window: &crate::window::Window,
+        terminal_rows: Option<&[usize]>,
+    ) {
+        if !self.config.spell.enabled {
+            return;
+        }
+        let layout = self.layout_for_window(window);
+        let (Some(first), Some(last)) = (layout.rows.first(), layout.rows.last()) else {
+            return;
+        };
+        let misspellings =
+            self.viewport_misspellings(window.buffer_index, first.line, last.line + 1);
+        let tab_width = self.tab_width_for_buffer_index(window.buffer_index);
+        for misspelling in misspellings {
+            let Some(line) = self
+                .buffer_manager
+                .get(window.buffer_index)
+                .and_then(|buffer| buffer.get(misspelling.line))
+            else {
+                continue;
+            };
+            let line = trim_line_ending(&line);
+            let start_col =
+                display_width_with_tabs(char_prefix(line, misspelling.start), tab_width);
+            let end_col = display_width_with_tabs(char_prefix(line, misspelling.end), tab_width);
+            for point in self.display_col_range_points_in_layout(
+                window,
+                &layout,
+                misspelling.line,
+                start_col,
+                end_col,
+            ) {
+                if terminal_rows.is_some_and(|rows| !rows.contains(&point.y))
+                    || point.x >= buffer.width
+                    || point.y >= buffer.height
+                {
+                    continue;
+                }
+                if let Some(cell) = buffer.cells.get_mut(point.y * buffer.width + point.x) {
+                    cell.style = self.theme.spell_bad_style(&cell.style);
+                }
+            }
+        }
+    }
+
+    /// Finds the start of the next (`]s`) or previous (`[s`) misspelled word,
+    /// wrapping around the buffer when `wrapscan` is set.
+    pub(super) fn misspelling_navigation_target(
+        &mut self,
+        forward: bool,
+    ) -> Result<Option<TextPosition>, String> {
+        self.load_spell_checker()?;
+        let buffer_index = self.buffer_manager.active_index();
+        let line_count = self.current_buffer().len();
+        let chunk = if self.current_buffer().line_range_byte_len(0, line_count)
+            <= MAX_HIGHLIGHT_SLICE_BYTES
+        {
+            line_count.max(1)
+        } else {
+            SPELL_SCAN_LINES
+        };
+        let cursor = self.cursor_text_position();
+        let cursor = (cursor.line, cursor.character);
+        let position = |misspelling: &Misspelling| (misspelling.line, misspelling.start);
+
+        let mut found = None;
+        if forward {
+            let mut start = cursor.0 / chunk * chunk;
+            while found.is_none() && start < line_count {
+                found = self
+                    .misspellings_in_lines(buffer_index, start, start + chunk)
+                    .iter()
+                    .map(position)
+                    .find(|position| *position > cursor);
+                start += chunk;
+            }
+            let mut start = 0;
+            while found.is_none() && self.config.search.wrapscan && start <= cursor.0 {
+                found = self
+                    .misspellings_in_lines(buffer_index, start, start + chunk)
+                    .first()
+                    .map(position);
+                start += chunk;
+            }
+        } else {
+            let mut start = cursor.0 / chunk * chunk;
+            loop {
+                found = self
+                    .misspellings_in_lines(buffer_index, start, start + chunk)
+                    .iter()
+                    .map(position)
+                    .rfind(|position| *position < cursor);
+                if found.is_some() || start == 0 {
+                    break;
+                }
+                start -= chunk;
+            }
+            let mut start = line_count.saturating_sub(1) / chunk * chunk;
+            while found.is_none() && self.config.search.wrapscan && start + chunk > cursor.0 {
+                found = self
+                    .misspellings_in_lines(buffer_index, start, start + chunk)
+                    .last()
+                    .map(position);
+                if start == 0 {
+                    break;
+                }
+                start -= chunk;
+            }
+        }
+
+        Ok(found.map(|(line, character)| {
+            TextPosition::new(line, self.char_to_grapheme_on_line(character, line))
+        }))
+    }
+
+    /// Returns the word the cursor is on as spell checking sees it: letters
+    /// joined by apostrophes.
+    fn spell_word_at_cursor(&self) -> Option<(TextRange, String)> {
+        let line_index = self.buffer_line();
+        let line = self.current_buffer().get(line_index)?;
+        let chars = trim_line_ending(&line).chars().collect::<Vec<_>>();
+        let cursor = self.grapheme_to_char_on_line(self.cx, line_index);
+        if !chars.get(cursor).is_some_and(|c| c.is_alphabetic()) {
+            return None;
+        }
+        let joins = |index: usize| {
+            chars[index].is_alphabetic()
+                || (matches!(chars[index], '\'' | '’')
+                    && index > 0
+                    && chars[index - 1].is_alphabetic()
+                    && chars.get(index + 1).is_some_and(|c| c.is_alphabetic()))
+        };
+        let mut start = cursor;
+        while start > 0 && joins(start - 1) {
+            start -= 1;
+        }
+        let mut end = cursor + 1;
+        while end < chars.len() && joins(end) {
+            end += 1;
+        }
+        Some((
+            TextRange {
+                start: TextPosition::new(line_index, start),
+                end: TextPosition::new(line_index, end),
+            },
+            chars[start..end].iter().collect(),
+        ))
+    }
+
+    /// Opens the `z=` picker of replacements for the word under the cursor.
+    pub(super) fn open_spelling_suggestions(&mut self) {
+        if let Err(error) = self.load_spell_checker() {
+            self.set_notification_message(Severity::Error, Some(error));
+            return;
+        }
+        let Some((_, word)) = self.spell_word_at_cursor() else {
+            self.set_legacy_message(Some("no word under cursor".to_string()));
+            return;
+        };
+        let Some(checker) = self.spell_checker() else {
+            return;
+        };
+        let mut suggestions = checker.suggest(&word);
+        suggestions.truncate(MAX_SUGGESTIONS);
+        if suggestions.is_empty() {
+            self.set_legacy_message(Some(format!("no suggestions for {word:?}")));
+            return;
+        }
+        let picker = Picker::builder()
+            .title(&format!("Change {word:?} to"))
+            .items(suggestions)
+            .select_action(Action::ReplaceMisspelledWord)
+            .build(self);
+        self.current_dialog = Some(Box::new(picker));
+    }
+
+    /// Replaces the word under the cursor with `replacement` as one undo step.
+    pub(super) async fn replace_misspelled_word(
+        &mut self,
+        replacement: &str,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        let Some((range, _)) = self.spell_word_at_cursor() else {
+            return Ok(());
+        };
+        self.begin_transaction("spelling");
+        self.replace_range(range, replacement);
+        self.move_to_text_position(range.start);
+        self.commit_transaction(self.cursor_snapshot());
+        self.refresh_cursor_goal();
+        self.notify_change(runtime).await
+    }
+
+    /// Adds the word under the cursor to the good (`zg`) or wrong (`zw`) words
+    /// of the word list.
+    pub(super) fn mark_spelling(&mut self, good: bool) {
+        if let Err(error) = self.load_spell_checker() {
+            self.set_notification_message(Severity::Error, Some(error));
+            return;
+        }
+        let Some((_, word)) = self.spell_word_at_cursor() else {
+            self.set_legacy_message(Some("no word under cursor".to_string()));
+            return;
+        };
+        let Some(Ok(checker)) = self.spell.checker.as_mut().map(|(_, checker)| checker) else {
+            return;
+        };
+        if let Err(error) = checker.add_word(&word, good) {
+            self.set_notification_message(
+                Severity::Error,
+                Some(format!("failed to update the word list: {error}")),
+            );
+        } else {
+            let list = if good { "good" } else { "wrong" };
+            self.set_legacy_message(Some(format!("added {word:?} to the {list} words")));
+        }
+        self.spell.generation = self.spell.generation.wrapping_add(1);
+    }
+
+    /// Switches spell checking on or off, loading the dictionaries again when
+    /// it is switched on.
+    pub(super) fn set_spell(&mut self, enabled: bool) {
+        self.config.spell.enabled = enabled;
+        if enabled {
+            self.spell.checker = None;
+            if let Err(error) = self.load_spell_checker() {
+                self.set_notification_message(Severity::Error, Some(error));
+            }
+        }
+    }
+
+    /// Checks spelling against the dictionaries for `languages`.
+    pub(super) fn set_spell_languages(&mut self, languages: Vec<String>) {
+        self.config.spell.languages = languages;
+        if self.config.spell.enabled {
+            if let Err(error) = self.load_spell_checker() {
+                self.set_notification_message(Severity::Error, Some(error));
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Repeating substitutes: `&`, `g&`, `:[range]&[&][flags]` and `.` after a confirmed
+//! substitute.
+//!
+//! The last substitute's pattern, replacement and flags are kept as
+//! [`LastSubstitute`] and saved with the session. `&` and `:&` repeat it without
+//! its flags, while `g&` and `:&&` keep them. A substitute answered with `c` is
+//! remembered as a [`ConfirmedSubstitute`], so `.` resolves its range again and
+//! replaces the matches that were accepted without asking.
+
+use super::*;
+
+impl Editor {
+    /// Returns the lines a substitute with `range` covers, the current line when
+    /// `range` is empty.
+    pub(super) fn substitute_lines(&self, range: &str) -> anyhow::Result<ExLineRange> {
+        if range.is_empty() {
+            let line = self.buffer_line();
+            return Ok(ExLineRange {
+                start_line: line,
+                end_line: line,
+            });
+        }
+        self.resolve_ex_line_range(range, "substitute")
+    }
+
+    /// Parses the `&[&][flags]` that follows the range of `:&`.
+    pub(super) fn parse_repeat_substitute_command(
+        &self,
+        range: &str,
+        arguments: &str,
+    ) -> anyhow::Result<SubstituteCommand> {
+        let last = self.last_substitute()?;
+        let (keep_flags, flags) = match arguments.strip_prefix('&') {
+            Some(flags) => (true, flags.trim()),
+            None => (false, arguments.trim()),
+        };
+        anyhow::ensure!(
+            flags.chars().all(|flag| matches!(flag, 'g' | 'i' | 'c')),
+            "unsupported substitute flags: {flags}"
+        );
+        let lines = self.substitute_lines(range)?;
+        Ok(SubstituteCommand {
+            range: range.to_string(),
+            start_line: lines.start_line,
+            end_line: lines.end_line,
+            pattern: last.pattern.clone(),
+            replacement: last.replacement.clone(),
+            replace_all: keep_flags && last.replace_all || flags.contains('g'),
+            case_insensitive: keep_flags && last.case_insensitive || flags.contains('i'),
+            confirm: keep_flags && last.confirm || flags.contains('c'),
+        })
+    }
+
+    /// Returns the command `&` runs, or with `all_lines` the command `g&` runs.
+    pub(super) fn repeated_substitute_command(
+        &self,
+        all_lines: bool,
+    ) -> anyhow::Result<SubstituteCommand> {
+        if all_lines {
+            self.parse_repeat_substitute_command("%", "&")
+        } else {
+            self.parse_repeat_substitute_command("", "")
+        }
+    }
+
+    fn last_substitute(&self) -> anyhow::Result<&LastSubstitute> {
+        self.last_substitute
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("no previous substitute to repeat"))
+    }
+
+    /// Returns the replacement of `command` with `~` expanded in the Vim dialect.
+    pub(super) fn substitute_replacement(&self, command: &SubstituteCommand) -> String {
+        match self.config.search.dialect {
+            SearchDialect::Vim if !command.replacement.starts_with("\\=") => {
+                vim_regex::Replacement::expand_previous(
+                    &command.replacement,
+                    self.last_substitute
+                        .as_ref()
+                        .map(|last| last.replacement.as_str()),
+                )
+            }
+            _ => command.replacement.clone(),
+        }
+    }
+
+    pub(super) fn remember_substitute(&mut self, command: &SubstituteCommand, replacement: String) {
+        let previous = self
+            .last_substitute
+            .as_ref()
+            .map(|last| last.replacement.as_str());
+        if previous != Some(replacement.as_str()) {
+            // `~` in a cached Vim-dialect search now means something else.
+            self.search_match_cache = None;
+            self.search_match_history.clear();
+        }
+        self.last_substitute = Some(LastSubstitute {
+            pattern: command.pattern.clone(),
+            replacement,
+            replace_all: command.replace_all,
+            case_insensitive: command.case_insensitive,
+            confirm: command.confirm,
+        });
+    }
+
+    /// Makes a confirmed substitute the change `.` repeats. `replace_rest` is whether
+    /// matches past `answers` are replaced, as after `a`.
+    pub(super) fn remember_confirmed_substitute(
+        &mut self,
+        command: SubstituteCommand,
+        answers: Vec<bool>,
+        replace_rest: bool,
+    ) {
+        if self.replaying_semantic_change {
+            return;
+        }
+        self.pending_semantic_change = None;
+        self.last_semantic_change = Some(SemanticChange {
+            events: Vec::new(),
+            substitute: Some(ConfirmedSubstitute {
+                command,
+                answers,
+                replace_rest,
+            }),
+        });
+    }
+
+    /// Replays a confirmed substitute on its range resolved from the cursor,
+    /// replacing the matches the answers accepted.
+    pub(super) async fn replay_confirmed_substitute(
+        &mut self,
+        substitute: &ConfirmedSubstitute,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        let mut command = substitute.command.clone();
+        let planned = self.substitute_lines(&command.range).and_then(|lines| {
+            command.start_line = lines.start_line;
+            command.end_line = lines.end_line;
+            let replacement = self.substitute_replacement(&command);
+            self.plan_substitutions(&command, &replacement)
+                .map(|substitutions| (substitutions, replacement))
+        });
+        let (substitutions, replacement) = match planned {
+            Ok(planned) => planned,
+            Err(error) => {
+                self.set_legacy_message(Some(error.to_string()));
+                return self.render(buffer);
+            }
+        };
+        self.remember_substitute(&command, replacement);
+        let accepted = substitutions
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                substitute
+                    .answers
+                    .get(*index)
+                    .copied()
+                    .unwrap_or(substitute.replace_rest)
+            })
+            .map(|(_, substitution)| substitution)
+            .collect();
+        self.apply_substitutions(accepted, buffer, runtime).await
+    }
+}
@@ -0,0 +1,336 @@
+//! Surround commands: `ys{motion}`, `yss`, Visual `S`, `cs{old}{new}` and
+//! `ds{old}`.
+//!
+//! `ys` and Visual `S` leave a [`PendingSurround`] holding the range to wrap, and
+//! `cs` and `ds` leave one waiting for the delimiters to look for. The keys that
+//! follow resolve here into a surround action; `t`, `<`, `f` and `F` first read a
+//! tag or function name up to `Enter`, or `>` for a tag. Every edit is one undo
+//! step, and `.` replays the keys that named the delimiters.
+
+use super::*;
+use crate::surround::{self, Delimiters, SurroundingPair};
+
+impl Editor {
+    /// Waits for the delimiters to add around `range`.
+    pub(super) fn start_surround(&mut self, range: TextRange, linewise: bool) -> Option<KeyAction> {
+        self.pending_operator = None;
+        self.repeater = None;
+        self.pending_surround = Some(PendingSurround {
+            step: SurroundStep::Add { range, linewise },
+            prompt: None,
+        });
+        self.waiting_command = Some("ys".to_string());
+        Some(KeyAction::None)
+    }
+
+    /// Waits for the delimiters `ds`, or with `change` `cs`, works on.
+    pub(super) fn start_surround_search(&mut self, change: bool) -> Option<KeyAction> {
+        self.pending_operator = None;
+        self.repeater = None;
+        let (step, command) = if change {
+            (SurroundStep::Change, "cs")
+        } else {
+            (SurroundStep::Delete, "ds")
+        };
+        self.pending_surround = Some(PendingSurround { step, prompt: None });
+        self.waiting_command = Some(command.to_string());
+        Some(KeyAction::None)
+    }
+
+    /// Returns the range `yss` wraps: the current line from its first non-blank,
+    /// or with a count that many lines.
+    pub(super) fn surround_lines_range(&self, count: u16) -> Option<TextRange> {
+        let first = self.buffer_line();
+        let last = first
+            .saturating_add(usize::from(count.max(1)) - 1)
+            .min(self.last_navigable_line());
+        let line = self.current_line_contents()?;
+        let indent = line
+            .chars()
+            .take_while(|character| matches!(character, ' ' | '\t'))
+            .count();
+        Some(TextRange::new(
+            TextPosition::new(first, indent),
+            TextPosition::new(last, self.line_character_len(last)),
+        ))
+    }
+
+    pub(super) fn handle_surround_event(&mut self, ev: &Event) -> Option<KeyAction> {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press | KeyEventKind::Repeat,
+            ..
+        }) = ev
+        else {
+            return None;
+        };
+        let mut pending = self.pending_surround.take()?;
+        self.waiting_command = None;
+        if *code == KeyCode::Esc {
+            return Some(KeyAction::None);
+        }
+        let typed = matches!(*modifiers, KeyModifiers::NONE | KeyModifiers::SHIFT);
+
+        if let Some(prompt) = &mut pending.prompt {
+            let finished = match code {
+                KeyCode::Enter => true,
+                KeyCode::Char('>') if prompt.kind == SurroundPromptKind::Tag => true,
+                KeyCode::Char(character) if typed => {
+                    prompt.text.push(*character);
+                    false
+                }
+                KeyCode::Backspace => {
+                    prompt.text.pop();
+                    false
+                }
+                _ => false,
+            };
+            if !finished {
+                self.waiting_command = Some(prompt.display());
+                self.pending_surround = Some(pending);
+                return Some(KeyAction::None);
+            }
+            let delimiters = match prompt.kind {
+                SurroundPromptKind::Tag => surround::tag_delimiters(&prompt.text),
+                SurroundPromptKind::Function { spaced } => {
+                    surround::function_delimiters(&prompt.text, spaced)
+                }
+            };
+            return self.finish_surround(pending.step, delimiters);
+        }
+
+        let KeyCode::Char(key) = *code else {
+            self.set_legacy_message(Some("invalid surround delimiter".to_string()));
+            return Some(KeyAction::None);
+        };
+        if !typed {
+            self.set_legacy_message(Some("invalid surround delimiter".to_string()));
+            return Some(KeyAction::None);
+        }
+        match pending.step {
+            SurroundStep::Delete => Some(KeyAction::Single(Action::DeleteSurround(key))),
+            SurroundStep::Change => {
+                self.waiting_command = Some(format!("cs{key}"));
+                self.pending_surround = Some(PendingSurround {
+                    step: SurroundStep::ChangeTo { target: key },
+                    prompt: None,
+                });
+                Some(KeyAction::None)
+            }
+            step @ (SurroundStep::Add { .. } | SurroundStep::ChangeTo { .. }) => {
+                let kind = match key {
+                    't' | '<' => Some(SurroundPromptKind::Tag),
+                    'f' => Some(SurroundPromptKind::Function { spaced: false }),
+                    'F' => Some(SurroundPromptKind::Function { spaced: true }),
+                    _ => None,
+                };
+                if let Some(kind) = kind {
+                    let prompt = SurroundPrompt {
+                        kind,
+                        text: String::new(),
+                    };
+                    self.waiting_command = Some(prompt.display());
+                    self.pending_surround = Some(PendingSurround {
+                        step,
+                        prompt: Some(prompt),
+                    });
+                    return Some(KeyAction::None);
+                }
+                let delimiters = surround::delimiters_for_key(key, &self.config.matchit.pairs);
+                self.finish_surround(step, delimiters)
+            }
+        }
+    }
+
+    fn finish_surround(
+        &mut self,
+        step: SurroundStep,
+        delimiters: Option<Delimiters>,
+    ) -> Option<KeyAction> {
+        let Some(Delimiters { open, close }) = delimiters else {
+            self.set_legacy_message(Some("invalid surround delimiter".to_string()));
+            return Some(KeyAction::None);
+        };
+        let action = match step {
+            SurroundStep::Add { range, linewise } => Action::AddSurround {
+                range,
+                linewise,
+                open,
+                close,
+            },
+            SurroundStep::ChangeTo { target } => Action::ChangeSurround {
+                target,
+                open,
+                close,
+            },
+            SurroundStep::Delete | SurroundStep::Change => return Some(KeyAction::None),
+        };
+        Some(KeyAction::Single(action))
+    }
+
+    /// Starts Visual `S` on the selection. Characterwise selections are wrapped in
+    /// place and linewise ones get the delimiters on lines of their own.
+    pub(super) async fn surround_selection(
+        &mut self,
+        buffer: &mut RenderBuffer,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        let Some(selection) = self.selection else {
+            return Ok(());
+        };
+        let (x0, y0, x1, y1) = selection.into();
+        let (range, linewise) = match self.mode {
+            Mode::Visual => (
+                TextRange::new(
+                    TextPosition::new(y0, self.grapheme_to_char_on_line(x0, y0)),
+                    self.visual_selection_end_position(x1, y1),
+                ),
+                false,
+            ),
+            Mode::VisualLine => (
+                TextRange::new(
+                    TextPosition::new(y0, 0),
+                    TextPosition::new(y1, self.line_character_len(y1)),
+                ),
+                true,
+            ),
+            _ => {
+                self.set_legacy_message(Some(
+                    "surround needs a characterwise or linewise selection".to_string(),
+                ));
+                return Ok(());
+            }
+        };
+        self.execute(&Action::EnterMode(Mode::Normal), buffer, runtime)
+            .await?;
+        self.start_surround(range, linewise);
+        Ok(())
+    }
+
+    /// Wraps `range` in `open` and `close`. A characterwise range drops its
+    /// trailing whitespace first; a linewise one gets the delimiters on lines of
+    /// their own at the indentation of its first line.
+    pub(super) async fn add_surround(
+        &mut self,
+        range: TextRange,
+        linewise: bool,
+        open: &str,
+        close: &str,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        let (start, end, open, close) = if linewise {
+            let first = range.start.line;
+            let last = if range.end.line > first && range.end.character == 0 {
+                range.end.line - 1
+            } else {
+                range.end.line
+            };
+            let indent = self
+                .current_buffer()
+                .get(first)
+                .map(|line| Self::leading_indentation_text(&line).to_string())
+                .unwrap_or_default();
+            (
+                TextPosition::new(first, 0),
+                TextPosition::new(last, self.line_character_len(last)),
+                format!("{indent}{}\n", open.trim()),
+                format!("\n{indent}{}", close.trim()),
+            )
+        } else {
+            let text = self.current_buffer().text_in_range(range);
+            let kept = text.trim_end().chars().count();
+            (
+                range.start,
+                offset_text_position(range.start, &text, kept),
+                open.to_string(),
+                close.to_string(),
+            )
+        };
+
+        self.begin_transaction("surround");
+        self.replace_range(TextRange::insertion(end), &close);
+        self.replace_range(TextRange::insertion(start), &open);
+        self.move_to_text_position(start);
+        if linewise {
+            self.move_to_first_non_blank_on_current_line();
+        }
+        self.commit_transaction(self.cursor_snapshot());
+        self.refresh_cursor_goal();
+        self.notify_change(runtime).await
+    }
+
+    /// Removes the delimiters `target` names around the cursor, or replaces them
+    /// with `replacement`.
+    pub(super) async fn replace_surround(
+        &mut self,
+        target: char,
+        replacement: Option<(&str, &str)>,
+        runtime: &mut Runtime,
+    ) -> anyhow::Result<()> {
+        let Some(pair) = self.surrounding_pair(target) else {
+            self.set_legacy_message(Some(format!("no surrounding {target} found")));
+            return Ok(());
+        };
+        let (open, close) = replacement.unwrap_or(("", ""));
+        let buffer = self.current_buffer();
+        let range = |characters: std::ops::Range<usize>| {
+            TextRange::new(
+                buffer.char_idx_to_position(characters.start),
+                buffer.char_idx_to_position(characters.end),
+            )
+        };
+        let (open_range, close_range) = (range(pair.open), range(pair.close));
+
+        self.begin_transaction("surround");
+        self.replace_range(close_range, close);
+        self.replace_range(open_range, open);
+        self.move_to_text_position(open_range.start);
+        self.commit_transaction(self.cursor_snapshot());
+        self.refresh_cursor_goal();
+        self.notify_change(runtime).await
+    }
+
+    /// Finds the delimiters `target` names around the cursor, as character
+    /// indexes. An opening bracket also takes the whitespace inside the pair.
+    fn surrounding_pair(&self, target: char) -> Option<SurroundingPair> {
+        let buffer = self.current_buffer();
+        let cursor = buffer.position_to_char_idx(self.cursor_text_position());
+        let text = || buffer.contents_snapshot().to_string();
+        let around = |kind: TextObjectKind| {
+            let range = self.text_object_range_with_count(TextObjectScope::Around, kind, 1)?;
+            let start = buffer.position_to_char_idx(range.start);
+            let end = buffer.position_to_char_idx(range.end);
+            Some(SurroundingPair {
+                open: start..start + 1,
+                close: end - 1..end,
+            })
+        };
+
+        let key = if target == '<' { '>' } else { target };
+        if let Some((open, close, spaced)) = surround::bracket_for_key(key) {
+            let pair = around(TextObjectKind::Delimited { open, close })?;
+            return Some(if spaced {
+                surround::include_inner_whitespace(&text(), pair)
+            } else {
+                pair
+            });
+        }
+        match target {
+            '"' | '\'' | '`' => around(TextObjectKind::Quote(target)),
+            't' => surround::find_tag(&text(), cursor),
+            'f' => surround::find_function_call(&text(), cursor),
+            _ => {
+                if let Some([open, close]) =
+                    surround::configured_pair_for_key(target, &self.config.matchit.pairs)
+                {
+                    return surround::find_literal_pair(&text(), cursor, open, close);
+                }
+                let delimiter = target.to_string();
+                (!target.is_alphanumeric() && !target.is_whitespace())
+                    .then(|| surround::find_literal_pair(&text(), cursor, &delimiter, &delimiter))
+                    .flatten()
+            }
+        }
+    }
+}
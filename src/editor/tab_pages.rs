@@ -0,0 +1,213 @@
+//! Tab pages: `:tabnew`, `:tabclose`, `:tabonly`, `:tabmove`, `gt` and `gT`.
+//!
+//! The current page's layout is `window_manager`, and switching pages swaps another
+//! [`WindowManager`] into it, so window commands only ever see the current page.
+//! Buffers, registers, plugin panels and workspaces belong to the editor rather than
+//! to a page: panels stay docked while pages change and a workspace covers every
+//! page. The tab line takes the first row, above panels and windows, whenever more
+//! than one page is open.
+
+use super::*;
+use crate::unicode_utils::truncate_display_width;
+
+impl Editor {
+    /// Rows the tab line takes: one while several tab pages are open.
+    pub(super) fn tab_line_height(&self) -> usize {
+        usize::from(self.tab_pages.count() > 1 && self.learn_session.is_none())
+    }
+
+    /// Opens a tab page after the current one showing `file`, or a new empty buffer.
+    pub(super) async fn open_tab_page(
+        &mut self,
+        file: Option<&str>,
+        buffer: &mut RenderBuffer,
+    ) -> anyhow::Result<()> {
+        let buffer_index = match file {
+            Some(file) => match self.load_or_reuse_file_buffer(file).await {
+                Ok((index, _, _)) => index,
+                Err(error) => {
+                    self.set_legacy_message(Some(format!("Failed to open file: {error}")));
+                    return self.render(buffer);
+                }
+            },
+            None => {
+                self.buffer_manager
+                    .push_buffer(Buffer::new(/*file*/ None, String::new()));
+                self.buffer_manager.len() - 1
+            }
+        };
+        let mut page = WindowManager::new(
+            buffer_index,
+            (usize::from(self.size.0), usize::from(self.size.1)),
+        );
+        let wrap = self.config.wrap.unwrap_or(true);
+        for window in page.windows_mut() {
+            window.wrap = wrap;
+        }
+        self.leave_tab_page();
+        self.tab_pages.open(&mut self.window_manager, page);
+        self.enter_tab_page();
+        self.request_diagnostics().await?;
+        self.render(buffer)
+    }
+
+    /// Makes the tab page at `index` current.
+    pub(super) fn select_tab_page(&mut self, index: usize) -> bool {
+        if index == self.tab_pages.current_index() || index >= self.tab_pages.count() {
+            return false;
+        }
+        self.leave_tab_page();
+        self.tab_pages.select(&mut self.window_manager, index);
+        self.enter_tab_page();
+        true
+    }
+
+    /// Goes `offset` tab pages forwards or backwards, wrapping around at either end.
+    pub(super) fn cycle_tab_page(&mut self, offset: isize) -> bool {
+        let count = self.tab_pages.count() as isize;
+        let index = (self.tab_pages.current_index() as isize + offset).rem_euclid(count);
+        self.select_tab_page(index as usize)
+    }
+
+    /// Closes the current tab page and its windows. The buffers they showed stay open.
+    pub(super) fn close_tab_page(&mut self) -> bool {
+        if self.tab_pages.count() == 1 {
+            self.set_legacy_message(Some("cannot close the last tab page".to_string()));
+            return false;
+        }
+        self.leave_tab_page();
+        self.tab_pages.close(&mut self.window_manager);
+        self.enter_tab_page();
+        true
+    }
+
+    /// Closes every tab page but the current one.
+    pub(super) fn close_other_tab_pages(&mut self) -> bool {
+        if self.tab_pages.count() == 1 {
+            return false;
+        }
+        self.leave_tab_page();
+        self.tab_pages.close_others();
+        self.enter_tab_page();
+        true
+    }
+
+    /// Resolves the argument of `:tabmove` to the position the current page moves
+    /// to: `N` puts it after page `N`, `+N` and `-N` move it relative to where it
+    /// is, and no argument or `$` moves it to the end.
+    pub(super) fn tab_page_move_target(&self, argument: &str) -> Option<usize> {
+        let current = self.tab_pages.current_index();
+        let last = self.tab_pages.count() - 1;
+        let offset = |digits: &str| {
+            if digits.is_empty() {
+                Some(1)
+            } else {
+                digits.parse::<usize>().ok()
+            }
+        };
+        let target = match argument {
+            "" | "$" => last,
+            _ => {
+                if let Some(digits) = argument.strip_prefix('+') {
+                    current.saturating_add(offset(digits)?)
+                } else if let Some(digits) = argument.strip_prefix('-') {
+                    current.saturating_sub(offset(digits)?)
+                } else {
+                    let after = argument.parse::<usize>().ok()?;
+                    if after > current {
+                        after - 1
+                    } else {
+                        after
+                    }
+                }
+            }
+        };
+        Some(target.min(last))
+    }
+
+    fn leave_tab_page(&mut self) {
+        self.clear_pane_zoom();
+        self.sync_to_window();
+    }
+
+    /// Takes over the view state of the page that just became current.
+    fn enter_tab_page(&mut self) {
+        self.divider_drag = None;
+        self.pane_resize_mode = None;
+        self.sync_with_window();
+        self.apply_panel_layout();
+        self.check_bounds();
+        self.force_full_redraw = true;
+    }
+
+    /// Draws one label per tab page: the window count when above one, `+` when a
+    /// window shows a modified buffer, and the name of the focused window's buffer.
+    pub(super) fn draw_tab_line(&self, buffer: &mut RenderBuffer) {
+        if self.tab_line_height() == 0 || self.size.0 == 0 {
+            return;
+        }
+        let width = usize::from(self.size.0);
+        let inactive = &self.theme.statusline_style.inner_style;
+        let active = &self.theme.statusline_style.outer_style;
+        buffer.fill_ascii_spaces(0, 0, width, inactive);
+
+        let current = self.tab_pages.current_index();
+        let labels = self
+            .tab_pages
+            .pages(&self.window_manager)
+            .map(|page| self.tab_label(page))
+            .collect::<Vec<_>>();
+        // Keep the current label on screen by dropping labels from the left.
+        let mut first = 0;
+        while first < current
+            && labels[first..=current]
+                .iter()
+                .map(|label| display_width(label))
+                .sum::<usize>()
+                > width
+        {
+            first += 1;
+        }
+        let mut x = 0;
+        for (index, label) in labels.iter().enumerate().skip(first) {
+            if x >= width {
+                break;
+            }
+            let text = truncate_display_width(label, width - x);
+            let style = if index == current { active } else { inactive };
+            buffer.set_text(x, 0, &text, style);
+            x += display_width(&text);
+        }
+    }
+
+    fn tab_label(&self, page: &WindowManager) -> String {
+        let windows = page.windows();
+        let modified = windows.iter().any(|window| {
+            self.buffer_manager
+                .get(window.buffer_index)
+                .is_some_and(Buffer::is_dirty)
+        });
+        let name = page
+            .active_window()
+            .and_then(|window| self.buffer_manager.get(window.buffer_index))
+            .map(|source| {
+                Path::new(source.name()).file_name().map_or_else(
+                    || source.name().to_string(),
+                    |name| name.to_string_lossy().into_owned(),
+                )
+            })
+            .unwrap_or_default();
+        let mut prefix = String::new();
+        if windows.len() > 1 {
+            prefix.push_str(&windows.len().to_string());
+        }
+        if modified {
+            prefix.push('+');
+        }
+        if prefix.is_empty() {
+            format!(" {name} ")
+        } else {
+            format!(" {prefix} {name} ")
+        }
+    }
+}
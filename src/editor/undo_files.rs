@@ -0,0 +1,430 @@
+//! Undo trees kept across editor restarts, Red's equivalent of Vim's `'undofile'`.
+//!
+//! Writing a file also writes its [`UndoHistory`] under a name derived from the
+//! file's canonical path. Reopening the file restores that history only when the
+//! text still hashes to what was written, the file is within the configured age,
+//! and the tree passes the same validation crash recovery applies.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read as _, Write as _},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use ropey::Rope;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+
+use crate::{buffer::Buffer, config::UndoFileConfig, undo::UndoHistory};
+
+const UNDO_FILE_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedUndo {
+    version: u32,
+    path: PathBuf,
+    content_sha256: [u8; 32],
+    written_at_ms: u64,
+    history: UndoHistory,
+}
+
+/// Reads and writes owner-private undo files in one directory.
+pub(super) struct UndoFileStore {
+    directory: PathBuf,
+}
+
+impl UndoFileStore {
+    pub(super) fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+
+    /// Returns the history saved for `buffer`'s file when it still describes the
+    /// buffer's text. Stale or unreadable undo files are removed.
+    pub(super) fn load(
+        &self,
+        buffer: &Buffer,
+        config: &UndoFileConfig,
+    ) -> anyhow::Result<Option<UndoHistory>> {
+        let Some(path) = canonical_file(buffer) else {
+            return Ok(None);
+        };
+        let undo_path = undo_file_path(&self.directory, &path);
+        let persisted = match read_undo_file(&undo_path, config.max_bytes) {
+            Ok(Some(persisted)) => persisted,
+            Ok(None) => return Ok(None),
+            Err(error) => {
+                remove_undo_file(&undo_path);
+                return Err(error);
+            }
+        };
+        if persisted.version != UNDO_FILE_SCHEMA_VERSION
+            || persisted.path != path
+            || persisted.content_sha256 != hash_rope(&buffer.contents_snapshot())
+            || expired(persisted.written_at_ms, config)
+        {
+            remove_undo_file(&undo_path);
+            return Ok(None);
+        }
+        let history = persisted.history;
+        if let Err(error) = history.validate() {
+            remove_undo_file(&undo_path);
+            anyhow::bail!(
+                "undo file for {} contains an invalid undo tree: {error}",
+                path.display()
+            );
+        }
+        if history.is_dirty() || history.is_transaction_active() {
+            remove_undo_file(&undo_path);
+            return Ok(None);
+        }
+        Ok(Some(history))
+    }
+
+    /// Writes `buffer`'s history for the text it just saved. History that would
+    /// exceed `max_bytes` loses its oldest states until it fits.
+    pub(super) fn write(&self, buffer: &Buffer, config: &UndoFileConfig) -> anyhow::Result<()> {
+        let Some(path) = canonical_file(buffer) else {
+            return Ok(());
+        };
+        let mut history = buffer.undo_history.clone();
+        history.cancel_transaction_if_empty();
+        anyhow::ensure!(
+            !history.is_transaction_active(),
+            "cannot write an undo file during an edit"
+        );
+        if history.node_count() == 0 {
+            remove_undo_file(&undo_file_path(&self.directory, &path));
+            return Ok(());
+        }
+        let mut persisted = PersistedUndo {
+            version: UNDO_FILE_SCHEMA_VERSION,
+            content_sha256: hash_rope(&buffer.contents_snapshot()),
+            written_at_ms: current_time_ms(),
+            path,
+            history,
+        };
+        let contents = loop {
+            let contents = serde_json::to_vec(&persisted)?;
+            if contents.len() as u64 <= config.max_bytes {
+                break contents;
+            }
+            let nodes = persisted.history.node_count();
+            anyhow::ensure!(
+                nodes > 1,
+                "undo file for {} exceeds {} bytes",
+                persisted.path.display(),
+                config.max_bytes
+            );
+            persisted.history.set_max_nodes(nodes / 2);
+        };
+        write_undo_file(
+            &self.directory,
+            &undo_file_path(&self.directory, &persisted.path),
+            &contents,
+        )
+    }
+
+    /// Removes undo files that have not been written within the age limit.
+    pub(super) fn prune(&self, config: &UndoFileConfig) {
+        let Ok(entries) = fs::read_dir(&self.directory) else {
+            return;
+        };
+        let max_age = max_age(config);
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let stale = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age > max_age);
+            if metadata.is_file() && stale {
+                remove_undo_file(&entry.path());
+            }
+        }
+    }
+}
+
+fn canonical_file(buffer: &Buffer) -> Option<PathBuf> {
+    fs::canonicalize(buffer.file.as_deref()?).ok()
+}
+
+fn undo_file_path(directory: &Path, file: &Path) -> PathBuf {
+    let digest = Sha256::digest(file.to_string_lossy().as_bytes());
+    directory.join(format!("{digest:x}.json"))
+}
+
+fn max_age(config: &UndoFileConfig) -> Duration {
+    Duration::from_secs(config.max_age_days.saturating_mul(24 * 60 * 60))
+}
+
+fn expired(written_at_ms: u64, config: &UndoFileConfig) -> bool {
+    current_time_ms().saturating_sub(written_at_ms)
+        > u64::try_from(max_age(config).as_millis()).unwrap_or(u64::MAX)
+}
+
+fn current_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .and_then(|duration| u64::try_from(duration.as_millis()).ok())
+        .unwrap_or_default()
+}
+
+fn hash_rope(contents: &Rope) -> [u8; 32] {
+    let mut digest = Sha256::new();
+    for chunk in contents.chunks() {
+        digest.update(chunk.as_bytes());
+    }
+    digest.finalize().into()
+}
+
+fn remove_undo_file(path: &Path) {
+    if let Err(error) = fs::remove_file(path) {
+        if error.kind() != io::ErrorKind::NotFound {
+            crate::log!("failed to remove undo file {}: {error}", path.display());
+        }
+    }
+}
+
+fn read_undo_file(path: &Path, max_bytes: u64) -> anyhow::Result<Option<PersistedUndo>> {
+    let mut file = match open_undo_file(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+    let metadata = file.metadata()?;
+    anyhow::ensure!(
+        metadata.is_file() && metadata.len() <= max_bytes,
+        "undo file must be a bounded regular file"
+    );
+    let mut contents = Vec::new();
+    (&mut file).take(max_bytes + 1).read_to_end(&mut contents)?;
+    anyhow::ensure!(
+        contents.len() as u64 <= max_bytes,
+        "undo file exceeds its read limit"
+    );
+    Ok(Some(serde_json::from_slice(&contents)?))
+}
+
+#[cfg(unix)]
+fn open_undo_file(path: &Path) -> io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt as _;
+
+    fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(nix::libc::O_NOFOLLOW | nix::libc::O_NONBLOCK)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn open_undo_file(path: &Path) -> io::Result<File> {
+    if fs::symlink_metadata(path)?.file_type().is_symlink() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "undo file must not be a symlink",
+        ));
+    }
+    File::open(path)
+}
+
+fn write_undo_file(directory: &Path, destination: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    fs::create_dir_all(directory)?;
+    let metadata = fs::symlink_metadata(directory)?;
+    anyhow::ensure!(
+        metadata.is_dir() && !metadata.file_type().is_symlink(),
+        "undo file directory must not be a symlink"
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        fs::set_permissions(directory, fs::Permissions::from_mode(0o700))?;
+    }
+    if let Ok(metadata) = fs::symlink_metadata(destination) {
+        anyhow::ensure!(
+            metadata.is_file() && !metadata.file_type().is_symlink(),
+            "undo file destination must be a regular file"
+        );
+    }
+    let mut temporary = tempfile::NamedTempFile::new_in(directory)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        temporary
+            .as_file()
+            .set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    temporary.write_all(contents)?;
+    temporary.as_file().sync_all()?;
+    temporary
+        .persist(destination)
+        .map_err(|error| error.error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, time::Duration};
+
+    use super::{expired, undo_file_path, UndoFileStore};
+    use crate::{
+        buffer::Buffer,
+        config::UndoFileConfig,
+        undo::{CursorSnapshot, TextPosition, TextRange},
+    };
+
+    fn config() -> UndoFileConfig {
+        UndoFileConfig {
+            enabled: true,
+            ..UndoFileConfig::default()
+        }
+    }
+
+    fn saved_buffer(path: &Path, changes: usize) -> Buffer {
+        let contents = "x".repeat(64 * changes);
+        fs::write(path, &contents).unwrap();
+        let mut buffer = Buffer::new(Some(path.to_string_lossy().into_owned()), contents);
+        for change in 0..changes {
+            let history = &mut buffer.undo_history;
+            history.begin_transaction("insert", CursorSnapshot::default());
+            history.record_replace(
+                TextRange::insertion(TextPosition::new(0, change * 64)),
+                change * 64,
+                String::new(),
+                "x".repeat(64),
+            );
+            assert!(history.commit_transaction(CursorSnapshot::default()));
+        }
+        buffer.undo_history.mark_saved();
+        buffer
+    }
+
+    fn stored_path(store: &UndoFileStore, file: &Path) -> std::path::PathBuf {
+        undo_file_path(&store.directory, &fs::canonicalize(file).unwrap())
+    }
+
+    #[test]
+    fn write_drops_the_oldest_states_until_the_file_fits() {
+        let root = tempfile::tempdir().unwrap();
+        let store = UndoFileStore::new(root.path().join("undo"));
+        let file = root.path().join("file.txt");
+        let buffer = saved_buffer(&file, 16);
+
+        store.write(&buffer, &config()).unwrap();
+        let full = fs::metadata(stored_path(&store, &file)).unwrap().len();
+
+        let limited = UndoFileConfig {
+            max_bytes: full / 3,
+            ..config()
+        };
+        store.write(&buffer, &limited).unwrap();
+        assert!(fs::metadata(stored_path(&store, &file)).unwrap().len() <= limited.max_bytes);
+        let history = store.load(&buffer, &limited).unwrap().unwrap();
+        assert!((1..16).contains(&history.node_count()));
+
+        let tiny = UndoFileConfig {
+            max_bytes: 16,
+            ..config()
+        };
+        let error = store.write(&buffer, &tiny).unwrap_err();
+        assert!(error.to_string().contains("exceeds 16 bytes"));
+    }
+
+    #[test]
+    fn expired_files_are_discarded_on_load_and_by_prune() {
+        let config = config();
+        assert!(!expired(super::current_time_ms(), &config));
+        assert!(expired(0, &config));
+
+        let root = tempfile::tempdir().unwrap();
+        let store = UndoFileStore::new(root.path().join("undo"));
+        let file = root.path().join("file.txt");
+        let buffer = saved_buffer(&file, 2);
+        store.write(&buffer, &config).unwrap();
+        let undo_path = stored_path(&store, &file);
+        let mut persisted: serde_json::Value =
+            serde_json::from_slice(&fs::read(&undo_path).unwrap()).unwrap();
+        persisted["written_at_ms"] = 0.into();
+        fs::write(&undo_path, serde_json::to_vec(&persisted).unwrap()).unwrap();
+        assert!(store.load(&buffer, &config).unwrap().is_none());
+        assert!(!undo_path.exists());
+
+        store.write(&buffer, &config).unwrap();
+        let fresh = root.path().join("undo").join("fresh.json");
+        fs::write(&fresh, "{}").unwrap();
+        let age = Duration::from_secs(2 * 24 * 60 * 60);
+        fs::File::options()
+            .write(true)
+            .open(&undo_path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - age)
+            .unwrap();
+        store.prune(&UndoFileConfig {
+            max_age_days: 1,
+            ..config
+        });
+        assert!(!undo_path.exists());
+        assert!(fresh.exists());
+    }
+
+    #[test]
+    fn corrupt_truncated_and_invalid_files_are_rejected_and_removed() {
+        let root = tempfile::tempdir().unwrap();
+        let store = UndoFileStore::new(root.path().join("undo"));
+        let file = root.path().join("file.txt");
+        let buffer = saved_buffer(&file, 2);
+        let config = config();
+        store.write(&buffer, &config).unwrap();
+        let undo_path = stored_path(&store, &file);
+        let valid = fs::read(&undo_path).unwrap();
+
+        fs::write(&undo_path, b"not an undo file").unwrap();
+        assert!(store.load(&buffer, &config).is_err());
+        assert!(!undo_path.exists());
+
+        fs::write(&undo_path, &valid[..valid.len() / 2]).unwrap();
+        assert!(store.load(&buffer, &config).is_err());
+        assert!(!undo_path.exists());
+
+        let mut persisted: serde_json::Value = serde_json::from_slice(&valid).unwrap();
+        persisted["history"]["current_revision"] = 1.into();
+        fs::write(&undo_path, serde_json::to_vec(&persisted).unwrap()).unwrap();
+        let error = store.load(&buffer, &config).unwrap_err();
+        assert!(error.to_string().contains("invalid undo tree"));
+        assert!(!undo_path.exists());
+
+        fs::write(&undo_path, &valid).unwrap();
+        assert!(store.load(&buffer, &config).unwrap().is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_undo_files_are_refused() {
+        let root = tempfile::tempdir().unwrap();
+        let store = UndoFileStore::new(root.path().join("undo"));
+        let file = root.path().join("file.txt");
+        let buffer = saved_buffer(&file, 2);
+        let config = config();
+        store.write(&buffer, &config).unwrap();
+        let undo_path = stored_path(&store, &file);
+        let target = root.path().join("elsewhere.json");
+        fs::rename(&undo_path, &target).unwrap();
+        std::os::unix::fs::symlink(&target, &undo_path).unwrap();
+
+        assert!(store.load(&buffer, &config).is_err());
+        assert!(fs::symlink_metadata(&undo_path).is_err());
+        assert!(target.exists());
+
+        std::os::unix::fs::symlink(&target, &undo_path).unwrap();
+        assert!(store.write(&buffer, &config).is_err());
+        assert_eq!(
+            fs::read_link(&undo_path).unwrap(),
+            target,
+            "writing must not replace or follow the symlink"
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Visual-block geometry, `A` append and blockwise put.
+//!
+//! A block's edges are display columns, so tabs and wide characters line up the way
+//! they are drawn rather than by grapheme count. While the cursor goal is the line
+//! end, as after `$`, the block ends at each row's own end instead of a shared
+//! column, and [`LastVisualSelection`] keeps that ragged edge for `gv`.
+
+use super::*;
+
+/// The display columns a Visual block covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct BlockColumns {
+    pub(super) start: usize,
+    pub(super) end: BlockEdge,
+}
+
+/// The right edge of a Visual block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum BlockEdge {
+    /// Exclusive display column shared by every row.
+    Column(usize),
+    /// Each row's own end, after `$`.
+    LineEnd,
+}
+
+impl Editor {
+    /// Whether the Visual block extends to the end of every row.
+    pub(super) fn block_extends_to_line_end(&self) -> bool {
+        self.mode == Mode::VisualBlock && self.cursor_goal == CursorGoal::LineEnd
+    }
+
+    /// The columns spanned by the cells under the block's two corners.
+    pub(super) fn block_columns(&self, selection: Rect) -> BlockColumns {
+        let tab_width = self.active_tab_width();
+        let corner = |x: usize, y: usize| {
+            let contents = self.current_buffer().get(y).unwrap_or_default();
+            let line = trim_line_ending(&contents);
+            let start = grapheme_to_column_with_tabs(line, x, tab_width);
+            let end = grapheme_to_column_with_tabs(line, x + 1, tab_width).max(start + 1);
+            (start, end)
+        };
+        let (x0, y0, x1, y1) = selection.into();
+        let (start0, end0) = corner(x0, y0);
+        let (start1, end1) = corner(x1, y1);
+        BlockColumns {
+            start: start0.min(start1),
+            end: if self.block_extends_to_line_end() {
+                BlockEdge::LineEnd
+            } else {
+                BlockEdge::Column(end0.max(end1))
+            },
+        }
+    }
+
+    /// Graphemes the block covers on `line`, or `None` when the line ends before
+    /// the block starts.
+    pub(super) fn block_row_graphemes(
+        &self,
+        line: usize,
+        columns: BlockColumns,
+    ) -> Option<(usize, usize)> {
+        let contents = self.current_buffer().get(line)?;
+        block_graphemes(
+            trim_line_ending(&contents),
+            columns,
+            self.active_tab_width(),
+        )
+    }
+
+    /// The text the block covers on `line`.
+    pub(super) fn block_row_range(&self, line: usize, columns: BlockColumns) -> Option<TextRange> {
+        let (start, end) = self.block_row_graphemes(line, columns)?;
+        Some(TextRange::new(
+            TextPosition::new(line, self.grapheme_to_char_on_line(start, line)),
+            TextPosition::new(line, self.grapheme_to_char_on_line(end, line)),
+        ))
+    }
+
+    /// Where `A` appends on `line`, padding a row that ends before a column edge
+    /// with spaces. Callers own the transaction.
+    pub(super) fn block_append_position(&mut self, line: usize, edge: BlockEdge) -> usize {
+        let contents = self.current_buffer().get(line).unwrap_or_default();
+        let contents = trim_line_ending(&contents);
+        let BlockEdge::Column(column) = edge else {
+            return grapheme_len(contents);
+        };
+        let tab_width = self.active_tab_width();
+        let width = display_width_with_tabs(contents, tab_width);
+        if width < column {
+            self.replace_range(
+                TextRange::insertion(TextPosition::new(line, contents.chars().count())),
+                &" ".repeat(column - width),
+            );
+            return grapheme_len(contents) + column - width;
+        }
+        column_to_grapheme_with_tabs(contents, column, tab_width)
+    }
+
+    /// Puts a blockwise register as a rectangle at the cursor's display column,
+    /// adding rows past the end of the buffer and padding rows that end before it.
+    pub(super) fn insert_blockwise(
+        &mut self,
+        x: usize,
+        y: usize,
+        contents: &Content,
+        before: bool,
+    ) {
+        let tab_width = self.active_tab_width();
+        let first = self.current_buffer().get(y).unwrap_or_default();
+        let first = trim_line_ending(&first);
+        let after = if before || first.is_empty() { x } else { x + 1 };
+        let column = grapheme_to_column_with_tabs(first, after, tab_width);
+        let block = contents.text.lines().collect::<Vec<_>>();
+        let width = block_width(&block, tab_width);
+
+        for (offset, block_line) in block.iter().enumerate() {
+            let row = y + offset;
+            while self.current_buffer().len() < row {
+                let last = self.current_buffer().len();
+                let end = TextPosition::new(last, self.line_character_len(last));
+                self.replace_range(TextRange::insertion(end), "\n");
+            }
+            let current = self.current_buffer().get(row).unwrap_or_default();
+            let current = trim_line_ending(&current);
+            let line = put_block_line(current, column, block_line, width, tab_width);
+            if line != current {
+                self.replace_range(
+                    TextRange::new(
+                        TextPosition::new(row, 0),
+                        TextPosition::new(row, current.chars().count()),
+                    ),
+                    &line,
+                );
+            }
+        }
+
+        let first = self.current_buffer().get(y).unwrap_or_default();
+        let cursor = column_to_grapheme_with_tabs(trim_line_ending(&first), column, tab_width);
+        self.move_to_text_position(TextPosition::new(
+            y,
+            self.grapheme_to_char_on_line(cursor, y),
+        ));
+    }
+}
+
+fn block_graphemes(line: &str, columns: BlockColumns, tab_width: usize) -> Option<(usize, usize)> {
+    let len = grapheme_len(line);
+    let start = column_to_grapheme_with_tabs(line, columns.start, tab_width);
+    if start >= len {
+        return None;
+    }
+    let end = match columns.end {
+        BlockEdge::LineEnd => len,
+        BlockEdge::Column(end) => {
+            column_to_grapheme_with_tabs(line, end.saturating_sub(1), tab_width) + 1
+        }
+    };
+    Some((start, end.clamp(start, len)))
+}
+
+/// Puts `block` into `lines` as a rectangle whose top-left corner is display
+/// `column` of row `y`, the way [`Editor::insert_blockwise`] edits the buffer.
+pub(super) fn put_block_rows(
+    lines: &mut Vec<String>,
+    y: usize,
+    column: usize,
+    block: &[&str],
+    tab_width: usize,
+) {
+    let width = block_width(block, tab_width);
+    for (offset, block_line) in block.iter().enumerate() {
+        while lines.len() <= y + offset {
+            lines.push(String::new());
+        }
+        let line = &mut lines[y + offset];
+        *line = put_block_line(line, column, block_line, width, tab_width);
+    }
+}
+
+fn block_width(block: &[&str], tab_width: usize) -> usize {
+    block
+        .iter()
+        .map(|line| display_width_with_tabs(line, tab_width))
+        .max()
+        .unwrap_or(0)
+}
+
+/// `line` with `text` put at display `column`. Text following the column is kept
+/// aligned by padding `text` to the block's `width`, and a row ending before the
+/// column is padded out to it.
+fn put_block_line(line: &str, column: usize, text: &str, width: usize, tab_width: usize) -> String {
+    let line_width = display_width_with_tabs(line, tab_width);
+    if line_width <= column {
+        if text.is_empty() {
+            return line.to_string();
+        }
+        return format!("{line}{}{text}", " ".repeat(column - line_width));
+    }
+    let at = grapheme_to_byte(line, column_to_grapheme_with_tabs(line, column, tab_width));
+    let padding = width.saturating_sub(display_width_with_tabs(text, tab_width));
+    format!(
+        "{}{text}{}{}",
+        &line[..at],
+        " ".repeat(padding),
+        &line[at..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_rows_follow_display_columns_and_ragged_ends() {
+        let columns = BlockColumns {
+            start: 2,
+            end: BlockEdge::Column(4),
+        };
+        assert_eq!(block_graphemes("abcdef", columns, 4), Some((2, 4)));
+        assert_eq!(block_graphemes("a\tbc", columns, 4), Some((1, 2)));
+        assert_eq!(block_graphemes("日本語", columns, 4), Some((1, 2)));
+        assert_eq!(block_graphemes("ab", columns, 4), None);
+
+        let ragged = BlockColumns {
+            start: 1,
+            end: BlockEdge::LineEnd,
+        };
+        assert_eq!(block_graphemes("abcdef", ragged, 4), Some((1, 6)));
+        assert_eq!(block_graphemes("ab", ragged, 4), Some((1, 2)));
+    }
+
+    #[test]
+    fn put_block_line_pads_short_rows_and_keeps_following_text_aligned() {
+        assert_eq!(put_block_line("ab", 4, "xy", 3, 4), "ab  xy");
+        assert_eq!(put_block_line("ab", 4, "", 3, 4), "ab");
+        assert_eq!(put_block_line("abcdef", 2, "x", 3, 4), "abx  cdef");
+        assert_eq!(put_block_line("日本語", 2, "x", 1, 4), "日x本語");
+    }
+}
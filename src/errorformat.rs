@@ -0,0 +1,488 @@
+//! Compiler output parsing for `:make`.
+//!
+//! An [`ErrorFormat`] reads one line of a build tool's output as a
+//! [`CompilerMessage`]. The built-in formats read rustc and cargo JSON diagnostics,
+//! gcc and clang, `tsc --pretty false`, eslint's default and `unix` output, and a
+//! generic `file:line[:col]: message`. Any other tool is described with a pattern
+//! using `%f`, `%l`, `%c`, `%t`, `%m` and `%%`, a small subset of Vim's
+//! `'errorformat'`. [`OutputParser`] tries its formats in order on each line and
+//! remembers the file that eslint's default output names on a line of its own.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde_json::Value;
+
+static GCC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(.+?):(\d+):(?:(\d+):)? (fatal error|error|warning|note): (.*)$").unwrap()
+});
+static TSC: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.+?)\((\d+),(\d+)\): (error|warning|message) (.*)$").unwrap());
+static ESLINT_ROW: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s+(\d+):(\d+)\s+(error|warning)\s+(.*?)(?:\s{2,}(\S+))?\s*$").unwrap()
+});
+static ESLINT_UNIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(.+?):(\d+):(\d+): (.*) \[(Error|Warning)/([^\]]+)\]$").unwrap()
+});
+static GENERIC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^((?:[A-Za-z]:)?[^:\s][^:]*):(\d+):(?:(\d+):)?\s*(.+)$").unwrap()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    Error,
+    Warning,
+    Note,
+    Info,
+}
+
+impl MessageKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Note => "note",
+            Self::Info => "info",
+        }
+    }
+
+    fn from_word(word: &str) -> Option<Self> {
+        match word.to_ascii_lowercase().as_str() {
+            "error" | "fatal error" | "error: internal compiler error" => Some(Self::Error),
+            "warning" => Some(Self::Warning),
+            "note" | "help" => Some(Self::Note),
+            "info" | "message" => Some(Self::Info),
+            _ => None,
+        }
+    }
+
+    /// Reads `%t`, which Vim matches against a single character.
+    fn from_char(character: char) -> Option<Self> {
+        match character.to_ascii_lowercase() {
+            'e' => Some(Self::Error),
+            'w' => Some(Self::Warning),
+            'n' => Some(Self::Note),
+            'i' => Some(Self::Info),
+            _ => None,
+        }
+    }
+}
+
+/// One location a build tool reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompilerMessage {
+    /// The file as the tool printed it, often relative to its working directory.
+    pub file: String,
+    /// One-based line.
+    pub line: usize,
+    /// One-based column, when the tool gives one.
+    pub column: Option<usize>,
+    /// The column counts UTF-16 code units, as JavaScript tools and rustc's
+    /// character columns do, rather than bytes.
+    pub utf16_columns: bool,
+    pub kind: Option<MessageKind>,
+    pub text: String,
+}
+
+impl CompilerMessage {
+    /// `error: text`, or just the text when the tool gave no kind.
+    pub fn summary(&self) -> String {
+        match self.kind {
+            Some(kind) => format!("{}: {}", kind.label(), self.text),
+            None => self.text.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ErrorFormat {
+    /// `cargo --message-format=json` and `rustc --error-format=json`.
+    Cargo,
+    Gcc,
+    Tsc,
+    Eslint,
+    Generic,
+    Pattern(PatternFormat),
+}
+
+impl ErrorFormat {
+    /// Reads a built-in format name (`cargo`, `gcc`, `tsc`, `eslint` or `generic`)
+    /// or a pattern containing `%f` and `%l`.
+    pub fn parse(spec: &str) -> anyhow::Result<Self> {
+        Ok(match spec {
+            "cargo" | "rustc" => Self::Cargo,
+            "gcc" | "clang" => Self::Gcc,
+            "tsc" => Self::Tsc,
+            "eslint" => Self::Eslint,
+            "generic" => Self::Generic,
+            pattern if pattern.contains('%') => Self::Pattern(PatternFormat::parse(pattern)?),
+            name => anyhow::bail!("unknown errorformat: {name}"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PatternField {
+    File,
+    Line,
+    Column,
+    Kind,
+    Message,
+}
+
+/// An errorformat pattern compiled to an anchored regex.
+#[derive(Debug, Clone)]
+pub struct PatternFormat {
+    regex: Regex,
+    fields: Vec<PatternField>,
+}
+
+impl PatternFormat {
+    fn parse(pattern: &str) -> anyhow::Result<Self> {
+        let mut source = String::from("^");
+        let mut fields = Vec::new();
+        let mut literal = String::new();
+        let mut characters = pattern.chars();
+        while let Some(character) = characters.next() {
+            if character != '%' {
+                literal.push(character);
+                continue;
+            }
+            let (field, capture) = match characters.next() {
+                Some('%') => {
+                    literal.push('%');
+                    continue;
+                }
+                Some('f') => (PatternField::File, r"(.+?)"),
+                Some('l') => (PatternField::Line, r"(\d+)"),
+                Some('c') => (PatternField::Column, r"(\d+)"),
+                Some('t') => (PatternField::Kind, r"([A-Za-z])"),
+                Some('m') => (PatternField::Message, r"(.*)"),
+                Some(other) => anyhow::bail!("unsupported %{other} in errorformat: {pattern}"),
+                None => anyhow::bail!("trailing % in errorformat: {pattern}"),
+            };
+            anyhow::ensure!(
+                !fields.contains(&field),
+                "errorformat repeats a field: {pattern}"
+            );
+            source.push_str(&regex::escape(&std::mem::take(&mut literal)));
+            source.push_str(capture);
+            fields.push(field);
+        }
+        source.push_str(&regex::escape(&literal));
+        source.push('$');
+        anyhow::ensure!(
+            fields.contains(&PatternField::File) && fields.contains(&PatternField::Line),
+            "errorformat needs %f and %l: {pattern}"
+        );
+        Ok(Self {
+            regex: Regex::new(&source)?,
+            fields,
+        })
+    }
+
+    fn parse_line(&self, line: &str) -> Option<CompilerMessage> {
+        let captures = self.regex.captures(line)?;
+        let mut message = CompilerMessage {
+            file: String::new(),
+            line: 0,
+            column: None,
+            utf16_columns: false,
+            kind: None,
+            text: String::new(),
+        };
+        for (field, value) in self.fields.iter().zip(captures.iter().skip(1)) {
+            let value = value?.as_str();
+            match field {
+                PatternField::File => message.file = value.to_string(),
+                PatternField::Line => message.line = value.parse().ok()?,
+                PatternField::Column => message.column = value.parse().ok(),
+                PatternField::Kind => {
+                    message.kind = value.chars().next().and_then(MessageKind::from_char)
+                }
+                PatternField::Message => message.text = value.trim().to_string(),
+            }
+        }
+        Some(message)
+    }
+}
+
+/// Reads a build's output a line at a time.
+#[derive(Debug, Clone)]
+pub struct OutputParser {
+    formats: Vec<ErrorFormat>,
+    /// The file eslint's default output last named.
+    eslint_file: Option<String>,
+}
+
+impl OutputParser {
+    pub fn new(formats: Vec<ErrorFormat>) -> Self {
+        Self {
+            formats,
+            eslint_file: None,
+        }
+    }
+
+    /// Returns the message the first matching format reads from `line`.
+    pub fn parse_line(&mut self, line: &str) -> Option<CompilerMessage> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.trim().is_empty() {
+            return None;
+        }
+        let mut eslint_header = false;
+        let mut parsed = None;
+        for format in &self.formats {
+            parsed = match format {
+                ErrorFormat::Cargo => parse_cargo(line),
+                ErrorFormat::Gcc => parse_gcc(line),
+                ErrorFormat::Tsc => parse_tsc(line),
+                ErrorFormat::Eslint => {
+                    eslint_header = !line.starts_with(char::is_whitespace);
+                    parse_eslint(line, self.eslint_file.as_deref())
+                }
+                ErrorFormat::Generic => parse_generic(line),
+                ErrorFormat::Pattern(pattern) => pattern.parse_line(line),
+            };
+            if parsed.is_some() {
+                break;
+            }
+        }
+        if parsed.is_none() && eslint_header && !line.starts_with('✖') {
+            self.eslint_file = Some(line.trim_end().to_string());
+        }
+        parsed
+    }
+}
+
+fn number(value: Option<regex::Match<'_>>) -> Option<usize> {
+    value?.as_str().parse().ok()
+}
+
+fn parse_cargo(line: &str) -> Option<CompilerMessage> {
+    if !line.starts_with('{') {
+        return None;
+    }
+    let value = serde_json::from_str::<Value>(line).ok()?;
+    // Cargo wraps rustc's diagnostic; rustc alone prints it bare.
+    let diagnostic = match value.get("reason").and_then(Value::as_str) {
+        Some("compiler-message") => value.get("message")?,
+        Some(_) => return None,
+        None => &value,
+    };
+    let span = diagnostic
+        .get("spans")?
+        .as_array()?
+        .iter()
+        .find(|span| span.get("is_primary").and_then(Value::as_bool) == Some(true))?;
+    let mut text = diagnostic.get("message")?.as_str()?.to_string();
+    if let Some(code) = diagnostic
+        .get("code")
+        .and_then(|code| code.get("code"))
+        .and_then(Value::as_str)
+    {
+        text = format!("{text} [{code}]");
+    }
+    Some(CompilerMessage {
+        file: span.get("file_name")?.as_str()?.to_string(),
+        line: usize::try_from(span.get("line_start")?.as_u64()?).ok()?,
+        column: span
+            .get("column_start")
+            .and_then(Value::as_u64)
+            .and_then(|column| usize::try_from(column).ok()),
+        utf16_columns: true,
+        kind: diagnostic
+            .get("level")
+            .and_then(Value::as_str)
+            .and_then(MessageKind::from_word),
+        text,
+    })
+}
+
+fn parse_gcc(line: &str) -> Option<CompilerMessage> {
+    let captures = GCC.captures(line)?;
+    Some(CompilerMessage {
+        file: captures[1].to_string(),
+        line: number(captures.get(2))?,
+        column: number(captures.get(3)),
+        utf16_columns: false,
+        kind: MessageKind::from_word(&captures[4]),
+        text: captures[5].trim().to_string(),
+    })
+}
+
+fn parse_tsc(line: &str) -> Option<CompilerMessage> {
+    let captures = TSC.captures(line)?;
+    Some(CompilerMessage {
+        file: captures[1].to_string(),
+        line: number(captures.get(2))?,
+        column: number(captures.get(3)),
+        utf16_columns: true,
+        kind: MessageKind::from_word(&captures[4]),
+        text: captures[5].trim().to_string(),
+    })
+}
+
+fn parse_eslint(line: &str, file: Option<&str>) -> Option<CompilerMessage> {
+    if let Some(captures) = ESLINT_UNIX.captures(line) {
+        return Some(CompilerMessage {
+            file: captures[1].to_string(),
+            line: number(captures.get(2))?,
+            column: number(captures.get(3)),
+            utf16_columns: true,
+            kind: MessageKind::from_word(&captures[5]),
+            text: format!("{} ({})", captures[4].trim(), &captures[6]),
+        });
+    }
+    let captures = ESLINT_ROW.captures(line)?;
+    let text = captures[4].trim();
+    Some(CompilerMessage {
+        file: file?.to_string(),
+        line: number(captures.get(1))?,
+        column: number(captures.get(2)),
+        utf16_columns: true,
+        kind: MessageKind::from_word(&captures[3]),
+        text: match captures.get(5) {
+            Some(rule) => format!("{text} ({})", rule.as_str()),
+            None => text.to_string(),
+        },
+    })
+}
+
+fn parse_generic(line: &str) -> Option<CompilerMessage> {
+    let captures = GENERIC.captures(line)?;
+    let text = captures[4].trim();
+    // Read a leading `error:` or `warning:` as the kind, as gcc-like tools print it.
+    let (kind, text) = match text.split_once(':') {
+        Some((word, rest)) => match MessageKind::from_word(word.trim()) {
+            Some(kind) => (Some(kind), rest.trim()),
+            None => (None, text),
+        },
+        None => (None, text),
+    };
+    Some(CompilerMessage {
+        file: captures[1].to_string(),
+        line: number(captures.get(2))?,
+        column: number(captures.get(3)),
+        utf16_columns: false,
+        kind,
+        text: text.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser(formats: &[&str]) -> OutputParser {
+        OutputParser::new(
+            formats
+                .iter()
+                .map(|format| ErrorFormat::parse(format).unwrap())
+                .collect(),
+        )
+    }
+
+    fn located(message: &CompilerMessage) -> (&str, usize, Option<usize>) {
+        (message.file.as_str(), message.line, message.column)
+    }
+
+    #[test]
+    fn cargo_json_reads_the_primary_span_and_skips_other_records() {
+        let mut parser = parser(&["cargo"]);
+        let line = r#"{"reason":"compiler-message","message":{"message":"mismatched types","code":{"code":"E0308"},"level":"error","spans":[{"file_name":"src/other.rs","line_start":9,"column_start":1,"is_primary":false},{"file_name":"src/main.rs","line_start":4,"column_start":18,"is_primary":true}]}}"#;
+        let message = parser.parse_line(line).unwrap();
+        assert_eq!(located(&message), ("src/main.rs", 4, Some(18)));
+        assert_eq!(message.summary(), "error: mismatched types [E0308]");
+        assert!(message.utf16_columns);
+
+        let bare = r#"{"$message_type":"diagnostic","message":"unused variable: `x`","code":null,"level":"warning","spans":[{"file_name":"lib.rs","line_start":2,"column_start":9,"is_primary":true}]}"#;
+        assert_eq!(
+            parser.parse_line(bare).unwrap().summary(),
+            "warning: unused variable: `x`"
+        );
+        assert!(parser
+            .parse_line(r#"{"reason":"compiler-artifact","target":{}}"#)
+            .is_none());
+        assert!(parser
+            .parse_line(r#"{"reason":"compiler-message","message":{"message":"aborting","level":"error","spans":[]}}"#)
+            .is_none());
+        assert!(parser.parse_line("   Compiling red v0.6.0").is_none());
+    }
+
+    #[test]
+    fn gcc_tsc_and_generic_lines_read_their_locations() {
+        let mut parser = parser(&["gcc", "tsc", "generic"]);
+        let gcc = parser
+            .parse_line("src/a.c:12:5: fatal error: missing.h: No such file")
+            .unwrap();
+        assert_eq!(located(&gcc), ("src/a.c", 12, Some(5)));
+        assert_eq!(gcc.summary(), "error: missing.h: No such file");
+
+        let tsc = parser
+            .parse_line("src/app.ts(3,7): error TS2322: Type 'string' is not assignable.")
+            .unwrap();
+        assert_eq!(located(&tsc), ("src/app.ts", 3, Some(7)));
+        assert_eq!(
+            tsc.summary(),
+            "error: TS2322: Type 'string' is not assignable."
+        );
+
+        let pytest = parser
+            .parse_line("tests/test_a.py:41: AssertionError")
+            .unwrap();
+        assert_eq!(located(&pytest), ("tests/test_a.py", 41, None));
+        assert_eq!(pytest.summary(), "AssertionError");
+        let windows = parser
+            .parse_line(r"C:\src\main.go:8:2: warning: shadowed")
+            .unwrap();
+        assert_eq!(located(&windows), (r"C:\src\main.go", 8, Some(2)));
+        assert_eq!(windows.kind, Some(MessageKind::Warning));
+
+        assert!(parser.parse_line("    at run (src/a.js:3:4)").is_none());
+        assert!(parser
+            .parse_line("error: could not compile `red` due to 2 previous errors")
+            .is_none());
+    }
+
+    #[test]
+    fn eslint_default_output_uses_the_file_named_above_its_rows() {
+        let mut parser = parser(&["eslint"]);
+        assert!(parser.parse_line("/work/src/a.js").is_none());
+        let row = parser
+            .parse_line("  1:10  error  'foo' is defined but never used  no-unused-vars")
+            .unwrap();
+        assert_eq!(located(&row), ("/work/src/a.js", 1, Some(10)));
+        assert_eq!(
+            row.summary(),
+            "error: 'foo' is defined but never used (no-unused-vars)"
+        );
+        assert!(parser
+            .parse_line("✖ 1 problem (1 error, 0 warnings)")
+            .is_none());
+
+        let unix = parser
+            .parse_line("/work/b.js:2:1: Unexpected console statement. [Warning/no-console]")
+            .unwrap();
+        assert_eq!(located(&unix), ("/work/b.js", 2, Some(1)));
+        assert_eq!(
+            unix.summary(),
+            "warning: Unexpected console statement. (no-console)"
+        );
+    }
+
+    #[test]
+    fn patterns_read_vim_errorformat_fields() {
+        let mut parser = parser(&["[%t] %f (%l): %m", "%f|%l col %c| %m"]);
+        let message = parser.parse_line("[W] lib/x.rb (12): 50% done").unwrap();
+        assert_eq!(located(&message), ("lib/x.rb", 12, None));
+        assert_eq!(message.summary(), "warning: 50% done");
+        let message = parser.parse_line("a.txt|3 col 4| bad").unwrap();
+        assert_eq!(located(&message), ("a.txt", 3, Some(4)));
+        assert!(parser.parse_line("a.txt|3| bad").is_none());
+
+        assert!(ErrorFormat::parse("%f only").is_err());
+        assert!(ErrorFormat::parse("%f:%l:%x").is_err());
+        assert!(ErrorFormat::parse("%f:%l:%l").is_err());
+        assert!(ErrorFormat::parse("cobol").is_err());
+    }
+}
@@ -0,0 +1,275 @@
+//! Ex line range syntax.
+//!
+//! A range is up to two addresses separated by `,` or `;`, or `%` for the whole
+//! buffer. An address is a line number, `.`, `$`, a mark (`'a`), or a forward or
+//! backward search (`/pat/`, `?pat?`), followed by any number of `+N`/`-N` offsets.
+//! An address made only of offsets counts from the current line, and a missing
+//! address on either side of the separator means the current line. With `;` the
+//! first address becomes the current line before the second one is resolved.
+//!
+//! This module only parses; resolving addresses against a buffer happens in the
+//! editor.
+
+/// Where an address starts counting from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum AddressBase {
+    /// A one-based line number. `0` is only meaningful to commands like `:m0`.
+    Line(usize),
+    Current,
+    Last,
+    Mark(char),
+    /// `/pattern/`; an empty pattern reuses the last search.
+    SearchForward(String),
+    /// `?pattern?`; an empty pattern reuses the last search.
+    SearchBackward(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Address {
+    pub(crate) base: AddressBase,
+    pub(crate) offset: i64,
+}
+
+impl Address {
+    const CURRENT: Address = Address {
+        base: AddressBase::Current,
+        offset: 0,
+    };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LineRange {
+    Whole,
+    Addresses {
+        start: Address,
+        end: Option<Address>,
+        /// The separator was `;`.
+        set_current: bool,
+    },
+}
+
+/// Splits the leading range off an Ex command line. Returns an empty range when the
+/// command does not start with one.
+pub(crate) fn split(command: &str) -> (&str, &str) {
+    match scan_range(command) {
+        Some((_, end)) => command.split_at(end),
+        None => ("", command),
+    }
+}
+
+/// Parses a range that [`split`] returned.
+pub(crate) fn parse(text: &str) -> anyhow::Result<LineRange> {
+    match scan_range(text) {
+        Some((range, end)) if end == text.len() => Ok(range),
+        _ => anyhow::bail!("invalid range: {text}"),
+    }
+}
+
+/// Parses a single address, such as the destination of `:m` or `:t`.
+pub(crate) fn parse_address(text: &str) -> anyhow::Result<Address> {
+    match scan_address(text, 0) {
+        Some((address, end)) if end == text.len() => Ok(address),
+        _ => anyhow::bail!("invalid address: {text}"),
+    }
+}
+
+fn scan_range(input: &str) -> Option<(LineRange, usize)> {
+    if input.starts_with('%') {
+        return Some((LineRange::Whole, 1));
+    }
+    let (start, position) = match scan_address(input, 0) {
+        Some((address, end)) => (Some(address), end),
+        None => (None, 0),
+    };
+    let separator = input[position..].chars().next();
+    if !matches!(separator, Some(',' | ';')) {
+        return start.map(|start| {
+            (
+                LineRange::Addresses {
+                    start,
+                    end: None,
+                    set_current: false,
+                },
+                position,
+            )
+        });
+    }
+    let (end, position) = match scan_address(input, position + 1) {
+        Some((address, end)) => (address, end),
+        None => (Address::CURRENT, position + 1),
+    };
+    Some((
+        LineRange::Addresses {
+            start: start.unwrap_or(Address::CURRENT),
+            end: Some(end),
+            set_current: separator == Some(';'),
+        },
+        position,
+    ))
+}
+
+fn scan_address(input: &str, start: usize) -> Option<(Address, usize)> {
+    let rest = &input[start..];
+    let mut chars = rest.chars();
+    let (base, mut position) = match chars.next() {
+        Some(character) if character.is_ascii_digit() => {
+            let digits = leading_digits(rest);
+            (
+                Some(AddressBase::Line(parse_count(&rest[..digits]))),
+                start + digits,
+            )
+        }
+        Some('.') => (Some(AddressBase::Current), start + 1),
+        Some('$') => (Some(AddressBase::Last), start + 1),
+        Some('\'') => {
+            let mark = chars.next()?;
+            (Some(AddressBase::Mark(mark)), start + 1 + mark.len_utf8())
+        }
+        Some(delimiter @ ('/' | '?')) => {
+            let (pattern, length) = scan_pattern(&rest[1..], delimiter);
+            let base = if delimiter == '/' {
+                AddressBase::SearchForward(pattern)
+            } else {
+                AddressBase::SearchBackward(pattern)
+            };
+            (Some(base), start + 1 + length)
+        }
+        _ => (None, start),
+    };
+
+    let mut offset = 0i64;
+    let mut has_offset = false;
+    while let Some(sign @ ('+' | '-')) = input[position..].chars().next() {
+        position += 1;
+        let digits = leading_digits(&input[position..]);
+        let amount = if digits == 0 {
+            1
+        } else {
+            i64::try_from(parse_count(&input[position..position + digits])).unwrap_or(i64::MAX)
+        };
+        position += digits;
+        offset = if sign == '+' {
+            offset.saturating_add(amount)
+        } else {
+            offset.saturating_sub(amount)
+        };
+        has_offset = true;
+    }
+
+    if base.is_none() && !has_offset {
+        return None;
+    }
+    Some((
+        Address {
+            base: base.unwrap_or(AddressBase::Current),
+            offset,
+        },
+        position,
+    ))
+}
+
+/// Reads a search pattern up to an unescaped `delimiter` or the end of the input.
+/// Returns the pattern with `\{delimiter}` unescaped and the bytes consumed,
+/// including the closing delimiter.
+fn scan_pattern(input: &str, delimiter: char) -> (String, usize) {
+    let mut pattern = String::new();
+    let mut chars = input.char_indices();
+    while let Some((index, character)) = chars.next() {
+        if character == delimiter {
+            return (pattern, index + delimiter.len_utf8());
+        }
+        if character == '\\' {
+            match chars.next() {
+                Some((_, escaped)) if escaped == delimiter => pattern.push(escaped),
+                Some((_, escaped)) => {
+                    pattern.push('\\');
+                    pattern.push(escaped);
+                }
+                None => pattern.push('\\'),
+            }
+            continue;
+        }
+        pattern.push(character);
+    }
+    (pattern, input.len())
+}
+
+fn leading_digits(input: &str) -> usize {
+    input.bytes().take_while(u8::is_ascii_digit).count()
+}
+
+fn parse_count(digits: &str) -> usize {
+    digits.parse().unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(base: AddressBase, offset: i64) -> Address {
+        Address { base, offset }
+    }
+
+    #[test]
+    fn splits_ranges_off_commands() {
+        assert_eq!(split("%s/a/b/"), ("%", "s/a/b/"));
+        assert_eq!(split("'<,'>normal A;"), ("'<,'>", "normal A;"));
+        assert_eq!(split(".,$d x"), (".,$", "d x"));
+        assert_eq!(split("/foo/,/bar/-1y"), ("/foo/,/bar/-1", "y"));
+        assert_eq!(split("?a\\?b?m0"), ("?a\\?b?", "m0"));
+        assert_eq!(split("+2"), ("+2", ""));
+        assert_eq!(split("write"), ("", "write"));
+        assert_eq!(split("'"), ("", "'"));
+    }
+
+    #[test]
+    fn parses_addresses_and_separators() {
+        assert_eq!(
+            parse("3,$").unwrap(),
+            LineRange::Addresses {
+                start: address(AddressBase::Line(3), 0),
+                end: Some(address(AddressBase::Last, 0)),
+                set_current: false,
+            }
+        );
+        assert_eq!(
+            parse("'a+1;+--").unwrap(),
+            LineRange::Addresses {
+                start: address(AddressBase::Mark('a'), 1),
+                end: Some(address(AddressBase::Current, -1)),
+                set_current: true,
+            }
+        );
+        assert_eq!(
+            parse(",5").unwrap(),
+            LineRange::Addresses {
+                start: Address::CURRENT,
+                end: Some(address(AddressBase::Line(5), 0)),
+                set_current: false,
+            }
+        );
+        assert_eq!(
+            parse("/a\\/b/").unwrap(),
+            LineRange::Addresses {
+                start: address(AddressBase::SearchForward("a/b".to_string()), 0),
+                end: None,
+                set_current: false,
+            }
+        );
+        assert_eq!(parse("%").unwrap(), LineRange::Whole);
+        assert!(parse("1,2d").is_err());
+    }
+
+    #[test]
+    fn parses_destination_addresses() {
+        assert_eq!(
+            parse_address("0").unwrap(),
+            address(AddressBase::Line(0), 0)
+        );
+        assert_eq!(
+            parse_address("$-2").unwrap(),
+            address(AddressBase::Last, -2)
+        );
+        assert!(parse_address("x").is_err());
+    }
+}
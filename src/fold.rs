@@ -0,0 +1,379 @@
+//! Window-local folds over logical buffer lines.
+//!
+//! A [`FoldState`] belongs to one window and describes the buffer that window is
+//! displaying. Folds are inclusive line ranges that may nest; a closed fold hides every
+//! line after its first, and the outermost closed fold wins when closed folds overlap.
+//! Manual folds come from `zf`, while language-server and tree-sitter folds are
+//! replaced wholesale when their source is refreshed, keeping the open or closed state
+//! of folds that still start on the same line.
+//!
+//! Ranges are line numbers rather than anchors, so editors must call
+//! [`FoldState::adjust_for_edit`] for every buffer replacement to keep them aligned.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{buffer::BufferId, undo::TextRange};
+
+/// Where a fold came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FoldSource {
+    /// Created with `zf`.
+    Manual,
+    /// Reported by `textDocument/foldingRange`.
+    Lsp,
+    /// Derived from the structural tree-sitter queries.
+    Syntax,
+}
+
+/// One inclusive range of logical lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fold {
+    /// First line, which stays visible as the fold's placeholder row.
+    pub start: usize,
+    /// Last line hidden by the fold.
+    pub end: usize,
+    /// Whether the fold currently hides its lines.
+    #[serde(default)]
+    pub closed: bool,
+    /// Provider that created the fold.
+    pub source: FoldSource,
+}
+
+impl Fold {
+    pub fn contains(&self, line: usize) -> bool {
+        (self.start..=self.end).contains(&line)
+    }
+
+    fn span(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+/// Folds for the buffer currently displayed by a window.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FoldState {
+    buffer_id: Option<BufferId>,
+    folds: Vec<Fold>,
+    /// Buffer revision the provider folds were computed from.
+    provided: Option<u64>,
+}
+
+impl FoldState {
+    /// Restores persisted folds that are claimed by the first buffer they are bound to.
+    pub(crate) fn restored(folds: Vec<Fold>) -> Self {
+        Self {
+            buffer_id: None,
+            folds,
+            provided: None,
+        }
+    }
+
+    /// Binds folds to the buffer the window displays. Restored folds are unbound until
+    /// then; folds bound to another buffer are discarded.
+    pub(crate) fn bind(&mut self, buffer_id: BufferId) {
+        if self.buffer_id.is_none() {
+            self.buffer_id = Some(buffer_id);
+        } else {
+            self.folds_mut(buffer_id);
+        }
+    }
+
+    /// Folds for `buffer_id`, or none when the window has switched buffers since.
+    pub(crate) fn folds(&self, buffer_id: BufferId) -> &[Fold] {
+        if self.buffer_id == Some(buffer_id) {
+            &self.folds
+        } else {
+            &[]
+        }
+    }
+
+    /// Mutable folds for `buffer_id`, discarding folds left from another buffer.
+    pub(crate) fn folds_mut(&mut self, buffer_id: BufferId) -> &mut Vec<Fold> {
+        if self.buffer_id != Some(buffer_id) {
+            self.buffer_id = Some(buffer_id);
+            self.folds.clear();
+            self.provided = None;
+        }
+        &mut self.folds
+    }
+
+    /// Adds a fold, replacing any fold over the same lines.
+    pub(crate) fn add(&mut self, buffer_id: BufferId, fold: Fold) {
+        let folds = self.folds_mut(buffer_id);
+        folds.retain(|existing| (existing.start, existing.end) != (fold.start, fold.end));
+        folds.push(fold);
+        sort_folds(folds);
+    }
+
+    /// Folds worth persisting, regardless of which buffer claimed them.
+    pub(crate) fn snapshot(&self) -> Vec<Fold> {
+        self.folds.clone()
+    }
+
+    /// Outermost closed ranges in line order.
+    pub(crate) fn closed_ranges(&self, buffer_id: BufferId) -> Vec<(usize, usize)> {
+        closed_ranges(self.folds(buffer_id))
+    }
+
+    /// Revision of `buffer_id` the provider folds were last computed from.
+    pub(crate) fn provided_revision(&self, buffer_id: BufferId) -> Option<u64> {
+        self.provided.filter(|_| self.buffer_id == Some(buffer_id))
+    }
+
+    /// Replaces provider folds while keeping manual folds and the closed state of
+    /// provider folds that still start on the same line.
+    pub(crate) fn replace_provided(
+        &mut self,
+        buffer_id: BufferId,
+        revision: u64,
+        source: FoldSource,
+        ranges: &[(usize, usize)],
+    ) {
+        self.folds_mut(buffer_id);
+        self.provided = Some(revision);
+        let folds = &mut self.folds;
+        let closed = folds
+            .iter()
+            .filter(|fold| fold.source != FoldSource::Manual && fold.closed)
+            .map(|fold| fold.start)
+            .collect::<Vec<_>>();
+        folds.retain(|fold| fold.source == FoldSource::Manual);
+        for &(start, end) in ranges {
+            if end <= start
+                || folds
+                    .iter()
+                    .any(|fold| fold.start == start && fold.end == end)
+            {
+                continue;
+            }
+            folds.push(Fold {
+                start,
+                end,
+                closed: closed.contains(&start),
+                source,
+            });
+        }
+        sort_folds(folds);
+    }
+
+    /// Shifts folds after `range` was replaced by text containing `inserted` line
+    /// breaks.
+    pub(crate) fn adjust_for_edit(
+        &mut self,
+        buffer_id: BufferId,
+        range: TextRange,
+        inserted: usize,
+    ) {
+        if self.buffer_id != Some(buffer_id) || self.folds.is_empty() {
+            return;
+        }
+        let (first, removed_end, inserted_end) = if range.start.character == 0
+            && range.end.character == 0
+            && range.end.line > range.start.line
+        {
+            // Whole lines were replaced; the line at `range.end` only moves.
+            (
+                range.start.line,
+                range.end.line,
+                range.start.line + inserted,
+            )
+        } else {
+            (
+                range.start.line,
+                range.end.line + 1,
+                range.start.line + inserted + 1,
+            )
+        };
+        if removed_end == inserted_end {
+            return;
+        }
+        let map_start = |line: usize| {
+            if line < first {
+                line
+            } else if line >= removed_end {
+                line + inserted_end - removed_end
+            } else {
+                line.min(inserted_end.saturating_sub(1)).max(first)
+            }
+        };
+        let map_end = |line: usize| {
+            if line < first {
+                Some(line)
+            } else if line >= removed_end {
+                Some(line + inserted_end - removed_end)
+            } else if inserted_end > first {
+                Some(line.min(inserted_end - 1))
+            } else {
+                first.checked_sub(1)
+            }
+        };
+        self.folds.retain_mut(|fold| {
+            let Some(end) = map_end(fold.end) else {
+                return false;
+            };
+            fold.start = map_start(fold.start);
+            fold.end = end;
+            fold.start <= fold.end
+        });
+        sort_folds(&mut self.folds);
+        self.folds
+            .dedup_by(|right, left| left.start == right.start && left.end == right.end);
+    }
+}
+
+fn sort_folds(folds: &mut [Fold]) {
+    folds.sort_by_key(|fold| (fold.start, std::cmp::Reverse(fold.end)));
+}
+
+/// Outermost closed ranges in line order.
+pub(crate) fn closed_ranges(folds: &[Fold]) -> Vec<(usize, usize)> {
+    let mut closed = folds
+        .iter()
+        .filter(|fold| fold.closed)
+        .map(|fold| (fold.start, fold.end))
+        .collect::<Vec<_>>();
+    closed.sort_unstable_by_key(|(start, end)| (*start, std::cmp::Reverse(*end)));
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(closed.len());
+    for (start, end) in closed {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Closed range hiding `line`, if any.
+pub(crate) fn closed_range_at(ranges: &[(usize, usize)], line: usize) -> Option<(usize, usize)> {
+    let index = ranges.partition_point(|(start, _)| *start <= line);
+    index
+        .checked_sub(1)
+        .map(|index| ranges[index])
+        .filter(|(_, end)| line <= *end)
+}
+
+/// Index of the innermost fold containing `line` that satisfies `filter`.
+pub(crate) fn innermost_fold(
+    folds: &[Fold],
+    line: usize,
+    filter: impl Fn(&Fold) -> bool,
+) -> Option<usize> {
+    folds
+        .iter()
+        .enumerate()
+        .filter(|(_, fold)| fold.contains(line) && filter(fold))
+        .min_by_key(|(_, fold)| fold.span())
+        .map(|(index, _)| index)
+}
+
+/// Index of the outermost fold containing `line` that satisfies `filter`.
+pub(crate) fn outermost_fold(
+    folds: &[Fold],
+    line: usize,
+    filter: impl Fn(&Fold) -> bool,
+) -> Option<usize> {
+    folds
+        .iter()
+        .enumerate()
+        .filter(|(_, fold)| fold.contains(line) && filter(fold))
+        .max_by_key(|(_, fold)| fold.span())
+        .map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::undo::TextPosition;
+
+    fn fold(start: usize, end: usize, closed: bool) -> Fold {
+        Fold {
+            start,
+            end,
+            closed,
+            source: FoldSource::Manual,
+        }
+    }
+
+    fn state(folds: Vec<Fold>) -> (FoldState, BufferId) {
+        let id = buffer_id();
+        let mut state = FoldState::restored(folds);
+        state.bind(id);
+        (state, id)
+    }
+
+    fn buffer_id() -> BufferId {
+        crate::buffer::Buffer::new(None, String::new()).id()
+    }
+
+    fn range(start: (usize, usize), end: (usize, usize)) -> TextRange {
+        TextRange::new(
+            TextPosition::new(start.0, start.1),
+            TextPosition::new(end.0, end.1),
+        )
+    }
+
+    #[test]
+    fn closed_ranges_merge_nested_folds_into_the_outermost() {
+        let folds = [
+            fold(2, 4, true),
+            fold(1, 8, true),
+            fold(10, 12, false),
+            fold(11, 12, true),
+        ];
+
+        let ranges = closed_ranges(&folds);
+
+        assert_eq!(ranges, vec![(1, 8), (11, 12)]);
+        assert_eq!(closed_range_at(&ranges, 5), Some((1, 8)));
+        assert_eq!(closed_range_at(&ranges, 9), None);
+        assert_eq!(closed_range_at(&ranges, 12), Some((11, 12)));
+    }
+
+    #[test]
+    fn folds_follow_line_insertions_and_deletions() {
+        let (mut state, id) = state(vec![fold(2, 4, true), fold(6, 8, false)]);
+
+        state.adjust_for_edit(id, range((0, 0), (0, 0)), 1);
+        assert_eq!(state.closed_ranges(id), vec![(3, 5)]);
+
+        state.adjust_for_edit(id, range((3, 0), (6, 0)), 0);
+        assert_eq!(state.folds(id), &[fold(4, 6, false)]);
+
+        state.adjust_for_edit(id, range((5, 2), (5, 2)), 2);
+        assert_eq!(state.folds(id), &[fold(4, 8, false)]);
+    }
+
+    #[test]
+    fn provider_refresh_keeps_manual_folds_and_closed_starts() {
+        let (mut state, id) = state(vec![fold(0, 3, false)]);
+        state.replace_provided(id, 1, FoldSource::Lsp, &[(5, 9), (12, 14)]);
+        state.folds_mut(id)[1].closed = true;
+
+        state.replace_provided(id, 2, FoldSource::Syntax, &[(0, 3), (5, 10), (20, 20)]);
+
+        assert_eq!(
+            state.folds(id),
+            &[
+                fold(0, 3, false),
+                Fold {
+                    start: 5,
+                    end: 10,
+                    closed: true,
+                    source: FoldSource::Syntax,
+                },
+            ]
+        );
+        assert_eq!(state.provided_revision(id), Some(2));
+    }
+
+    #[test]
+    fn switching_buffers_discards_window_folds() {
+        let (mut state, id) = state(vec![fold(1, 2, true)]);
+        let other = buffer_id();
+
+        assert!(state.folds(other).is_empty());
+        assert!(state.folds_mut(other).is_empty());
+        assert!(state.folds(id).is_empty());
+    }
+}
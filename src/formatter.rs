@@ -2,11 +2,12 @@
 //!
 //! Language packs describe stdin-to-stdout tools. Red resolves project-local
 //! executables before `PATH`, launches them without a shell, and bounds each run.
+//! Ex filters such as `:{range}!cmd` reuse the same runner.
 
 use std::{
     env,
     path::{Path, PathBuf},
-    process::Stdio,
+    process::{ExitStatus, Stdio},
     time::Duration,
 };
 
@@ -29,6 +30,22 @@ pub struct FormattedDocument {
     pub contents: String,
 }
 
+/// The result of an Ex filter command that ran to completion.
+#[derive(Debug)]
+pub struct FilterOutput {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+struct BoundedRun {
+    write_result: std::io::Result<()>,
+    status: ExitStatus,
+    stdout: Vec<u8>,
+    stdout_overflowed: bool,
+    stderr: Vec<u8>,
+}
+
 /// Returns the workspace used for formatter discovery and execution.
 #[must_use]
 pub fn workspace_root(file: &Path, root_markers: &[String]) -> PathBuf {
@@ -150,7 +167,75 @@ pub async fn format_document(
     command
         .args(arguments)
         .current_dir(&workspace)
-        .envs(environment)
+        .envs(environment);
+    let label = format!("formatter {}", config.name);
+    let run = run_bounded(command, &label, contents).await?;
+    if !run.status.success() {
+        let stderr = String::from_utf8_lossy(&run.stderr);
+        let detail = stderr.trim();
+        return Err(if detail.is_empty() {
+            anyhow!("{label} exited with {}", run.status)
+        } else {
+            anyhow!("{label} exited with {}: {detail}", run.status)
+        });
+    }
+    run.write_result
+        .with_context(|| format!("failed to write document to {label}"))?;
+    anyhow::ensure!(!run.stdout_overflowed, "{label} returned more than 16 MiB");
+    let stdout = run.stdout;
+    let contents =
+        String::from_utf8(stdout).with_context(|| format!("{label} returned non-UTF-8 output"))?;
+    Ok(Some(FormattedDocument {
+        name: if config.name.trim().is_empty() {
+            config.command.clone()
+        } else {
+            config.name.clone()
+        },
+        contents,
+    }))
+}
+
+/// Runs an Ex filter such as `:{range}!sort -u` in `directory`, feeding it `input`.
+///
+/// The command line is split into words like a shell would split it, honoring quotes
+/// and backslash escapes, but no shell runs it: pipes, redirections and variables
+/// are passed to the program as plain arguments.
+pub async fn run_filter(
+    command_line: &str,
+    directory: &Path,
+    input: &str,
+) -> anyhow::Result<FilterOutput> {
+    let words = split_command_line(command_line)?;
+    let Some((program, arguments)) = words.split_first() else {
+        anyhow::bail!("missing command");
+    };
+    let executable = resolve_command(program, directory)
+        .ok_or_else(|| anyhow!("command not found: {program}"))?;
+    let mut command = Command::new(&executable);
+    command.args(arguments).current_dir(directory);
+    let label = format!("command {program}");
+    let run = run_bounded(command, &label, input).await?;
+    // A filter may exit without reading all of its input, like `head`.
+    if let Err(error) = run.write_result {
+        anyhow::ensure!(
+            error.kind() == std::io::ErrorKind::BrokenPipe,
+            "failed to write input to {label}: {error}"
+        );
+    }
+    anyhow::ensure!(!run.stdout_overflowed, "{label} returned more than 16 MiB");
+    Ok(FilterOutput {
+        status: run.status,
+        stdout: String::from_utf8(run.stdout)
+            .with_context(|| format!("{label} returned non-UTF-8 output"))?,
+        stderr: String::from_utf8_lossy(&run.stderr).into_owned(),
+    })
+}
+
+/// Runs `command` without a shell, writing `input` to its stdin while reading its
+/// output. Both output streams are capped and the whole run is bounded by
+/// [`FORMAT_TIMEOUT`]; `label` names the program in errors.
+async fn run_bounded(mut command: Command, label: &str, input: &str) -> anyhow::Result<BoundedRun> {
+    command
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -158,20 +243,20 @@ pub async fn format_document(
 
     let mut child = command
         .spawn()
-        .with_context(|| format!("failed to launch formatter {}", config.name))?;
+        .with_context(|| format!("failed to launch {label}"))?;
     let mut stdin = child
         .stdin
         .take()
-        .ok_or_else(|| anyhow!("formatter {} has no stdin", config.name))?;
+        .ok_or_else(|| anyhow!("{label} has no stdin"))?;
     let stdout = child
         .stdout
         .take()
-        .ok_or_else(|| anyhow!("formatter {} has no stdout", config.name))?;
+        .ok_or_else(|| anyhow!("{label} has no stdout"))?;
     let stderr = child
         .stderr
         .take()
-        .ok_or_else(|| anyhow!("formatter {} has no stderr", config.name))?;
-    let input = contents.as_bytes();
+        .ok_or_else(|| anyhow!("{label} has no stderr"))?;
+    let input = input.as_bytes();
     let run = async move {
         let write_input = async move {
             stdin.write_all(input).await?;
@@ -187,36 +272,76 @@ pub async fn format_document(
     };
     let (write_result, stdout_result, stderr_result, status_result) = timeout(FORMAT_TIMEOUT, run)
         .await
-        .map_err(|_| anyhow!("formatter {} timed out after 30 seconds", config.name))?;
-    let status = status_result?;
+        .map_err(|_| anyhow!("{label} timed out after 30 seconds"))?;
     let (stdout, stdout_overflowed) = stdout_result?;
     let (stderr, _) = stderr_result?;
-    if !status.success() {
-        let stderr = String::from_utf8_lossy(&stderr);
-        let detail = stderr.trim();
-        return Err(if detail.is_empty() {
-            anyhow!("formatter {} exited with {}", config.name, status)
-        } else {
-            anyhow!("formatter {} exited with {}: {detail}", config.name, status)
-        });
+    Ok(BoundedRun {
+        write_result,
+        status: status_result?,
+        stdout,
+        stdout_overflowed,
+        stderr,
+    })
+}
+
+/// Splits a command line into words. Single quotes keep their contents literally,
+/// double quotes allow `\"` and `\\`, and a backslash outside quotes escapes the
+/// next character.
+pub(crate) fn split_command_line(command_line: &str) -> anyhow::Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command_line.chars();
+    while let Some(character) = chars.next() {
+        match character {
+            ' ' | '\t' => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(quoted) => word.push(quoted),
+                        None => anyhow::bail!("unterminated ' in command"),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped @ ('"' | '\\')) => word.push(escaped),
+                            Some(other) => {
+                                word.push('\\');
+                                word.push(other);
+                            }
+                            None => anyhow::bail!("unterminated \" in command"),
+                        },
+                        Some(quoted) => word.push(quoted),
+                        None => anyhow::bail!("unterminated \" in command"),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                word.push(chars.next().unwrap_or('\\'));
+            }
+            character => {
+                in_word = true;
+                word.push(character);
+            }
+        }
     }
-    write_result
-        .with_context(|| format!("failed to write document to formatter {}", config.name))?;
-    anyhow::ensure!(
-        !stdout_overflowed,
-        "formatter {} returned more than 16 MiB",
-        config.name
-    );
-    let contents = String::from_utf8(stdout)
-        .with_context(|| format!("formatter {} returned non-UTF-8 output", config.name))?;
-    Ok(Some(FormattedDocument {
-        name: if config.name.trim().is_empty() {
-            config.command.clone()
-        } else {
-            config.name.clone()
-        },
-        contents,
-    }))
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
 }
 
 #[cfg(test)]
@@ -269,4 +394,28 @@ mod tests {
         assert_eq!(formatted.name, "Test Format");
         assert_eq!(formatted.contents, "HELLO\n");
     }
+
+    #[test]
+    fn splits_command_lines_with_quotes_and_escapes() {
+        assert_eq!(
+            split_command_line(r#"sed -e 's/a b/c/' "x \"y\"" z\ w ''"#).unwrap(),
+            ["sed", "-e", "s/a b/c/", "x \"y\"", "z w", ""]
+        );
+        assert!(split_command_line("echo 'open").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn filters_input_through_a_command_without_a_shell() {
+        let temp = tempfile::tempdir().unwrap();
+        let output = run_filter("tr a-z A-Z", temp.path(), "hello\n")
+            .await
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, "HELLO\n");
+
+        let output = run_filter("echo a | b", temp.path(), "").await.unwrap();
+        assert_eq!(output.stdout, "a | b\n");
+        assert!(run_filter("", temp.path(), "").await.is_err());
+    }
 }
@@ -879,6 +879,129 @@ impl Highlighter {
         Ok(styles)
     }
 
+    /// Returns the byte ranges of `code` that spell checking looks at: the comment
+    /// and string captures of the language's highlight query, or for Markdown every
+    /// byte outside code blocks, code spans, and link destinations.
+    pub fn spell_ranges(
+        &mut self,
+        language_id: &str,
+        code: &str,
+    ) -> anyhow::Result<Vec<Range<usize>>> {
+        let registry = Arc::clone(&self.registry);
+        let Some(definition) = registry.languages.get(language_id) else {
+            return Ok(Vec::new());
+        };
+        if matches!(definition.specialized, Some(SpecializedHighlighter::Husk)) {
+            let comment = self.husk_styles.comment.as_ref();
+            let string = self.husk_styles.string.as_ref();
+            return Ok(merge_ranges(
+                highlight_husk(code, &self.husk_styles)
+                    .into_iter()
+                    .filter(|info| {
+                        comment.is_some_and(|style| *style == info.style)
+                            || string.is_some_and(|style| *style == info.style)
+                    })
+                    .map(|info| info.start..info.end)
+                    .collect(),
+            ));
+        }
+        let Some(grammar) = &definition.grammar else {
+            return Ok(Vec::new());
+        };
+        if definition.highlight_queries.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.ensure_language_highlighter(definition, grammar)?;
+        let Some(highlighter) = self.highlighters.get_mut(&definition.id) else {
+            return Ok(Vec::new());
+        };
+        // Reuse the highlight parse of the same text; otherwise parse without
+        // disturbing the tree kept for incremental highlighting.
+        let tree = match &highlighter.cached_tree {
+            Some(cached) if cached.source == code => cached.tree.clone(),
+            _ => match highlighter.parser.parse(code, None) {
+                Some(tree) => tree,
+                None => return Ok(Vec::new()),
+            },
+        };
+
+        let markdown = language_id == "markdown";
+        let mut ranges = Vec::new();
+        let mut cursor = QueryCursor::new();
+        let mut captures = cursor.captures(&highlighter.query, tree.root_node(), code.as_bytes());
+        while let Some((mat, index)) = captures.next() {
+            let capture = mat.captures[*index];
+            let name = highlighter.query.capture_names()[capture.index as usize];
+            let selected = if markdown {
+                [
+                    "markup.raw",
+                    "markup.underline.link",
+                    "punctuation.definition.raw",
+                ]
+                .iter()
+                .any(|scope| name.starts_with(scope))
+            } else {
+                spell_checked_capture(name)
+            };
+            if selected {
+                ranges.push(capture.node.start_byte()..capture.node.end_byte());
+            }
+        }
+        if !markdown {
+            return Ok(merge_ranges(ranges));
+        }
+
+        ranges.extend(markdown_code_spans(code));
+        let mut prose = Vec::new();
+        let mut start = 0;
+        for excluded in merge_ranges(ranges) {
+            if excluded.start > start {
+                prose.push(start..excluded.start);
+            }
+            start = start.max(excluded.end);
+        }
+        if start < code.len() {
+            prose.push(start..code.len());
+        }
+        Ok(prose)
+    }
+
+    fn ensure_language_highlighter(
+        &mut self,
+        definition: &RuntimeLanguageDefinition,
+        grammar: &GrammarSource,
+    ) -> anyhow::Result<()> {
+        if self.highlighters.contains_key(&definition.id) {
+            return Ok(());
+        }
+        let language = grammar_language(grammar);
+        let mut parser = Parser::new();
+        parser.set_language(&language)?;
+        let highlight_query = definition.highlight_queries.join("\n");
+        let query = Query::new(&language, &highlight_query)?;
+        let capture_styles = query
+            .capture_names()
+            .iter()
+            .map(|scope| self.theme.get_style(scope))
+            .collect();
+        let injection_query = definition
+            .injection_query
+            .as_deref()
+            .map(|query| Query::new(&language, query))
+            .transpose()?;
+        self.highlighters.insert(
+            definition.id.clone(),
+            LanguageHighlighter {
+                parser,
+                query,
+                injection_query,
+                capture_styles,
+                cached_tree: None,
+            },
+        );
+        Ok(())
+    }
+
     /// Recompute one independent commit line or preserve one stable Husk token.
     fn reuse_specialized_token_highlight(
         &mut self,
@@ -1308,34 +1431,7 @@ impl Highlighter {
         if definition.highlight_queries.is_empty() {
             return Ok(Vec::new());
         }
-
-        if !self.highlighters.contains_key(&definition.id) {
-            let language = grammar_language(grammar);
-            let mut parser = Parser::new();
-            parser.set_language(&language)?;
-            let highlight_query = definition.highlight_queries.join("\n");
-            let query = Query::new(&language, &highlight_query)?;
-            let capture_styles = query
-                .capture_names()
-                .iter()
-                .map(|scope| self.theme.get_style(scope))
-                .collect();
-            let injection_query = definition
-                .injection_query
-                .as_deref()
-                .map(|query| Query::new(&language, query))
-                .transpose()?;
-            self.highlighters.insert(
-                definition.id.clone(),
-                LanguageHighlighter {
-                    parser,
-                    query,
-                    injection_query,
-                    capture_styles,
-                    cached_tree: None,
-                },
-            );
-        }
+        self.ensure_language_highlighter(definition, grammar)?;
 
         let mut colors = Vec::new();
         let mut raw_injections = Vec::new();
@@ -2075,6 +2171,67 @@ fn powershell_sensitive_identifier(identifier: &str) -> bool {
     )
 }
 
+/// Comments and ordinary strings hold prose; regular expressions, escapes, and
+/// special strings such as symbols and paths do not.
+fn spell_checked_capture(scope: &str) -> bool {
+    let within = |parent: &str| {
+        scope == parent
+            || scope
+                .strip_prefix(parent)
+                .is_some_and(|rest| rest.starts_with('.'))
+    };
+    within("comment")
+        || (within("string")
+            && ![
+                "string.regexp",
+                "string.regex",
+                "string.escape",
+                "string.special",
+            ]
+            .iter()
+            .any(|excluded| scope.starts_with(excluded)))
+}
+
+/// Sorts `ranges` and joins the ones that overlap or touch.
+fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_unstable_by_key(|range| (range.start, range.end));
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Finds backtick code spans on single lines, which the block-level Markdown
+/// grammar leaves inside paragraphs.
+fn markdown_code_spans(code: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for line in code.split_inclusive('\n') {
+        let mut search = 0;
+        while let Some(open) = line[search..].find('`').map(|index| search + index) {
+            let ticks = line[open..]
+                .bytes()
+                .take_while(|byte| *byte == b'`')
+                .count();
+            let fence = &line[open..open + ticks];
+            let Some(close) = line[open + ticks..]
+                .find(fence)
+                .map(|index| open + ticks + index)
+            else {
+                break;
+            };
+            spans.push(offset + open..offset + close + ticks);
+            search = close + ticks;
+        }
+        offset += line.len();
+    }
+    spans
+}
+
 fn capture_refines_equal_range(scope: &str) -> bool {
     match scope {
         "property" | "escape" | "string.escape" => true,
@@ -2103,10 +2260,8 @@ fn collect_injections(
         for capture in mat.captures {
             let capture_name = query.capture_names()[capture.index as usize];
             match capture_name {
-                "injection.language" => {
-                    if language_name.is_none() {
-                        language_name = capture.node.utf8_text(code.as_bytes()).ok();
-                    }
+                "injection.language" if language_name.is_none() => {
+                    language_name = capture.node.utf8_text(code.as_bytes()).ok();
                 }
                 "injection.content" => {
                     content = Some((capture.node.start_byte(), capture.node.end_byte()));
@@ -0,0 +1,343 @@
+//! Targets and arithmetic for `Ctrl-a`/`Ctrl-x`.
+//!
+//! [`find_target`] picks what an increment applies to on one line the way Vim does:
+//! the number under the cursor, or else the first one after it. Decimal numbers take a
+//! directly preceding `-` as their sign; `0x` and `0b` numbers are unsigned, wrap at 64
+//! bits and keep their digit count and letter case. Leading-zero octal, booleans,
+//! weekday names and ISO dates are opt-in through [`IncrementConfig`]. Positions are
+//! character indexes into the line.
+
+use chrono::{Datelike, Months, NaiveDate, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+use crate::config::IncrementConfig;
+
+/// What an increment target is and how it changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncrementKind {
+    Decimal,
+    Hex,
+    Octal,
+    Binary,
+    Boolean,
+    Weekday,
+    /// An ISO `YYYY-MM-DD` date changed in the unit under the cursor.
+    Date(DateUnit),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateUnit {
+    Year,
+    Month,
+    Day,
+}
+
+/// A character range of a line that an increment replaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct IncrementTarget {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) kind: IncrementKind,
+}
+
+const WEEKDAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// Finds the target under or after `cursor`.
+pub(crate) fn find_target(
+    line: &str,
+    cursor: usize,
+    config: &IncrementConfig,
+) -> Option<IncrementTarget> {
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut index = 0;
+    while index < chars.len() {
+        let target = if chars[index].is_ascii_digit() {
+            date_at(&chars, index, cursor, config).or_else(|| number_at(&chars, index, config))
+        } else if chars[index].is_alphabetic() {
+            word_at(&chars, index, config)
+        } else {
+            None
+        };
+        let Some(target) = target else {
+            index += 1;
+            continue;
+        };
+        if target.end > cursor {
+            return Some(target);
+        }
+        index = target.end;
+    }
+    None
+}
+
+fn number_at(chars: &[char], start: usize, config: &IncrementConfig) -> Option<IncrementTarget> {
+    let prefixed = |markers: [char; 2], digit: fn(&char) -> bool| {
+        (chars[start] == '0'
+            && chars.get(start + 1).is_some_and(|c| markers.contains(c))
+            && chars.get(start + 2).is_some_and(digit))
+        .then(|| start + 2 + chars[start + 2..].iter().take_while(|c| digit(c)).count())
+    };
+    if let Some(end) = prefixed(['x', 'X'], char::is_ascii_hexdigit) {
+        return Some(IncrementTarget {
+            start,
+            end,
+            kind: IncrementKind::Hex,
+        });
+    }
+    if let Some(end) = prefixed(['b', 'B'], |c| matches!(c, '0' | '1')) {
+        return Some(IncrementTarget {
+            start,
+            end,
+            kind: IncrementKind::Binary,
+        });
+    }
+    let end = start
+        + chars[start..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+    let digits = &chars[start..end];
+    if config.octal && digits.len() > 1 && digits[0] == '0' && digits.iter().all(|c| *c < '8') {
+        return Some(IncrementTarget {
+            start,
+            end,
+            kind: IncrementKind::Octal,
+        });
+    }
+    let signed = start > 0 && chars[start - 1] == '-';
+    Some(IncrementTarget {
+        start: if signed { start - 1 } else { start },
+        end,
+        kind: IncrementKind::Decimal,
+    })
+}
+
+fn date_at(
+    chars: &[char],
+    start: usize,
+    cursor: usize,
+    config: &IncrementConfig,
+) -> Option<IncrementTarget> {
+    if !config.dates || chars.len() < start + 10 {
+        return None;
+    }
+    let text = chars[start..start + 10].iter().collect::<String>();
+    let shaped = text.chars().enumerate().all(|(offset, c)| {
+        matches!(offset, 4 | 7) == (c == '-') && (c == '-' || c.is_ascii_digit())
+    });
+    if !shaped
+        || chars.get(start + 10).is_some_and(char::is_ascii_digit)
+        || NaiveDate::parse_from_str(&text, "%Y-%m-%d").is_err()
+    {
+        return None;
+    }
+    let unit = match cursor.checked_sub(start) {
+        Some(0..=4) => DateUnit::Year,
+        Some(5..=7) => DateUnit::Month,
+        _ => DateUnit::Day,
+    };
+    Some(IncrementTarget {
+        start,
+        end: start + 10,
+        kind: IncrementKind::Date(unit),
+    })
+}
+
+fn word_at(chars: &[char], start: usize, config: &IncrementConfig) -> Option<IncrementTarget> {
+    if start > 0 && (chars[start - 1].is_alphanumeric() || chars[start - 1] == '_') {
+        return None;
+    }
+    let end = start
+        + chars[start..]
+            .iter()
+            .take_while(|c| c.is_alphanumeric() || **c == '_')
+            .count();
+    let word = chars[start..end].iter().collect::<String>().to_lowercase();
+    let kind = if config.booleans && matches!(word.as_str(), "true" | "false") {
+        IncrementKind::Boolean
+    } else if config.weekdays && weekday_index(&word).is_some() {
+        IncrementKind::Weekday
+    } else {
+        return None;
+    };
+    Some(IncrementTarget { start, end, kind })
+}
+
+fn weekday_index(word: &str) -> Option<usize> {
+    WEEKDAYS
+        .iter()
+        .position(|day| *day == word || (word.len() == 3 && day.starts_with(word)))
+}
+
+/// Returns `text` changed by `delta`, or `None` when it is not a `kind` target.
+pub(crate) fn apply(text: &str, delta: i64, kind: IncrementKind) -> Option<String> {
+    match kind {
+        IncrementKind::Decimal => {
+            let (negative, digits) = match text.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, text),
+            };
+            let magnitude = digits.parse::<i128>().ok()?;
+            let value = if negative { -magnitude } else { magnitude };
+            let value = (value + i128::from(delta)).clamp(i64::MIN.into(), i64::MAX.into());
+            // Zero padding is kept, as Vim does when octal is not recognized.
+            let width = if digits.len() > 1 && digits.starts_with('0') {
+                digits.len()
+            } else {
+                0
+            };
+            let sign = if value < 0 { "-" } else { "" };
+            Some(format!("{sign}{:0width$}", value.unsigned_abs()))
+        }
+        IncrementKind::Hex | IncrementKind::Binary | IncrementKind::Octal => {
+            let (prefix, digits, radix) = match kind {
+                IncrementKind::Hex => (&text[..2], &text[2..], 16),
+                IncrementKind::Binary => (&text[..2], &text[2..], 2),
+                _ => ("0", &text[1..], 8),
+            };
+            let value = u64::from_str_radix(digits, radix).ok()?;
+            let value = value.wrapping_add_signed(delta);
+            let width = digits.len();
+            let formatted = match radix {
+                16 if digits.chars().any(|c| c.is_ascii_uppercase()) => {
+                    format!("{value:0width$X}")
+                }
+                16 => format!("{value:0width$x}"),
+                2 => format!("{value:0width$b}"),
+                _ => format!("{value:0width$o}"),
+            };
+            Some(format!("{prefix}{formatted}"))
+        }
+        IncrementKind::Boolean => {
+            let flipped = match text.to_lowercase().as_str() {
+                "true" if delta % 2 != 0 => "false",
+                "false" if delta % 2 != 0 => "true",
+                "true" | "false" => return Some(text.to_string()),
+                _ => return None,
+            };
+            Some(match_case(text, flipped))
+        }
+        IncrementKind::Weekday => {
+            let index = weekday_index(&text.to_lowercase())?;
+            let day = WEEKDAYS[(index as i64 + delta).rem_euclid(7) as usize];
+            let day = if text.chars().count() == 3 {
+                &day[..3]
+            } else {
+                day
+            };
+            Some(match_case(text, day))
+        }
+        IncrementKind::Date(unit) => {
+            let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+            let date = match unit {
+                DateUnit::Day => date.checked_add_signed(TimeDelta::try_days(delta)?)?,
+                DateUnit::Month | DateUnit::Year => {
+                    let months = if unit == DateUnit::Year {
+                        delta.checked_mul(12)?
+                    } else {
+                        delta
+                    };
+                    let shift = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+                    if months < 0 {
+                        date.checked_sub_months(shift)?
+                    } else {
+                        date.checked_add_months(shift)?
+                    }
+                }
+            };
+            (0..=9999)
+                .contains(&date.year())
+                .then(|| date.format("%Y-%m-%d").to_string())
+        }
+    }
+}
+
+/// Spells `word` in the capitalization of `original`: lower, upper, or title case.
+fn match_case(original: &str, word: &str) -> String {
+    if original.chars().all(|c| c.is_uppercase()) {
+        word.to_uppercase()
+    } else if original.chars().next().is_some_and(char::is_uppercase) {
+        let mut chars = word.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    } else {
+        word.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_formats() -> IncrementConfig {
+        IncrementConfig {
+            octal: true,
+            booleans: true,
+            weekdays: true,
+            dates: true,
+        }
+    }
+
+    fn increment(line: &str, cursor: usize, delta: i64, config: &IncrementConfig) -> String {
+        let target = find_target(line, cursor, config).unwrap();
+        let chars = line.chars().collect::<Vec<_>>();
+        let text = chars[target.start..target.end].iter().collect::<String>();
+        let replacement = apply(&text, delta, target.kind).unwrap();
+        let before = chars[..target.start].iter().collect::<String>();
+        let after = chars[target.end..].iter().collect::<String>();
+        format!("{before}{replacement}{after}")
+    }
+
+    #[test]
+    fn finds_the_number_under_or_after_the_cursor() {
+        let config = IncrementConfig::default();
+        assert_eq!(increment("a 12 b 7", 0, 1, &config), "a 13 b 7");
+        assert_eq!(increment("a 12 b 7", 3, 1, &config), "a 13 b 7");
+        assert_eq!(increment("a 12 b 7", 4, 1, &config), "a 12 b 8");
+        assert_eq!(increment("x-1", 0, 3, &config), "x2");
+        assert_eq!(increment("value 5", 0, -7, &config), "value -2");
+        assert_eq!(increment("id007", 0, 1, &config), "id008");
+        assert!(find_target("no numbers", 0, &config).is_none());
+        assert!(find_target("1 and", 2, &config).is_none());
+    }
+
+    #[test]
+    fn prefixed_numbers_keep_their_width_and_case_and_wrap_unsigned() {
+        let config = IncrementConfig::default();
+        assert_eq!(increment("0x0f", 0, 1, &config), "0x10");
+        assert_eq!(increment("0xFE", 3, 1, &config), "0xFF");
+        assert_eq!(increment("0x00", 0, -1, &config), "0xffffffffffffffff");
+        assert_eq!(increment("0b0101", 0, 1, &config), "0b0110");
+        assert_eq!(increment("-0x10", 0, 1, &config), "-0x11");
+    }
+
+    #[test]
+    fn leading_zero_octal_booleans_weekdays_and_dates_are_opt_in() {
+        let config = all_formats();
+        assert_eq!(increment("007", 0, 1, &config), "010");
+        assert_eq!(increment("089", 0, 1, &config), "090");
+        assert_eq!(increment("let x = true;", 0, 1, &config), "let x = false;");
+        assert_eq!(increment("TRUE", 0, 2, &config), "TRUE");
+        assert_eq!(increment("Sunday", 0, 1, &config), "Monday");
+        assert_eq!(increment("wed", 0, -3, &config), "sun");
+        assert_eq!(increment("due 2024-02-28", 0, 2, &config), "due 2024-03-01");
+        assert_eq!(increment("2024-01-31", 6, 1, &config), "2024-02-29");
+        assert_eq!(increment("2024-02-29", 1, 1, &config), "2025-02-28");
+
+        let defaults = IncrementConfig::default();
+        assert!(find_target("true Sunday", 0, &defaults).is_none());
+        assert_eq!(increment("2024-01-31", 0, 1, &defaults), "2025-01-31");
+    }
+}
@@ -53,6 +53,18 @@ pub(crate) fn indent_for_line(
     }
 }
 
+/// Returns the indentation Insert-mode `Ctrl-t`, or with `outdent` `Ctrl-d`, gives a
+/// line indented by `columns`. Like Vim, the result is rounded to a multiple of
+/// `shift_width`.
+pub(crate) fn shift_indent_columns(columns: usize, shift_width: usize, outdent: bool) -> usize {
+    let shift_width = shift_width.max(1);
+    if outdent {
+        columns.saturating_sub(1) / shift_width * shift_width
+    } else {
+        (columns / shift_width + 1) * shift_width
+    }
+}
+
 /// Mirrors the useful parts of Vim's `indentkeys` for the bundled providers.
 pub(crate) fn should_reindent_after(
     language_id: Option<&str>,
@@ -362,7 +374,7 @@ fn analyze_python(lines: &[&str], tab_width: usize) -> Vec<PythonLineAnalysis> {
 
 #[cfg(test)]
 mod tests {
-    use super::{indent_for_line, should_reindent_after, IndentDecision};
+    use super::{indent_for_line, shift_indent_columns, should_reindent_after, IndentDecision};
 
     fn python(document: &str, line: usize) -> IndentDecision {
         indent_for_line(Some("python"), document, line, 4, 4)
@@ -423,4 +435,14 @@ mod tests {
         assert!(!should_reindent_after(Some("python"), 'x', "value = x"));
         assert!(!should_reindent_after(Some("rust"), ':', "label:"));
     }
+
+    #[test]
+    fn insert_mode_shifts_round_to_the_shift_width() {
+        assert_eq!(shift_indent_columns(0, 4, false), 4);
+        assert_eq!(shift_indent_columns(6, 4, false), 8);
+        assert_eq!(shift_indent_columns(8, 4, false), 12);
+        assert_eq!(shift_indent_columns(6, 4, true), 4);
+        assert_eq!(shift_indent_columns(8, 4, true), 4);
+        assert_eq!(shift_indent_columns(0, 4, true), 0);
+    }
 }
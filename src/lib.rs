@@ -31,9 +31,13 @@ pub mod copilot;
 pub mod dispatcher;
 pub mod editing;
 pub mod editor;
+pub mod errorformat;
+mod ex_range;
+pub mod fold;
 pub mod formatter;
 pub mod headless;
 pub mod highlighter;
+pub mod increment;
 mod indent;
 pub mod inline_assist;
 pub mod inline_context;
@@ -41,6 +45,7 @@ pub mod inline_history;
 pub mod keyboard;
 pub mod language;
 mod learn;
+pub mod line_sort;
 pub mod logger;
 pub mod lsp;
 pub mod matchit;
@@ -50,9 +55,13 @@ pub mod plugin;
 pub mod preferences;
 mod self_check;
 pub mod session;
+pub mod spell;
 pub mod splash;
+pub mod substitute_expression;
+mod surround;
 pub mod sync;
 pub mod syntax_indent;
+mod tab_page;
 pub mod terminal_input;
 pub mod terminal_output;
 pub mod text_layout;
@@ -63,6 +72,7 @@ pub mod ui;
 pub mod undo;
 pub mod unicode_utils;
 pub mod utils;
+pub mod vim_regex;
 pub mod whats_new;
 pub mod window;
 
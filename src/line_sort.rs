@@ -0,0 +1,330 @@
+//! Line ordering for `:sort`.
+//!
+//! [`SortOptions::parse`] reads the arguments of `:sort[!] [n|x|f|b|o] [i] [u] [r]
+//! [/pattern/]` and [`sort_lines`] orders lines the way Vim does. The sort is stable
+//! and `!` reverses its result. With a pattern the key is the text after the first
+//! match, or the match itself with `r`; lines without a match keep their order ahead
+//! of the sorted lines. Numeric sorts read the first number in the key, and lines
+//! without one sort first. `u` drops a line equal to the one kept before it,
+//! ignoring case with `i`.
+
+use std::cmp::Ordering;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKind {
+    #[default]
+    Text,
+    Decimal,
+    Hex,
+    Octal,
+    Binary,
+    Float,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortOptions {
+    pub kind: SortKind,
+    pub ignore_case: bool,
+    pub unique: bool,
+    pub reverse: bool,
+    /// Sort on the pattern match instead of the text after it (`r`).
+    pub use_match: bool,
+    /// `None` without a pattern; an empty pattern reuses the last search.
+    pub pattern: Option<String>,
+}
+
+impl SortOptions {
+    /// Parses the flags and pattern after `:sort`. `reverse` is the command's `!`.
+    pub fn parse(arguments: &str, reverse: bool) -> anyhow::Result<Self> {
+        let mut options = Self {
+            reverse,
+            ..Self::default()
+        };
+        let mut kinds = Vec::new();
+        let mut chars = arguments.char_indices();
+        while let Some((index, flag)) = chars.next() {
+            match flag {
+                ' ' | '\t' => {}
+                'i' => options.ignore_case = true,
+                'u' => options.unique = true,
+                'r' => options.use_match = true,
+                'n' => kinds.push(SortKind::Decimal),
+                'x' => kinds.push(SortKind::Hex),
+                'o' => kinds.push(SortKind::Octal),
+                'b' => kinds.push(SortKind::Binary),
+                'f' => kinds.push(SortKind::Float),
+                delimiter if !delimiter.is_alphanumeric() && !matches!(delimiter, '"' | '\\') => {
+                    anyhow::ensure!(options.pattern.is_none(), "sort accepts only one pattern");
+                    let rest = &arguments[index + delimiter.len_utf8()..];
+                    let (pattern, length) = pattern_until(rest, delimiter);
+                    options.pattern = Some(pattern);
+                    for _ in 0..rest[..length].chars().count() {
+                        chars.next();
+                    }
+                }
+                flag => anyhow::bail!("invalid sort flag: {flag}"),
+            }
+        }
+        kinds.dedup();
+        anyhow::ensure!(
+            kinds.len() <= 1,
+            "sort accepts only one of n, x, o, b and f"
+        );
+        options.kind = kinds.first().copied().unwrap_or_default();
+        Ok(options)
+    }
+}
+
+/// Returns the pattern up to an unescaped `delimiter` and the bytes consumed,
+/// including the delimiter when present.
+fn pattern_until(text: &str, delimiter: char) -> (String, usize) {
+    let mut pattern = String::new();
+    let mut chars = text.char_indices();
+    while let Some((index, character)) = chars.next() {
+        if character == delimiter {
+            return (pattern, index + delimiter.len_utf8());
+        }
+        if character == '\\' {
+            match chars.next() {
+                Some((_, escaped)) if escaped == delimiter => pattern.push(escaped),
+                Some((_, escaped)) => {
+                    pattern.push('\\');
+                    pattern.push(escaped);
+                }
+                None => pattern.push('\\'),
+            }
+            continue;
+        }
+        pattern.push(character);
+    }
+    (pattern, text.len())
+}
+
+#[derive(Debug, Clone)]
+enum Key {
+    Text(String),
+    /// `None` when the key has no number.
+    Integer(Option<i128>),
+    Float(Option<f64>),
+}
+
+impl Key {
+    fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Text(left), Self::Text(right)) => left.cmp(right),
+            (Self::Integer(left), Self::Integer(right)) => left.cmp(right),
+            (Self::Float(left), Self::Float(right)) => match (left, right) {
+                (Some(left), Some(right)) => left.total_cmp(right),
+                (left, right) => left.is_some().cmp(&right.is_some()),
+            },
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+/// Sorts `lines`. Each line comes with the text its key is read from: the whole
+/// line, or the columns of a Visual block.
+pub fn sort_lines(
+    lines: Vec<(String, String)>,
+    options: &SortOptions,
+    pattern: Option<&Regex>,
+) -> Vec<String> {
+    let mut keyed = lines
+        .into_iter()
+        .map(|(line, source)| {
+            let key = sort_key(&source, options, pattern);
+            (key, line)
+        })
+        .collect::<Vec<_>>();
+    // `sort_by` is stable, so equal keys keep their order.
+    keyed.sort_by(|(left, _), (right, _)| left.compare(right));
+    if options.reverse {
+        keyed.reverse();
+    }
+
+    let mut sorted: Vec<String> = Vec::with_capacity(keyed.len());
+    for (_, line) in keyed {
+        let duplicate = options.unique
+            && sorted.last().is_some_and(|previous| {
+                if options.ignore_case {
+                    previous.to_lowercase() == line.to_lowercase()
+                } else {
+                    *previous == line
+                }
+            });
+        if !duplicate {
+            sorted.push(line);
+        }
+    }
+    sorted
+}
+
+fn sort_key(source: &str, options: &SortOptions, pattern: Option<&Regex>) -> Key {
+    let text = match pattern {
+        None => source,
+        Some(pattern) => match crate::vim_regex::find_spans(pattern, source).next() {
+            Some(found) if options.use_match => &source[found],
+            Some(found) => &source[found.end..],
+            None => "",
+        },
+    };
+    match options.kind {
+        SortKind::Text if options.ignore_case => Key::Text(text.to_lowercase()),
+        SortKind::Text => Key::Text(text.to_string()),
+        SortKind::Decimal => Key::Integer(first_integer(text, 10)),
+        SortKind::Hex => Key::Integer(first_integer(text, 16)),
+        SortKind::Octal => Key::Integer(first_integer(text, 8)),
+        SortKind::Binary => Key::Integer(first_integer(text, 2)),
+        SortKind::Float => Key::Float(first_float(text)),
+    }
+}
+
+/// Reads the first number in `radix`, with an optional `-` sign and, for hex and
+/// binary, an optional `0x`/`0b` prefix.
+fn first_integer(text: &str, radix: u32) -> Option<i128> {
+    let bytes = text.as_bytes();
+    let start = bytes
+        .iter()
+        .position(|byte| char::from(*byte).is_digit(radix))?;
+    let mut digits_start = start;
+    let prefix = match radix {
+        16 => Some(b'x'),
+        2 => Some(b'b'),
+        _ => None,
+    };
+    if let Some(prefix) = prefix {
+        if bytes[start] == b'0'
+            && bytes
+                .get(start + 1)
+                .is_some_and(|byte| byte.to_ascii_lowercase() == prefix)
+            && bytes
+                .get(start + 2)
+                .is_some_and(|byte| char::from(*byte).is_digit(radix))
+        {
+            digits_start = start + 2;
+        }
+    }
+    let digits_end = digits_start
+        + bytes[digits_start..]
+            .iter()
+            .take_while(|byte| char::from(**byte).is_digit(radix))
+            .count();
+    let value = i128::from_str_radix(&text[digits_start..digits_end], radix).unwrap_or(i128::MAX);
+    let negative = start > 0 && bytes[start - 1] == b'-';
+    Some(if negative { -value } else { value })
+}
+
+/// Reads the first decimal number, with an optional sign, fraction and exponent.
+fn first_float(text: &str) -> Option<f64> {
+    let bytes = text.as_bytes();
+    let start = bytes.iter().enumerate().position(|(index, byte)| {
+        byte.is_ascii_digit()
+            || (*byte == b'.' && bytes.get(index + 1).is_some_and(u8::is_ascii_digit))
+    })?;
+    let start = if start > 0 && matches!(bytes[start - 1], b'-' | b'+') {
+        start - 1
+    } else {
+        start
+    };
+    let length = bytes[start..]
+        .iter()
+        .take_while(|byte| {
+            byte.is_ascii_digit() || matches!(byte, b'.' | b'e' | b'E' | b'+' | b'-')
+        })
+        .count();
+    (1..=length)
+        .rev()
+        .find_map(|length| text[start..start + length].parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(lines: &[&str], arguments: &str, reverse: bool) -> Vec<String> {
+        let options = SortOptions::parse(arguments, reverse).unwrap();
+        let pattern = options
+            .pattern
+            .as_deref()
+            .map(|pattern| Regex::new(pattern).unwrap());
+        sort_lines(
+            lines
+                .iter()
+                .map(|line| (line.to_string(), line.to_string()))
+                .collect(),
+            &options,
+            pattern.as_ref(),
+        )
+    }
+
+    #[test]
+    fn parses_flags_in_any_order_with_a_pattern() {
+        let options = SortOptions::parse(" u i n /a\\/b/ r", true).unwrap();
+        assert_eq!(
+            options,
+            SortOptions {
+                kind: SortKind::Decimal,
+                ignore_case: true,
+                unique: true,
+                reverse: true,
+                use_match: true,
+                pattern: Some("a/b".to_string()),
+            }
+        );
+        assert!(SortOptions::parse("n x", false).is_err());
+        assert!(SortOptions::parse("z", false).is_err());
+    }
+
+    #[test]
+    fn sorts_text_stably_with_case_unique_and_reverse() {
+        assert_eq!(
+            sorted(&["b", "B", "a", "b"], "", false),
+            ["B", "a", "b", "b"]
+        );
+        assert_eq!(sorted(&["b", "B", "a", "b"], "u", false), ["B", "a", "b"]);
+        assert_eq!(sorted(&["b", "B", "a", "b"], "i u", false), ["a", "b"]);
+        assert_eq!(sorted(&["b", "c", "a"], "", true), ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn numeric_sorts_put_lines_without_numbers_first() {
+        assert_eq!(
+            sorted(&["x10", "none", "x-2", "x9", "also none"], "n", false),
+            ["none", "also none", "x-2", "x9", "x10"]
+        );
+        assert_eq!(
+            sorted(&["0x1F", "ff", "0XA"], "x", false),
+            ["0XA", "0x1F", "ff"]
+        );
+        assert_eq!(
+            sorted(&["0b11", "10", "0b1"], "b", false),
+            ["0b1", "10", "0b11"]
+        );
+        assert_eq!(sorted(&["17", "7", "10"], "o", false), ["7", "10", "17"]);
+        assert_eq!(
+            sorted(&["v 1.5e1", "v -0.5", "v .25", "v"], "f", false),
+            ["v", "v -0.5", "v .25", "v 1.5e1"]
+        );
+    }
+
+    #[test]
+    fn patterns_pick_the_key_after_or_inside_the_match() {
+        let lines = ["b=2 z", "a=3 y", "none", "c=1 x"];
+        assert_eq!(
+            sorted(&lines, "/=\\d /", false),
+            ["none", "c=1 x", "a=3 y", "b=2 z"]
+        );
+        assert_eq!(
+            sorted(&lines, "n r /\\d/", false),
+            ["none", "c=1 x", "b=2 z", "a=3 y"]
+        );
+        assert_eq!(
+            sorted(&lines, "n r /\\d/", true),
+            ["a=3 y", "b=2 z", "c=1 x", "none"]
+        );
+    }
+}
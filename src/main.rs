@@ -211,7 +211,7 @@ async fn run_editor_inner(args: Args) -> anyhow::Result<()> {
     LOGGER.get_or_init(|| logger);
     let preferences = PreferencesStore::load(preferences_file);
 
-    loaded.config.startup_file_count = args.files.len();
+    loaded.config.startup_files = args.files.clone();
     loaded.config.startup_session_resumed = args.resume;
 
     if let Some(root) = &args.root {
@@ -268,6 +268,8 @@ async fn run_editor_inner(args: Args) -> anyhow::Result<()> {
         }
     }
     editor.enable_diagnostic_cache(Config::path("cache/lsp-diagnostics"));
+    editor.enable_undo_files(Config::path("cache/undo"));
+    editor.set_spell_directory(Config::path("spell"));
     editor.set_session_store(session_store);
 
     if let Some(session) = &args.core_session {
@@ -2573,9 +2573,16 @@ pub struct PanelManager {
     pending_focused: Option<String>,
     last_row_click: Option<RowClick>,
     last_text_link_click: Option<TextLinkClick>,
+    /// Rows above every pane that belong to the editor, such as the tab line.
+    top_inset: usize,
 }
 
 impl PanelManager {
+    /// Starts pane placement `rows` below the top of the terminal.
+    pub(crate) fn set_top_inset(&mut self, rows: usize) {
+        self.top_inset = rows;
+    }
+
     pub(crate) fn set_presentation(&mut self, presentation: PanelPresentation) {
         if self.presentation == presentation {
             return;
@@ -2957,6 +2964,22 @@ impl PanelManager {
         }
     }
 
+    /// Retitles a live panel without resetting its rows, selection, or layout.
+    pub fn set_panel_title(&mut self, id: &str, title: Option<String>) -> bool {
+        let config = if let Some(panel) = self.panels.get_mut(id) {
+            &mut panel.config
+        } else if let Some(panel) = self.text_panels.get_mut(id) {
+            &mut panel.config
+        } else {
+            return false;
+        };
+        if config.title == title {
+            return false;
+        }
+        config.title = title;
+        true
+    }
+
     /// Replaces a row panel with a compact tree while preserving its stable selection.
     pub fn update_tree_panel(&mut self, id: &str, model: TreePanelModel) {
         if let Some(panel) = self.panels.get_mut(id) {
@@ -4583,6 +4606,8 @@ impl PanelManager {
         terminal_width: usize,
         terminal_height: usize,
     ) -> Vec<PanelPlacement> {
+        let top = self.top_inset;
+        let terminal_height = terminal_height.saturating_sub(top);
         match &self.presentation {
             PanelPresentation::Hidden => return Vec::new(),
             PanelPresentation::Zoomed { id, .. } => {
@@ -4591,7 +4616,7 @@ impl PanelManager {
                     .then(|| PanelPlacement {
                         id: id.clone(),
                         x: 0,
-                        y: 0,
+                        y: top,
                         width: terminal_width,
                         height: terminal_height.saturating_sub(2),
                     })
@@ -4655,7 +4680,7 @@ impl PanelManager {
                 placements.push(PanelPlacement {
                     id: id.clone(),
                     x,
-                    y,
+                    y: y + top,
                     width,
                     height,
                 });
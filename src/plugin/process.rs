@@ -282,6 +282,15 @@ impl ProcessManager {
         Ok(process_id)
     }
 
+    /// Lets `owner` run `command`, for processes the editor starts itself, such as
+    /// `:make`, rather than a plugin with configured permissions.
+    pub fn allow_command(&mut self, owner: &str, command: &str) {
+        let permissions = self.permissions.entry(owner.to_string()).or_default();
+        if !permissions.process.iter().any(|allowed| allowed == command) {
+            permissions.process.push(command.to_string());
+        }
+    }
+
     pub fn kill(&mut self, plugin_name: &str, process_id: &str) -> anyhow::Result<()> {
         self.require_process_permission(plugin_name)?;
         self.refresh_events();
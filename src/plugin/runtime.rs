@@ -2181,6 +2181,15 @@ impl RedHost {
                 request_id,
                 include_declaration: args.first().and_then(Value::as_bool).unwrap_or(true),
             },
+            "SetQuickfixList" => {
+                let list = args
+                    .first()
+                    .map(value_to_json)
+                    .map(serde_json::from_value)
+                    .transpose()?
+                    .ok_or_else(|| anyhow::anyhow!("SetQuickfixList requires a list"))?;
+                PluginRequest::SetQuickfixList { request_id, list }
+            }
             "ResolveThemeStyle" => {
                 let spec = args
                     .first()
@@ -9147,11 +9156,9 @@ mod tests {
                 PluginRequest::SetTextPanelStatus {
                     id,
                     status: Some(status),
-                } => {
-                    if id == "agent-conversation" && status.label == "Stopping…" {
-                        assert!(reset_status, "stopping must restart the elapsed timer");
-                        stopping_status = status.busy;
-                    }
+                } if id == "agent-conversation" && status.label == "Stopping…" => {
+                    assert!(reset_status, "stopping must restart the elapsed timer");
+                    stopping_status = status.busy;
                 }
                 PluginRequest::AgentCloseSession { .. } => {
                     panic!("cancellation must not close an active stream before completion")
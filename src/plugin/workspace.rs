@@ -995,15 +995,15 @@ impl PluginWorkspace {
                         .clamp(20, width.saturating_sub(21).max(20)),
                 );
             }
-            "toggle" | "back_toggle" | "focus_next" | "focus_previous" => {
-                if self.model.detail_document.is_some() {
-                    self.focus = match self.focus {
-                        WorkspaceFocus::Rows => WorkspaceFocus::Detail,
-                        WorkspaceFocus::Detail => WorkspaceFocus::Rows,
-                    };
-                    if self.focus == WorkspaceFocus::Rows {
-                        self.rows_hidden = false;
-                    }
+            "toggle" | "back_toggle" | "focus_next" | "focus_previous"
+                if self.model.detail_document.is_some() =>
+            {
+                self.focus = match self.focus {
+                    WorkspaceFocus::Rows => WorkspaceFocus::Detail,
+                    WorkspaceFocus::Detail => WorkspaceFocus::Rows,
+                };
+                if self.focus == WorkspaceFocus::Rows {
+                    self.rows_hidden = false;
                 }
             }
             "focus_rows" => {
@@ -35,3 +35,24 @@
 
 (command
   argument: (word) @parameter.inner)
+
+(c_style_for_statement
+  body: (_) @loop.inner) @loop.outer
+
+(compound_statement
+  .
+  "{"
+  _+ @block.inner
+  "}") @block.outer
+
+(do_group
+  .
+  "do"
+  _+ @block.inner
+  "done") @block.outer
+
+(subshell
+  .
+  "("
+  _+ @block.inner
+  ")") @block.outer
@@ -178,3 +178,82 @@
   ","? @parameter.outer)
 
 (comment) @comment.outer
+
+(block) @block.outer
+
+(block
+  .
+  "{"
+  _+ @block.inner
+  "}")
+
+(if_statement) @conditional.outer
+
+(if_statement
+  condition: (_) @conditional.inner)
+
+(if_statement
+  (block
+    .
+    "{"
+    _+ @conditional.inner
+    "}"))
+
+(switch_statement) @conditional.outer
+
+(switch_section) @conditional.inner
+
+(for_statement) @loop.outer
+
+(foreach_statement) @loop.outer
+
+(while_statement) @loop.outer
+
+(do_statement) @loop.outer
+
+[
+  (for_statement
+    (block
+      .
+      "{"
+      _+ @loop.inner
+      "}"))
+  (foreach_statement
+    (block
+      .
+      "{"
+      _+ @loop.inner
+      "}"))
+  (while_statement
+    (block
+      .
+      "{"
+      _+ @loop.inner
+      "}"))
+  (do_statement
+    (block
+      .
+      "{"
+      _+ @loop.inner
+      "}"))
+]
+
+(local_declaration_statement
+  (variable_declaration
+    (variable_declarator
+      .
+      (identifier) @assignment.lhs
+      (_) @assignment.inner @assignment.rhs .))) @assignment.outer
+
+(local_declaration_statement
+  (variable_declaration
+    (variable_declarator
+      .
+      (identifier) @assignment.inner)))
+
+(assignment_expression
+  left: (_) @assignment.lhs
+  right: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(assignment_expression
+  left: (_) @assignment.inner)
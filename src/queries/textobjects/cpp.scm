@@ -123,3 +123,87 @@
 
 (new_expression
   (argument_list) @call.inner) @call.outer
+
+(compound_statement) @block.outer
+
+(compound_statement
+  .
+  "{"
+  _+ @block.inner
+  "}")
+
+(if_statement) @conditional.outer
+
+(if_statement
+  condition: (_) @conditional.inner)
+
+(if_statement
+  consequence: (compound_statement
+    .
+    "{"
+    _+ @conditional.inner
+    "}"))
+
+(if_statement
+  alternative: (_
+    (compound_statement
+      .
+      "{"
+      _+ @conditional.inner
+      "}")))
+
+(switch_statement) @conditional.outer
+
+(case_statement) @conditional.inner
+
+(for_statement) @loop.outer
+
+(for_statement
+  body: (compound_statement
+    .
+    "{"
+    _+ @loop.inner
+    "}"))
+
+(while_statement) @loop.outer
+
+(while_statement
+  body: (compound_statement
+    .
+    "{"
+    _+ @loop.inner
+    "}"))
+
+(do_statement) @loop.outer
+
+(do_statement
+  body: (compound_statement
+    .
+    "{"
+    _+ @loop.inner
+    "}"))
+
+(declaration
+  declarator: (init_declarator
+    declarator: (_) @assignment.lhs
+    value: (_) @assignment.inner @assignment.rhs)) @assignment.outer
+
+(declaration
+  declarator: (init_declarator
+    declarator: (_) @assignment.inner))
+
+(assignment_expression
+  left: (_) @assignment.lhs
+  right: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(assignment_expression
+  left: (_) @assignment.inner)
+
+(for_range_loop) @loop.outer
+
+(for_range_loop
+  body: (compound_statement
+    .
+    "{"
+    _+ @loop.inner
+    "}"))
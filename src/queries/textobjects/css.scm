@@ -1 +1,49 @@
 (comment) @comment.outer
+
+(block) @block.outer
+
+(block
+  .
+  "{"
+  _+ @block.inner
+  "}")
+
+[
+  (media_statement)
+  (supports_statement)
+] @conditional.outer
+
+[
+  (media_statement
+    (block
+      .
+      "{"
+      _+ @conditional.inner
+      "}"))
+  (supports_statement
+    (block
+      .
+      "{"
+      _+ @conditional.inner
+      "}"))
+]
+
+(arguments
+  "," @parameter.outer
+  .
+  (_) @parameter.inner @parameter.outer)
+
+(arguments
+  .
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(declaration
+  (property_name) @assignment.lhs
+  ":"
+  _+ @assignment.inner @assignment.rhs
+  ";") @assignment.outer
+
+(declaration
+  (property_name) @assignment.inner)
@@ -19,8 +19,12 @@
   (else_clause)
   (for_statement)
   (while_statement)
+  (begin_statement)
 ]) @block.outer
 
+(begin_statement
+  (_)+ @block.inner)
+
 ; call
 ; call.inner doesn't work because it can't select *all* arguments
 (command) @call.outer
@@ -54,7 +58,7 @@
 
 ; parameter
 (command
-  argument: (_) @parameter.outer)
+  argument: (_) @parameter.inner @parameter.outer)
 
 ; return
 (return
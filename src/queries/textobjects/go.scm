@@ -87,3 +87,55 @@
   (_) @parameter.inner @parameter.outer
   .
   ","? @parameter.outer)
+
+(block
+  .
+  "{"
+  _+ @block.inner
+  "}") @block.outer
+
+(if_statement
+  alternative: (_
+    (_) @conditional.inner)?) @conditional.outer
+
+(if_statement
+  consequence: (block)? @conditional.inner)
+
+(if_statement
+  condition: (_) @conditional.inner)
+
+(expression_case) @conditional.inner
+
+(expression_switch_statement) @conditional.outer
+
+(type_case) @conditional.inner
+
+(type_switch_statement) @conditional.outer
+
+(for_statement
+  body: (block
+    .
+    "{"
+    _+ @loop.inner
+    "}")) @loop.outer
+
+(short_var_declaration
+  left: (_) @assignment.lhs
+  right: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(short_var_declaration
+  left: (_) @assignment.inner)
+
+(assignment_statement
+  left: (_) @assignment.lhs
+  right: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(assignment_statement
+  left: (_) @assignment.inner)
+
+(var_spec
+  name: (_) @assignment.lhs
+  value: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(var_spec
+  name: (_) @assignment.inner)
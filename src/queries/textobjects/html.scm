@@ -52,3 +52,16 @@
   (#match? @_tag "^(html|section|h[0-9]|header|title|head|body)$"))
 
 (comment) @comment.outer
+
+(start_tag
+  (attribute) @parameter.inner @parameter.outer)
+
+(attribute
+  (attribute_name) @assignment.lhs
+  [
+    (attribute_value)
+    (quoted_attribute_value)
+  ] @assignment.inner @assignment.rhs) @assignment.outer
+
+(attribute
+  (attribute_name) @assignment.inner)
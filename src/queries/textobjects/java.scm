@@ -54,3 +54,82 @@
   (line_comment)
   (block_comment)
 ] @comment.outer
+
+(block) @block.outer
+
+(block
+  .
+  "{"
+  _+ @block.inner
+  "}")
+
+(if_statement) @conditional.outer
+
+(if_statement
+  condition: (_) @conditional.inner)
+
+(if_statement
+  consequence: (block
+    .
+    "{"
+    _+ @conditional.inner
+    "}"))
+
+(if_statement
+  alternative: (block
+    .
+    "{"
+    _+ @conditional.inner
+    "}"))
+
+(for_statement) @loop.outer
+
+(for_statement
+  body: (block
+    .
+    "{"
+    _+ @loop.inner
+    "}"))
+
+(enhanced_for_statement) @loop.outer
+
+(enhanced_for_statement
+  body: (block
+    .
+    "{"
+    _+ @loop.inner
+    "}"))
+
+(while_statement) @loop.outer
+
+(while_statement
+  body: (block
+    .
+    "{"
+    _+ @loop.inner
+    "}"))
+
+(do_statement) @loop.outer
+
+(do_statement
+  body: (block
+    .
+    "{"
+    _+ @loop.inner
+    "}"))
+
+(local_variable_declaration
+  declarator: (variable_declarator
+    name: (_) @assignment.lhs
+    value: (_) @assignment.inner @assignment.rhs)) @assignment.outer
+
+(local_variable_declaration
+  declarator: (variable_declarator
+    name: (_) @assignment.inner))
+
+(assignment_expression
+  left: (_) @assignment.lhs
+  right: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(assignment_expression
+  left: (_) @assignment.inner)
@@ -1 +1,42 @@
 (comment) @comment.outer
+
+(object
+  .
+  "{"
+  _+ @block.inner
+  "}") @block.outer
+
+(array
+  .
+  "["
+  _+ @block.inner
+  "]") @block.outer
+
+(object
+  "," @parameter.outer
+  .
+  (pair) @parameter.inner @parameter.outer)
+
+(object
+  .
+  (pair) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(array
+  "," @parameter.outer
+  .
+  (_) @parameter.inner @parameter.outer)
+
+(array
+  .
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(pair
+  key: (_) @assignment.lhs
+  value: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(pair
+  key: (_) @assignment.inner)
@@ -43,3 +43,62 @@
   (line_comment)
   (multiline_comment)
 ] @comment.outer
+
+[
+  (control_structure_body)
+  (function_body)
+] @block.outer
+
+[
+  (control_structure_body
+    .
+    "{"
+    _+ @block.inner
+    "}")
+  (function_body
+    .
+    "{"
+    _+ @block.inner
+    "}")
+]
+
+(if_expression) @conditional.outer
+
+(if_expression
+  (control_structure_body) @conditional.inner)
+
+(when_expression) @conditional.outer
+
+(when_entry
+  (control_structure_body) @conditional.inner)
+
+[
+  (for_statement)
+  (while_statement)
+  (do_while_statement)
+] @loop.outer
+
+[
+  (for_statement
+    (control_structure_body) @loop.inner)
+  (while_statement
+    (control_structure_body) @loop.inner)
+  (do_while_statement
+    (control_structure_body) @loop.inner)
+]
+
+(assignment
+  (directly_assignable_expression) @assignment.lhs
+  (_) @assignment.inner @assignment.rhs .) @assignment.outer
+
+(assignment
+  (directly_assignable_expression) @assignment.inner)
+
+(property_declaration
+  (variable_declaration) @assignment.lhs
+  "="
+  .
+  (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(property_declaration
+  (variable_declaration) @assignment.inner)
@@ -120,3 +120,92 @@
     "("
     _+ @call.inner
     ")"))
+
+(compound_statement) @block.outer
+
+(compound_statement
+  .
+  "{"
+  _+ @block.inner
+  "}")
+
+(if_statement) @conditional.outer
+
+(if_statement
+  (parenthesized_expression) @conditional.inner)
+
+[
+  (if_statement
+    (compound_statement
+      .
+      "{"
+      _+ @conditional.inner
+      "}"))
+  (else_if_clause
+    (compound_statement
+      .
+      "{"
+      _+ @conditional.inner
+      "}"))
+  (else_clause
+    (compound_statement
+      .
+      "{"
+      _+ @conditional.inner
+      "}"))
+]
+
+(switch_statement) @conditional.outer
+
+[
+  (case_statement)
+  (default_statement)
+] @conditional.inner
+
+[
+  (for_statement)
+  (foreach_statement)
+  (while_statement)
+  (do_statement)
+] @loop.outer
+
+[
+  (for_statement
+    (compound_statement
+      .
+      "{"
+      _+ @loop.inner
+      "}"))
+  (foreach_statement
+    (compound_statement
+      .
+      "{"
+      _+ @loop.inner
+      "}"))
+  (while_statement
+    (compound_statement
+      .
+      "{"
+      _+ @loop.inner
+      "}"))
+  (do_statement
+    (compound_statement
+      .
+      "{"
+      _+ @loop.inner
+      "}"))
+]
+
+(assignment_expression
+  left: (_) @assignment.lhs
+  right: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(assignment_expression
+  left: (_) @assignment.inner)
+
+(augmented_assignment_expression
+  left: (_) @assignment.lhs
+  right: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(augmented_assignment_expression
+  left: (_) @assignment.inner)
@@ -25,3 +25,52 @@
   (class_method_parameter) @parameter.inner)
 
 (comment) @comment.outer
+
+(invokation_expression
+  (argument_list
+    (argument_expression_list
+      (argument_expression) @parameter.inner)))
+
+(if_statement
+  (statement_block) @conditional.inner) @conditional.outer
+
+(elseif_clause
+  (statement_block) @conditional.inner)
+
+(else_clause
+  (statement_block) @conditional.inner)
+
+(switch_statement
+  (switch_body) @conditional.inner) @conditional.outer
+
+[
+  (while_statement)
+  (do_statement)
+  (for_statement)
+  (foreach_statement)
+] @loop.outer
+
+(while_statement
+  (statement_block) @loop.inner)
+
+(do_statement
+  (statement_block) @loop.inner)
+
+(for_statement
+  (statement_block) @loop.inner)
+
+(foreach_statement
+  (statement_block) @loop.inner)
+
+(statement_block
+  (statement_list) @block.inner) @block.outer
+
+(script_block_expression
+  (script_block) @block.inner) @block.outer
+
+(assignment_expression
+  (left_assignment_expression) @assignment.lhs
+  value: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(assignment_expression
+  (left_assignment_expression) @assignment.inner)
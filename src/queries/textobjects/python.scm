@@ -165,3 +165,36 @@
   ","? @parameter.outer)
 
 (comment) @comment.outer
+
+(_
+  (block) @block.inner) @block.outer
+
+(if_statement
+  alternative: (_
+    (_) @conditional.inner)?) @conditional.outer
+
+(if_statement
+  consequence: (block)? @conditional.inner)
+
+(if_statement
+  condition: (_) @conditional.inner)
+
+(while_statement
+  body: (block)? @loop.inner) @loop.outer
+
+(for_statement
+  body: (block)? @loop.inner) @loop.outer
+
+(assignment
+  left: (_) @assignment.lhs
+  right: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(assignment
+  left: (_) @assignment.inner)
+
+(augmented_assignment
+  left: (_) @assignment.lhs
+  right: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(augmented_assignment
+  left: (_) @assignment.inner)
@@ -37,3 +37,61 @@
 (comment) @comment.outer
 
 (multiline_comment) @comment.outer
+
+(_
+  (statements) @block.inner) @block.outer
+
+[
+  (if_statement)
+  (guard_statement)
+  (switch_statement)
+] @conditional.outer
+
+[
+  (if_statement
+    (statements) @conditional.inner)
+  (guard_statement
+    (statements) @conditional.inner)
+  (switch_entry
+    (statements) @conditional.inner)
+]
+
+[
+  (for_statement)
+  (while_statement)
+  (repeat_while_statement)
+] @loop.outer
+
+[
+  (for_statement
+    (statements) @loop.inner)
+  (while_statement
+    (statements) @loop.inner)
+  (repeat_while_statement
+    (statements) @loop.inner)
+]
+
+(function_declaration
+  "," @parameter.outer
+  .
+  (parameter) @parameter.inner @parameter.outer)
+
+(function_declaration
+  .
+  (parameter) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(assignment
+  target: (_) @assignment.lhs
+  result: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(assignment
+  target: (_) @assignment.inner)
+
+(property_declaration
+  name: (_) @assignment.lhs
+  value: (_) @assignment.inner @assignment.rhs) @assignment.outer
+
+(property_declaration
+  name: (_) @assignment.inner)
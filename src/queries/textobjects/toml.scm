@@ -29,3 +29,21 @@
   ","? @parameter.outer)
 
 (comment) @comment.outer
+
+(table
+  (pair)+ @block.inner) @block.outer
+
+(table_array_element
+  (pair)+ @block.inner) @block.outer
+
+(inline_table
+  (pair)+ @block.inner) @block.outer
+
+(pair
+  .
+  (_) @assignment.lhs
+  (_) @assignment.inner @assignment.rhs .) @assignment.outer
+
+(pair
+  .
+  (_) @assignment.inner)
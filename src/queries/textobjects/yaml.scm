@@ -17,3 +17,37 @@
   (integer_scalar)
   (float_scalar)
 ] @number.inner
+
+; block
+(block_mapping
+  (block_mapping_pair)+ @block.inner) @block.outer
+
+(block_sequence
+  (block_sequence_item)+ @block.inner) @block.outer
+
+; parameter
+(flow_sequence
+  "," @parameter.outer
+  .
+  (_) @parameter.inner @parameter.outer)
+
+(flow_sequence
+  .
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(flow_mapping
+  "," @parameter.outer
+  .
+  (_) @parameter.inner @parameter.outer)
+
+(flow_mapping
+  .
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(flow_pair
+  key: (_) @assignment.lhs
+  value: (_) @assignment.inner @assignment.rhs) @assignment.outer
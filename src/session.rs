@@ -17,7 +17,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     agent_conversation::AgentConversationSnapshot,
-    editor::Content,
+    editor::{Content, LastSubstitute},
     plugin::PanelManagerSnapshot,
     undo::{TextPosition, UndoHistory},
     window::WindowManagerSnapshot,
@@ -86,12 +86,22 @@ pub struct SessionSnapshot {
     pub current_buffer_index: usize,
     /// Split tree, focused window, and per-window view state.
     pub window_layout: WindowManagerSnapshot,
+    /// Layouts of every tab page in order when more than one was open. The current
+    /// page's entry matches [`Self::window_layout`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tab_layouts: Vec<WindowManagerSnapshot>,
+    /// Position of the current page in [`Self::tab_layouts`].
+    #[serde(default)]
+    pub current_tab: usize,
     /// Plugin pane shells and editor-owned interaction state, keyed by stable IDs.
     #[serde(default)]
     pub panels: PanelManagerSnapshot,
     /// Named editor registers.
     #[serde(default)]
     pub registers: HashMap<char, Content>,
+    /// Pattern, replacement and flags of the last `:s`, which `&` repeats.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_substitute: Option<LastSubstitute>,
     /// Jump-list entries in traversal order.
     #[serde(default)]
     pub jumps: Vec<SessionJump>,
@@ -253,6 +263,9 @@ pub struct SessionVisualSelection {
     pub mode: SessionVisualMode,
     /// Whether the selection anchor is the earlier `<` endpoint.
     pub anchor_at_start: bool,
+    /// Whether a block selection extended to each line's end after `$`.
+    #[serde(default)]
+    pub to_line_end: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -2315,10 +2328,14 @@ mod tests {
                     cx: 0,
                     cy: 0,
                     vx: 0,
+                    folds: Vec::new(),
                 },
             },
+            tab_layouts: Vec::new(),
+            current_tab: 0,
             panels: PanelManagerSnapshot::default(),
             registers: HashMap::new(),
+            last_substitute: None,
             jumps: Vec::new(),
             jump_index: 0,
             window_jumps: Vec::new(),
@@ -0,0 +1,825 @@
+//! Hunspell-compatible spell checking for comments, strings, and prose.
+//!
+//! [`Dictionary`] reads the common subset of Hunspell's `.aff`/`.dic` format: prefix
+//! and suffix rules with cross products and one level of suffix continuation, the
+//! `FLAG` encodings, `TRY` and `REP` suggestion hints, and the `FORBIDDENWORD`,
+//! `NEEDAFFIX`, `KEEPCASE`, `NOSUGGEST`, and `ONLYINCOMPOUND` flags. Compounding and
+//! morphology are ignored. [`SpellChecker`] combines the dictionaries named by
+//! `spelllang` with the user's `zg`/`zw` word list, and [`spell_words`] finds the
+//! words worth checking in a stretch of text.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+type Flag = u32;
+
+/// Most suggestions `z=` offers for one word.
+pub const MAX_SUGGESTIONS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlagFormat {
+    /// One character per flag, the Hunspell default.
+    Short,
+    /// Two characters per flag (`FLAG long`).
+    Long,
+    /// Comma-separated decimal numbers (`FLAG num`).
+    Numeric,
+}
+
+impl FlagFormat {
+    fn parse(self, flags: &str) -> Vec<Flag> {
+        match self {
+            Self::Short => flags.chars().map(Flag::from).collect(),
+            Self::Long => flags
+                .chars()
+                .collect::<Vec<_>>()
+                .chunks(2)
+                .map(|pair| {
+                    pair.iter()
+                        .fold(0, |flag, &character| (flag << 16) | Flag::from(character))
+                })
+                .collect(),
+            Self::Numeric => flags
+                .split(',')
+                .filter_map(|flag| flag.trim().parse().ok())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ConditionItem {
+    Any,
+    Character(char),
+    Class {
+        negated: bool,
+        characters: Vec<char>,
+    },
+}
+
+impl ConditionItem {
+    fn matches(&self, character: char) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Character(expected) => *expected == character,
+            Self::Class {
+                negated,
+                characters,
+            } => characters.contains(&character) != *negated,
+        }
+    }
+}
+
+fn parse_condition(condition: &str) -> Vec<ConditionItem> {
+    let mut items = Vec::new();
+    let mut characters = condition.chars();
+    while let Some(character) = characters.next() {
+        items.push(match character {
+            '.' => ConditionItem::Any,
+            '[' => {
+                let mut class = characters
+                    .by_ref()
+                    .take_while(|&character| character != ']')
+                    .collect::<Vec<_>>();
+                let negated = class.first() == Some(&'^');
+                if negated {
+                    class.remove(0);
+                }
+                ConditionItem::Class {
+                    negated,
+                    characters: class,
+                }
+            }
+            character => ConditionItem::Character(character),
+        });
+    }
+    items
+}
+
+/// One `PFX` or `SFX` rule.
+#[derive(Debug, Clone)]
+struct Affix {
+    flag: Flag,
+    cross_product: bool,
+    strip: String,
+    add: String,
+    condition: Vec<ConditionItem>,
+    continuation: Vec<Flag>,
+}
+
+impl Affix {
+    /// Undoes this suffix, returning the stem when `word` could have been built with it.
+    fn strip_suffix(&self, word: &str) -> Option<String> {
+        let remaining = word.strip_suffix(self.add.as_str())?;
+        if remaining.is_empty() {
+            return None;
+        }
+        let stem = format!("{remaining}{}", self.strip);
+        let tail = stem.chars().rev().take(self.condition.len());
+        let matched = tail
+            .zip(self.condition.iter().rev())
+            .filter(|(character, item)| item.matches(*character))
+            .count();
+        (matched == self.condition.len()).then_some(stem)
+    }
+
+    /// Undoes this prefix, returning the stem when `word` could have been built with it.
+    fn strip_prefix(&self, word: &str) -> Option<String> {
+        let remaining = word.strip_prefix(self.add.as_str())?;
+        if remaining.is_empty() {
+            return None;
+        }
+        let stem = format!("{}{remaining}", self.strip);
+        let matched = stem
+            .chars()
+            .zip(&self.condition)
+            .filter(|(character, item)| item.matches(*character))
+            .count();
+        (matched == self.condition.len()).then_some(stem)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Casing {
+    Lower,
+    Capitalized,
+    Upper,
+    Mixed,
+}
+
+fn casing(word: &str) -> Casing {
+    let mut letters = word.chars().filter(|character| character.is_alphabetic());
+    let Some(first) = letters.next() else {
+        return Casing::Lower;
+    };
+    let rest = letters.collect::<Vec<_>>();
+    let rest_lower = rest.iter().all(|character| !character.is_uppercase());
+    let rest_upper = rest.iter().all(|character| !character.is_lowercase());
+    match (first.is_uppercase(), rest_lower, rest_upper) {
+        (false, true, _) => Casing::Lower,
+        (true, true, _) => Casing::Capitalized,
+        (true, _, true) => Casing::Upper,
+        _ => Casing::Mixed,
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut characters = word.chars();
+    characters
+        .next()
+        .map(|first| first.to_uppercase().chain(characters).collect())
+        .unwrap_or_default()
+}
+
+/// A Hunspell dictionary loaded from one `.aff`/`.dic` pair.
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    words: HashMap<String, Vec<Box<[Flag]>>>,
+    prefixes: Vec<Affix>,
+    suffixes: Vec<Affix>,
+    replacements: Vec<(String, String)>,
+    try_characters: Vec<char>,
+    forbidden: Option<Flag>,
+    need_affix: Option<Flag>,
+    keep_case: Option<Flag>,
+    no_suggest: Option<Flag>,
+    only_in_compound: Option<Flag>,
+}
+
+impl Dictionary {
+    /// Loads `{name}.aff` and `{name}.dic` from `directory`.
+    pub fn load(directory: &Path, name: &str) -> anyhow::Result<Self> {
+        let base = dictionary_base(directory, name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "no dictionary for {name}: expected {name}.aff and {name}.dic in {}",
+                directory.display()
+            )
+        })?;
+        let affixes = fs::read(base.with_extension("aff"))?;
+        let words = fs::read(base.with_extension("dic"))?;
+        Ok(Self::parse(&affixes, &words))
+    }
+
+    /// Parses the contents of an `.aff` and a `.dic` file.
+    pub fn parse(affixes: &[u8], words: &[u8]) -> Self {
+        let utf8 = latin1(affixes).lines().any(|line| {
+            let mut fields = line.split_whitespace();
+            fields.next() == Some("SET")
+                && fields
+                    .next()
+                    .is_some_and(|encoding| encoding.eq_ignore_ascii_case("UTF-8"))
+        });
+        let decode = |contents: &[u8]| {
+            if utf8 {
+                String::from_utf8_lossy(contents).into_owned()
+            } else {
+                latin1(contents)
+            }
+        };
+
+        let mut dictionary = Self::default();
+        let mut format = FlagFormat::Short;
+        let mut cross_products = HashMap::new();
+        for line in decode(affixes).lines() {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let &[keyword, value, ..] = fields.as_slice() else {
+                continue;
+            };
+            let flag = || format.parse(value).first().copied();
+            match keyword {
+                "FLAG" => {
+                    format = match value {
+                        "long" => FlagFormat::Long,
+                        "num" => FlagFormat::Numeric,
+                        _ => FlagFormat::Short,
+                    };
+                }
+                "TRY" => dictionary.try_characters = value.chars().collect(),
+                "REP" if fields.len() >= 3 => dictionary
+                    .replacements
+                    .push((value.replace('_', " "), fields[2].replace('_', " "))),
+                "FORBIDDENWORD" => dictionary.forbidden = flag(),
+                "NEEDAFFIX" | "PSEUDOROOT" => dictionary.need_affix = flag(),
+                "KEEPCASE" => dictionary.keep_case = flag(),
+                "NOSUGGEST" => dictionary.no_suggest = flag(),
+                "ONLYINCOMPOUND" => dictionary.only_in_compound = flag(),
+                "PFX" | "SFX" if fields.len() >= 4 => {
+                    let Some(flag) = flag() else {
+                        continue;
+                    };
+                    let key = (keyword, flag);
+                    let Some(&cross_product) = cross_products.get(&key) else {
+                        cross_products.insert(key, fields[2] == "Y");
+                        continue;
+                    };
+                    let strip = match fields[2] {
+                        "0" => String::new(),
+                        strip => strip.to_string(),
+                    };
+                    let (add, continuation) = fields[3]
+                        .split_once('/')
+                        .map_or((fields[3], Vec::new()), |(add, flags)| {
+                            (add, format.parse(flags))
+                        });
+                    let affix = Affix {
+                        flag,
+                        cross_product,
+                        strip,
+                        add: if add == "0" {
+                            String::new()
+                        } else {
+                            add.to_string()
+                        },
+                        condition: fields
+                            .get(4)
+                            .map(|condition| parse_condition(condition))
+                            .unwrap_or_default(),
+                        continuation,
+                    };
+                    if keyword == "PFX" {
+                        dictionary.prefixes.push(affix);
+                    } else {
+                        dictionary.suffixes.push(affix);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if dictionary.try_characters.is_empty() {
+            dictionary.try_characters = ('a'..='z').collect();
+        }
+
+        for (index, line) in decode(words).lines().enumerate() {
+            // The first line holds the approximate word count.
+            if index == 0 && line.trim().parse::<usize>().is_ok() {
+                continue;
+            }
+            let Some(entry) = line
+                .split(['\t', ' '])
+                .next()
+                .filter(|entry| !entry.is_empty())
+            else {
+                continue;
+            };
+            let (word, flags) = split_dictionary_entry(entry);
+            dictionary
+                .words
+                .entry(word)
+                .or_default()
+                .push(format.parse(&flags).into_boxed_slice());
+        }
+        dictionary
+    }
+
+    /// Returns whether `word` is spelled correctly, allowing the lowercase form of a
+    /// capitalized or all-caps word.
+    pub fn check(&self, word: &str) -> bool {
+        self.analyze(word).is_some()
+    }
+
+    /// Returns the root flags of the first analysis that accepts `word`.
+    fn analyze(&self, word: &str) -> Option<&[Flag]> {
+        if let Some(flags) = self.accepts(word, false) {
+            return Some(flags);
+        }
+        let lower = word.to_lowercase();
+        match casing(word) {
+            Casing::Capitalized => self.accepts(&lower, true),
+            Casing::Upper => self
+                .accepts(&lower, true)
+                .or_else(|| self.accepts(&capitalize(&lower), true)),
+            Casing::Lower | Casing::Mixed => None,
+        }
+    }
+
+    fn accepts(&self, word: &str, case_changed: bool) -> Option<&[Flag]> {
+        if self.is_forbidden(word) {
+            return None;
+        }
+        if let Some(flags) = self.root(word, &[], case_changed) {
+            return Some(flags);
+        }
+        for suffix in &self.suffixes {
+            let Some(stem) = suffix.strip_suffix(word) else {
+                continue;
+            };
+            if let Some(flags) = self.root(&stem, &[suffix.flag], case_changed) {
+                return Some(flags);
+            }
+            // An inner suffix may allow this one as its continuation.
+            for inner in self
+                .suffixes
+                .iter()
+                .filter(|inner| inner.continuation.contains(&suffix.flag))
+            {
+                if let Some(flags) = inner
+                    .strip_suffix(&stem)
+                    .and_then(|root| self.root(&root, &[inner.flag], case_changed))
+                {
+                    return Some(flags);
+                }
+            }
+            if !suffix.cross_product {
+                continue;
+            }
+            for prefix in self.prefixes.iter().filter(|prefix| prefix.cross_product) {
+                if let Some(flags) = prefix
+                    .strip_prefix(&stem)
+                    .and_then(|root| self.root(&root, &[suffix.flag, prefix.flag], case_changed))
+                {
+                    return Some(flags);
+                }
+            }
+        }
+        self.prefixes.iter().find_map(|prefix| {
+            prefix
+                .strip_prefix(word)
+                .and_then(|root| self.root(&root, &[prefix.flag], case_changed))
+        })
+    }
+
+    fn is_forbidden(&self, word: &str) -> bool {
+        self.forbidden.is_some_and(|forbidden| {
+            self.words
+                .get(word)
+                .is_some_and(|homonyms| homonyms.iter().any(|flags| flags.contains(&forbidden)))
+        })
+    }
+
+    /// Finds a dictionary entry for `stem` that carries every flag in `required`.
+    fn root(&self, stem: &str, required: &[Flag], case_changed: bool) -> Option<&[Flag]> {
+        let has =
+            |flags: &[Flag], flag: Option<Flag>| flag.is_some_and(|flag| flags.contains(&flag));
+        self.words
+            .get(stem)?
+            .iter()
+            .map(AsRef::as_ref)
+            .find(|flags| {
+                required.iter().all(|flag| flags.contains(flag))
+                    && !has(flags, self.forbidden)
+                    && !(required.is_empty()
+                        && (has(flags, self.need_affix) || has(flags, self.only_in_compound)))
+                    && !(case_changed && has(flags, self.keep_case))
+            })
+    }
+
+    fn suggestable(&self, word: &str) -> bool {
+        self.analyze(word).is_some_and(|flags| {
+            self.no_suggest
+                .is_none_or(|no_suggest| !flags.contains(&no_suggest))
+        })
+    }
+}
+
+fn latin1(contents: &[u8]) -> String {
+    contents.iter().map(|&byte| char::from(byte)).collect()
+}
+
+/// Splits `word/flags`, where `\/` is a literal slash inside the word.
+fn split_dictionary_entry(entry: &str) -> (String, String) {
+    let mut word = String::new();
+    let mut characters = entry.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => match characters.next() {
+                Some('/') => word.push('/'),
+                Some(other) => {
+                    word.push('\\');
+                    word.push(other);
+                }
+                None => word.push('\\'),
+            },
+            '/' => return (word, characters.collect()),
+            character => word.push(character),
+        }
+    }
+    (word, String::new())
+}
+
+/// Finds `{name}.aff` beside `{name}.dic`, tolerating the case and `-`/`_`
+/// spelling differences between `spelllang` values and dictionary file names.
+fn dictionary_base(directory: &Path, name: &str) -> Option<PathBuf> {
+    let exact = directory.join(name);
+    if exact.with_extension("aff").is_file() && exact.with_extension("dic").is_file() {
+        return Some(exact);
+    }
+    let normalized = |name: &str| name.to_ascii_lowercase().replace('-', "_");
+    let wanted = normalized(name);
+    fs::read_dir(directory)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "aff"))
+        .find(|path| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| normalized(stem) == wanted)
+                && path.with_extension("dic").is_file()
+        })
+        .map(|path| path.with_extension(""))
+}
+
+/// The dictionaries for `spelllang` plus the user's good and wrong words.
+#[derive(Debug, Default)]
+pub struct SpellChecker {
+    dictionaries: Vec<Dictionary>,
+    word_list: Option<PathBuf>,
+    good: HashSet<String>,
+    wrong: HashSet<String>,
+}
+
+impl SpellChecker {
+    /// Loads each language's dictionary from `directory` and the word list named
+    /// after the first language, `{language}.add`.
+    pub fn load(directory: &Path, languages: &[String]) -> anyhow::Result<Self> {
+        anyhow::ensure!(!languages.is_empty(), "spelllang is empty");
+        let dictionaries = languages
+            .iter()
+            .map(|language| Dictionary::load(directory, language))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let word_list = directory.join(format!("{}.add", languages[0]));
+        let mut checker = Self::new(dictionaries);
+        match fs::read_to_string(&word_list) {
+            Ok(contents) => checker.read_word_list(&contents),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error.into()),
+        }
+        checker.word_list = Some(word_list);
+        Ok(checker)
+    }
+
+    pub fn new(dictionaries: Vec<Dictionary>) -> Self {
+        Self {
+            dictionaries,
+            ..Self::default()
+        }
+    }
+
+    /// Reads Vim's word-list format: one word per line, `word/!` for a wrong word,
+    /// and `#` comments.
+    pub fn read_word_list(&mut self, contents: &str) {
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.strip_suffix("/!") {
+                Some(word) => self.wrong.insert(word.to_string()),
+                None => self.good.insert(line.to_string()),
+            };
+        }
+    }
+
+    /// Returns whether `word` is spelled correctly. Typographic apostrophes match
+    /// the ASCII ones dictionaries use.
+    pub fn check(&self, word: &str) -> bool {
+        let word = &word.replace('’', "'");
+        let lower = word.to_lowercase();
+        if self.wrong.contains(word) || self.wrong.contains(&lower) {
+            return false;
+        }
+        if self.good.contains(word)
+            || (matches!(casing(word), Casing::Capitalized | Casing::Upper)
+                && self.good.contains(&lower))
+        {
+            return true;
+        }
+        self.dictionaries
+            .iter()
+            .any(|dictionary| dictionary.check(word))
+    }
+
+    /// Marks `word` as good (`zg`) or wrong (`zw`) and rewrites the word list.
+    pub fn add_word(&mut self, word: &str, good: bool) -> anyhow::Result<()> {
+        if good {
+            self.wrong.remove(word);
+            self.good.insert(word.to_string());
+        } else {
+            self.good.remove(word);
+            self.wrong.insert(word.to_string());
+        }
+        let Some(path) = &self.word_list else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.word_list_contents())?;
+        Ok(())
+    }
+
+    fn word_list_contents(&self) -> String {
+        let mut lines = self
+            .good
+            .iter()
+            .cloned()
+            .chain(self.wrong.iter().map(|word| format!("{word}/!")))
+            .collect::<Vec<_>>();
+        lines.sort_unstable();
+        lines.into_iter().map(|line| line + "\n").collect()
+    }
+
+    /// Returns replacements for `word`, most likely first, matching its capitalization.
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        let original_casing = casing(word);
+        let lower = if matches!(original_casing, Casing::Capitalized | Casing::Upper) {
+            word.to_lowercase()
+        } else {
+            word.to_string()
+        };
+        let characters = lower.chars().collect::<Vec<_>>();
+        let mut candidates = Vec::new();
+        for dictionary in &self.dictionaries {
+            for (from, to) in &dictionary.replacements {
+                for (start, _) in lower.match_indices(from.as_str()) {
+                    candidates.push(format!(
+                        "{}{to}{}",
+                        &lower[..start],
+                        &lower[start + from.len()..]
+                    ));
+                }
+            }
+        }
+        for index in 1..characters.len() {
+            let mut swapped = characters.clone();
+            swapped.swap(index - 1, index);
+            candidates.push(swapped.into_iter().collect());
+        }
+        let try_characters = self
+            .dictionaries
+            .iter()
+            .flat_map(|dictionary| dictionary.try_characters.iter().copied())
+            .filter(|character| !character.is_uppercase())
+            .fold(Vec::new(), |mut unique, character| {
+                if !unique.contains(&character) {
+                    unique.push(character);
+                }
+                unique
+            });
+        for index in 0..characters.len() {
+            for &replacement in &try_characters {
+                if replacement != characters[index] {
+                    let mut replaced = characters.clone();
+                    replaced[index] = replacement;
+                    candidates.push(replaced.into_iter().collect());
+                }
+            }
+        }
+        for index in 0..characters.len() {
+            let mut deleted = characters.clone();
+            deleted.remove(index);
+            candidates.push(deleted.into_iter().collect());
+        }
+        for index in 0..=characters.len() {
+            for &inserted in &try_characters {
+                let mut extended = characters.clone();
+                extended.insert(index, inserted);
+                candidates.push(extended.into_iter().collect());
+            }
+        }
+        for index in 1..characters.len() {
+            let (left, right) = characters.split_at(index);
+            candidates.push(format!(
+                "{} {}",
+                left.iter().collect::<String>(),
+                right.iter().collect::<String>()
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        candidates
+            .into_iter()
+            .filter(|candidate| *candidate != lower)
+            .map(|candidate| match original_casing {
+                Casing::Capitalized => capitalize(&candidate),
+                Casing::Upper => candidate.to_uppercase(),
+                Casing::Lower | Casing::Mixed => candidate,
+            })
+            .filter(|candidate| {
+                candidate
+                    .split(' ')
+                    .all(|part| !part.is_empty() && self.suggestable(part))
+            })
+            .filter(|candidate| seen.insert(candidate.clone()))
+            .take(MAX_SUGGESTIONS)
+            .collect()
+    }
+
+    fn suggestable(&self, word: &str) -> bool {
+        let lower = word.to_lowercase();
+        if self.wrong.contains(word) || self.wrong.contains(&lower) {
+            return false;
+        }
+        self.good.contains(word)
+            || self
+                .dictionaries
+                .iter()
+                .any(|dictionary| dictionary.suggestable(word))
+    }
+}
+
+/// Returns the byte ranges of the words in `text` that should be spell checked.
+///
+/// Words are letters joined by apostrophes. Paths, URLs, addresses, dotted names,
+/// and words that contain digits or underscores, mix upper and lower case after
+/// the first letter, or are written in capitals are taken to be code or acronyms
+/// and skipped, as are single letters.
+pub fn spell_words(text: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut chunk_start = None;
+    for (index, character) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        match (character.is_whitespace(), chunk_start) {
+            (false, None) => chunk_start = Some(index),
+            (true, Some(start)) => {
+                chunk_start = None;
+                chunk_words(text, start..index, &mut words);
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+fn chunk_words(text: &str, chunk: Range<usize>, words: &mut Vec<Range<usize>>) {
+    let source = &text[chunk.clone()];
+    let characters = source.char_indices().collect::<Vec<_>>();
+    let dotted = characters.windows(3).any(|window| {
+        window[1].1 == '.' && window[0].1.is_alphanumeric() && window[2].1.is_alphanumeric()
+    });
+    if dotted || source.contains(['/', '\\', '@', '_']) || source.contains("::") {
+        return;
+    }
+
+    let is_apostrophe = |character: char| matches!(character, '\'' | '’');
+    let mut index = 0;
+    while index < characters.len() {
+        if !characters[index].1.is_alphanumeric() {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        while index < characters.len()
+            && (characters[index].1.is_alphanumeric()
+                || (is_apostrophe(characters[index].1)
+                    && characters
+                        .get(index + 1)
+                        .is_some_and(|(_, next)| next.is_alphabetic())))
+        {
+            index += 1;
+        }
+        let end = characters
+            .get(index)
+            .map_or(source.len(), |(offset, _)| *offset);
+        let word = &source[characters[start].0..end];
+        let letters = word.chars().filter(|character| character.is_alphabetic());
+        if word.chars().any(|character| character.is_numeric())
+            || letters.count() < 2
+            || matches!(casing(word), Casing::Upper | Casing::Mixed)
+        {
+            continue;
+        }
+        words.push(chunk.start + characters[start].0..chunk.start + end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{spell_words, Dictionary, SpellChecker};
+
+    const AFFIXES: &str = "SET UTF-8
+TRY esianrtolcdugmphbyfvkwz
+FORBIDDENWORD !
+KEEPCASE K
+NOSUGGEST N
+REP 1
+REP f ph
+
+PFX U Y 1
+PFX U 0 un .
+
+SFX S Y 3
+SFX S y ies [^aeiou]y
+SFX S 0 s [aeiou]y
+SFX S 0 s [^y]
+
+SFX D Y 2
+SFX D 0 ed [^e]
+SFX D 0 d e
+";
+
+    const WORDS: &str = "8
+the
+fly/S
+play/SUD
+photo/S
+GmbH/K
+chart
+colour/!
+heck/N
+";
+
+    fn checker() -> SpellChecker {
+        SpellChecker::new(vec![Dictionary::parse(
+            AFFIXES.as_bytes(),
+            WORDS.as_bytes(),
+        )])
+    }
+
+    #[test]
+    fn affix_rules_case_variants_and_flags_decide_spelling() {
+        let checker = checker();
+        for word in [
+            "the", "flies", "plays", "unplayed", "unplays", "photos", "The", "THE", "GmbH",
+        ] {
+            assert!(checker.check(word), "{word} should be accepted");
+        }
+        for word in [
+            "flys", "playies", "unfly", "teh", "colour", "GMBH", "charts",
+        ] {
+            assert!(!checker.check(word), "{word} should be rejected");
+        }
+    }
+
+    #[test]
+    fn word_lists_override_dictionaries() {
+        let mut checker = checker();
+        checker.read_word_list("# personal words\nfrobnicate\nthe/!\n");
+        assert!(checker.check("frobnicate"));
+        assert!(checker.check("Frobnicate"));
+        assert!(!checker.check("the"));
+        assert!(!checker.check("The"));
+    }
+
+    #[test]
+    fn suggestions_use_edits_replacements_and_original_case() {
+        let checker = checker();
+        assert_eq!(
+            checker.suggest("teh").first().map(String::as_str),
+            Some("the")
+        );
+        assert_eq!(
+            checker.suggest("Fotos").first().map(String::as_str),
+            Some("Photos")
+        );
+        assert!(checker.suggest("plaied").contains(&"played".to_string()));
+        assert!(checker.suggest("thefly").contains(&"the fly".to_string()));
+        assert!(!checker.suggest("hek").contains(&"heck".to_string()));
+    }
+
+    #[test]
+    fn spell_words_skip_code_like_tokens() {
+        let text = "Don't chek snake_case, camelCase, HTTP, x, utf8, \
+                    https://exmple.com or file.rs; chek dashed-wrds.";
+        let words = spell_words(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect::<Vec<_>>();
+        assert_eq!(words, ["Don't", "chek", "or", "chek", "dashed", "wrds"]);
+    }
+}
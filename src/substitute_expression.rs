@@ -0,0 +1,97 @@
+//! Husk expressions for `:s/pattern/\=expression/`.
+//!
+//! [`SubstituteExpression::compile`] wraps the expression in a function of `m`, the
+//! matched text followed by its capture groups, and `line`, the one-based line
+//! number. A string result is the replacement, and a number or boolean is inserted
+//! as text. The expression runs on an embedded [`Engine`] with the default
+//! husk-runtime limits, so one that loops or allocates without bound fails its call
+//! instead of stalling the editor.
+
+use husk_runtime::{Engine, Instance, OwnedValue};
+
+const FUNCTION: &str = "replacement";
+
+pub struct SubstituteExpression {
+    instance: Instance<()>,
+}
+
+impl SubstituteExpression {
+    pub fn compile(expression: &str) -> anyhow::Result<Self> {
+        let engine = Engine::<()>::builder().build()?;
+        let compiled = engine
+            .compile_source(
+                "substitute",
+                "<substitute>",
+                &format!("fn {FUNCTION}(m: [String], line: i32) -> String {{\n{expression}\n}}\n"),
+            )
+            .map_err(|error| anyhow::anyhow!("{}", summary(&error)))?;
+        let instance = engine
+            .instantiate(compiled, ())
+            .map_err(|error| anyhow::anyhow!("{}", summary(&error)))?;
+        Ok(Self { instance })
+    }
+
+    /// Evaluates the expression for one match. `groups` starts with the matched
+    /// text; `line` is zero-based.
+    pub fn evaluate(&mut self, groups: &[&str], line: usize) -> anyhow::Result<String> {
+        let groups = groups
+            .iter()
+            .map(|group| OwnedValue::String((*group).to_string()))
+            .collect();
+        let line = i32::try_from(line + 1).unwrap_or(i32::MAX);
+        let value = self
+            .instance
+            .call(FUNCTION, &[OwnedValue::List(groups), OwnedValue::I32(line)])
+            .map_err(|error| anyhow::anyhow!("{}", summary(&error)))?;
+        match value {
+            OwnedValue::String(text) => Ok(text),
+            OwnedValue::I32(number) => Ok(number.to_string()),
+            OwnedValue::I64(number) => Ok(number.to_string()),
+            OwnedValue::F64(number) => Ok(number.to_string()),
+            OwnedValue::Bool(value) => Ok(value.to_string()),
+            value => anyhow::bail!("expression returned {value:?} instead of a string"),
+        }
+    }
+}
+
+/// Returns the first line of a Husk diagnostic without its code, since the rest
+/// quotes the generated wrapper function rather than what was typed.
+fn summary(error: &anyhow::Error) -> String {
+    let message = error.to_string();
+    let first_line = message.lines().next().unwrap_or_default();
+    match first_line.split_once("]: ") {
+        Some((code, rest)) if code.starts_with("error[") => rest.to_string(),
+        _ => first_line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_strings_numbers_and_groups() {
+        let mut upper = SubstituteExpression::compile("m[1].to_uppercase() + m[0]").unwrap();
+        assert_eq!(upper.evaluate(&["ab", "a"], 0).unwrap(), "Aab");
+
+        let mut number =
+            SubstituteExpression::compile("m[1].parse::<i32>().unwrap_or(0) * 2 + line").unwrap();
+        assert_eq!(number.evaluate(&["n21", "21"], 2).unwrap(), "45");
+
+        let mut flag = SubstituteExpression::compile("m[0].is_empty()").unwrap();
+        assert_eq!(flag.evaluate(&[""], 0).unwrap(), "true");
+    }
+
+    #[test]
+    fn reports_compile_and_limit_errors() {
+        let error = SubstituteExpression::compile("upper(m[0])")
+            .err()
+            .unwrap()
+            .to_string();
+        assert_eq!(error, "unknown identifier `upper`");
+
+        let mut endless = SubstituteExpression::compile("loop {}").unwrap();
+        let error = endless.evaluate(&["x"], 0).unwrap_err().to_string();
+        assert!(error.contains("instruction budget"), "{error}");
+    }
+}
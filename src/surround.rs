@@ -0,0 +1,366 @@
+//! Delimiters for the surround commands: `ys{motion}`, `yss`, Visual `S`, `cs` and
+//! `ds`.
+//!
+//! [`delimiters_for_key`] maps the key typed after an add or change to the text
+//! that goes on either side. Opening brackets add a space inside, closing brackets
+//! and the `b`, `B`, `r` and `a` aliases do not, and a key that starts one of the
+//! configured `matchit.pairs` inserts that pair. Tags and function calls take a
+//! name typed at a prompt, see [`tag_delimiters`] and [`function_delimiters`].
+//!
+//! The `find_*` functions locate existing delimiters around a cursor for `cs` and
+//! `ds`. They take a document and a character index into it and return
+//! character ranges, so the editor converts positions at the boundary.
+
+use std::ops::Range;
+
+use regex::Regex;
+
+/// The text added before and after a surrounded range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Delimiters {
+    pub(crate) open: String,
+    pub(crate) close: String,
+}
+
+impl Delimiters {
+    fn new(open: &str, close: &str) -> Self {
+        Self {
+            open: open.to_string(),
+            close: close.to_string(),
+        }
+    }
+}
+
+/// Character ranges of the delimiters around a cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SurroundingPair {
+    pub(crate) open: Range<usize>,
+    pub(crate) close: Range<usize>,
+}
+
+/// Returns the single-character bracket pair a surround key names, and whether
+/// the key asks for space inside it.
+pub(crate) fn bracket_for_key(key: char) -> Option<(char, char, bool)> {
+    match key {
+        '(' => Some(('(', ')', true)),
+        ')' | 'b' => Some(('(', ')', false)),
+        '[' => Some(('[', ']', true)),
+        ']' | 'r' => Some(('[', ']', false)),
+        '{' => Some(('{', '}', true)),
+        '}' | 'B' => Some(('{', '}', false)),
+        '>' | 'a' => Some(('<', '>', false)),
+        _ => None,
+    }
+}
+
+/// Returns the configured pair whose opener or closer starts with `key`.
+pub(crate) fn configured_pair_for_key(key: char, pairs: &[[String; 2]]) -> Option<&[String; 2]> {
+    pairs
+        .iter()
+        .find(|[open, close]| open.starts_with(key) || close.starts_with(key))
+}
+
+/// Returns the delimiters a surround key adds, or `None` for letters and digits
+/// that name nothing. Prompting keys are handled by the caller.
+pub(crate) fn delimiters_for_key(key: char, pairs: &[[String; 2]]) -> Option<Delimiters> {
+    if let Some((open, close, spaced)) = bracket_for_key(key) {
+        return Some(if spaced {
+            Delimiters::new(&format!("{open} "), &format!(" {close}"))
+        } else {
+            Delimiters::new(&open.to_string(), &close.to_string())
+        });
+    }
+    if let Some([open, close]) = configured_pair_for_key(key, pairs) {
+        return Some(Delimiters::new(open, close));
+    }
+    if key.is_alphanumeric() || key.is_whitespace() || key.is_control() {
+        return None;
+    }
+    Some(Delimiters::new(&key.to_string(), &key.to_string()))
+}
+
+/// Returns the delimiters for a tag typed as `div class="x"`, with or without the
+/// surrounding `<` and `>`.
+pub(crate) fn tag_delimiters(input: &str) -> Option<Delimiters> {
+    let input = input.trim();
+    let input = input.strip_prefix('<').unwrap_or(input);
+    let input = input.strip_suffix('>').unwrap_or(input).trim();
+    let name = input.split_whitespace().next()?;
+    Some(Delimiters {
+        open: format!("<{input}>"),
+        close: format!("</{name}>"),
+    })
+}
+
+/// Returns the delimiters that turn a range into the argument list of `name`.
+pub(crate) fn function_delimiters(name: &str, spaced: bool) -> Option<Delimiters> {
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    Some(if spaced {
+        Delimiters {
+            open: format!("{name}( "),
+            close: " )".to_string(),
+        }
+    } else {
+        Delimiters {
+            open: format!("{name}("),
+            close: ")".to_string(),
+        }
+    })
+}
+
+/// Finds the innermost element whose tags enclose `cursor`. Self-closing tags,
+/// comments and declarations are skipped.
+pub(crate) fn find_tag(text: &str, cursor: usize) -> Option<SurroundingPair> {
+    let tag = Regex::new(r"<(/?)([A-Za-z][\w:.-]*)(?:\s[^<>]*?)?(/?)>").ok()?;
+    let mut open_tags: Vec<(&str, Range<usize>)> = Vec::new();
+    let mut best: Option<SurroundingPair> = None;
+    let mut char_offset = 0;
+    let mut byte_offset = 0;
+    for captures in tag.captures_iter(text) {
+        let whole = captures.get(0)?;
+        char_offset += text[byte_offset..whole.start()].chars().count();
+        byte_offset = whole.start();
+        let start = char_offset;
+        let end = start + whole.as_str().chars().count();
+        let name = captures.get(2)?.as_str();
+        if !captures[3].is_empty() {
+            continue;
+        }
+        if captures[1].is_empty() {
+            open_tags.push((name, start..end));
+            continue;
+        }
+        let Some(index) = open_tags
+            .iter()
+            .rposition(|(open_name, _)| *open_name == name)
+        else {
+            continue;
+        };
+        let open = open_tags[index].1.clone();
+        open_tags.truncate(index);
+        if open.start <= cursor && cursor < end {
+            let inner = best
+                .as_ref()
+                .is_some_and(|best| best.open.start >= open.start);
+            if !inner {
+                best = Some(SurroundingPair {
+                    open,
+                    close: start..end,
+                });
+            }
+        }
+    }
+    best
+}
+
+/// Finds the call whose parentheses enclose `cursor`, with the callee name as
+/// part of the opening delimiter.
+pub(crate) fn find_function_call(text: &str, cursor: usize) -> Option<SurroundingPair> {
+    let characters = text.chars().collect::<Vec<_>>();
+    let mut search_from = cursor;
+    loop {
+        let pair = nested_pair(&characters, search_from, &['('], &[')'])?;
+        let paren = pair.open.start;
+        let mut name_start = paren;
+        while name_start > 0 && is_callee_char(characters[name_start - 1]) {
+            name_start -= 1;
+        }
+        if name_start < paren {
+            return Some(SurroundingPair {
+                open: name_start..paren + 1,
+                close: pair.close,
+            });
+        }
+        // A bare parenthesised group; look for a call around it.
+        search_from = paren.checked_sub(1)?;
+    }
+}
+
+/// Finds `open` and `close` around `cursor`. Distinct delimiters nest; identical
+/// ones pair up left to right on the cursor's line.
+pub(crate) fn find_literal_pair(
+    text: &str,
+    cursor: usize,
+    open: &str,
+    close: &str,
+) -> Option<SurroundingPair> {
+    let characters = text.chars().collect::<Vec<_>>();
+    let open = open.chars().collect::<Vec<_>>();
+    let close = close.chars().collect::<Vec<_>>();
+    if open.is_empty() || close.is_empty() || cursor >= characters.len() {
+        return None;
+    }
+    if open != close {
+        return nested_pair(&characters, cursor, &open, &close);
+    }
+
+    let line_start = characters[..cursor]
+        .iter()
+        .rposition(|character| *character == '\n')
+        .map_or(0, |index| index + 1);
+    let line_end = characters[cursor..]
+        .iter()
+        .position(|character| *character == '\n')
+        .map_or(characters.len(), |index| cursor + index);
+    let mut tokens = Vec::new();
+    let mut index = line_start;
+    while index + open.len() <= line_end {
+        if characters[index..].starts_with(&open) {
+            tokens.push(index);
+            index += open.len();
+        } else {
+            index += 1;
+        }
+    }
+    tokens.chunks_exact(2).find_map(|pair| {
+        let (first, second) = (pair[0], pair[1]);
+        (first <= cursor && cursor < second + close.len()).then(|| SurroundingPair {
+            open: first..first + open.len(),
+            close: second..second + close.len(),
+        })
+    })
+}
+
+/// Extends the delimiters of `pair` over the spaces and tabs just inside them.
+pub(crate) fn include_inner_whitespace(text: &str, pair: SurroundingPair) -> SurroundingPair {
+    let characters = text.chars().collect::<Vec<_>>();
+    let is_blank = |index: usize| matches!(characters.get(index), Some(' ' | '\t'));
+    let mut open_end = pair.open.end;
+    while open_end < pair.close.start && is_blank(open_end) {
+        open_end += 1;
+    }
+    let mut close_start = pair.close.start;
+    while close_start > open_end && is_blank(close_start - 1) {
+        close_start -= 1;
+    }
+    SurroundingPair {
+        open: pair.open.start..open_end,
+        close: close_start..pair.close.end,
+    }
+}
+
+/// Returns the innermost `open`/`close` pair that contains `cursor`.
+fn nested_pair(
+    characters: &[char],
+    cursor: usize,
+    open: &[char],
+    close: &[char],
+) -> Option<SurroundingPair> {
+    let mut openers = Vec::new();
+    let mut best: Option<SurroundingPair> = None;
+    let mut index = 0;
+    while index < characters.len() {
+        if characters[index..].starts_with(open) {
+            openers.push(index);
+            index += open.len();
+        } else if characters[index..].starts_with(close) {
+            if let Some(start) = openers.pop() {
+                let end = index + close.len();
+                let inner = best.as_ref().is_some_and(|best| best.open.start > start);
+                if start <= cursor && cursor < end && !inner {
+                    best = Some(SurroundingPair {
+                        open: start..start + open.len(),
+                        close: index..end,
+                    });
+                }
+            }
+            index += close.len();
+        } else {
+            index += 1;
+        }
+    }
+    best
+}
+
+fn is_callee_char(character: char) -> bool {
+    character.is_alphanumeric() || matches!(character, '_' | '.' | ':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs() -> Vec<[String; 2]> {
+        vec![["«".to_string(), "»".to_string()]]
+    }
+
+    #[test]
+    fn keys_map_to_spaced_plain_and_configured_delimiters() {
+        let pairs = pairs();
+        assert_eq!(
+            delimiters_for_key('(', &pairs),
+            Some(Delimiters::new("( ", " )"))
+        );
+        assert_eq!(
+            delimiters_for_key('B', &pairs),
+            Some(Delimiters::new("{", "}"))
+        );
+        assert_eq!(
+            delimiters_for_key('»', &pairs),
+            Some(Delimiters::new("«", "»"))
+        );
+        assert_eq!(
+            delimiters_for_key('*', &pairs),
+            Some(Delimiters::new("*", "*"))
+        );
+        assert_eq!(delimiters_for_key('x', &pairs), None);
+    }
+
+    #[test]
+    fn tags_and_functions_come_from_the_typed_name() {
+        assert_eq!(
+            tag_delimiters("div class=\"a\""),
+            Some(Delimiters::new("<div class=\"a\">", "</div>"))
+        );
+        assert_eq!(
+            tag_delimiters("<em>"),
+            Some(Delimiters::new("<em>", "</em>"))
+        );
+        assert_eq!(tag_delimiters(" "), None);
+        assert_eq!(
+            function_delimiters("print", true),
+            Some(Delimiters::new("print( ", " )"))
+        );
+    }
+
+    #[test]
+    fn finds_the_innermost_tag_and_call_around_the_cursor() {
+        let text = "<p>a <b>bold</b> <br/>é</p>";
+        let pair = find_tag(text, 9).unwrap();
+        assert_eq!((pair.open, pair.close), (5..8, 12..16));
+        let pair = find_tag(text, 22).unwrap();
+        assert_eq!((pair.open, pair.close), (0..3, 23..27));
+
+        let text = "x = outer((1), inner(2))";
+        let pair = find_function_call(text, 11).unwrap();
+        assert_eq!((pair.open, pair.close), (4..10, 23..24));
+        let pair = find_function_call(text, 21).unwrap();
+        assert_eq!((pair.open, pair.close), (15..21, 22..23));
+    }
+
+    #[test]
+    fn finds_nested_and_same_line_literal_pairs() {
+        let text = "«a «b» c»";
+        let pair = find_literal_pair(text, 7, "«", "»").unwrap();
+        assert_eq!((pair.open, pair.close), (0..1, 8..9));
+        let pair = find_literal_pair(text, 4, "«", "»").unwrap();
+        assert_eq!((pair.open, pair.close), (3..4, 5..6));
+
+        let text = "*one* two\n*three*";
+        let pair = find_literal_pair(text, 2, "*", "*").unwrap();
+        assert_eq!((pair.open, pair.close), (0..1, 4..5));
+        assert_eq!(find_literal_pair(text, 7, "*", "*"), None);
+
+        let pair = include_inner_whitespace(
+            "(  x )",
+            SurroundingPair {
+                open: 0..1,
+                close: 5..6,
+            },
+        );
+        assert_eq!((pair.open, pair.close), (0..3, 4..6));
+    }
+}
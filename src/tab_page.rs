@@ -0,0 +1,188 @@
+//! Tab pages: window layouts the editor switches between as a whole.
+//!
+//! Each tab page is a [`WindowManager`] with its own split tree, focused window and
+//! per-window view state. The editor keeps the current page in its `window_manager`
+//! field, so window commands never need to know about tab pages, and [`TabPages`]
+//! holds the others in order on either side of it. Buffers, registers, plugin panels
+//! and workspaces are shared by every page.
+
+use std::mem;
+
+use crate::window::{Window, WindowManager};
+
+/// The tab pages that are not current, in order around the current one.
+#[derive(Default)]
+pub struct TabPages {
+    before: Vec<WindowManager>,
+    after: Vec<WindowManager>,
+}
+
+impl TabPages {
+    /// Rebuilds tab pages from `pages` in order, returning the page at `current`
+    /// separately. Returns `None` when `pages` is empty.
+    pub fn from_pages(
+        mut pages: Vec<WindowManager>,
+        current: usize,
+    ) -> Option<(WindowManager, Self)> {
+        if pages.is_empty() {
+            return None;
+        }
+        let current = current.min(pages.len() - 1);
+        let after = pages.split_off(current + 1);
+        let page = pages.pop()?;
+        Some((
+            page,
+            Self {
+                before: pages,
+                after,
+            },
+        ))
+    }
+
+    /// Returns the number of tab pages, the current one included.
+    pub fn count(&self) -> usize {
+        self.before.len() + 1 + self.after.len()
+    }
+
+    /// Returns the position of the current tab page.
+    pub fn current_index(&self) -> usize {
+        self.before.len()
+    }
+
+    /// Returns every tab page in order, with `current` in its place.
+    pub fn pages<'a>(
+        &'a self,
+        current: &'a WindowManager,
+    ) -> impl Iterator<Item = &'a WindowManager> {
+        self.before
+            .iter()
+            .chain(std::iter::once(current))
+            .chain(&self.after)
+    }
+
+    /// Returns the windows of every tab page except the current one.
+    pub fn windows_mut(&mut self) -> impl Iterator<Item = &mut Window> {
+        self.before
+            .iter_mut()
+            .chain(&mut self.after)
+            .flat_map(WindowManager::windows_mut)
+    }
+
+    /// Makes `page` current, just after the page `current` held until now.
+    pub fn open(&mut self, current: &mut WindowManager, page: WindowManager) {
+        self.before.push(mem::replace(current, page));
+    }
+
+    /// Makes the page at `index` current. Returns `false` when it already is or
+    /// does not exist.
+    pub fn select(&mut self, current: &mut WindowManager, index: usize) -> bool {
+        let active = self.current_index();
+        if index == active || index >= self.count() {
+            return false;
+        }
+        let mut others = self.take_others();
+        let target = others.remove(if index < active { index } else { index - 1 });
+        let previous = mem::replace(current, target);
+        others.insert(if active < index { active } else { active - 1 }, previous);
+        self.split_others(others, index);
+        true
+    }
+
+    /// Closes the current page and makes the one after it current, or the one
+    /// before it when it was the last. The last page cannot be closed.
+    pub fn close(&mut self, current: &mut WindowManager) -> Option<WindowManager> {
+        let next = if self.after.is_empty() {
+            self.before.pop()?
+        } else {
+            self.after.remove(0)
+        };
+        Some(mem::replace(current, next))
+    }
+
+    /// Closes every page but the current one, returning them in order.
+    pub fn close_others(&mut self) -> Vec<WindowManager> {
+        self.take_others()
+    }
+
+    /// Moves the current page to `index`, or to the end when `index` is past it.
+    pub fn move_current(&mut self, index: usize) {
+        let index = index.min(self.count() - 1);
+        let others = self.take_others();
+        self.split_others(others, index);
+    }
+
+    fn take_others(&mut self) -> Vec<WindowManager> {
+        let mut others = mem::take(&mut self.before);
+        others.append(&mut self.after);
+        others
+    }
+
+    fn split_others(&mut self, mut others: Vec<WindowManager>, current: usize) {
+        self.after = others.split_off(current);
+        self.before = others;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(buffer_index: usize) -> WindowManager {
+        WindowManager::new(buffer_index, (80, 24))
+    }
+
+    fn order(pages: &TabPages, current: &WindowManager) -> Vec<usize> {
+        pages
+            .pages(current)
+            .map(|page| page.active_window().unwrap().buffer_index)
+            .collect()
+    }
+
+    #[test]
+    fn opens_selects_and_moves_pages_around_the_current_one() {
+        let mut current = page(0);
+        let mut pages = TabPages::default();
+        pages.open(&mut current, page(1));
+        pages.open(&mut current, page(2));
+        assert_eq!(order(&pages, &current), [0, 1, 2]);
+        assert_eq!(pages.current_index(), 2);
+
+        assert!(pages.select(&mut current, 0));
+        assert_eq!(current.active_window().unwrap().buffer_index, 0);
+        assert_eq!(order(&pages, &current), [0, 1, 2]);
+        assert!(!pages.select(&mut current, 0));
+        assert!(!pages.select(&mut current, 3));
+
+        pages.open(&mut current, page(3));
+        assert_eq!(order(&pages, &current), [0, 3, 1, 2]);
+        assert_eq!(pages.current_index(), 1);
+
+        pages.move_current(usize::MAX);
+        assert_eq!(order(&pages, &current), [0, 1, 2, 3]);
+        pages.move_current(0);
+        assert_eq!(order(&pages, &current), [3, 0, 1, 2]);
+        assert_eq!(pages.current_index(), 0);
+    }
+
+    #[test]
+    fn closing_prefers_the_next_page_and_keeps_the_last_one() {
+        let (mut current, mut pages) =
+            TabPages::from_pages(vec![page(0), page(1), page(2)], 1).unwrap();
+        assert_eq!(pages.count(), 3);
+
+        let closed = pages.close(&mut current).unwrap();
+        assert_eq!(closed.active_window().unwrap().buffer_index, 1);
+        assert_eq!(order(&pages, &current), [0, 2]);
+        assert_eq!(pages.current_index(), 1);
+
+        pages.close(&mut current).unwrap();
+        assert_eq!(order(&pages, &current), [0]);
+        assert!(pages.close(&mut current).is_none());
+
+        let (current, mut pages) = TabPages::from_pages(vec![page(0), page(1)], 9).unwrap();
+        assert_eq!(pages.current_index(), 1);
+        assert_eq!(pages.close_others().len(), 1);
+        assert_eq!(order(&pages, &current), [1]);
+        assert!(TabPages::from_pages(Vec::new(), 0).is_none());
+    }
+}
@@ -39,6 +39,12 @@ pub(crate) enum SyntaxObjectKind {
     Class,
     Comment,
     Parameter,
+    Block,
+    Conditional,
+    Loop,
+    Assignment,
+    AssignmentLhs,
+    AssignmentRhs,
 }
 
 impl SyntaxObjectKind {
@@ -48,6 +54,13 @@ impl SyntaxObjectKind {
             'f' => Some(Self::Function),
             'c' => Some(Self::Class),
             'k' => Some(Self::Comment),
+            'a' => Some(Self::Parameter),
+            'x' => Some(Self::Block),
+            'o' => Some(Self::Conditional),
+            'l' => Some(Self::Loop),
+            '=' => Some(Self::Assignment),
+            'L' => Some(Self::AssignmentLhs),
+            'R' => Some(Self::AssignmentRhs),
             _ => None,
         }
     }
@@ -64,10 +77,30 @@ impl SyntaxObjectKind {
             "comment.inner" => Some((Self::Comment, TextObjectScope::Inner)),
             "parameter.outer" => Some((Self::Parameter, TextObjectScope::Around)),
             "parameter.inner" => Some((Self::Parameter, TextObjectScope::Inner)),
+            "block.outer" => Some((Self::Block, TextObjectScope::Around)),
+            "block.inner" => Some((Self::Block, TextObjectScope::Inner)),
+            "conditional.outer" => Some((Self::Conditional, TextObjectScope::Around)),
+            "conditional.inner" => Some((Self::Conditional, TextObjectScope::Inner)),
+            "loop.outer" => Some((Self::Loop, TextObjectScope::Around)),
+            "loop.inner" => Some((Self::Loop, TextObjectScope::Inner)),
+            "assignment.outer" => Some((Self::Assignment, TextObjectScope::Around)),
+            "assignment.inner" => Some((Self::Assignment, TextObjectScope::Inner)),
+            // Either side of an assignment has no surroundings to include.
+            "assignment.lhs" => Some((Self::AssignmentLhs, TextObjectScope::Inner)),
+            "assignment.rhs" => Some((Self::AssignmentRhs, TextObjectScope::Inner)),
             _ => None,
         }
     }
 
+    /// The scope whose starts anchor motions and swaps. Outer parameters carry
+    /// their separators, so parameters are anchored on their inner range.
+    const fn anchor_scope(self) -> TextObjectScope {
+        match self {
+            Self::Parameter => TextObjectScope::Inner,
+            _ => TextObjectScope::Around,
+        }
+    }
+
     const fn capture_prefix(self) -> &'static str {
         match self {
             Self::Call => "@call.",
@@ -75,6 +108,12 @@ impl SyntaxObjectKind {
             Self::Class => "@class.",
             Self::Comment => "@comment.",
             Self::Parameter => "@parameter.",
+            Self::Block => "@block.",
+            Self::Conditional => "@conditional.",
+            Self::Loop => "@loop.",
+            Self::Assignment => "@assignment.",
+            Self::AssignmentLhs => "@assignment.lhs",
+            Self::AssignmentRhs => "@assignment.rhs",
         }
     }
 }
@@ -115,6 +154,7 @@ enum SearchGoal {
     Selection,
     Motion { backward: bool, count: u16 },
     Swap { backward: bool },
+    Document,
 }
 
 /// Caches document parses and indexes only the structural object requested by an edit.
@@ -151,6 +191,12 @@ impl SyntaxTextObjectService {
         kind: SyntaxObjectKind,
         scope: TextObjectScope,
     ) -> anyhow::Result<Option<ResolvedTextObject>> {
+        if matches!(
+            kind,
+            SyntaxObjectKind::AssignmentLhs | SyntaxObjectKind::AssignmentRhs
+        ) {
+            return self.select_assignment_side(buffer, language_id, cursor, kind);
+        }
         let Some(document) =
             self.document(buffer, language_id, kind, cursor, SearchGoal::Selection)?
         else {
@@ -198,6 +244,43 @@ impl SyntaxTextObjectService {
         }))
     }
 
+    /// A side of the innermost assignment around the cursor, or the next side
+    /// after it when no assignment contains the cursor.
+    fn select_assignment_side(
+        &mut self,
+        buffer: &Buffer,
+        language_id: &str,
+        cursor: TextPosition,
+        kind: SyntaxObjectKind,
+    ) -> anyhow::Result<Option<ResolvedTextObject>> {
+        let assignment = self
+            .select(
+                buffer,
+                language_id,
+                cursor,
+                SyntaxObjectKind::Assignment,
+                TextObjectScope::Around,
+            )?
+            .filter(|assignment| contains_position(assignment.range, cursor));
+        let Some(document) =
+            self.document(buffer, language_id, kind, cursor, SearchGoal::Selection)?
+        else {
+            return Ok(None);
+        };
+        let sides = document.captures.get(&kind).into_iter().flatten().copied();
+        let side = match assignment {
+            // Sides of assignments nested in the right-hand side start later.
+            Some(assignment) => sides
+                .filter(|side| contains_range(assignment.range, side.range))
+                .min_by_key(|side| side.start),
+            None => best_capture(sides, cursor, true),
+        };
+        Ok(side.map(|side| ResolvedTextObject {
+            range: side.range,
+            linewise: false,
+        }))
+    }
+
     pub(crate) fn motion_target(
         &mut self,
         buffer: &Buffer,
@@ -222,7 +305,7 @@ impl SyntaxTextObjectService {
             .get(&kind)
             .into_iter()
             .flatten()
-            .filter(|capture| capture.scope == TextObjectScope::Around)
+            .filter(|capture| capture.scope == kind.anchor_scope())
             .map(|capture| capture.range.start)
             .filter(|position| {
                 if backward {
@@ -260,11 +343,7 @@ impl SyntaxTextObjectService {
         else {
             return Ok(None);
         };
-        let scope = if kind == SyntaxObjectKind::Parameter {
-            TextObjectScope::Inner
-        } else {
-            TextObjectScope::Around
-        };
+        let scope = kind.anchor_scope();
         let candidates = document
             .captures
             .get(&kind)
@@ -297,6 +376,52 @@ impl SyntaxTextObjectService {
         Ok(adjacent.map(|capture| (current.range, capture.range)))
     }
 
+    /// Multi-line function, class, and comment spans used as folds when no language
+    /// server provides folding ranges. Ranges are inclusive line pairs in line order.
+    pub(crate) fn fold_ranges(
+        &mut self,
+        buffer: &Buffer,
+        language_id: &str,
+    ) -> anyhow::Result<Vec<(usize, usize)>> {
+        let mut ranges = Vec::new();
+        for kind in [
+            SyntaxObjectKind::Function,
+            SyntaxObjectKind::Class,
+            SyntaxObjectKind::Comment,
+        ] {
+            let Some(document) = self.document(
+                buffer,
+                language_id,
+                kind,
+                TextPosition::new(0, 0),
+                SearchGoal::Document,
+            )?
+            else {
+                return Ok(Vec::new());
+            };
+            ranges.extend(
+                document
+                    .captures
+                    .get(&kind)
+                    .into_iter()
+                    .flatten()
+                    .filter(|capture| capture.scope == TextObjectScope::Around)
+                    .map(|capture| {
+                        let end = if capture.range.end.character == 0 {
+                            capture.range.end.line.saturating_sub(1)
+                        } else {
+                            capture.range.end.line
+                        };
+                        (capture.range.start.line, end)
+                    })
+                    .filter(|(start, end)| end > start),
+            );
+        }
+        ranges.sort_unstable();
+        ranges.dedup();
+        Ok(ranges)
+    }
+
     fn document(
         &mut self,
         buffer: &Buffer,
@@ -532,6 +657,7 @@ impl SearchGoal {
             Self::Selection | Self::Swap { .. } => {
                 position.saturating_sub(window)..position.saturating_add(window).min(length)
             }
+            Self::Document => 0..length,
         }
     }
 
@@ -545,6 +671,7 @@ impl SearchGoal {
             return false;
         };
         match self {
+            Self::Document => true,
             Self::Selection => captures.iter().any(|capture| {
                 contains_position(capture.range, position)
                     || position_key(capture.range.start) > position_key(position)
@@ -552,7 +679,7 @@ impl SearchGoal {
             Self::Motion { backward, count } => {
                 let mut positions = captures
                     .iter()
-                    .filter(|capture| capture.scope == TextObjectScope::Around)
+                    .filter(|capture| capture.scope == kind.anchor_scope())
                     .map(|capture| position_key(capture.range.start))
                     .filter(|start| {
                         if backward {
@@ -567,11 +694,7 @@ impl SearchGoal {
                 positions.len() >= usize::from(count.max(1))
             }
             Self::Swap { backward } => {
-                let scope = if kind == SyntaxObjectKind::Parameter {
-                    TextObjectScope::Inner
-                } else {
-                    TextObjectScope::Around
-                };
+                let scope = kind.anchor_scope();
                 let Some(current) = best_capture(
                     captures
                         .iter()
@@ -717,8 +840,12 @@ fn capture_container(kind: SyntaxObjectKind, node: Node<'_>) -> Option<Container
                     | "object_type"
                     | "interface_body"
                     | "array"
+                    | "object"
                     | "inline_table"
                     | "table_constructor"
+                    | "flow_sequence"
+                    | "flow_mapping"
+                    | "command"
             ) {
                 return Some(Container {
                     start: candidate.start_byte(),
@@ -852,6 +979,12 @@ mod tests {
                 SyntaxObjectKind::Class,
                 SyntaxObjectKind::Comment,
                 SyntaxObjectKind::Parameter,
+                SyntaxObjectKind::Block,
+                SyntaxObjectKind::Conditional,
+                SyntaxObjectKind::Loop,
+                SyntaxObjectKind::Assignment,
+                SyntaxObjectKind::AssignmentLhs,
+                SyntaxObjectKind::AssignmentRhs,
             ] {
                 let scoped = structural_query_patterns(&source)
                     .into_iter()
@@ -1002,6 +1135,207 @@ mod tests {
         assert_eq!(next.range.start, TextPosition::new(1, 0));
     }
 
+    #[test]
+    fn rust_arguments_blocks_conditionals_loops_and_assignment_sides() {
+        let source = "fn run(a: u8, b: u8) {\n    let total = a + b;\n    if total > 1 {\n        go(total, 2);\n    }\n    for item in 0..3 {\n        step(item);\n    }\n}\n";
+        let buffer = Buffer::new(Some("sample.rs".to_string()), source.to_string());
+        let mut service = SyntaxTextObjectService::new(Arc::new(LanguageRegistry::bundled()));
+
+        for (cursor, kind, scope, expected) in [
+            (
+                (0, 14),
+                SyntaxObjectKind::Parameter,
+                TextObjectScope::Inner,
+                "b: u8",
+            ),
+            (
+                (3, 8),
+                SyntaxObjectKind::Block,
+                TextObjectScope::Inner,
+                "go(total, 2);",
+            ),
+            (
+                (3, 8),
+                SyntaxObjectKind::Conditional,
+                TextObjectScope::Around,
+                "if total > 1 {\n        go(total, 2);\n    }",
+            ),
+            (
+                (6, 8),
+                SyntaxObjectKind::Loop,
+                TextObjectScope::Inner,
+                "step(item);",
+            ),
+            (
+                (1, 8),
+                SyntaxObjectKind::Assignment,
+                TextObjectScope::Around,
+                "let total = a + b;",
+            ),
+            (
+                (1, 16),
+                SyntaxObjectKind::AssignmentLhs,
+                TextObjectScope::Around,
+                "total",
+            ),
+            (
+                (1, 8),
+                SyntaxObjectKind::AssignmentRhs,
+                TextObjectScope::Inner,
+                "a + b",
+            ),
+        ] {
+            let object = service
+                .select(
+                    &buffer,
+                    "rust",
+                    TextPosition::new(cursor.0, cursor.1),
+                    kind,
+                    scope,
+                )
+                .unwrap()
+                .unwrap_or_else(|| panic!("{kind:?} {scope:?} should resolve"));
+            assert_eq!(buffer.text_in_range(object.range), expected, "{kind:?}");
+            assert!(!object.linewise);
+        }
+
+        let next = service
+            .motion_target(
+                &buffer,
+                "rust",
+                TextPosition::new(0, 7),
+                SyntaxObjectKind::Parameter,
+                false,
+                1,
+            )
+            .unwrap();
+        assert_eq!(next, Some(TextPosition::new(0, 14)));
+    }
+
+    #[test]
+    fn python_and_go_fallbacks_carry_arguments_blocks_conditionals_loops_and_assignments() {
+        // Both grammars come from language packs, so only the fallback queries
+        // bundled for them are available to check here.
+        for (language_id, source) in [
+            ("python", include_str!("queries/textobjects/python.scm")),
+            ("go", include_str!("queries/textobjects/go.scm")),
+        ] {
+            let patterns = structural_query_patterns(source);
+            for kind in [
+                SyntaxObjectKind::Parameter,
+                SyntaxObjectKind::Block,
+                SyntaxObjectKind::Conditional,
+                SyntaxObjectKind::Loop,
+                SyntaxObjectKind::Assignment,
+                SyntaxObjectKind::AssignmentLhs,
+                SyntaxObjectKind::AssignmentRhs,
+            ] {
+                assert!(
+                    patterns
+                        .iter()
+                        .any(|pattern| pattern.contains(kind.capture_prefix())),
+                    "{language_id} should capture {kind:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn data_and_shell_grammars_bundle_the_new_object_queries() {
+        let mut service = SyntaxTextObjectService::new(Arc::new(LanguageRegistry::bundled()));
+        for (language_id, name, source, cursor, kind, scope, expected) in [
+            (
+                "json",
+                "sample.json",
+                "{\"a\": [1, 2], \"b\": true}",
+                (0, 10),
+                SyntaxObjectKind::Parameter,
+                TextObjectScope::Inner,
+                "2",
+            ),
+            (
+                "json",
+                "sample.json",
+                "{\"a\": [1, 2], \"b\": true}",
+                (0, 16),
+                SyntaxObjectKind::AssignmentRhs,
+                TextObjectScope::Inner,
+                "true",
+            ),
+            (
+                "toml",
+                "sample.toml",
+                "[package]\nname = \"red\"\n",
+                (1, 0),
+                SyntaxObjectKind::AssignmentLhs,
+                TextObjectScope::Inner,
+                "name",
+            ),
+            (
+                "yaml",
+                "sample.yaml",
+                "items: [one, two]\n",
+                (0, 13),
+                SyntaxObjectKind::Parameter,
+                TextObjectScope::Inner,
+                "two",
+            ),
+            (
+                "bash",
+                "sample.sh",
+                "for ((i = 0; i < 3; i++)); do\n  echo \"$i\"\ndone\n",
+                (1, 2),
+                SyntaxObjectKind::Loop,
+                TextObjectScope::Around,
+                "for ((i = 0; i < 3; i++)); do\n  echo \"$i\"\ndone",
+            ),
+            (
+                "fish",
+                "sample.fish",
+                "begin\n    echo olá\nend\n",
+                (1, 4),
+                SyntaxObjectKind::Block,
+                TextObjectScope::Inner,
+                "echo olá",
+            ),
+            (
+                "powershell",
+                "sample.ps1",
+                "$total = 1\nwhile ($total) {\n    $total = 0\n}\n",
+                (2, 4),
+                SyntaxObjectKind::Loop,
+                TextObjectScope::Around,
+                "while ($total) {\n    $total = 0\n}",
+            ),
+            (
+                "powershell",
+                "sample.ps1",
+                "$total = 1\n",
+                (0, 0),
+                SyntaxObjectKind::AssignmentLhs,
+                TextObjectScope::Inner,
+                "$total",
+            ),
+        ] {
+            let buffer = Buffer::new(Some(name.to_string()), source.to_string());
+            let object = service
+                .select(
+                    &buffer,
+                    language_id,
+                    TextPosition::new(cursor.0, cursor.1),
+                    kind,
+                    scope,
+                )
+                .unwrap()
+                .unwrap_or_else(|| panic!("{language_id} {kind:?} should resolve"));
+            assert_eq!(
+                buffer.text_in_range(object.range),
+                expected,
+                "{language_id} {kind:?}"
+            );
+        }
+    }
+
     #[test]
     fn cache_refreshes_after_edit_and_registry_replacement() {
         let mut buffer = Buffer::new(
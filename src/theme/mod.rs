@@ -202,6 +202,46 @@ impl Theme {
         style
     }
 
+    /// Placeholder row drawn in place of a closed fold.
+    pub(crate) fn folded_style(&self) -> Style {
+        let editor_background = self.style.bg.unwrap_or_default();
+        let background = self
+            .colors
+            .get("editor.foldBackground")
+            .copied()
+            .map(|color| blend_color(color, editor_background))
+            .or(self
+                .line_highlight_style
+                .as_ref()
+                .and_then(|style| style.bg))
+            .unwrap_or(editor_background);
+        let foreground = self
+            .get_style("comment")
+            .and_then(|style| style.fg)
+            .or(self.ui_style.muted.fg)
+            .or(self.style.fg)
+            .unwrap_or(Color::Rgb {
+                r: 128,
+                g: 128,
+                b: 128,
+            });
+        Style {
+            fg: Some(ensure_minimum_contrast(foreground, background, 4.5)),
+            bg: Some(background),
+            ..Style::default()
+        }
+    }
+
+    pub(crate) fn fold_marker_style(&self) -> Style {
+        let mut style = self.gutter_style.fallback_bg(&self.style);
+        style.fg = self
+            .colors
+            .get("editorGutter.foldingControlForeground")
+            .copied()
+            .or(style.fg);
+        style
+    }
+
     pub fn get_style(&self, scope: &str) -> Option<Style> {
         compatible_scopes(scope).into_iter().find_map(|candidate| {
             self.token_styles.iter().find_map(|ts| {
@@ -252,6 +292,20 @@ impl Theme {
         })
     }
 
+    /// Underlines a misspelled word, recolouring it only when the theme sets
+    /// `red.spellBadForeground`.
+    pub(crate) fn spell_bad_style(&self, content: &Style) -> Style {
+        Style {
+            fg: self
+                .colors
+                .get("red.spellBadForeground")
+                .copied()
+                .or(content.fg),
+            underline: true,
+            ..content.clone()
+        }
+    }
+
     pub(crate) fn list_selection_style(&self) -> Style {
         Style {
             fg: self
@@ -280,7 +280,9 @@ impl Component for AgentComposer {
                     Mode::Normal => ActionMode::Normal,
                     Mode::Visual | Mode::VisualLine | Mode::VisualBlock => ActionMode::Visual,
                     Mode::Search => ActionMode::Read,
-                    Mode::Insert | Mode::Command => ActionMode::Insert,
+                    Mode::Insert | Mode::Replace | Mode::VirtualReplace | Mode::Command => {
+                        ActionMode::Insert
+                    }
                 })
                 .with_status(self.validation_status)
                 .render(
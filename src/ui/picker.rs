@@ -50,6 +50,7 @@ use super::{
 
 type SelectAction = Box<dyn Fn(String) -> Action + Send>;
 type FilterAction = Box<dyn Fn(&PickerItem, &str) -> Option<i64> + Send + Sync>;
+type ListAction = Box<dyn Fn(Vec<String>) -> Action + Send>;
 type FilterTieBreaker = Box<dyn Fn(&PickerItem) -> usize + Send + Sync>;
 type FilterHighlightAction = Box<dyn Fn(&PickerItem, &str) -> PickerFilterHighlights + Send>;
 
@@ -408,6 +409,8 @@ pub struct Picker {
     busy_since: Option<Instant>,
     busy_frame: u64,
     key_actions: Vec<PickerKeyAction>,
+    /// Editor handlers for key actions that act on every visible row, by action id.
+    list_actions: HashMap<String, ListAction>,
     preview: Option<PickerPreview>,
     item_preview_root: Option<PathBuf>,
     placeholder: Option<String>,
@@ -589,6 +592,7 @@ impl Picker {
             busy_since: None,
             busy_frame: 0,
             key_actions: Vec::new(),
+            list_actions: HashMap::new(),
             preview: None,
             item_preview_root: None,
             placeholder: None,
@@ -1234,6 +1238,19 @@ impl Picker {
             .key_actions
             .iter()
             .find(|action| action.key.to_ascii_lowercase().replace("ctrl-", "c-") == key)?;
+        if let Some(list_action) = self.list_actions.get(&action.action) {
+            let dynamic_items = self.dynamic_items.as_ref()?;
+            let ids = self
+                .visible_dynamic_items
+                .iter()
+                .filter_map(|index| dynamic_items.get(*index))
+                .map(|item| item.id.clone())
+                .collect();
+            return Some(KeyAction::Multiple(vec![
+                Action::CloseDialog,
+                list_action(ids),
+            ]));
+        }
         if let Some(handle) = self.callback_handle {
             return Some(KeyAction::Single(Action::NotifyPicker(
                 handle,
@@ -3808,6 +3825,7 @@ pub struct PickerBuilder {
     id: Option<i32>,
     select_action: Option<SelectAction>,
     filter_action: Option<FilterAction>,
+    list_actions: Vec<(PickerKeyAction, ListAction)>,
     incremental_filter: bool,
     filter_tie_breaker: Option<FilterTieBreaker>,
     filter_highlight_action: Option<FilterHighlightAction>,
@@ -3835,6 +3853,7 @@ impl PickerBuilder {
             id: None,
             select_action: None,
             filter_action: None,
+            list_actions: Vec::new(),
             incremental_filter: false,
             filter_tie_breaker: None,
             filter_highlight_action: None,
@@ -3884,6 +3903,17 @@ impl PickerBuilder {
         self
     }
 
+    /// Adds a key action that closes the picker and hands the ids of every row the
+    /// query leaves visible to `action`, in display order.
+    pub(crate) fn list_action(
+        mut self,
+        key_action: PickerKeyAction,
+        action: impl Fn(Vec<String>) -> Action + Send + 'static,
+    ) -> Self {
+        self.list_actions.push((key_action, Box::new(action)));
+        self
+    }
+
     /// Reuses prior matches when an extended query cannot match previously rejected rows.
     ///
     /// Custom scorers may opt in only when their matching predicate is prefix-monotonic.
@@ -3974,6 +4004,7 @@ impl PickerBuilder {
         let id = self.id;
         let select_action = self.select_action;
         let filter_action = self.filter_action;
+        let list_actions = self.list_actions;
         let incremental_filter = self.incremental_filter;
         let filter_tie_breaker = self.filter_tie_breaker;
         let filter_highlight_action = self.filter_highlight_action;
@@ -3995,6 +4026,12 @@ impl PickerBuilder {
             picker.select_action = Some(select_action);
         }
         picker.filter_action = filter_action;
+        for (key_action, action) in list_actions {
+            picker
+                .list_actions
+                .insert(key_action.action.clone(), action);
+            picker.key_actions.push(key_action);
+        }
         picker.incremental_filter = incremental_filter;
         picker.filter_tie_breaker = filter_tie_breaker;
         picker.filter_highlight_action = filter_highlight_action;
@@ -4030,7 +4067,7 @@ mod tests {
 
     use super::{
         picker_file_icon, picker_file_icon_color, picker_kind_icon, PickerFilterHighlights,
-        PICKER_ICON_WIDTH,
+        PickerKeyAction, PICKER_ICON_WIDTH,
     };
     use crate::{
         buffer::Buffer,
@@ -6907,6 +6944,39 @@ mod tests {
         assert_eq!(visible_picker_labels(&picker), ["second"]);
     }
 
+    #[test]
+    fn list_actions_receive_every_visible_row_in_order() {
+        let editor = test_editor();
+        let mut picker = Picker::builder()
+            .structured_items(vec![
+                dynamic_item("needle", "needle"),
+                dynamic_item("other", "other"),
+                dynamic_item("nearby", "nearby"),
+            ])
+            .list_action(
+                PickerKeyAction {
+                    key: "Ctrl-q".to_string(),
+                    action: "quickfix".to_string(),
+                    label: Some("Send to quickfix list".to_string()),
+                },
+                |ids| Action::Print(ids.join(",")),
+            )
+            .build(&editor);
+
+        picker.filter("ne");
+        assert_eq!(
+            picker.handle_event(&key(KeyCode::Char('q'), KeyModifiers::CONTROL)),
+            Some(KeyAction::Multiple(vec![
+                Action::CloseDialog,
+                Action::Print("needle,nearby".to_string()),
+            ]))
+        );
+        assert!(picker
+            .surface_actions()
+            .iter()
+            .any(|action| action.id == "custom:quickfix"));
+    }
+
     #[test]
     fn replacing_picker_items_invalidates_previous_refinement_candidates() {
         let editor = test_editor();
@@ -120,7 +120,7 @@ pub struct RevertEdit {
 
 /// One concrete replacement applied while traversing undo history, expressed in
 /// the buffer's character coordinates immediately before that replacement.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct AppliedTextEdit {
     /// Inclusive absolute character index before replay.
     pub start_char: usize,
@@ -128,6 +128,10 @@ pub struct AppliedTextEdit {
     pub end_char: usize,
     /// Unicode scalar length of the inserted text.
     pub new_char_len: usize,
+    /// The replaced range in line coordinates before replay.
+    pub range: TextRange,
+    /// Line breaks in the inserted text.
+    pub new_line_breaks: usize,
 }
 
 /// Cursor and viewport state restored around an undo-tree transaction.
@@ -150,13 +154,40 @@ impl CursorSnapshot {
     }
 }
 
+/// One extra cursor of a multi-cursor selection set, in absolute character indexes.
+///
+/// `anchor` equals `head` for a plain cursor; otherwise the range spans the Visual
+/// selection between them. The primary cursor is described by [`CursorSnapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub struct SelectionRange {
+    /// Fixed end of the selection.
+    pub anchor: usize,
+    /// End of the selection that carries the cursor.
+    pub head: usize,
+}
+
+impl SelectionRange {
+    /// Creates a collapsed range holding only a cursor.
+    pub fn cursor(head: usize) -> Self {
+        Self { anchor: head, head }
+    }
+
+    /// Returns the lower and upper character indexes of the range.
+    pub fn span(&self) -> (usize, usize) {
+        (self.anchor.min(self.head), self.anchor.max(self.head))
+    }
+}
+
 /// One logical, attributed undo step and its cursor boundary.
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct EditTransaction {
     /// Stable UUID used by history and selective-revert commands.
     pub id: String,
-    /// Best-effort Unix timestamp in milliseconds.
+    /// Best-effort Unix time in milliseconds at which the transaction was committed.
     pub timestamp_ms: u128,
+    /// Number of the latest file write that saved the state this transaction produced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write: Option<u64>,
     /// Subsystem that initiated the change.
     pub origin: EditOrigin,
     /// Human-readable action label.
@@ -167,6 +198,12 @@ pub struct EditTransaction {
     pub before_cursor: CursorSnapshot,
     /// Cursor state after the final replacement.
     pub after_cursor: CursorSnapshot,
+    /// Secondary cursors before the first replacement.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub before_selections: Vec<SelectionRange>,
+    /// Secondary cursors after the final replacement.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after_selections: Vec<SelectionRange>,
     before_revision: u64,
     after_revision: u64,
 }
@@ -184,15 +221,15 @@ impl EditTransaction {
     ) -> Self {
         Self {
             id: uuid::Uuid::new_v4().to_string(),
-            timestamp_ms: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_millis(),
+            timestamp_ms: now_ms(),
+            write: None,
             origin,
             label: label.into(),
             edits: Vec::new(),
             before_cursor,
             after_cursor: before_cursor,
+            before_selections: Vec::new(),
+            after_selections: Vec::new(),
             before_revision,
             after_revision: before_revision,
         }
@@ -230,10 +267,73 @@ pub struct UndoTreeEntry {
     pub origin: EditOrigin,
     /// Best-effort Unix timestamp in milliseconds.
     pub timestamp_ms: u128,
+    /// Latest file write that saved this state, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write: Option<u64>,
     /// Recorded replacements in application order.
     pub edits: Vec<TextEdit>,
 }
 
+/// How far `:earlier` and `:later` move through undo states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UndoDistance {
+    /// A number of changes in the order they were made.
+    Changes(usize),
+    /// A span of wall-clock time in milliseconds.
+    Milliseconds(u64),
+    /// A number of file writes.
+    Writes(usize),
+}
+
+impl UndoDistance {
+    /// Parses the argument of `:earlier` or `:later`: a count of changes, or a
+    /// count followed by `s`, `m`, `h` or `d` for time or `f` for file writes.
+    /// An empty argument means one change.
+    pub fn parse(argument: &str) -> Option<Self> {
+        if argument.is_empty() {
+            return Some(Self::Changes(1));
+        }
+        let (count, unit) = argument.split_at(
+            argument
+                .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+                .len(),
+        );
+        let count = count.parse::<usize>().ok()?;
+        let seconds: u64 = match unit {
+            "" => return Some(Self::Changes(count)),
+            "f" => return Some(Self::Writes(count)),
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            _ => return None,
+        };
+        let span = u64::try_from(count).ok()?.checked_mul(seconds * 1000)?;
+        Some(Self::Milliseconds(span))
+    }
+}
+
+/// Describes how long ago `timestamp_ms` was, such as `42s ago` or `3h ago`.
+pub fn relative_age(timestamp_ms: u128) -> String {
+    let seconds = now_ms().saturating_sub(timestamp_ms) / 1000;
+    match seconds {
+        0..60 => format!("{seconds}s ago"),
+        60..3_600 => format!("{}m ago", seconds / 60),
+        3_600..86_400 => format!("{}h ago", seconds / 3_600),
+        _ => format!("{}d ago", seconds / 86_400),
+    }
+}
+
+/// One step along the path between two undo states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoStep {
+    /// Undo the current change.
+    Undo,
+    /// Redo the given change, a child of the current state.
+    Redo(usize),
+}
+
 pub const DEFAULT_MAX_UNDO_NODES: usize = 10_000;
 
 fn default_max_undo_nodes() -> usize {
@@ -261,6 +361,10 @@ pub struct UndoHistory {
     next_revision: u64,
     #[serde(default = "default_max_undo_nodes")]
     max_nodes: usize,
+    #[serde(default)]
+    writes: u64,
+    #[serde(default)]
+    root_write: Option<u64>,
 }
 
 impl Default for UndoHistory {
@@ -277,6 +381,8 @@ impl Default for UndoHistory {
             saved_revision: 0,
             next_revision: 1,
             max_nodes: DEFAULT_MAX_UNDO_NODES,
+            writes: 0,
+            root_write: None,
         }
     }
 }
@@ -347,6 +453,7 @@ impl UndoHistory {
                 label: node.transaction.label.clone(),
                 origin: node.transaction.origin.clone(),
                 timestamp_ms: node.transaction.timestamp_ms,
+                write: node.transaction.write,
                 edits: node.transaction.edits.clone(),
             })
             .collect()
@@ -409,6 +516,7 @@ impl UndoHistory {
         }
 
         transaction.after_cursor = after_cursor;
+        transaction.timestamp_ms = now_ms();
         transaction.after_revision = self.next_revision;
         self.next_revision += 1;
         self.current_revision = transaction.after_revision;
@@ -432,6 +540,54 @@ impl UndoHistory {
         true
     }
 
+    /// Returns the revision of the selected history state.
+    pub fn current_revision(&self) -> u64 {
+        self.current_revision
+    }
+
+    /// Folds every transaction committed since history was at `revision` into one
+    /// step that records the secondary cursors around it.
+    ///
+    /// Multi-cursor commands replay per cursor and may commit several transactions;
+    /// they only merge when they form the linear tail ending at the current node.
+    /// Returns `true` when a transaction now carries the selections.
+    pub fn merge_transactions_since(
+        &mut self,
+        revision: u64,
+        before_selections: Vec<SelectionRange>,
+        after_selections: Vec<SelectionRange>,
+    ) -> bool {
+        let len = self.nodes.len();
+        if self.current != Some(len.wrapping_sub(1)) {
+            return false;
+        }
+        let mut first = len - 1;
+        while self.nodes[first].transaction.before_revision != revision {
+            match self.nodes[first].parent {
+                Some(parent) if parent + 1 == first && self.nodes[parent].children == [first] => {
+                    first = parent;
+                }
+                _ => return false,
+            }
+        }
+
+        let merged = self.nodes.split_off(first + 1);
+        let node = &mut self.nodes[first];
+        for next in merged {
+            node.transaction.edits.extend(next.transaction.edits);
+            node.transaction.after_cursor = next.transaction.after_cursor;
+            node.transaction.after_revision = next.transaction.after_revision;
+            node.transaction.write = next.transaction.write;
+        }
+        node.children.clear();
+        node.transaction.before_selections = before_selections;
+        node.transaction.after_selections = after_selections;
+        self.branch_selection
+            .retain(|&parent, _| parent == branch_key(None) || parent < first);
+        self.current = Some(first);
+        true
+    }
+
     /// Enforces `max_nodes` by retaining the newest part of the active branch.
     ///
     /// Once history exceeds the cap, alternate branches are discarded and the
@@ -454,6 +610,12 @@ impl UndoHistory {
         let new_root_revision = retained.first().map_or(self.root_revision, |&index| {
             self.nodes[index].transaction.before_revision
         });
+        let new_root_write = match retained.first() {
+            Some(&index) => self.nodes[index].parent.map_or(self.root_write, |parent| {
+                self.nodes[parent].transaction.write
+            }),
+            None => self.root_write,
+        };
 
         let retained_count = retained.len();
         let mut retained_indices = retained.iter().copied();
@@ -476,6 +638,7 @@ impl UndoHistory {
 
         self.nodes = nodes;
         self.root_revision = new_root_revision;
+        self.root_write = new_root_write;
         self.root_children.clear();
         if !self.nodes.is_empty() {
             self.root_children.push(0);
@@ -512,6 +675,164 @@ impl UndoHistory {
         self.saved_revision = self.current_revision;
     }
 
+    /// Records that the current state was written to its file, numbering writes
+    /// from one so `:earlier {N}f` can return to them.
+    pub fn record_write(&mut self) {
+        self.writes += 1;
+        let write = Some(self.writes);
+        match self.current {
+            Some(index) => self.nodes[index].transaction.write = write,
+            None => self.root_write = write,
+        }
+    }
+
+    /// Returns the number of the current state: zero before the first retained
+    /// change, otherwise the position of the current change in the order changes
+    /// were made.
+    pub fn change_number(&self) -> usize {
+        self.current.map_or(0, |index| index + 1)
+    }
+
+    /// Returns the number of the newest retained change.
+    pub fn newest_change(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the commit time and latest write of the state with number `change`.
+    pub fn change_info(&self, change: usize) -> Option<(Option<u128>, Option<u64>)> {
+        match change.checked_sub(1) {
+            None => Some((None, self.root_write)),
+            Some(index) => self
+                .nodes
+                .get(index)
+                .map(|node| (Some(node.transaction.timestamp_ms), node.transaction.write)),
+        }
+    }
+
+    /// Returns the state `distance` before the current one. Time is measured from
+    /// the current state's commit time; the state before every change counts as
+    /// the oldest possible time, so an earlier state always exists unless the
+    /// current state is already the first.
+    pub fn earlier_change(&self, distance: UndoDistance) -> usize {
+        let current = self.change_number();
+        match distance {
+            UndoDistance::Changes(count) => current.saturating_sub(count),
+            UndoDistance::Milliseconds(span) => {
+                let target = self.state_time(current).saturating_sub(u128::from(span));
+                (1..current)
+                    .rev()
+                    .find(|&change| self.state_time(change) <= target)
+                    .unwrap_or(0)
+            }
+            UndoDistance::Writes(count) => {
+                let writes = self.written_states();
+                // Unsaved changes first return to the write they were made after.
+                let position = match write_position(&writes, current) {
+                    Ok(position) | Err(position) => position,
+                };
+                position
+                    .checked_sub(count)
+                    .map_or(0, |position| writes[position].1)
+            }
+        }
+    }
+
+    /// Returns the state `distance` after the current one. A time span too short
+    /// to reach the next change still moves to it, and going past the last write
+    /// reaches the newest change.
+    pub fn later_change(&self, distance: UndoDistance) -> usize {
+        let current = self.change_number();
+        let newest = self.newest_change();
+        match distance {
+            UndoDistance::Changes(count) => current.saturating_add(count).min(newest),
+            UndoDistance::Milliseconds(span) => {
+                let target = self.state_time(current).saturating_add(u128::from(span));
+                (current + 1..=newest)
+                    .rev()
+                    .find(|&change| self.state_time(change) <= target)
+                    .unwrap_or((current + 1).min(newest))
+            }
+            UndoDistance::Writes(count) => {
+                let writes = self.written_states();
+                let position = match write_position(&writes, current) {
+                    Ok(position) => position + count,
+                    Err(position) => (position + count).saturating_sub(1),
+                };
+                writes.get(position).map_or(newest, |&(_, change)| change)
+            }
+        }
+    }
+
+    /// Returns the undo and redo steps that lead from the current state to
+    /// `change`, or `None` when no such state is retained.
+    pub fn path_to_change(&self, change: usize) -> Option<Vec<UndoStep>> {
+        if change > self.nodes.len() {
+            return None;
+        }
+        let target = change.checked_sub(1);
+        let mut target_path = Vec::new();
+        let mut cursor = target;
+        while let Some(index) = cursor {
+            target_path.push(index);
+            cursor = self.nodes[index].parent;
+        }
+
+        let mut steps = Vec::new();
+        let mut cursor = self.current;
+        while let Some(index) = cursor {
+            if let Some(position) = target_path.iter().position(|&node| node == index) {
+                target_path.truncate(position);
+                break;
+            }
+            steps.push(UndoStep::Undo);
+            cursor = self.nodes[index].parent;
+        }
+        steps.extend(
+            target_path
+                .into_iter()
+                .rev()
+                .map(|index| UndoStep::Redo(index + 1)),
+        );
+        Some(steps)
+    }
+
+    /// Makes `change` the branch the next redo follows. Returns `false` when it
+    /// is not a child of the current state.
+    pub fn select_redo(&mut self, change: usize) -> bool {
+        let Some(index) = change.checked_sub(1) else {
+            return false;
+        };
+        let Some(position) = self
+            .children_for(self.current)
+            .iter()
+            .position(|&child| child == index)
+        else {
+            return false;
+        };
+        self.branch_selection
+            .insert(branch_key(self.current), position);
+        true
+    }
+
+    fn state_time(&self, change: usize) -> u128 {
+        change
+            .checked_sub(1)
+            .map_or(0, |index| self.nodes[index].transaction.timestamp_ms)
+    }
+
+    /// Returns `(write, change)` for every state a write saved, oldest write first.
+    fn written_states(&self) -> Vec<(u64, usize)> {
+        let mut writes = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, node)| node.transaction.write.map(|write| (write, index + 1)))
+            .chain(self.root_write.map(|write| (write, 0)))
+            .collect::<Vec<_>>();
+        writes.sort_unstable();
+        writes
+    }
+
     /// Returns whether the selected history revision differs from the saved revision.
     pub fn is_dirty(&self) -> bool {
         self.current_revision != self.saved_revision
@@ -602,8 +923,19 @@ impl UndoHistory {
                     "undo-tree child {child} is referenced more than once"
                 );
             }
+            anyhow::ensure!(
+                node.transaction
+                    .write
+                    .is_none_or(|write| write > 0 && write <= self.writes),
+                "undo-tree node {index} records a write that never happened"
+            );
             validate_transaction(&node.transaction, index)?;
         }
+        anyhow::ensure!(
+            self.root_write
+                .is_none_or(|write| write > 0 && write <= self.writes),
+            "undo-tree root records a write that never happened"
+        );
 
         for (index, count) in incoming.into_iter().enumerate() {
             anyhow::ensure!(count == 1, "undo-tree node {index} is unreachable");
@@ -803,6 +1135,8 @@ impl UndoHistory {
                         start_char: buffer.position_to_char_idx(current_range.start),
                         end_char: buffer.position_to_char_idx(current_range.end),
                         new_char_len: old_text.chars().count(),
+                        range: current_range,
+                        new_line_breaks: old_text.matches('\n').count(),
                     });
                     buffer.replace_range_raw(current_range, old_text);
                 }
@@ -837,6 +1171,8 @@ impl UndoHistory {
                         start_char: buffer.position_to_char_idx(range.start),
                         end_char: buffer.position_to_char_idx(range.end),
                         new_char_len: new_text.chars().count(),
+                        range: *range,
+                        new_line_breaks: new_text.matches('\n').count(),
                     });
                     buffer.replace_range_raw(*range, new_text);
                 }
@@ -888,6 +1224,27 @@ fn validate_transaction(transaction: &EditTransaction, node: usize) -> anyhow::R
     Ok(())
 }
 
+fn now_ms() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+/// Locates `change` among `writes`: `Ok` with its position when a write saved
+/// it, otherwise `Err` with the number of writes that saved earlier changes.
+fn write_position(writes: &[(u64, usize)], change: usize) -> Result<usize, usize> {
+    writes
+        .iter()
+        .position(|&(_, written)| written == change)
+        .ok_or_else(|| {
+            writes
+                .iter()
+                .filter(|&&(_, written)| written < change)
+                .count()
+        })
+}
+
 fn branch_key(parent: Option<usize>) -> usize {
     parent.unwrap_or(usize::MAX)
 }
@@ -938,7 +1295,10 @@ fn transform_char_index(
 
 #[cfg(test)]
 mod tests {
-    use super::{CursorSnapshot, TextPosition, TextRange, UndoHistory};
+    use super::{
+        CursorSnapshot, SelectionRange, TextPosition, TextRange, UndoDistance, UndoHistory,
+        UndoStep,
+    };
     use crate::buffer::Buffer;
 
     fn commit_insertion(history: &mut UndoHistory, character: usize, text: &str) {
@@ -1087,4 +1447,127 @@ mod tests {
         history.redo(&mut buffer).unwrap();
         assert_eq!(buffer.contents(), "abx");
     }
+
+    #[test]
+    fn merged_cursor_transactions_undo_as_one_step_with_their_selections() {
+        let mut history = UndoHistory::default();
+        commit_insertion(&mut history, 0, "ab");
+        let mark = history.current_revision();
+        commit_insertion(&mut history, 2, "x");
+        commit_insertion(&mut history, 0, "x");
+        let mut buffer = Buffer::new(None, "xabx".to_string());
+
+        assert!(history.merge_transactions_since(
+            mark,
+            vec![SelectionRange::cursor(0)],
+            vec![SelectionRange::cursor(1)],
+        ));
+
+        assert_eq!(history.node_count(), 2);
+        history.validate().unwrap();
+        let latest = history.latest_transaction().unwrap();
+        assert_eq!(latest.edits.len(), 2);
+        assert_eq!(latest.before_selections, [SelectionRange::cursor(0)]);
+        assert_eq!(latest.after_selections, [SelectionRange::cursor(1)]);
+
+        history.undo(&mut buffer).unwrap();
+        assert_eq!(buffer.contents(), "ab");
+        history.redo(&mut buffer).unwrap();
+        assert_eq!(buffer.contents(), "xabx");
+        assert!(!history.merge_transactions_since(
+            history.current_revision(),
+            Vec::new(),
+            Vec::new()
+        ));
+    }
+
+    #[test]
+    fn earlier_and_later_follow_changes_times_and_writes() {
+        let mut history = UndoHistory::default();
+        for (character, text) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            commit_insertion(&mut history, character, text);
+            history.nodes[character].transaction.timestamp_ms = 10_000 * (character as u128 + 1);
+            if character % 2 == 1 {
+                history.record_write();
+            }
+        }
+        history.validate().unwrap();
+        assert_eq!(history.change_number(), 4);
+        assert_eq!(history.change_info(4), Some((Some(40_000), Some(2))));
+
+        assert_eq!(history.earlier_change(UndoDistance::Changes(3)), 1);
+        assert_eq!(history.earlier_change(UndoDistance::Changes(9)), 0);
+        assert_eq!(
+            history.earlier_change(UndoDistance::Milliseconds(15_000)),
+            2
+        );
+        assert_eq!(
+            history.earlier_change(UndoDistance::Milliseconds(60_000)),
+            0
+        );
+        assert_eq!(history.earlier_change(UndoDistance::Writes(1)), 2);
+        assert_eq!(history.earlier_change(UndoDistance::Writes(2)), 0);
+
+        history.current = Some(2);
+        history.current_revision = history.nodes[2].transaction.after_revision;
+        // Unsaved changes return to the write they were made after first.
+        assert_eq!(history.earlier_change(UndoDistance::Writes(1)), 2);
+        assert_eq!(history.earlier_change(UndoDistance::Writes(2)), 0);
+        assert_eq!(history.later_change(UndoDistance::Writes(1)), 4);
+        assert_eq!(history.later_change(UndoDistance::Milliseconds(1)), 4);
+
+        history.current = Some(0);
+        history.current_revision = history.nodes[0].transaction.after_revision;
+        assert_eq!(history.later_change(UndoDistance::Writes(1)), 2);
+        assert_eq!(history.later_change(UndoDistance::Writes(3)), 4);
+        assert_eq!(history.later_change(UndoDistance::Milliseconds(1)), 2);
+        assert_eq!(history.later_change(UndoDistance::Milliseconds(25_000)), 3);
+        assert_eq!(history.later_change(UndoDistance::Changes(9)), 4);
+    }
+
+    #[test]
+    fn paths_between_branches_undo_to_the_shared_state_and_redo_down() {
+        let mut history = UndoHistory::default();
+        let mut buffer = Buffer::new(None, "ab".to_string());
+        commit_insertion(&mut history, 0, "a");
+        commit_insertion(&mut history, 1, "b");
+        history.undo(&mut buffer).unwrap();
+        buffer.replace_range_raw(TextRange::insertion(TextPosition::new(0, 1)), "x");
+        commit_insertion(&mut history, 1, "x");
+        assert_eq!(buffer.contents(), "ax");
+
+        assert_eq!(
+            history.path_to_change(2),
+            Some(vec![UndoStep::Undo, UndoStep::Redo(2)])
+        );
+        assert_eq!(
+            history.path_to_change(0),
+            Some(vec![UndoStep::Undo, UndoStep::Undo])
+        );
+        assert_eq!(history.path_to_change(3), Some(Vec::new()));
+        assert_eq!(history.path_to_change(4), None);
+
+        history.undo(&mut buffer).unwrap();
+        assert!(history.select_redo(2));
+        assert!(!history.select_redo(1));
+        history.redo(&mut buffer).unwrap();
+        assert_eq!(buffer.contents(), "ab");
+    }
+
+    #[test]
+    fn undo_distances_parse_counts_and_units() {
+        assert_eq!(UndoDistance::parse(""), Some(UndoDistance::Changes(1)));
+        assert_eq!(UndoDistance::parse("4"), Some(UndoDistance::Changes(4)));
+        assert_eq!(
+            UndoDistance::parse("10m"),
+            Some(UndoDistance::Milliseconds(600_000))
+        );
+        assert_eq!(
+            UndoDistance::parse("30s"),
+            Some(UndoDistance::Milliseconds(30_000))
+        );
+        assert_eq!(UndoDistance::parse("3f"), Some(UndoDistance::Writes(3)));
+        assert_eq!(UndoDistance::parse("m"), None);
+        assert_eq!(UndoDistance::parse("2w"), None);
+    }
 }
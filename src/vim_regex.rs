@@ -0,0 +1,1165 @@
+//! Vim search patterns for `search.dialect = "vim"`.
+//!
+//! [`translate`] rewrites a Vim pattern into the syntax of the `regex` crate,
+//! following `\v`, `\m`, `\M` and `\V` as it goes. The engine has no lookaround, so
+//! `\zs` and `\ze` become a group around the part that counts as the match, and
+//! [`find_spans`] and [`captures_spans`] rescan from each match start so a match may
+//! begin inside the text an earlier one looked past. Items the engine cannot express
+//! are errors that name the item and its column rather than approximations.
+//! [`Replacement`] reads the replacement half of a Vim-dialect `:s`.
+
+use std::{fmt, ops::Range};
+
+use regex::{CaptureMatches, Captures, Match, Matches, Regex};
+
+/// Name of the group around the text between `\zs` and `\ze`.
+pub const FOCUS_GROUP: &str = "red_zs";
+
+/// A pattern item the translator cannot express, with its one-based column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranslateError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TranslateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.column)
+    }
+}
+
+impl std::error::Error for TranslateError {}
+
+/// A pattern in the `regex` crate's syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranslatedPattern {
+    pub regex: String,
+    /// Number of groups opened before the `\zs` group, whose index shifts the
+    /// groups after it.
+    focus_after: Option<usize>,
+}
+
+impl TranslatedPattern {
+    /// Wraps a pattern that is already in the `regex` crate's syntax.
+    pub fn untranslated(pattern: &str) -> Self {
+        Self {
+            regex: pattern.to_string(),
+            focus_after: None,
+        }
+    }
+
+    /// Returns the `regex` group index of group `group` as the pattern numbers it.
+    /// Group 0 is the whole match, which is the `\zs`..`\ze` part when there is one.
+    pub fn group_index(&self, group: usize) -> usize {
+        match self.focus_after {
+            Some(before) if group == 0 => before + 1,
+            Some(before) if group > before => group + 1,
+            _ => group,
+        }
+    }
+
+    /// Returns the text of the whole match followed by each group, with `""` for a
+    /// group that took no part in the match.
+    pub fn groups<'h>(&self, captures: &Captures<'h>) -> Vec<&'h str> {
+        let count = captures.len() - 1 - usize::from(self.focus_after.is_some());
+        (0..=count)
+            .map(|group| {
+                captures
+                    .get(self.group_index(group))
+                    .map_or("", |group| group.as_str())
+            })
+            .collect()
+    }
+}
+
+/// Returns `Some(true)` for a pattern with `\c` and `Some(false)` for one with
+/// `\C`, which override `ignorecase` and `smartcase`.
+pub fn case_override(pattern: &str) -> Option<bool> {
+    let mut chars = pattern.chars();
+    let mut result = None;
+    while let Some(character) = chars.next() {
+        if character == '\\' {
+            match chars.next() {
+                Some('c') => return Some(true),
+                Some('C') => result = Some(false),
+                _ => {}
+            }
+        }
+    }
+    result
+}
+
+/// Whether `pattern` has an uppercase letter that is not part of an item such as
+/// `\S` or `\_X`, for `smartcase`.
+pub fn has_uppercase(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(character) = chars.next() {
+        if character == '\\' {
+            if let Some('_' | '%' | 'z') = chars.next() {
+                chars.next();
+            }
+        } else if character.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+/// Escapes `text` so a magic pattern matches it literally.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        if matches!(
+            character,
+            '\\' | '/' | '.' | '*' | '$' | '^' | '~' | '[' | ']'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+/// Translates a Vim pattern. `~` matches `previous_replacement`, the replacement
+/// of the last substitute.
+pub fn translate(
+    pattern: &str,
+    previous_replacement: Option<&str>,
+) -> Result<TranslatedPattern, TranslateError> {
+    let mut translator = Translator {
+        pattern,
+        position: 0,
+        magic: Level::Magic,
+        previous_replacement,
+        output: String::from(PREFIX),
+        groups: Vec::new(),
+        group_count: 0,
+        branch_start: true,
+        atom: None,
+        quantified: false,
+        top_level_branch: None,
+        focus_start: None,
+        focus_end: None,
+    };
+    while let Some((start, token)) = translator.next_token() {
+        translator.item(start, token)?;
+    }
+    if let Some(&(_, column)) = translator.groups.last() {
+        return Err(translator.error(column, "unmatched \\("));
+    }
+    let focus_after = translator.wrap_focus()?;
+    Ok(TranslatedPattern {
+        regex: translator.output,
+        focus_after,
+    })
+}
+
+/// Multi-line mode with CRLF-aware anchors, so `^` and `$` work at line boundaries
+/// and `.` stops at either line ending.
+const PREFIX: &str = "(?mR)";
+
+/// How many characters are special: `\V`, `\M`, `\m` and `\v`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    VeryNomagic,
+    Nomagic,
+    Magic,
+    VeryMagic,
+}
+
+/// A pattern character with `\v`, `\m`, `\M` and `\V` applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    Special(char),
+    /// A backslash and a letter, digit or `_`, whose meaning is the same at every
+    /// magic level.
+    Escape(char),
+}
+
+struct Translator<'a> {
+    pattern: &'a str,
+    position: usize,
+    magic: Level,
+    previous_replacement: Option<&'a str>,
+    output: String,
+    /// Output offset and pattern offset of each open group.
+    groups: Vec<(usize, usize)>,
+    group_count: usize,
+    branch_start: bool,
+    /// Output offset of the last atom, which a multi such as `*` repeats.
+    atom: Option<usize>,
+    quantified: bool,
+    /// Pattern offset of the first `\|` outside any group.
+    top_level_branch: Option<usize>,
+    /// Output offset, group count and pattern offset at `\zs`.
+    focus_start: Option<(usize, usize, usize)>,
+    /// Output offset and pattern offset at `\ze`.
+    focus_end: Option<(usize, usize)>,
+}
+
+impl Translator<'_> {
+    fn error(&self, byte: usize, message: impl Into<String>) -> TranslateError {
+        TranslateError {
+            column: self.pattern[..byte].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+
+    /// Returns the pattern text from `start` to the current position.
+    fn source(&self, start: usize) -> &str {
+        &self.pattern[start..self.position]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.pattern[self.position..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let character = self.peek()?;
+        self.position += character.len_utf8();
+        Some(character)
+    }
+
+    /// Reads one token and the byte offset where it starts.
+    fn next_token(&mut self) -> Option<(usize, Token)> {
+        let start = self.position;
+        let character = self.bump()?;
+        let escaped = character == '\\';
+        let character = if escaped {
+            match self.bump() {
+                Some(character) => character,
+                None => return Some((start, Token::Literal('\\'))),
+            }
+        } else {
+            character
+        };
+        let special = match character {
+            '(' | ')' | '|' | '+' | '?' | '=' | '{' | '@' | '%' | '<' | '>' | '&' => {
+                escaped != (self.magic == Level::VeryMagic)
+            }
+            '.' | '*' | '[' | '~' => escaped != (self.magic >= Level::Magic),
+            '^' | '$' => escaped == (self.magic == Level::VeryNomagic),
+            '_' if escaped => return Some((start, Token::Escape(character))),
+            _ if escaped && character.is_ascii_alphanumeric() => {
+                return Some((start, Token::Escape(character)));
+            }
+            _ => false,
+        };
+        Some((
+            start,
+            if special {
+                Token::Special(character)
+            } else {
+                Token::Literal(character)
+            },
+        ))
+    }
+
+    fn item(&mut self, start: usize, token: Token) -> Result<(), TranslateError> {
+        match token {
+            Token::Literal(character) => self.atom_text(&literal(character)),
+            Token::Escape(character) => self.escape(start, character)?,
+            Token::Special(character) => self.special(start, character)?,
+        }
+        Ok(())
+    }
+
+    /// Appends an atom that a following multi repeats.
+    fn atom_text(&mut self, text: &str) {
+        self.atom = Some(self.output.len());
+        self.output.push_str(text);
+        self.branch_start = false;
+        self.quantified = false;
+    }
+
+    /// Appends an item that matches no text, such as an anchor.
+    fn zero_width(&mut self, text: &str) {
+        self.output.push_str(text);
+        self.atom = None;
+        self.branch_start = false;
+        self.quantified = false;
+    }
+
+    fn escape(&mut self, start: usize, character: char) -> Result<(), TranslateError> {
+        if let Some(class) = class(character) {
+            self.atom_text(class);
+            return Ok(());
+        }
+        match character {
+            'v' => self.magic = Level::VeryMagic,
+            'm' => self.magic = Level::Magic,
+            'M' => self.magic = Level::Nomagic,
+            'V' => self.magic = Level::VeryNomagic,
+            'c' | 'C' => {}
+            'e' => self.atom_text(r"\x1B"),
+            't' => self.atom_text(r"\t"),
+            'r' => self.atom_text(r"\r"),
+            'b' => self.atom_text(r"\x08"),
+            'n' => self.atom_text(r"\n"),
+            '_' => self.line_break_item(start)?,
+            'z' => match self.bump() {
+                Some('s') => self.mark_focus_start(start)?,
+                Some('e') => self.mark_focus_end(start)?,
+                Some('(') => {
+                    return Err(self.error(start, "external group \\z( is not supported"));
+                }
+                _ => {
+                    return Err(
+                        self.error(start, format!("unsupported item {}", self.source(start)))
+                    );
+                }
+            },
+            '1'..='9' => {
+                return Err(self.error(
+                    start,
+                    format!("backreference \\{character} is not supported in a pattern"),
+                ));
+            }
+            _ => return Err(self.error(start, format!("unsupported escape \\{character}"))),
+        }
+        Ok(())
+    }
+
+    /// Reads what follows `\_`: a class that also matches a line break, `\_.`,
+    /// `\_^`, `\_$` or `\_[]`.
+    fn line_break_item(&mut self, start: usize) -> Result<(), TranslateError> {
+        match self.bump() {
+            Some('.') => self.atom_text("(?s:.)"),
+            Some('^') => self.zero_width("^"),
+            Some('$') => self.zero_width("$"),
+            Some('[') => {
+                let Some(collection) = self.collection(start)? else {
+                    return Err(self.error(start, "missing ] after \\_["));
+                };
+                self.atom_text(&format!(r"(?:{collection}|\n)"));
+            }
+            Some(character) => {
+                let Some(class) = class(character) else {
+                    return Err(
+                        self.error(start, format!("unsupported item {}", self.source(start)))
+                    );
+                };
+                self.atom_text(&format!(r"(?:{class}|\n)"));
+            }
+            None => return Err(self.error(start, "missing character after \\_")),
+        }
+        Ok(())
+    }
+
+    fn special(&mut self, start: usize, character: char) -> Result<(), TranslateError> {
+        match character {
+            '^' if self.branch_start => self.zero_width("^"),
+            '$' if self.at_branch_end() => self.zero_width("$"),
+            '^' | '$' => self.atom_text(&literal(character)),
+            '.' => self.atom_text("."),
+            '~' => {
+                let Some(replacement) = self.previous_replacement else {
+                    return Err(self.error(start, "no previous substitute replacement for ~"));
+                };
+                self.atom_text(&format!("(?:{})", regex::escape(replacement)));
+            }
+            '[' => match self.collection(start)? {
+                Some(collection) => self.atom_text(&collection),
+                None => self.atom_text(r"\["),
+            },
+            // Vim reads a `*` with nothing to repeat as a literal star.
+            '*' if self.atom.is_none() => self.atom_text(r"\*"),
+            '*' => self.repeat(start, "*")?,
+            '+' => self.repeat(start, "+")?,
+            '=' | '?' => self.repeat(start, "?")?,
+            '{' => {
+                let quantifier = self.brace(start)?;
+                self.repeat(start, &quantifier)?;
+            }
+            '(' => self.open_group(start, true),
+            ')' => {
+                let Some((open, _)) = self.groups.pop() else {
+                    return Err(self.error(start, format!("unmatched {}", self.source(start))));
+                };
+                self.output.push(')');
+                self.atom = Some(open);
+                self.branch_start = false;
+                self.quantified = false;
+            }
+            '|' => {
+                if self.groups.is_empty() && self.top_level_branch.is_none() {
+                    self.top_level_branch = Some(start);
+                }
+                self.output.push('|');
+                self.atom = None;
+                self.branch_start = true;
+                self.quantified = false;
+            }
+            '<' => self.zero_width(r"\b{start}"),
+            '>' => self.zero_width(r"\b{end}"),
+            '&' => {
+                return Err(self.error(
+                    start,
+                    format!("concat branch {} is not supported", self.source(start)),
+                ));
+            }
+            '@' => return Err(self.lookaround(start)),
+            '%' => self.percent(start)?,
+            _ => self.atom_text(&literal(character)),
+        }
+        Ok(())
+    }
+
+    /// Whether the next token ends a branch, which makes `$` an anchor.
+    fn at_branch_end(&mut self) -> bool {
+        let position = self.position;
+        let next = self.next_token();
+        let at_end = match next {
+            None => true,
+            Some((_, Token::Special('|' | ')' | '&'))) => true,
+            Some((_, Token::Escape('n'))) => true,
+            Some((_, Token::Escape('z'))) => self.peek() == Some('e'),
+            Some(_) => false,
+        };
+        self.position = position;
+        at_end
+    }
+
+    fn repeat(&mut self, start: usize, quantifier: &str) -> Result<(), TranslateError> {
+        let Some(atom) = self.atom else {
+            return Err(self.error(start, format!("{} follows nothing", self.source(start))));
+        };
+        if self.quantified {
+            self.output.insert_str(atom, "(?:");
+            self.output.push(')');
+        }
+        self.output.push_str(quantifier);
+        self.quantified = true;
+        Ok(())
+    }
+
+    /// Reads the rest of `\{n,m}` and returns the equivalent quantifier. A leading
+    /// `-` makes it lazy.
+    fn brace(&mut self, start: usize) -> Result<String, TranslateError> {
+        let lazy = self.peek() == Some('-');
+        if lazy {
+            self.bump();
+        }
+        let minimum = self.number(10, usize::MAX);
+        let comma = self.peek() == Some(',');
+        let maximum = if comma {
+            self.bump();
+            self.number(10, usize::MAX)
+        } else {
+            None
+        };
+        if self.peek() == Some('\\') {
+            self.bump();
+        }
+        if self.bump() != Some('}') {
+            return Err(self.error(start, format!("missing }} after {}", self.source(start))));
+        }
+        let minimum = minimum.map(|value| value as usize);
+        let maximum = maximum.map(|value| value as usize);
+        let quantifier = match (minimum, comma, maximum) {
+            (None, false, _) | (None | Some(0), true, None) => "*".to_string(),
+            (Some(count), false, _) => format!("{{{count}}}"),
+            (Some(minimum), true, None) => format!("{{{minimum},}}"),
+            (minimum, true, Some(maximum)) => {
+                let minimum = minimum.unwrap_or(0);
+                format!("{{{},{}}}", minimum.min(maximum), minimum.max(maximum))
+            }
+        };
+        Ok(if lazy { quantifier + "?" } else { quantifier })
+    }
+
+    /// Reads up to `digits` digits in `radix`.
+    fn number(&mut self, radix: u32, digits: usize) -> Option<u32> {
+        let rest = &self.pattern[self.position..];
+        let length = rest
+            .chars()
+            .take(digits)
+            .take_while(|character| character.is_digit(radix))
+            .count();
+        let value = u32::from_str_radix(&rest[..length], radix).ok()?;
+        self.position += length;
+        Some(value)
+    }
+
+    fn open_group(&mut self, start: usize, capturing: bool) {
+        self.groups.push((self.output.len(), start));
+        if capturing {
+            self.group_count += 1;
+            self.output.push('(');
+        } else {
+            self.output.push_str("(?:");
+        }
+        self.atom = None;
+        self.branch_start = true;
+        self.quantified = false;
+    }
+
+    /// Reads a `[]` collection after its `[`. Returns `None`, leaving the position
+    /// alone, when there is no closing `]`, since Vim then matches `[` literally.
+    fn collection(&mut self, start: usize) -> Result<Option<String>, TranslateError> {
+        let position = self.position;
+        let mut output = String::from("[");
+        if self.peek() == Some('^') {
+            self.bump();
+            // A negated collection still stops at the end of the line.
+            output.push_str(r"^\n");
+        }
+        let mut first = true;
+        loop {
+            let Some(character) = self.bump() else {
+                self.position = position;
+                return Ok(None);
+            };
+            match character {
+                ']' if !first => break,
+                '[' if self.peek() == Some(':') => {
+                    let rest = &self.pattern[self.position + 1..];
+                    let Some(length) = rest.find(":]") else {
+                        output.push_str(r"\[");
+                        first = false;
+                        continue;
+                    };
+                    let name = &rest[..length];
+                    let Some(class) = named_class(name) else {
+                        return Err(self.error(
+                            self.position - 1,
+                            format!("unsupported character class [:{name}:]"),
+                        ));
+                    };
+                    output.push_str(class);
+                    self.position += length + 3;
+                }
+                '[' if matches!(self.peek(), Some('=' | '.')) => {
+                    return Err(self.error(
+                        self.position - 1,
+                        "equivalence classes and collating elements are not supported",
+                    ));
+                }
+                '-' if !first && self.peek() != Some(']') => output.push('-'),
+                '\\' => {
+                    let escape = self.position - 1;
+                    let value = match self.peek() {
+                        Some('e') => Some('\u{1B}'),
+                        Some('t') => Some('\t'),
+                        Some('r') => Some('\r'),
+                        Some('b') => Some('\u{8}'),
+                        Some('n') => Some('\n'),
+                        Some(character @ ('\\' | ']' | '^' | '-')) => Some(character),
+                        _ => None,
+                    };
+                    if let Some(value) = value {
+                        self.bump();
+                        output.push_str(&literal(value));
+                    } else if let Some(code) = self.character_code(escape)? {
+                        output.push_str(&literal(code));
+                    } else {
+                        output.push_str(r"\\");
+                    }
+                }
+                _ => output.push_str(&literal(character)),
+            }
+            first = false;
+        }
+        if output == "[" {
+            return Err(self.error(start, "empty collection"));
+        }
+        output.push(']');
+        Ok(Some(output))
+    }
+
+    /// Reads `d123`, `o40`, `x20`, `u20AC` or `U0001F600` after a backslash or
+    /// `\%`. Returns `None`, leaving the position alone, for anything else.
+    fn character_code(&mut self, start: usize) -> Result<Option<char>, TranslateError> {
+        let (radix, digits) = match self.peek() {
+            Some('d') => (10, usize::MAX),
+            Some('o') => (8, 4),
+            Some('x') => (16, 2),
+            Some('u') => (16, 4),
+            Some('U') => (16, 8),
+            _ => return Ok(None),
+        };
+        let position = self.position;
+        self.bump();
+        let Some(code) = self.number(radix, digits) else {
+            self.position = position;
+            return Ok(None);
+        };
+        char::from_u32(code)
+            .map(Some)
+            .ok_or_else(|| self.error(start, format!("{} is not a character", self.source(start))))
+    }
+
+    fn percent(&mut self, start: usize) -> Result<(), TranslateError> {
+        if let Some(code) = self.character_code(start)? {
+            self.atom_text(&literal(code));
+            return Ok(());
+        }
+        match self.bump() {
+            Some('(') => self.open_group(start, false),
+            Some('^') => self.zero_width(r"\A"),
+            Some('$') => self.zero_width(r"\z"),
+            Some('[') => {
+                return Err(self.error(start, "optional sequence \\%[] is not supported"));
+            }
+            Some('d' | 'o' | 'x' | 'u' | 'U') => {
+                return Err(self.error(
+                    start,
+                    format!("missing number after {}", self.source(start)),
+                ));
+            }
+            Some(character)
+                if character.is_ascii_digit()
+                    || matches!(
+                        character,
+                        'V' | '#' | 'l' | 'c' | 'v' | 'C' | '\'' | '<' | '>'
+                    ) =>
+            {
+                while self.peek().is_some_and(|next| next.is_ascii_digit()) {
+                    self.bump();
+                }
+                if matches!(character, '\'') || character.is_ascii_digit() {
+                    self.bump();
+                }
+                return Err(self.error(
+                    start,
+                    format!("position item {} is not supported", self.source(start)),
+                ));
+            }
+            _ => {
+                return Err(self.error(start, format!("unsupported item {}", self.source(start))));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads `\@=`, `\@!`, `\@<=`, `\@<!` or `\@>` and explains why it cannot be used.
+    fn lookaround(&mut self, start: usize) -> TranslateError {
+        while self.peek().is_some_and(|next| next.is_ascii_digit()) {
+            self.bump();
+        }
+        let message = match self.bump() {
+            Some('=') => "lookahead {} is not supported; end the match with \\ze instead",
+            Some('!') => "negative lookahead {} is not supported",
+            Some('>') => "atomic group {} is not supported",
+            Some('<') => match self.bump() {
+                Some('=') => "lookbehind {} is not supported; start the match with \\zs instead",
+                Some('!') => "negative lookbehind {} is not supported",
+                _ => "unsupported item {}",
+            },
+            _ => "unsupported item {}",
+        };
+        self.error(start, message.replace("{}", self.source(start)))
+    }
+
+    fn mark_focus_start(&mut self, start: usize) -> Result<(), TranslateError> {
+        self.check_focus_marker(start, self.focus_start.is_some())?;
+        self.focus_start = Some((self.output.len(), self.group_count, start));
+        self.zero_width("");
+        Ok(())
+    }
+
+    fn mark_focus_end(&mut self, start: usize) -> Result<(), TranslateError> {
+        self.check_focus_marker(start, self.focus_end.is_some())?;
+        self.focus_end = Some((self.output.len(), start));
+        self.zero_width("");
+        Ok(())
+    }
+
+    fn check_focus_marker(&self, start: usize, repeated: bool) -> Result<(), TranslateError> {
+        let marker = &self.pattern[start..start + 3];
+        if repeated {
+            return Err(self.error(start, format!("{marker} can only be used once")));
+        }
+        if !self.groups.is_empty() {
+            return Err(self.error(start, format!("{marker} inside a group is not supported")));
+        }
+        Ok(())
+    }
+
+    /// Puts the text between `\zs` and `\ze` in [`FOCUS_GROUP`] and returns the number
+    /// of groups before it.
+    fn wrap_focus(&mut self) -> Result<Option<usize>, TranslateError> {
+        if self.focus_start.is_none() && self.focus_end.is_none() {
+            return Ok(None);
+        }
+        let marker = self
+            .focus_start
+            .map(|(_, _, start)| start)
+            .into_iter()
+            .chain(self.focus_end.map(|(_, start)| start))
+            .min()
+            .unwrap_or_default();
+        if let Some(branch) = self.top_level_branch {
+            return Err(self.error(
+                branch.min(marker),
+                format!(
+                    "{} cannot be combined with a top-level alternative",
+                    &self.pattern[marker..marker + 3]
+                ),
+            ));
+        }
+        let (start, before) = self
+            .focus_start
+            .map_or((PREFIX.len(), 0), |(output, groups, _)| (output, groups));
+        let end = self
+            .focus_end
+            .map_or(self.output.len(), |(output, _)| output);
+        if end < start {
+            let (_, marker) = self.focus_end.unwrap_or_default();
+            return Err(self.error(marker, "\\ze comes before \\zs"));
+        }
+        self.output.insert(end, ')');
+        self.output
+            .insert_str(start, &format!("(?P<{FOCUS_GROUP}>"));
+        Ok(Some(before))
+    }
+}
+
+fn literal(character: char) -> String {
+    regex::escape(character.encode_utf8(&mut [0; 4]))
+}
+
+/// Character classes such as `\s` and `\a`. Negated classes leave out line breaks,
+/// which only the `\_` forms match.
+fn class(character: char) -> Option<&'static str> {
+    Some(match character {
+        's' => r"[ \t]",
+        'S' => r"[^ \t\n]",
+        'd' => "[0-9]",
+        'D' => r"[^0-9\n]",
+        'w' => "[0-9A-Za-z_]",
+        'W' => r"[^0-9A-Za-z_\n]",
+        'h' => "[A-Za-z_]",
+        'H' => r"[^A-Za-z_\n]",
+        'a' => "[A-Za-z]",
+        'A' => r"[^A-Za-z\n]",
+        'l' => "[a-z]",
+        'L' => r"[^a-z\n]",
+        'u' => "[A-Z]",
+        'U' => r"[^A-Z\n]",
+        'x' => "[0-9A-Fa-f]",
+        'X' => r"[^0-9A-Fa-f\n]",
+        'o' => "[0-7]",
+        'O' => r"[^0-7\n]",
+        'i' | 'k' => r"\w",
+        'I' | 'K' => r"[\w&&[^0-9]]",
+        'f' => r"[\w/.\-+,#$%~=]",
+        'F' => r"[[\w/.\-+,#$%~=]&&[^0-9]]",
+        'p' => r"[^\x00-\x1F\x7F]",
+        'P' => r"[^\x00-\x1F\x7F0-9]",
+        _ => return None,
+    })
+}
+
+/// Classes for `[:name:]` inside a collection.
+fn named_class(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alnum" => "[:alnum:]",
+        "alpha" => "[:alpha:]",
+        "blank" => "[:blank:]",
+        "cntrl" => "[:cntrl:]",
+        "digit" => "[:digit:]",
+        "graph" => "[:graph:]",
+        "lower" => "[:lower:]",
+        "print" => "[:print:]",
+        "punct" => "[:punct:]",
+        "space" => "[:space:]",
+        "upper" => "[:upper:]",
+        "xdigit" => "[:xdigit:]",
+        "return" => r"\r",
+        "tab" => r"\t",
+        "escape" => r"\x1B",
+        "backspace" => r"\x08",
+        "ident" | "keyword" => r"\w",
+        "fname" => r"\w/.\-+,#$%~=",
+        _ => return None,
+    })
+}
+
+/// Iterates the non-overlapping matches of `regex` in `haystack`. For a pattern
+/// with `\zs` or `\ze` each span is the [`FOCUS_GROUP`] part of a match.
+pub fn find_spans<'r, 'h>(regex: &'r Regex, haystack: &'h str) -> Spans<'r, 'h> {
+    Spans(match focus_group(regex) {
+        Some(_) => SpansInner::Focus(captures_spans(regex, haystack)),
+        None => SpansInner::Plain(regex.find_iter(haystack)),
+    })
+}
+
+/// Iterates the captures of the matches [`find_spans`] reports; [`focus`] returns
+/// each one's span.
+pub fn captures_spans<'r, 'h>(regex: &'r Regex, haystack: &'h str) -> CapturesSpans<'r, 'h> {
+    CapturesSpans(match focus_group(regex) {
+        Some(group) => CapturesInner::Rescan {
+            regex,
+            haystack,
+            group,
+            next: Some(0),
+            last: None,
+        },
+        None => CapturesInner::Plain(regex.captures_iter(haystack)),
+    })
+}
+
+/// Returns the part of a match that counts as the match.
+pub fn focus<'h>(captures: &Captures<'h>) -> Match<'h> {
+    captures
+        .name(FOCUS_GROUP)
+        .or_else(|| captures.get(0))
+        .expect("regex captures always include the full match")
+}
+
+fn focus_group(regex: &Regex) -> Option<usize> {
+    regex
+        .capture_names()
+        .position(|name| name == Some(FOCUS_GROUP))
+}
+
+pub struct Spans<'r, 'h>(SpansInner<'r, 'h>);
+
+enum SpansInner<'r, 'h> {
+    Plain(Matches<'r, 'h>),
+    Focus(CapturesSpans<'r, 'h>),
+}
+
+impl Iterator for Spans<'_, '_> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            SpansInner::Plain(matches) => matches.next().map(|matched| matched.range()),
+            SpansInner::Focus(captures) => captures.next().map(|captures| focus(&captures).range()),
+        }
+    }
+}
+
+pub struct CapturesSpans<'r, 'h>(CapturesInner<'r, 'h>);
+
+enum CapturesInner<'r, 'h> {
+    Plain(CaptureMatches<'r, 'h>),
+    /// Searches again one character after each match start, since the text a
+    /// match consumed before `\zs` may hold the next focus.
+    Rescan {
+        regex: &'r Regex,
+        haystack: &'h str,
+        group: usize,
+        next: Option<usize>,
+        last: Option<Range<usize>>,
+    },
+}
+
+impl<'h> Iterator for CapturesSpans<'_, 'h> {
+    type Item = Captures<'h>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (regex, haystack, group, next, last) = match &mut self.0 {
+            CapturesInner::Plain(captures) => return captures.next(),
+            CapturesInner::Rescan {
+                regex,
+                haystack,
+                group,
+                next,
+                last,
+            } => (*regex, *haystack, *group, next, last),
+        };
+        loop {
+            let captures = regex.captures_at(haystack, next.take()?)?;
+            let whole = captures.get(0)?;
+            *next = haystack[whole.start()..]
+                .chars()
+                .next()
+                .map(|character| whole.start() + character.len_utf8());
+            let Some(span) = captures.get(group).map(|span| span.range()) else {
+                continue;
+            };
+            let overlaps = last.as_ref().is_some_and(|last| {
+                span.start < last.end || (span.is_empty() && span.start == last.end)
+            });
+            if !overlaps {
+                *last = Some(span);
+                return Some(captures);
+            }
+        }
+    }
+}
+
+/// The replacement of a Vim-dialect `:s`: `&` and `\0` insert the match, `\1`..`\9`
+/// a group, `\r` and `\n` a line break, `\t` a tab, and `\u`, `\l`, `\U`, `\L`,
+/// `\e` and `\E` change case. Any other escaped character is inserted as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    parts: Vec<ReplacementPart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ReplacementPart {
+    Text(String),
+    Group(usize),
+    Case(CaseChange),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaseChange {
+    UpperNext,
+    LowerNext,
+    Upper,
+    Lower,
+    End,
+}
+
+impl Replacement {
+    /// Replaces each unescaped `~` in `replacement` with `previous`, the replacement
+    /// of the last substitute. The result is what the next `~` stands for.
+    pub fn expand_previous(replacement: &str, previous: Option<&str>) -> String {
+        let mut expanded = String::with_capacity(replacement.len());
+        let mut chars = replacement.chars();
+        while let Some(character) = chars.next() {
+            match character {
+                '\\' => {
+                    expanded.push('\\');
+                    if let Some(escaped) = chars.next() {
+                        expanded.push(escaped);
+                    }
+                }
+                '~' => expanded.push_str(previous.unwrap_or_default()),
+                _ => expanded.push(character),
+            }
+        }
+        expanded
+    }
+
+    pub fn parse(replacement: &str) -> Self {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = replacement.chars();
+        while let Some(character) = chars.next() {
+            let part = match character {
+                '&' => ReplacementPart::Group(0),
+                '\\' => match chars.next() {
+                    Some(digit @ '0'..='9') => {
+                        ReplacementPart::Group(digit.to_digit(10).unwrap_or_default() as usize)
+                    }
+                    Some('u') => ReplacementPart::Case(CaseChange::UpperNext),
+                    Some('l') => ReplacementPart::Case(CaseChange::LowerNext),
+                    Some('U') => ReplacementPart::Case(CaseChange::Upper),
+                    Some('L') => ReplacementPart::Case(CaseChange::Lower),
+                    Some('e' | 'E') => ReplacementPart::Case(CaseChange::End),
+                    Some('r' | 'n') => {
+                        text.push('\n');
+                        continue;
+                    }
+                    Some('t') => {
+                        text.push('\t');
+                        continue;
+                    }
+                    Some(escaped) => {
+                        text.push(escaped);
+                        continue;
+                    }
+                    None => {
+                        text.push('\\');
+                        continue;
+                    }
+                },
+                _ => {
+                    text.push(character);
+                    continue;
+                }
+            };
+            if !text.is_empty() {
+                parts.push(ReplacementPart::Text(std::mem::take(&mut text)));
+            }
+            parts.push(part);
+        }
+        if !text.is_empty() {
+            parts.push(ReplacementPart::Text(text));
+        }
+        Self { parts }
+    }
+
+    /// Builds the replacement for one match of `pattern`.
+    pub fn expand(&self, captures: &Captures<'_>, pattern: &TranslatedPattern) -> String {
+        let mut output = String::new();
+        let mut next = None;
+        let mut all = None;
+        for part in &self.parts {
+            let text = match part {
+                ReplacementPart::Text(text) => text.as_str(),
+                ReplacementPart::Group(group) => captures
+                    .get(pattern.group_index(*group))
+                    .map_or("", |group| group.as_str()),
+                ReplacementPart::Case(change) => {
+                    match change {
+                        CaseChange::UpperNext => next = Some(true),
+                        CaseChange::LowerNext => next = Some(false),
+                        CaseChange::Upper => all = Some(true),
+                        CaseChange::Lower => all = Some(false),
+                        CaseChange::End => all = None,
+                    }
+                    continue;
+                }
+            };
+            for character in text.chars() {
+                match next.take().or(all) {
+                    Some(true) => output.extend(character.to_uppercase()),
+                    Some(false) => output.extend(character.to_lowercase()),
+                    None => output.push(character),
+                }
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regex(pattern: &str) -> String {
+        translate(pattern, Some("prev"))
+            .unwrap()
+            .regex
+            .strip_prefix(PREFIX)
+            .unwrap()
+            .to_string()
+    }
+
+    fn error(pattern: &str) -> String {
+        translate(pattern, None).unwrap_err().to_string()
+    }
+
+    fn spans(pattern: &str, haystack: &str) -> Vec<String> {
+        let translated = translate(pattern, None).unwrap();
+        let regex = Regex::new(&translated.regex).unwrap();
+        find_spans(&regex, haystack)
+            .map(|span| haystack[span].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn translates_magic_items() {
+        for (pattern, expected) in [
+            (r"\<word\>", r"\b{start}word\b{end}"),
+            (r"\(a\|b\)\+c\=", "(a|b)+c?"),
+            (r"a\{2,3}b\{-1,}c\{}d\{,4}", "a{2,3}b{1,}?c*d{0,4}"),
+            (r"x\{3}", "x{3}"),
+            (r"\%(ab\)*", "(?:ab)*"),
+            ("^*a.b$", r"^\*a.b$"),
+            ("a^b$c", r"a\^b\$c"),
+            (r"\d\+\s\w", r"[0-9]+[ \t][0-9A-Za-z_]"),
+            (r"\_s\+", r"(?:[ \t]|\n)+"),
+            (r"a\_.b", "a(?s:.)b"),
+            ("[a-z]x[^0-9]", r"[a-z]x[^\n0-9]"),
+            ("[]a]", r"[\]a]"),
+            ("[a", r"\[a"),
+            (r"[\t[:digit:]&~-]", "[\t[:digit:]\\&\\~\\-]"),
+            (r"\%x41\%u20AC", "A€"),
+            (r"~", "(?:prev)"),
+            (r"\%^a\%$", r"\Aa\z"),
+            ("a**", "(?:a*)*"),
+            (r"a\.b\*", r"a\.b\*"),
+            (r"a\cB", "aB"),
+        ] {
+            assert_eq!(regex(pattern), expected, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn switches_magic_levels() {
+        assert_eq!(regex(r"\v(a|b){2}<x>"), r"(a|b){2}\b{start}x\b{end}");
+        assert_eq!(regex(r"\v\(a\)"), r"\(a\)");
+        assert_eq!(regex(r"\Ma.b\.c*\*"), r"a\.b.c\**");
+        assert_eq!(regex(r"\Va.b*[c]$"), r"a\.b\*\[c\]\$");
+        assert_eq!(regex(r"\V\^a\.\$"), "^a.$");
+        assert_eq!(regex(r"\Va^$"), r"a\^\$");
+        assert_eq!(regex(r"\va+\m+"), r"a+\+");
+    }
+
+    #[test]
+    fn reports_untranslatable_items_with_columns() {
+        for (pattern, expected) in [
+            (
+                r"foo\(bar\)\@=",
+                r"lookahead \@= is not supported; end the match with \ze instead at column 11",
+            ),
+            (
+                r"\(foo\)\@<=bar",
+                r"lookbehind \@<= is not supported; start the match with \zs instead at column 8",
+            ),
+            (
+                r"\v(a)@!",
+                "negative lookahead @! is not supported at column 6",
+            ),
+            (
+                r"\(a\)\1",
+                r"backreference \1 is not supported in a pattern at column 6",
+            ),
+            (r"a\&b", r"concat branch \& is not supported at column 2"),
+            (r"\%V", r"position item \%V is not supported at column 1"),
+            (
+                r"a\%23l",
+                r"position item \%23l is not supported at column 2",
+            ),
+            (r"\j", r"unsupported escape \j at column 1"),
+            (r"\(a", r"unmatched \( at column 1"),
+            ("a\\)", r"unmatched \) at column 2"),
+            (r"\+", r"\+ follows nothing at column 1"),
+            ("~", "no previous substitute replacement for ~ at column 1"),
+            (
+                r"\(a\zsb\)",
+                r"\zs inside a group is not supported at column 4",
+            ),
+            (
+                r"a\zsb\|c",
+                r"\zs cannot be combined with a top-level alternative at column 2",
+            ),
+            ("é\\{2", r"missing } after \{2 at column 2"),
+        ] {
+            assert_eq!(error(pattern), expected, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn focus_markers_rescan_after_each_match_start() {
+        assert_eq!(spans(r"foo\zsbar", "foobar bar foobar"), ["bar", "bar"]);
+        assert_eq!(spans(r"foo\zebar", "foobar foobaz"), ["foo"]);
+        assert_eq!(spans(r"a\zsa", "aaaa"), ["a", "a", "a"]);
+        assert_eq!(spans(r"\<\w\+\ze(", "call(x) f (y)"), ["call"]);
+        assert_eq!(spans(r"x\zs\ze", "xx"), ["", ""]);
+        assert_eq!(spans(r"\<\(\w\)\w*", "ab cd"), ["ab", "cd"]);
+    }
+
+    #[test]
+    fn replacements_use_vim_groups_and_case_changes() {
+        let pattern = translate(r"\(\w\+\) \zs\(\w\+\)", None).unwrap();
+        let regex = Regex::new(&pattern.regex).unwrap();
+        let captures = captures_spans(&regex, "hello world").next().unwrap();
+        assert_eq!(focus(&captures).as_str(), "world");
+        for (replacement, expected) in [
+            (r"\2-\1", "world-hello"),
+            (r"[&] \0", "[world] world"),
+            (r"\u\2 \U\1\E!", "World HELLO!"),
+            (r"\L\u\2X", "Worldx"),
+            (r"a\rb\tc\&\\", "a\nb\tc&\\"),
+            ("$1", "$1"),
+        ] {
+            assert_eq!(
+                Replacement::parse(replacement).expand(&captures, &pattern),
+                expected,
+                "{replacement}"
+            );
+        }
+        assert_eq!(Replacement::expand_previous(r"<~\~>", Some("x")), r"<x\~>");
+        assert_eq!(pattern.groups(&captures), ["world", "hello", "world"]);
+    }
+
+    #[test]
+    fn case_and_escape_helpers_skip_items() {
+        assert!(!has_uppercase(r"\S\+\_X\%V"));
+        assert!(has_uppercase(r"\sFoo"));
+        assert_eq!(case_override(r"a\Cb\c"), Some(true));
+        assert_eq!(case_override(r"a\C"), Some(false));
+        assert_eq!(case_override(r"a\\c"), None);
+        assert_eq!(escape("a.b*[c]/~"), r"a\.b\*\[c\]\/\~");
+    }
+}
@@ -12,6 +12,7 @@
 use crate::{
     buffer::BufferId,
     editor::{CursorGoal, Point},
+    fold::{Fold, FoldState},
     undo::TextPosition,
 };
 use serde::{Deserialize, Serialize};
@@ -165,6 +166,9 @@ pub struct Window {
 
     /// Cursor locations remembered for CTRL-O/CTRL-I navigation in this window.
     pub(crate) jump_list: Box<JumpList>,
+
+    /// Folds over the displayed buffer.
+    pub(crate) folds: FoldState,
 }
 
 /// One edit-tracked destination in a window's jumplist.
@@ -211,6 +215,7 @@ impl Window {
             active: false,
             vx: 0,
             jump_list: Box::default(),
+            folds: FoldState::default(),
         }
     }
 
@@ -648,6 +653,32 @@ mod tests {
             .all(|window| !original_ids.contains(&window.id)));
     }
 
+    #[test]
+    fn snapshot_round_trips_window_folds() {
+        let mut manager = WindowManager::new(0, (80, 26));
+        let buffer_id = crate::buffer::Buffer::new(None, String::new()).id();
+        let fold = Fold {
+            start: 2,
+            end: 6,
+            closed: true,
+            source: crate::fold::FoldSource::Manual,
+        };
+        manager
+            .active_window_mut()
+            .unwrap()
+            .folds
+            .folds_mut(buffer_id)
+            .push(fold);
+
+        let snapshot = manager.snapshot();
+        let mut restored =
+            WindowManager::from_snapshot(&snapshot, (80, 26), &HashMap::from([(0, 0)])).unwrap();
+        let window = restored.active_window_mut().unwrap();
+        window.folds.bind(buffer_id);
+
+        assert_eq!(window.folds.folds(buffer_id), &[fold]);
+    }
+
     #[test]
     fn indexed_window_access_matches_tree_order() {
         let mut manager = WindowManager::new(0, (80, 26));
@@ -916,6 +947,9 @@ pub enum SplitSnapshot {
         cy: usize,
         /// Legacy viewport x offset.
         vx: usize,
+        /// Folds over the displayed buffer.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        folds: Vec<Fold>,
     },
     /// Top-and-bottom child snapshots.
     Horizontal {
@@ -1095,6 +1129,7 @@ impl Split {
                 cx: window.cx,
                 cy: window.cy,
                 vx: window.vx,
+                folds: window.folds.snapshot(),
             },
             Split::Horizontal { top, bottom, ratio } => SplitSnapshot::Horizontal {
                 ratio: *ratio,
@@ -1120,6 +1155,7 @@ impl Split {
                 cx,
                 cy,
                 vx,
+                folds,
             } => {
                 let mapped_buffer = *buffer_map.get(buffer_index)?;
                 let mut window = Window::new(mapped_buffer, Point::new(0, 0), (0, 0));
@@ -1130,6 +1166,7 @@ impl Split {
                 window.cx = *cx;
                 window.cy = *cy;
                 window.vx = *vx;
+                window.folds = FoldState::restored(folds.clone());
                 Some(Split::Window(window))
             }
             SplitSnapshot::Horizontal { top, bottom, ratio } => Some(Split::Horizontal {
@@ -12,13 +12,14 @@ use red::{
     buffer::{Buffer, SyntaxSelection},
     clipboard::MemoryClipboardProvider,
     color::Color,
-    config::{Config, KeyAction, LanguageConfig, MatchitLanguageConfig},
-    editor::{Action, Content, Editor, Mode, SearchDirection},
+    config::{Config, KeyAction, LanguageConfig, MatchitLanguageConfig, SearchDialect},
+    editor::{Action, Content, Editor, Mode, QuickfixItem, QuickfixListSpec, SearchDirection},
     lsp::LspClient,
     notification::{MessageAction, Notice, NotificationSource, Severity},
     plugin::{
-        PanelConfig, PanelRow, PanelRowKind, PanelSegment, PanelSide, Runtime, TextPanelBlock,
-        TextPanelBlockFormat, TextPanelBlockKind, TextPanelComposerConfig,
+        LocationColumnEncoding, PanelConfig, PanelRow, PanelRowKind, PanelSegment, PanelSide,
+        PluginLocation, Runtime, TextPanelBlock, TextPanelBlockFormat, TextPanelBlockKind,
+        TextPanelComposerConfig,
     },
     preferences::PreferencesStore,
     theme::{Style, Theme},
@@ -27,7 +28,7 @@ use red::{
 };
 use std::{
     env, fs,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex, MutexGuard},
     time::{Instant, SystemTime, UNIX_EPOCH},
 };
@@ -1247,6 +1248,112 @@ async fn macro_register_notation_can_be_inspected_and_edited() {
         .is_some_and(|message| message.contains("a: i!<Esc>j")));
 }
 
+#[tokio::test]
+async fn named_registers_store_append_and_paste_text() {
+    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+
+    type_normal_keys(&mut harness, "\"ayyj\"Ayy").await;
+    type_normal_keys(&mut harness, "G\"ap").await;
+
+    harness.assert_buffer_contents("one\ntwo\nthree\none\ntwo");
+}
+
+#[tokio::test]
+async fn delete_registers_keep_numbered_small_delete_and_yank_history() {
+    let buffer = Buffer::new(None, "one\ntwo\nthree four".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+
+    type_normal_keys(&mut harness, "dddd").await;
+    type_normal_keys(&mut harness, "x").await;
+    type_normal_keys(&mut harness, "\"2p").await;
+    type_normal_keys(&mut harness, "\"_dd").await;
+    type_normal_keys(&mut harness, "\"1pj0\"-P").await;
+
+    harness.assert_buffer_contents("hree four\nttwo\n");
+
+    let buffer = Buffer::new(None, "alpha beta".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+
+    type_normal_keys(&mut harness, "ywwdw0\"0P").await;
+
+    harness.assert_buffer_contents("alpha alpha ");
+}
+
+#[tokio::test]
+async fn dot_repeat_advances_numbered_register_puts() {
+    let buffer = Buffer::new(None, "a\nb\nc".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+
+    type_normal_keys(&mut harness, "dddd\"1p.").await;
+
+    harness.assert_buffer_contents("c\nb\na");
+}
+
+#[tokio::test]
+async fn counted_register_puts_repeat_with_dot_and_macros() {
+    let buffer = Buffer::new(None, "ab".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+
+    type_normal_keys(&mut harness, "\"ayl\"a3p.").await;
+
+    harness.assert_buffer_contents("aaaaaaab");
+
+    let buffer = Buffer::new(None, "one\ntwo".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+
+    type_normal_keys(&mut harness, "qq\"Ayyjq@q\"aP").await;
+
+    harness.assert_buffer_contents("one\none\ntwo\ntwo");
+}
+
+#[tokio::test]
+async fn clipboard_registers_use_the_clipboard_provider() {
+    let buffer = Buffer::new(None, "one\ntwo".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    let clipboard_text = Arc::new(Mutex::new(None));
+    harness
+        .editor
+        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
+            clipboard_text.clone(),
+        )));
+
+    type_normal_keys(&mut harness, "\"+yw").await;
+    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("one"));
+
+    *clipboard_text.lock().unwrap() = Some("zz".to_string());
+    type_normal_keys(&mut harness, "j\"*P").await;
+
+    harness.assert_buffer_contents("one\nzztwo");
+}
+
+#[tokio::test]
+async fn read_only_registers_paste_but_reject_writes() {
+    let buffer = Buffer::new(None, "x".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+
+    type_normal_keys(&mut harness, "ihi").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    type_normal_keys(&mut harness, "\".p").await;
+
+    harness.assert_buffer_contents("hihix");
+
+    type_normal_keys(&mut harness, "\".dd").await;
+
+    harness.assert_buffer_contents("hihix");
+    assert!(harness
+        .last_error()
+        .is_some_and(|message| message.contains("register . is read-only")));
+
+    harness
+        .execute_action(Action::Command("registers".to_string()))
+        .await
+        .unwrap();
+    type_normal_keys(&mut harness, "0\":P").await;
+
+    harness.assert_buffer_contents("registershihix");
+}
+
 #[tokio::test]
 async fn recursive_macro_stops_at_the_deterministic_depth_limit() {
     let buffer = Buffer::new(None, "text".to_string());
@@ -1550,5554 +1657,6322 @@ async fn substitute_supports_current_whole_numeric_and_visual_ranges() {
 }
 
 #[tokio::test]
-async fn visual_colon_prefills_last_visual_range_and_can_be_cancelled() {
-    for mode in [Mode::Visual, Mode::VisualLine, Mode::VisualBlock] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, "one\ntwo\nthree".to_string()),
-            default_key_config(),
-        );
-        harness
-            .execute_action(Action::EnterMode(mode))
-            .await
-            .unwrap();
-        harness.execute_action(Action::MoveDown).await.unwrap();
-
-        command_key(&mut harness, KeyCode::Char(':')).await;
-
-        harness.assert_mode(Mode::Command);
-        assert_eq!(harness.commandline_text(), "'<,'>");
-
-        command_key(&mut harness, KeyCode::Backspace).await;
-        assert_eq!(harness.commandline_text(), "'<,'");
-        command_key(&mut harness, KeyCode::Esc).await;
-        harness.assert_mode(Mode::Normal);
-        assert_eq!(harness.commandline_text(), "");
-    }
-}
+async fn global_runs_an_ex_command_on_matching_lines_as_one_undo_step() {
+    let mut harness = EditorHarness::with_content("a1\nb\na2\nb\na3");
+    harness
+        .execute_action(Action::Command("g/^a/s/a/X/".to_string()))
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("X1\nb\nX2\nb\nX3");
+    harness.assert_cursor_at(0, 4);
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("a1\nb\na2\nb\na3");
 
-#[tokio::test]
-async fn visual_colon_substitute_is_line_scoped_for_every_visual_mode() {
-    for mode in [Mode::Visual, Mode::VisualLine, Mode::VisualBlock] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, "foo foo\nFoo foo\nfoo foo".to_string()),
-            default_key_config(),
-        );
+    for command in ["v/^a/s/b/Y/", "g!/a/s/b/Y/", "2,4global!#a#s/b/Y/"] {
         harness
-            .execute_action(Action::EnterMode(mode))
+            .execute_action(Action::Command(command.to_string()))
             .await
             .unwrap();
-        harness.execute_action(Action::MoveDown).await.unwrap();
-
-        command_key(&mut harness, KeyCode::Char(':')).await;
-        type_normal_keys(&mut harness, "s/foo/bar/gi").await;
-        command_key(&mut harness, KeyCode::Enter).await;
-
-        harness.assert_mode(Mode::Normal);
-        harness.assert_buffer_contents("bar bar\nbar bar\nfoo foo");
+        harness.assert_buffer_contents("a1\nY\na2\nY\na3");
         harness.execute_action(Action::Undo).await.unwrap();
-        harness.assert_buffer_contents("foo foo\nFoo foo\nfoo foo");
     }
-}
 
-#[tokio::test]
-async fn confirmed_substitute_tracks_each_match_and_is_one_undo_transaction() {
-    let mut harness = EditorHarness::with_content("foo foo\nalpha beta\nfoo gamma");
     harness
-        .execute_action(Action::Command("%s/foo/bar/gc".to_string()))
+        .execute_action(Action::Command("g/missing/s/a/b/".to_string()))
         .await
         .unwrap();
-    harness.assert_buffer_contents("foo foo\nalpha beta\nfoo gamma");
-    harness.assert_cursor_at(0, 0);
-    let first_match = harness.render_cursor_position().unwrap();
-
-    type_normal_keys(&mut harness, "y").await;
-    harness.assert_cursor_at(4, 0);
-    assert_eq!(
-        harness.render_cursor_position(),
-        Some((first_match.0 + 4, first_match.1))
-    );
-
-    type_normal_keys(&mut harness, "n").await;
-    harness.assert_cursor_at(0, 2);
-    assert_eq!(
-        harness.render_cursor_position(),
-        Some((first_match.0, first_match.1 + 2))
-    );
-
-    type_normal_keys(&mut harness, "a").await;
-    harness.assert_buffer_contents("bar foo\nalpha beta\nbar gamma");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("foo foo\nalpha beta\nfoo gamma");
+    harness.assert_buffer_contents("a1\nb\na2\nb\na3");
+    assert_eq!(harness.last_error(), Some("pattern not found: missing"));
 }
 
 #[tokio::test]
-async fn confirmed_substitute_scrolls_to_an_offscreen_match() {
-    let content = (0..10)
-        .map(|line| {
-            if matches!(line, 0 | 9) {
-                "foo".to_string()
-            } else {
-                format!("line {line}")
-            }
-        })
-        .collect::<Vec<_>>()
-        .join("\n");
-    let buffer = Buffer::new(None, content);
-    let mut harness = EditorHarness::with_config_and_size(
-        buffer,
-        Config::default(),
-        /*width*/ 40,
-        /*height*/ 5,
+async fn global_normal_skips_marked_lines_that_were_deleted_or_joined() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "keep\ndrop\ndrop\nkeep\ndrop\nkeep".to_string()),
+        default_key_config(),
     );
     harness
-        .execute_action(Action::Command("%s/foo/bar/gc".to_string()))
+        .execute_action(Action::Command("g/drop/normal dd".to_string()))
         .await
         .unwrap();
-    let first_match = harness.render_cursor_position().unwrap();
-
-    type_normal_keys(&mut harness, "y").await;
-
-    assert_eq!(harness.buffer_line(), 9);
-    assert_eq!(harness.viewport_top(), 9);
-    assert_eq!(harness.render_cursor_position(), Some(first_match));
-}
+    harness.assert_buffer_contents("keep\nkeep\nkeep");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("keep\ndrop\ndrop\nkeep\ndrop\nkeep");
 
-#[tokio::test]
-async fn substitute_uses_rust_regex_captures_and_escaped_delimiters() {
-    let mut harness = EditorHarness::with_content("path/a-12 path/b-34");
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "x\nx\nx\ny".to_string()),
+        default_key_config(),
+    );
     harness
-        .execute_action(Action::Command(
-            r"s/path\/([a-z])-(\d+)/$1:$2/g".to_string(),
-        ))
+        .execute_action(Action::Command("g/x/normal J".to_string()))
         .await
         .unwrap();
-
-    harness.assert_buffer_contents("a:12 b:34");
+    harness.assert_buffer_contents("x x\nx y");
 }
 
 #[tokio::test]
-async fn substitute_does_not_match_the_carriage_return_in_crlf_buffers() {
-    let buffer = Buffer::new(None, "abc\r\ndef\r\n".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+async fn normal_replays_keys_at_the_cursor_over_ranges_and_the_visual_range() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "one\ntwo\nthree".to_string()),
+        default_key_config(),
+    );
+    harness
+        .execute_action(Action::Command("2,3normal A!".to_string()))
+        .await
+        .unwrap();
+    harness.assert_mode(Mode::Normal);
+    harness.assert_buffer_contents("one\ntwo!\nthree!");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("one\ntwo\nthree");
 
+    type_normal_keys(&mut harness, "gg$").await;
     harness
-        .execute_action(Action::Command("%s/.$/X/".to_string()))
+        .execute_action(Action::Command("norm x".to_string()))
         .await
         .unwrap();
+    harness.assert_buffer_contents("on\ntwo\nthree");
 
-    harness.assert_buffer_contents("abX\r\ndeX\r\n");
+    type_normal_keys(&mut harness, "jVj").await;
+    command_key(&mut harness, KeyCode::Char(':')).await;
+    type_normal_keys(&mut harness, "normal I# ").await;
+    command_key(&mut harness, KeyCode::Enter).await;
+    harness.assert_mode(Mode::Normal);
+    harness.assert_buffer_contents("on\n# two\n# three");
     harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("abc\r\ndef\r\n");
+    harness.assert_buffer_contents("on\ntwo\nthree");
 }
 
 #[tokio::test]
-async fn substitute_preserves_unicode_marks_across_undo_and_redo() {
-    let source = "😀 value one\r\n😀 value two\r\n😀 value three\r\n";
-    let replacement = "longer_value";
-    let mut harness =
-        EditorHarness::with_config(Buffer::new(None, source.to_string()), default_key_config());
-
-    harness.execute_action(Action::MoveDown).await.unwrap();
-    for _ in 0..8 {
-        harness.execute_action(Action::MoveRight).await.unwrap();
+async fn ex_addresses_resolve_marks_searches_and_offsets() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "alpha\nbeta\ngamma\ndelta\nepsilon".to_string()),
+        default_key_config(),
+    );
+    for command in ["/gamma/,$-1d", "/gamma/;+d", "3,'ad"] {
+        if command.contains("'a") {
+            type_normal_keys(&mut harness, "3Gjmagg").await;
+        }
+        harness
+            .execute_action(Action::Command(command.to_string()))
+            .await
+            .unwrap();
+        harness.assert_buffer_contents("alpha\nbeta\nepsilon");
+        harness.execute_action(Action::Undo).await.unwrap();
+        harness.assert_buffer_contents("alpha\nbeta\ngamma\ndelta\nepsilon");
     }
-    harness.execute_action(Action::SetMark('a')).await.unwrap();
-    harness
-        .execute_action(Action::Command(format!("%s/value/{replacement}/g")))
-        .await
-        .unwrap();
-
-    harness.assert_buffer_contents(&source.replace("value", replacement));
-    type_normal_keys(&mut harness, "`a").await;
-    harness.assert_cursor_at(8 + replacement.len() - "value".len(), 1);
 
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents(source);
-    type_normal_keys(&mut harness, "`a").await;
-    harness.assert_cursor_at(8, 1);
+    for (command, line) in [("4", 3), ("?alpha?+1", 1), ("+2", 3), ("$", 4), (".-3", 1)] {
+        harness
+            .execute_action(Action::Command(command.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(harness.buffer_line(), line, "{command}");
+    }
 
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents(&source.replace("value", replacement));
-    type_normal_keys(&mut harness, "`a").await;
-    harness.assert_cursor_at(8 + replacement.len() - "value".len(), 1);
+    for (command, error) in [
+        ("/missing/d", "Pattern not found: missing"),
+        ("'bd", "mark b is not set"),
+        ("1-3d", "delete range is before the start of the buffer"),
+        ("4,2d", "delete range start exceeds its end"),
+    ] {
+        harness
+            .execute_action(Action::Command(command.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(harness.last_error(), Some(error), "{command}");
+    }
+    harness.assert_buffer_contents("alpha\nbeta\ngamma\ndelta\nepsilon");
 }
 
 #[tokio::test]
-async fn format_on_save_restores_save_as_identity_and_insert_transaction_after_sync_failure() {
-    let temp = tempfile::tempdir().unwrap();
-    let source = temp.path().join("source.rs");
-    let target = temp.path().join("target.py");
-    fs::write(&source, "disk source\n").unwrap();
-    let source_file = source.to_string_lossy().into_owned();
-    let target_file = target.to_string_lossy().into_owned();
-    let lsp = RecordingLsp::failing_next_did_open();
-    let events = lsp.events();
-    let config = Config::default();
-    let mut editor = Editor::test_with_size(
-        Box::new(lsp),
-        /*width*/ 80,
-        /*height*/ 24,
-        config,
-        Theme::default(),
-        vec![Buffer::new(
-            Some(source_file.clone()),
-            "unsaved source\n".to_string(),
-        )],
-    )
-    .unwrap();
-    editor.test_disable_terminal_output();
-    editor
-        .test_execute_production_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
-    assert!(editor
-        .test_current_buffer()
-        .undo_history
-        .is_transaction_active());
+async fn move_copy_and_shift_lines_are_single_undo_steps() {
+    let original = "a\nb\nc\nd\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, original.to_string()),
+        default_key_config(),
+    );
+    for (command, expected, line) in [
+        ("1,2m$", "c\nd\na\nb\n", 3),
+        ("4m0", "d\na\nb\nc\n", 0),
+        ("2,3t0", "b\nc\na\nb\nc\nd\n", 1),
+        ("1co$", "a\nb\nc\nd\na\n", 4),
+        ("g/^/m0", "d\nc\nb\na\n", 0),
+        ("2,3>>", "a\n        b\n        c\nd\n", 2),
+        ("> 2", "    a\n    b\nc\nd\n", 1),
+    ] {
+        harness
+            .execute_action(Action::Command("1".to_string()))
+            .await
+            .unwrap();
+        harness
+            .execute_action(Action::Command(command.to_string()))
+            .await
+            .unwrap();
+        harness.assert_buffer_contents(expected);
+        assert_eq!(harness.buffer_line(), line, "{command}");
+        harness.execute_action(Action::Undo).await.unwrap();
+        harness.assert_buffer_contents(original);
+    }
 
-    let error = editor
-        .test_execute_production_action(Action::SaveAs(target_file.clone()))
+    harness
+        .execute_action(Action::Command("1,2m1".to_string()))
         .await
-        .unwrap_err();
-
-    assert!(error.to_string().contains("injected didOpen failure"));
-    assert!(editor.test_is_insert());
-    assert!(editor
-        .test_current_buffer()
-        .undo_history
-        .is_transaction_active());
+        .unwrap();
     assert_eq!(
-        editor.test_current_buffer().file.as_deref(),
-        Some(source_file.as_str())
+        harness.last_error(),
+        Some("cannot move a range of lines into itself")
     );
-    assert_eq!(editor.test_current_buffer().contents(), "unsaved source\n");
-    assert_eq!(fs::read_to_string(&source).unwrap(), "disk source\n");
-    assert!(!target.exists());
-    let events = events.lock().unwrap();
-    assert!(events
-        .iter()
-        .any(|event| matches!(event, LspEvent::DidOpen(file) if file == &target_file)));
-    assert!(events
-        .iter()
-        .any(|event| matches!(event, LspEvent::DidOpen(file) if file == &source_file)));
+    harness.assert_buffer_contents(original);
 }
 
-#[cfg(unix)]
 #[tokio::test]
-async fn crash_recovery_keeps_transcript_in_memory_when_preferences_are_unsafe() {
-    let temp = tempfile::tempdir().unwrap();
-    let outside = temp.path().join("outside-preferences.json");
-    let preferences_path = temp.path().join("preferences.json");
-    let recovered_path = temp.path().join("recovered.txt");
-    fs::write(&outside, "outside secret").unwrap();
-    fs::write(&recovered_path, "disk base\n").unwrap();
-    std::os::unix::fs::symlink(&outside, &preferences_path).unwrap();
-    let buffer = Buffer::new(
-        Some(recovered_path.to_string_lossy().into_owned()),
-        "recovered text\n".to_string(),
-    );
-    let mut source = EditorHarness::with_config(buffer, default_key_config());
-    let mut snapshot = source.editor.test_session_snapshot();
-    snapshot.agent_transcript = Some("You: recover me\nAgent: retained\n".to_string());
-    let mut conversation = red::agent_conversation::AgentConversationSnapshot::new(
-        "thread-recoverable",
-        temp.path().to_string_lossy(),
-    );
-    conversation.append_user("turn-1", "recover me");
-    conversation.append_agent_delta("turn-1", "retained");
-    snapshot.agent_conversation = Some(conversation.clone());
-    snapshot.agent_session_resumable = true;
-    let restored_buffers = Editor::buffers_from_session_snapshot(&snapshot);
-    let preferences = PreferencesStore::load(&preferences_path);
-    let mut editor = Editor::test_with_size_and_preferences(
-        Box::new(MockLsp),
-        /*width*/ 80,
-        /*height*/ 24,
+async fn sort_orders_lines_with_flags_as_single_undo_steps() {
+    let original = "c 3\nb 10\na 2\nb 10\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, original.to_string()),
         default_key_config(),
-        Theme::default(),
-        restored_buffers,
-        preferences,
-    )
-    .unwrap();
-    editor.test_disable_terminal_output();
-
-    fs::write(&recovered_path, "external change\n").unwrap();
-    let divergences = editor.restore_session_snapshot(&snapshot).unwrap();
+    );
+    for (command, expected, line) in [
+        ("sort", "a 2\nb 10\nb 10\nc 3\n", 0),
+        ("sor n", "a 2\nc 3\nb 10\nb 10\n", 0),
+        ("sort! u", "c 3\nb 10\na 2\n", 0),
+        ("2,3sort", "c 3\na 2\nb 10\nb 10\n", 1),
+        ("sort / /", "b 10\nb 10\na 2\nc 3\n", 0),
+        ("sort n /b /", "c 3\na 2\nb 10\nb 10\n", 0),
+    ] {
+        harness
+            .execute_action(Action::Command("4".to_string()))
+            .await
+            .unwrap();
+        harness
+            .execute_action(Action::Command(command.to_string()))
+            .await
+            .unwrap();
+        harness.assert_buffer_contents(expected);
+        assert_eq!(harness.buffer_line(), line, "{command}");
+        harness.execute_action(Action::Undo).await.unwrap();
+        harness.assert_buffer_contents(original);
+    }
 
-    let recovered = editor.test_session_snapshot();
-    assert_eq!(divergences.len(), 1);
+    harness
+        .execute_action(Action::Command("sort n x".to_string()))
+        .await
+        .unwrap();
     assert_eq!(
-        recovered.agent_transcript.as_deref(),
-        Some("You: recover me\nAgent: retained\n")
+        harness.last_error(),
+        Some("sort accepts only one of n, x, o, b and f")
     );
-    assert_eq!(recovered.agent_conversation, Some(conversation));
-    assert_eq!(recovered.buffers[0].contents, "recovered text\n");
-    assert_eq!(fs::read_to_string(outside).unwrap(), "outside secret");
-    let restored = EditorHarness { editor };
-    assert!(restored.last_error().is_some_and(|message| {
-        message.contains("changed on disk") && message.contains("could not be persisted")
-    }));
-}
-
-#[tokio::test]
-async fn crash_session_finalizes_an_active_insert_transaction_in_the_snapshot() {
-    let buffer = Buffer::new(None, "base\n".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "iuser ").await;
-    assert!(harness.is_insert());
-
-    let snapshot = harness.editor.test_session_snapshot();
-    let mut restored_buffers = Editor::buffers_from_session_snapshot(&snapshot);
-    let mut restored = EditorHarness::with_config(restored_buffers.remove(0), default_key_config());
-
-    restored.assert_buffer_contents("user base\n");
-    restored.execute_action(Action::Undo).await.unwrap();
-    restored.assert_buffer_contents("base\n");
-    assert!(harness.is_insert());
+    harness.assert_buffer_contents(original);
 }
 
 #[tokio::test]
-async fn unchanged_recovery_snapshots_are_skipped_and_failures_back_off() {
-    let directory = tempfile::tempdir().unwrap();
-    let store = red::session::SessionStore::for_owner(directory.path(), "editor-one").unwrap();
-    let buffer = Buffer::new(None, "base\n".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.editor.set_session_store(store.clone());
-
-    harness
-        .editor
-        .test_persist_session_snapshot(/*force*/ true, /*due*/ true);
-    let generation = store.load().unwrap().generation;
+async fn sort_uses_visual_block_columns_and_keeps_marks_and_jumps() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "a3 x\nc1 y\nb2 z\n".to_string()),
+        default_key_config(),
+    );
+    harness.execute_action(Action::MoveRight).await.unwrap();
     harness
-        .editor
-        .test_persist_session_snapshot(/*force*/ false, /*due*/ true);
-    assert_eq!(store.load().unwrap().generation, generation);
-
-    let blocked_root = directory.path().join("not-a-directory");
-    fs::write(&blocked_root, "blocked").unwrap();
-    let blocked = red::session::SessionStore::for_owner(&blocked_root, "editor-two").unwrap();
-    harness.editor.set_session_store(blocked);
+        .execute_action(Action::EnterMode(Mode::VisualBlock))
+        .await
+        .unwrap();
+    harness.execute_action(Action::MoveDown).await.unwrap();
+    harness.execute_action(Action::MoveDown).await.unwrap();
     harness
-        .editor
-        .test_persist_session_snapshot(/*force*/ false, /*due*/ true);
-    std::thread::sleep(std::time::Duration::from_millis(25));
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
+        .unwrap();
     harness
-        .editor
-        .test_persist_session_snapshot(/*force*/ false, /*due*/ false);
-    assert!(harness.editor.test_session_snapshot_is_backing_off());
-
-    let warning = harness.commandline_row();
-    assert!(warning.contains("Crash recovery is not being saved"));
-    let warning_id = harness
-        .editor
-        .notifications()
-        .primary(Instant::now())
-        .unwrap()
-        .id;
-
-    let error_id = harness
-        .editor
-        .publish_notification(Notice::new(
-            NotificationSource::Editor,
-            Severity::Error,
-            "a newer LSP error",
-        ))
+        .execute_action(Action::Command("'<,'>sort".to_string()))
+        .await
         .unwrap();
-    let status = harness.commandline_row();
-    assert!(status.contains("a newer LSP error"));
-    assert!(status.contains("2 need attention"));
-    assert!(harness
-        .editor
-        .notifications()
-        .get(warning_id)
-        .unwrap()
-        .is_active(Instant::now()));
-    harness.editor.test_set_size(/*width*/ 8, /*height*/ 4);
-    assert_eq!(harness.commandline_row(), "× a  [2]");
-    harness.editor.test_set_size(/*width*/ 120, /*height*/ 24);
+    harness.assert_buffer_contents("c1 y\nb2 z\na3 x\n");
+    assert_eq!(harness.buffer_line(), 0);
 
-    harness.execute_action(Action::OpenMessages).await.unwrap();
+    harness.execute_action(Action::JumpBack).await.unwrap();
+    assert_eq!(harness.buffer_line(), 2);
     harness
-        .execute_action(Action::MessageHistory(MessageAction::Acknowledge))
+        .execute_action(Action::Command("'<".to_string()))
         .await
         .unwrap();
+    assert_eq!(harness.buffer_line(), 0);
     harness
-        .execute_action(Action::MessageHistory(MessageAction::Close))
+        .execute_action(Action::Command("'>".to_string()))
         .await
         .unwrap();
-    assert!(!harness
-        .editor
-        .notifications()
-        .get(error_id)
-        .unwrap()
-        .is_active(Instant::now()));
+    assert_eq!(harness.buffer_line(), 2);
 
     harness
-        .execute_action(Action::Command("1".to_string()))
+        .execute_action(Action::Command("'<,'>sort!".to_string()))
         .await
         .unwrap();
-    harness.editor.test_set_size(/*width*/ 120, /*height*/ 24);
-    assert!(harness
-        .commandline_row()
-        .contains("Crash recovery is not being saved"));
-
-    harness.editor.set_session_store(store);
-    harness
-        .editor
-        .test_persist_session_snapshot(/*force*/ true, /*due*/ true);
-    assert!(!harness
-        .commandline_row()
-        .contains("Crash recovery is not being saved"));
+    harness.assert_buffer_contents("a3 x\nb2 z\nc1 y\n");
 }
 
+#[cfg(unix)]
 #[tokio::test]
-async fn periodic_recovery_snapshot_materializes_shared_buffer_contents() {
-    let directory = tempfile::tempdir().unwrap();
-    let store = red::session::SessionStore::for_owner(directory.path(), "editor-one").unwrap();
-    let expected = format!("prefix 👋 {}\n", "text ".repeat(64 * 1024));
-    let buffer = Buffer::new(None, expected.clone());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.editor.set_session_store(store.clone());
-
-    harness
-        .editor
-        .test_persist_session_snapshot(/*force*/ false, /*due*/ true);
-    harness.editor.test_finish_session_snapshot();
-
-    assert_eq!(store.load().unwrap().buffers[0].contents, expected);
-}
-
-fn tree_rows() -> Vec<PanelRow> {
-    ["root", "src", "main.rs"]
-        .into_iter()
-        .map(|id| PanelRow {
-            id: id.to_string(),
-            path: Some(id.to_string()),
-            expanded: Some(false),
-            kind: if id.ends_with(".rs") {
-                PanelRowKind::File
-            } else {
-                PanelRowKind::Directory
-            },
-            segments: vec![PanelSegment {
-                text: id.to_string(),
-                style: None,
-                semantic: None,
-            }],
-            right_segments: vec![],
-        })
-        .collect()
-}
-
-fn numbered_tree_rows(count: usize) -> Vec<PanelRow> {
-    (0..count)
-        .map(|index| {
-            let id = format!("row-{index:02}.rs");
-            PanelRow {
-                id: id.clone(),
-                path: Some(id.clone()),
-                expanded: Some(false),
-                kind: PanelRowKind::File,
-                segments: vec![PanelSegment {
-                    text: id,
-                    style: None,
-                    semantic: None,
-                }],
-                right_segments: vec![],
-            }
-        })
-        .collect()
-}
-
-fn add_tree_panel(harness: &mut EditorHarness) {
-    harness.editor.test_create_panel(
-        "tree",
-        PanelConfig {
-            side: PanelSide::Left,
-            width: 20,
-            title: None,
-            composer: None,
-            surface: None,
-            border: None,
-            header_actions: Vec::new(),
-        },
+async fn filters_replace_lines_with_command_output_as_one_undo_step() {
+    let original = "b\nc\na\nd";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, original.to_string()),
+        default_key_config(),
     );
-    harness.editor.test_update_panel("tree", tree_rows());
-}
+    for (command, expected, line) in [
+        ("1,3!sort", "a\nb\nc\nd", 0),
+        ("2,$!tr a-z A-Z", "b\nC\nA\nD", 1),
+        ("%!sh -c 'cat; echo careful >&2'", original, 0),
+        ("2,3!true", "b\nd", 1),
+        ("3,4!head -n 1", "b\nc\na", 2),
+    ] {
+        harness
+            .execute_action(Action::Command(command.to_string()))
+            .await
+            .unwrap();
+        harness.assert_buffer_contents(expected);
+        assert_eq!(harness.buffer_line(), line, "{command}");
+        harness.execute_action(Action::Undo).await.unwrap();
+        harness.assert_buffer_contents(original);
+    }
+    let stderr = harness
+        .editor
+        .notifications()
+        .records()
+        .find(|record| record.content.summary == "careful");
+    assert!(stderr.is_some());
 
-async fn command_key(harness: &mut EditorHarness, code: KeyCode) {
     harness
-        .execute_event(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+        .execute_action(Action::Command(
+            "%!sh -c 'echo broken >&2; exit 3'".to_string(),
+        ))
         .await
         .unwrap();
+    harness.assert_buffer_contents(original);
+    assert_eq!(
+        harness.last_error(),
+        Some("sh -c 'echo broken >&2; exit 3' exited with exit status: 3")
+    );
+    let failure = harness.editor.notifications().records().last().unwrap();
+    assert_eq!(failure.content.details.as_deref(), Some("broken"));
 }
 
+#[cfg(unix)]
 #[tokio::test]
-async fn command_backspace_exits_an_empty_prompt() {
+async fn read_and_write_commands_insert_output_files_and_pipe_lines() {
+    let directory = tempfile::tempdir().unwrap();
+    let file = directory.path().join("lines.txt");
+    std::fs::write(&file, "from file\n").unwrap();
     let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "unchanged".to_string()),
+        Buffer::new(None, "one\ntwo\n".to_string()),
         default_key_config(),
     );
-    command_key(&mut harness, KeyCode::Char(':')).await;
-    harness.assert_mode(Mode::Command);
-    assert_eq!(harness.commandline_row().trim_end(), ":");
-
-    command_key(&mut harness, KeyCode::Backspace).await;
-
-    harness.assert_mode(Mode::Normal);
-    assert_eq!(harness.commandline_text(), "");
-    assert_eq!(harness.commandline_row().trim_end(), "");
-    harness.assert_buffer_contents("unchanged");
-}
-
-#[tokio::test]
-async fn command_backspace_deletes_text_before_exiting() {
-    for character in ['x', 'é', ' '] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, "unchanged".to_string()),
-            default_key_config(),
-        );
-        command_key(&mut harness, KeyCode::Char(':')).await;
-        command_key(&mut harness, KeyCode::Char(character)).await;
-
-        command_key(&mut harness, KeyCode::Backspace).await;
-
-        harness.assert_mode(Mode::Command);
-        assert_eq!(harness.commandline_text(), "");
-        assert_eq!(harness.commandline_row().trim_end(), ":");
-
-        command_key(&mut harness, KeyCode::Backspace).await;
-        harness.assert_mode(Mode::Normal);
-        assert_eq!(harness.commandline_row().trim_end(), "");
-        harness.assert_buffer_contents("unchanged");
-    }
-}
-
-struct CurrentDirGuard {
-    original: PathBuf,
-    _lock: MutexGuard<'static, ()>,
-}
-
-impl Drop for CurrentDirGuard {
-    fn drop(&mut self) {
-        env::set_current_dir(&self.original).unwrap();
-    }
-}
 
-fn command_completion_temp_dir(name: &str) -> (PathBuf, CurrentDirGuard) {
-    let lock = COMMAND_COMPLETION_CWD_LOCK.lock().unwrap();
-    let original = env::current_dir().unwrap();
-    let root = env::temp_dir().join(format!(
-        "red-command-completion-{name}-{}",
-        uuid::Uuid::new_v4()
-    ));
-    fs::create_dir_all(&root).unwrap();
-    env::set_current_dir(&root).unwrap();
-    (
-        root,
-        CurrentDirGuard {
-            original,
-            _lock: lock,
-        },
-    )
-}
+    harness
+        .execute_action(Action::Command("r !printf 'x\\ny\\n'".to_string()))
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("one\nx\ny\ntwo\n");
+    assert_eq!(harness.buffer_line(), 1);
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("one\ntwo\n");
 
-#[tokio::test]
-async fn command_history_recalls_previous_commands_with_up_and_down() {
-    let mut harness = EditorHarness::with_content("");
     harness
-        .execute_action(Action::Command("alpha-one".to_string()))
+        .execute_action(Action::Command(format!("0r {}", file.display())))
         .await
         .unwrap();
+    harness.assert_buffer_contents("from file\none\ntwo\n");
     harness
-        .execute_action(Action::Command("beta-two".to_string()))
+        .execute_action(Action::Command(format!("$r {}", file.display())))
         .await
         .unwrap();
-    harness.set_commandline(Mode::Command, "");
-
-    command_key(&mut harness, KeyCode::Up).await;
-    assert_eq!(harness.commandline_text(), "beta-two");
-
-    command_key(&mut harness, KeyCode::Up).await;
-    assert_eq!(harness.commandline_text(), "alpha-one");
+    harness.assert_buffer_contents("from file\none\ntwo\nfrom file\n");
 
-    command_key(&mut harness, KeyCode::Up).await;
-    assert_eq!(harness.commandline_text(), "alpha-one");
-
-    command_key(&mut harness, KeyCode::Down).await;
-    assert_eq!(harness.commandline_text(), "beta-two");
+    harness
+        .execute_action(Action::Command("2,3w !wc -l".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(harness.last_error().map(str::trim), Some("2"));
+    harness
+        .execute_action(Action::Command("w !tail -n 1".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(harness.last_error(), Some("from file"));
+    harness.assert_buffer_contents("from file\none\ntwo\nfrom file\n");
 
-    command_key(&mut harness, KeyCode::Down).await;
-    assert_eq!(harness.commandline_text(), "");
+    harness
+        .execute_action(Action::Command("!echo hello".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(harness.last_error(), Some("hello"));
 }
 
 #[tokio::test]
-async fn command_history_filters_by_typed_prefix() {
-    let mut harness = EditorHarness::with_content("");
-    for command in ["buffer-next", "write", "buffer-delete"] {
+async fn filter_operator_opens_the_command_line_with_a_range() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "a\nb\nc\nd\n".to_string()),
+        default_key_config(),
+    );
+    for (keys, expected) in [("!!", ".!"), ("3!!", ".,.+2!"), ("j!k", ".,.+1!")] {
         harness
-            .execute_action(Action::Command(command.to_string()))
+            .execute_action(Action::Command("1".to_string()))
+            .await
+            .unwrap();
+        type_normal_keys(&mut harness, keys).await;
+        assert_eq!(harness.mode(), Mode::Command, "{keys}");
+        assert_eq!(harness.commandline_text(), expected, "{keys}");
+        assert_eq!(harness.buffer_line(), 0, "{keys}");
+        harness
+            .execute_action(Action::EnterMode(Mode::Normal))
             .await
             .unwrap();
     }
-    harness.set_commandline(Mode::Command, "b");
-
-    command_key(&mut harness, KeyCode::Up).await;
-    assert_eq!(harness.commandline_text(), "buffer-delete");
-
-    command_key(&mut harness, KeyCode::Up).await;
-    assert_eq!(harness.commandline_text(), "buffer-next");
-
-    command_key(&mut harness, KeyCode::Down).await;
-    assert_eq!(harness.commandline_text(), "buffer-delete");
 
-    command_key(&mut harness, KeyCode::Down).await;
-    assert_eq!(harness.commandline_text(), "b");
+    type_normal_keys(&mut harness, "Vj!").await;
+    assert_eq!(harness.mode(), Mode::Command);
+    assert_eq!(harness.commandline_text(), "'<,'>!");
 }
 
 #[tokio::test]
-async fn command_history_editing_recalled_command_resets_prefix_session() {
-    let mut harness = EditorHarness::with_content("");
+async fn ranged_delete_yank_and_write_use_registers_and_files() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "one\ntwo\nthree\n".to_string()),
+        default_key_config(),
+    );
     harness
-        .execute_action(Action::Command("buffer-delete".to_string()))
+        .execute_action(Action::Command("2,3y a".to_string()))
         .await
         .unwrap();
-    harness.set_commandline(Mode::Command, "b");
-
-    command_key(&mut harness, KeyCode::Up).await;
-    assert_eq!(harness.commandline_text(), "buffer-delete");
-
-    command_key(&mut harness, KeyCode::Char('x')).await;
-    assert_eq!(harness.commandline_text(), "buffer-deletex");
-
-    command_key(&mut harness, KeyCode::Up).await;
-    assert_eq!(harness.commandline_text(), "buffer-deletex");
-}
-
-#[tokio::test]
-async fn whitespace_only_commands_are_not_saved_to_history() {
-    let mut harness = EditorHarness::with_content("");
     harness
-        .execute_action(Action::Command("   ".to_string()))
+        .execute_action(Action::Command("2d x 5".to_string()))
         .await
         .unwrap();
-    harness.set_commandline(Mode::Command, "");
-
-    command_key(&mut harness, KeyCode::Up).await;
-
-    assert_eq!(harness.commandline_text(), "");
-}
+    harness.assert_buffer_contents("one\n");
+    type_normal_keys(&mut harness, "\"ap").await;
+    harness.assert_buffer_contents("one\ntwo\nthree\n");
+    type_normal_keys(&mut harness, "G\"xp").await;
+    harness.assert_buffer_contents("one\ntwo\nthree\ntwo\nthree\n");
 
-#[tokio::test]
-async fn enew_opens_an_unnamed_buffer_without_saving_the_previous_buffer() {
-    let path = temp_file_path("enew-dirty-buffer");
-    fs::write(&path, "saved contents\n").unwrap();
-    let buffer = Buffer::new(Some(path.clone()), "saved contents\n".to_string());
-    let mut harness = EditorHarness::with_buffer(buffer);
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("part.txt");
+    let write = format!("2,3w {}", path.display());
     harness
-        .execute_action(Action::InsertCharAtCursorPos('x'))
+        .execute_action(Action::Command(write.clone()))
         .await
         .unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "two\nthree\n");
 
     harness
-        .execute_action(Action::Command("enew".to_string()))
+        .execute_action(Action::Command(format!("1w {}", path.display())))
         .await
         .unwrap();
-
-    assert_eq!(
-        harness.buffer_names(),
-        vec![path.clone(), "[No Name]".into()]
-    );
-    assert_eq!(harness.current_buffer_index(), 1);
-    assert!(harness.editor.test_current_buffer().is_unnamed());
-    assert!(harness.editor.test_current_buffer().is_blank());
-    assert!(!harness.is_dirty());
-    assert_eq!(fs::read_to_string(&path).unwrap(), "saved contents\n");
-
+    assert!(harness
+        .last_error()
+        .unwrap()
+        .ends_with("already exists (add ! to override)"));
     harness
-        .execute_action(Action::PreviousBuffer)
+        .execute_action(Action::Command(format!("1w! {}", path.display())))
         .await
         .unwrap();
-    assert_eq!(harness.buffer_contents(), "xsaved contents\n");
-    assert!(harness.is_dirty());
-    fs::remove_file(path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\n");
 }
 
 #[tokio::test]
-async fn enew_reuses_a_clean_unnamed_empty_buffer() {
-    let mut harness = EditorHarness::new();
-    let original_id = harness.editor.test_current_buffer().id();
-
-    for command in ["ene", "ene!", "enew", "enew!"] {
+async fn visual_colon_prefills_last_visual_range_and_can_be_cancelled() {
+    for mode in [Mode::Visual, Mode::VisualLine, Mode::VisualBlock] {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, "one\ntwo\nthree".to_string()),
+            default_key_config(),
+        );
         harness
-            .execute_action(Action::Command(command.to_string()))
+            .execute_action(Action::EnterMode(mode))
             .await
             .unwrap();
+        harness.execute_action(Action::MoveDown).await.unwrap();
 
-        assert_eq!(harness.buffer_names(), vec!["[No Name]"]);
-        assert_eq!(harness.editor.test_current_buffer().id(), original_id);
+        command_key(&mut harness, KeyCode::Char(':')).await;
+
+        harness.assert_mode(Mode::Command);
+        assert_eq!(harness.commandline_text(), "'<,'>");
+
+        command_key(&mut harness, KeyCode::Backspace).await;
+        assert_eq!(harness.commandline_text(), "'<,'");
+        command_key(&mut harness, KeyCode::Esc).await;
+        harness.assert_mode(Mode::Normal);
+        assert_eq!(harness.commandline_text(), "");
     }
 }
 
 #[tokio::test]
-async fn enew_abbreviation_requires_its_vim_compatible_minimum_prefix() {
-    let mut harness = EditorHarness::with_content("original contents\n");
+async fn visual_colon_substitute_is_line_scoped_for_every_visual_mode() {
+    for mode in [Mode::Visual, Mode::VisualLine, Mode::VisualBlock] {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, "foo foo\nFoo foo\nfoo foo".to_string()),
+            default_key_config(),
+        );
+        harness
+            .execute_action(Action::EnterMode(mode))
+            .await
+            .unwrap();
+        harness.execute_action(Action::MoveDown).await.unwrap();
 
-    harness
-        .execute_action(Action::Command("en".to_string()))
-        .await
-        .unwrap();
+        command_key(&mut harness, KeyCode::Char(':')).await;
+        type_normal_keys(&mut harness, "s/foo/bar/gi").await;
+        command_key(&mut harness, KeyCode::Enter).await;
 
-    assert_eq!(harness.buffer_names(), vec!["[No Name]"]);
-    assert_eq!(harness.buffer_contents(), "original contents\n");
-    assert_eq!(harness.last_error(), Some("unknown command \"en\""));
+        harness.assert_mode(Mode::Normal);
+        harness.assert_buffer_contents("bar bar\nbar bar\nfoo foo");
+        harness.execute_action(Action::Undo).await.unwrap();
+        harness.assert_buffer_contents("foo foo\nFoo foo\nfoo foo");
+    }
+}
 
+#[tokio::test]
+async fn confirmed_substitute_tracks_each_match_and_is_one_undo_transaction() {
+    let mut harness = EditorHarness::with_content("foo foo\nalpha beta\nfoo gamma");
     harness
-        .execute_action(Action::Command("ene".to_string()))
+        .execute_action(Action::Command("%s/foo/bar/gc".to_string()))
         .await
         .unwrap();
+    harness.assert_buffer_contents("foo foo\nalpha beta\nfoo gamma");
+    harness.assert_cursor_at(0, 0);
+    let first_match = harness.render_cursor_position().unwrap();
 
-    assert_eq!(harness.buffer_names(), vec!["[No Name]", "[No Name]"]);
-    assert!(harness.editor.test_current_buffer().is_blank());
+    type_normal_keys(&mut harness, "y").await;
+    harness.assert_cursor_at(4, 0);
+    assert_eq!(
+        harness.render_cursor_position(),
+        Some((first_match.0 + 4, first_match.1))
+    );
+
+    type_normal_keys(&mut harness, "n").await;
+    harness.assert_cursor_at(0, 2);
+    assert_eq!(
+        harness.render_cursor_position(),
+        Some((first_match.0, first_match.1 + 2))
+    );
+
+    type_normal_keys(&mut harness, "a").await;
+    harness.assert_buffer_contents("bar foo\nalpha beta\nbar gamma");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("foo foo\nalpha beta\nfoo gamma");
 }
 
 #[tokio::test]
-async fn enew_changes_only_the_active_split() {
-    let mut harness = EditorHarness::with_content("original contents\n");
-    harness.execute_action(Action::SplitVertical).await.unwrap();
-
+async fn dot_replays_the_answers_of_a_confirmed_substitute_without_asking() {
+    let buffer = Buffer::new(None, "a a a\nb\na a a a\n".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
     harness
-        .execute_action(Action::Command("enew".to_string()))
+        .execute_action(Action::Command("s/a/x/gc".to_string()))
         .await
         .unwrap();
+    type_normal_keys(&mut harness, "yny").await;
+    harness.assert_buffer_contents("x a x\nb\na a a a\n");
 
-    assert_eq!(harness.window_count(), 2);
-    assert_eq!(harness.current_buffer_index(), 1);
-    assert!(harness.editor.test_current_buffer().is_blank());
+    type_normal_keys(&mut harness, "jj.").await;
+    harness.assert_buffer_contents("x a x\nb\nx a x a\n");
+    assert!(harness.is_normal());
+    assert_eq!(harness.last_error(), None);
 
-    harness.execute_action(Action::NextWindow).await.unwrap();
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("x a x\nb\na a a a\n");
 
-    assert_eq!(harness.current_buffer_index(), 0);
-    assert_eq!(harness.buffer_contents(), "original contents\n");
+    harness
+        .execute_action(Action::Command("s/a/y/gc".to_string()))
+        .await
+        .unwrap();
+    type_normal_keys(&mut harness, "na").await;
+    harness.assert_buffer_contents("x a x\nb\na y y y\n");
+    harness.execute_action(Action::Undo).await.unwrap();
+    type_normal_keys(&mut harness, "gg.").await;
+    harness.assert_buffer_contents("x a x\nb\na a a a\n");
+    type_normal_keys(&mut harness, "G.").await;
+    harness.assert_buffer_contents("x a x\nb\na y y y\n");
 }
 
 #[tokio::test]
-async fn enew_rejects_file_arguments_without_changing_buffers() {
-    let mut harness = EditorHarness::with_content("original contents\n");
+async fn ampersand_commands_repeat_the_last_substitute_and_survive_recovery() {
+    let contents = "aa\naa\naa\naa";
+    let buffer = Buffer::new(None, contents.to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "&").await;
+    assert_eq!(
+        harness.last_error(),
+        Some("no previous substitute to repeat")
+    );
 
     harness
-        .execute_action(Action::Command("enew unexpected.txt".to_string()))
+        .execute_action(Action::Command("s/a/b/g".to_string()))
+        .await
+        .unwrap();
+    harness
+        .execute_action(Action::Command("3&&".to_string()))
         .await
         .unwrap();
+    harness.assert_buffer_contents("bb\naa\nbb\naa");
+    type_normal_keys(&mut harness, "j&").await;
+    harness.assert_buffer_contents("bb\nba\nbb\naa");
+    // `&` left no flags for `g&` to keep.
+    type_normal_keys(&mut harness, "g&").await;
+    harness.assert_buffer_contents("bb\nbb\nbb\nba");
+    harness
+        .execute_action(Action::Command("&x".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        harness.last_error(),
+        Some("unsupported substitute flags: x")
+    );
 
-    assert_eq!(harness.buffer_names(), vec!["[No Name]"]);
-    assert_eq!(harness.buffer_contents(), "original contents\n");
-    assert_eq!(harness.last_error(), Some("usage: enew"));
+    let snapshot = harness.editor.test_session_snapshot();
+    let buffer = Buffer::new(None, contents.to_string());
+    let mut restored = EditorHarness::with_config(buffer, default_key_config());
+    restored.editor.restore_session_snapshot(&snapshot).unwrap();
+    type_normal_keys(&mut restored, "g&").await;
+    restored.assert_buffer_contents("ba\nba\nba\nba");
 }
 
 #[tokio::test]
-async fn new_and_vnew_create_fresh_unnamed_buffers_in_new_splits() {
-    for command in ["new", "vnew"] {
-        let mut harness = EditorHarness::new();
-        let original_id = harness.editor.test_current_buffer().id();
-
-        harness
-            .execute_action(Action::Command(command.to_string()))
-            .await
-            .unwrap();
+async fn confirmed_substitute_scrolls_to_an_offscreen_match() {
+    let content = (0..10)
+        .map(|line| {
+            if matches!(line, 0 | 9) {
+                "foo".to_string()
+            } else {
+                format!("line {line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let buffer = Buffer::new(None, content);
+    let mut harness = EditorHarness::with_config_and_size(
+        buffer,
+        Config::default(),
+        /*width*/ 40,
+        /*height*/ 5,
+    );
+    harness
+        .execute_action(Action::Command("%s/foo/bar/gc".to_string()))
+        .await
+        .unwrap();
+    let first_match = harness.render_cursor_position().unwrap();
 
-        assert_eq!(harness.window_count(), 2, "{command}");
-        assert_eq!(harness.buffer_names(), vec!["[No Name]", "[No Name]"]);
-        assert_eq!(harness.current_buffer_index(), 1);
-        assert_ne!(harness.editor.test_current_buffer().id(), original_id);
-        assert!(harness.editor.test_current_buffer().is_blank());
+    type_normal_keys(&mut harness, "y").await;
 
-        harness.execute_action(Action::NextWindow).await.unwrap();
-        assert_eq!(harness.editor.test_current_buffer().id(), original_id);
-    }
+    assert_eq!(harness.buffer_line(), 9);
+    assert_eq!(harness.viewport_top(), 9);
+    assert_eq!(harness.render_cursor_position(), Some(first_match));
 }
 
 #[tokio::test]
-async fn new_and_vnew_accept_paths_without_writing_missing_files() {
-    let directory = tempfile::tempdir().unwrap();
+async fn substitute_uses_rust_regex_captures_and_escaped_delimiters() {
+    let mut harness = EditorHarness::with_content("path/a-12 path/b-34");
+    harness
+        .execute_action(Action::Command(
+            r"s/path\/([a-z])-(\d+)/$1:$2/g".to_string(),
+        ))
+        .await
+        .unwrap();
 
-    for (command, name) in [("new", "horizontal.rs"), ("vnew", "vertical.rs")] {
-        let path = directory.path().join(name);
-        let mut harness = EditorHarness::with_content("original contents\n");
+    harness.assert_buffer_contents("a:12 b:34");
+}
+
+#[tokio::test]
+async fn substitute_uses_vim_patterns_and_replacements_when_the_dialect_is_vim() {
+    let mut config = default_key_config();
+    config.search.dialect = SearchDialect::Vim;
+    let buffer = Buffer::new(None, "foo(1) bar(22)\nsize: 10px 20px\n".to_string());
+    let mut harness = EditorHarness::with_config(buffer, config);
 
+    for (command, expected) in [
+        (
+            r"s/\v(\w+)\((\d+)\)/\2:\u\1/g",
+            "1:Foo 22:Bar\nsize: 10px 20px\n",
+        ),
+        (r"2s/\d\+\zepx/[&]/g", "1:Foo 22:Bar\nsize: [10]px [20]px\n"),
+        (r"1s/Bar/~!/", "1:Foo 22:[Bar]!\nsize: [10]px [20]px\n"),
+        (
+            r"1s/\<\(\a\)\(\a*\)\>/\U\2\E\1/",
+            "1:OOF 22:[Bar]!\nsize: [10]px [20]px\n",
+        ),
+    ] {
         harness
-            .execute_action(Action::Command(format!("{command} {}", path.display())))
+            .execute_action(Action::Command(command.to_string()))
             .await
             .unwrap();
-
-        assert_eq!(harness.window_count(), 2, "{command}");
-        assert_eq!(
-            harness.editor.test_current_buffer().file.as_deref(),
-            path.to_str()
-        );
-        assert!(!path.exists(), "{command} should not create a disk file");
-
-        harness.execute_action(Action::NextWindow).await.unwrap();
-        assert_eq!(harness.buffer_contents(), "original contents\n");
+        assert_eq!(harness.last_error(), None, "{command}");
+        harness.assert_buffer_contents(expected);
     }
+
+    harness
+        .execute_action(Action::Command(r"s/a\&b/x/".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        harness.last_error(),
+        Some(r"invalid substitute pattern: concat branch \& is not supported at column 2")
+    );
 }
 
 #[tokio::test]
-async fn buffer_commands_select_names_stable_numbers_and_the_alternate() {
-    let directory = tempfile::tempdir().unwrap();
-    let first = directory.path().join("one.rs");
-    let second = directory.path().join("two.rs");
-    let third = directory.path().join("three.rs");
-    let buffers = [(&first, "one\n"), (&second, "two\n"), (&third, "three\n")]
-        .into_iter()
-        .map(|(path, contents)| {
-            Buffer::new(
-                Some(path.to_string_lossy().into_owned()),
-                contents.to_string(),
-            )
-        })
-        .collect();
-    let mut editor = Editor::test_with_size(
-        Box::new(MockLsp),
-        /*width*/ 80,
-        /*height*/ 24,
-        Config::default(),
-        Theme::default(),
-        buffers,
-    )
-    .unwrap();
-    editor.test_disable_terminal_output();
-    let mut harness = EditorHarness { editor };
+async fn substitute_expressions_evaluate_husk_for_each_match_in_one_transaction() {
+    let original = "item 4\nitem 10\nname: ada\n";
+    let buffer = Buffer::new(None, original.to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
     harness
-        .execute_action(Action::Command("b two.rs".to_string()))
+        .execute_action(Action::Command(
+            r"1,2s/item (\d+)/\=m[1].parse::<i32>().unwrap_or(0) * 10 + line/".to_string(),
+        ))
         .await
         .unwrap();
-    assert_eq!(harness.current_buffer_index(), 1);
-    let second_id = harness.editor.test_current_buffer().id().as_u64();
-
+    assert_eq!(harness.last_error(), None);
+    harness.assert_buffer_contents("41\n102\nname: ada\n");
     harness
-        .execute_action(Action::Command("buffer #".to_string()))
+        .execute_action(Action::Command(
+            r"%s/\w+$/\=m[0].to_uppercase()/".to_string(),
+        ))
         .await
         .unwrap();
-    assert_eq!(harness.current_buffer_index(), 0);
+    harness.assert_buffer_contents("41\n102\nname: ADA\n");
+
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents(original);
 
     harness
-        .execute_action(Action::Command(format!("buffer {second_id}")))
+        .execute_action(Action::Command(r"s/item/\=upper(m[0])/".to_string()))
         .await
         .unwrap();
-    assert_eq!(harness.current_buffer_index(), 1);
-
+    assert_eq!(
+        harness.last_error(),
+        Some("invalid substitute expression: unknown identifier `upper`")
+    );
     harness
-        .execute_action(Action::Command("b#".to_string()))
+        .execute_action(Action::Command(r"%s/\d+/\=loop {}/".to_string()))
         .await
         .unwrap();
-    assert_eq!(harness.current_buffer_index(), 0);
-    assert_eq!(harness.buffer_names().len(), 3);
-
-    harness
-        .execute_action(Action::Command("b thre".to_string()))
-        .await
-        .unwrap();
-    assert_eq!(harness.current_buffer_index(), 2);
+    assert!(harness.last_error().is_some_and(|error| error
+        .starts_with("substitute expression failed on line 1: ")
+        && error.contains("instruction budget")));
+    harness.assert_buffer_contents(original);
 }
 
 #[tokio::test]
-async fn buffer_selection_rejects_ambiguous_names_and_supports_stable_ids() {
-    let buffers = vec![
-        Buffer::new(/*file*/ None, "first\n".to_string()),
-        Buffer::new(/*file*/ None, "second\n".to_string()),
-    ];
-    let second_id = buffers[1].id().as_u64();
-    let mut editor = Editor::test_with_size(
-        Box::new(MockLsp),
-        /*width*/ 80,
-        /*height*/ 24,
-        Config::default(),
-        Theme::default(),
-        buffers,
-    )
-    .unwrap();
-    editor.test_disable_terminal_output();
-    let mut harness = EditorHarness { editor };
+async fn substitute_does_not_match_the_carriage_return_in_crlf_buffers() {
+    let buffer = Buffer::new(None, "abc\r\ndef\r\n".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
     harness
-        .execute_action(Action::Command("b [No Name]".to_string()))
+        .execute_action(Action::Command("%s/.$/X/".to_string()))
         .await
         .unwrap();
-    assert_eq!(harness.current_buffer_index(), 0);
-    assert!(harness
-        .last_error()
-        .is_some_and(|message| message.contains("Multiple buffers match")));
 
-    harness
-        .execute_action(Action::OpenBufferById(second_id))
-        .await
-        .unwrap();
-    assert_eq!(harness.current_buffer_index(), 1);
-    assert_eq!(harness.buffer_contents(), "second\n");
+    harness.assert_buffer_contents("abX\r\ndeX\r\n");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("abc\r\ndef\r\n");
 }
 
 #[tokio::test]
-async fn buffer_listing_aliases_show_stable_numbers_without_opening_splits() {
-    for command in ["ls", "buffers", "files", "b", "buffer"] {
-        let mut harness = EditorHarness::with_content("contents\n");
-        harness
-            .execute_action(Action::InsertCharAtCursorPos('x'))
-            .await
-            .unwrap();
-        let id = harness.editor.test_current_buffer().id().as_u64();
-
-        harness
-            .execute_action(Action::Command(command.to_string()))
-            .await
-            .unwrap();
+async fn substitute_preserves_unicode_marks_across_undo_and_redo() {
+    let source = "😀 value one\r\n😀 value two\r\n😀 value three\r\n";
+    let replacement = "longer_value";
+    let mut harness =
+        EditorHarness::with_config(Buffer::new(None, source.to_string()), default_key_config());
 
-        let listing = harness.last_error().unwrap();
-        assert!(listing.contains(&id.to_string()), "{command}: {listing}");
-        assert!(listing.contains("[No Name]"), "{command}: {listing}");
-        assert!(listing.contains("[+]"), "{command}: {listing}");
-        assert_eq!(harness.window_count(), 1, "{command}");
+    harness.execute_action(Action::MoveDown).await.unwrap();
+    for _ in 0..8 {
+        harness.execute_action(Action::MoveRight).await.unwrap();
     }
-}
-
-#[tokio::test]
-async fn standard_buffer_navigation_aliases_switch_existing_buffers() {
-    let mut harness = EditorHarness::with_content("first\n");
-    harness
-        .execute_action(Action::Command("enew".to_string()))
-        .await
-        .unwrap();
-
-    harness
-        .execute_action(Action::Command("bprevious".to_string()))
-        .await
-        .unwrap();
-    assert_eq!(harness.current_buffer_index(), 0);
-
+    harness.execute_action(Action::SetMark('a')).await.unwrap();
     harness
-        .execute_action(Action::Command("bnext".to_string()))
+        .execute_action(Action::Command(format!("%s/value/{replacement}/g")))
         .await
         .unwrap();
-    assert_eq!(harness.current_buffer_index(), 1);
-}
 
-#[tokio::test]
-async fn saveas_writes_the_current_buffer_to_its_new_file() {
-    let directory = tempfile::tempdir().unwrap();
-    let path = directory.path().join("saved notes.txt");
-    let mut harness = EditorHarness::with_content("draft contents\n");
+    harness.assert_buffer_contents(&source.replace("value", replacement));
+    type_normal_keys(&mut harness, "`a").await;
+    harness.assert_cursor_at(8 + replacement.len() - "value".len(), 1);
 
-    harness
-        .execute_action(Action::Command(format!("saveas {}", path.display())))
-        .await
-        .unwrap();
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents(source);
+    type_normal_keys(&mut harness, "`a").await;
+    harness.assert_cursor_at(8, 1);
 
-    assert_eq!(fs::read_to_string(&path).unwrap(), "draft contents\n");
-    assert_eq!(
-        harness.editor.test_current_buffer().file.as_deref(),
-        path.to_str()
-    );
-    assert!(!harness.is_dirty());
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents(&source.replace("value", replacement));
+    type_normal_keys(&mut harness, "`a").await;
+    harness.assert_cursor_at(8 + replacement.len() - "value".len(), 1);
 }
 
 #[tokio::test]
-async fn file_assigns_a_buffer_name_without_writing_or_clearing_changes() {
-    let directory = tempfile::tempdir().unwrap();
-    let path = directory.path().join("unsaved notes.txt");
-    let mut harness = EditorHarness::with_content("draft contents\n");
-    harness
-        .execute_action(Action::InsertCharAtCursorPos('x'))
+async fn format_on_save_restores_save_as_identity_and_insert_transaction_after_sync_failure() {
+    let temp = tempfile::tempdir().unwrap();
+    let source = temp.path().join("source.rs");
+    let target = temp.path().join("target.py");
+    fs::write(&source, "disk source\n").unwrap();
+    let source_file = source.to_string_lossy().into_owned();
+    let target_file = target.to_string_lossy().into_owned();
+    let lsp = RecordingLsp::failing_next_did_open();
+    let events = lsp.events();
+    let config = Config::default();
+    let mut editor = Editor::test_with_size(
+        Box::new(lsp),
+        /*width*/ 80,
+        /*height*/ 24,
+        config,
+        Theme::default(),
+        vec![Buffer::new(
+            Some(source_file.clone()),
+            "unsaved source\n".to_string(),
+        )],
+    )
+    .unwrap();
+    editor.test_disable_terminal_output();
+    editor
+        .test_execute_production_action(Action::EnterMode(Mode::Insert))
         .await
         .unwrap();
+    assert!(editor
+        .test_current_buffer()
+        .undo_history
+        .is_transaction_active());
 
-    harness
-        .execute_action(Action::Command(format!("file {}", path.display())))
+    let error = editor
+        .test_execute_production_action(Action::SaveAs(target_file.clone()))
         .await
-        .unwrap();
+        .unwrap_err();
 
+    assert!(error.to_string().contains("injected didOpen failure"));
+    assert!(editor.test_is_insert());
+    assert!(editor
+        .test_current_buffer()
+        .undo_history
+        .is_transaction_active());
     assert_eq!(
-        harness.editor.test_current_buffer().file.as_deref(),
-        path.to_str()
+        editor.test_current_buffer().file.as_deref(),
+        Some(source_file.as_str())
     );
-    assert_eq!(harness.buffer_contents(), "xdraft contents\n");
-    assert!(harness.is_dirty());
-    assert!(!path.exists());
-
-    harness
-        .execute_action(Action::Command("file".to_string()))
-        .await
-        .unwrap();
-    assert_eq!(harness.last_error(), path.to_str());
+    assert_eq!(editor.test_current_buffer().contents(), "unsaved source\n");
+    assert_eq!(fs::read_to_string(&source).unwrap(), "disk source\n");
+    assert!(!target.exists());
+    let events = events.lock().unwrap();
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, LspEvent::DidOpen(file) if file == &target_file)));
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, LspEvent::DidOpen(file) if file == &source_file)));
 }
 
+#[cfg(unix)]
 #[tokio::test]
-async fn file_rejects_paths_already_open_in_another_buffer() {
-    let directory = tempfile::tempdir().unwrap();
-    let path = directory.path().join("open.rs");
-    let buffers = vec![
-        Buffer::new(/*file*/ None, "draft\n".to_string()),
-        Buffer::new(
-            Some(path.to_string_lossy().into_owned()),
-            "existing\n".to_string(),
-        ),
-    ];
-    let mut editor = Editor::test_with_size(
+async fn crash_recovery_keeps_transcript_in_memory_when_preferences_are_unsafe() {
+    let temp = tempfile::tempdir().unwrap();
+    let outside = temp.path().join("outside-preferences.json");
+    let preferences_path = temp.path().join("preferences.json");
+    let recovered_path = temp.path().join("recovered.txt");
+    fs::write(&outside, "outside secret").unwrap();
+    fs::write(&recovered_path, "disk base\n").unwrap();
+    std::os::unix::fs::symlink(&outside, &preferences_path).unwrap();
+    let buffer = Buffer::new(
+        Some(recovered_path.to_string_lossy().into_owned()),
+        "recovered text\n".to_string(),
+    );
+    let mut source = EditorHarness::with_config(buffer, default_key_config());
+    let mut snapshot = source.editor.test_session_snapshot();
+    snapshot.agent_transcript = Some("You: recover me\nAgent: retained\n".to_string());
+    let mut conversation = red::agent_conversation::AgentConversationSnapshot::new(
+        "thread-recoverable",
+        temp.path().to_string_lossy(),
+    );
+    conversation.append_user("turn-1", "recover me");
+    conversation.append_agent_delta("turn-1", "retained");
+    snapshot.agent_conversation = Some(conversation.clone());
+    snapshot.agent_session_resumable = true;
+    let restored_buffers = Editor::buffers_from_session_snapshot(&snapshot);
+    let preferences = PreferencesStore::load(&preferences_path);
+    let mut editor = Editor::test_with_size_and_preferences(
         Box::new(MockLsp),
         /*width*/ 80,
         /*height*/ 24,
-        Config::default(),
+        default_key_config(),
         Theme::default(),
-        buffers,
+        restored_buffers,
+        preferences,
     )
     .unwrap();
     editor.test_disable_terminal_output();
-    let mut harness = EditorHarness { editor };
 
-    harness
-        .execute_action(Action::Command(format!("file {}", path.display())))
-        .await
-        .unwrap();
+    fs::write(&recovered_path, "external change\n").unwrap();
+    let divergences = editor.restore_session_snapshot(&snapshot).unwrap();
 
-    assert!(harness.editor.test_current_buffer().is_unnamed());
-    assert!(harness
-        .last_error()
-        .is_some_and(|message| message.contains("already exists")));
-    assert!(!path.exists());
+    let recovered = editor.test_session_snapshot();
+    assert_eq!(divergences.len(), 1);
+    assert_eq!(
+        recovered.agent_transcript.as_deref(),
+        Some("You: recover me\nAgent: retained\n")
+    );
+    assert_eq!(recovered.agent_conversation, Some(conversation));
+    assert_eq!(recovered.buffers[0].contents, "recovered text\n");
+    assert_eq!(fs::read_to_string(outside).unwrap(), "outside secret");
+    let restored = EditorHarness { editor };
+    assert!(restored.last_error().is_some_and(|message| {
+        message.contains("changed on disk") && message.contains("could not be persisted")
+    }));
 }
 
 #[tokio::test]
-async fn saveas_requires_a_file_argument() {
-    let mut harness = EditorHarness::with_content("draft contents\n");
+async fn crash_session_finalizes_an_active_insert_transaction_in_the_snapshot() {
+    let buffer = Buffer::new(None, "base\n".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "iuser ").await;
+    assert!(harness.is_insert());
 
-    harness
-        .execute_action(Action::Command("saveas".to_string()))
-        .await
-        .unwrap();
+    let snapshot = harness.editor.test_session_snapshot();
+    let mut restored_buffers = Editor::buffers_from_session_snapshot(&snapshot);
+    let mut restored = EditorHarness::with_config(restored_buffers.remove(0), default_key_config());
 
-    assert!(harness.editor.test_current_buffer().is_unnamed());
-    assert_eq!(harness.last_error(), Some("usage: saveas <file>"));
+    restored.assert_buffer_contents("user base\n");
+    restored.execute_action(Action::Undo).await.unwrap();
+    restored.assert_buffer_contents("base\n");
+    assert!(harness.is_insert());
 }
 
 #[tokio::test]
-async fn unknown_command_does_not_expand_into_reload_write_or_split_actions() {
-    let path = temp_file_path("unknown-buffer-command");
-    fs::write(&path, "disk contents\n").unwrap();
-    let buffer = Buffer::new(Some(path.clone()), "disk contents\n".to_string());
-    let mut harness = EditorHarness::with_buffer(buffer);
-    harness
-        .execute_action(Action::InsertCharAtCursorPos('x'))
-        .await
-        .unwrap();
+async fn unchanged_recovery_snapshots_are_skipped_and_failures_back_off() {
+    let directory = tempfile::tempdir().unwrap();
+    let store = red::session::SessionStore::for_owner(directory.path(), "editor-one").unwrap();
+    let buffer = Buffer::new(None, "base\n".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    harness.editor.set_session_store(store.clone());
 
-    for command in ["eneww", "nwe", "lss"] {
-        harness
-            .execute_action(Action::Command(command.to_string()))
-            .await
-            .unwrap();
+    harness
+        .editor
+        .test_persist_session_snapshot(/*force*/ true, /*due*/ true);
+    let generation = store.load().unwrap().generation;
+    harness
+        .editor
+        .test_persist_session_snapshot(/*force*/ false, /*due*/ true);
+    assert_eq!(store.load().unwrap().generation, generation);
 
-        assert_eq!(harness.buffer_contents(), "xdisk contents\n");
-        assert_eq!(fs::read_to_string(&path).unwrap(), "disk contents\n");
-        assert_eq!(harness.window_count(), 1);
-        assert!(harness
-            .last_error()
-            .is_some_and(|message| message.contains("unknown command")));
-    }
+    let blocked_root = directory.path().join("not-a-directory");
+    fs::write(&blocked_root, "blocked").unwrap();
+    let blocked = red::session::SessionStore::for_owner(&blocked_root, "editor-two").unwrap();
+    harness.editor.set_session_store(blocked);
+    harness
+        .editor
+        .test_persist_session_snapshot(/*force*/ false, /*due*/ true);
+    std::thread::sleep(std::time::Duration::from_millis(25));
+    harness
+        .editor
+        .test_persist_session_snapshot(/*force*/ false, /*due*/ false);
+    assert!(harness.editor.test_session_snapshot_is_backing_off());
 
-    fs::remove_file(path).unwrap();
-}
+    let warning = harness.commandline_row();
+    assert!(warning.contains("Crash recovery is not being saved"));
+    let warning_id = harness
+        .editor
+        .notifications()
+        .primary(Instant::now())
+        .unwrap()
+        .id;
 
-#[tokio::test]
-async fn edit_without_file_argument_reloads_current_file() {
-    let path = temp_file_path("edit-reload");
-    fs::write(&path, "one\ntwo\nthree\n").unwrap();
-    let buffer = Buffer::new(Some(path.clone()), "one\ntwo\nthree\n".to_string());
-    let mut harness = EditorHarness::with_buffer(buffer);
-    harness.execute_action(Action::MoveDown).await.unwrap();
-    fs::write(&path, "one\nchanged\nthree\n").unwrap();
+    let error_id = harness
+        .editor
+        .publish_notification(Notice::new(
+            NotificationSource::Editor,
+            Severity::Error,
+            "a newer LSP error",
+        ))
+        .unwrap();
+    let status = harness.commandline_row();
+    assert!(status.contains("a newer LSP error"));
+    assert!(status.contains("2 need attention"));
+    assert!(harness
+        .editor
+        .notifications()
+        .get(warning_id)
+        .unwrap()
+        .is_active(Instant::now()));
+    harness.editor.test_set_size(/*width*/ 8, /*height*/ 4);
+    assert_eq!(harness.commandline_row(), "× a  [2]");
+    harness.editor.test_set_size(/*width*/ 120, /*height*/ 24);
 
+    harness.execute_action(Action::OpenMessages).await.unwrap();
     harness
-        .execute_action(Action::Command("e".to_string()))
+        .execute_action(Action::MessageHistory(MessageAction::Acknowledge))
         .await
         .unwrap();
-
-    assert_eq!(harness.buffer_contents(), "one\nchanged\nthree\n");
-    assert_eq!(harness.cursor_position(), (0, 1));
-    assert!(!harness.is_dirty());
-    fs::remove_file(path).unwrap();
-}
-
-#[tokio::test]
-async fn edit_without_force_refuses_to_reload_dirty_current_file() {
-    let path = temp_file_path("edit-reload-dirty");
-    fs::write(&path, "one\ntwo\n").unwrap();
-    let buffer = Buffer::new(Some(path.clone()), "one\ntwo\n".to_string());
-    let mut harness = EditorHarness::with_buffer(buffer);
     harness
-        .execute_action(Action::InsertCharAtCursorPos('x'))
+        .execute_action(Action::MessageHistory(MessageAction::Close))
         .await
         .unwrap();
-    fs::write(&path, "one\nchanged\n").unwrap();
+    assert!(!harness
+        .editor
+        .notifications()
+        .get(error_id)
+        .unwrap()
+        .is_active(Instant::now()));
 
     harness
-        .execute_action(Action::Command("e".to_string()))
+        .execute_action(Action::Command("1".to_string()))
         .await
         .unwrap();
+    harness.editor.test_set_size(/*width*/ 120, /*height*/ 24);
+    assert!(harness
+        .commandline_row()
+        .contains("Crash recovery is not being saved"));
 
-    assert_eq!(harness.buffer_contents(), "xone\ntwo\n");
-    assert_eq!(
-        harness.last_error(),
-        Some("E37: No write since last change (add ! to override)")
-    );
-    assert!(harness.is_dirty());
-    fs::remove_file(path).unwrap();
+    harness.editor.set_session_store(store);
+    harness
+        .editor
+        .test_persist_session_snapshot(/*force*/ true, /*due*/ true);
+    assert!(!harness
+        .commandline_row()
+        .contains("Crash recovery is not being saved"));
 }
 
 #[tokio::test]
-async fn edit_with_force_reloads_dirty_current_file() {
-    let path = temp_file_path("edit-reload-force");
-    fs::write(&path, "one\ntwo\n").unwrap();
-    let buffer = Buffer::new(Some(path.clone()), "one\ntwo\n".to_string());
-    let mut harness = EditorHarness::with_buffer(buffer);
-    harness.execute_action(Action::MoveDown).await.unwrap();
-    harness
-        .execute_action(Action::InsertCharAtCursorPos('x'))
-        .await
-        .unwrap();
-    fs::write(&path, "one\nchanged\n").unwrap();
+async fn periodic_recovery_snapshot_materializes_shared_buffer_contents() {
+    let directory = tempfile::tempdir().unwrap();
+    let store = red::session::SessionStore::for_owner(directory.path(), "editor-one").unwrap();
+    let expected = format!("prefix 👋 {}\n", "text ".repeat(64 * 1024));
+    let buffer = Buffer::new(None, expected.clone());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    harness.editor.set_session_store(store.clone());
 
     harness
-        .execute_action(Action::Command("e!".to_string()))
-        .await
-        .unwrap();
-
-    assert_eq!(harness.buffer_contents(), "one\nchanged\n");
-    assert_eq!(harness.cursor_position(), (1, 1));
-    assert!(!harness.is_dirty());
-
-    harness.execute_action(Action::Undo).await.unwrap();
-    assert_eq!(harness.buffer_contents(), "one\nxtwo\n");
-    assert_eq!(harness.cursor_position(), (1, 1));
-    assert!(harness.is_dirty());
+        .editor
+        .test_persist_session_snapshot(/*force*/ false, /*due*/ true);
+    harness.editor.test_finish_session_snapshot();
 
-    harness.execute_action(Action::Redo).await.unwrap();
-    assert_eq!(harness.buffer_contents(), "one\nchanged\n");
-    assert_eq!(harness.cursor_position(), (1, 1));
-    assert!(!harness.is_dirty());
-    fs::remove_file(path).unwrap();
+    assert_eq!(store.load().unwrap().buffers[0].contents, expected);
 }
 
-#[test]
-fn command_tab_completes_edit_file_argument() {
-    let (root, _guard) = command_completion_temp_dir("edit");
-    fs::create_dir(root.join("src")).unwrap();
-    fs::write(root.join("sample.txt"), "").unwrap();
-    let mut harness = EditorHarness::with_content("");
-    harness.set_commandline(Mode::Command, "e sr");
+fn tree_rows() -> Vec<PanelRow> {
+    ["root", "src", "main.rs"]
+        .into_iter()
+        .map(|id| PanelRow {
+            id: id.to_string(),
+            path: Some(id.to_string()),
+            expanded: Some(false),
+            kind: if id.ends_with(".rs") {
+                PanelRowKind::File
+            } else {
+                PanelRowKind::Directory
+            },
+            segments: vec![PanelSegment {
+                text: id.to_string(),
+                style: None,
+                semantic: None,
+            }],
+            right_segments: vec![],
+        })
+        .collect()
+}
 
-    harness.editor.test_complete_command_path_next();
+fn numbered_tree_rows(count: usize) -> Vec<PanelRow> {
+    (0..count)
+        .map(|index| {
+            let id = format!("row-{index:02}.rs");
+            PanelRow {
+                id: id.clone(),
+                path: Some(id.clone()),
+                expanded: Some(false),
+                kind: PanelRowKind::File,
+                segments: vec![PanelSegment {
+                    text: id,
+                    style: None,
+                    semantic: None,
+                }],
+                right_segments: vec![],
+            }
+        })
+        .collect()
+}
 
-    assert_eq!(harness.commandline_text(), "e src/");
-    drop(_guard);
-    fs::remove_dir_all(root).unwrap();
+fn add_tree_panel(harness: &mut EditorHarness) {
+    harness.editor.test_create_panel(
+        "tree",
+        PanelConfig {
+            side: PanelSide::Left,
+            width: 20,
+            title: None,
+            composer: None,
+            surface: None,
+            border: None,
+            header_actions: Vec::new(),
+        },
+    );
+    harness.editor.test_update_panel("tree", tree_rows());
 }
 
-#[tokio::test]
-async fn command_tab_opens_completed_paths_with_spaces() {
-    let directory = tempfile::tempdir().unwrap();
-    let path = directory.path().join("name  with spaces.txt!");
-    let command_directory = directory.path().to_string_lossy().replace('\\', "/");
-    let command_path = format!("{command_directory}/name  with spaces.txt!");
-    fs::write(&path, "completed file contents\n").unwrap();
+async fn command_key(harness: &mut EditorHarness, code: KeyCode) {
+    harness
+        .execute_event(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+        .await
+        .unwrap();
+}
 
-    for command in [
-        "e", "ed", "edit", "e!", "new", "vne", "vnew", "spl", "split", "sp", "vsp", "vsplit", "vs",
-    ] {
-        let mut harness = EditorHarness::with_content("");
-        harness.set_commandline(
-            Mode::Command,
-            &format!("{command} {command_directory}/name"),
-        );
+#[tokio::test]
+async fn command_backspace_exits_an_empty_prompt() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "unchanged".to_string()),
+        default_key_config(),
+    );
+    command_key(&mut harness, KeyCode::Char(':')).await;
+    harness.assert_mode(Mode::Command);
+    assert_eq!(harness.commandline_row().trim_end(), ":");
 
-        command_key(&mut harness, KeyCode::Tab).await;
-        assert_eq!(
-            harness.commandline_text(),
-            format!("{command} {command_path}")
-        );
-        command_key(&mut harness, KeyCode::Enter).await;
+    command_key(&mut harness, KeyCode::Backspace).await;
 
-        assert_eq!(
-            harness.buffer_contents(),
-            "completed file contents\n",
-            "{command} did not open the completed path"
-        );
-    }
+    harness.assert_mode(Mode::Normal);
+    assert_eq!(harness.commandline_text(), "");
+    assert_eq!(harness.commandline_row().trim_end(), "");
+    harness.assert_buffer_contents("unchanged");
 }
 
 #[tokio::test]
-async fn command_tab_writes_completed_paths_with_spaces() {
-    let directory = tempfile::tempdir().unwrap();
-    let path = directory.path().join("name  with spaces.txt!");
-    let command_directory = directory.path().to_string_lossy().replace('\\', "/");
-    let command_path = format!("{command_directory}/name  with spaces.txt!");
-
-    for command in ["w", "wr", "wri", "write", "w!", "write!"] {
-        fs::write(&path, "old contents\n").unwrap();
-        let mut harness = EditorHarness::with_content("saved contents\n");
-        harness.set_commandline(
-            Mode::Command,
-            &format!("{command} {command_directory}/name"),
+async fn command_backspace_deletes_text_before_exiting() {
+    for character in ['x', 'é', ' '] {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, "unchanged".to_string()),
+            default_key_config(),
         );
+        command_key(&mut harness, KeyCode::Char(':')).await;
+        command_key(&mut harness, KeyCode::Char(character)).await;
 
-        command_key(&mut harness, KeyCode::Tab).await;
-        assert_eq!(
-            harness.commandline_text(),
-            format!("{command} {command_path}")
-        );
-        command_key(&mut harness, KeyCode::Enter).await;
+        command_key(&mut harness, KeyCode::Backspace).await;
 
-        assert_eq!(
-            fs::read_to_string(&path).unwrap(),
-            "saved contents\n",
-            "{command} did not write the completed path"
-        );
-        assert!(!directory.path().join("name").exists());
+        harness.assert_mode(Mode::Command);
+        assert_eq!(harness.commandline_text(), "");
+        assert_eq!(harness.commandline_row().trim_end(), ":");
+
+        command_key(&mut harness, KeyCode::Backspace).await;
+        harness.assert_mode(Mode::Normal);
+        assert_eq!(harness.commandline_row().trim_end(), "");
+        harness.assert_buffer_contents("unchanged");
     }
 }
 
-#[test]
-fn command_tab_preserves_relative_path_prefix() {
-    let (root, _guard) = command_completion_temp_dir("relative-prefix");
-    fs::create_dir(root.join("src")).unwrap();
-    let mut harness = EditorHarness::with_content("");
-    harness.set_commandline(Mode::Command, "e ./sr");
+struct CurrentDirGuard {
+    original: PathBuf,
+    _lock: MutexGuard<'static, ()>,
+}
 
-    harness.editor.test_complete_command_path_next();
+impl Drop for CurrentDirGuard {
+    fn drop(&mut self) {
+        env::set_current_dir(&self.original).unwrap();
+    }
+}
 
-    assert_eq!(harness.commandline_text(), "e ./src/");
-    drop(_guard);
-    fs::remove_dir_all(root).unwrap();
+fn command_completion_temp_dir(name: &str) -> (PathBuf, CurrentDirGuard) {
+    let lock = COMMAND_COMPLETION_CWD_LOCK.lock().unwrap();
+    let original = env::current_dir().unwrap();
+    let root = env::temp_dir().join(format!(
+        "red-command-completion-{name}-{}",
+        uuid::Uuid::new_v4()
+    ));
+    fs::create_dir_all(&root).unwrap();
+    env::set_current_dir(&root).unwrap();
+    (
+        root,
+        CurrentDirGuard {
+            original,
+            _lock: lock,
+        },
+    )
 }
 
-#[test]
-fn command_tab_completes_dot_to_current_directory_prefix() {
-    let (root, _guard) = command_completion_temp_dir("dot");
+#[tokio::test]
+async fn command_history_recalls_previous_commands_with_up_and_down() {
     let mut harness = EditorHarness::with_content("");
-    harness.set_commandline(Mode::Command, "e .");
-
-    harness.editor.test_complete_command_path_next();
+    harness
+        .execute_action(Action::Command("alpha-one".to_string()))
+        .await
+        .unwrap();
+    harness
+        .execute_action(Action::Command("beta-two".to_string()))
+        .await
+        .unwrap();
+    harness.set_commandline(Mode::Command, "");
 
-    assert_eq!(harness.commandline_text(), "e ./");
-    drop(_guard);
-    fs::remove_dir_all(root).unwrap();
-}
+    command_key(&mut harness, KeyCode::Up).await;
+    assert_eq!(harness.commandline_text(), "beta-two");
 
-#[test]
-fn command_tab_cycles_file_matches_and_backtab_reverses() {
-    let (root, _guard) = command_completion_temp_dir("cycle");
-    fs::write(root.join("src_a.rs"), "").unwrap();
-    fs::write(root.join("src_b.rs"), "").unwrap();
-    let mut harness = EditorHarness::with_content("");
-    harness.set_commandline(Mode::Command, "e src");
+    command_key(&mut harness, KeyCode::Up).await;
+    assert_eq!(harness.commandline_text(), "alpha-one");
 
-    harness.editor.test_complete_command_path_next();
-    assert_eq!(harness.commandline_text(), "e src_a.rs");
+    command_key(&mut harness, KeyCode::Up).await;
+    assert_eq!(harness.commandline_text(), "alpha-one");
 
-    harness.editor.test_complete_command_path_next();
-    assert_eq!(harness.commandline_text(), "e src_b.rs");
+    command_key(&mut harness, KeyCode::Down).await;
+    assert_eq!(harness.commandline_text(), "beta-two");
 
-    harness.editor.test_complete_command_path_previous();
-    assert_eq!(harness.commandline_text(), "e src_a.rs");
-    drop(_guard);
-    fs::remove_dir_all(root).unwrap();
+    command_key(&mut harness, KeyCode::Down).await;
+    assert_eq!(harness.commandline_text(), "");
 }
 
-#[test]
-fn command_tab_sorts_directories_before_files() {
-    let (root, _guard) = command_completion_temp_dir("directories-first");
-    fs::create_dir(root.join("app")).unwrap();
-    fs::write(root.join("alpha.txt"), "").unwrap();
+#[tokio::test]
+async fn command_history_filters_by_typed_prefix() {
     let mut harness = EditorHarness::with_content("");
-    harness.set_commandline(Mode::Command, "e a");
+    for command in ["buffer-next", "write", "buffer-delete"] {
+        harness
+            .execute_action(Action::Command(command.to_string()))
+            .await
+            .unwrap();
+    }
+    harness.set_commandline(Mode::Command, "b");
 
-    harness.editor.test_complete_command_path_next();
+    command_key(&mut harness, KeyCode::Up).await;
+    assert_eq!(harness.commandline_text(), "buffer-delete");
 
-    assert_eq!(harness.commandline_text(), "e app/");
-    drop(_guard);
-    fs::remove_dir_all(root).unwrap();
+    command_key(&mut harness, KeyCode::Up).await;
+    assert_eq!(harness.commandline_text(), "buffer-next");
+
+    command_key(&mut harness, KeyCode::Down).await;
+    assert_eq!(harness.commandline_text(), "buffer-delete");
+
+    command_key(&mut harness, KeyCode::Down).await;
+    assert_eq!(harness.commandline_text(), "b");
 }
 
-#[test]
-fn command_tab_completes_file_arguments_for_split_vsplit_and_write() {
-    let (root, _guard) = command_completion_temp_dir("file-commands");
-    fs::create_dir(root.join("target")).unwrap();
+#[tokio::test]
+async fn command_history_editing_recalled_command_resets_prefix_session() {
+    let mut harness = EditorHarness::with_content("");
+    harness
+        .execute_action(Action::Command("buffer-delete".to_string()))
+        .await
+        .unwrap();
+    harness.set_commandline(Mode::Command, "b");
 
-    for command in [
-        "sp ta",
-        "vs ta",
-        "w ta",
-        "write ta",
-        "split ta",
-        "vsplit ta",
-    ] {
-        let mut harness = EditorHarness::with_content("");
-        harness.set_commandline(Mode::Command, command);
+    command_key(&mut harness, KeyCode::Up).await;
+    assert_eq!(harness.commandline_text(), "buffer-delete");
 
-        harness.editor.test_complete_command_path_next();
+    command_key(&mut harness, KeyCode::Char('x')).await;
+    assert_eq!(harness.commandline_text(), "buffer-deletex");
 
-        let command_name = command.split_once(' ').unwrap().0;
-        assert_eq!(
-            harness.commandline_text(),
-            format!("{command_name} target/")
-        );
-    }
-    drop(_guard);
-    fs::remove_dir_all(root).unwrap();
+    command_key(&mut harness, KeyCode::Up).await;
+    assert_eq!(harness.commandline_text(), "buffer-deletex");
 }
 
-#[test]
-fn command_tab_ignores_non_file_commands() {
-    let (root, _guard) = command_completion_temp_dir("non-file");
-    fs::create_dir(root.join("src")).unwrap();
+#[tokio::test]
+async fn whitespace_only_commands_are_not_saved_to_history() {
     let mut harness = EditorHarness::with_content("");
-    harness.set_commandline(Mode::Command, "q sr");
+    harness
+        .execute_action(Action::Command("   ".to_string()))
+        .await
+        .unwrap();
+    harness.set_commandline(Mode::Command, "");
 
-    harness.editor.test_complete_command_path_next();
+    command_key(&mut harness, KeyCode::Up).await;
 
-    assert_eq!(harness.commandline_text(), "q sr");
-    drop(_guard);
-    fs::remove_dir_all(root).unwrap();
+    assert_eq!(harness.commandline_text(), "");
 }
 
-#[test]
-fn command_tab_completes_syntax_names_and_aliases() {
-    for (command, expected) in [
-        ("syntax ru", "syntax rust"),
-        ("syn ym", "syn yaml"),
-        ("ft rs", "ft rust"),
-        ("syntax ", "syntax auto"),
-    ] {
-        let mut harness = EditorHarness::with_content("");
-        harness.set_commandline(Mode::Command, command);
-
-        harness.editor.test_complete_command_path_next();
-
-        assert_eq!(harness.commandline_text(), expected);
-    }
-}
+#[tokio::test]
+async fn enew_opens_an_unnamed_buffer_without_saving_the_previous_buffer() {
+    let path = temp_file_path("enew-dirty-buffer");
+    fs::write(&path, "saved contents\n").unwrap();
+    let buffer = Buffer::new(Some(path.clone()), "saved contents\n".to_string());
+    let mut harness = EditorHarness::with_buffer(buffer);
+    harness
+        .execute_action(Action::InsertCharAtCursorPos('x'))
+        .await
+        .unwrap();
 
-#[test]
-fn command_tab_does_not_complete_a_second_syntax_argument() {
-    let mut harness = EditorHarness::with_content("");
-    harness.set_commandline(Mode::Command, "syntax rust extra");
+    harness
+        .execute_action(Action::Command("enew".to_string()))
+        .await
+        .unwrap();
 
-    harness.editor.test_complete_command_path_next();
+    assert_eq!(
+        harness.buffer_names(),
+        vec![path.clone(), "[No Name]".into()]
+    );
+    assert_eq!(harness.current_buffer_index(), 1);
+    assert!(harness.editor.test_current_buffer().is_unnamed());
+    assert!(harness.editor.test_current_buffer().is_blank());
+    assert!(!harness.is_dirty());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "saved contents\n");
 
-    assert_eq!(harness.commandline_text(), "syntax rust extra");
+    harness
+        .execute_action(Action::PreviousBuffer)
+        .await
+        .unwrap();
+    assert_eq!(harness.buffer_contents(), "xsaved contents\n");
+    assert!(harness.is_dirty());
+    fs::remove_file(path).unwrap();
 }
 
 #[tokio::test]
-async fn command_tab_key_event_completes_file_argument() {
-    let root = env::temp_dir().join(format!(
-        "red-command-completion-event-{}",
-        uuid::Uuid::new_v4()
-    ));
-    fs::create_dir_all(root.join("target")).unwrap();
-    let mut harness = EditorHarness::with_content("");
-    harness.set_commandline(Mode::Command, &format!("e {}/ta", root.display()));
+async fn enew_reuses_a_clean_unnamed_empty_buffer() {
+    let mut harness = EditorHarness::new();
+    let original_id = harness.editor.test_current_buffer().id();
 
-    command_key(&mut harness, KeyCode::Tab).await;
+    for command in ["ene", "ene!", "enew", "enew!"] {
+        harness
+            .execute_action(Action::Command(command.to_string()))
+            .await
+            .unwrap();
 
-    assert_eq!(
-        harness.commandline_text(),
-        format!("e {}/target/", root.display())
-    );
-    fs::remove_dir_all(root).unwrap();
+        assert_eq!(harness.buffer_names(), vec!["[No Name]"]);
+        assert_eq!(harness.editor.test_current_buffer().id(), original_id);
+    }
 }
 
 #[tokio::test]
-async fn command_tab_completes_builtin_names_and_cycles_matches() {
-    let mut harness = EditorHarness::with_content("");
-    harness.set_commandline(Mode::Command, "wr");
+async fn enew_abbreviation_requires_its_vim_compatible_minimum_prefix() {
+    let mut harness = EditorHarness::with_content("original contents\n");
 
-    command_key(&mut harness, KeyCode::Tab).await;
-    assert_eq!(harness.commandline_text(), "wrap");
+    harness
+        .execute_action(Action::Command("en".to_string()))
+        .await
+        .unwrap();
 
-    command_key(&mut harness, KeyCode::Tab).await;
-    assert_eq!(harness.commandline_text(), "write");
+    assert_eq!(harness.buffer_names(), vec!["[No Name]"]);
+    assert_eq!(harness.buffer_contents(), "original contents\n");
+    assert_eq!(harness.last_error(), Some("unknown command \"en\""));
 
-    command_key(&mut harness, KeyCode::BackTab).await;
-    assert_eq!(harness.commandline_text(), "wrap");
-}
+    harness
+        .execute_action(Action::Command("ene".to_string()))
+        .await
+        .unwrap();
 
-#[tokio::test]
-async fn command_tab_completes_arguments_without_executing_them() {
-    let mut harness = EditorHarness::with_content("");
-    harness.set_commandline(Mode::Command, "Copilot sign");
-    command_key(&mut harness, KeyCode::Tab).await;
-    assert_eq!(harness.commandline_text(), "Copilot signin");
-    command_key(&mut harness, KeyCode::Tab).await;
-    assert_eq!(harness.commandline_text(), "Copilot signout");
-    command_key(&mut harness, KeyCode::BackTab).await;
-    assert_eq!(harness.commandline_text(), "Copilot signin");
-    command_key(&mut harness, KeyCode::Char('x')).await;
-    command_key(&mut harness, KeyCode::Tab).await;
-    assert_eq!(harness.commandline_text(), "Copilot signinx");
-    assert_eq!(harness.last_error(), None);
+    assert_eq!(harness.buffer_names(), vec!["[No Name]", "[No Name]"]);
+    assert!(harness.editor.test_current_buffer().is_blank());
 }
 
 #[tokio::test]
-async fn command_tab_completion_remains_case_sensitive() {
-    let mut harness = EditorHarness::with_content("");
-    harness.set_commandline(Mode::Command, "Wr");
+async fn enew_changes_only_the_active_split() {
+    let mut harness = EditorHarness::with_content("original contents\n");
+    harness.execute_action(Action::SplitVertical).await.unwrap();
 
-    command_key(&mut harness, KeyCode::Tab).await;
+    harness
+        .execute_action(Action::Command("enew".to_string()))
+        .await
+        .unwrap();
 
-    assert_eq!(harness.commandline_text(), "Wr");
+    assert_eq!(harness.window_count(), 2);
+    assert_eq!(harness.current_buffer_index(), 1);
+    assert!(harness.editor.test_current_buffer().is_blank());
+
+    harness.execute_action(Action::NextWindow).await.unwrap();
+
+    assert_eq!(harness.current_buffer_index(), 0);
+    assert_eq!(harness.buffer_contents(), "original contents\n");
 }
 
 #[tokio::test]
-async fn wrap_commands_toggle_line_wrapping() {
-    let mut harness = EditorHarness::with_content("short");
-    assert!(harness.wrap());
+async fn enew_rejects_file_arguments_without_changing_buffers() {
+    let mut harness = EditorHarness::with_content("original contents\n");
 
     harness
-        .execute_action(Action::Command("nowrap".to_string()))
+        .execute_action(Action::Command("enew unexpected.txt".to_string()))
         .await
         .unwrap();
-    assert!(!harness.wrap());
 
-    harness
-        .execute_action(Action::Command("wrap".to_string()))
-        .await
-        .unwrap();
-    assert!(harness.wrap());
+    assert_eq!(harness.buffer_names(), vec!["[No Name]"]);
+    assert_eq!(harness.buffer_contents(), "original contents\n");
+    assert_eq!(harness.last_error(), Some("usage: enew"));
 }
 
 #[tokio::test]
-async fn vim_parity_gw_toggles_wrapping_without_overriding_big_word_motion() {
-    let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "foo.bar baz".to_string()),
-        default_key_config(),
-    );
-    let initial_wrap = harness.wrap();
+async fn new_and_vnew_create_fresh_unnamed_buffers_in_new_splits() {
+    for command in ["new", "vnew"] {
+        let mut harness = EditorHarness::new();
+        let original_id = harness.editor.test_current_buffer().id();
 
-    type_normal_keys(&mut harness, "gW").await;
+        harness
+            .execute_action(Action::Command(command.to_string()))
+            .await
+            .unwrap();
 
-    assert_eq!(harness.wrap(), !initial_wrap);
-    harness.assert_cursor_at(0, 0);
+        assert_eq!(harness.window_count(), 2, "{command}");
+        assert_eq!(harness.buffer_names(), vec!["[No Name]", "[No Name]"]);
+        assert_eq!(harness.current_buffer_index(), 1);
+        assert_ne!(harness.editor.test_current_buffer().id(), original_id);
+        assert!(harness.editor.test_current_buffer().is_blank());
 
-    type_normal_keys(&mut harness, "gW").await;
+        harness.execute_action(Action::NextWindow).await.unwrap();
+        assert_eq!(harness.editor.test_current_buffer().id(), original_id);
+    }
+}
 
-    assert_eq!(harness.wrap(), initial_wrap);
-    harness.assert_cursor_at(0, 0);
+#[tokio::test]
+async fn new_and_vnew_accept_paths_without_writing_missing_files() {
+    let directory = tempfile::tempdir().unwrap();
+
+    for (command, name) in [("new", "horizontal.rs"), ("vnew", "vertical.rs")] {
+        let path = directory.path().join(name);
+        let mut harness = EditorHarness::with_content("original contents\n");
+
+        harness
+            .execute_action(Action::Command(format!("{command} {}", path.display())))
+            .await
+            .unwrap();
+
+        assert_eq!(harness.window_count(), 2, "{command}");
+        assert_eq!(
+            harness.editor.test_current_buffer().file.as_deref(),
+            path.to_str()
+        );
+        assert!(!path.exists(), "{command} should not create a disk file");
+
+        harness.execute_action(Action::NextWindow).await.unwrap();
+        assert_eq!(harness.buffer_contents(), "original contents\n");
+    }
 }
 
 #[tokio::test]
-async fn syntax_commands_set_reset_and_disable_buffer_local_syntax() {
-    let buffer = Buffer::new(Some("notes.txt".to_string()), "fn main() {}".to_string());
-    let mut harness = EditorHarness::with_buffer(buffer);
+async fn buffer_commands_select_names_stable_numbers_and_the_alternate() {
+    let directory = tempfile::tempdir().unwrap();
+    let first = directory.path().join("one.rs");
+    let second = directory.path().join("two.rs");
+    let third = directory.path().join("three.rs");
+    let buffers = [(&first, "one\n"), (&second, "two\n"), (&third, "three\n")]
+        .into_iter()
+        .map(|(path, contents)| {
+            Buffer::new(
+                Some(path.to_string_lossy().into_owned()),
+                contents.to_string(),
+            )
+        })
+        .collect();
+    let mut editor = Editor::test_with_size(
+        Box::new(MockLsp),
+        /*width*/ 80,
+        /*height*/ 24,
+        Config::default(),
+        Theme::default(),
+        buffers,
+    )
+    .unwrap();
+    editor.test_disable_terminal_output();
+    let mut harness = EditorHarness { editor };
 
     harness
-        .execute_action(Action::Command("ft RS".to_string()))
+        .execute_action(Action::Command("b two.rs".to_string()))
         .await
         .unwrap();
-    assert_eq!(
-        harness.editor.test_current_buffer().syntax_selection(),
-        &SyntaxSelection::Language("rust".to_string())
-    );
-    assert_eq!(harness.last_error(), Some("syntax: rust"));
-    assert!(!harness.is_dirty());
+    assert_eq!(harness.current_buffer_index(), 1);
+    let second_id = harness.editor.test_current_buffer().id().as_u64();
 
     harness
-        .execute_action(Action::Command("syn off".to_string()))
+        .execute_action(Action::Command("buffer #".to_string()))
         .await
         .unwrap();
-    assert_eq!(
-        harness.editor.test_current_buffer().syntax_selection(),
-        &SyntaxSelection::Off
-    );
-    assert_eq!(harness.last_error(), Some("syntax: off"));
+    assert_eq!(harness.current_buffer_index(), 0);
 
     harness
-        .execute_action(Action::Command("sy rust".to_string()))
+        .execute_action(Action::Command(format!("buffer {second_id}")))
         .await
         .unwrap();
-    assert_eq!(
-        harness.editor.test_current_buffer().syntax_selection(),
-        &SyntaxSelection::Language("rust".to_string())
-    );
+    assert_eq!(harness.current_buffer_index(), 1);
 
     harness
-        .execute_action(Action::Command("syntax auto".to_string()))
+        .execute_action(Action::Command("b#".to_string()))
         .await
         .unwrap();
-    assert_eq!(
-        harness.editor.test_current_buffer().syntax_selection(),
-        &SyntaxSelection::Auto
-    );
-    assert_eq!(harness.last_error(), Some("syntax: auto"));
-}
-
-#[tokio::test]
-async fn invalid_syntax_commands_leave_the_existing_selection_unchanged() {
-    let buffer = Buffer::new(Some("notes.txt".to_string()), "fn main() {}".to_string());
-    let mut harness = EditorHarness::with_buffer(buffer);
-    harness
-        .execute_action(Action::Command("syntax rust".to_string()))
-        .await
-        .unwrap();
-
-    harness
-        .execute_action(Action::Command("syntax madeup".to_string()))
-        .await
-        .unwrap();
-    assert_eq!(
-        harness.editor.test_current_buffer().syntax_selection(),
-        &SyntaxSelection::Language("rust".to_string())
-    );
-    assert_eq!(
-        harness.last_error(),
-        Some("unknown syntax \"madeup\" (try :syntax)")
-    );
+    assert_eq!(harness.current_buffer_index(), 0);
+    assert_eq!(harness.buffer_names().len(), 3);
 
     harness
-        .execute_action(Action::Command("syntax rust yaml".to_string()))
+        .execute_action(Action::Command("b thre".to_string()))
         .await
         .unwrap();
-    assert_eq!(
-        harness.editor.test_current_buffer().syntax_selection(),
-        &SyntaxSelection::Language("rust".to_string())
-    );
-    assert_eq!(
-        harness.last_error(),
-        Some("usage: syntax [language|auto|off]")
-    );
-    assert!(!harness.is_dirty());
+    assert_eq!(harness.current_buffer_index(), 2);
 }
 
 #[tokio::test]
-async fn submitted_commands_are_persisted_to_preferences() {
-    let dir = std::env::temp_dir().join(format!("red-command-history-{}", uuid::Uuid::new_v4()));
-    let path = dir.join("preferences.json");
-    let lsp = Box::new(MockLsp) as Box<dyn LspClient>;
-    let config = Config::default();
-    let buffer = Buffer::new(None, String::new());
-    let mut editor = Editor::test_with_size_and_preferences(
-        lsp,
-        80,
-        24,
-        config,
+async fn buffer_selection_rejects_ambiguous_names_and_supports_stable_ids() {
+    let buffers = vec![
+        Buffer::new(/*file*/ None, "first\n".to_string()),
+        Buffer::new(/*file*/ None, "second\n".to_string()),
+    ];
+    let second_id = buffers[1].id().as_u64();
+    let mut editor = Editor::test_with_size(
+        Box::new(MockLsp),
+        /*width*/ 80,
+        /*height*/ 24,
+        Config::default(),
         Theme::default(),
-        vec![buffer],
-        PreferencesStore::load(&path),
+        buffers,
     )
     .unwrap();
     editor.test_disable_terminal_output();
+    let mut harness = EditorHarness { editor };
 
-    editor
-        .test_execute_production_action(Action::Command("persist-me".to_string()))
+    harness
+        .execute_action(Action::Command("b [No Name]".to_string()))
         .await
         .unwrap();
+    assert_eq!(harness.current_buffer_index(), 0);
+    assert!(harness
+        .last_error()
+        .is_some_and(|message| message.contains("Multiple buffers match")));
 
-    let store = PreferencesStore::load(&path);
-    assert_eq!(store.command_history(), ["persist-me"]);
-    fs::remove_dir_all(dir).ok();
+    harness
+        .execute_action(Action::OpenBufferById(second_id))
+        .await
+        .unwrap();
+    assert_eq!(harness.current_buffer_index(), 1);
+    assert_eq!(harness.buffer_contents(), "second\n");
 }
 
 #[tokio::test]
-async fn test_insert_mode() {
-    let mut harness = EditorHarness::with_content("Hello World");
+async fn buffer_listing_aliases_show_stable_numbers_without_opening_splits() {
+    for command in ["ls", "buffers", "files", "b", "buffer"] {
+        let mut harness = EditorHarness::with_content("contents\n");
+        harness
+            .execute_action(Action::InsertCharAtCursorPos('x'))
+            .await
+            .unwrap();
+        let id = harness.editor.test_current_buffer().id().as_u64();
 
-    // Debug: Check initial cursor position and buffer state
-    println!("Initial cursor position: {:?}", harness.cursor_position());
-    println!("Number of lines: {}", harness.line_count());
-    if let Some(line) = harness.line_contents(0) {
-        println!("Line 0 content: {:?}", line);
+        harness
+            .execute_action(Action::Command(command.to_string()))
+            .await
+            .unwrap();
+
+        let listing = harness.last_error().unwrap();
+        assert!(listing.contains(&id.to_string()), "{command}: {listing}");
+        assert!(listing.contains("[No Name]"), "{command}: {listing}");
+        assert!(listing.contains("[+]"), "{command}: {listing}");
+        assert_eq!(harness.window_count(), 1, "{command}");
     }
+}
 
-    // Enter insert mode with 'i'
+#[tokio::test]
+async fn standard_buffer_navigation_aliases_switch_existing_buffers() {
+    let mut harness = EditorHarness::with_content("first\n");
     harness
-        .execute_action(Action::EnterMode(Mode::Insert))
+        .execute_action(Action::Command("enew".to_string()))
         .await
         .unwrap();
-    harness.assert_mode(Mode::Insert);
-
-    // Debug: Check cursor position after entering insert mode
-    println!(
-        "Cursor position after entering insert mode: {:?}",
-        harness.cursor_position()
-    );
-
-    // Type some text
-    harness.type_text("Hi ").await.unwrap();
-
-    // Debug: Check actual buffer contents
-    let contents = harness.buffer_contents();
-    println!("Actual buffer contents: {:?}", contents);
-    println!("Buffer length: {}", contents.len());
-    println!("Ends with newline: {}", contents.ends_with('\n'));
 
-    harness.assert_buffer_contents("Hi Hello World");
+    harness
+        .execute_action(Action::Command("bprevious".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(harness.current_buffer_index(), 0);
 
-    // Exit insert mode (ESC)
     harness
-        .execute_action(Action::EnterMode(Mode::Normal))
+        .execute_action(Action::Command("bnext".to_string()))
         .await
         .unwrap();
-    harness.assert_mode(Mode::Normal);
+    assert_eq!(harness.current_buffer_index(), 1);
 }
 
 #[tokio::test]
-async fn test_append_mode() {
-    let mut harness = EditorHarness::with_content("Hello World");
-
-    // Move cursor to 'o' in 'Hello' (position 4)
-    for _ in 0..4 {
-        harness.execute_action(Action::MoveRight).await.unwrap();
-    }
+async fn saveas_writes_the_current_buffer_to_its_new_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("saved notes.txt");
+    let mut harness = EditorHarness::with_content("draft contents\n");
 
-    // Enter append mode with 'a' - should insert after current character
-    harness.execute_action(Action::MoveRight).await.unwrap();
     harness
-        .execute_action(Action::EnterMode(Mode::Insert))
+        .execute_action(Action::Command(format!("saveas {}", path.display())))
         .await
         .unwrap();
-    harness.assert_mode(Mode::Insert);
 
-    // Type text
-    harness.type_text(" there").await.unwrap();
-    harness.assert_buffer_contents("Hello there World");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "draft contents\n");
+    assert_eq!(
+        harness.editor.test_current_buffer().file.as_deref(),
+        path.to_str()
+    );
+    assert!(!harness.is_dirty());
+}
 
-    // Exit insert mode
+#[tokio::test]
+async fn file_assigns_a_buffer_name_without_writing_or_clearing_changes() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("unsaved notes.txt");
+    let mut harness = EditorHarness::with_content("draft contents\n");
     harness
-        .execute_action(Action::EnterMode(Mode::Normal))
+        .execute_action(Action::InsertCharAtCursorPos('x'))
         .await
         .unwrap();
-}
-
-#[tokio::test]
-async fn test_open_line_below() {
-    let mut harness = EditorHarness::with_content("Line 1\nLine 2");
 
-    // Open line below with 'o' - InsertLineBelowCursor
     harness
-        .execute_action(Action::InsertLineBelowCursor)
+        .execute_action(Action::Command(format!("file {}", path.display())))
         .await
         .unwrap();
-    harness.assert_mode(Mode::Insert);
-
-    // Should have created a new line and moved cursor there
-    harness.assert_cursor_at(0, 1);
 
-    // Type on the new line
-    harness.type_text("New line").await.unwrap();
-    harness.assert_buffer_contents("Line 1\nNew line\nLine 2");
+    assert_eq!(
+        harness.editor.test_current_buffer().file.as_deref(),
+        path.to_str()
+    );
+    assert_eq!(harness.buffer_contents(), "xdraft contents\n");
+    assert!(harness.is_dirty());
+    assert!(!path.exists());
 
-    // Exit insert mode
     harness
-        .execute_action(Action::EnterMode(Mode::Normal))
+        .execute_action(Action::Command("file".to_string()))
         .await
         .unwrap();
+    assert_eq!(harness.last_error(), path.to_str());
 }
 
 #[tokio::test]
-async fn test_enter_on_opened_indented_blank_line_moves_generated_indentation() {
-    let mut harness = EditorHarness::with_content("fn name() {\n    let a = 1;\n}");
+async fn file_rejects_paths_already_open_in_another_buffer() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("open.rs");
+    let buffers = vec![
+        Buffer::new(/*file*/ None, "draft\n".to_string()),
+        Buffer::new(
+            Some(path.to_string_lossy().into_owned()),
+            "existing\n".to_string(),
+        ),
+    ];
+    let mut editor = Editor::test_with_size(
+        Box::new(MockLsp),
+        /*width*/ 80,
+        /*height*/ 24,
+        Config::default(),
+        Theme::default(),
+        buffers,
+    )
+    .unwrap();
+    editor.test_disable_terminal_output();
+    let mut harness = EditorHarness { editor };
 
-    harness.execute_action(Action::MoveDown).await.unwrap();
     harness
-        .execute_action(Action::InsertLineBelowCursor)
+        .execute_action(Action::Command(format!("file {}", path.display())))
         .await
         .unwrap();
-    harness.assert_cursor_at(4, 2);
-
-    harness.execute_action(Action::InsertNewLine).await.unwrap();
 
-    harness.assert_cursor_at(4, 3);
-    harness.assert_buffer_contents("fn name() {\n    let a = 1;\n\n    \n}");
+    assert!(harness.editor.test_current_buffer().is_unnamed());
+    assert!(harness
+        .last_error()
+        .is_some_and(|message| message.contains("already exists")));
+    assert!(!path.exists());
 }
 
 #[tokio::test]
-async fn python_autoindent_requires_the_language_pack() {
-    let buffer = Buffer::new(
-        Some("sample.py".to_string()),
-        "def something(x):".to_string(),
-    );
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+async fn saveas_requires_a_file_argument() {
+    let mut harness = EditorHarness::with_content("draft contents\n");
 
     harness
-        .execute_action(Action::InsertLineBelowCursor)
+        .execute_action(Action::Command("saveas".to_string()))
         .await
         .unwrap();
 
-    harness.assert_cursor_at(0, 1);
-    harness.assert_buffer_contents("def something(x):\n");
+    assert!(harness.editor.test_current_buffer().is_unnamed());
+    assert_eq!(harness.last_error(), Some("usage: saveas <file>"));
 }
 
 #[tokio::test]
-async fn python_enter_indents_a_suite_and_dedents_after_return() {
-    let mut harness = python_harness("");
+async fn unknown_command_does_not_expand_into_reload_write_or_split_actions() {
+    let path = temp_file_path("unknown-buffer-command");
+    fs::write(&path, "disk contents\n").unwrap();
+    let buffer = Buffer::new(Some(path.clone()), "disk contents\n".to_string());
+    let mut harness = EditorHarness::with_buffer(buffer);
     harness
-        .execute_action(Action::EnterMode(Mode::Insert))
+        .execute_action(Action::InsertCharAtCursorPos('x'))
         .await
         .unwrap();
-    harness.type_text("def something(x):").await.unwrap();
-    harness.execute_action(Action::InsertNewLine).await.unwrap();
-    harness.assert_cursor_at(4, 1);
-    harness.type_text("return x").await.unwrap();
-    harness.execute_action(Action::InsertNewLine).await.unwrap();
-
-    harness.assert_cursor_at(0, 2);
-    harness.assert_buffer_contents("def something(x):\n    return x\n");
-}
 
-#[tokio::test]
-async fn python_ignores_colons_in_comments_and_strings() {
-    for source in ["value = 1  # note:", "value = \"note:\""] {
-        let mut harness = python_harness(source);
+    for command in ["eneww", "nwe", "lss"] {
         harness
-            .execute_action(Action::EnterMode(Mode::Insert))
+            .execute_action(Action::Command(command.to_string()))
             .await
             .unwrap();
-        harness.execute_action(Action::MoveToLineEnd).await.unwrap();
-        harness.execute_action(Action::MoveRight).await.unwrap();
-        harness.execute_action(Action::InsertNewLine).await.unwrap();
-        harness.assert_cursor_at(0, 1);
-    }
-}
 
-#[tokio::test]
-async fn python_continuations_align_and_use_hanging_indent() {
-    let mut aligned = python_harness("call(first,");
-    aligned
-        .execute_action(Action::InsertLineBelowCursor)
-        .await
-        .unwrap();
-    aligned.assert_cursor_at(5, 1);
-    aligned.type_text("second").await.unwrap();
-    aligned.assert_buffer_contents("call(first,\n     second");
+        assert_eq!(harness.buffer_contents(), "xdisk contents\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "disk contents\n");
+        assert_eq!(harness.window_count(), 1);
+        assert!(harness
+            .last_error()
+            .is_some_and(|message| message.contains("unknown command")));
+    }
 
-    let mut hanging = python_harness("values = [");
-    hanging
-        .execute_action(Action::InsertLineBelowCursor)
-        .await
-        .unwrap();
-    hanging.assert_cursor_at(8, 1);
+    fs::remove_file(path).unwrap();
 }
 
 #[tokio::test]
-async fn python_typing_else_and_except_reindents_the_current_line() {
-    let mut else_harness = python_harness("if ready:\n    work()\n    ");
-    else_harness
-        .execute_action(Action::MoveToBottom)
-        .await
-        .unwrap();
-    else_harness
-        .execute_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
-    else_harness
-        .execute_action(Action::MoveToLineEnd)
-        .await
-        .unwrap();
-    else_harness
-        .execute_action(Action::MoveRight)
-        .await
-        .unwrap();
-    else_harness.type_text("else:").await.unwrap();
-    else_harness.assert_buffer_contents("if ready:\n    work()\nelse:");
-    else_harness.assert_cursor_at(5, 2);
-
-    let mut except_harness = python_harness("try:\n    work()\n    ");
-    except_harness
-        .execute_action(Action::MoveToBottom)
-        .await
-        .unwrap();
-    except_harness
-        .execute_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
-    except_harness
-        .execute_action(Action::MoveToLineEnd)
-        .await
-        .unwrap();
-    except_harness
-        .execute_action(Action::MoveRight)
-        .await
-        .unwrap();
-    except_harness.type_text("except:").await.unwrap();
-    except_harness.assert_buffer_contents("try:\n    work()\nexcept:");
-}
+async fn edit_without_file_argument_reloads_current_file() {
+    let path = temp_file_path("edit-reload");
+    fs::write(&path, "one\ntwo\nthree\n").unwrap();
+    let buffer = Buffer::new(Some(path.clone()), "one\ntwo\nthree\n".to_string());
+    let mut harness = EditorHarness::with_buffer(buffer);
+    harness.execute_action(Action::MoveDown).await.unwrap();
+    fs::write(&path, "one\nchanged\nthree\n").unwrap();
 
-#[tokio::test]
-async fn backspace_uses_soft_tab_stops_inside_leading_indentation() {
-    let mut harness = python_harness("values = [");
     harness
-        .execute_action(Action::InsertLineBelowCursor)
+        .execute_action(Action::Command("e".to_string()))
         .await
         .unwrap();
-    harness.assert_cursor_at(8, 1);
 
-    harness
-        .execute_action(Action::DeletePreviousChar)
-        .await
-        .unwrap();
-    harness.assert_cursor_at(4, 1);
-    harness
-        .execute_action(Action::DeletePreviousChar)
-        .await
-        .unwrap();
-    harness.assert_cursor_at(0, 1);
-    harness.type_text("value").await.unwrap();
-    harness.assert_buffer_contents("values = [\nvalue");
+    assert_eq!(harness.buffer_contents(), "one\nchanged\nthree\n");
+    assert_eq!(harness.cursor_position(), (0, 1));
+    assert!(!harness.is_dirty());
+    fs::remove_file(path).unwrap();
 }
 
 #[tokio::test]
-async fn tab_advances_to_the_next_soft_tab_stop() {
-    let mut harness = python_harness("  value");
-    harness
-        .execute_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
+async fn edit_without_force_refuses_to_reload_dirty_current_file() {
+    let path = temp_file_path("edit-reload-dirty");
+    fs::write(&path, "one\ntwo\n").unwrap();
+    let buffer = Buffer::new(Some(path.clone()), "one\ntwo\n".to_string());
+    let mut harness = EditorHarness::with_buffer(buffer);
     harness
-        .execute_action(Action::SetCursor(2, 0))
-        .await
-        .unwrap();
-    harness.execute_action(Action::InsertTab).await.unwrap();
-
-    harness.assert_cursor_at(4, 0);
-    harness.assert_buffer_contents("    value");
-}
-
-#[tokio::test]
-async fn untouched_generated_indent_is_removed_on_escape_and_carried_across_enter() {
-    let mut escaped = python_harness("def something(x):");
-    escaped
-        .execute_action(Action::InsertLineBelowCursor)
-        .await
-        .unwrap();
-    escaped
-        .execute_action(Action::EnterMode(Mode::Normal))
-        .await
-        .unwrap();
-    escaped.assert_buffer_contents("def something(x):\n");
-
-    let mut carried = python_harness("def something(x):");
-    carried
-        .execute_action(Action::InsertLineBelowCursor)
+        .execute_action(Action::InsertCharAtCursorPos('x'))
         .await
         .unwrap();
-    carried.execute_action(Action::InsertNewLine).await.unwrap();
-    carried.type_text("value").await.unwrap();
-    carried.assert_buffer_contents("def something(x):\n\n    value");
-}
+    fs::write(&path, "one\nchanged\n").unwrap();
 
-#[tokio::test]
-async fn python_open_above_and_forced_syntax_use_the_same_provider() {
-    let mut above = python_harness("def something(x):\n    value");
-    above.execute_action(Action::MoveDown).await.unwrap();
-    above
-        .execute_action(Action::InsertLineAtCursor)
+    harness
+        .execute_action(Action::Command("e".to_string()))
         .await
         .unwrap();
-    above.assert_cursor_at(4, 1);
-    above.type_text("other").await.unwrap();
-    above.assert_buffer_contents("def something(x):\n    other\n    value");
 
-    let buffer = Buffer::new(
-        Some("notes.txt".to_string()),
-        "def something(x):".to_string(),
+    assert_eq!(harness.buffer_contents(), "xone\ntwo\n");
+    assert_eq!(
+        harness.last_error(),
+        Some("E37: No write since last change (add ! to override)")
     );
-    let mut forced = EditorHarness::with_config(buffer, python_pack_config());
-    forced
-        .execute_action(Action::SetSyntax("python".to_string()))
-        .await
-        .unwrap();
-    forced
-        .execute_action(Action::InsertLineBelowCursor)
-        .await
-        .unwrap();
-    forced.assert_cursor_at(4, 1);
+    assert!(harness.is_dirty());
+    fs::remove_file(path).unwrap();
 }
 
 #[tokio::test]
-async fn generated_indent_cleans_up_when_insert_mode_moves_to_another_line() {
-    let mut harness = python_harness("def something(x):\nnext");
-    harness
-        .execute_action(Action::InsertLineBelowCursor)
-        .await
-        .unwrap();
-    harness.assert_buffer_contents("def something(x):\n    \nnext");
-
+async fn edit_with_force_reloads_dirty_current_file() {
+    let path = temp_file_path("edit-reload-force");
+    fs::write(&path, "one\ntwo\n").unwrap();
+    let buffer = Buffer::new(Some(path.clone()), "one\ntwo\n".to_string());
+    let mut harness = EditorHarness::with_buffer(buffer);
     harness.execute_action(Action::MoveDown).await.unwrap();
-    harness.assert_buffer_contents("def something(x):\n\nnext");
-}
-
-#[tokio::test]
-async fn python_autoindent_and_inserted_text_undo_as_one_change() {
-    let mut harness = python_harness("def something(x):\nnext");
     harness
-        .execute_action(Action::InsertLineBelowCursor)
+        .execute_action(Action::InsertCharAtCursorPos('x'))
         .await
         .unwrap();
-    harness.type_text("value").await.unwrap();
+    fs::write(&path, "one\nchanged\n").unwrap();
+
     harness
-        .execute_action(Action::EnterMode(Mode::Normal))
+        .execute_action(Action::Command("e!".to_string()))
         .await
         .unwrap();
-    harness.assert_buffer_contents("def something(x):\n    value\nnext");
+
+    assert_eq!(harness.buffer_contents(), "one\nchanged\n");
+    assert_eq!(harness.cursor_position(), (1, 1));
+    assert!(!harness.is_dirty());
 
     harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("def something(x):\nnext");
+    assert_eq!(harness.buffer_contents(), "one\nxtwo\n");
+    assert_eq!(harness.cursor_position(), (1, 1));
+    assert!(harness.is_dirty());
+
     harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents("def something(x):\n    value\nnext");
+    assert_eq!(harness.buffer_contents(), "one\nchanged\n");
+    assert_eq!(harness.cursor_position(), (1, 1));
+    assert!(!harness.is_dirty());
+    fs::remove_file(path).unwrap();
 }
 
-#[tokio::test]
-async fn test_enter_on_existing_whitespace_only_line_preserves_indentation() {
-    let mut harness = EditorHarness::with_content("    \nnext");
+#[test]
+fn command_tab_completes_edit_file_argument() {
+    let (root, _guard) = command_completion_temp_dir("edit");
+    fs::create_dir(root.join("src")).unwrap();
+    fs::write(root.join("sample.txt"), "").unwrap();
+    let mut harness = EditorHarness::with_content("");
+    harness.set_commandline(Mode::Command, "e sr");
 
-    harness
-        .execute_action(Action::SetCursor(3, 0))
-        .await
-        .unwrap();
-    harness.execute_action(Action::InsertNewLine).await.unwrap();
+    harness.editor.test_complete_command_path_next();
 
-    harness.assert_cursor_at(4, 1);
-    harness.assert_buffer_contents("   \n     \nnext");
+    assert_eq!(harness.commandline_text(), "e src/");
+    drop(_guard);
+    fs::remove_dir_all(root).unwrap();
 }
 
 #[tokio::test]
-async fn test_open_line_above() {
-    let mut harness = EditorHarness::with_content("Line 1\nLine 2");
-
-    // Move to second line
-    harness.execute_action(Action::MoveDown).await.unwrap();
-    println!(
-        "After MoveDown - cursor at: {:?}",
-        harness.cursor_position()
-    );
-
-    // Open line above with 'O' - InsertLineAtCursor
-    harness
-        .execute_action(Action::InsertLineAtCursor)
-        .await
-        .unwrap();
-    println!(
-        "After InsertLineAtCursor - cursor at: {:?}",
-        harness.cursor_position()
-    );
-    println!("Buffer contents: {:?}", harness.buffer_contents());
-    harness.assert_mode(Mode::Insert);
+async fn command_tab_opens_completed_paths_with_spaces() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("name  with spaces.txt!");
+    let command_directory = directory.path().to_string_lossy().replace('\\', "/");
+    let command_path = format!("{command_directory}/name  with spaces.txt!");
+    fs::write(&path, "completed file contents\n").unwrap();
 
-    // Should have created a new line above and moved cursor there
-    harness.assert_cursor_at(0, 1);
+    for command in [
+        "e", "ed", "edit", "e!", "new", "vne", "vnew", "spl", "split", "sp", "vsp", "vsplit", "vs",
+    ] {
+        let mut harness = EditorHarness::with_content("");
+        harness.set_commandline(
+            Mode::Command,
+            &format!("{command} {command_directory}/name"),
+        );
 
-    // Type on the new line
-    harness.type_text("Middle line").await.unwrap();
-    harness.assert_buffer_contents("Line 1\nMiddle line\nLine 2");
+        command_key(&mut harness, KeyCode::Tab).await;
+        assert_eq!(
+            harness.commandline_text(),
+            format!("{command} {command_path}")
+        );
+        command_key(&mut harness, KeyCode::Enter).await;
 
-    // Exit insert mode
-    harness
-        .execute_action(Action::EnterMode(Mode::Normal))
-        .await
-        .unwrap();
+        assert_eq!(
+            harness.buffer_contents(),
+            "completed file contents\n",
+            "{command} did not open the completed path"
+        );
+    }
 }
 
 #[tokio::test]
-async fn test_delete_char() {
-    let mut harness = EditorHarness::with_content("Hello World");
+async fn command_tab_writes_completed_paths_with_spaces() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("name  with spaces.txt!");
+    let command_directory = directory.path().to_string_lossy().replace('\\', "/");
+    let command_path = format!("{command_directory}/name  with spaces.txt!");
 
-    // Delete character under cursor with 'x'
-    harness
-        .execute_action(Action::DeleteCharAtCursorPos)
-        .await
-        .unwrap();
-    harness.assert_buffer_contents("ello World");
+    for command in ["w", "wr", "wri", "write", "w!", "write!"] {
+        fs::write(&path, "old contents\n").unwrap();
+        let mut harness = EditorHarness::with_content("saved contents\n");
+        harness.set_commandline(
+            Mode::Command,
+            &format!("{command} {command_directory}/name"),
+        );
 
-    // Move to space and delete
-    harness
-        .execute_action(Action::MoveToNextWord)
-        .await
-        .unwrap();
-    harness.execute_action(Action::MoveLeft).await.unwrap();
-    harness
-        .execute_action(Action::DeleteCharAtCursorPos)
-        .await
-        .unwrap();
-    harness.assert_buffer_contents("elloWorld");
-}
+        command_key(&mut harness, KeyCode::Tab).await;
+        assert_eq!(
+            harness.commandline_text(),
+            format!("{command} {command_path}")
+        );
+        command_key(&mut harness, KeyCode::Enter).await;
 
-#[tokio::test]
-async fn test_delete_line() {
-    let mut harness = EditorHarness::with_content("Line 1\nLine 2\nLine 3");
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "saved contents\n",
+            "{command} did not write the completed path"
+        );
+        assert!(!directory.path().join("name").exists());
+    }
+}
 
-    // Move to second line
-    harness.execute_action(Action::MoveDown).await.unwrap();
+#[test]
+fn command_tab_preserves_relative_path_prefix() {
+    let (root, _guard) = command_completion_temp_dir("relative-prefix");
+    fs::create_dir(root.join("src")).unwrap();
+    let mut harness = EditorHarness::with_content("");
+    harness.set_commandline(Mode::Command, "e ./sr");
 
-    // Delete line with 'dd'
-    println!("Before delete: {:?}", harness.buffer_contents());
-    println!("Cursor at: {:?}", harness.cursor_position());
-    println!("Line under cursor: {:?}", harness.current_line());
-    harness
-        .execute_action(Action::DeleteCurrentLine)
-        .await
-        .unwrap();
-    println!("After delete: {:?}", harness.buffer_contents());
-    println!("Cursor at after: {:?}", harness.cursor_position());
-    println!("Line under cursor after: {:?}", harness.current_line());
-    harness.assert_buffer_contents("Line 1\nLine 3");
+    harness.editor.test_complete_command_path_next();
 
-    // Cursor should be on what was line 3
-    harness.assert_cursor_at(0, 1);
+    assert_eq!(harness.commandline_text(), "e ./src/");
+    drop(_guard);
+    fs::remove_dir_all(root).unwrap();
 }
 
-#[tokio::test]
-async fn test_delete_to_end_of_line() {
-    let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "Hello World Test".to_string()),
-        line_end_delete_key_config(),
-    );
+#[test]
+fn command_tab_completes_dot_to_current_directory_prefix() {
+    let (root, _guard) = command_completion_temp_dir("dot");
+    let mut harness = EditorHarness::with_content("");
+    harness.set_commandline(Mode::Command, "e .");
 
-    type_normal_keys(&mut harness, "wD").await;
+    harness.editor.test_complete_command_path_next();
 
-    harness.assert_buffer_contents("Hello ");
+    assert_eq!(harness.commandline_text(), "e ./");
+    drop(_guard);
+    fs::remove_dir_all(root).unwrap();
 }
 
-#[tokio::test]
-async fn test_change_word() {
-    let mut harness = EditorHarness::with_content("Hello World Test");
+#[test]
+fn command_tab_cycles_file_matches_and_backtab_reverses() {
+    let (root, _guard) = command_completion_temp_dir("cycle");
+    fs::write(root.join("src_a.rs"), "").unwrap();
+    fs::write(root.join("src_b.rs"), "").unwrap();
+    let mut harness = EditorHarness::with_content("");
+    harness.set_commandline(Mode::Command, "e src");
 
-    // Change word with 'cw' - delete word then enter insert mode
-    harness.execute_action(Action::DeleteWord).await.unwrap();
-    harness
-        .execute_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
-    harness.assert_mode(Mode::Insert);
+    harness.editor.test_complete_command_path_next();
+    assert_eq!(harness.commandline_text(), "e src_a.rs");
 
-    // Type replacement
-    harness.type_text("Hi ").await.unwrap();
-    harness.assert_buffer_contents("Hi World Test");
+    harness.editor.test_complete_command_path_next();
+    assert_eq!(harness.commandline_text(), "e src_b.rs");
 
-    // Exit insert mode
-    harness
-        .execute_action(Action::EnterMode(Mode::Normal))
-        .await
-        .unwrap();
+    harness.editor.test_complete_command_path_previous();
+    assert_eq!(harness.commandline_text(), "e src_a.rs");
+    drop(_guard);
+    fs::remove_dir_all(root).unwrap();
 }
 
-#[tokio::test]
-async fn visual_change_replaces_selection_and_undoes_as_one_transaction() {
-    let clipboard_text = Arc::new(Mutex::new(None));
-    let buffer = Buffer::new(None, "alpha beta".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness
-        .editor
-        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
-            clipboard_text.clone(),
-        )));
-
-    type_normal_keys(&mut harness, "vwc").await;
-
-    harness.assert_mode(Mode::Insert);
-    harness.assert_buffer_contents("eta");
-    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("alpha b"));
-
-    type_normal_keys(&mut harness, "REPLACED").await;
-    command_key(&mut harness, KeyCode::Esc).await;
+#[test]
+fn command_tab_sorts_directories_before_files() {
+    let (root, _guard) = command_completion_temp_dir("directories-first");
+    fs::create_dir(root.join("app")).unwrap();
+    fs::write(root.join("alpha.txt"), "").unwrap();
+    let mut harness = EditorHarness::with_content("");
+    harness.set_commandline(Mode::Command, "e a");
 
-    harness.assert_mode(Mode::Normal);
-    harness.assert_buffer_contents("REPLACEDeta");
+    harness.editor.test_complete_command_path_next();
 
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("alpha beta");
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents("REPLACEDeta");
+    assert_eq!(harness.commandline_text(), "e app/");
+    drop(_guard);
+    fs::remove_dir_all(root).unwrap();
 }
 
-#[tokio::test]
-async fn visual_line_change_leaves_one_replacement_line() {
-    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+#[test]
+fn command_tab_completes_file_arguments_for_split_vsplit_and_write() {
+    let (root, _guard) = command_completion_temp_dir("file-commands");
+    fs::create_dir(root.join("target")).unwrap();
 
-    type_normal_keys(&mut harness, "Vjc").await;
+    for command in [
+        "sp ta",
+        "vs ta",
+        "w ta",
+        "write ta",
+        "split ta",
+        "vsplit ta",
+    ] {
+        let mut harness = EditorHarness::with_content("");
+        harness.set_commandline(Mode::Command, command);
 
-    harness.assert_mode(Mode::Insert);
-    harness.assert_buffer_contents("\nthree");
-    type_normal_keys(&mut harness, "X").await;
-    command_key(&mut harness, KeyCode::Esc).await;
+        harness.editor.test_complete_command_path_next();
 
-    harness.assert_buffer_contents("X\nthree");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("one\ntwo\nthree");
+        let command_name = command.split_once(' ').unwrap().0;
+        assert_eq!(
+            harness.commandline_text(),
+            format!("{command_name} target/")
+        );
+    }
+    drop(_guard);
+    fs::remove_dir_all(root).unwrap();
 }
 
-#[tokio::test]
-async fn visual_block_change_replaces_each_selected_row() {
-    let buffer = Buffer::new(None, "abcd\nefgh\nijkl".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('v'),
-            KeyModifiers::CONTROL,
-        )))
-        .await
-        .unwrap();
-    type_normal_keys(&mut harness, "jjlc").await;
+#[test]
+fn command_tab_ignores_non_file_commands() {
+    let (root, _guard) = command_completion_temp_dir("non-file");
+    fs::create_dir(root.join("src")).unwrap();
+    let mut harness = EditorHarness::with_content("");
+    harness.set_commandline(Mode::Command, "q sr");
 
-    harness.assert_mode(Mode::Insert);
-    harness.assert_buffer_contents("cd\ngh\nkl");
-    type_normal_keys(&mut harness, "X").await;
-    command_key(&mut harness, KeyCode::Esc).await;
+    harness.editor.test_complete_command_path_next();
 
-    harness.assert_buffer_contents("Xcd\nXgh\nXkl");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("abcd\nefgh\nijkl");
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents("Xcd\nXgh\nXkl");
+    assert_eq!(harness.commandline_text(), "q sr");
+    drop(_guard);
+    fs::remove_dir_all(root).unwrap();
 }
 
-#[tokio::test]
-async fn visual_block_change_uses_buffer_rows_after_scrolling() {
-    let content = (0..40)
-        .map(|line| format!("abcd-{line:02}"))
-        .collect::<Vec<_>>()
-        .join("\n");
-    let buffer = Buffer::new(None, content.clone());
-    let mut harness = EditorHarness::with_config_and_size(buffer, default_key_config(), 80, 10);
-    harness
-        .execute_action(Action::SetCursor(0, 30))
-        .await
-        .unwrap();
+#[test]
+fn command_tab_completes_syntax_names_and_aliases() {
+    for (command, expected) in [
+        ("syntax ru", "syntax rust"),
+        ("syn ym", "syn yaml"),
+        ("ft rs", "ft rust"),
+        ("syntax ", "syntax auto"),
+    ] {
+        let mut harness = EditorHarness::with_content("");
+        harness.set_commandline(Mode::Command, command);
 
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('v'),
-            KeyModifiers::CONTROL,
-        )))
-        .await
-        .unwrap();
-    type_normal_keys(&mut harness, "jlcX").await;
-    command_key(&mut harness, KeyCode::Esc).await;
+        harness.editor.test_complete_command_path_next();
 
-    assert_eq!(
-        harness.line_contents(30).unwrap().trim_end_matches('\n'),
-        "Xcd-30"
-    );
-    assert_eq!(
-        harness.line_contents(31).unwrap().trim_end_matches('\n'),
-        "Xcd-31"
-    );
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents(&content);
+        assert_eq!(harness.commandline_text(), expected);
+    }
 }
 
-#[tokio::test]
-async fn visual_change_inserts_after_multicodepoint_graphemes() {
-    let family = "👨‍👩‍👧‍👦";
-    let buffer = Buffer::new(None, format!("{family} alpha beta"));
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+#[test]
+fn command_tab_does_not_complete_a_second_syntax_argument() {
+    let mut harness = EditorHarness::with_content("");
+    harness.set_commandline(Mode::Command, "syntax rust extra");
 
-    type_normal_keys(&mut harness, "ll").await;
-    harness.assert_cursor_at(2, 0);
-    type_normal_keys(&mut harness, "vwcX").await;
-    command_key(&mut harness, KeyCode::Esc).await;
+    harness.editor.test_complete_command_path_next();
 
-    harness.assert_buffer_contents(&format!("{family} Xeta"));
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents(&format!("{family} alpha beta"));
+    assert_eq!(harness.commandline_text(), "syntax rust extra");
 }
 
 #[tokio::test]
-async fn test_delete_inner_word_key_sequence() {
-    let mut harness = EditorHarness::with_content("alpha beta gamma");
-    harness
-        .execute_action(Action::MoveToNextWord)
-        .await
-        .unwrap();
+async fn command_tab_key_event_completes_file_argument() {
+    let root = env::temp_dir().join(format!(
+        "red-command-completion-event-{}",
+        uuid::Uuid::new_v4()
+    ));
+    fs::create_dir_all(root.join("target")).unwrap();
+    let mut harness = EditorHarness::with_content("");
+    harness.set_commandline(Mode::Command, &format!("e {}/ta", root.display()));
 
-    type_normal_keys(&mut harness, "diw").await;
+    command_key(&mut harness, KeyCode::Tab).await;
 
-    harness.assert_buffer_contents("alpha  gamma");
-    harness.assert_cursor_at(6, 0);
+    assert_eq!(
+        harness.commandline_text(),
+        format!("e {}/target/", root.display())
+    );
+    fs::remove_dir_all(root).unwrap();
 }
 
 #[tokio::test]
-async fn test_delete_inner_word_excludes_macro_bang_from_identifier() {
-    let mut harness = EditorHarness::with_content("println!(\"hi\");");
+async fn command_tab_completes_builtin_names_and_cycles_matches() {
+    let mut harness = EditorHarness::with_content("");
+    harness.set_commandline(Mode::Command, "wr");
 
-    type_normal_keys(&mut harness, "diw").await;
+    command_key(&mut harness, KeyCode::Tab).await;
+    assert_eq!(harness.commandline_text(), "wrap");
 
-    harness.assert_buffer_contents("!(\"hi\");");
-    harness.assert_cursor_at(0, 0);
+    command_key(&mut harness, KeyCode::Tab).await;
+    assert_eq!(harness.commandline_text(), "write");
+
+    command_key(&mut harness, KeyCode::BackTab).await;
+    assert_eq!(harness.commandline_text(), "wrap");
 }
 
 #[tokio::test]
-async fn test_visual_inner_word_excludes_macro_bang_from_identifier() {
-    let mut config = Config::default();
-    config.keys.normal.insert(
-        "v".to_string(),
-        KeyAction::Single(Action::EnterMode(Mode::Visual)),
-    );
-    config.keys.visual.insert(
-        "x".to_string(),
-        KeyAction::Multiple(vec![Action::Delete, Action::EnterMode(Mode::Normal)]),
-    );
-    let buffer = Buffer::new(None, "println!(\"hi\");".to_string());
-    let mut harness = EditorHarness::with_config(buffer, config);
-
-    type_normal_keys(&mut harness, "viwx").await;
-
-    harness.assert_buffer_contents("!(\"hi\");");
-    harness.assert_cursor_at(0, 0);
+async fn command_tab_completes_arguments_without_executing_them() {
+    let mut harness = EditorHarness::with_content("");
+    harness.set_commandline(Mode::Command, "Copilot sign");
+    command_key(&mut harness, KeyCode::Tab).await;
+    assert_eq!(harness.commandline_text(), "Copilot signin");
+    command_key(&mut harness, KeyCode::Tab).await;
+    assert_eq!(harness.commandline_text(), "Copilot signout");
+    command_key(&mut harness, KeyCode::BackTab).await;
+    assert_eq!(harness.commandline_text(), "Copilot signin");
+    command_key(&mut harness, KeyCode::Char('x')).await;
+    command_key(&mut harness, KeyCode::Tab).await;
+    assert_eq!(harness.commandline_text(), "Copilot signinx");
+    assert_eq!(harness.last_error(), None);
 }
 
 #[tokio::test]
-async fn test_delete_around_word_key_sequence() {
-    let mut harness = EditorHarness::with_content("alpha beta gamma");
-    harness
-        .execute_action(Action::MoveToNextWord)
-        .await
-        .unwrap();
+async fn command_tab_completion_remains_case_sensitive() {
+    let mut harness = EditorHarness::with_content("");
+    harness.set_commandline(Mode::Command, "Wr");
 
-    type_normal_keys(&mut harness, "daw").await;
+    command_key(&mut harness, KeyCode::Tab).await;
 
-    harness.assert_buffer_contents("alpha gamma");
-    harness.assert_cursor_at(6, 0);
+    assert_eq!(harness.commandline_text(), "Wr");
 }
 
 #[tokio::test]
-async fn test_change_inner_word_key_sequence() {
-    let mut harness = EditorHarness::with_content("alpha beta gamma");
+async fn wrap_commands_toggle_line_wrapping() {
+    let mut harness = EditorHarness::with_content("short");
+    assert!(harness.wrap());
+
     harness
-        .execute_action(Action::MoveToNextWord)
+        .execute_action(Action::Command("nowrap".to_string()))
         .await
         .unwrap();
+    assert!(!harness.wrap());
 
-    type_normal_keys(&mut harness, "ciw").await;
-
-    harness.assert_mode(Mode::Insert);
-    harness.type_text("BETA").await.unwrap();
-    harness.assert_buffer_contents("alpha BETA gamma");
+    harness
+        .execute_action(Action::Command("wrap".to_string()))
+        .await
+        .unwrap();
+    assert!(harness.wrap());
 }
 
 #[tokio::test]
-async fn test_delete_inner_and_around_nested_parens() {
-    let mut harness = EditorHarness::with_content("foo(bar(baz), qux)");
-    for _ in 0..8 {
-        harness.execute_action(Action::MoveRight).await.unwrap();
-    }
+async fn vim_parity_gw_toggles_wrapping_without_overriding_big_word_motion() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "foo.bar baz".to_string()),
+        default_key_config(),
+    );
+    let initial_wrap = harness.wrap();
 
-    type_normal_keys(&mut harness, "di(").await;
-    harness.assert_buffer_contents("foo(bar(), qux)");
+    type_normal_keys(&mut harness, "gW").await;
 
-    let mut harness = EditorHarness::with_content("foo(bar(baz), qux)");
-    for _ in 0..8 {
-        harness.execute_action(Action::MoveRight).await.unwrap();
-    }
+    assert_eq!(harness.wrap(), !initial_wrap);
+    harness.assert_cursor_at(0, 0);
 
-    type_normal_keys(&mut harness, "da(").await;
-    harness.assert_buffer_contents("foo(bar, qux)");
+    type_normal_keys(&mut harness, "gW").await;
+
+    assert_eq!(harness.wrap(), initial_wrap);
+    harness.assert_cursor_at(0, 0);
 }
 
 #[tokio::test]
-async fn test_delete_inner_multiline_braces() {
-    let mut harness = EditorHarness::with_content("fn main() {\n    call(arg);\n}");
-    harness.execute_action(Action::MoveDown).await.unwrap();
+async fn syntax_commands_set_reset_and_disable_buffer_local_syntax() {
+    let buffer = Buffer::new(Some("notes.txt".to_string()), "fn main() {}".to_string());
+    let mut harness = EditorHarness::with_buffer(buffer);
+
     harness
-        .execute_action(Action::MoveToFirstLineChar)
+        .execute_action(Action::Command("ft RS".to_string()))
         .await
         .unwrap();
+    assert_eq!(
+        harness.editor.test_current_buffer().syntax_selection(),
+        &SyntaxSelection::Language("rust".to_string())
+    );
+    assert_eq!(harness.last_error(), Some("syntax: rust"));
+    assert!(!harness.is_dirty());
 
-    type_normal_keys(&mut harness, "di{").await;
+    harness
+        .execute_action(Action::Command("syn off".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        harness.editor.test_current_buffer().syntax_selection(),
+        &SyntaxSelection::Off
+    );
+    assert_eq!(harness.last_error(), Some("syntax: off"));
 
-    harness.assert_buffer_contents("fn main() {}");
-    harness.assert_cursor_at(11, 0);
-}
+    harness
+        .execute_action(Action::Command("sy rust".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        harness.editor.test_current_buffer().syntax_selection(),
+        &SyntaxSelection::Language("rust".to_string())
+    );
 
-#[tokio::test]
-async fn test_delete_text_object_aliases() {
-    let mut harness = EditorHarness::with_content("items[alpha]");
-    for _ in 0..7 {
-        harness.execute_action(Action::MoveRight).await.unwrap();
-    }
-    type_normal_keys(&mut harness, "di]").await;
-    harness.assert_buffer_contents("items[]");
+    harness
+        .execute_action(Action::Command("syntax auto".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        harness.editor.test_current_buffer().syntax_selection(),
+        &SyntaxSelection::Auto
+    );
+    assert_eq!(harness.last_error(), Some("syntax: auto"));
+}
 
-    let mut harness = EditorHarness::with_content("block{alpha}");
-    for _ in 0..7 {
-        harness.execute_action(Action::MoveRight).await.unwrap();
-    }
-    type_normal_keys(&mut harness, "diB").await;
-    harness.assert_buffer_contents("block{}");
+#[tokio::test]
+async fn invalid_syntax_commands_leave_the_existing_selection_unchanged() {
+    let buffer = Buffer::new(Some("notes.txt".to_string()), "fn main() {}".to_string());
+    let mut harness = EditorHarness::with_buffer(buffer);
+    harness
+        .execute_action(Action::Command("syntax rust".to_string()))
+        .await
+        .unwrap();
 
-    let mut harness = EditorHarness::with_content("Option<alpha>");
-    for _ in 0..8 {
-        harness.execute_action(Action::MoveRight).await.unwrap();
-    }
-    type_normal_keys(&mut harness, "di>").await;
-    harness.assert_buffer_contents("Option<>");
+    harness
+        .execute_action(Action::Command("syntax madeup".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        harness.editor.test_current_buffer().syntax_selection(),
+        &SyntaxSelection::Language("rust".to_string())
+    );
+    assert_eq!(
+        harness.last_error(),
+        Some("unknown syntax \"madeup\" (try :syntax)")
+    );
 
-    let mut harness = EditorHarness::with_content("let c = 'x';");
-    for _ in 0..9 {
-        harness.execute_action(Action::MoveRight).await.unwrap();
-    }
-    type_normal_keys(&mut harness, "di'").await;
-    harness.assert_buffer_contents("let c = '';");
+    harness
+        .execute_action(Action::Command("syntax rust yaml".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        harness.editor.test_current_buffer().syntax_selection(),
+        &SyntaxSelection::Language("rust".to_string())
+    );
+    assert_eq!(
+        harness.last_error(),
+        Some("usage: syntax [language|auto|off]")
+    );
+    assert!(!harness.is_dirty());
+}
 
-    let mut harness = EditorHarness::with_content("cmd `alpha`");
-    for _ in 0..6 {
-        harness.execute_action(Action::MoveRight).await.unwrap();
-    }
-    type_normal_keys(&mut harness, "di`").await;
-    harness.assert_buffer_contents("cmd ``");
+#[tokio::test]
+async fn submitted_commands_are_persisted_to_preferences() {
+    let dir = std::env::temp_dir().join(format!("red-command-history-{}", uuid::Uuid::new_v4()));
+    let path = dir.join("preferences.json");
+    let lsp = Box::new(MockLsp) as Box<dyn LspClient>;
+    let config = Config::default();
+    let buffer = Buffer::new(None, String::new());
+    let mut editor = Editor::test_with_size_and_preferences(
+        lsp,
+        80,
+        24,
+        config,
+        Theme::default(),
+        vec![buffer],
+        PreferencesStore::load(&path),
+    )
+    .unwrap();
+    editor.test_disable_terminal_output();
+
+    editor
+        .test_execute_production_action(Action::Command("persist-me".to_string()))
+        .await
+        .unwrap();
+
+    let store = PreferencesStore::load(&path);
+    assert_eq!(store.command_history(), ["persist-me"]);
+    fs::remove_dir_all(dir).ok();
 }
 
 #[tokio::test]
-async fn test_q_text_object_alias_selects_double_quotes() {
-    let mut config = Config::default();
-    config.keys.normal.insert(
-        "v".to_string(),
-        KeyAction::Single(Action::EnterMode(Mode::Visual)),
-    );
-    config.keys.visual.insert(
-        "x".to_string(),
-        KeyAction::Multiple(vec![Action::Delete, Action::EnterMode(Mode::Normal)]),
-    );
+async fn test_insert_mode() {
+    let mut harness = EditorHarness::with_content("Hello World");
 
-    let buffer = Buffer::new(None, "let s = \"hello\";".to_string());
-    let mut harness = EditorHarness::with_config(buffer, config);
-    for _ in 0..10 {
-        harness.execute_action(Action::MoveRight).await.unwrap();
+    // Debug: Check initial cursor position and buffer state
+    println!("Initial cursor position: {:?}", harness.cursor_position());
+    println!("Number of lines: {}", harness.line_count());
+    if let Some(line) = harness.line_contents(0) {
+        println!("Line 0 content: {:?}", line);
     }
 
-    type_normal_keys(&mut harness, "viqx").await;
+    // Enter insert mode with 'i'
+    harness
+        .execute_action(Action::EnterMode(Mode::Insert))
+        .await
+        .unwrap();
+    harness.assert_mode(Mode::Insert);
 
-    harness.assert_buffer_contents("let s = \"\";");
+    // Debug: Check cursor position after entering insert mode
+    println!(
+        "Cursor position after entering insert mode: {:?}",
+        harness.cursor_position()
+    );
 
-    let mut harness = EditorHarness::with_content("let s = \"hello\";");
-    for _ in 0..10 {
-        harness.execute_action(Action::MoveRight).await.unwrap();
-    }
+    // Type some text
+    harness.type_text("Hi ").await.unwrap();
 
-    type_normal_keys(&mut harness, "diq").await;
+    // Debug: Check actual buffer contents
+    let contents = harness.buffer_contents();
+    println!("Actual buffer contents: {:?}", contents);
+    println!("Buffer length: {}", contents.len());
+    println!("Ends with newline: {}", contents.ends_with('\n'));
 
-    harness.assert_buffer_contents("let s = \"\";");
+    harness.assert_buffer_contents("Hi Hello World");
+
+    // Exit insert mode (ESC)
+    harness
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
+        .unwrap();
+    harness.assert_mode(Mode::Normal);
 }
 
 #[tokio::test]
-async fn test_delete_inner_and_around_quotes() {
-    let mut harness = EditorHarness::with_content("let s = \"hello world\";");
-    for _ in 0..10 {
+async fn test_append_mode() {
+    let mut harness = EditorHarness::with_content("Hello World");
+
+    // Move cursor to 'o' in 'Hello' (position 4)
+    for _ in 0..4 {
         harness.execute_action(Action::MoveRight).await.unwrap();
     }
 
-    type_normal_keys(&mut harness, "di\"").await;
-    harness.assert_buffer_contents("let s = \"\";");
+    // Enter append mode with 'a' - should insert after current character
+    harness.execute_action(Action::MoveRight).await.unwrap();
+    harness
+        .execute_action(Action::EnterMode(Mode::Insert))
+        .await
+        .unwrap();
+    harness.assert_mode(Mode::Insert);
 
-    let mut harness = EditorHarness::with_content("let s = \"hello world\";");
-    for _ in 0..10 {
-        harness.execute_action(Action::MoveRight).await.unwrap();
-    }
+    // Type text
+    harness.type_text(" there").await.unwrap();
+    harness.assert_buffer_contents("Hello there World");
 
-    type_normal_keys(&mut harness, "da\"").await;
-    harness.assert_buffer_contents("let s = ;");
+    // Exit insert mode
+    harness
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
+        .unwrap();
 }
 
 #[tokio::test]
-async fn test_invalid_operator_motion_does_not_edit() {
-    let mut harness = EditorHarness::with_content("alpha beta");
+async fn test_open_line_below() {
+    let mut harness = EditorHarness::with_content("Line 1\nLine 2");
 
-    type_normal_keys(&mut harness, "diz").await;
+    // Open line below with 'o' - InsertLineBelowCursor
+    harness
+        .execute_action(Action::InsertLineBelowCursor)
+        .await
+        .unwrap();
+    harness.assert_mode(Mode::Insert);
 
-    harness.assert_buffer_contents("alpha beta");
-    harness.assert_mode(Mode::Normal);
-    assert_eq!(harness.last_error(), Some("invalid operator motion"));
+    // Should have created a new line and moved cursor there
+    harness.assert_cursor_at(0, 1);
+
+    // Type on the new line
+    harness.type_text("New line").await.unwrap();
+    harness.assert_buffer_contents("Line 1\nNew line\nLine 2");
+
+    // Exit insert mode
+    harness
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
+        .unwrap();
 }
 
 #[tokio::test]
-async fn operator_line_counts_delete_yank_and_change_as_one_edit() {
-    let buffer = Buffer::new(None, "one\ntwo\nthree\nfour".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "2dd").await;
-    harness.assert_buffer_contents("three\nfour");
-    type_normal_keys(&mut harness, "u").await;
-    harness.assert_buffer_contents("one\ntwo\nthree\nfour");
+async fn test_enter_on_opened_indented_blank_line_moves_generated_indentation() {
+    let mut harness = EditorHarness::with_content("fn name() {\n    let a = 1;\n}");
 
-    let buffer = Buffer::new(None, "one\ntwo\nthree\nfour".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "2yyGp").await;
-    harness.assert_buffer_contents("one\ntwo\nthree\nfour\none\ntwo");
+    harness.execute_action(Action::MoveDown).await.unwrap();
+    harness
+        .execute_action(Action::InsertLineBelowCursor)
+        .await
+        .unwrap();
+    harness.assert_cursor_at(4, 2);
 
-    let buffer = Buffer::new(None, "one\ntwo\nthree\nfour".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "d2d").await;
-    harness.assert_buffer_contents("three\nfour");
+    harness.execute_action(Action::InsertNewLine).await.unwrap();
 
-    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "2ccX").await;
-    command_key(&mut harness, KeyCode::Esc).await;
-    harness.assert_buffer_contents("X\nthree");
-    type_normal_keys(&mut harness, "u").await;
-    harness.assert_buffer_contents("one\ntwo\nthree");
+    harness.assert_cursor_at(4, 3);
+    harness.assert_buffer_contents("fn name() {\n    let a = 1;\n\n    \n}");
 }
 
 #[tokio::test]
-async fn operator_and_motion_counts_multiply_for_words_and_character_motions() {
-    for keys in ["2dw", "d2w"] {
-        let buffer = Buffer::new(None, "one two three four five".to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-        type_normal_keys(&mut harness, keys).await;
-        harness.assert_buffer_contents("three four five");
-    }
+async fn python_autoindent_requires_the_language_pack() {
+    let buffer = Buffer::new(
+        Some("sample.py".to_string()),
+        "def something(x):".to_string(),
+    );
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    let buffer = Buffer::new(None, "one two three four five six".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "2d2w").await;
-    harness.assert_buffer_contents("five six");
+    harness
+        .execute_action(Action::InsertLineBelowCursor)
+        .await
+        .unwrap();
 
-    for keys in ["2df.", "d2f."] {
-        let buffer = Buffer::new(None, "a.b.c.d".to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-        type_normal_keys(&mut harness, keys).await;
-        harness.assert_buffer_contents("c.d");
-    }
+    harness.assert_cursor_at(0, 1);
+    harness.assert_buffer_contents("def something(x):\n");
+}
 
-    let buffer = Buffer::new(None, "a.b.c.d".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "d2t.").await;
-    harness.assert_buffer_contents(".c.d");
+#[tokio::test]
+async fn python_enter_indents_a_suite_and_dedents_after_return() {
+    let mut harness = python_harness("");
+    harness
+        .execute_action(Action::EnterMode(Mode::Insert))
+        .await
+        .unwrap();
+    harness.type_text("def something(x):").await.unwrap();
+    harness.execute_action(Action::InsertNewLine).await.unwrap();
+    harness.assert_cursor_at(4, 1);
+    harness.type_text("return x").await.unwrap();
+    harness.execute_action(Action::InsertNewLine).await.unwrap();
 
-    let buffer = Buffer::new(None, "α β γ δ".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "c2wX").await;
-    command_key(&mut harness, KeyCode::Esc).await;
-    harness.assert_buffer_contents("X γ δ");
+    harness.assert_cursor_at(0, 2);
+    harness.assert_buffer_contents("def something(x):\n    return x\n");
+}
 
-    for (contents, keys, expected) in [
-        ("one two", "d2w", ""),
-        ("α β", "d2w", ""),
-        ("one x", "dw", "x"),
-    ] {
-        let buffer = Buffer::new(None, contents.to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-        type_normal_keys(&mut harness, keys).await;
-        harness.assert_buffer_contents(expected);
+#[tokio::test]
+async fn python_ignores_colons_in_comments_and_strings() {
+    for source in ["value = 1  # note:", "value = \"note:\""] {
+        let mut harness = python_harness(source);
+        harness
+            .execute_action(Action::EnterMode(Mode::Insert))
+            .await
+            .unwrap();
+        harness.execute_action(Action::MoveToLineEnd).await.unwrap();
+        harness.execute_action(Action::MoveRight).await.unwrap();
+        harness.execute_action(Action::InsertNewLine).await.unwrap();
+        harness.assert_cursor_at(0, 1);
     }
 }
 
 #[tokio::test]
-async fn counted_operator_survives_dot_and_macro_replay() {
-    let buffer = Buffer::new(None, "one two three\nfour five six".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "d2wj.").await;
-    harness.assert_buffer_contents("three\nsix");
+async fn python_continuations_align_and_use_hanging_indent() {
+    let mut aligned = python_harness("call(first,");
+    aligned
+        .execute_action(Action::InsertLineBelowCursor)
+        .await
+        .unwrap();
+    aligned.assert_cursor_at(5, 1);
+    aligned.type_text("second").await.unwrap();
+    aligned.assert_buffer_contents("call(first,\n     second");
 
-    let buffer = Buffer::new(None, "one two three\nfour five six".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "qad2wjq@a").await;
-    harness.assert_buffer_contents("three\nsix");
+    let mut hanging = python_harness("values = [");
+    hanging
+        .execute_action(Action::InsertLineBelowCursor)
+        .await
+        .unwrap();
+    hanging.assert_cursor_at(8, 1);
 }
 
 #[tokio::test]
-async fn zz_centers_an_interior_line_and_clamps_at_file_edges() {
-    let content = (0..40)
-        .map(|line| format!("line-{line:02}"))
-        .collect::<Vec<_>>()
-        .join("\n");
+async fn python_typing_else_and_except_reindents_the_current_line() {
+    let mut else_harness = python_harness("if ready:\n    work()\n    ");
+    else_harness
+        .execute_action(Action::MoveToBottom)
+        .await
+        .unwrap();
+    else_harness
+        .execute_action(Action::EnterMode(Mode::Insert))
+        .await
+        .unwrap();
+    else_harness
+        .execute_action(Action::MoveToLineEnd)
+        .await
+        .unwrap();
+    else_harness
+        .execute_action(Action::MoveRight)
+        .await
+        .unwrap();
+    else_harness.type_text("else:").await.unwrap();
+    else_harness.assert_buffer_contents("if ready:\n    work()\nelse:");
+    else_harness.assert_cursor_at(5, 2);
 
-    let buffer = Buffer::new(None, content.clone());
-    let mut harness = EditorHarness::with_config_and_size(buffer, default_key_config(), 80, 10);
-    type_normal_keys(&mut harness, "jzz").await;
-    assert_eq!(harness.viewport_top(), 0);
-    assert_eq!(harness.buffer_line(), 1);
+    let mut except_harness = python_harness("try:\n    work()\n    ");
+    except_harness
+        .execute_action(Action::MoveToBottom)
+        .await
+        .unwrap();
+    except_harness
+        .execute_action(Action::EnterMode(Mode::Insert))
+        .await
+        .unwrap();
+    except_harness
+        .execute_action(Action::MoveToLineEnd)
+        .await
+        .unwrap();
+    except_harness
+        .execute_action(Action::MoveRight)
+        .await
+        .unwrap();
+    except_harness.type_text("except:").await.unwrap();
+    except_harness.assert_buffer_contents("try:\n    work()\nexcept:");
+}
 
+#[tokio::test]
+async fn backspace_uses_soft_tab_stops_inside_leading_indentation() {
+    let mut harness = python_harness("values = [");
     harness
-        .execute_action(Action::SetCursor(0, 20))
+        .execute_action(Action::InsertLineBelowCursor)
         .await
         .unwrap();
-    type_normal_keys(&mut harness, "zz").await;
-    assert_eq!(harness.viewport_top(), 16);
-    assert_eq!(harness.buffer_line(), 20);
-    assert_eq!(harness.render_cursor_position().unwrap().1, 4);
+    harness.assert_cursor_at(8, 1);
 
     harness
-        .execute_action(Action::SetCursor(0, 39))
+        .execute_action(Action::DeletePreviousChar)
         .await
         .unwrap();
-    type_normal_keys(&mut harness, "zz").await;
-    assert_eq!(harness.viewport_top(), 32);
-    assert_eq!(harness.buffer_line(), 39);
-    assert_eq!(harness.render_cursor_position().unwrap().1, 7);
+    harness.assert_cursor_at(4, 1);
+    harness
+        .execute_action(Action::DeletePreviousChar)
+        .await
+        .unwrap();
+    harness.assert_cursor_at(0, 1);
+    harness.type_text("value").await.unwrap();
+    harness.assert_buffer_contents("values = [\nvalue");
 }
 
 #[tokio::test]
-async fn test_delete_till_forward_accepts_any_target_character() {
-    for (content, keys, expected) in [
-        ("alpha.beta", "dt.", ".beta"),
-        ("alpha beta", "dtb", "beta"),
-        ("alpha¶beta", "dt¶", "¶beta"),
-    ] {
-        let mut harness = EditorHarness::with_content(content);
-
-        type_normal_keys(&mut harness, keys).await;
+async fn tab_advances_to_the_next_soft_tab_stop() {
+    let mut harness = python_harness("  value");
+    harness
+        .execute_action(Action::EnterMode(Mode::Insert))
+        .await
+        .unwrap();
+    harness
+        .execute_action(Action::SetCursor(2, 0))
+        .await
+        .unwrap();
+    harness.execute_action(Action::InsertTab).await.unwrap();
 
-        harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(0, 0);
-    }
+    harness.assert_cursor_at(4, 0);
+    harness.assert_buffer_contents("    value");
 }
 
 #[tokio::test]
-async fn test_delete_till_adjacent_target_deletes_current_character() {
-    let mut harness = EditorHarness::with_content("a.alpha");
-
-    type_normal_keys(&mut harness, "dt.").await;
+async fn untouched_generated_indent_is_removed_on_escape_and_carried_across_enter() {
+    let mut escaped = python_harness("def something(x):");
+    escaped
+        .execute_action(Action::InsertLineBelowCursor)
+        .await
+        .unwrap();
+    escaped
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
+        .unwrap();
+    escaped.assert_buffer_contents("def something(x):\n");
 
-    harness.assert_buffer_contents(".alpha");
-    harness.assert_cursor_at(0, 0);
-    assert_eq!(harness.last_error(), None);
+    let mut carried = python_harness("def something(x):");
+    carried
+        .execute_action(Action::InsertLineBelowCursor)
+        .await
+        .unwrap();
+    carried.execute_action(Action::InsertNewLine).await.unwrap();
+    carried.type_text("value").await.unwrap();
+    carried.assert_buffer_contents("def something(x):\n\n    value");
 }
 
 #[tokio::test]
-async fn test_delete_till_missing_target_does_not_edit() {
-    let mut harness = EditorHarness::with_content("alpha beta");
-
-    type_normal_keys(&mut harness, "dt.").await;
+async fn python_open_above_and_forced_syntax_use_the_same_provider() {
+    let mut above = python_harness("def something(x):\n    value");
+    above.execute_action(Action::MoveDown).await.unwrap();
+    above
+        .execute_action(Action::InsertLineAtCursor)
+        .await
+        .unwrap();
+    above.assert_cursor_at(4, 1);
+    above.type_text("other").await.unwrap();
+    above.assert_buffer_contents("def something(x):\n    other\n    value");
 
-    harness.assert_buffer_contents("alpha beta");
-    harness.assert_cursor_at(0, 0);
-    assert_eq!(harness.last_error(), Some("character not found"));
+    let buffer = Buffer::new(
+        Some("notes.txt".to_string()),
+        "def something(x):".to_string(),
+    );
+    let mut forced = EditorHarness::with_config(buffer, python_pack_config());
+    forced
+        .execute_action(Action::SetSyntax("python".to_string()))
+        .await
+        .unwrap();
+    forced
+        .execute_action(Action::InsertLineBelowCursor)
+        .await
+        .unwrap();
+    forced.assert_cursor_at(4, 1);
 }
 
 #[tokio::test]
-async fn find_and_till_forward_move_to_the_requested_character() {
-    let mut harness = EditorHarness::with_content("alpha.beta.gamma");
-
-    type_normal_keys(&mut harness, "f.").await;
-    harness.assert_cursor_at(5, 0);
+async fn generated_indent_cleans_up_when_insert_mode_moves_to_another_line() {
+    let mut harness = python_harness("def something(x):\nnext");
+    harness
+        .execute_action(Action::InsertLineBelowCursor)
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("def something(x):\n    \nnext");
 
-    let mut harness = EditorHarness::with_content("alpha.beta.gamma");
-    type_normal_keys(&mut harness, "t.").await;
-    harness.assert_cursor_at(4, 0);
-}
-
-#[tokio::test]
-async fn counted_find_and_till_forward_use_the_nth_match() {
-    let mut harness = EditorHarness::with_content("alpha.beta.gamma");
-
-    type_normal_keys(&mut harness, "2f.").await;
-    harness.assert_cursor_at(10, 0);
-
-    let mut harness = EditorHarness::with_content("alpha.beta.gamma");
-    type_normal_keys(&mut harness, "2t.").await;
-    harness.assert_cursor_at(9, 0);
+    harness.execute_action(Action::MoveDown).await.unwrap();
+    harness.assert_buffer_contents("def something(x):\n\nnext");
 }
 
 #[tokio::test]
-async fn delete_and_change_accept_find_forward_suffixes() {
-    let mut harness = EditorHarness::with_content("alpha.beta");
-    type_normal_keys(&mut harness, "df.").await;
-    harness.assert_buffer_contents("beta");
+async fn python_autoindent_and_inserted_text_undo_as_one_change() {
+    let mut harness = python_harness("def something(x):\nnext");
+    harness
+        .execute_action(Action::InsertLineBelowCursor)
+        .await
+        .unwrap();
+    harness.type_text("value").await.unwrap();
+    harness
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("def something(x):\n    value\nnext");
 
-    let mut harness = EditorHarness::with_content("alpha.beta");
-    type_normal_keys(&mut harness, "cf.").await;
-    harness.assert_mode(Mode::Insert);
-    harness.type_text("X").await.unwrap();
-    harness.assert_buffer_contents("Xbeta");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("def something(x):\nnext");
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents("def something(x):\n    value\nnext");
 }
 
 #[tokio::test]
-async fn change_till_forward_keeps_the_target_character() {
-    let mut harness = EditorHarness::with_content("alpha.beta");
+async fn test_enter_on_existing_whitespace_only_line_preserves_indentation() {
+    let mut harness = EditorHarness::with_content("    \nnext");
 
-    type_normal_keys(&mut harness, "ct.").await;
-    harness.assert_mode(Mode::Insert);
-    harness.type_text("X").await.unwrap();
+    harness
+        .execute_action(Action::SetCursor(3, 0))
+        .await
+        .unwrap();
+    harness.execute_action(Action::InsertNewLine).await.unwrap();
 
-    harness.assert_buffer_contents("X.beta");
+    harness.assert_cursor_at(4, 1);
+    harness.assert_buffer_contents("   \n     \nnext");
 }
 
 #[tokio::test]
-async fn yank_accepts_find_and_till_forward_suffixes() {
-    let mut harness = EditorHarness::with_content("alpha.beta");
-    let clipboard_text = Arc::new(Mutex::new(None));
-    harness
-        .editor
-        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
-            clipboard_text.clone(),
-        )));
+async fn test_open_line_above() {
+    let mut harness = EditorHarness::with_content("Line 1\nLine 2");
 
-    type_normal_keys(&mut harness, "yf.").await;
-    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("alpha."));
-    harness.assert_buffer_contents("alpha.beta");
+    // Move to second line
+    harness.execute_action(Action::MoveDown).await.unwrap();
+    println!(
+        "After MoveDown - cursor at: {:?}",
+        harness.cursor_position()
+    );
 
-    let mut harness = EditorHarness::with_content("alpha.beta");
-    let clipboard_text = Arc::new(Mutex::new(None));
+    // Open line above with 'O' - InsertLineAtCursor
     harness
-        .editor
-        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
-            clipboard_text.clone(),
-        )));
+        .execute_action(Action::InsertLineAtCursor)
+        .await
+        .unwrap();
+    println!(
+        "After InsertLineAtCursor - cursor at: {:?}",
+        harness.cursor_position()
+    );
+    println!("Buffer contents: {:?}", harness.buffer_contents());
+    harness.assert_mode(Mode::Insert);
 
-    type_normal_keys(&mut harness, "yt.").await;
-    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("alpha"));
-    harness.assert_buffer_contents("alpha.beta");
-}
+    // Should have created a new line above and moved cursor there
+    harness.assert_cursor_at(0, 1);
 
-#[tokio::test]
-async fn visual_find_and_till_forward_extend_the_selection() {
-    let buffer = Buffer::new(None, "alpha.beta".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "vf.").await;
-    harness.assert_mode(Mode::Visual);
-    harness.assert_cursor_at(5, 0);
-    type_normal_keys(&mut harness, "x").await;
-    harness.assert_buffer_contents("beta");
+    // Type on the new line
+    harness.type_text("Middle line").await.unwrap();
+    harness.assert_buffer_contents("Line 1\nMiddle line\nLine 2");
 
-    let buffer = Buffer::new(None, "alpha.beta".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "vt.").await;
-    harness.assert_mode(Mode::Visual);
-    harness.assert_cursor_at(4, 0);
-    type_normal_keys(&mut harness, "x").await;
-    harness.assert_buffer_contents(".beta");
+    // Exit insert mode
+    harness
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
+        .unwrap();
 }
 
 #[tokio::test]
-async fn missing_find_forward_target_does_not_move_or_edit() {
-    let mut harness = EditorHarness::with_content("alpha beta");
+async fn test_delete_char() {
+    let mut harness = EditorHarness::with_content("Hello World");
 
-    type_normal_keys(&mut harness, "f.").await;
+    // Delete character under cursor with 'x'
+    harness
+        .execute_action(Action::DeleteCharAtCursorPos)
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("ello World");
 
-    harness.assert_buffer_contents("alpha beta");
-    harness.assert_cursor_at(0, 0);
-    assert_eq!(harness.last_error(), Some("character not found"));
+    // Move to space and delete
+    harness
+        .execute_action(Action::MoveToNextWord)
+        .await
+        .unwrap();
+    harness.execute_action(Action::MoveLeft).await.unwrap();
+    harness
+        .execute_action(Action::DeleteCharAtCursorPos)
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("elloWorld");
 }
 
 #[tokio::test]
-async fn test_delete_and_change_line_key_sequences() {
-    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
-    harness.execute_action(Action::MoveDown).await.unwrap();
-
-    type_normal_keys(&mut harness, "dd").await;
-
-    harness.assert_buffer_contents("one\nthree");
-    harness.assert_cursor_at(0, 1);
+async fn test_delete_line() {
+    let mut harness = EditorHarness::with_content("Line 1\nLine 2\nLine 3");
 
-    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
+    // Move to second line
     harness.execute_action(Action::MoveDown).await.unwrap();
 
-    type_normal_keys(&mut harness, "cc").await;
+    // Delete line with 'dd'
+    println!("Before delete: {:?}", harness.buffer_contents());
+    println!("Cursor at: {:?}", harness.cursor_position());
+    println!("Line under cursor: {:?}", harness.current_line());
+    harness
+        .execute_action(Action::DeleteCurrentLine)
+        .await
+        .unwrap();
+    println!("After delete: {:?}", harness.buffer_contents());
+    println!("Cursor at after: {:?}", harness.cursor_position());
+    println!("Line under cursor after: {:?}", harness.current_line());
+    harness.assert_buffer_contents("Line 1\nLine 3");
 
-    harness.assert_mode(Mode::Insert);
-    harness.type_text("changed").await.unwrap();
-    harness.assert_buffer_contents("one\nchanged\nthree");
+    // Cursor should be on what was line 3
+    harness.assert_cursor_at(0, 1);
 }
 
 #[tokio::test]
-async fn test_yank_line_key_sequence_pastes_linewise() {
-    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
-    harness.execute_action(Action::MoveDown).await.unwrap();
+async fn test_delete_to_end_of_line() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "Hello World Test".to_string()),
+        line_end_delete_key_config(),
+    );
 
-    type_normal_keys(&mut harness, "yy").await;
+    type_normal_keys(&mut harness, "wD").await;
 
-    harness.assert_buffer_contents("one\ntwo\nthree");
-    assert!(!harness.is_dirty());
-    harness.assert_cursor_at(0, 1);
+    harness.assert_buffer_contents("Hello ");
+}
 
-    harness.execute_action(Action::Paste).await.unwrap();
-    harness.assert_buffer_contents("one\ntwo\ntwo\nthree");
+#[tokio::test]
+async fn test_change_word() {
+    let mut harness = EditorHarness::with_content("Hello World Test");
 
-    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
-    harness.execute_action(Action::MoveDown).await.unwrap();
+    // Change word with 'cw' - delete word then enter insert mode
+    harness.execute_action(Action::DeleteWord).await.unwrap();
+    harness
+        .execute_action(Action::EnterMode(Mode::Insert))
+        .await
+        .unwrap();
+    harness.assert_mode(Mode::Insert);
 
-    type_normal_keys(&mut harness, "yy").await;
-    harness.execute_action(Action::MoveDown).await.unwrap();
-    harness.execute_action(Action::PasteBefore).await.unwrap();
+    // Type replacement
+    harness.type_text("Hi ").await.unwrap();
+    harness.assert_buffer_contents("Hi World Test");
 
-    harness.assert_buffer_contents("one\ntwo\ntwo\nthree");
+    // Exit insert mode
+    harness
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
+        .unwrap();
 }
 
 #[tokio::test]
-async fn yanking_default_register_writes_system_clipboard() {
-    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
+async fn visual_change_replaces_selection_and_undoes_as_one_transaction() {
     let clipboard_text = Arc::new(Mutex::new(None));
+    let buffer = Buffer::new(None, "alpha beta".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
     harness
         .editor
         .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
             clipboard_text.clone(),
         )));
-    harness.execute_action(Action::MoveDown).await.unwrap();
 
-    type_normal_keys(&mut harness, "yy").await;
+    type_normal_keys(&mut harness, "vwc").await;
 
-    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("two\n"));
-}
+    harness.assert_mode(Mode::Insert);
+    harness.assert_buffer_contents("eta");
+    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("alpha b"));
 
-#[tokio::test]
-async fn deleting_default_register_writes_system_clipboard() {
-    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
-    let clipboard_text = Arc::new(Mutex::new(None));
-    harness
-        .editor
-        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
-            clipboard_text.clone(),
-        )));
-    harness.execute_action(Action::MoveDown).await.unwrap();
+    type_normal_keys(&mut harness, "REPLACED").await;
+    command_key(&mut harness, KeyCode::Esc).await;
 
-    harness
-        .execute_action(Action::DeleteCurrentLine)
-        .await
-        .unwrap();
+    harness.assert_mode(Mode::Normal);
+    harness.assert_buffer_contents("REPLACEDeta");
 
-    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("two\n"));
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("alpha beta");
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents("REPLACEDeta");
 }
 
 #[tokio::test]
-async fn paste_reads_external_system_clipboard_text() {
-    let mut harness = EditorHarness::with_content("abc");
-    harness
-        .editor
-        .test_set_clipboard(Box::new(MemoryClipboardProvider::with_text("system")));
-
-    harness.execute_action(Action::PasteBefore).await.unwrap();
-
-    harness.assert_buffer_contents("systemabc");
-}
-
-#[tokio::test]
-async fn pending_key_sequences_use_waiting_cursor_state() {
-    let mut config = Config::default();
-    config.keys.normal.insert(
-        "g".to_string(),
-        KeyAction::Nested(
-            [("g".to_string(), KeyAction::Single(Action::MoveToTop))]
-                .into_iter()
-                .collect(),
-        ),
-    );
-    config
-        .keys
-        .normal
-        .insert("j".to_string(), KeyAction::Single(Action::MoveDown));
+async fn visual_line_change_leaves_one_replacement_line() {
     let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
-    let mut harness = EditorHarness::with_config(buffer, config);
-
-    type_normal_keys(&mut harness, "g").await;
-    assert!(harness.is_waiting_for_key_sequence());
-
-    type_normal_keys(&mut harness, "g").await;
-    assert!(!harness.is_waiting_for_key_sequence());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    type_normal_keys(&mut harness, "d").await;
-    assert!(harness.is_waiting_for_key_sequence());
+    type_normal_keys(&mut harness, "Vjc").await;
 
-    type_normal_keys(&mut harness, "d").await;
-    assert!(!harness.is_waiting_for_key_sequence());
+    harness.assert_mode(Mode::Insert);
+    harness.assert_buffer_contents("\nthree");
+    type_normal_keys(&mut harness, "X").await;
+    command_key(&mut harness, KeyCode::Esc).await;
 
-    type_normal_keys(&mut harness, "2").await;
-    assert!(harness.is_waiting_for_key_sequence());
+    harness.assert_buffer_contents("X\nthree");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("one\ntwo\nthree");
+}
 
-    type_normal_keys(&mut harness, "j").await;
-    assert!(!harness.is_waiting_for_key_sequence());
+#[tokio::test]
+async fn visual_block_change_replaces_each_selected_row() {
+    let buffer = Buffer::new(None, "abcd\nefgh\nijkl".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
     harness
-        .execute_action(Action::EnterMode(Mode::Visual))
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('v'),
+            KeyModifiers::CONTROL,
+        )))
         .await
         .unwrap();
-    type_normal_keys(&mut harness, "i").await;
-    assert!(harness.is_waiting_for_key_sequence());
-}
-
-#[tokio::test]
-async fn literal_space_key_starts_leader_sequence() {
-    let mut config = Config::default();
-    config.keys.normal.insert(
-        " ".to_string(),
-        KeyAction::Nested(
-            [("t".to_string(), KeyAction::Single(Action::MoveToBottom))]
-                .into_iter()
-                .collect(),
-        ),
-    );
-    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
-    let mut harness = EditorHarness::with_config(buffer, config);
-
-    type_normal_keys(&mut harness, " ").await;
-    assert!(harness.is_waiting_for_key_sequence());
-
-    type_normal_keys(&mut harness, "t").await;
-
-    assert!(!harness.is_waiting_for_key_sequence());
-    harness.assert_cursor_at(0, 2);
-}
-
-#[tokio::test]
-async fn named_space_key_still_starts_leader_sequence() {
-    let mut config = Config::default();
-    config.keys.normal.insert(
-        "Space".to_string(),
-        KeyAction::Nested(
-            [("t".to_string(), KeyAction::Single(Action::MoveToBottom))]
-                .into_iter()
-                .collect(),
-        ),
-    );
-    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
-    let mut harness = EditorHarness::with_config(buffer, config);
-
-    type_normal_keys(&mut harness, " ").await;
-    assert!(harness.is_waiting_for_key_sequence());
+    type_normal_keys(&mut harness, "jjlc").await;
 
-    type_normal_keys(&mut harness, "t").await;
+    harness.assert_mode(Mode::Insert);
+    harness.assert_buffer_contents("cd\ngh\nkl");
+    type_normal_keys(&mut harness, "X").await;
+    command_key(&mut harness, KeyCode::Esc).await;
 
-    assert!(!harness.is_waiting_for_key_sequence());
-    harness.assert_cursor_at(0, 2);
+    harness.assert_buffer_contents("Xcd\nXgh\nXkl");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("abcd\nefgh\nijkl");
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents("Xcd\nXgh\nXkl");
 }
 
 #[tokio::test]
-async fn ctrl_space_keeps_named_key_binding() {
-    let mut config = Config::default();
-    config.keys.insert.insert(
-        "Ctrl-Space".to_string(),
-        KeyAction::Single(Action::MoveToBottom),
-    );
-    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
-    let mut harness = EditorHarness::with_config(buffer, config);
-
+async fn visual_block_change_uses_buffer_rows_after_scrolling() {
+    let content = (0..40)
+        .map(|line| format!("abcd-{line:02}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let buffer = Buffer::new(None, content.clone());
+    let mut harness = EditorHarness::with_config_and_size(buffer, default_key_config(), 80, 10);
     harness
-        .execute_action(Action::EnterMode(Mode::Insert))
+        .execute_action(Action::SetCursor(0, 30))
         .await
         .unwrap();
+
     harness
         .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char(' '),
+            KeyCode::Char('v'),
             KeyModifiers::CONTROL,
         )))
         .await
         .unwrap();
+    type_normal_keys(&mut harness, "jlcX").await;
+    command_key(&mut harness, KeyCode::Esc).await;
 
-    harness.assert_cursor_at(0, 2);
+    assert_eq!(
+        harness.line_contents(30).unwrap().trim_end_matches('\n'),
+        "Xcd-30"
+    );
+    assert_eq!(
+        harness.line_contents(31).unwrap().trim_end_matches('\n'),
+        "Xcd-31"
+    );
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents(&content);
 }
 
 #[tokio::test]
-async fn test_change_line() {
-    let mut harness = EditorHarness::with_content("Line 1\nLine 2\nLine 3");
+async fn visual_change_inserts_after_multicodepoint_graphemes() {
+    let family = "👨‍👩‍👧‍👦";
+    let buffer = Buffer::new(None, format!("{family} alpha beta"));
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    // Move to second line
-    harness.execute_action(Action::MoveDown).await.unwrap();
+    type_normal_keys(&mut harness, "ll").await;
+    harness.assert_cursor_at(2, 0);
+    type_normal_keys(&mut harness, "vwcX").await;
+    command_key(&mut harness, KeyCode::Esc).await;
 
-    // Change line with 'cc' - delete line content and enter insert mode
-    harness
-        .execute_action(Action::MoveToLineStart)
-        .await
-        .unwrap();
-    let line_len = harness.current_line().unwrap().trim_end().len();
-    for _ in 0..line_len {
-        harness
-            .execute_action(Action::DeleteCharAtCursorPos)
-            .await
-            .unwrap();
-    }
+    harness.assert_buffer_contents(&format!("{family} Xeta"));
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents(&format!("{family} alpha beta"));
+}
+
+#[tokio::test]
+async fn test_delete_inner_word_key_sequence() {
+    let mut harness = EditorHarness::with_content("alpha beta gamma");
     harness
-        .execute_action(Action::EnterMode(Mode::Insert))
+        .execute_action(Action::MoveToNextWord)
         .await
         .unwrap();
-    harness.assert_mode(Mode::Insert);
 
-    // Type replacement
-    harness.type_text("Changed line").await.unwrap();
-    harness.assert_buffer_contents("Line 1\nChanged line\nLine 3");
+    type_normal_keys(&mut harness, "diw").await;
 
-    // Exit insert mode
-    harness
-        .execute_action(Action::EnterMode(Mode::Normal))
-        .await
-        .unwrap();
+    harness.assert_buffer_contents("alpha  gamma");
+    harness.assert_cursor_at(6, 0);
 }
 
 #[tokio::test]
-async fn test_replace_char() {
-    let buffer = Buffer::new(None, "Hello World".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+async fn test_delete_inner_word_excludes_macro_bang_from_identifier() {
+    let mut harness = EditorHarness::with_content("println!(\"hi\");");
 
-    type_normal_keys(&mut harness, "rJ").await;
+    type_normal_keys(&mut harness, "diw").await;
 
-    harness.assert_buffer_contents("Jello World");
-    harness.assert_mode(Mode::Normal);
+    harness.assert_buffer_contents("!(\"hi\");");
+    harness.assert_cursor_at(0, 0);
 }
 
 #[tokio::test]
-async fn replace_char_accepts_operator_and_character_motion_prefixes() {
-    for replacement in ['d', 'c', 'y', 'f', 't', 'F', 'T'] {
-        let buffer = Buffer::new(None, "abc".to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-
-        type_normal_keys(&mut harness, &format!("r{replacement}")).await;
+async fn test_visual_inner_word_excludes_macro_bang_from_identifier() {
+    let mut config = Config::default();
+    config.keys.normal.insert(
+        "v".to_string(),
+        KeyAction::Single(Action::EnterMode(Mode::Visual)),
+    );
+    config.keys.visual.insert(
+        "x".to_string(),
+        KeyAction::Multiple(vec![Action::Delete, Action::EnterMode(Mode::Normal)]),
+    );
+    let buffer = Buffer::new(None, "println!(\"hi\");".to_string());
+    let mut harness = EditorHarness::with_config(buffer, config);
 
-        harness.assert_buffer_contents(&format!("{replacement}bc"));
-        harness.assert_mode(Mode::Normal);
+    type_normal_keys(&mut harness, "viwx").await;
 
-        type_normal_keys(&mut harness, "x").await;
-        harness.assert_buffer_contents("bc");
-    }
+    harness.assert_buffer_contents("!(\"hi\");");
+    harness.assert_cursor_at(0, 0);
 }
 
 #[tokio::test]
-async fn visual_replace_accepts_operator_and_character_motion_prefixes() {
-    for replacement in ['d', 'c', 'y', 'f', 't', 'F', 'T'] {
-        let buffer = Buffer::new(None, "abc".to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
+async fn test_delete_around_word_key_sequence() {
+    let mut harness = EditorHarness::with_content("alpha beta gamma");
+    harness
+        .execute_action(Action::MoveToNextWord)
+        .await
+        .unwrap();
 
-        type_normal_keys(&mut harness, &format!("vlr{replacement}")).await;
+    type_normal_keys(&mut harness, "daw").await;
 
-        harness.assert_buffer_contents(&format!("{replacement}{replacement}c"));
-        harness.assert_mode(Mode::Normal);
-    }
+    harness.assert_buffer_contents("alpha gamma");
+    harness.assert_cursor_at(6, 0);
 }
 
 #[tokio::test]
-async fn test_insert_at_line_start() {
-    let mut harness = EditorHarness::with_content("    Hello World");
-
-    // Move cursor to middle
+async fn test_change_inner_word_key_sequence() {
+    let mut harness = EditorHarness::with_content("alpha beta gamma");
     harness
         .execute_action(Action::MoveToNextWord)
         .await
         .unwrap();
 
-    // Insert at start of line with 'I' - move to start and enter insert
-    harness
-        .execute_action(Action::MoveToLineStart)
-        .await
-        .unwrap();
-    harness
-        .execute_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
+    type_normal_keys(&mut harness, "ciw").await;
+
     harness.assert_mode(Mode::Insert);
-    harness.assert_cursor_at(0, 0);
+    harness.type_text("BETA").await.unwrap();
+    harness.assert_buffer_contents("alpha BETA gamma");
+}
 
-    // Type text
-    harness.type_text("Start: ").await.unwrap();
-    harness.assert_buffer_contents("Start:     Hello World");
+#[tokio::test]
+async fn test_delete_inner_and_around_nested_parens() {
+    let mut harness = EditorHarness::with_content("foo(bar(baz), qux)");
+    for _ in 0..8 {
+        harness.execute_action(Action::MoveRight).await.unwrap();
+    }
 
-    // Exit insert mode
-    harness
-        .execute_action(Action::EnterMode(Mode::Normal))
-        .await
-        .unwrap();
+    type_normal_keys(&mut harness, "di(").await;
+    harness.assert_buffer_contents("foo(bar(), qux)");
+
+    let mut harness = EditorHarness::with_content("foo(bar(baz), qux)");
+    for _ in 0..8 {
+        harness.execute_action(Action::MoveRight).await.unwrap();
+    }
+
+    type_normal_keys(&mut harness, "da(").await;
+    harness.assert_buffer_contents("foo(bar, qux)");
 }
 
 #[tokio::test]
-async fn test_insert_key_escape_without_insert_stays_on_original_character() {
-    let mut config = Config::default();
-    config.keys.normal.insert(
-        "i".to_string(),
-        KeyAction::Single(Action::EnterMode(Mode::Insert)),
-    );
-    config.keys.insert.insert(
-        "Esc".to_string(),
-        KeyAction::Single(Action::EnterMode(Mode::Normal)),
-    );
-    let buffer = Buffer::new(None, "abc".to_string());
-    let mut harness = EditorHarness::with_config(buffer, config);
-    harness.execute_action(Action::MoveRight).await.unwrap();
-    let start = harness.render_cursor_position().unwrap();
-
-    type_normal_keys(&mut harness, "i").await;
+async fn test_delete_inner_multiline_braces() {
+    let mut harness = EditorHarness::with_content("fn main() {\n    call(arg);\n}");
+    harness.execute_action(Action::MoveDown).await.unwrap();
     harness
-        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+        .execute_action(Action::MoveToFirstLineChar)
         .await
         .unwrap();
 
-    harness.assert_mode(Mode::Normal);
-    harness.assert_cursor_at(1, 0);
-    assert_eq!(harness.render_cursor_position(), Some(start));
+    type_normal_keys(&mut harness, "di{").await;
+
+    harness.assert_buffer_contents("fn main() {}");
+    harness.assert_cursor_at(11, 0);
 }
 
 #[tokio::test]
-async fn test_append_key_positions_cursor_after_current_character() {
-    let mut config = Config::default();
-    config.keys.normal.insert(
-        "a".to_string(),
-        KeyAction::Multiple(vec![Action::EnterMode(Mode::Insert), Action::MoveRight]),
-    );
-    config.keys.insert.insert(
-        "Esc".to_string(),
-        KeyAction::Single(Action::EnterMode(Mode::Normal)),
-    );
-    let buffer = Buffer::new(None, "abc".to_string());
-    let mut harness = EditorHarness::with_config(buffer, config);
-    let start = harness.render_cursor_position().unwrap();
+async fn test_delete_text_object_aliases() {
+    let mut harness = EditorHarness::with_content("items[alpha]");
+    for _ in 0..7 {
+        harness.execute_action(Action::MoveRight).await.unwrap();
+    }
+    type_normal_keys(&mut harness, "di]").await;
+    harness.assert_buffer_contents("items[]");
 
-    type_normal_keys(&mut harness, "a").await;
+    let mut harness = EditorHarness::with_content("block{alpha}");
+    for _ in 0..7 {
+        harness.execute_action(Action::MoveRight).await.unwrap();
+    }
+    type_normal_keys(&mut harness, "diB").await;
+    harness.assert_buffer_contents("block{}");
 
-    harness.assert_mode(Mode::Insert);
-    harness.assert_cursor_at(1, 0);
-    assert_eq!(
-        harness.render_cursor_position(),
-        Some((start.0 + 1, start.1))
-    );
+    let mut harness = EditorHarness::with_content("Option<alpha>");
+    for _ in 0..8 {
+        harness.execute_action(Action::MoveRight).await.unwrap();
+    }
+    type_normal_keys(&mut harness, "di>").await;
+    harness.assert_buffer_contents("Option<>");
 
-    harness.type_text("X").await.unwrap();
-    harness.assert_buffer_contents("aXbc");
+    let mut harness = EditorHarness::with_content("let c = 'x';");
+    for _ in 0..9 {
+        harness.execute_action(Action::MoveRight).await.unwrap();
+    }
+    type_normal_keys(&mut harness, "di'").await;
+    harness.assert_buffer_contents("let c = '';");
 
-    harness
-        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
-        .await
-        .unwrap();
-    harness.assert_mode(Mode::Normal);
-    harness.assert_cursor_at(1, 0);
+    let mut harness = EditorHarness::with_content("cmd `alpha`");
+    for _ in 0..6 {
+        harness.execute_action(Action::MoveRight).await.unwrap();
+    }
+    type_normal_keys(&mut harness, "di`").await;
+    harness.assert_buffer_contents("cmd ``");
 }
 
 #[tokio::test]
-async fn test_append_key_escape_without_insert_returns_to_original_character() {
+async fn test_q_text_object_alias_selects_double_quotes() {
     let mut config = Config::default();
     config.keys.normal.insert(
-        "a".to_string(),
-        KeyAction::Multiple(vec![Action::EnterMode(Mode::Insert), Action::MoveRight]),
+        "v".to_string(),
+        KeyAction::Single(Action::EnterMode(Mode::Visual)),
     );
-    config.keys.insert.insert(
-        "Esc".to_string(),
-        KeyAction::Single(Action::EnterMode(Mode::Normal)),
+    config.keys.visual.insert(
+        "x".to_string(),
+        KeyAction::Multiple(vec![Action::Delete, Action::EnterMode(Mode::Normal)]),
     );
-    let buffer = Buffer::new(None, "abc".to_string());
+
+    let buffer = Buffer::new(None, "let s = \"hello\";".to_string());
     let mut harness = EditorHarness::with_config(buffer, config);
-    let start = harness.render_cursor_position().unwrap();
+    for _ in 0..10 {
+        harness.execute_action(Action::MoveRight).await.unwrap();
+    }
 
-    type_normal_keys(&mut harness, "a").await;
-    harness
-        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
-        .await
-        .unwrap();
+    type_normal_keys(&mut harness, "viqx").await;
 
-    harness.assert_mode(Mode::Normal);
-    harness.assert_cursor_at(0, 0);
-    assert_eq!(harness.render_cursor_position(), Some(start));
+    harness.assert_buffer_contents("let s = \"\";");
+
+    let mut harness = EditorHarness::with_content("let s = \"hello\";");
+    for _ in 0..10 {
+        harness.execute_action(Action::MoveRight).await.unwrap();
+    }
+
+    type_normal_keys(&mut harness, "diq").await;
+
+    harness.assert_buffer_contents("let s = \"\";");
 }
 
 #[tokio::test]
-async fn test_append_line_key_positions_cursor_after_line_end() {
-    let mut config = Config::default();
-    config.keys.normal.insert(
-        "A".to_string(),
-        KeyAction::Multiple(vec![
-            Action::MoveToLineEnd,
-            Action::EnterMode(Mode::Insert),
-            Action::MoveRight,
-        ]),
-    );
-    config.keys.insert.insert(
-        "Esc".to_string(),
-        KeyAction::Single(Action::EnterMode(Mode::Normal)),
-    );
-    let buffer = Buffer::new(None, "abc".to_string());
-    let mut harness = EditorHarness::with_config(buffer, config);
-    let start = harness.render_cursor_position().unwrap();
-
-    type_normal_keys(&mut harness, "A").await;
+async fn test_delete_inner_and_around_quotes() {
+    let mut harness = EditorHarness::with_content("let s = \"hello world\";");
+    for _ in 0..10 {
+        harness.execute_action(Action::MoveRight).await.unwrap();
+    }
 
-    harness.assert_mode(Mode::Insert);
-    harness.assert_cursor_at(3, 0);
-    assert_eq!(
-        harness.render_cursor_position(),
-        Some((start.0 + 3, start.1))
-    );
+    type_normal_keys(&mut harness, "di\"").await;
+    harness.assert_buffer_contents("let s = \"\";");
 
-    harness.type_text("X").await.unwrap();
-    harness.assert_buffer_contents("abcX");
+    let mut harness = EditorHarness::with_content("let s = \"hello world\";");
+    for _ in 0..10 {
+        harness.execute_action(Action::MoveRight).await.unwrap();
+    }
 
-    harness
-        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
-        .await
-        .unwrap();
-    harness.assert_mode(Mode::Normal);
-    harness.assert_cursor_at(3, 0);
+    type_normal_keys(&mut harness, "da\"").await;
+    harness.assert_buffer_contents("let s = ;");
 }
 
 #[tokio::test]
-async fn test_append_line_key_escape_without_insert_returns_to_last_character() {
-    let mut config = Config::default();
-    config.keys.normal.insert(
-        "A".to_string(),
-        KeyAction::Multiple(vec![
-            Action::MoveToLineEnd,
-            Action::EnterMode(Mode::Insert),
-            Action::MoveRight,
-        ]),
-    );
-    config.keys.insert.insert(
-        "Esc".to_string(),
-        KeyAction::Single(Action::EnterMode(Mode::Normal)),
-    );
-    let buffer = Buffer::new(None, "abc".to_string());
-    let mut harness = EditorHarness::with_config(buffer, config);
-    let start = harness.render_cursor_position().unwrap();
+async fn test_invalid_operator_motion_does_not_edit() {
+    let mut harness = EditorHarness::with_content("alpha beta");
 
-    type_normal_keys(&mut harness, "A").await;
-    harness
-        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
-        .await
-        .unwrap();
+    type_normal_keys(&mut harness, "diz").await;
 
+    harness.assert_buffer_contents("alpha beta");
     harness.assert_mode(Mode::Normal);
-    harness.assert_cursor_at(2, 0);
-    assert_eq!(
-        harness.render_cursor_position(),
-        Some((start.0 + 2, start.1))
-    );
+    assert_eq!(harness.last_error(), Some("invalid operator motion"));
 }
 
 #[tokio::test]
-async fn test_append_at_line_end() {
-    let mut harness = EditorHarness::with_content("Hello World");
+async fn operator_line_counts_delete_yank_and_change_as_one_edit() {
+    let buffer = Buffer::new(None, "one\ntwo\nthree\nfour".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "2dd").await;
+    harness.assert_buffer_contents("three\nfour");
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents("one\ntwo\nthree\nfour");
 
-    // Append at end of line with 'A' - move to end and enter insert
-    harness.execute_action(Action::MoveToLineEnd).await.unwrap();
-    harness
-        .execute_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
-    harness.execute_action(Action::MoveRight).await.unwrap();
-    harness.assert_mode(Mode::Insert);
+    let buffer = Buffer::new(None, "one\ntwo\nthree\nfour".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "2yyGp").await;
+    harness.assert_buffer_contents("one\ntwo\nthree\nfour\none\ntwo");
 
-    // Type text
-    harness.type_text(" Test").await.unwrap();
-    harness.assert_buffer_contents("Hello World Test");
+    let buffer = Buffer::new(None, "one\ntwo\nthree\nfour".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "d2d").await;
+    harness.assert_buffer_contents("three\nfour");
 
-    // Exit insert mode
-    harness
-        .execute_action(Action::EnterMode(Mode::Normal))
-        .await
-        .unwrap();
+    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "2ccX").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("X\nthree");
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents("one\ntwo\nthree");
 }
 
 #[tokio::test]
-async fn test_escape_from_insert_clamps_to_last_line_character() {
-    let mut harness = EditorHarness::with_content("Hello");
-
-    harness.execute_action(Action::MoveToLineEnd).await.unwrap();
-    harness
-        .execute_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
-    harness.execute_action(Action::MoveRight).await.unwrap();
-    harness.assert_cursor_at(5, 0);
+async fn operator_and_motion_counts_multiply_for_words_and_character_motions() {
+    for keys in ["2dw", "d2w"] {
+        let buffer = Buffer::new(None, "one two three four five".to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
+        type_normal_keys(&mut harness, keys).await;
+        harness.assert_buffer_contents("three four five");
+    }
 
-    harness
-        .execute_action(Action::EnterMode(Mode::Normal))
-        .await
-        .unwrap();
-    harness.assert_cursor_at(4, 0);
-}
+    let buffer = Buffer::new(None, "one two three four five six".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "2d2w").await;
+    harness.assert_buffer_contents("five six");
 
-#[tokio::test]
-async fn test_delete_word() {
-    let mut harness = EditorHarness::with_content("Hello World Test");
+    for keys in ["2df.", "d2f."] {
+        let buffer = Buffer::new(None, "a.b.c.d".to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
+        type_normal_keys(&mut harness, keys).await;
+        harness.assert_buffer_contents("c.d");
+    }
 
-    // Delete word with 'dw'
-    harness.execute_action(Action::DeleteWord).await.unwrap();
-    harness.assert_buffer_contents("World Test");
+    let buffer = Buffer::new(None, "a.b.c.d".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "d2t.").await;
+    harness.assert_buffer_contents(".c.d");
 
-    // Delete another word (including space)
-    harness.execute_action(Action::DeleteWord).await.unwrap();
-    harness.assert_buffer_contents("Test");
-}
+    let buffer = Buffer::new(None, "α β γ δ".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "c2wX").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("X γ δ");
 
-#[tokio::test]
-async fn delete_word_at_line_end_preserves_the_line_break() {
     for (contents, keys, expected) in [
-        ("alpha beta\nnext line", "wdw", "alpha \nnext line"),
-        (
-            "alpha beta   \n    next line",
-            "wdw",
-            "alpha \n    next line",
-        ),
-        ("alpha !!!\nnext line", "wdw", "alpha \nnext line"),
-        ("alpha βeta\n    δelta", "wdw", "alpha \n    δelta"),
-        ("alpha beta\r\nnext line", "wdw", "alpha \r\nnext line"),
-        ("alpha beta   \nnext line", "weldw", "alpha beta\nnext line"),
-        ("   \nnext line", "dw", "\nnext line"),
-        ("\n    next line", "dw", "    next line"),
-        ("\n\nnext line", "dw", "\nnext line"),
+        ("one two", "d2w", ""),
+        ("α β", "d2w", ""),
+        ("one x", "dw", "x"),
     ] {
         let buffer = Buffer::new(None, contents.to_string());
         let mut harness = EditorHarness::with_config(buffer, default_key_config());
-
         type_normal_keys(&mut harness, keys).await;
-
         harness.assert_buffer_contents(expected);
     }
 }
 
 #[tokio::test]
-async fn counted_delete_word_can_cross_a_line_break() {
-    for keys in ["wd2w", "w2dw"] {
-        let buffer = Buffer::new(None, "alpha beta\n    next line".to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-
-        type_normal_keys(&mut harness, keys).await;
+async fn counted_operator_survives_dot_and_macro_replay() {
+    let buffer = Buffer::new(None, "one two three\nfour five six".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "d2wj.").await;
+    harness.assert_buffer_contents("three\nsix");
 
-        harness.assert_buffer_contents("alpha line");
-    }
+    let buffer = Buffer::new(None, "one two three\nfour five six".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "qad2wjq@a").await;
+    harness.assert_buffer_contents("three\nsix");
 }
 
 #[tokio::test]
-async fn delete_word_action_preserves_line_breaks_and_deletes_through_eof() {
-    for (contents, expected) in [
-        ("alpha beta\nnext line", "alpha \nnext line"),
-        ("alpha beta   \n    next line", "alpha \n    next line"),
-        ("alpha beta\r\nnext line", "alpha \r\nnext line"),
-        ("alpha βeta\n    δelta", "alpha \n    δelta"),
-        ("alpha beta", "alpha "),
-        ("alpha βeta", "alpha "),
-    ] {
-        let mut harness = EditorHarness::with_content(contents);
-        harness
-            .execute_action(Action::MoveToNextWord)
-            .await
-            .unwrap();
+async fn zz_centers_an_interior_line_and_clamps_at_file_edges() {
+    let content = (0..40)
+        .map(|line| format!("line-{line:02}"))
+        .collect::<Vec<_>>()
+        .join("\n");
 
-        harness.execute_action(Action::DeleteWord).await.unwrap();
+    let buffer = Buffer::new(None, content.clone());
+    let mut harness = EditorHarness::with_config_and_size(buffer, default_key_config(), 80, 10);
+    type_normal_keys(&mut harness, "jzz").await;
+    assert_eq!(harness.viewport_top(), 0);
+    assert_eq!(harness.buffer_line(), 1);
 
-        harness.assert_buffer_contents(expected);
-    }
+    harness
+        .execute_action(Action::SetCursor(0, 20))
+        .await
+        .unwrap();
+    type_normal_keys(&mut harness, "zz").await;
+    assert_eq!(harness.viewport_top(), 16);
+    assert_eq!(harness.buffer_line(), 20);
+    assert_eq!(harness.render_cursor_position().unwrap().1, 4);
+
+    harness
+        .execute_action(Action::SetCursor(0, 39))
+        .await
+        .unwrap();
+    type_normal_keys(&mut harness, "zz").await;
+    assert_eq!(harness.viewport_top(), 32);
+    assert_eq!(harness.buffer_line(), 39);
+    assert_eq!(harness.render_cursor_position().unwrap().1, 7);
 }
 
 #[tokio::test]
-async fn test_join_lines() {
-    for (contents, keys, expected, cursor) in [
-        ("alpha\n    beta", "J", "alpha beta", (5, 0)),
-        ("alpha\n    ) tail", "J", "alpha) tail", (5, 0)),
-        ("alpha \n    beta", "J", "alpha beta", (6, 0)),
-        ("α\u{0301}\r\n    β", "J", "α\u{0301} β", (1, 0)),
-        (
-            "one\n  two\n    three\nfour",
-            "3J",
-            "one two three\nfour",
-            (7, 0),
-        ),
-        ("alpha \n    beta", "gJ", "alpha     beta", (6, 0)),
-        (
-            "one\n  two\n    three\nfour",
-            "VjjJ",
-            "one two three\nfour",
-            (7, 0),
-        ),
-        (
-            "one \n  two\n    three\nfour",
-            "VjjgJ",
-            "one   two    three\nfour",
-            (9, 0),
-        ),
-        (
-            "one \n  two\n    three\nfour",
-            "3gJ",
-            "one   two    three\nfour",
-            (9, 0),
-        ),
+async fn test_delete_till_forward_accepts_any_target_character() {
+    for (content, keys, expected) in [
+        ("alpha.beta", "dt.", ".beta"),
+        ("alpha beta", "dtb", "beta"),
+        ("alpha¶beta", "dt¶", "¶beta"),
     ] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
-            default_key_config(),
-        );
+        let mut harness = EditorHarness::with_content(content);
 
         type_normal_keys(&mut harness, keys).await;
 
         harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(cursor.0, cursor.1);
-        harness.assert_mode(Mode::Normal);
+        harness.assert_cursor_at(0, 0);
     }
 }
 
 #[tokio::test]
-async fn join_lines_is_one_undoable_repeatable_change() {
-    let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "one\n  two\nthree\n  four".to_string()),
-        default_key_config(),
-    );
+async fn test_delete_till_adjacent_target_deletes_current_character() {
+    let mut harness = EditorHarness::with_content("a.alpha");
 
-    type_normal_keys(&mut harness, "Jj.").await;
-    harness.assert_buffer_contents("one two\nthree four");
+    type_normal_keys(&mut harness, "dt.").await;
 
-    type_normal_keys(&mut harness, "u").await;
-    harness.assert_buffer_contents("one two\nthree\n  four");
-    type_normal_keys(&mut harness, "u").await;
-    harness.assert_buffer_contents("one\n  two\nthree\n  four");
+    harness.assert_buffer_contents(".alpha");
+    harness.assert_cursor_at(0, 0);
+    assert_eq!(harness.last_error(), None);
 }
 
 #[tokio::test]
-async fn join_lines_survives_macro_replay_and_eof() {
-    let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "one\n  two\nthree\n  four".to_string()),
-        default_key_config(),
-    );
+async fn test_delete_till_missing_target_does_not_edit() {
+    let mut harness = EditorHarness::with_content("alpha beta");
 
-    type_normal_keys(&mut harness, "qaJjq@a").await;
-    harness.assert_buffer_contents("one two\nthree four");
+    type_normal_keys(&mut harness, "dt.").await;
 
-    type_normal_keys(&mut harness, "GJ").await;
-    harness.assert_buffer_contents("one two\nthree four");
+    harness.assert_buffer_contents("alpha beta");
+    harness.assert_cursor_at(0, 0);
+    assert_eq!(harness.last_error(), Some("character not found"));
 }
 
 #[tokio::test]
-async fn join_ex_command_supports_count_and_bang() {
-    for (contents, command, expected) in [
-        ("one\n  two\nthree", "join", "one two\nthree"),
-        ("one\n  two\nthree\nfour", "j 3", "one two three\nfour"),
-        ("one \n  two\nthree", "join!", "one   two\nthree"),
-        ("one \n  two\nthree\nfour", "j! 3", "one   twothree\nfour"),
-    ] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
-            default_key_config(),
-        );
-
-        harness
-            .execute_action(Action::Command(command.to_string()))
-            .await
-            .unwrap();
+async fn find_and_till_forward_move_to_the_requested_character() {
+    let mut harness = EditorHarness::with_content("alpha.beta.gamma");
 
-        harness.assert_buffer_contents(expected);
-    }
-}
+    type_normal_keys(&mut harness, "f.").await;
+    harness.assert_cursor_at(5, 0);
 
-#[tokio::test]
-async fn join_ex_command_supports_numeric_and_visual_ranges() {
-    for (contents, command, expected) in [
-        ("one\ntwo\nthree\nfour", "2join", "one\ntwo three\nfour"),
-        (
-            "one\ntwo\n  three\nfour",
-            "2,3join!",
-            "one\ntwo  three\nfour",
-        ),
-        ("one\n  two\nthree", "%join", "one two three"),
-    ] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
-            default_key_config(),
-        );
+    let mut harness = EditorHarness::with_content("alpha.beta.gamma");
+    type_normal_keys(&mut harness, "t.").await;
+    harness.assert_cursor_at(4, 0);
+}
 
-        harness
-            .execute_action(Action::Command(command.to_string()))
-            .await
-            .unwrap();
+#[tokio::test]
+async fn counted_find_and_till_forward_use_the_nth_match() {
+    let mut harness = EditorHarness::with_content("alpha.beta.gamma");
 
-        harness.assert_buffer_contents(expected);
-    }
+    type_normal_keys(&mut harness, "2f.").await;
+    harness.assert_cursor_at(10, 0);
 
-    let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "one\n  two\nthree".to_string()),
-        default_key_config(),
-    );
-    type_normal_keys(&mut harness, "Vj:join").await;
-    command_key(&mut harness, KeyCode::Enter).await;
-    harness.assert_buffer_contents("one two\nthree");
+    let mut harness = EditorHarness::with_content("alpha.beta.gamma");
+    type_normal_keys(&mut harness, "2t.").await;
+    harness.assert_cursor_at(9, 0);
 }
 
 #[tokio::test]
-async fn unsupported_ranged_command_reports_a_specific_error() {
-    let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "one\ntwo".to_string()),
-        default_key_config(),
-    );
-    type_normal_keys(&mut harness, "Vj:w").await;
-    command_key(&mut harness, KeyCode::Enter).await;
+async fn delete_and_change_accept_find_forward_suffixes() {
+    let mut harness = EditorHarness::with_content("alpha.beta");
+    type_normal_keys(&mut harness, "df.").await;
+    harness.assert_buffer_contents("beta");
 
-    assert_eq!(
-        harness.last_error(),
-        Some("command \"w\" does not support a line range")
-    );
-    harness.assert_buffer_contents("one\ntwo");
+    let mut harness = EditorHarness::with_content("alpha.beta");
+    type_normal_keys(&mut harness, "cf.").await;
+    harness.assert_mode(Mode::Insert);
+    harness.type_text("X").await.unwrap();
+    harness.assert_buffer_contents("Xbeta");
 }
 
 #[tokio::test]
-async fn test_undo_redo() {
-    let mut harness = EditorHarness::with_content("Hello World");
-
-    // Make a change
-    harness
-        .execute_action(Action::DeleteCharAtCursorPos)
-        .await
-        .unwrap();
-    harness.assert_buffer_contents("ello World");
+async fn change_till_forward_keeps_the_target_character() {
+    let mut harness = EditorHarness::with_content("alpha.beta");
 
-    // Undo with 'u'
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("Hello World");
+    type_normal_keys(&mut harness, "ct.").await;
+    harness.assert_mode(Mode::Insert);
+    harness.type_text("X").await.unwrap();
 
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents("ello World");
+    harness.assert_buffer_contents("X.beta");
 }
 
 #[tokio::test]
-async fn undo_and_redo_boundaries_report_no_op() {
-    let mut harness = EditorHarness::with_content("Hello World");
+async fn yank_accepts_find_and_till_forward_suffixes() {
+    let mut harness = EditorHarness::with_content("alpha.beta");
+    let clipboard_text = Arc::new(Mutex::new(None));
+    harness
+        .editor
+        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
+            clipboard_text.clone(),
+        )));
 
-    harness.execute_action(Action::Undo).await.unwrap();
-    assert!(harness
-        .commandline_row()
-        .contains("already at oldest change"));
+    type_normal_keys(&mut harness, "yf.").await;
+    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("alpha."));
+    harness.assert_buffer_contents("alpha.beta");
 
-    harness.execute_action(Action::Redo).await.unwrap();
-    assert!(harness
-        .commandline_row()
-        .contains("already at newest change"));
+    let mut harness = EditorHarness::with_content("alpha.beta");
+    let clipboard_text = Arc::new(Mutex::new(None));
+    harness
+        .editor
+        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
+            clipboard_text.clone(),
+        )));
+
+    type_normal_keys(&mut harness, "yt.").await;
+    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("alpha"));
+    harness.assert_buffer_contents("alpha.beta");
 }
 
 #[tokio::test]
-async fn test_undo_multi_character_insert_session() {
-    let mut harness = EditorHarness::with_content("");
-
-    harness
-        .execute_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
-    harness.type_text("hello").await.unwrap();
-    harness
-        .execute_action(Action::EnterMode(Mode::Normal))
-        .await
-        .unwrap();
+async fn visual_find_and_till_forward_extend_the_selection() {
+    let buffer = Buffer::new(None, "alpha.beta".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "vf.").await;
+    harness.assert_mode(Mode::Visual);
+    harness.assert_cursor_at(5, 0);
+    type_normal_keys(&mut harness, "x").await;
+    harness.assert_buffer_contents("beta");
 
-    harness.assert_buffer_contents("hello\n");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("\n");
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents("hello\n");
+    let buffer = Buffer::new(None, "alpha.beta".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "vt.").await;
+    harness.assert_mode(Mode::Visual);
+    harness.assert_cursor_at(4, 0);
+    type_normal_keys(&mut harness, "x").await;
+    harness.assert_buffer_contents(".beta");
 }
 
 #[tokio::test]
-async fn test_undo_insert_backspace_session() {
-    let mut harness = EditorHarness::with_content("");
+async fn missing_find_forward_target_does_not_move_or_edit() {
+    let mut harness = EditorHarness::with_content("alpha beta");
 
-    harness
-        .execute_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
-    harness.type_text("abc").await.unwrap();
-    harness
-        .execute_action(Action::DeletePreviousChar)
-        .await
-        .unwrap();
-    harness
-        .execute_action(Action::EnterMode(Mode::Normal))
-        .await
-        .unwrap();
+    type_normal_keys(&mut harness, "f.").await;
 
-    harness.assert_buffer_contents("ab\n");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("\n");
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents("ab\n");
+    harness.assert_buffer_contents("alpha beta");
+    harness.assert_cursor_at(0, 0);
+    assert_eq!(harness.last_error(), Some("character not found"));
 }
 
 #[tokio::test]
-async fn test_backspace_at_line_start_joins_with_previous_line() {
-    let mut harness = EditorHarness::with_content("abc\ndef");
-
+async fn test_delete_and_change_line_key_sequences() {
+    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
     harness.execute_action(Action::MoveDown).await.unwrap();
-    harness
-        .execute_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
-    harness
-        .execute_action(Action::DeletePreviousChar)
-        .await
-        .unwrap();
 
-    harness.assert_buffer_contents("abcdef");
-    harness.assert_cursor_at(3, 0);
-}
+    type_normal_keys(&mut harness, "dd").await;
 
-#[tokio::test]
-async fn test_undo_delete_range_and_word() {
-    let mut harness = EditorHarness::with_content("hello world");
+    harness.assert_buffer_contents("one\nthree");
+    harness.assert_cursor_at(0, 1);
 
-    harness.execute_action(Action::DeleteWord).await.unwrap();
-    harness.assert_buffer_contents("world");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("hello world");
+    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
+    harness.execute_action(Action::MoveDown).await.unwrap();
 
-    harness
-        .execute_action(Action::DeleteRange(0, 0, 5, 0))
-        .await
-        .unwrap();
-    harness.assert_buffer_contents(" world");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("hello world");
+    type_normal_keys(&mut harness, "cc").await;
+
+    harness.assert_mode(Mode::Insert);
+    harness.type_text("changed").await.unwrap();
+    harness.assert_buffer_contents("one\nchanged\nthree");
 }
 
 #[tokio::test]
-async fn test_undo_delete_current_line() {
+async fn test_yank_line_key_sequence_pastes_linewise() {
     let mut harness = EditorHarness::with_content("one\ntwo\nthree");
-
     harness.execute_action(Action::MoveDown).await.unwrap();
-    harness
-        .execute_action(Action::DeleteCurrentLine)
-        .await
-        .unwrap();
-    harness.assert_buffer_contents("one\nthree");
-    harness.execute_action(Action::Undo).await.unwrap();
+
+    type_normal_keys(&mut harness, "yy").await;
+
     harness.assert_buffer_contents("one\ntwo\nthree");
+    assert!(!harness.is_dirty());
+    harness.assert_cursor_at(0, 1);
 
-    let mut harness = EditorHarness::with_content("single");
-    harness
-        .execute_action(Action::DeleteCurrentLine)
-        .await
-        .unwrap();
-    harness.assert_buffer_contents("");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("single");
-}
+    harness.execute_action(Action::Paste).await.unwrap();
+    harness.assert_buffer_contents("one\ntwo\ntwo\nthree");
 
-#[tokio::test]
-async fn test_delete_current_line_yanks_for_linewise_paste_before() {
     let mut harness = EditorHarness::with_content("one\ntwo\nthree");
-
     harness.execute_action(Action::MoveDown).await.unwrap();
-    harness
-        .execute_action(Action::DeleteCurrentLine)
-        .await
-        .unwrap();
-    harness.assert_buffer_contents("one\nthree");
 
-    harness
-        .execute_action(Action::MoveToLineStart)
-        .await
-        .unwrap();
+    type_normal_keys(&mut harness, "yy").await;
+    harness.execute_action(Action::MoveDown).await.unwrap();
     harness.execute_action(Action::PasteBefore).await.unwrap();
-    harness.assert_buffer_contents("one\ntwo\nthree");
+
+    harness.assert_buffer_contents("one\ntwo\ntwo\nthree");
 }
 
 #[tokio::test]
-async fn test_undo_multiline_insert_and_unicode() {
-    let mut harness = EditorHarness::with_content("");
-
-    harness
-        .execute_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
-    harness.type_text("a👋").await.unwrap();
-    harness.execute_action(Action::InsertNewLine).await.unwrap();
-    harness.type_text("é").await.unwrap();
+async fn yanking_default_register_writes_system_clipboard() {
+    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
+    let clipboard_text = Arc::new(Mutex::new(None));
     harness
-        .execute_action(Action::EnterMode(Mode::Normal))
-        .await
-        .unwrap();
+        .editor
+        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
+            clipboard_text.clone(),
+        )));
+    harness.execute_action(Action::MoveDown).await.unwrap();
 
-    harness.assert_buffer_contents("a👋\né\n");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("\n");
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents("a👋\né\n");
+    type_normal_keys(&mut harness, "yy").await;
+
+    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("two\n"));
 }
 
 #[tokio::test]
-async fn test_redo_stack_clears_after_new_edit() {
-    let mut harness = EditorHarness::with_content("abc");
-
+async fn deleting_default_register_writes_system_clipboard() {
+    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
+    let clipboard_text = Arc::new(Mutex::new(None));
     harness
-        .execute_action(Action::DeleteCharAtCursorPos)
-        .await
-        .unwrap();
-    harness.assert_buffer_contents("bc");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("abc");
+        .editor
+        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
+            clipboard_text.clone(),
+        )));
+    harness.execute_action(Action::MoveDown).await.unwrap();
+
     harness
-        .execute_action(Action::InsertCharAtCursorPos('z'))
+        .execute_action(Action::DeleteCurrentLine)
         .await
         .unwrap();
-    harness.assert_buffer_contents("zabc");
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents("zabc");
+
+    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("two\n"));
 }
 
 #[tokio::test]
-async fn undo_tree_preserves_and_traverses_sibling_branches() {
+async fn paste_reads_external_system_clipboard_text() {
     let mut harness = EditorHarness::with_content("abc");
     harness
-        .execute_action(Action::DeleteCharAtCursorPos)
-        .await
-        .unwrap();
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness
-        .execute_action(Action::InsertCharAtCursorPos('z'))
-        .await
-        .unwrap();
-    harness.execute_action(Action::Undo).await.unwrap();
+        .editor
+        .test_set_clipboard(Box::new(MemoryClipboardProvider::with_text("system")));
 
-    harness
-        .execute_action(Action::SelectPreviousUndoBranch)
-        .await
-        .unwrap();
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents("bc");
+    harness.execute_action(Action::PasteBefore).await.unwrap();
 
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness
-        .execute_action(Action::SelectNextUndoBranch)
-        .await
-        .unwrap();
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents("zabc");
+    harness.assert_buffer_contents("systemabc");
 }
 
 #[tokio::test]
-async fn selective_revert_applies_only_when_the_post_image_still_matches() {
-    let mut harness = EditorHarness::with_content("abc");
-    harness
-        .execute_action(Action::DeleteCharAtCursorPos)
-        .await
-        .unwrap();
-    let transaction_id = harness.editor.test_undo_tree()[0].transaction_id.clone();
-    harness.execute_action(Action::MoveToLineEnd).await.unwrap();
-    harness
-        .execute_action(Action::DeleteCharAtCursorPos)
-        .await
-        .unwrap();
-    harness.assert_buffer_contents("b");
+async fn pending_key_sequences_use_waiting_cursor_state() {
+    let mut config = Config::default();
+    config.keys.normal.insert(
+        "g".to_string(),
+        KeyAction::Nested(
+            [("g".to_string(), KeyAction::Single(Action::MoveToTop))]
+                .into_iter()
+                .collect(),
+        ),
+    );
+    config
+        .keys
+        .normal
+        .insert("j".to_string(), KeyAction::Single(Action::MoveDown));
+    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
+    let mut harness = EditorHarness::with_config(buffer, config);
 
-    harness
-        .execute_action(Action::RevertTransaction(transaction_id))
-        .await
-        .unwrap();
-    harness.assert_buffer_contents("ab");
-    assert!(harness.editor.test_undo_tree().len() >= 3);
+    type_normal_keys(&mut harness, "g").await;
+    assert!(harness.is_waiting_for_key_sequence());
+
+    type_normal_keys(&mut harness, "g").await;
+    assert!(!harness.is_waiting_for_key_sequence());
+
+    type_normal_keys(&mut harness, "d").await;
+    assert!(harness.is_waiting_for_key_sequence());
+
+    type_normal_keys(&mut harness, "d").await;
+    assert!(!harness.is_waiting_for_key_sequence());
+
+    type_normal_keys(&mut harness, "2").await;
+    assert!(harness.is_waiting_for_key_sequence());
+
+    type_normal_keys(&mut harness, "j").await;
+    assert!(!harness.is_waiting_for_key_sequence());
 
-    let mut harness = EditorHarness::with_content("abc");
     harness
-        .execute_action(Action::DeleteCharAtCursorPos)
+        .execute_action(Action::EnterMode(Mode::Visual))
         .await
         .unwrap();
-    let transaction_id = harness.editor.test_undo_tree()[0].transaction_id.clone();
+    type_normal_keys(&mut harness, "i").await;
+    assert!(harness.is_waiting_for_key_sequence());
+}
+
+#[tokio::test]
+async fn literal_space_key_starts_leader_sequence() {
+    let mut config = Config::default();
+    config.keys.normal.insert(
+        " ".to_string(),
+        KeyAction::Nested(
+            [("t".to_string(), KeyAction::Single(Action::MoveToBottom))]
+                .into_iter()
+                .collect(),
+        ),
+    );
+    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
+    let mut harness = EditorHarness::with_config(buffer, config);
+
+    type_normal_keys(&mut harness, " ").await;
+    assert!(harness.is_waiting_for_key_sequence());
+
+    type_normal_keys(&mut harness, "t").await;
+
+    assert!(!harness.is_waiting_for_key_sequence());
+    harness.assert_cursor_at(0, 2);
+}
+
+#[tokio::test]
+async fn named_space_key_still_starts_leader_sequence() {
+    let mut config = Config::default();
+    config.keys.normal.insert(
+        "Space".to_string(),
+        KeyAction::Nested(
+            [("t".to_string(), KeyAction::Single(Action::MoveToBottom))]
+                .into_iter()
+                .collect(),
+        ),
+    );
+    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
+    let mut harness = EditorHarness::with_config(buffer, config);
+
+    type_normal_keys(&mut harness, " ").await;
+    assert!(harness.is_waiting_for_key_sequence());
+
+    type_normal_keys(&mut harness, "t").await;
+
+    assert!(!harness.is_waiting_for_key_sequence());
+    harness.assert_cursor_at(0, 2);
+}
+
+#[tokio::test]
+async fn ctrl_space_keeps_named_key_binding() {
+    let mut config = Config::default();
+    config.keys.insert.insert(
+        "Ctrl-Space".to_string(),
+        KeyAction::Single(Action::MoveToBottom),
+    );
+    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
+    let mut harness = EditorHarness::with_config(buffer, config);
+
     harness
-        .execute_action(Action::InsertCharAtCursorPos('X'))
+        .execute_action(Action::EnterMode(Mode::Insert))
         .await
         .unwrap();
     harness
-        .execute_action(Action::RevertTransaction(transaction_id))
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char(' '),
+            KeyModifiers::CONTROL,
+        )))
         .await
         .unwrap();
-    harness.assert_buffer_contents("Xbc");
-    assert!(harness
-        .last_error()
-        .is_some_and(|message| message.contains("revert conflict")));
+
+    harness.assert_cursor_at(0, 2);
 }
 
 #[tokio::test]
-async fn selective_revert_accepts_adjacent_insertions_from_one_insert_transaction() {
-    let mut harness = EditorHarness::with_content("");
+async fn test_change_line() {
+    let mut harness = EditorHarness::with_content("Line 1\nLine 2\nLine 3");
 
+    // Move to second line
+    harness.execute_action(Action::MoveDown).await.unwrap();
+
+    // Change line with 'cc' - delete line content and enter insert mode
     harness
-        .execute_action(Action::EnterMode(Mode::Insert))
+        .execute_action(Action::MoveToLineStart)
         .await
         .unwrap();
-    harness.type_text("abc").await.unwrap();
+    let line_len = harness.current_line().unwrap().trim_end().len();
+    for _ in 0..line_len {
+        harness
+            .execute_action(Action::DeleteCharAtCursorPos)
+            .await
+            .unwrap();
+    }
     harness
-        .execute_action(Action::EnterMode(Mode::Normal))
+        .execute_action(Action::EnterMode(Mode::Insert))
         .await
         .unwrap();
-    harness.assert_buffer_contents("abc\n");
-    let transaction_id = harness.editor.test_undo_tree()[0].transaction_id.clone();
+    harness.assert_mode(Mode::Insert);
+
+    // Type replacement
+    harness.type_text("Changed line").await.unwrap();
+    harness.assert_buffer_contents("Line 1\nChanged line\nLine 3");
 
+    // Exit insert mode
     harness
-        .execute_action(Action::RevertTransaction(transaction_id))
+        .execute_action(Action::EnterMode(Mode::Normal))
         .await
         .unwrap();
+}
 
-    harness.assert_buffer_contents("\n");
-    assert!(!harness
-        .last_error()
-        .is_some_and(|message| message.contains("revert conflict")));
+#[tokio::test]
+async fn test_replace_char() {
+    let buffer = Buffer::new(None, "Hello World".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+
+    type_normal_keys(&mut harness, "rJ").await;
+
+    harness.assert_buffer_contents("Jello World");
+    harness.assert_mode(Mode::Normal);
 }
 
 #[tokio::test]
-async fn selective_revert_shifts_a_replacement_past_a_later_left_edge_insertion() {
-    let mut harness = EditorHarness::with_content("abc");
-    harness.execute_action(Action::MoveRight).await.unwrap();
-    harness
-        .execute_action(Action::ReplaceCharsAtCursor {
-            character: 'B',
-            count: 1,
-        })
-        .await
-        .unwrap();
-    harness.assert_buffer_contents("aBc");
-    let transaction_id = harness.editor.test_undo_tree()[0].transaction_id.clone();
-    harness
-        .execute_action(Action::InsertCharAtCursorPos('!'))
-        .await
-        .unwrap();
-    harness.assert_buffer_contents("a!Bc");
-
-    harness
-        .execute_action(Action::RevertTransaction(transaction_id))
-        .await
-        .unwrap();
+async fn replace_char_accepts_operator_and_character_motion_prefixes() {
+    for replacement in ['d', 'c', 'y', 'f', 't', 'F', 'T'] {
+        let buffer = Buffer::new(None, "abc".to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    harness.assert_buffer_contents("a!bc");
-    assert!(!harness
-        .last_error()
-        .is_some_and(|message| message.contains("revert conflict")));
-}
+        type_normal_keys(&mut harness, &format!("r{replacement}")).await;
 
-#[tokio::test]
-async fn test_undo_does_not_create_new_undo_entries() {
-    let mut harness = EditorHarness::with_content("abc");
+        harness.assert_buffer_contents(&format!("{replacement}bc"));
+        harness.assert_mode(Mode::Normal);
 
-    harness
-        .execute_action(Action::DeleteCharAtCursorPos)
-        .await
-        .unwrap();
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("abc");
+        type_normal_keys(&mut harness, "x").await;
+        harness.assert_buffer_contents("bc");
+    }
 }
 
 #[tokio::test]
-async fn test_undo_indent_and_unindent() {
-    let mut harness = EditorHarness::with_content("line");
+async fn visual_replace_accepts_operator_and_character_motion_prefixes() {
+    for replacement in ['d', 'c', 'y', 'f', 't', 'F', 'T'] {
+        let buffer = Buffer::new(None, "abc".to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    harness.execute_action(Action::IndentLine).await.unwrap();
-    harness.assert_buffer_contents("    line");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("line");
+        type_normal_keys(&mut harness, &format!("vlr{replacement}")).await;
 
-    harness.execute_action(Action::IndentLine).await.unwrap();
-    harness.execute_action(Action::UnindentLine).await.unwrap();
-    harness.assert_buffer_contents("line");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("    line");
+        harness.assert_buffer_contents(&format!("{replacement}{replacement}c"));
+        harness.assert_mode(Mode::Normal);
+    }
 }
 
 #[tokio::test]
-async fn visual_indent_shifts_all_selected_lines_as_one_change() {
-    let buffer = Buffer::new(None, "one\n\n  two\n   ".to_string());
+async fn replace_mode_overwrites_graphemes_and_backspace_restores_them() {
+    let buffer = Buffer::new(None, "abcdef\nab".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    type_normal_keys(&mut harness, "Vjjj>").await;
+    type_normal_keys(&mut harness, "Rxy").await;
+    harness.assert_mode(Mode::Replace);
+    assert!(harness.statusline_row().contains("REPLACE"));
+    harness.assert_buffer_contents("xycdef\nab");
 
+    command_key(&mut harness, KeyCode::Backspace).await;
+    harness.assert_buffer_contents("xbcdef\nab");
+    harness.assert_cursor_at(1, 0);
+    command_key(&mut harness, KeyCode::Esc).await;
     harness.assert_mode(Mode::Normal);
-    harness.assert_buffer_contents("    one\n\n      two\n       ");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("one\n\n  two\n   ");
-}
-
-#[tokio::test]
-async fn visual_indent_supports_character_block_and_counted_selections() {
-    for mode in [Mode::Visual, Mode::VisualBlock] {
-        let buffer = Buffer::new(None, "one\ntwo".to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-        harness
-            .execute_action(Action::EnterMode(mode))
-            .await
-            .unwrap();
-        harness.execute_action(Action::MoveDown).await.unwrap();
-        type_normal_keys(&mut harness, ">").await;
+    harness.assert_cursor_at(0, 0);
 
-        harness.assert_mode(Mode::Normal);
-        harness.assert_buffer_contents("    one\n    two");
-    }
+    type_normal_keys(&mut harness, "jRxyz").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("xbcdef\nxyz");
 
-    let buffer = Buffer::new(None, "one\ntwo".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "Vj2>").await;
-    harness.assert_buffer_contents("        one\n        two");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("one\ntwo");
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents("xbcdef\nab");
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents("abcdef\nab");
 }
 
 #[tokio::test]
-async fn visual_unindent_shifts_all_selected_lines_as_one_change() {
-    let buffer = Buffer::new(None, "    one\n\n      two\n       ".to_string());
+async fn counted_replace_mode_replays_typed_text_with_dot_and_macros() {
+    let buffer = Buffer::new(None, "abcdefghij\n0123456789\nline".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    type_normal_keys(&mut harness, "Vjjj<").await;
+    type_normal_keys(&mut harness, "3Rab").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("abababghij\n0123456789\nline");
+    harness.assert_cursor_at(5, 0);
 
-    harness.assert_mode(Mode::Normal);
-    harness.assert_buffer_contents("one\n\n  two\n   ");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("    one\n\n      two\n       ");
+    type_normal_keys(&mut harness, "j0.").await;
+    harness.assert_buffer_contents("abababghij\nababab6789\nline");
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents("abababghij\n0123456789\nline");
+
+    type_normal_keys(&mut harness, "qqRX").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    type_normal_keys(&mut harness, "jq0@q").await;
+    harness.assert_buffer_contents("abababghij\nX123456789\nXine");
 }
 
 #[tokio::test]
-async fn visual_unindent_supports_character_block_and_counted_selections() {
-    for mode in [Mode::Visual, Mode::VisualBlock] {
-        let buffer = Buffer::new(None, "    one\n    two".to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-        harness
-            .execute_action(Action::EnterMode(mode))
-            .await
-            .unwrap();
-        harness.execute_action(Action::MoveDown).await.unwrap();
-        type_normal_keys(&mut harness, "<").await;
+async fn virtual_replace_keeps_tabs_and_wide_characters_aligned() {
+    let buffer = Buffer::new(None, "\tx\n界x".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-        harness.assert_mode(Mode::Normal);
-        harness.assert_buffer_contents("one\ntwo");
-    }
+    type_normal_keys(&mut harness, "gRa").await;
+    harness.assert_mode(Mode::VirtualReplace);
+    assert!(harness.statusline_row().contains("V-REPLACE"));
+    harness.assert_buffer_contents("a\tx\n界x");
+    command_key(&mut harness, KeyCode::Backspace).await;
+    harness.assert_buffer_contents("\tx\n界x");
+    command_key(&mut harness, KeyCode::Esc).await;
 
-    let buffer = Buffer::new(None, "        one\n    two".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    type_normal_keys(&mut harness, "Vj2<").await;
-    harness.assert_buffer_contents("one\ntwo");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("        one\n    two");
+    type_normal_keys(&mut harness, "jgRa").await;
+    harness.assert_buffer_contents("\tx\na x");
+    type_normal_keys(&mut harness, "b").await;
+    harness.assert_buffer_contents("\tx\nabx");
+    command_key(&mut harness, KeyCode::Backspace).await;
+    command_key(&mut harness, KeyCode::Backspace).await;
+    harness.assert_buffer_contents("\tx\n界x");
+}
+
+async fn control_key(harness: &mut EditorHarness, key: char) {
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char(key),
+            KeyModifiers::CONTROL,
+        )))
+        .await
+        .unwrap();
 }
 
 #[tokio::test]
-async fn gv_reselects_lines_after_visual_indent() {
-    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
+async fn ctrl_a_and_ctrl_x_change_the_next_number_as_one_repeatable_step() {
+    let buffer = Buffer::new(None, "x = 9;\nid 0x0f\nend -1".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    type_normal_keys(&mut harness, "Vj>gv").await;
+    control_key(&mut harness, 'a').await;
+    harness.assert_buffer_contents("x = 10;\nid 0x0f\nend -1");
+    harness.assert_cursor_at(5, 0);
 
-    harness.assert_mode(Mode::VisualLine);
-    assert_eq!(
-        harness.selection().map(|(_, y0, _, y1)| (y0, y1)),
-        Some((0, 1))
-    );
-    harness.assert_buffer_contents("    one\n    two\nthree");
+    type_normal_keys(&mut harness, "5").await;
+    control_key(&mut harness, 'x').await;
+    harness.assert_buffer_contents("x = 5;\nid 0x0f\nend -1");
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents("x = 10;\nid 0x0f\nend -1");
+
+    type_normal_keys(&mut harness, "j0").await;
+    control_key(&mut harness, 'a').await;
+    harness.assert_buffer_contents("x = 10;\nid 0x10\nend -1");
+
+    type_normal_keys(&mut harness, "qq").await;
+    type_normal_keys(&mut harness, "3").await;
+    control_key(&mut harness, 'a').await;
+    type_normal_keys(&mut harness, "q").await;
+    harness.assert_buffer_contents("x = 10;\nid 0x13\nend -1");
+    type_normal_keys(&mut harness, "j0@q").await;
+    harness.assert_buffer_contents("x = 10;\nid 0x13\nend 2");
+    type_normal_keys(&mut harness, ".").await;
+    harness.assert_buffer_contents("x = 10;\nid 0x13\nend 5");
 }
 
 #[tokio::test]
-async fn gv_reselects_lines_after_visual_unindent() {
-    let buffer = Buffer::new(None, "    one\n    two\nthree".to_string());
+async fn visual_g_ctrl_a_numbers_selected_lines_as_a_sequence() {
+    let buffer = Buffer::new(None, "item 0\nitem 0\nnone\nitem 0".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    type_normal_keys(&mut harness, "Vj<gv").await;
+    type_normal_keys(&mut harness, "VGg").await;
+    control_key(&mut harness, 'a').await;
+    harness.assert_mode(Mode::Normal);
+    harness.assert_buffer_contents("item 1\nitem 2\nnone\nitem 3");
 
-    harness.assert_mode(Mode::VisualLine);
-    assert_eq!(
-        harness.selection().map(|(_, y0, _, y1)| (y0, y1)),
-        Some((0, 1))
-    );
-    harness.assert_buffer_contents("one\ntwo\nthree");
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents("item 0\nitem 0\nnone\nitem 0");
+
+    type_normal_keys(&mut harness, "ggjVj2").await;
+    control_key(&mut harness, 'x').await;
+    harness.assert_buffer_contents("item 0\nitem -2\nnone\nitem 0");
 }
 
 #[tokio::test]
-async fn test_undo_visual_char_line_and_block_delete() {
-    let mut harness = EditorHarness::with_content("abcde");
+async fn test_insert_at_line_start() {
+    let mut harness = EditorHarness::with_content("    Hello World");
+
+    // Move cursor to middle
     harness
-        .execute_action(Action::EnterMode(Mode::Visual))
+        .execute_action(Action::MoveToNextWord)
         .await
         .unwrap();
-    harness.execute_action(Action::MoveRight).await.unwrap();
-    harness.execute_action(Action::MoveRight).await.unwrap();
-    harness.execute_action(Action::Delete).await.unwrap();
-    harness.assert_buffer_contents("de");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("abcde");
 
-    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
+    // Insert at start of line with 'I' - move to start and enter insert
     harness
-        .execute_action(Action::EnterMode(Mode::VisualLine))
+        .execute_action(Action::MoveToLineStart)
         .await
         .unwrap();
-    harness.execute_action(Action::MoveDown).await.unwrap();
-    harness.execute_action(Action::Delete).await.unwrap();
-    harness.assert_buffer_contents("three");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("one\ntwo\nthree");
-
-    let mut harness = EditorHarness::with_content("abc\ndef");
     harness
-        .execute_action(Action::EnterMode(Mode::VisualBlock))
+        .execute_action(Action::EnterMode(Mode::Insert))
         .await
         .unwrap();
-    harness.execute_action(Action::MoveRight).await.unwrap();
-    harness.execute_action(Action::MoveDown).await.unwrap();
-    harness.execute_action(Action::Delete).await.unwrap();
-    harness.assert_buffer_contents("c\nf");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("abc\ndef");
-}
+    harness.assert_mode(Mode::Insert);
+    harness.assert_cursor_at(0, 0);
 
-#[tokio::test]
-async fn test_visual_block_insert_undoes_and_redoes_as_one_transaction() {
-    let mut harness = EditorHarness::with_content("impl\nfn\nColor\n}\n}");
+    // Type text
+    harness.type_text("Start: ").await.unwrap();
+    harness.assert_buffer_contents("Start:     Hello World");
 
-    harness
-        .execute_action(Action::EnterMode(Mode::VisualBlock))
-        .await
-        .unwrap();
-    for _ in 0..4 {
-        harness.execute_action(Action::MoveDown).await.unwrap();
-    }
-    harness.execute_action(Action::InsertBlock).await.unwrap();
-    harness
-        .execute_action(Action::InsertCharAtCursorPos(' '))
-        .await
-        .unwrap();
+    // Exit insert mode
     harness
         .execute_action(Action::EnterMode(Mode::Normal))
         .await
         .unwrap();
+}
 
-    harness.assert_buffer_contents(" impl\n fn\n Color\n }\n }");
+#[tokio::test]
+async fn test_insert_key_escape_without_insert_stays_on_original_character() {
+    let mut config = Config::default();
+    config.keys.normal.insert(
+        "i".to_string(),
+        KeyAction::Single(Action::EnterMode(Mode::Insert)),
+    );
+    config.keys.insert.insert(
+        "Esc".to_string(),
+        KeyAction::Single(Action::EnterMode(Mode::Normal)),
+    );
+    let buffer = Buffer::new(None, "abc".to_string());
+    let mut harness = EditorHarness::with_config(buffer, config);
+    harness.execute_action(Action::MoveRight).await.unwrap();
+    let start = harness.render_cursor_position().unwrap();
 
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("impl\nfn\nColor\n}\n}");
+    type_normal_keys(&mut harness, "i").await;
+    harness
+        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+        .await
+        .unwrap();
 
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents(" impl\n fn\n Color\n }\n }");
+    harness.assert_mode(Mode::Normal);
+    harness.assert_cursor_at(1, 0);
+    assert_eq!(harness.render_cursor_position(), Some(start));
 }
 
 #[tokio::test]
-async fn test_visual_block_insert_coalesces_replayed_change_notifications() {
-    let path = temp_file_path("visual-block-insert-lsp");
-    let lsp = RecordingLsp::default();
-    let events = lsp.events();
-    let config = Config::default();
-    let theme = Theme::default();
-    let buffer = Buffer::new(Some(path.clone()), "impl\nfn\nColor\n}\n}".to_string());
-    let mut editor =
-        Editor::test_with_size(Box::new(lsp), 80, 24, config, theme, vec![buffer]).unwrap();
-    editor.test_disable_terminal_output();
-    let mut harness = EditorHarness { editor };
+async fn test_append_key_positions_cursor_after_current_character() {
+    let mut config = Config::default();
+    config.keys.normal.insert(
+        "a".to_string(),
+        KeyAction::Multiple(vec![Action::EnterMode(Mode::Insert), Action::MoveRight]),
+    );
+    config.keys.insert.insert(
+        "Esc".to_string(),
+        KeyAction::Single(Action::EnterMode(Mode::Normal)),
+    );
+    let buffer = Buffer::new(None, "abc".to_string());
+    let mut harness = EditorHarness::with_config(buffer, config);
+    let start = harness.render_cursor_position().unwrap();
+
+    type_normal_keys(&mut harness, "a").await;
+
+    harness.assert_mode(Mode::Insert);
+    harness.assert_cursor_at(1, 0);
+    assert_eq!(
+        harness.render_cursor_position(),
+        Some((start.0 + 1, start.1))
+    );
+
+    harness.type_text("X").await.unwrap();
+    harness.assert_buffer_contents("aXbc");
 
     harness
-        .execute_action(Action::EnterMode(Mode::VisualBlock))
-        .await
-        .unwrap();
-    for _ in 0..4 {
-        harness.execute_action(Action::MoveDown).await.unwrap();
-    }
-    harness.execute_action(Action::InsertBlock).await.unwrap();
-    harness
-        .execute_action(Action::InsertCharAtCursorPos(' '))
+        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
         .await
         .unwrap();
+    harness.assert_mode(Mode::Normal);
+    harness.assert_cursor_at(1, 0);
+}
+
+#[tokio::test]
+async fn test_append_key_escape_without_insert_returns_to_original_character() {
+    let mut config = Config::default();
+    config.keys.normal.insert(
+        "a".to_string(),
+        KeyAction::Multiple(vec![Action::EnterMode(Mode::Insert), Action::MoveRight]),
+    );
+    config.keys.insert.insert(
+        "Esc".to_string(),
+        KeyAction::Single(Action::EnterMode(Mode::Normal)),
+    );
+    let buffer = Buffer::new(None, "abc".to_string());
+    let mut harness = EditorHarness::with_config(buffer, config);
+    let start = harness.render_cursor_position().unwrap();
+
+    type_normal_keys(&mut harness, "a").await;
     harness
-        .execute_action(Action::EnterMode(Mode::Normal))
+        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
         .await
         .unwrap();
 
-    let did_change_count = events
-        .lock()
-        .unwrap()
-        .iter()
-        .filter(|event| matches!(event, LspEvent::DidChange(file) if file == &path))
-        .count();
+    harness.assert_mode(Mode::Normal);
+    harness.assert_cursor_at(0, 0);
+    assert_eq!(harness.render_cursor_position(), Some(start));
+}
+
+#[tokio::test]
+async fn test_append_line_key_positions_cursor_after_line_end() {
+    let mut config = Config::default();
+    config.keys.normal.insert(
+        "A".to_string(),
+        KeyAction::Multiple(vec![
+            Action::MoveToLineEnd,
+            Action::EnterMode(Mode::Insert),
+            Action::MoveRight,
+        ]),
+    );
+    config.keys.insert.insert(
+        "Esc".to_string(),
+        KeyAction::Single(Action::EnterMode(Mode::Normal)),
+    );
+    let buffer = Buffer::new(None, "abc".to_string());
+    let mut harness = EditorHarness::with_config(buffer, config);
+    let start = harness.render_cursor_position().unwrap();
+
+    type_normal_keys(&mut harness, "A").await;
+
+    harness.assert_mode(Mode::Insert);
+    harness.assert_cursor_at(3, 0);
     assert_eq!(
-        did_change_count, 2,
-        "expected one notification for the initial insert and one coalesced replay notification"
+        harness.render_cursor_position(),
+        Some((start.0 + 3, start.1))
     );
 
-    let _ = fs::remove_file(path);
+    harness.type_text("X").await.unwrap();
+    harness.assert_buffer_contents("abcX");
+
+    harness
+        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+        .await
+        .unwrap();
+    harness.assert_mode(Mode::Normal);
+    harness.assert_cursor_at(3, 0);
 }
 
 #[tokio::test]
-async fn test_visual_block_insert_clears_selection_background_after_apply() {
-    let mut harness = EditorHarness::with_content("impl\nfn\nColor\n}\n}");
-    let selection_bg = Color::Rgb {
-        r: 12,
-        g: 34,
-        b: 56,
-    };
-    harness.editor.theme.selection_style = Some(Style {
-        bg: Some(selection_bg),
-        ..Default::default()
-    });
+async fn test_append_line_key_escape_without_insert_returns_to_last_character() {
+    let mut config = Config::default();
+    config.keys.normal.insert(
+        "A".to_string(),
+        KeyAction::Multiple(vec![
+            Action::MoveToLineEnd,
+            Action::EnterMode(Mode::Insert),
+            Action::MoveRight,
+        ]),
+    );
+    config.keys.insert.insert(
+        "Esc".to_string(),
+        KeyAction::Single(Action::EnterMode(Mode::Normal)),
+    );
+    let buffer = Buffer::new(None, "abc".to_string());
+    let mut harness = EditorHarness::with_config(buffer, config);
+    let start = harness.render_cursor_position().unwrap();
 
+    type_normal_keys(&mut harness, "A").await;
     harness
-        .execute_action(Action::EnterMode(Mode::VisualBlock))
+        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
         .await
         .unwrap();
-    for _ in 0..4 {
-        harness.execute_action(Action::MoveDown).await.unwrap();
-    }
-    harness.execute_action(Action::InsertBlock).await.unwrap();
+
+    harness.assert_mode(Mode::Normal);
+    harness.assert_cursor_at(2, 0);
+    assert_eq!(
+        harness.render_cursor_position(),
+        Some((start.0 + 2, start.1))
+    );
+}
+
+#[tokio::test]
+async fn test_append_at_line_end() {
+    let mut harness = EditorHarness::with_content("Hello World");
+
+    // Append at end of line with 'A' - move to end and enter insert
+    harness.execute_action(Action::MoveToLineEnd).await.unwrap();
     harness
-        .execute_action(Action::InsertCharAtCursorPos(' '))
+        .execute_action(Action::EnterMode(Mode::Insert))
         .await
         .unwrap();
+    harness.execute_action(Action::MoveRight).await.unwrap();
+    harness.assert_mode(Mode::Insert);
+
+    // Type text
+    harness.type_text(" Test").await.unwrap();
+    harness.assert_buffer_contents("Hello World Test");
+
+    // Exit insert mode
     harness
         .execute_action(Action::EnterMode(Mode::Normal))
         .await
         .unwrap();
-
-    for y in 0..5 {
-        for x in 0..40 {
-            assert_ne!(
-                harness.render_cell_bg(x, y).unwrap(),
-                Some(selection_bg),
-                "selection background leaked at ({x}, {y}) after block insert"
-            );
-        }
-    }
 }
 
 #[tokio::test]
-async fn test_visual_line_selection_uses_buffer_lines_after_scrolling() {
-    let content = (0..40)
-        .map(|line| format!("line-{line:02}"))
-        .collect::<Vec<_>>()
-        .join("\n");
-    let mut harness = EditorHarness::with_content(&content);
+async fn test_escape_from_insert_clamps_to_last_line_character() {
+    let mut harness = EditorHarness::with_content("Hello");
 
+    harness.execute_action(Action::MoveToLineEnd).await.unwrap();
     harness
-        .execute_action(Action::SetCursor(0, 30))
+        .execute_action(Action::EnterMode(Mode::Insert))
         .await
         .unwrap();
-    assert_eq!(harness.viewport_top(), 9);
-    harness.assert_cursor_at(0, 30);
+    harness.execute_action(Action::MoveRight).await.unwrap();
+    harness.assert_cursor_at(5, 0);
 
     harness
-        .execute_action(Action::EnterMode(Mode::VisualLine))
+        .execute_action(Action::EnterMode(Mode::Normal))
         .await
         .unwrap();
-    harness.execute_action(Action::MoveDown).await.unwrap();
-    harness.execute_action(Action::Delete).await.unwrap();
-
-    let remaining = harness.buffer_contents();
-    assert!(
-        !remaining.contains("line-30\nline-31"),
-        "visual line delete should remove the scrolled-to buffer lines"
-    );
-    assert!(
-        remaining.contains("line-21"),
-        "visual line delete should not use viewport-relative rows as buffer lines"
-    );
+    harness.assert_cursor_at(4, 0);
 }
 
 #[tokio::test]
-async fn visual_line_delete_whole_scrolled_buffer_repositions_cursor_safely() {
-    let content = (0..40)
-        .map(|line| format!("line-{line:02}"))
-        .collect::<Vec<_>>()
-        .join("\n");
-    let buffer = Buffer::new(None, content.clone());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-
-    type_normal_keys(&mut harness, "ggVGx").await;
+async fn test_delete_word() {
+    let mut harness = EditorHarness::with_content("Hello World Test");
 
-    harness.assert_buffer_contents("");
-    harness.assert_cursor_at(0, 0);
-    assert_eq!(harness.viewport_top(), 0);
-    harness.assert_mode(Mode::Normal);
+    // Delete word with 'dw'
+    harness.execute_action(Action::DeleteWord).await.unwrap();
+    harness.assert_buffer_contents("World Test");
 
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents(&content);
+    // Delete another word (including space)
+    harness.execute_action(Action::DeleteWord).await.unwrap();
+    harness.assert_buffer_contents("Test");
 }
 
 #[tokio::test]
-async fn visual_paste_replaces_whole_document_from_system_clipboard() {
-    let content = (0..40)
-        .map(|line| format!("line-{line:02}"))
-        .collect::<Vec<_>>()
-        .join("\n");
-    let clipboard_text = Arc::new(Mutex::new(Some("replacement".to_string())));
-    let buffer = Buffer::new(None, content.clone());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness
-        .editor
-        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
-            clipboard_text.clone(),
-        )));
-
-    type_normal_keys(&mut harness, "ggVGp").await;
-
-    harness.assert_buffer_contents("replacement");
-    harness.assert_cursor_at(0, 0);
-    harness.assert_mode(Mode::Normal);
-    assert_eq!(
-        clipboard_text.lock().unwrap().as_deref(),
-        Some(content.as_str())
-    );
+async fn delete_word_at_line_end_preserves_the_line_break() {
+    for (contents, keys, expected) in [
+        ("alpha beta\nnext line", "wdw", "alpha \nnext line"),
+        (
+            "alpha beta   \n    next line",
+            "wdw",
+            "alpha \n    next line",
+        ),
+        ("alpha !!!\nnext line", "wdw", "alpha \nnext line"),
+        ("alpha βeta\n    δelta", "wdw", "alpha \n    δelta"),
+        ("alpha beta\r\nnext line", "wdw", "alpha \r\nnext line"),
+        ("alpha beta   \nnext line", "weldw", "alpha beta\nnext line"),
+        ("   \nnext line", "dw", "\nnext line"),
+        ("\n    next line", "dw", "    next line"),
+        ("\n\nnext line", "dw", "\nnext line"),
+    ] {
+        let buffer = Buffer::new(None, contents.to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents(&content);
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents("replacement");
+        type_normal_keys(&mut harness, keys).await;
+
+        harness.assert_buffer_contents(expected);
+    }
 }
 
 #[tokio::test]
-async fn visual_line_paste_replaces_large_interior_selection_with_one_line() {
-    let content = (1..=20)
-        .map(|line| format!("line-{line:02}"))
-        .collect::<Vec<_>>()
-        .join("\n");
-    let buffer = Buffer::new(None, content);
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness
-        .editor
-        .test_set_clipboard(Box::new(MemoryClipboardProvider::with_text(
-            "node dist/src/cli.js plan validate examples/hello-world.yaml",
-        )));
-    harness
-        .execute_action(Action::SetCursor(0, 2))
-        .await
-        .unwrap();
-    harness
-        .execute_action(Action::EnterMode(Mode::VisualLine))
-        .await
-        .unwrap();
-    for _ in 0..5 {
-        harness.execute_action(Action::MoveDown).await.unwrap();
-    }
+async fn counted_delete_word_can_cross_a_line_break() {
+    for keys in ["wd2w", "w2dw"] {
+        let buffer = Buffer::new(None, "alpha beta\n    next line".to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    type_normal_keys(&mut harness, "p").await;
+        type_normal_keys(&mut harness, keys).await;
 
-    harness.assert_buffer_contents(
-        "line-01\nline-02\nnode dist/src/cli.js plan validate examples/hello-world.yaml\nline-09\nline-10\nline-11\nline-12\nline-13\nline-14\nline-15\nline-16\nline-17\nline-18\nline-19\nline-20",
-    );
-    harness.assert_cursor_at(0, 2);
-    harness.assert_mode(Mode::Normal);
+        harness.assert_buffer_contents("alpha line");
+    }
 }
 
 #[tokio::test]
-async fn visual_line_paste_replaces_small_interior_selection_with_many_lines() {
-    let content = (1..=8)
-        .map(|line| format!("line-{line:02}"))
-        .collect::<Vec<_>>()
-        .join("\n");
-    let buffer = Buffer::new(None, content);
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness
-        .editor
-        .test_set_clipboard(Box::new(MemoryClipboardProvider::with_text(
-            "replacement-a\nreplacement-b\nreplacement-c",
-        )));
-    harness
-        .execute_action(Action::SetCursor(0, 2))
-        .await
-        .unwrap();
-    harness
-        .execute_action(Action::EnterMode(Mode::VisualLine))
-        .await
-        .unwrap();
+async fn delete_word_action_preserves_line_breaks_and_deletes_through_eof() {
+    for (contents, expected) in [
+        ("alpha beta\nnext line", "alpha \nnext line"),
+        ("alpha beta   \n    next line", "alpha \n    next line"),
+        ("alpha beta\r\nnext line", "alpha \r\nnext line"),
+        ("alpha βeta\n    δelta", "alpha \n    δelta"),
+        ("alpha beta", "alpha "),
+        ("alpha βeta", "alpha "),
+    ] {
+        let mut harness = EditorHarness::with_content(contents);
+        harness
+            .execute_action(Action::MoveToNextWord)
+            .await
+            .unwrap();
 
-    type_normal_keys(&mut harness, "p").await;
+        harness.execute_action(Action::DeleteWord).await.unwrap();
 
-    harness.assert_buffer_contents(
-        "line-01\nline-02\nreplacement-a\nreplacement-b\nreplacement-c\nline-04\nline-05\nline-06\nline-07\nline-08",
-    );
-    harness.assert_cursor_at(0, 2);
-    harness.assert_mode(Mode::Normal);
+        harness.assert_buffer_contents(expected);
+    }
 }
 
 #[tokio::test]
-async fn visual_uppercase_p_preserves_system_clipboard() {
-    let clipboard_text = Arc::new(Mutex::new(Some("replacement".to_string())));
-    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness
-        .editor
-        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
-            clipboard_text.clone(),
-        )));
+async fn test_join_lines() {
+    for (contents, keys, expected, cursor) in [
+        ("alpha\n    beta", "J", "alpha beta", (5, 0)),
+        ("alpha\n    ) tail", "J", "alpha) tail", (5, 0)),
+        ("alpha \n    beta", "J", "alpha beta", (6, 0)),
+        ("α\u{0301}\r\n    β", "J", "α\u{0301} β", (1, 0)),
+        (
+            "one\n  two\n    three\nfour",
+            "3J",
+            "one two three\nfour",
+            (7, 0),
+        ),
+        ("alpha \n    beta", "gJ", "alpha     beta", (6, 0)),
+        (
+            "one\n  two\n    three\nfour",
+            "VjjJ",
+            "one two three\nfour",
+            (7, 0),
+        ),
+        (
+            "one \n  two\n    three\nfour",
+            "VjjgJ",
+            "one   two    three\nfour",
+            (9, 0),
+        ),
+        (
+            "one \n  two\n    three\nfour",
+            "3gJ",
+            "one   two    three\nfour",
+            (9, 0),
+        ),
+    ] {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, contents.to_string()),
+            default_key_config(),
+        );
 
-    type_normal_keys(&mut harness, "ggVGP").await;
+        type_normal_keys(&mut harness, keys).await;
 
-    harness.assert_buffer_contents("replacement");
-    harness.assert_mode(Mode::Normal);
-    assert_eq!(
-        clipboard_text.lock().unwrap().as_deref(),
-        Some("replacement")
-    );
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(cursor.0, cursor.1);
+        harness.assert_mode(Mode::Normal);
+    }
 }
 
 #[tokio::test]
-async fn visual_paste_replaces_and_captures_a_unicode_grapheme() {
-    let family = "👨‍👩‍👧‍👦";
-    let clipboard_text = Arc::new(Mutex::new(Some("X".to_string())));
-    let buffer = Buffer::new(None, format!("a{family}b"));
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness
-        .editor
-        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
-            clipboard_text.clone(),
-        )));
-    harness.execute_action(Action::MoveRight).await.unwrap();
-    harness
-        .execute_action(Action::EnterMode(Mode::Visual))
-        .await
-        .unwrap();
+async fn join_lines_is_one_undoable_repeatable_change() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "one\n  two\nthree\n  four".to_string()),
+        default_key_config(),
+    );
 
-    type_normal_keys(&mut harness, "p").await;
+    type_normal_keys(&mut harness, "Jj.").await;
+    harness.assert_buffer_contents("one two\nthree four");
 
-    harness.assert_buffer_contents("aXb");
-    harness.assert_cursor_at(1, 0);
-    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some(family));
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents("one two\nthree\n  four");
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents("one\n  two\nthree\n  four");
 }
 
 #[tokio::test]
-async fn visual_paste_matches_selection_and_register_kinds() {
-    let sources = [
-        Content::charwise("Q".to_string()),
-        Content::linewise("X\nY\n".to_string()),
-        Content::blockwise("XY\nUV\n".to_string()),
-    ];
+async fn join_lines_survives_macro_replay_and_eof() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "one\n  two\nthree\n  four".to_string()),
+        default_key_config(),
+    );
 
-    for ((source, expected), cursor) in sources
-        .iter()
-        .cloned()
-        .zip([
-            "pre Q post\nsecond\nthird",
-            "pre \nX\nY\n post\nsecond\nthird",
-            "pre XY post\nsecoUVnd\nthird",
-        ])
-        .zip([(4, 0), (0, 1), (4, 0)])
-    {
+    type_normal_keys(&mut harness, "qaJjq@a").await;
+    harness.assert_buffer_contents("one two\nthree four");
+
+    type_normal_keys(&mut harness, "GJ").await;
+    harness.assert_buffer_contents("one two\nthree four");
+}
+
+#[tokio::test]
+async fn join_ex_command_supports_count_and_bang() {
+    for (contents, command, expected) in [
+        ("one\n  two\nthree", "join", "one two\nthree"),
+        ("one\n  two\nthree\nfour", "j 3", "one two three\nfour"),
+        ("one \n  two\nthree", "join!", "one   two\nthree"),
+        ("one \n  two\nthree\nfour", "j! 3", "one   twothree\nfour"),
+    ] {
         let mut harness = EditorHarness::with_config(
-            Buffer::new(None, "pre abc post\nsecond\nthird".to_string()),
+            Buffer::new(None, contents.to_string()),
             default_key_config(),
         );
-        harness.editor.test_set_default_register(source);
-        for _ in 0..4 {
-            harness.execute_action(Action::MoveRight).await.unwrap();
-        }
+
         harness
-            .execute_action(Action::EnterMode(Mode::Visual))
+            .execute_action(Action::Command(command.to_string()))
             .await
             .unwrap();
-        for _ in 0..2 {
-            harness.execute_action(Action::MoveRight).await.unwrap();
-        }
-
-        harness.execute_action(Action::Paste).await.unwrap();
 
         harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(cursor.0, cursor.1);
     }
+}
 
-    for (source, expected) in
-        sources
-            .iter()
-            .cloned()
-            .zip(["one\nQ\nfour", "one\nX\nY\nfour", "one\nXY\nUV\nfour"])
-    {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, "one\ntwo\nthree\nfour".to_string()),
-            default_key_config(),
-        );
-        harness.editor.test_set_default_register(source);
-        harness.execute_action(Action::MoveDown).await.unwrap();
-        harness
-            .execute_action(Action::EnterMode(Mode::VisualLine))
-            .await
-            .unwrap();
-        harness.execute_action(Action::MoveDown).await.unwrap();
-
-        harness.execute_action(Action::Paste).await.unwrap();
-
-        harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(0, 1);
-    }
-
-    for ((source, expected), cursor) in sources
-        .into_iter()
-        .zip([
-            "Q11zz\nQ22yy\nQ33xx",
-            "11zz\n22yy\n33xx\nX\nY",
-            "XY11zz\nUV22yy\n33xx",
-        ])
-        .zip([(0, 0), (0, 3), (0, 0)])
-    {
+#[tokio::test]
+async fn join_ex_command_supports_numeric_and_visual_ranges() {
+    for (contents, command, expected) in [
+        ("one\ntwo\nthree\nfour", "2join", "one\ntwo three\nfour"),
+        (
+            "one\ntwo\n  three\nfour",
+            "2,3join!",
+            "one\ntwo  three\nfour",
+        ),
+        ("one\n  two\nthree", "%join", "one two three"),
+    ] {
         let mut harness = EditorHarness::with_config(
-            Buffer::new(None, "aa11zz\nbb22yy\ncc33xx".to_string()),
+            Buffer::new(None, contents.to_string()),
             default_key_config(),
         );
-        harness.editor.test_set_default_register(source);
+
         harness
-            .execute_action(Action::EnterMode(Mode::VisualBlock))
+            .execute_action(Action::Command(command.to_string()))
             .await
             .unwrap();
-        harness.execute_action(Action::MoveRight).await.unwrap();
-        harness.execute_action(Action::MoveDown).await.unwrap();
-        harness.execute_action(Action::MoveDown).await.unwrap();
-
-        harness.execute_action(Action::Paste).await.unwrap();
 
         harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(cursor.0, cursor.1);
     }
+
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "one\n  two\nthree".to_string()),
+        default_key_config(),
+    );
+    type_normal_keys(&mut harness, "Vj:join").await;
+    command_key(&mut harness, KeyCode::Enter).await;
+    harness.assert_buffer_contents("one two\nthree");
 }
 
 #[tokio::test]
-async fn visual_paste_emits_one_change_notification() {
-    let path = temp_file_path("visual-paste-lsp");
-    let lsp = RecordingLsp::default();
-    let events = lsp.events();
-    let buffer = Buffer::new(Some(path.clone()), "one\ntwo\nthree".to_string());
-    let mut editor = Editor::test_with_size(
-        Box::new(lsp),
-        80,
-        24,
+async fn unsupported_ranged_command_reports_a_specific_error() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "one\ntwo".to_string()),
         default_key_config(),
-        Theme::default(),
-        vec![buffer],
-    )
-    .unwrap();
-    editor.test_disable_terminal_output();
-    editor.test_set_clipboard(Box::new(MemoryClipboardProvider::default()));
-    editor.test_set_default_register(Content::charwise("replacement".to_string()));
-    let mut harness = EditorHarness { editor };
+    );
+    type_normal_keys(&mut harness, "Vj:w").await;
+    command_key(&mut harness, KeyCode::Enter).await;
+
+    assert_eq!(
+        harness.last_error(),
+        Some("command \"w\" does not support a line range")
+    );
+    harness.assert_buffer_contents("one\ntwo");
+}
+
+#[tokio::test]
+async fn test_undo_redo() {
+    let mut harness = EditorHarness::with_content("Hello World");
+
+    // Make a change
     harness
-        .execute_action(Action::EnterMode(Mode::VisualLine))
+        .execute_action(Action::DeleteCharAtCursorPos)
         .await
         .unwrap();
-    harness.execute_action(Action::MoveDown).await.unwrap();
+    harness.assert_buffer_contents("ello World");
 
-    harness.execute_action(Action::Paste).await.unwrap();
+    // Undo with 'u'
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("Hello World");
 
-    let did_change_count = events
-        .lock()
-        .unwrap()
-        .iter()
-        .filter(|event| matches!(event, LspEvent::DidChange(file) if file == &path))
-        .count();
-    assert_eq!(did_change_count, 1);
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents("ello World");
+}
 
-    let _ = fs::remove_file(path);
+#[tokio::test]
+async fn undo_and_redo_boundaries_report_no_op() {
+    let mut harness = EditorHarness::with_content("Hello World");
+
+    harness.execute_action(Action::Undo).await.unwrap();
+    assert!(harness
+        .commandline_row()
+        .contains("already at oldest change"));
+
+    harness.execute_action(Action::Redo).await.unwrap();
+    assert!(harness
+        .commandline_row()
+        .contains("already at newest change"));
 }
 
 #[tokio::test]
-async fn bracketed_paste_inserts_multiline_text_once() {
-    let path = temp_file_path("bracketed-paste-lsp");
-    let lsp = RecordingLsp::default();
-    let events = lsp.events();
-    let buffer = Buffer::new(Some(path.clone()), "\n".to_string());
-    let mut editor = Editor::test_with_size(
-        Box::new(lsp),
-        80,
-        24,
-        default_key_config(),
-        Theme::default(),
-        vec![buffer],
-    )
-    .unwrap();
-    editor.test_disable_terminal_output();
-    let mut harness = EditorHarness { editor };
+async fn test_undo_multi_character_insert_session() {
+    let mut harness = EditorHarness::with_content("");
+
     harness
         .execute_action(Action::EnterMode(Mode::Insert))
         .await
         .unwrap();
-
+    harness.type_text("hello").await.unwrap();
     harness
-        .execute_event(Event::Paste("alpha\r\nbeta 👋".to_string()))
+        .execute_action(Action::EnterMode(Mode::Normal))
         .await
         .unwrap();
 
-    harness.assert_buffer_contents("alpha\nbeta 👋\n");
-    harness.assert_cursor_at(6, 1);
-    let did_change_count = events
-        .lock()
-        .unwrap()
-        .iter()
-        .filter(|event| matches!(event, LspEvent::DidChange(file) if file == &path))
-        .count();
-    assert_eq!(did_change_count, 1);
+    harness.assert_buffer_contents("hello\n");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("\n");
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents("hello\n");
+}
+
+#[tokio::test]
+async fn test_undo_insert_backspace_session() {
+    let mut harness = EditorHarness::with_content("");
 
+    harness
+        .execute_action(Action::EnterMode(Mode::Insert))
+        .await
+        .unwrap();
+    harness.type_text("abc").await.unwrap();
+    harness
+        .execute_action(Action::DeletePreviousChar)
+        .await
+        .unwrap();
     harness
         .execute_action(Action::EnterMode(Mode::Normal))
         .await
         .unwrap();
+
+    harness.assert_buffer_contents("ab\n");
     harness.execute_action(Action::Undo).await.unwrap();
     harness.assert_buffer_contents("\n");
-
-    let _ = fs::remove_file(path);
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents("ab\n");
 }
 
 #[tokio::test]
-async fn disabled_lsp_skips_document_change_notifications() {
-    let path = temp_file_path("disabled-lsp-change");
-    let lsp = RecordingLsp::default();
-    let events = lsp.events();
-    let mut config = default_key_config();
-    config.lsp.enabled = false;
-    let buffer = Buffer::new(Some(path.clone()), "text".to_string());
-    let mut editor = Editor::test_with_size(
-        Box::new(lsp),
-        80,
-        24,
-        config,
-        Theme::default(),
-        vec![buffer],
-    )
-    .unwrap();
-    editor.test_disable_terminal_output();
-    let mut harness = EditorHarness { editor };
+async fn test_backspace_at_line_start_joins_with_previous_line() {
+    let mut harness = EditorHarness::with_content("abc\ndef");
 
+    harness.execute_action(Action::MoveDown).await.unwrap();
     harness
-        .execute_action(Action::InsertCharAtCursorPos('x'))
+        .execute_action(Action::EnterMode(Mode::Insert))
+        .await
+        .unwrap();
+    harness
+        .execute_action(Action::DeletePreviousChar)
         .await
         .unwrap();
 
-    harness.assert_buffer_contents("xtext");
-    assert!(events.lock().unwrap().iter().all(|event| {
-        !matches!(event, LspEvent::DidOpen(file) | LspEvent::DidChange(file) if file == &path)
-    }));
+    harness.assert_buffer_contents("abcdef");
+    harness.assert_cursor_at(3, 0);
 }
 
 #[tokio::test]
-async fn bracketed_paste_uses_first_line_in_command_mode() {
-    let mut harness = EditorHarness::with_content("safe");
-    harness.set_commandline(Mode::Command, "");
+async fn test_undo_delete_range_and_word() {
+    let mut harness = EditorHarness::with_content("hello world");
+
+    harness.execute_action(Action::DeleteWord).await.unwrap();
+    harness.assert_buffer_contents("world");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("hello world");
 
     harness
-        .execute_event(Event::Paste("q\r\nj".to_string()))
+        .execute_action(Action::DeleteRange(0, 0, 5, 0))
         .await
         .unwrap();
-
-    assert_eq!(harness.commandline_text(), "q");
-    harness.assert_mode(Mode::Command);
+    harness.assert_buffer_contents(" world");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("hello world");
 }
 
 #[tokio::test]
-async fn bracketed_paste_uses_first_line_in_search_mode() {
-    let mut harness = EditorHarness::with_content("alpha beta");
+async fn test_undo_delete_current_line() {
+    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
+
+    harness.execute_action(Action::MoveDown).await.unwrap();
     harness
-        .execute_action(Action::EnterSearch(SearchDirection::Forward))
+        .execute_action(Action::DeleteCurrentLine)
         .await
         .unwrap();
+    harness.assert_buffer_contents("one\nthree");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("one\ntwo\nthree");
 
+    let mut harness = EditorHarness::with_content("single");
     harness
-        .execute_event(Event::Paste("alpha\r\nbeta".to_string()))
+        .execute_action(Action::DeleteCurrentLine)
         .await
         .unwrap();
-
-    assert_eq!(harness.commandline_text(), "alpha");
-    harness.assert_mode(Mode::Search);
+    harness.assert_buffer_contents("");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("single");
 }
 
 #[tokio::test]
-async fn bracketed_paste_is_ignored_in_normal_mode() {
-    let mut harness = EditorHarness::with_content("safe");
+async fn test_delete_current_line_yanks_for_linewise_paste_before() {
+    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
 
+    harness.execute_action(Action::MoveDown).await.unwrap();
     harness
-        .execute_event(Event::Paste("iddanger".to_string()))
+        .execute_action(Action::DeleteCurrentLine)
         .await
         .unwrap();
-
-    harness.assert_buffer_contents("safe");
-    harness.assert_mode(Mode::Normal);
-}
-
-#[tokio::test]
-async fn bracketed_paste_cancels_pending_normal_key_sequence() {
-    let mut harness = EditorHarness::with_content("safe word");
-    type_normal_keys(&mut harness, "d").await;
-    assert!(harness.is_waiting_for_key_sequence());
+    harness.assert_buffer_contents("one\nthree");
 
     harness
-        .execute_event(Event::Paste("ignored".to_string()))
+        .execute_action(Action::MoveToLineStart)
         .await
         .unwrap();
-    type_normal_keys(&mut harness, "w").await;
-
-    assert!(!harness.is_waiting_for_key_sequence());
-    harness.assert_buffer_contents("safe word");
-    harness.assert_mode(Mode::Normal);
+    harness.execute_action(Action::PasteBefore).await.unwrap();
+    harness.assert_buffer_contents("one\ntwo\nthree");
 }
 
 #[tokio::test]
-async fn test_undo_paste_and_paste_before() {
-    let mut harness = EditorHarness::with_content("hello world");
+async fn test_undo_multiline_insert_and_unicode() {
+    let mut harness = EditorHarness::with_content("");
 
     harness
-        .execute_action(Action::EnterMode(Mode::Visual))
+        .execute_action(Action::EnterMode(Mode::Insert))
         .await
         .unwrap();
-    for _ in 0..5 {
-        harness.execute_action(Action::MoveRight).await.unwrap();
-    }
-    harness.execute_action(Action::Delete).await.unwrap();
+    harness.type_text("a👋").await.unwrap();
+    harness.execute_action(Action::InsertNewLine).await.unwrap();
+    harness.type_text("é").await.unwrap();
     harness
         .execute_action(Action::EnterMode(Mode::Normal))
         .await
         .unwrap();
-    harness.assert_buffer_contents("world");
-    harness.execute_action(Action::MoveToLineEnd).await.unwrap();
-    harness.execute_action(Action::Paste).await.unwrap();
-    harness.assert_buffer_contents("worldhello ");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("world");
 
-    harness
-        .execute_action(Action::MoveToLineStart)
-        .await
-        .unwrap();
-    harness.execute_action(Action::PasteBefore).await.unwrap();
-    harness.assert_buffer_contents("hello world");
+    harness.assert_buffer_contents("a👋\né\n");
     harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("world");
+    harness.assert_buffer_contents("\n");
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents("a👋\né\n");
 }
 
 #[tokio::test]
-async fn test_undo_insert_text_action() {
+async fn test_redo_stack_clears_after_new_edit() {
     let mut harness = EditorHarness::with_content("abc");
-    let content = Content::charwise("ZZ".to_string());
 
     harness
-        .execute_action(Action::InsertText {
-            x: 1,
-            y: 0,
-            content,
-        })
+        .execute_action(Action::DeleteCharAtCursorPos)
         .await
         .unwrap();
-    harness.assert_buffer_contents("aZZbc");
+    harness.assert_buffer_contents("bc");
     harness.execute_action(Action::Undo).await.unwrap();
     harness.assert_buffer_contents("abc");
+    harness
+        .execute_action(Action::InsertCharAtCursorPos('z'))
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("zabc");
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents("zabc");
 }
 
 #[tokio::test]
-async fn test_undo_history_is_per_buffer() {
-    let lsp = Box::new(MockLsp) as Box<dyn LspClient + Send>;
-    let config = Config::default();
-    let theme = Theme::default();
-    let buffers = vec![
-        Buffer::new(None, "one".to_string()),
-        Buffer::new(None, "two".to_string()),
-    ];
-    let mut editor = Editor::test_with_size(lsp, 80, 24, config, theme, buffers).unwrap();
-    editor.test_disable_terminal_output();
-    let mut harness = EditorHarness { editor };
-
+async fn undo_tree_preserves_and_traverses_sibling_branches() {
+    let mut harness = EditorHarness::with_content("abc");
     harness
         .execute_action(Action::DeleteCharAtCursorPos)
         .await
         .unwrap();
-    harness.assert_buffer_contents("ne");
-    harness.execute_action(Action::NextBuffer).await.unwrap();
-    harness.assert_buffer_contents("two");
     harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("two");
     harness
-        .execute_action(Action::PreviousBuffer)
+        .execute_action(Action::InsertCharAtCursorPos('z'))
         .await
         .unwrap();
-    harness.assert_buffer_contents("ne");
     harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("one");
-}
-
-#[tokio::test]
-async fn test_buffer_delete_removes_current_buffer_from_list() {
-    let lsp = Box::new(MockLsp) as Box<dyn LspClient + Send>;
-    let config = Config::default();
-    let theme = Theme::default();
-    let buffers = vec![
-        Buffer::new(Some("one.rs".to_string()), "one".to_string()),
-        Buffer::new(Some("two.rs".to_string()), "two".to_string()),
-        Buffer::new(Some("three.rs".to_string()), "three".to_string()),
-    ];
-    let mut editor = Editor::test_with_size(lsp, 80, 24, config, theme, buffers).unwrap();
-    editor.test_disable_terminal_output();
-    let mut harness = EditorHarness { editor };
 
-    harness.execute_action(Action::NextBuffer).await.unwrap();
     harness
-        .execute_action(Action::Command("bd".to_string()))
+        .execute_action(Action::SelectPreviousUndoBranch)
         .await
         .unwrap();
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents("bc");
 
-    assert_eq!(harness.buffer_names(), vec!["one.rs", "three.rs"]);
-    assert_eq!(harness.current_buffer_index(), 1);
-    harness.assert_buffer_contents("three");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness
+        .execute_action(Action::SelectNextUndoBranch)
+        .await
+        .unwrap();
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents("zabc");
 }
 
 #[tokio::test]
-async fn test_buffer_delete_requires_force_for_dirty_buffer() {
-    let lsp = Box::new(MockLsp) as Box<dyn LspClient + Send>;
-    let config = Config::default();
-    let theme = Theme::default();
-    let buffers = vec![
-        Buffer::new(Some("one.rs".to_string()), "one".to_string()),
-        Buffer::new(Some("two.rs".to_string()), "two".to_string()),
-    ];
-    let mut editor = Editor::test_with_size(lsp, 80, 24, config, theme, buffers).unwrap();
-    editor.test_disable_terminal_output();
-    let mut harness = EditorHarness { editor };
-
+async fn selective_revert_applies_only_when_the_post_image_still_matches() {
+    let mut harness = EditorHarness::with_content("abc");
     harness
         .execute_action(Action::DeleteCharAtCursorPos)
         .await
         .unwrap();
+    let transaction_id = harness.editor.test_undo_tree()[0].transaction_id.clone();
+    harness.execute_action(Action::MoveToLineEnd).await.unwrap();
     harness
-        .execute_action(Action::Command("bd".to_string()))
+        .execute_action(Action::DeleteCharAtCursorPos)
         .await
         .unwrap();
-
-    assert_eq!(harness.buffer_names(), vec!["one.rs", "two.rs"]);
-    assert_eq!(
-        harness.last_error(),
-        Some("No write since last change (add ! to override)")
-    );
-    harness.assert_buffer_contents("ne");
+    harness.assert_buffer_contents("b");
 
     harness
-        .execute_action(Action::Command("bd!".to_string()))
+        .execute_action(Action::RevertTransaction(transaction_id))
         .await
         .unwrap();
+    harness.assert_buffer_contents("ab");
+    assert!(harness.editor.test_undo_tree().len() >= 3);
 
-    assert_eq!(harness.buffer_names(), vec!["two.rs"]);
-    harness.assert_buffer_contents("two");
-}
-
-#[tokio::test]
-async fn test_preview_theme_reports_missing_theme_without_changing_buffer() {
     let mut harness = EditorHarness::with_content("abc");
-
     harness
-        .execute_action(Action::PreviewTheme(
-            "definitely-missing-theme.json".to_string(),
-        ))
+        .execute_action(Action::DeleteCharAtCursorPos)
         .await
         .unwrap();
-
-    assert_eq!(
-        harness.last_error(),
-        Some("Theme file definitely-missing-theme.json not found")
-    );
-    harness.assert_buffer_contents("abc");
+    let transaction_id = harness.editor.test_undo_tree()[0].transaction_id.clone();
+    harness
+        .execute_action(Action::InsertCharAtCursorPos('X'))
+        .await
+        .unwrap();
+    harness
+        .execute_action(Action::RevertTransaction(transaction_id))
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("Xbc");
+    assert!(harness
+        .last_error()
+        .is_some_and(|message| message.contains("revert conflict")));
 }
 
 #[tokio::test]
-async fn test_dirty_clears_after_manual_same_length_replacement() {
-    let mut harness = EditorHarness::with_content("abc");
-    for (character, dirty) in [('z', true), ('a', false)] {
-        harness
-            .execute_action(Action::ReplaceCharsAtCursor {
-                character,
-                count: 1,
-            })
-            .await
-            .unwrap();
-        assert_eq!(harness.is_dirty(), dirty);
-    }
-    harness.assert_buffer_contents("abc");
-    assert!(harness.editor.test_current_buffer().undo_history.is_dirty());
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("zbc");
-    assert!(harness.is_dirty());
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents("abc");
-    assert!(!harness.is_dirty());
-}
+async fn selective_revert_accepts_adjacent_insertions_from_one_insert_transaction() {
+    let mut harness = EditorHarness::with_content("");
 
-#[tokio::test]
-async fn test_dirty_clears_before_leaving_insert_mode() {
-    let mut harness =
-        EditorHarness::with_config(Buffer::new(None, "abc".to_string()), default_key_config());
     harness
         .execute_action(Action::EnterMode(Mode::Insert))
         .await
         .unwrap();
-    harness.type_text("z").await.unwrap();
-    assert!(harness.is_dirty());
+    harness.type_text("abc").await.unwrap();
     harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Backspace,
-            KeyModifiers::NONE,
-        )))
+        .execute_action(Action::EnterMode(Mode::Normal))
         .await
         .unwrap();
-    harness.assert_buffer_contents("abc");
-    assert!(harness.is_insert());
-    assert!(!harness.is_dirty());
+    harness.assert_buffer_contents("abc\n");
+    let transaction_id = harness.editor.test_undo_tree()[0].transaction_id.clone();
+
     harness
-        .execute_action(Action::EnterMode(Mode::Normal))
+        .execute_action(Action::RevertTransaction(transaction_id))
         .await
         .unwrap();
-    assert!(!harness.is_dirty());
+
+    harness.assert_buffer_contents("\n");
+    assert!(!harness
+        .last_error()
+        .is_some_and(|message| message.contains("revert conflict")));
 }
 
 #[tokio::test]
-async fn test_dirty_session_baseline_survives_external_disk_changes() {
-    let directory = tempfile::tempdir().unwrap();
-    let path = directory.path().join("saved.txt");
-    fs::write(&path, "abc").unwrap();
-    let buffer = Buffer::new(Some(path.to_string_lossy().into_owned()), "abc".into());
-    let mut source = EditorHarness::with_buffer(buffer);
-    source
+async fn selective_revert_shifts_a_replacement_past_a_later_left_edge_insertion() {
+    let mut harness = EditorHarness::with_content("abc");
+    harness.execute_action(Action::MoveRight).await.unwrap();
+    harness
         .execute_action(Action::ReplaceCharsAtCursor {
-            character: 'z',
+            character: 'B',
             count: 1,
         })
         .await
         .unwrap();
-    fs::write(&path, "external").unwrap();
-    let snapshot = source.editor.test_session_snapshot();
-    assert_eq!(snapshot.buffers[0].saved_contents.as_deref(), Some("abc"));
-    assert_eq!(
-        snapshot.buffers[0].disk_contents.as_deref(),
-        Some("external")
-    );
-    let encoded = serde_json::to_vec(&snapshot).unwrap();
-    let snapshot = serde_json::from_slice(&encoded).unwrap();
-    let mut buffers = Editor::buffers_from_session_snapshot(&snapshot);
-    let mut restored = EditorHarness::with_buffer(buffers.remove(0));
-    assert!(restored.is_dirty());
-    restored
-        .execute_action(Action::ReplaceCharsAtCursor {
-            character: 'a',
-            count: 1,
-        })
+    harness.assert_buffer_contents("aBc");
+    let transaction_id = harness.editor.test_undo_tree()[0].transaction_id.clone();
+    harness
+        .execute_action(Action::InsertCharAtCursorPos('!'))
         .await
         .unwrap();
-    assert!(!restored.is_dirty());
-    assert_eq!(fs::read_to_string(&path).unwrap(), "external");
-}
+    harness.assert_buffer_contents("a!Bc");
 
-#[tokio::test]
-async fn test_dirty_background_session_writer_keeps_the_saved_baseline() {
-    let directory = tempfile::tempdir().unwrap();
-    let store = red::session::SessionStore::for_owner(directory.path(), "dirty-baseline").unwrap();
-    let mut harness = EditorHarness::with_content("abc");
-    harness.editor.set_session_store(store.clone());
     harness
-        .execute_action(Action::ReplaceCharsAtCursor {
-            character: 'z',
-            count: 1,
-        })
+        .execute_action(Action::RevertTransaction(transaction_id))
         .await
         .unwrap();
-    harness
-        .editor
-        .test_persist_session_snapshot(/*force*/ true, /*due*/ true);
-    let snapshot = store.load().unwrap();
-    assert_eq!(snapshot.buffers[0].contents, "zbc");
-    assert_eq!(snapshot.buffers[0].saved_contents.as_deref(), Some("abc"));
+
+    harness.assert_buffer_contents("a!bc");
+    assert!(!harness
+        .last_error()
+        .is_some_and(|message| message.contains("revert conflict")));
 }
 
 #[tokio::test]
-async fn test_dirty_clears_when_undo_returns_to_clean_revision() {
+async fn test_undo_does_not_create_new_undo_entries() {
     let mut harness = EditorHarness::with_content("abc");
-    assert!(!harness.is_dirty());
 
     harness
-        .execute_action(Action::InsertCharAtCursorPos('z'))
+        .execute_action(Action::DeleteCharAtCursorPos)
         .await
         .unwrap();
-    assert!(harness.is_dirty());
-
+    harness.execute_action(Action::Undo).await.unwrap();
     harness.execute_action(Action::Undo).await.unwrap();
     harness.assert_buffer_contents("abc");
-    assert!(!harness.is_dirty());
-
-    harness.execute_action(Action::Redo).await.unwrap();
-    harness.assert_buffer_contents("zabc");
-    assert!(harness.is_dirty());
 }
 
 #[tokio::test]
-async fn test_dirty_checkpoint_moves_after_save() {
-    let path = temp_file_path("dirty-save");
-    fs::write(&path, "abc").unwrap();
-
-    let buffer = Buffer::new(Some(path.clone()), "abc".to_string());
-    let mut harness = EditorHarness::with_buffer(buffer);
-
-    harness
-        .execute_action(Action::InsertCharAtCursorPos('z'))
-        .await
-        .unwrap();
-    assert!(harness.is_dirty());
-    harness.execute_action(Action::Save).await.unwrap();
-    assert!(!harness.is_dirty());
+async fn test_undo_indent_and_unindent() {
+    let mut harness = EditorHarness::with_content("line");
 
-    harness
-        .execute_action(Action::InsertCharAtCursorPos('y'))
-        .await
-        .unwrap();
-    assert!(harness.is_dirty());
+    harness.execute_action(Action::IndentLine).await.unwrap();
+    harness.assert_buffer_contents("    line");
     harness.execute_action(Action::Undo).await.unwrap();
-    assert!(!harness.is_dirty());
+    harness.assert_buffer_contents("line");
 
-    let _ = fs::remove_file(path);
+    harness.execute_action(Action::IndentLine).await.unwrap();
+    harness.execute_action(Action::UnindentLine).await.unwrap();
+    harness.assert_buffer_contents("line");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("    line");
 }
 
 #[tokio::test]
-async fn test_save_during_insert_keeps_saved_buffer_clean_on_escape() {
-    let path = temp_file_path("dirty-save-insert");
-    fs::write(&path, "abc").unwrap();
+async fn visual_indent_shifts_all_selected_lines_as_one_change() {
+    let buffer = Buffer::new(None, "one\n\n  two\n   ".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    let buffer = Buffer::new(Some(path.clone()), "abc".to_string());
-    let mut harness = EditorHarness::with_buffer(buffer);
+    type_normal_keys(&mut harness, "Vjjj>").await;
 
-    harness
-        .execute_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
-    harness.type_text("z").await.unwrap();
-    assert!(harness.is_dirty());
+    harness.assert_mode(Mode::Normal);
+    harness.assert_buffer_contents("    one\n\n      two\n       ");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("one\n\n  two\n   ");
+}
 
-    harness.execute_action(Action::Save).await.unwrap();
-    assert!(!harness.is_dirty());
+#[tokio::test]
+async fn visual_indent_supports_character_block_and_counted_selections() {
+    for mode in [Mode::Visual, Mode::VisualBlock] {
+        let buffer = Buffer::new(None, "one\ntwo".to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
+        harness
+            .execute_action(Action::EnterMode(mode))
+            .await
+            .unwrap();
+        harness.execute_action(Action::MoveDown).await.unwrap();
+        type_normal_keys(&mut harness, ">").await;
 
-    harness
-        .execute_action(Action::EnterMode(Mode::Normal))
-        .await
-        .unwrap();
-    harness.assert_buffer_contents("zabc");
-    assert!(!harness.is_dirty());
+        harness.assert_mode(Mode::Normal);
+        harness.assert_buffer_contents("    one\n    two");
+    }
 
-    let _ = fs::remove_file(path);
+    let buffer = Buffer::new(None, "one\ntwo".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "Vj2>").await;
+    harness.assert_buffer_contents("        one\n        two");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("one\ntwo");
 }
 
 #[tokio::test]
-async fn test_dirty_remains_after_undoing_past_saved_revision() {
-    let path = temp_file_path("dirty-past-save");
-    fs::write(&path, "abc").unwrap();
-
-    let buffer = Buffer::new(Some(path.clone()), "abc".to_string());
-    let mut harness = EditorHarness::with_buffer(buffer);
+async fn visual_unindent_shifts_all_selected_lines_as_one_change() {
+    let buffer = Buffer::new(None, "    one\n\n      two\n       ".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    harness
-        .execute_action(Action::InsertCharAtCursorPos('z'))
-        .await
-        .unwrap();
-    harness.execute_action(Action::Save).await.unwrap();
-    assert!(!harness.is_dirty());
+    type_normal_keys(&mut harness, "Vjjj<").await;
 
+    harness.assert_mode(Mode::Normal);
+    harness.assert_buffer_contents("one\n\n  two\n   ");
     harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("abc");
-    assert!(harness.is_dirty());
-
-    let _ = fs::remove_file(path);
+    harness.assert_buffer_contents("    one\n\n      two\n       ");
 }
 
-#[test]
-fn test_right_panel_reserves_editor_window_width() {
-    let mut harness = EditorHarness::with_content("abcdef");
+#[tokio::test]
+async fn visual_unindent_supports_character_block_and_counted_selections() {
+    for mode in [Mode::Visual, Mode::VisualBlock] {
+        let buffer = Buffer::new(None, "    one\n    two".to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
+        harness
+            .execute_action(Action::EnterMode(mode))
+            .await
+            .unwrap();
+        harness.execute_action(Action::MoveDown).await.unwrap();
+        type_normal_keys(&mut harness, "<").await;
 
-    harness.editor.test_create_panel(
-        "tree",
-        PanelConfig {
-            side: PanelSide::Right,
-            width: 20,
-            title: None,
-            composer: None,
-            surface: None,
-            border: None,
-            header_actions: Vec::new(),
-        },
-    );
+        harness.assert_mode(Mode::Normal);
+        harness.assert_buffer_contents("one\ntwo");
+    }
 
-    let (position, size) = harness.editor.test_active_window_bounds().unwrap();
-    assert_eq!(position.x, 0);
-    assert_eq!(size.0, 59);
+    let buffer = Buffer::new(None, "        one\n    two".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    type_normal_keys(&mut harness, "Vj2<").await;
+    harness.assert_buffer_contents("one\ntwo");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("        one\n    two");
 }
 
-#[test]
-fn focused_panel_hides_editor_cursor_until_focus_returns() {
-    let mut harness = EditorHarness::with_content("abcdef");
-    let editor_cursor = harness.render_cursor_position();
-    add_tree_panel(&mut harness);
+#[tokio::test]
+async fn gv_reselects_lines_after_visual_indent() {
+    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
 
-    assert!(harness.editor.test_focus_panel("tree"));
-    assert_eq!(harness.render_cursor_position(), None);
+    type_normal_keys(&mut harness, "Vj>gv").await;
 
-    harness.editor.test_close_panel("tree");
-    assert_eq!(harness.render_cursor_position(), editor_cursor);
+    harness.assert_mode(Mode::VisualLine);
+    assert_eq!(
+        harness.selection().map(|(_, y0, _, y1)| (y0, y1)),
+        Some((0, 1))
+    );
+    harness.assert_buffer_contents("    one\n    two\nthree");
 }
 
 #[tokio::test]
-async fn focused_panel_commandline_receives_text_before_panel_shortcuts() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
+async fn gv_reselects_lines_after_visual_unindent() {
+    let buffer = Buffer::new(None, "    one\n    two\nthree".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    add_tree_panel(&mut harness);
-    assert!(harness.editor.test_focus_panel("tree"));
-
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char(':'),
-            KeyModifiers::NONE,
-        )))
-        .await
-        .unwrap();
-    harness.assert_mode(Mode::Command);
 
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('q'),
-            KeyModifiers::NONE,
-        )))
-        .await
-        .unwrap();
+    type_normal_keys(&mut harness, "Vj<gv").await;
 
-    assert_eq!(harness.commandline_text(), "q");
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
+    harness.assert_mode(Mode::VisualLine);
+    assert_eq!(
+        harness.selection().map(|(_, y0, _, y1)| (y0, y1)),
+        Some((0, 1))
+    );
+    harness.assert_buffer_contents("one\ntwo\nthree");
 }
 
 #[tokio::test]
-async fn focused_panel_does_not_fall_through_to_editing_keys() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    add_tree_panel(&mut harness);
-    assert!(harness.editor.test_focus_panel("tree"));
-
+async fn test_undo_visual_char_line_and_block_delete() {
+    let mut harness = EditorHarness::with_content("abcde");
     harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('x'),
-            KeyModifiers::NONE,
-        )))
+        .execute_action(Action::EnterMode(Mode::Visual))
         .await
         .unwrap();
+    harness.execute_action(Action::MoveRight).await.unwrap();
+    harness.execute_action(Action::MoveRight).await.unwrap();
+    harness.execute_action(Action::Delete).await.unwrap();
+    harness.assert_buffer_contents("de");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("abcde");
 
-    harness.assert_buffer_contents("abcdef");
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
-}
-
-#[test]
-fn focused_panel_allows_global_builtin_hotkeys() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut config = default_key_config();
-    config
-        .keys
-        .normal
-        .insert("x".to_string(), KeyAction::Single(Action::FilePicker));
-    config
-        .keys
-        .normal
-        .insert("Meta-p".to_string(), KeyAction::Single(Action::FilePicker));
-    let mut harness = EditorHarness::with_config(buffer, config);
-    add_tree_panel(&mut harness);
-    assert!(harness.editor.test_focus_panel("tree"));
-
-    for (code, modifiers, expected) in [
-        (
-            KeyCode::Char('p'),
-            KeyModifiers::CONTROL,
-            Action::FilePicker,
-        ),
-        (KeyCode::Char('p'), KeyModifiers::META, Action::FilePicker),
-        (KeyCode::Char('z'), KeyModifiers::CONTROL, Action::Suspend),
-        (
-            KeyCode::Char('x'),
-            KeyModifiers::ALT,
-            Action::CommandPalette,
-        ),
-    ] {
-        let action = harness
-            .editor
-            .test_handle_event(Event::Key(KeyEvent::new(code, modifiers)))
-            .unwrap();
-        assert_eq!(action, Some(KeyAction::Single(expected)));
-    }
+    let mut harness = EditorHarness::with_content("one\ntwo\nthree");
+    harness
+        .execute_action(Action::EnterMode(Mode::VisualLine))
+        .await
+        .unwrap();
+    harness.execute_action(Action::MoveDown).await.unwrap();
+    harness.execute_action(Action::Delete).await.unwrap();
+    harness.assert_buffer_contents("three");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("one\ntwo\nthree");
 
-    let local = harness
-        .editor
-        .test_handle_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('x'),
-            KeyModifiers::NONE,
-        )))
+    let mut harness = EditorHarness::with_content("abc\ndef");
+    harness
+        .execute_action(Action::EnterMode(Mode::VisualBlock))
+        .await
         .unwrap();
-    assert!(matches!(
-        local,
-        Some(KeyAction::Multiple(actions))
-            if actions.iter().any(|action| matches!(
-                action,
-                Action::NotifyPlugins(name, payload)
-                    if name == "panel:event:tree" && payload["action"] == "x"
-            ))
-    ));
+    harness.execute_action(Action::MoveRight).await.unwrap();
+    harness.execute_action(Action::MoveDown).await.unwrap();
+    harness.execute_action(Action::Delete).await.unwrap();
+    harness.assert_buffer_contents("c\nf");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("abc\ndef");
 }
 
 #[tokio::test]
-async fn focused_panel_allows_explicitly_global_plugin_commands() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    add_tree_panel(&mut harness);
-    assert!(harness.editor.test_focus_panel("tree"));
-    let mut runtime = Runtime::new();
-    runtime
-        .load_plugin(
-            "navigation",
-            r#"
-                pub fn activate() {
-                    red::add_command("NeoTree", noop, Json { scope: "global" });
-                    red::add_command("LspDocumentSymbols", noop);
-                }
-                fn noop() {}
-            "#,
-        )
+async fn test_visual_block_insert_undoes_and_redoes_as_one_transaction() {
+    let mut harness = EditorHarness::with_content("impl\nfn\nColor\n}\n}");
+
+    harness
+        .execute_action(Action::EnterMode(Mode::VisualBlock))
         .await
         .unwrap();
-
-    let action = harness
-        .editor
-        .test_handle_event_with_runtime(
-            Event::Key(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL)),
-            &runtime,
-        )
+    for _ in 0..4 {
+        harness.execute_action(Action::MoveDown).await.unwrap();
+    }
+    harness.execute_action(Action::InsertBlock).await.unwrap();
+    harness
+        .execute_action(Action::InsertCharAtCursorPos(' '))
+        .await
+        .unwrap();
+    harness
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
         .unwrap();
 
-    assert_eq!(
-        action,
-        Some(KeyAction::Single(Action::PluginCommand(
-            "NeoTree".to_string()
-        )))
-    );
+    harness.assert_buffer_contents(" impl\n fn\n Color\n }\n }");
 
-    let contextual = harness
-        .editor
-        .test_handle_event_with_runtime(
-            Event::Key(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL)),
-            &runtime,
-        )
-        .unwrap();
-    assert!(matches!(
-        contextual,
-        Some(KeyAction::Multiple(actions))
-            if actions.iter().any(|action| matches!(
-                action,
-                Action::NotifyPlugins(name, payload)
-                    if name == "panel:event:tree" && payload["action"] == "Ctrl-t"
-            ))
-    ));
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("impl\nfn\nColor\n}\n}");
+
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents(" impl\n fn\n Color\n }\n }");
 }
 
-#[test]
-fn focused_row_panel_forwards_file_operation_keys_to_its_plugin() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut config = default_key_config();
-    config
-        .keys
-        .normal
-        .insert("Ctrl-r".to_string(), KeyAction::Single(Action::FilePicker));
-    let mut harness = EditorHarness::with_config(buffer, config);
-    add_tree_panel(&mut harness);
-    assert!(harness.editor.test_focus_panel("tree"));
+#[tokio::test]
+async fn visual_block_dollar_keeps_ragged_rows_through_append_gv_and_shift() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "ab\nabcd\nx\nabc".to_string()),
+        default_key_config(),
+    );
+    press_modified_key(&mut harness, KeyCode::Char('v'), KeyModifiers::CONTROL).await;
+    type_normal_keys(&mut harness, "jj$A;").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("ab;\nabcd;\nx;\nabc");
 
-    for (code, modifiers, expected) in [
-        (KeyCode::Char('a'), KeyModifiers::NONE, "a"),
-        (KeyCode::Char('x'), KeyModifiers::NONE, "x"),
-        (KeyCode::Tab, KeyModifiers::NONE, "Tab"),
-        (KeyCode::Char('r'), KeyModifiers::CONTROL, "Ctrl-r"),
-    ] {
-        let action = harness
-            .editor
-            .test_handle_event(Event::Key(KeyEvent::new(code, modifiers)))
-            .unwrap();
-        assert!(matches!(
-            action,
-            Some(KeyAction::Multiple(actions))
-                if actions.iter().any(|action| matches!(
-                    action,
-                    Action::NotifyPlugins(name, payload)
-                        if name == "panel:event:tree" && payload["action"] == expected
-                ))
-        ));
-    }
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "abcd\nx\nabcd".to_string()),
+        default_key_config(),
+    );
+    type_normal_keys(&mut harness, "l").await;
+    press_modified_key(&mut harness, KeyCode::Char('v'), KeyModifiers::CONTROL).await;
+    type_normal_keys(&mut harness, "jjA|").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("ab|cd\nx |\nab|cd");
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents("abcd\nx\nabcd");
+
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "ab\nabcd\nx".to_string()),
+        default_key_config(),
+    );
+    type_normal_keys(&mut harness, "l").await;
+    press_modified_key(&mut harness, KeyCode::Char('v'), KeyModifiers::CONTROL).await;
+    type_normal_keys(&mut harness, "j$y").await;
+    type_normal_keys(&mut harness, "gv>").await;
+    harness.assert_buffer_contents("    ab\n    abcd\nx");
+    type_normal_keys(&mut harness, "gvx").await;
+    harness.assert_buffer_contents("    a\n    a\nx");
+    type_normal_keys(&mut harness, "Gp").await;
+    harness.assert_buffer_contents("    a\n    a\nxb\n bcd");
 }
 
-#[test]
-fn focused_row_panel_pages_with_control_keys_and_page_keys() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut harness = EditorHarness::with_config_and_size(buffer, default_key_config(), 80, 10);
-    add_tree_panel(&mut harness);
+#[tokio::test]
+async fn blockwise_put_aligns_display_columns_and_pads_short_rows() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "12345\n1\n12345".to_string()),
+        default_key_config(),
+    );
     harness
         .editor
-        .test_update_panel("tree", numbered_tree_rows(30));
-    assert!(harness.editor.test_focus_panel("tree"));
+        .test_set_default_register(Content::blockwise("xy\nz\n".to_string()));
+    type_normal_keys(&mut harness, "llp").await;
+    harness.assert_buffer_contents("123xy45\n1  z\n12345");
+    harness.assert_cursor_at(3, 0);
 
-    for (code, modifiers, expected_top, expected_selected) in [
-        (KeyCode::Char('f'), KeyModifiers::CONTROL, "row-06.rs", 9),
-        (KeyCode::PageDown, KeyModifiers::NONE, "row-12.rs", 15),
-        (KeyCode::Char('b'), KeyModifiers::CONTROL, "row-06.rs", 10),
-        (KeyCode::PageUp, KeyModifiers::NONE, "row-00.rs", 4),
-    ] {
-        let action = harness
-            .editor
-            .test_handle_event(Event::Key(KeyEvent::new(code, modifiers)))
-            .unwrap();
-        assert!(matches!(action, Some(KeyAction::Multiple(_))));
-        assert!(harness.render_row(0).unwrap().starts_with(expected_top));
-        assert_eq!(
-            harness.editor.test_focused_panel_selected_index("tree"),
-            Some(expected_selected)
-        );
-    }
+    type_normal_keys(&mut harness, "uP").await;
+    harness.assert_buffer_contents("12xy345\n1 z\n12345");
+    harness.assert_cursor_at(2, 0);
 
-    harness
-        .editor
-        .test_handle_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('G'),
-            KeyModifiers::NONE,
-        )))
-        .unwrap();
-    assert!(harness.render_row(0).unwrap().starts_with("row-22.rs"));
-    assert_eq!(
-        harness.editor.test_focused_panel_selected_index("tree"),
-        Some(29)
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "日本語\nabcdef\nab".to_string()),
+        default_key_config(),
     );
-
     harness
         .editor
-        .test_handle_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('g'),
-            KeyModifiers::NONE,
-        )))
-        .unwrap();
-    assert!(harness.render_row(0).unwrap().starts_with("row-00.rs"));
-    assert_eq!(
-        harness.editor.test_focused_panel_selected_index("tree"),
-        Some(0)
-    );
+        .test_set_default_register(Content::blockwise("x\nyy\nz\n".to_string()));
+    type_normal_keys(&mut harness, "lP").await;
+    harness.assert_buffer_contents("日x 本語\nabyycdef\nabz");
 }
 
 #[tokio::test]
-async fn focused_agent_panel_keeps_global_leader_until_the_composer_is_focused() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut config = default_key_config();
-    config
-        .keys
-        .normal
-        .insert("q".to_string(), KeyAction::Single(Action::FilePicker));
-    config
-        .keys
-        .normal
-        .insert("Ctrl-c".to_string(), KeyAction::Single(Action::Suspend));
-    let mut harness = EditorHarness::with_config(buffer, config);
-    harness.editor.test_create_text_panel(
-        "agent",
-        PanelConfig {
-            side: PanelSide::Right,
-            width: 40,
-            title: Some("Agent".to_string()),
-            composer: Some(TextPanelComposerConfig {
-                placeholder: "Ask".to_string(),
-                rows: 2,
-            }),
-            surface: None,
-            border: None,
-            header_actions: Vec::new(),
-        },
-    );
-    assert!(harness.editor.test_focus_panel("agent"));
-    let mut runtime = Runtime::new();
-    runtime
-        .load_plugin(
-            "agent",
-            r#"
-                pub fn activate() {
-                    red::add_command("Agent", noop, Json { scope: "global" });
-                    red::add_command("AgentToggle", noop, Json { scope: "global" });
-                }
-                fn noop() {}
-            "#,
-        )
+async fn test_visual_block_insert_coalesces_replayed_change_notifications() {
+    let path = temp_file_path("visual-block-insert-lsp");
+    let lsp = RecordingLsp::default();
+    let events = lsp.events();
+    let config = Config::default();
+    let theme = Theme::default();
+    let buffer = Buffer::new(Some(path.clone()), "impl\nfn\nColor\n}\n}".to_string());
+    let mut editor =
+        Editor::test_with_size(Box::new(lsp), 80, 24, config, theme, vec![buffer]).unwrap();
+    editor.test_disable_terminal_output();
+    let mut harness = EditorHarness { editor };
+
+    harness
+        .execute_action(Action::EnterMode(Mode::VisualBlock))
         .await
         .unwrap();
-
-    let toggle = harness
-        .editor
-        .test_handle_event_with_runtime(
-            Event::Key(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::ALT)),
-            &runtime,
-        )
+    for _ in 0..4 {
+        harness.execute_action(Action::MoveDown).await.unwrap();
+    }
+    harness.execute_action(Action::InsertBlock).await.unwrap();
+    harness
+        .execute_action(Action::InsertCharAtCursorPos(' '))
+        .await
+        .unwrap();
+    harness
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
         .unwrap();
+
+    let did_change_count = events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|event| matches!(event, LspEvent::DidChange(file) if file == &path))
+        .count();
     assert_eq!(
-        toggle,
-        Some(KeyAction::Single(Action::PluginCommand(
-            "AgentToggle".to_string()
-        )))
+        did_change_count, 2,
+        "expected one notification for the initial insert and one coalesced replay notification"
     );
 
-    let action = harness
-        .editor
-        .test_handle_event_with_runtime(
-            Event::Key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE)),
-            &runtime,
-        )
-        .unwrap();
-    let Some(KeyAction::Nested(leader)) = action else {
-        panic!("expected Space to start the leader sequence from the conversation, got {action:?}");
+    let _ = fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_visual_block_insert_clears_selection_background_after_apply() {
+    let mut harness = EditorHarness::with_content("impl\nfn\nColor\n}\n}");
+    let selection_bg = Color::Rgb {
+        r: 12,
+        g: 34,
+        b: 56,
     };
-    assert_eq!(leader.len(), 8);
-    for global in ["A", "?", "d", "e", "m", "N", "P", "s"] {
-        assert!(
-            leader.contains_key(global),
-            "global leader branch {global:?} must remain available"
-        );
-    }
-    for contextual in [" ", "a", "n", "p", "b", "f", ".", "r"] {
-        assert!(
-            !leader.contains_key(contextual),
-            "contextual leader branch {contextual:?} must be filtered"
-        );
+    harness.editor.theme.selection_style = Some(Style {
+        bg: Some(selection_bg),
+        ..Default::default()
+    });
+
+    harness
+        .execute_action(Action::EnterMode(Mode::VisualBlock))
+        .await
+        .unwrap();
+    for _ in 0..4 {
+        harness.execute_action(Action::MoveDown).await.unwrap();
     }
-    assert_eq!(
-        leader.get("m"),
-        Some(&KeyAction::Single(Action::OpenMessages))
-    );
-    assert_eq!(
-        leader.get("N"),
-        Some(&KeyAction::Single(Action::OpenLatestInlineCompletion))
-    );
-    assert_eq!(
-        leader.get("A"),
-        Some(&KeyAction::Single(Action::PluginCommand(
-            "Agent".to_string()
-        )))
-    );
+    harness.execute_action(Action::InsertBlock).await.unwrap();
+    harness
+        .execute_action(Action::InsertCharAtCursorPos(' '))
+        .await
+        .unwrap();
+    harness
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
+        .unwrap();
 
-    for (code, modifiers, expected) in [
-        (KeyCode::Char('q'), KeyModifiers::NONE, "close"),
-        (KeyCode::Char('c'), KeyModifiers::CONTROL, "interrupt"),
-    ] {
-        let action = harness
-            .editor
-            .test_handle_event_with_runtime(Event::Key(KeyEvent::new(code, modifiers)), &runtime)
-            .unwrap();
-        assert!(matches!(
-            action,
-            Some(KeyAction::Multiple(actions))
-                if actions.iter().any(|action| matches!(
-                    action,
-                    Action::NotifyPlugins(name, payload)
-                        if name == "panel:event:agent" && payload["action"] == expected
-                ))
-        ));
+    for y in 0..5 {
+        for x in 0..40 {
+            assert_ne!(
+                harness.render_cell_bg(x, y).unwrap(),
+                Some(selection_bg),
+                "selection background leaked at ({x}, {y}) after block insert"
+            );
+        }
     }
+}
 
-    assert!(harness.editor.test_focus_text_panel_composer("agent"));
-    let action = harness
-        .editor
-        .test_handle_event(Event::Key(KeyEvent::new(
-            KeyCode::Char(' '),
-            KeyModifiers::NONE,
-        )))
+#[tokio::test]
+async fn test_visual_line_selection_uses_buffer_lines_after_scrolling() {
+    let content = (0..40)
+        .map(|line| format!("line-{line:02}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut harness = EditorHarness::with_content(&content);
+
+    harness
+        .execute_action(Action::SetCursor(0, 30))
+        .await
         .unwrap();
-    assert!(matches!(
-        action,
-        Some(KeyAction::Multiple(actions))
-            if actions.iter().any(|action| matches!(
-                action,
-                Action::NotifyPlugins(name, payload)
-                    if name == "panel:event:agent" && payload["action"] == "composer_input"
-            ))
-    ));
-    assert!(harness.render_cursor_position().is_some());
+    assert_eq!(harness.viewport_top(), 9);
+    harness.assert_cursor_at(0, 30);
 
-    let action = harness
-        .editor
-        .test_handle_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('c'),
-            KeyModifiers::CONTROL,
-        )))
+    harness
+        .execute_action(Action::EnterMode(Mode::VisualLine))
+        .await
         .unwrap();
-    assert!(matches!(
-        action,
-        Some(KeyAction::Multiple(actions))
-            if actions.iter().any(|action| matches!(
-                action,
-                Action::NotifyPlugins(name, payload)
-                    if name == "panel:event:agent" && payload["action"] == "interrupt"
-            ))
-    ));
-    assert!(harness.render_cursor_position().is_some());
+    harness.execute_action(Action::MoveDown).await.unwrap();
+    harness.execute_action(Action::Delete).await.unwrap();
+
+    let remaining = harness.buffer_contents();
+    assert!(
+        !remaining.contains("line-30\nline-31"),
+        "visual line delete should remove the scrolled-to buffer lines"
+    );
+    assert!(
+        remaining.contains("line-21"),
+        "visual line delete should not use viewport-relative rows as buffer lines"
+    );
 }
 
-#[test]
-fn agent_scrollback_tab_focuses_a_cursor_and_visual_yank_uses_the_clipboard() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
+#[tokio::test]
+async fn visual_line_delete_whole_scrolled_buffer_repositions_cursor_safely() {
+    let content = (0..40)
+        .map(|line| format!("line-{line:02}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let buffer = Buffer::new(None, content.clone());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+
+    type_normal_keys(&mut harness, "ggVGx").await;
+
+    harness.assert_buffer_contents("");
+    harness.assert_cursor_at(0, 0);
+    assert_eq!(harness.viewport_top(), 0);
+    harness.assert_mode(Mode::Normal);
+
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents(&content);
+}
+
+#[tokio::test]
+async fn visual_paste_replaces_whole_document_from_system_clipboard() {
+    let content = (0..40)
+        .map(|line| format!("line-{line:02}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let clipboard_text = Arc::new(Mutex::new(Some("replacement".to_string())));
+    let buffer = Buffer::new(None, content.clone());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    let clipboard_text = Arc::new(Mutex::new(None));
     harness
         .editor
         .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
             clipboard_text.clone(),
         )));
-    harness.editor.test_create_text_panel(
-        "agent",
-        PanelConfig {
-            side: PanelSide::Right,
-            width: 24,
-            title: Some("Agent".to_string()),
-            composer: Some(TextPanelComposerConfig {
-                placeholder: "Ask".to_string(),
-                rows: 2,
-            }),
-            ..PanelConfig::default()
-        },
-    );
-    harness.editor.test_update_text_panel(
-        "agent",
-        vec![TextPanelBlock {
-            id: "answer".to_string(),
-            kind: TextPanelBlockKind::Text,
-            format: TextPanelBlockFormat::Plain,
-            text: "alpha beta gamma".to_string(),
-        }],
+
+    type_normal_keys(&mut harness, "ggVGp").await;
+
+    harness.assert_buffer_contents("replacement");
+    harness.assert_cursor_at(0, 0);
+    harness.assert_mode(Mode::Normal);
+    assert_eq!(
+        clipboard_text.lock().unwrap().as_deref(),
+        Some(content.as_str())
     );
-    assert!(harness.editor.test_focus_text_panel_composer("agent"));
 
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents(&content);
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents("replacement");
+}
+
+#[tokio::test]
+async fn visual_line_paste_replaces_large_interior_selection_with_one_line() {
+    let content = (1..=20)
+        .map(|line| format!("line-{line:02}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let buffer = Buffer::new(None, content);
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
     harness
         .editor
-        .test_handle_event(Event::Key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE)))
+        .test_set_clipboard(Box::new(MemoryClipboardProvider::with_text(
+            "node dist/src/cli.js plan validate examples/hello-world.yaml",
+        )));
+    harness
+        .execute_action(Action::SetCursor(0, 2))
+        .await
         .unwrap();
-    assert!(harness.render_cursor_position().is_some());
-
-    for key in ['g', 'w', 'v', 'e', 'y'] {
-        harness
-            .editor
-            .test_handle_event(Event::Key(KeyEvent::new(
-                KeyCode::Char(key),
-                KeyModifiers::NONE,
-            )))
-            .unwrap();
+    harness
+        .execute_action(Action::EnterMode(Mode::VisualLine))
+        .await
+        .unwrap();
+    for _ in 0..5 {
+        harness.execute_action(Action::MoveDown).await.unwrap();
     }
 
-    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("beta"));
-    assert_eq!(harness.last_error(), Some("4 characters yanked"));
-    assert!(harness.render_cursor_position().is_some());
+    type_normal_keys(&mut harness, "p").await;
+
+    harness.assert_buffer_contents(
+        "line-01\nline-02\nnode dist/src/cli.js plan validate examples/hello-world.yaml\nline-09\nline-10\nline-11\nline-12\nline-13\nline-14\nline-15\nline-16\nline-17\nline-18\nline-19\nline-20",
+    );
+    harness.assert_cursor_at(0, 2);
+    harness.assert_mode(Mode::Normal);
 }
 
-#[test]
-fn agent_scrollback_visual_yank_copies_code_without_markdown_frame() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
+#[tokio::test]
+async fn visual_line_paste_replaces_small_interior_selection_with_many_lines() {
+    let content = (1..=8)
+        .map(|line| format!("line-{line:02}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let buffer = Buffer::new(None, content);
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    let clipboard_text = Arc::new(Mutex::new(None));
     harness
         .editor
-        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
-            clipboard_text.clone(),
+        .test_set_clipboard(Box::new(MemoryClipboardProvider::with_text(
+            "replacement-a\nreplacement-b\nreplacement-c",
         )));
-    harness.editor.test_create_text_panel(
-        "agent",
-        PanelConfig {
-            side: PanelSide::Right,
-            width: 24,
-            title: Some("Agent".to_string()),
-            composer: Some(TextPanelComposerConfig {
-                placeholder: "Ask".to_string(),
-                rows: 2,
-            }),
-            ..PanelConfig::default()
-        },
-    );
-    harness.editor.test_update_text_panel(
-        "agent",
-        vec![TextPanelBlock {
-            id: "answer".to_string(),
-            kind: TextPanelBlockKind::Agent,
-            format: TextPanelBlockFormat::Markdown,
-            text: "```bash\n/game\n```".to_string(),
-        }],
-    );
-    assert!(harness.editor.test_focus_text_panel_composer("agent"));
+    harness
+        .execute_action(Action::SetCursor(0, 2))
+        .await
+        .unwrap();
+    harness
+        .execute_action(Action::EnterMode(Mode::VisualLine))
+        .await
+        .unwrap();
 
-    for code in [
-        KeyCode::Tab,
-        KeyCode::Char('g'),
-        KeyCode::Char('v'),
-        KeyCode::Char('G'),
-        KeyCode::Char('y'),
-    ] {
-        harness
-            .editor
-            .test_handle_event(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
-            .unwrap();
-    }
+    type_normal_keys(&mut harness, "p").await;
 
-    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("/game"));
+    harness.assert_buffer_contents(
+        "line-01\nline-02\nreplacement-a\nreplacement-b\nreplacement-c\nline-04\nline-05\nline-06\nline-07\nline-08",
+    );
+    harness.assert_cursor_at(0, 2);
+    harness.assert_mode(Mode::Normal);
 }
 
-#[test]
-fn mouse_drag_selects_agent_scrollback_text_for_visual_yank() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
+#[tokio::test]
+async fn visual_uppercase_p_preserves_system_clipboard() {
+    let clipboard_text = Arc::new(Mutex::new(Some("replacement".to_string())));
+    let buffer = Buffer::new(None, "one\ntwo\nthree".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    let clipboard_text = Arc::new(Mutex::new(None));
     harness
         .editor
         .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
             clipboard_text.clone(),
         )));
-    harness.editor.test_create_text_panel(
-        "agent",
-        PanelConfig {
-            side: PanelSide::Right,
-            width: 24,
-            title: Some("Agent".to_string()),
-            composer: Some(TextPanelComposerConfig {
-                placeholder: "Ask".to_string(),
-                rows: 2,
-            }),
-            ..PanelConfig::default()
-        },
-    );
-    harness.editor.test_update_text_panel(
-        "agent",
-        vec![TextPanelBlock {
-            id: "answer".to_string(),
-            kind: TextPanelBlockKind::Text,
-            format: TextPanelBlockFormat::Plain,
-            text: "alpha beta gamma".to_string(),
-        }],
+
+    type_normal_keys(&mut harness, "ggVGP").await;
+
+    harness.assert_buffer_contents("replacement");
+    harness.assert_mode(Mode::Normal);
+    assert_eq!(
+        clipboard_text.lock().unwrap().as_deref(),
+        Some("replacement")
     );
+}
 
-    for kind in [
-        MouseEventKind::Down(MouseButton::Left),
-        MouseEventKind::Drag(MouseButton::Left),
-        MouseEventKind::Up(MouseButton::Left),
-    ] {
-        let column = if matches!(kind, MouseEventKind::Down(_)) {
-            57
-        } else {
-            61
-        };
-        harness
-            .editor
-            .test_handle_event(Event::Mouse(MouseEvent {
-                kind,
-                column,
-                row: 2,
-                modifiers: KeyModifiers::NONE,
-            }))
-            .unwrap();
-    }
+#[tokio::test]
+async fn visual_paste_replaces_and_captures_a_unicode_grapheme() {
+    let family = "👨‍👩‍👧‍👦";
+    let clipboard_text = Arc::new(Mutex::new(Some("X".to_string())));
+    let buffer = Buffer::new(None, format!("a{family}b"));
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
     harness
         .editor
-        .test_handle_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('y'),
-            KeyModifiers::NONE,
-        )))
+        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
+            clipboard_text.clone(),
+        )));
+    harness.execute_action(Action::MoveRight).await.unwrap();
+    harness
+        .execute_action(Action::EnterMode(Mode::Visual))
+        .await
         .unwrap();
 
-    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("alpha"));
+    type_normal_keys(&mut harness, "p").await;
+
+    harness.assert_buffer_contents("aXb");
+    harness.assert_cursor_at(1, 0);
+    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some(family));
 }
 
-#[test]
-fn focused_agent_composer_keeps_control_j_local_and_routes_other_navigation() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.editor.test_create_text_panel(
-        "agent",
-        PanelConfig {
-            side: PanelSide::Right,
-            width: 40,
-            title: Some("Agent".to_string()),
-            composer: Some(TextPanelComposerConfig {
-                placeholder: "Ask".to_string(),
-                rows: 2,
-            }),
-            ..PanelConfig::default()
-        },
-    );
-    assert!(harness.editor.test_focus_text_panel_composer("agent"));
+#[tokio::test]
+async fn visual_paste_matches_selection_and_register_kinds() {
+    let sources = [
+        Content::charwise("Q".to_string()),
+        Content::linewise("X\nY\n".to_string()),
+        Content::blockwise("XY\nUV\n".to_string()),
+    ];
 
-    for (code, modifiers, expected) in [
-        (KeyCode::Char('h'), KeyModifiers::CONTROL, "up"),
-        (KeyCode::Char('j'), KeyModifiers::CONTROL, "composer_input"),
-        (KeyCode::Char('k'), KeyModifiers::CONTROL, "up"),
-        (KeyCode::Char('g'), KeyModifiers::CONTROL, "top"),
-        (
-            KeyCode::Char('G'),
-            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
-            "bottom",
-        ),
-    ] {
-        let action = harness
-            .editor
-            .test_handle_event(Event::Key(KeyEvent::new(code, modifiers)))
+    for ((source, expected), cursor) in sources
+        .iter()
+        .cloned()
+        .zip([
+            "pre Q post\nsecond\nthird",
+            "pre \nX\nY\n post\nsecond\nthird",
+            "pre XY post\nsecoUVnd\nthird",
+        ])
+        .zip([(4, 0), (0, 1), (4, 0)])
+    {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, "pre abc post\nsecond\nthird".to_string()),
+            default_key_config(),
+        );
+        harness.editor.test_set_default_register(source);
+        for _ in 0..4 {
+            harness.execute_action(Action::MoveRight).await.unwrap();
+        }
+        harness
+            .execute_action(Action::EnterMode(Mode::Visual))
+            .await
             .unwrap();
-        assert!(matches!(
-            action,
-            Some(KeyAction::Multiple(actions))
-                if actions.iter().any(|action| matches!(
-                    action,
-                    Action::NotifyPlugins(name, payload)
-                        if name == "panel:event:agent" && payload["action"] == expected
-                ))
-        ));
+        for _ in 0..2 {
+            harness.execute_action(Action::MoveRight).await.unwrap();
+        }
+
+        harness.execute_action(Action::Paste).await.unwrap();
+
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(cursor.0, cursor.1);
     }
 
-    let action = harness
-        .editor
-        .test_handle_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('j'),
-            KeyModifiers::NONE,
-        )))
-        .unwrap();
-    assert!(matches!(
-        action,
-        Some(KeyAction::Multiple(actions))
-            if actions.iter().any(|action| matches!(
-                action,
-                Action::NotifyPlugins(name, payload)
-                    if name == "panel:event:agent" && payload["action"] == "composer_input"
-            ))
-    ));
+    for (source, expected) in
+        sources
+            .iter()
+            .cloned()
+            .zip(["one\nQ\nfour", "one\nX\nY\nfour", "one\nXY\nUV\nfour"])
+    {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, "one\ntwo\nthree\nfour".to_string()),
+            default_key_config(),
+        );
+        harness.editor.test_set_default_register(source);
+        harness.execute_action(Action::MoveDown).await.unwrap();
+        harness
+            .execute_action(Action::EnterMode(Mode::VisualLine))
+            .await
+            .unwrap();
+        harness.execute_action(Action::MoveDown).await.unwrap();
+
+        harness.execute_action(Action::Paste).await.unwrap();
+
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(0, 1);
+    }
+
+    for ((source, expected), cursor) in sources
+        .into_iter()
+        .zip([
+            "Q11zz\nQ22yy\nQ33xx",
+            "11zz\n22yy\n33xx\nX\nY",
+            "XY11zz\nUV22yy\n33xx",
+        ])
+        .zip([(0, 0), (0, 3), (0, 0)])
+    {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, "aa11zz\nbb22yy\ncc33xx".to_string()),
+            default_key_config(),
+        );
+        harness.editor.test_set_default_register(source);
+        harness
+            .execute_action(Action::EnterMode(Mode::VisualBlock))
+            .await
+            .unwrap();
+        harness.execute_action(Action::MoveRight).await.unwrap();
+        harness.execute_action(Action::MoveDown).await.unwrap();
+        harness.execute_action(Action::MoveDown).await.unwrap();
+
+        harness.execute_action(Action::Paste).await.unwrap();
+
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(cursor.0, cursor.1);
+    }
 }
 
 #[tokio::test]
-async fn escape_from_focused_panel_restores_editor_cursor() {
-    let mut harness = EditorHarness::with_content("abcdef");
-    add_tree_panel(&mut harness);
-    let editor_cursor = harness.render_cursor_position();
-    assert!(harness.editor.test_focus_panel("tree"));
-
+async fn visual_paste_emits_one_change_notification() {
+    let path = temp_file_path("visual-paste-lsp");
+    let lsp = RecordingLsp::default();
+    let events = lsp.events();
+    let buffer = Buffer::new(Some(path.clone()), "one\ntwo\nthree".to_string());
+    let mut editor = Editor::test_with_size(
+        Box::new(lsp),
+        80,
+        24,
+        default_key_config(),
+        Theme::default(),
+        vec![buffer],
+    )
+    .unwrap();
+    editor.test_disable_terminal_output();
+    editor.test_set_clipboard(Box::new(MemoryClipboardProvider::default()));
+    editor.test_set_default_register(Content::charwise("replacement".to_string()));
+    let mut harness = EditorHarness { editor };
     harness
-        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+        .execute_action(Action::EnterMode(Mode::VisualLine))
         .await
         .unwrap();
+    harness.execute_action(Action::MoveDown).await.unwrap();
 
-    assert_eq!(harness.editor.test_focused_panel_id(), None);
-    assert_eq!(harness.render_cursor_position(), editor_cursor);
+    harness.execute_action(Action::Paste).await.unwrap();
+
+    let did_change_count = events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|event| matches!(event, LspEvent::DidChange(file) if file == &path))
+        .count();
+    assert_eq!(did_change_count, 1);
+
+    let _ = fs::remove_file(path);
 }
 
 #[tokio::test]
-async fn next_and_previous_window_cycle_through_focused_panels() {
-    let mut harness = EditorHarness::with_content("abcdef");
-    add_tree_panel(&mut harness);
+async fn bracketed_paste_inserts_multiline_text_once() {
+    let path = temp_file_path("bracketed-paste-lsp");
+    let lsp = RecordingLsp::default();
+    let events = lsp.events();
+    let buffer = Buffer::new(Some(path.clone()), "\n".to_string());
+    let mut editor = Editor::test_with_size(
+        Box::new(lsp),
+        80,
+        24,
+        default_key_config(),
+        Theme::default(),
+        vec![buffer],
+    )
+    .unwrap();
+    editor.test_disable_terminal_output();
+    let mut harness = EditorHarness { editor };
+    harness
+        .execute_action(Action::EnterMode(Mode::Insert))
+        .await
+        .unwrap();
 
-    harness.execute_action(Action::NextWindow).await.unwrap();
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
+    harness
+        .execute_event(Event::Paste("alpha\r\nbeta 👋".to_string()))
+        .await
+        .unwrap();
 
-    harness.execute_action(Action::NextWindow).await.unwrap();
-    assert_eq!(harness.editor.test_focused_panel_id(), None);
+    harness.assert_buffer_contents("alpha\nbeta 👋\n");
+    harness.assert_cursor_at(6, 1);
+    let did_change_count = events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|event| matches!(event, LspEvent::DidChange(file) if file == &path))
+        .count();
+    assert_eq!(did_change_count, 1);
 
     harness
-        .execute_action(Action::PreviousWindow)
+        .execute_action(Action::EnterMode(Mode::Normal))
         .await
         .unwrap();
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("\n");
+
+    let _ = fs::remove_file(path);
 }
 
 #[tokio::test]
-async fn focused_vertical_panels_resize_with_vim_width_chords() {
-    for side in [PanelSide::Left, PanelSide::Right] {
-        let buffer = Buffer::new(None, "first\nsecond\n".to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-        harness.editor.test_create_panel(
-            "inspector",
-            PanelConfig {
-                side,
-                width: 20,
-                ..PanelConfig::default()
-            },
-        );
-        assert!(harness.editor.test_focus_panel("inspector"));
-
-        execute_window_chord(&mut harness, '>').await;
-        assert_eq!(
-            harness.editor.test_panel_layout("inspector"),
-            Some((side, 21)),
-        );
-        assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
+async fn disabled_lsp_skips_document_change_notifications() {
+    let path = temp_file_path("disabled-lsp-change");
+    let lsp = RecordingLsp::default();
+    let events = lsp.events();
+    let mut config = default_key_config();
+    config.lsp.enabled = false;
+    let buffer = Buffer::new(Some(path.clone()), "text".to_string());
+    let mut editor = Editor::test_with_size(
+        Box::new(lsp),
+        80,
+        24,
+        config,
+        Theme::default(),
+        vec![buffer],
+    )
+    .unwrap();
+    editor.test_disable_terminal_output();
+    let mut harness = EditorHarness { editor };
 
-        execute_window_chord(&mut harness, '<').await;
-        assert_eq!(
-            harness.editor.test_panel_layout("inspector"),
-            Some((side, 20)),
-        );
+    harness
+        .execute_action(Action::InsertCharAtCursorPos('x'))
+        .await
+        .unwrap();
 
-        execute_window_chord(&mut harness, '+').await;
-        assert_eq!(
-            harness.editor.test_panel_layout("inspector"),
-            Some((side, 20)),
-            "height commands must not resize a vertical pane or the hidden editor window",
-        );
-    }
+    harness.assert_buffer_contents("xtext");
+    assert!(events.lock().unwrap().iter().all(|event| {
+        !matches!(event, LspEvent::DidOpen(file) | LspEvent::DidChange(file) if file == &path)
+    }));
+}
+
+#[tokio::test]
+async fn bracketed_paste_uses_first_line_in_command_mode() {
+    let mut harness = EditorHarness::with_content("safe");
+    harness.set_commandline(Mode::Command, "");
+
+    harness
+        .execute_event(Event::Paste("q\r\nj".to_string()))
+        .await
+        .unwrap();
+
+    assert_eq!(harness.commandline_text(), "q");
+    harness.assert_mode(Mode::Command);
+}
+
+#[tokio::test]
+async fn bracketed_paste_uses_first_line_in_search_mode() {
+    let mut harness = EditorHarness::with_content("alpha beta");
+    harness
+        .execute_action(Action::EnterSearch(SearchDirection::Forward))
+        .await
+        .unwrap();
+
+    harness
+        .execute_event(Event::Paste("alpha\r\nbeta".to_string()))
+        .await
+        .unwrap();
+
+    assert_eq!(harness.commandline_text(), "alpha");
+    harness.assert_mode(Mode::Search);
+}
+
+#[tokio::test]
+async fn bracketed_paste_is_ignored_in_normal_mode() {
+    let mut harness = EditorHarness::with_content("safe");
+
+    harness
+        .execute_event(Event::Paste("iddanger".to_string()))
+        .await
+        .unwrap();
+
+    harness.assert_buffer_contents("safe");
+    harness.assert_mode(Mode::Normal);
+}
+
+#[tokio::test]
+async fn bracketed_paste_cancels_pending_normal_key_sequence() {
+    let mut harness = EditorHarness::with_content("safe word");
+    type_normal_keys(&mut harness, "d").await;
+    assert!(harness.is_waiting_for_key_sequence());
+
+    harness
+        .execute_event(Event::Paste("ignored".to_string()))
+        .await
+        .unwrap();
+    type_normal_keys(&mut harness, "w").await;
+
+    assert!(!harness.is_waiting_for_key_sequence());
+    harness.assert_buffer_contents("safe word");
+    harness.assert_mode(Mode::Normal);
+}
+
+#[tokio::test]
+async fn test_undo_paste_and_paste_before() {
+    let mut harness = EditorHarness::with_content("hello world");
+
+    harness
+        .execute_action(Action::EnterMode(Mode::Visual))
+        .await
+        .unwrap();
+    for _ in 0..5 {
+        harness.execute_action(Action::MoveRight).await.unwrap();
+    }
+    harness.execute_action(Action::Delete).await.unwrap();
+    harness
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("world");
+    harness.execute_action(Action::MoveToLineEnd).await.unwrap();
+    harness.execute_action(Action::Paste).await.unwrap();
+    harness.assert_buffer_contents("worldhello ");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("world");
+
+    harness
+        .execute_action(Action::MoveToLineStart)
+        .await
+        .unwrap();
+    harness.execute_action(Action::PasteBefore).await.unwrap();
+    harness.assert_buffer_contents("hello world");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("world");
+}
+
+#[tokio::test]
+async fn test_undo_insert_text_action() {
+    let mut harness = EditorHarness::with_content("abc");
+    let content = Content::charwise("ZZ".to_string());
+
+    harness
+        .execute_action(Action::InsertText {
+            x: 1,
+            y: 0,
+            content,
+        })
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("aZZbc");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("abc");
+}
+
+#[tokio::test]
+async fn test_undo_history_is_per_buffer() {
+    let lsp = Box::new(MockLsp) as Box<dyn LspClient + Send>;
+    let config = Config::default();
+    let theme = Theme::default();
+    let buffers = vec![
+        Buffer::new(None, "one".to_string()),
+        Buffer::new(None, "two".to_string()),
+    ];
+    let mut editor = Editor::test_with_size(lsp, 80, 24, config, theme, buffers).unwrap();
+    editor.test_disable_terminal_output();
+    let mut harness = EditorHarness { editor };
+
+    harness
+        .execute_action(Action::DeleteCharAtCursorPos)
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("ne");
+    harness.execute_action(Action::NextBuffer).await.unwrap();
+    harness.assert_buffer_contents("two");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("two");
+    harness
+        .execute_action(Action::PreviousBuffer)
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("ne");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("one");
+}
+
+#[tokio::test]
+async fn test_buffer_delete_removes_current_buffer_from_list() {
+    let lsp = Box::new(MockLsp) as Box<dyn LspClient + Send>;
+    let config = Config::default();
+    let theme = Theme::default();
+    let buffers = vec![
+        Buffer::new(Some("one.rs".to_string()), "one".to_string()),
+        Buffer::new(Some("two.rs".to_string()), "two".to_string()),
+        Buffer::new(Some("three.rs".to_string()), "three".to_string()),
+    ];
+    let mut editor = Editor::test_with_size(lsp, 80, 24, config, theme, buffers).unwrap();
+    editor.test_disable_terminal_output();
+    let mut harness = EditorHarness { editor };
+
+    harness.execute_action(Action::NextBuffer).await.unwrap();
+    harness
+        .execute_action(Action::Command("bd".to_string()))
+        .await
+        .unwrap();
+
+    assert_eq!(harness.buffer_names(), vec!["one.rs", "three.rs"]);
+    assert_eq!(harness.current_buffer_index(), 1);
+    harness.assert_buffer_contents("three");
+}
+
+#[tokio::test]
+async fn test_buffer_delete_requires_force_for_dirty_buffer() {
+    let lsp = Box::new(MockLsp) as Box<dyn LspClient + Send>;
+    let config = Config::default();
+    let theme = Theme::default();
+    let buffers = vec![
+        Buffer::new(Some("one.rs".to_string()), "one".to_string()),
+        Buffer::new(Some("two.rs".to_string()), "two".to_string()),
+    ];
+    let mut editor = Editor::test_with_size(lsp, 80, 24, config, theme, buffers).unwrap();
+    editor.test_disable_terminal_output();
+    let mut harness = EditorHarness { editor };
+
+    harness
+        .execute_action(Action::DeleteCharAtCursorPos)
+        .await
+        .unwrap();
+    harness
+        .execute_action(Action::Command("bd".to_string()))
+        .await
+        .unwrap();
+
+    assert_eq!(harness.buffer_names(), vec!["one.rs", "two.rs"]);
+    assert_eq!(
+        harness.last_error(),
+        Some("No write since last change (add ! to override)")
+    );
+    harness.assert_buffer_contents("ne");
+
+    harness
+        .execute_action(Action::Command("bd!".to_string()))
+        .await
+        .unwrap();
+
+    assert_eq!(harness.buffer_names(), vec!["two.rs"]);
+    harness.assert_buffer_contents("two");
+}
+
+#[tokio::test]
+async fn test_preview_theme_reports_missing_theme_without_changing_buffer() {
+    let mut harness = EditorHarness::with_content("abc");
+
+    harness
+        .execute_action(Action::PreviewTheme(
+            "definitely-missing-theme.json".to_string(),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        harness.last_error(),
+        Some("Theme file definitely-missing-theme.json not found")
+    );
+    harness.assert_buffer_contents("abc");
+}
+
+#[tokio::test]
+async fn test_dirty_clears_after_manual_same_length_replacement() {
+    let mut harness = EditorHarness::with_content("abc");
+    for (character, dirty) in [('z', true), ('a', false)] {
+        harness
+            .execute_action(Action::ReplaceCharsAtCursor {
+                character,
+                count: 1,
+            })
+            .await
+            .unwrap();
+        assert_eq!(harness.is_dirty(), dirty);
+    }
+    harness.assert_buffer_contents("abc");
+    assert!(harness.editor.test_current_buffer().undo_history.is_dirty());
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("zbc");
+    assert!(harness.is_dirty());
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents("abc");
+    assert!(!harness.is_dirty());
+}
+
+#[tokio::test]
+async fn test_dirty_clears_before_leaving_insert_mode() {
+    let mut harness =
+        EditorHarness::with_config(Buffer::new(None, "abc".to_string()), default_key_config());
+    harness
+        .execute_action(Action::EnterMode(Mode::Insert))
+        .await
+        .unwrap();
+    harness.type_text("z").await.unwrap();
+    assert!(harness.is_dirty());
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Backspace,
+            KeyModifiers::NONE,
+        )))
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("abc");
+    assert!(harness.is_insert());
+    assert!(!harness.is_dirty());
+    harness
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
+        .unwrap();
+    assert!(!harness.is_dirty());
+}
+
+#[tokio::test]
+async fn test_dirty_session_baseline_survives_external_disk_changes() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("saved.txt");
+    fs::write(&path, "abc").unwrap();
+    let buffer = Buffer::new(Some(path.to_string_lossy().into_owned()), "abc".into());
+    let mut source = EditorHarness::with_buffer(buffer);
+    source
+        .execute_action(Action::ReplaceCharsAtCursor {
+            character: 'z',
+            count: 1,
+        })
+        .await
+        .unwrap();
+    fs::write(&path, "external").unwrap();
+    let snapshot = source.editor.test_session_snapshot();
+    assert_eq!(snapshot.buffers[0].saved_contents.as_deref(), Some("abc"));
+    assert_eq!(
+        snapshot.buffers[0].disk_contents.as_deref(),
+        Some("external")
+    );
+    let encoded = serde_json::to_vec(&snapshot).unwrap();
+    let snapshot = serde_json::from_slice(&encoded).unwrap();
+    let mut buffers = Editor::buffers_from_session_snapshot(&snapshot);
+    let mut restored = EditorHarness::with_buffer(buffers.remove(0));
+    assert!(restored.is_dirty());
+    restored
+        .execute_action(Action::ReplaceCharsAtCursor {
+            character: 'a',
+            count: 1,
+        })
+        .await
+        .unwrap();
+    assert!(!restored.is_dirty());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "external");
+}
+
+#[tokio::test]
+async fn test_dirty_background_session_writer_keeps_the_saved_baseline() {
+    let directory = tempfile::tempdir().unwrap();
+    let store = red::session::SessionStore::for_owner(directory.path(), "dirty-baseline").unwrap();
+    let mut harness = EditorHarness::with_content("abc");
+    harness.editor.set_session_store(store.clone());
+    harness
+        .execute_action(Action::ReplaceCharsAtCursor {
+            character: 'z',
+            count: 1,
+        })
+        .await
+        .unwrap();
+    harness
+        .editor
+        .test_persist_session_snapshot(/*force*/ true, /*due*/ true);
+    let snapshot = store.load().unwrap();
+    assert_eq!(snapshot.buffers[0].contents, "zbc");
+    assert_eq!(snapshot.buffers[0].saved_contents.as_deref(), Some("abc"));
+}
+
+#[tokio::test]
+async fn test_dirty_clears_when_undo_returns_to_clean_revision() {
+    let mut harness = EditorHarness::with_content("abc");
+    assert!(!harness.is_dirty());
+
+    harness
+        .execute_action(Action::InsertCharAtCursorPos('z'))
+        .await
+        .unwrap();
+    assert!(harness.is_dirty());
+
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("abc");
+    assert!(!harness.is_dirty());
+
+    harness.execute_action(Action::Redo).await.unwrap();
+    harness.assert_buffer_contents("zabc");
+    assert!(harness.is_dirty());
+}
+
+#[tokio::test]
+async fn test_dirty_checkpoint_moves_after_save() {
+    let path = temp_file_path("dirty-save");
+    fs::write(&path, "abc").unwrap();
+
+    let buffer = Buffer::new(Some(path.clone()), "abc".to_string());
+    let mut harness = EditorHarness::with_buffer(buffer);
+
+    harness
+        .execute_action(Action::InsertCharAtCursorPos('z'))
+        .await
+        .unwrap();
+    assert!(harness.is_dirty());
+    harness.execute_action(Action::Save).await.unwrap();
+    assert!(!harness.is_dirty());
+
+    harness
+        .execute_action(Action::InsertCharAtCursorPos('y'))
+        .await
+        .unwrap();
+    assert!(harness.is_dirty());
+    harness.execute_action(Action::Undo).await.unwrap();
+    assert!(!harness.is_dirty());
+
+    let _ = fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_save_during_insert_keeps_saved_buffer_clean_on_escape() {
+    let path = temp_file_path("dirty-save-insert");
+    fs::write(&path, "abc").unwrap();
+
+    let buffer = Buffer::new(Some(path.clone()), "abc".to_string());
+    let mut harness = EditorHarness::with_buffer(buffer);
+
+    harness
+        .execute_action(Action::EnterMode(Mode::Insert))
+        .await
+        .unwrap();
+    harness.type_text("z").await.unwrap();
+    assert!(harness.is_dirty());
+
+    harness.execute_action(Action::Save).await.unwrap();
+    assert!(!harness.is_dirty());
+
+    harness
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
+        .unwrap();
+    harness.assert_buffer_contents("zabc");
+    assert!(!harness.is_dirty());
+
+    let _ = fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_dirty_remains_after_undoing_past_saved_revision() {
+    let path = temp_file_path("dirty-past-save");
+    fs::write(&path, "abc").unwrap();
+
+    let buffer = Buffer::new(Some(path.clone()), "abc".to_string());
+    let mut harness = EditorHarness::with_buffer(buffer);
+
+    harness
+        .execute_action(Action::InsertCharAtCursorPos('z'))
+        .await
+        .unwrap();
+    harness.execute_action(Action::Save).await.unwrap();
+    assert!(!harness.is_dirty());
+
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("abc");
+    assert!(harness.is_dirty());
+
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_right_panel_reserves_editor_window_width() {
+    let mut harness = EditorHarness::with_content("abcdef");
+
+    harness.editor.test_create_panel(
+        "tree",
+        PanelConfig {
+            side: PanelSide::Right,
+            width: 20,
+            title: None,
+            composer: None,
+            surface: None,
+            border: None,
+            header_actions: Vec::new(),
+        },
+    );
+
+    let (position, size) = harness.editor.test_active_window_bounds().unwrap();
+    assert_eq!(position.x, 0);
+    assert_eq!(size.0, 59);
+}
+
+#[test]
+fn focused_panel_hides_editor_cursor_until_focus_returns() {
+    let mut harness = EditorHarness::with_content("abcdef");
+    let editor_cursor = harness.render_cursor_position();
+    add_tree_panel(&mut harness);
+
+    assert!(harness.editor.test_focus_panel("tree"));
+    assert_eq!(harness.render_cursor_position(), None);
+
+    harness.editor.test_close_panel("tree");
+    assert_eq!(harness.render_cursor_position(), editor_cursor);
+}
+
+#[tokio::test]
+async fn focused_panel_commandline_receives_text_before_panel_shortcuts() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    add_tree_panel(&mut harness);
+    assert!(harness.editor.test_focus_panel("tree"));
+
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char(':'),
+            KeyModifiers::NONE,
+        )))
+        .await
+        .unwrap();
+    harness.assert_mode(Mode::Command);
+
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('q'),
+            KeyModifiers::NONE,
+        )))
+        .await
+        .unwrap();
+
+    assert_eq!(harness.commandline_text(), "q");
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
+}
+
+#[tokio::test]
+async fn focused_panel_does_not_fall_through_to_editing_keys() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    add_tree_panel(&mut harness);
+    assert!(harness.editor.test_focus_panel("tree"));
+
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('x'),
+            KeyModifiers::NONE,
+        )))
+        .await
+        .unwrap();
+
+    harness.assert_buffer_contents("abcdef");
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
+}
+
+#[test]
+fn focused_panel_allows_global_builtin_hotkeys() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut config = default_key_config();
+    config
+        .keys
+        .normal
+        .insert("x".to_string(), KeyAction::Single(Action::FilePicker));
+    config
+        .keys
+        .normal
+        .insert("Meta-p".to_string(), KeyAction::Single(Action::FilePicker));
+    let mut harness = EditorHarness::with_config(buffer, config);
+    add_tree_panel(&mut harness);
+    assert!(harness.editor.test_focus_panel("tree"));
+
+    for (code, modifiers, expected) in [
+        (
+            KeyCode::Char('p'),
+            KeyModifiers::CONTROL,
+            Action::FilePicker,
+        ),
+        (KeyCode::Char('p'), KeyModifiers::META, Action::FilePicker),
+        (KeyCode::Char('z'), KeyModifiers::CONTROL, Action::Suspend),
+        (
+            KeyCode::Char('x'),
+            KeyModifiers::ALT,
+            Action::CommandPalette,
+        ),
+    ] {
+        let action = harness
+            .editor
+            .test_handle_event(Event::Key(KeyEvent::new(code, modifiers)))
+            .unwrap();
+        assert_eq!(action, Some(KeyAction::Single(expected)));
+    }
+
+    let local = harness
+        .editor
+        .test_handle_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('x'),
+            KeyModifiers::NONE,
+        )))
+        .unwrap();
+    assert!(matches!(
+        local,
+        Some(KeyAction::Multiple(actions))
+            if actions.iter().any(|action| matches!(
+                action,
+                Action::NotifyPlugins(name, payload)
+                    if name == "panel:event:tree" && payload["action"] == "x"
+            ))
+    ));
+}
+
+#[tokio::test]
+async fn focused_panel_allows_explicitly_global_plugin_commands() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    add_tree_panel(&mut harness);
+    assert!(harness.editor.test_focus_panel("tree"));
+    let mut runtime = Runtime::new();
+    runtime
+        .load_plugin(
+            "navigation",
+            r#"
+                pub fn activate() {
+                    red::add_command("NeoTree", noop, Json { scope: "global" });
+                    red::add_command("LspDocumentSymbols", noop);
+                }
+                fn noop() {}
+            "#,
+        )
+        .await
+        .unwrap();
+
+    let action = harness
+        .editor
+        .test_handle_event_with_runtime(
+            Event::Key(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL)),
+            &runtime,
+        )
+        .unwrap();
+
+    assert_eq!(
+        action,
+        Some(KeyAction::Single(Action::PluginCommand(
+            "NeoTree".to_string()
+        )))
+    );
+
+    let contextual = harness
+        .editor
+        .test_handle_event_with_runtime(
+            Event::Key(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL)),
+            &runtime,
+        )
+        .unwrap();
+    assert!(matches!(
+        contextual,
+        Some(KeyAction::Multiple(actions))
+            if actions.iter().any(|action| matches!(
+                action,
+                Action::NotifyPlugins(name, payload)
+                    if name == "panel:event:tree" && payload["action"] == "Ctrl-t"
+            ))
+    ));
+}
+
+#[test]
+fn focused_row_panel_forwards_file_operation_keys_to_its_plugin() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut config = default_key_config();
+    config
+        .keys
+        .normal
+        .insert("Ctrl-r".to_string(), KeyAction::Single(Action::FilePicker));
+    let mut harness = EditorHarness::with_config(buffer, config);
+    add_tree_panel(&mut harness);
+    assert!(harness.editor.test_focus_panel("tree"));
+
+    for (code, modifiers, expected) in [
+        (KeyCode::Char('a'), KeyModifiers::NONE, "a"),
+        (KeyCode::Char('x'), KeyModifiers::NONE, "x"),
+        (KeyCode::Tab, KeyModifiers::NONE, "Tab"),
+        (KeyCode::Char('r'), KeyModifiers::CONTROL, "Ctrl-r"),
+    ] {
+        let action = harness
+            .editor
+            .test_handle_event(Event::Key(KeyEvent::new(code, modifiers)))
+            .unwrap();
+        assert!(matches!(
+            action,
+            Some(KeyAction::Multiple(actions))
+                if actions.iter().any(|action| matches!(
+                    action,
+                    Action::NotifyPlugins(name, payload)
+                        if name == "panel:event:tree" && payload["action"] == expected
+                ))
+        ));
+    }
+}
+
+#[test]
+fn focused_row_panel_pages_with_control_keys_and_page_keys() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config_and_size(buffer, default_key_config(), 80, 10);
+    add_tree_panel(&mut harness);
+    harness
+        .editor
+        .test_update_panel("tree", numbered_tree_rows(30));
+    assert!(harness.editor.test_focus_panel("tree"));
+
+    for (code, modifiers, expected_top, expected_selected) in [
+        (KeyCode::Char('f'), KeyModifiers::CONTROL, "row-06.rs", 9),
+        (KeyCode::PageDown, KeyModifiers::NONE, "row-12.rs", 15),
+        (KeyCode::Char('b'), KeyModifiers::CONTROL, "row-06.rs", 10),
+        (KeyCode::PageUp, KeyModifiers::NONE, "row-00.rs", 4),
+    ] {
+        let action = harness
+            .editor
+            .test_handle_event(Event::Key(KeyEvent::new(code, modifiers)))
+            .unwrap();
+        assert!(matches!(action, Some(KeyAction::Multiple(_))));
+        assert!(harness.render_row(0).unwrap().starts_with(expected_top));
+        assert_eq!(
+            harness.editor.test_focused_panel_selected_index("tree"),
+            Some(expected_selected)
+        );
+    }
+
+    harness
+        .editor
+        .test_handle_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('G'),
+            KeyModifiers::NONE,
+        )))
+        .unwrap();
+    assert!(harness.render_row(0).unwrap().starts_with("row-22.rs"));
+    assert_eq!(
+        harness.editor.test_focused_panel_selected_index("tree"),
+        Some(29)
+    );
+
+    harness
+        .editor
+        .test_handle_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('g'),
+            KeyModifiers::NONE,
+        )))
+        .unwrap();
+    assert!(harness.render_row(0).unwrap().starts_with("row-00.rs"));
+    assert_eq!(
+        harness.editor.test_focused_panel_selected_index("tree"),
+        Some(0)
+    );
+}
+
+#[tokio::test]
+async fn focused_agent_panel_keeps_global_leader_until_the_composer_is_focused() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut config = default_key_config();
+    config
+        .keys
+        .normal
+        .insert("q".to_string(), KeyAction::Single(Action::FilePicker));
+    config
+        .keys
+        .normal
+        .insert("Ctrl-c".to_string(), KeyAction::Single(Action::Suspend));
+    let mut harness = EditorHarness::with_config(buffer, config);
+    harness.editor.test_create_text_panel(
+        "agent",
+        PanelConfig {
+            side: PanelSide::Right,
+            width: 40,
+            title: Some("Agent".to_string()),
+            composer: Some(TextPanelComposerConfig {
+                placeholder: "Ask".to_string(),
+                rows: 2,
+            }),
+            surface: None,
+            border: None,
+            header_actions: Vec::new(),
+        },
+    );
+    assert!(harness.editor.test_focus_panel("agent"));
+    let mut runtime = Runtime::new();
+    runtime
+        .load_plugin(
+            "agent",
+            r#"
+                pub fn activate() {
+                    red::add_command("Agent", noop, Json { scope: "global" });
+                    red::add_command("AgentToggle", noop, Json { scope: "global" });
+                }
+                fn noop() {}
+            "#,
+        )
+        .await
+        .unwrap();
+
+    let toggle = harness
+        .editor
+        .test_handle_event_with_runtime(
+            Event::Key(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::ALT)),
+            &runtime,
+        )
+        .unwrap();
+    assert_eq!(
+        toggle,
+        Some(KeyAction::Single(Action::PluginCommand(
+            "AgentToggle".to_string()
+        )))
+    );
+
+    let action = harness
+        .editor
+        .test_handle_event_with_runtime(
+            Event::Key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE)),
+            &runtime,
+        )
+        .unwrap();
+    let Some(KeyAction::Nested(leader)) = action else {
+        panic!("expected Space to start the leader sequence from the conversation, got {action:?}");
+    };
+    assert_eq!(leader.len(), 8);
+    for global in ["A", "?", "d", "e", "m", "N", "P", "s"] {
+        assert!(
+            leader.contains_key(global),
+            "global leader branch {global:?} must remain available"
+        );
+    }
+    for contextual in [" ", "a", "n", "p", "b", "f", ".", "r"] {
+        assert!(
+            !leader.contains_key(contextual),
+            "contextual leader branch {contextual:?} must be filtered"
+        );
+    }
+    assert_eq!(
+        leader.get("m"),
+        Some(&KeyAction::Single(Action::OpenMessages))
+    );
+    assert_eq!(
+        leader.get("N"),
+        Some(&KeyAction::Single(Action::OpenLatestInlineCompletion))
+    );
+    assert_eq!(
+        leader.get("A"),
+        Some(&KeyAction::Single(Action::PluginCommand(
+            "Agent".to_string()
+        )))
+    );
+
+    for (code, modifiers, expected) in [
+        (KeyCode::Char('q'), KeyModifiers::NONE, "close"),
+        (KeyCode::Char('c'), KeyModifiers::CONTROL, "interrupt"),
+    ] {
+        let action = harness
+            .editor
+            .test_handle_event_with_runtime(Event::Key(KeyEvent::new(code, modifiers)), &runtime)
+            .unwrap();
+        assert!(matches!(
+            action,
+            Some(KeyAction::Multiple(actions))
+                if actions.iter().any(|action| matches!(
+                    action,
+                    Action::NotifyPlugins(name, payload)
+                        if name == "panel:event:agent" && payload["action"] == expected
+                ))
+        ));
+    }
+
+    assert!(harness.editor.test_focus_text_panel_composer("agent"));
+    let action = harness
+        .editor
+        .test_handle_event(Event::Key(KeyEvent::new(
+            KeyCode::Char(' '),
+            KeyModifiers::NONE,
+        )))
+        .unwrap();
+    assert!(matches!(
+        action,
+        Some(KeyAction::Multiple(actions))
+            if actions.iter().any(|action| matches!(
+                action,
+                Action::NotifyPlugins(name, payload)
+                    if name == "panel:event:agent" && payload["action"] == "composer_input"
+            ))
+    ));
+    assert!(harness.render_cursor_position().is_some());
+
+    let action = harness
+        .editor
+        .test_handle_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL,
+        )))
+        .unwrap();
+    assert!(matches!(
+        action,
+        Some(KeyAction::Multiple(actions))
+            if actions.iter().any(|action| matches!(
+                action,
+                Action::NotifyPlugins(name, payload)
+                    if name == "panel:event:agent" && payload["action"] == "interrupt"
+            ))
+    ));
+    assert!(harness.render_cursor_position().is_some());
+}
+
+#[test]
+fn agent_scrollback_tab_focuses_a_cursor_and_visual_yank_uses_the_clipboard() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    let clipboard_text = Arc::new(Mutex::new(None));
+    harness
+        .editor
+        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
+            clipboard_text.clone(),
+        )));
+    harness.editor.test_create_text_panel(
+        "agent",
+        PanelConfig {
+            side: PanelSide::Right,
+            width: 24,
+            title: Some("Agent".to_string()),
+            composer: Some(TextPanelComposerConfig {
+                placeholder: "Ask".to_string(),
+                rows: 2,
+            }),
+            ..PanelConfig::default()
+        },
+    );
+    harness.editor.test_update_text_panel(
+        "agent",
+        vec![TextPanelBlock {
+            id: "answer".to_string(),
+            kind: TextPanelBlockKind::Text,
+            format: TextPanelBlockFormat::Plain,
+            text: "alpha beta gamma".to_string(),
+        }],
+    );
+    assert!(harness.editor.test_focus_text_panel_composer("agent"));
+
+    harness
+        .editor
+        .test_handle_event(Event::Key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE)))
+        .unwrap();
+    assert!(harness.render_cursor_position().is_some());
+
+    for key in ['g', 'w', 'v', 'e', 'y'] {
+        harness
+            .editor
+            .test_handle_event(Event::Key(KeyEvent::new(
+                KeyCode::Char(key),
+                KeyModifiers::NONE,
+            )))
+            .unwrap();
+    }
+
+    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("beta"));
+    assert_eq!(harness.last_error(), Some("4 characters yanked"));
+    assert!(harness.render_cursor_position().is_some());
+}
+
+#[test]
+fn agent_scrollback_visual_yank_copies_code_without_markdown_frame() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    let clipboard_text = Arc::new(Mutex::new(None));
+    harness
+        .editor
+        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
+            clipboard_text.clone(),
+        )));
+    harness.editor.test_create_text_panel(
+        "agent",
+        PanelConfig {
+            side: PanelSide::Right,
+            width: 24,
+            title: Some("Agent".to_string()),
+            composer: Some(TextPanelComposerConfig {
+                placeholder: "Ask".to_string(),
+                rows: 2,
+            }),
+            ..PanelConfig::default()
+        },
+    );
+    harness.editor.test_update_text_panel(
+        "agent",
+        vec![TextPanelBlock {
+            id: "answer".to_string(),
+            kind: TextPanelBlockKind::Agent,
+            format: TextPanelBlockFormat::Markdown,
+            text: "```bash\n/game\n```".to_string(),
+        }],
+    );
+    assert!(harness.editor.test_focus_text_panel_composer("agent"));
+
+    for code in [
+        KeyCode::Tab,
+        KeyCode::Char('g'),
+        KeyCode::Char('v'),
+        KeyCode::Char('G'),
+        KeyCode::Char('y'),
+    ] {
+        harness
+            .editor
+            .test_handle_event(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+            .unwrap();
+    }
+
+    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("/game"));
+}
+
+#[test]
+fn mouse_drag_selects_agent_scrollback_text_for_visual_yank() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    let clipboard_text = Arc::new(Mutex::new(None));
+    harness
+        .editor
+        .test_set_clipboard(Box::new(MemoryClipboardProvider::from(
+            clipboard_text.clone(),
+        )));
+    harness.editor.test_create_text_panel(
+        "agent",
+        PanelConfig {
+            side: PanelSide::Right,
+            width: 24,
+            title: Some("Agent".to_string()),
+            composer: Some(TextPanelComposerConfig {
+                placeholder: "Ask".to_string(),
+                rows: 2,
+            }),
+            ..PanelConfig::default()
+        },
+    );
+    harness.editor.test_update_text_panel(
+        "agent",
+        vec![TextPanelBlock {
+            id: "answer".to_string(),
+            kind: TextPanelBlockKind::Text,
+            format: TextPanelBlockFormat::Plain,
+            text: "alpha beta gamma".to_string(),
+        }],
+    );
+
+    for kind in [
+        MouseEventKind::Down(MouseButton::Left),
+        MouseEventKind::Drag(MouseButton::Left),
+        MouseEventKind::Up(MouseButton::Left),
+    ] {
+        let column = if matches!(kind, MouseEventKind::Down(_)) {
+            57
+        } else {
+            61
+        };
+        harness
+            .editor
+            .test_handle_event(Event::Mouse(MouseEvent {
+                kind,
+                column,
+                row: 2,
+                modifiers: KeyModifiers::NONE,
+            }))
+            .unwrap();
+    }
+    harness
+        .editor
+        .test_handle_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('y'),
+            KeyModifiers::NONE,
+        )))
+        .unwrap();
+
+    assert_eq!(clipboard_text.lock().unwrap().as_deref(), Some("alpha"));
+}
+
+#[test]
+fn focused_agent_composer_keeps_control_j_local_and_routes_other_navigation() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    harness.editor.test_create_text_panel(
+        "agent",
+        PanelConfig {
+            side: PanelSide::Right,
+            width: 40,
+            title: Some("Agent".to_string()),
+            composer: Some(TextPanelComposerConfig {
+                placeholder: "Ask".to_string(),
+                rows: 2,
+            }),
+            ..PanelConfig::default()
+        },
+    );
+    assert!(harness.editor.test_focus_text_panel_composer("agent"));
+
+    for (code, modifiers, expected) in [
+        (KeyCode::Char('h'), KeyModifiers::CONTROL, "up"),
+        (KeyCode::Char('j'), KeyModifiers::CONTROL, "composer_input"),
+        (KeyCode::Char('k'), KeyModifiers::CONTROL, "up"),
+        (KeyCode::Char('g'), KeyModifiers::CONTROL, "top"),
+        (
+            KeyCode::Char('G'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+            "bottom",
+        ),
+    ] {
+        let action = harness
+            .editor
+            .test_handle_event(Event::Key(KeyEvent::new(code, modifiers)))
+            .unwrap();
+        assert!(matches!(
+            action,
+            Some(KeyAction::Multiple(actions))
+                if actions.iter().any(|action| matches!(
+                    action,
+                    Action::NotifyPlugins(name, payload)
+                        if name == "panel:event:agent" && payload["action"] == expected
+                ))
+        ));
+    }
+
+    let action = harness
+        .editor
+        .test_handle_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('j'),
+            KeyModifiers::NONE,
+        )))
+        .unwrap();
+    assert!(matches!(
+        action,
+        Some(KeyAction::Multiple(actions))
+            if actions.iter().any(|action| matches!(
+                action,
+                Action::NotifyPlugins(name, payload)
+                    if name == "panel:event:agent" && payload["action"] == "composer_input"
+            ))
+    ));
+}
+
+#[tokio::test]
+async fn escape_from_focused_panel_restores_editor_cursor() {
+    let mut harness = EditorHarness::with_content("abcdef");
+    add_tree_panel(&mut harness);
+    let editor_cursor = harness.render_cursor_position();
+    assert!(harness.editor.test_focus_panel("tree"));
+
+    harness
+        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+        .await
+        .unwrap();
+
+    assert_eq!(harness.editor.test_focused_panel_id(), None);
+    assert_eq!(harness.render_cursor_position(), editor_cursor);
+}
+
+#[tokio::test]
+async fn next_and_previous_window_cycle_through_focused_panels() {
+    let mut harness = EditorHarness::with_content("abcdef");
+    add_tree_panel(&mut harness);
+
+    harness.execute_action(Action::NextWindow).await.unwrap();
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
+
+    harness.execute_action(Action::NextWindow).await.unwrap();
+    assert_eq!(harness.editor.test_focused_panel_id(), None);
+
+    harness
+        .execute_action(Action::PreviousWindow)
+        .await
+        .unwrap();
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
+}
+
+#[tokio::test]
+async fn focused_vertical_panels_resize_with_vim_width_chords() {
+    for side in [PanelSide::Left, PanelSide::Right] {
+        let buffer = Buffer::new(None, "first\nsecond\n".to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
+        harness.editor.test_create_panel(
+            "inspector",
+            PanelConfig {
+                side,
+                width: 20,
+                ..PanelConfig::default()
+            },
+        );
+        assert!(harness.editor.test_focus_panel("inspector"));
+
+        execute_window_chord(&mut harness, '>').await;
+        assert_eq!(
+            harness.editor.test_panel_layout("inspector"),
+            Some((side, 21)),
+        );
+        assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
+
+        execute_window_chord(&mut harness, '<').await;
+        assert_eq!(
+            harness.editor.test_panel_layout("inspector"),
+            Some((side, 20)),
+        );
+
+        execute_window_chord(&mut harness, '+').await;
+        assert_eq!(
+            harness.editor.test_panel_layout("inspector"),
+            Some((side, 20)),
+            "height commands must not resize a vertical pane or the hidden editor window",
+        );
+    }
 }
 
 #[tokio::test]
@@ -7115,602 +7990,1112 @@ async fn focused_horizontal_panels_resize_with_vim_height_chords() {
         );
         assert!(harness.editor.test_focus_panel("inspector"));
 
-        execute_window_chord(&mut harness, '+').await;
-        assert_eq!(
-            harness.editor.test_panel_layout("inspector"),
-            Some((side, 7)),
-        );
+        execute_window_chord(&mut harness, '+').await;
+        assert_eq!(
+            harness.editor.test_panel_layout("inspector"),
+            Some((side, 7)),
+        );
+
+        execute_window_chord(&mut harness, '-').await;
+        assert_eq!(
+            harness.editor.test_panel_layout("inspector"),
+            Some((side, 6)),
+        );
+
+        execute_window_chord(&mut harness, '>').await;
+        assert_eq!(
+            harness.editor.test_panel_layout("inspector"),
+            Some((side, 6)),
+            "width commands must not resize a horizontal pane or the hidden editor window",
+        );
+    }
+}
+
+#[tokio::test]
+async fn pane_resize_mode_moves_docked_panel_dividers_in_screen_direction() {
+    for (side, initial_size, grow_keys, shrink_keys) in [
+        (
+            PanelSide::Left,
+            20,
+            [KeyCode::Char('l'), KeyCode::Right],
+            [KeyCode::Char('h'), KeyCode::Left],
+        ),
+        (
+            PanelSide::Right,
+            20,
+            [KeyCode::Char('h'), KeyCode::Left],
+            [KeyCode::Char('l'), KeyCode::Right],
+        ),
+        (
+            PanelSide::Top,
+            6,
+            [KeyCode::Char('j'), KeyCode::Down],
+            [KeyCode::Char('k'), KeyCode::Up],
+        ),
+        (
+            PanelSide::Bottom,
+            6,
+            [KeyCode::Char('k'), KeyCode::Up],
+            [KeyCode::Char('j'), KeyCode::Down],
+        ),
+    ] {
+        let buffer = Buffer::new(None, "first\nsecond\n".to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
+        harness.editor.test_create_panel(
+            "inspector",
+            PanelConfig {
+                side,
+                width: initial_size,
+                ..PanelConfig::default()
+            },
+        );
+        assert!(harness.editor.test_focus_panel("inspector"));
+
+        execute_window_chord(&mut harness, 'r').await;
+        for key in grow_keys {
+            execute_unmodified_key(&mut harness, key).await;
+        }
+        assert_eq!(
+            harness.editor.test_panel_layout("inspector"),
+            Some((side, initial_size + 2)),
+            "moving the {side:?} panel's divider outward should grow it",
+        );
+
+        for key in shrink_keys {
+            execute_unmodified_key(&mut harness, key).await;
+        }
+        assert_eq!(
+            harness.editor.test_panel_layout("inspector"),
+            Some((side, initial_size)),
+            "moving the {side:?} panel's divider inward should shrink it",
+        );
+    }
+}
+
+#[tokio::test]
+async fn pane_resize_mode_resizes_editor_splits_with_hjkl_and_arrows() {
+    for (split_action, grow_keys, shrink_keys, vertical) in [
+        (
+            Action::SplitVertical,
+            [KeyCode::Char('h'), KeyCode::Left],
+            [KeyCode::Char('l'), KeyCode::Right],
+            true,
+        ),
+        (
+            Action::SplitHorizontal,
+            [KeyCode::Char('k'), KeyCode::Up],
+            [KeyCode::Char('j'), KeyCode::Down],
+            false,
+        ),
+    ] {
+        let buffer = Buffer::new(None, "first\nsecond\nthird\n".to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
+        harness.execute_action(split_action).await.unwrap();
+        let (initial_position, initial_size) = harness.editor.test_active_window_bounds().unwrap();
+        let initial_dimension = if vertical {
+            initial_size.0
+        } else {
+            initial_size.1
+        };
+
+        execute_window_chord(&mut harness, 'r').await;
+        assert!(harness.statusline_row().contains("RESIZE"));
+
+        for key in grow_keys {
+            execute_unmodified_key(&mut harness, key).await;
+        }
+        let (grown_position, grown_size) = harness.editor.test_active_window_bounds().unwrap();
+        let grown_dimension = if vertical { grown_size.0 } else { grown_size.1 };
+        assert_eq!(grown_dimension, initial_dimension + 2);
+        if vertical {
+            assert_eq!(grown_position.x, initial_position.x - 2);
+        } else {
+            assert_eq!(grown_position.y, initial_position.y - 2);
+        }
+
+        for key in shrink_keys {
+            execute_unmodified_key(&mut harness, key).await;
+        }
+        assert_eq!(
+            harness.editor.test_active_window_bounds().unwrap().1,
+            initial_size
+        );
+
+        execute_unmodified_key(&mut harness, KeyCode::Enter).await;
+        let statusline = harness.statusline_row();
+        assert!(!statusline.contains("RESIZE"), "{statusline:?}");
+        assert!(statusline.contains("NORMAL"), "{statusline:?}");
+    }
+}
+
+#[tokio::test]
+async fn focused_agent_composer_can_enter_and_exit_pane_resize_mode() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    harness.editor.test_create_text_panel(
+        "agent",
+        PanelConfig {
+            side: PanelSide::Right,
+            width: 20,
+            title: Some("Agent".to_string()),
+            composer: Some(TextPanelComposerConfig {
+                placeholder: "Ask".to_string(),
+                rows: 2,
+            }),
+            ..PanelConfig::default()
+        },
+    );
+    assert_eq!(harness.editor.test_focused_panel_id(), None);
+    let initial_editor_size = harness.editor.test_active_window_bounds().unwrap().1;
 
-        execute_window_chord(&mut harness, '-').await;
-        assert_eq!(
-            harness.editor.test_panel_layout("inspector"),
-            Some((side, 6)),
-        );
+    execute_window_chord(&mut harness, 'r').await;
+    execute_unmodified_key(&mut harness, KeyCode::Char('h')).await;
+    assert_eq!(
+        harness.editor.test_panel_layout("agent"),
+        Some((PanelSide::Right, 21)),
+        "moving the shared divider left should work while the editor is focused",
+    );
+    let resized_editor_size = harness.editor.test_active_window_bounds().unwrap().1;
+    assert_eq!(resized_editor_size.0, initial_editor_size.0 - 1,);
+    execute_unmodified_key(&mut harness, KeyCode::Right).await;
+    assert_eq!(
+        harness.editor.test_panel_layout("agent"),
+        Some((PanelSide::Right, 20))
+    );
+    execute_unmodified_key(&mut harness, KeyCode::Enter).await;
 
-        execute_window_chord(&mut harness, '>').await;
-        assert_eq!(
-            harness.editor.test_panel_layout("inspector"),
-            Some((side, 6)),
-            "width commands must not resize a horizontal pane or the hidden editor window",
-        );
+    assert!(harness.editor.test_focus_text_panel_composer("agent"));
+
+    execute_window_chord(&mut harness, 'r').await;
+    assert!(harness.statusline_row().contains("RESIZE"));
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
+
+    for key in [KeyCode::Char('h'), KeyCode::Left] {
+        execute_unmodified_key(&mut harness, key).await;
+    }
+    assert_eq!(
+        harness.editor.test_panel_layout("agent"),
+        Some((PanelSide::Right, 22))
+    );
+
+    for key in [KeyCode::Char('j'), KeyCode::Down, KeyCode::Char('x')] {
+        execute_unmodified_key(&mut harness, key).await;
+    }
+    assert_eq!(
+        harness.editor.test_panel_layout("agent"),
+        Some((PanelSide::Right, 22)),
+        "orthogonal and unsupported keys should leave the pane unchanged",
+    );
+    assert!(harness.statusline_row().contains("RESIZE"));
+
+    for key in [KeyCode::Char('l'), KeyCode::Right] {
+        execute_unmodified_key(&mut harness, key).await;
+    }
+    assert_eq!(
+        harness.editor.test_panel_layout("agent"),
+        Some((PanelSide::Right, 20))
+    );
+
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    assert!(!harness.statusline_row().contains("RESIZE"));
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
+
+    execute_unmodified_key(&mut harness, KeyCode::Char('l')).await;
+    assert_eq!(
+        harness.editor.test_panel_layout("agent"),
+        Some((PanelSide::Right, 20)),
+        "keys should return to the focused composer after leaving resize mode",
+    );
+}
+
+#[tokio::test]
+async fn pane_resize_mode_cancels_when_terminal_focus_is_lost() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    harness.execute_action(Action::SplitVertical).await.unwrap();
+
+    execute_window_chord(&mut harness, 'r').await;
+    assert!(harness.statusline_row().contains("RESIZE"));
+
+    harness.execute_event(Event::FocusLost).await.unwrap();
+    assert!(!harness.statusline_row().contains("RESIZE"));
+}
+
+#[tokio::test]
+async fn pane_resize_mode_cancels_when_terminal_geometry_changes() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    harness.execute_action(Action::SplitVertical).await.unwrap();
+
+    execute_window_chord(&mut harness, 'r').await;
+    assert!(harness.statusline_row().contains("RESIZE"));
+
+    harness.execute_event(Event::Resize(100, 30)).await.unwrap();
+    assert!(!harness.statusline_row().contains("RESIZE"));
+}
+
+#[tokio::test]
+async fn vim_resize_chords_grow_and_shrink_either_editor_split_by_one_cell() {
+    for (split_action, grow, shrink, first_window_action, vertical) in [
+        (
+            Action::SplitVertical,
+            '>',
+            '<',
+            Action::MoveWindowLeft,
+            true,
+        ),
+        (
+            Action::SplitHorizontal,
+            '+',
+            '-',
+            Action::MoveWindowUp,
+            false,
+        ),
+    ] {
+        for first_window in [false, true] {
+            let buffer = Buffer::new(None, "first\nsecond\nthird\n".to_string());
+            let mut harness = EditorHarness::with_config(buffer, default_key_config());
+            harness.execute_action(split_action.clone()).await.unwrap();
+            if first_window {
+                harness
+                    .execute_action(first_window_action.clone())
+                    .await
+                    .unwrap();
+            }
+            let (_, initial_size) = harness.editor.test_active_window_bounds().unwrap();
+            let initial_dimension = if vertical {
+                initial_size.0
+            } else {
+                initial_size.1
+            };
+
+            execute_window_chord(&mut harness, grow).await;
+            let (_, grown_size) = harness.editor.test_active_window_bounds().unwrap();
+            let grown_dimension = if vertical { grown_size.0 } else { grown_size.1 };
+            assert_eq!(grown_dimension, initial_dimension + 1);
+
+            execute_window_chord(&mut harness, shrink).await;
+            let (_, restored_size) = harness.editor.test_active_window_bounds().unwrap();
+            assert_eq!(restored_size, initial_size);
+        }
     }
 }
 
 #[tokio::test]
-async fn pane_resize_mode_moves_docked_panel_dividers_in_screen_direction() {
-    for (side, initial_size, grow_keys, shrink_keys) in [
-        (
-            PanelSide::Left,
-            20,
-            [KeyCode::Char('l'), KeyCode::Right],
-            [KeyCode::Char('h'), KeyCode::Left],
-        ),
-        (
-            PanelSide::Right,
-            20,
-            [KeyCode::Char('h'), KeyCode::Left],
-            [KeyCode::Char('l'), KeyCode::Right],
-        ),
-        (
-            PanelSide::Top,
-            6,
-            [KeyCode::Char('j'), KeyCode::Down],
-            [KeyCode::Char('k'), KeyCode::Up],
-        ),
-        (
-            PanelSide::Bottom,
-            6,
-            [KeyCode::Char('k'), KeyCode::Up],
-            [KeyCode::Char('j'), KeyCode::Down],
-        ),
-    ] {
+async fn counted_vim_resize_moves_editor_split_by_the_requested_cells() {
+    let buffer = Buffer::new(None, "first\nsecond\nthird\n".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    harness.execute_action(Action::SplitVertical).await.unwrap();
+    let (_, initial_size) = harness.editor.test_active_window_bounds().unwrap();
+
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('5'),
+            KeyModifiers::NONE,
+        )))
+        .await
+        .unwrap();
+    execute_window_chord(&mut harness, '>').await;
+
+    let (_, resized) = harness.editor.test_active_window_bounds().unwrap();
+    assert_eq!(resized.0, initial_size.0 + 5);
+    assert_eq!(resized.1, initial_size.1);
+}
+
+#[tokio::test]
+async fn focused_panel_window_resize_chords_honor_vim_counts() {
+    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    harness.editor.test_create_panel(
+        "inspector",
+        PanelConfig {
+            side: PanelSide::Left,
+            width: 20,
+            ..PanelConfig::default()
+        },
+    );
+    assert!(harness.editor.test_focus_panel("inspector"));
+
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('5'),
+            KeyModifiers::NONE,
+        )))
+        .await
+        .unwrap();
+    execute_window_chord(&mut harness, '>').await;
+
+    assert_eq!(
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Left, 25)),
+    );
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
+}
+
+#[tokio::test]
+async fn ctrl_w_equals_resets_a_focused_panel_without_balancing_editor_splits() {
+    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    harness.execute_action(Action::SplitVertical).await.unwrap();
+    harness
+        .execute_action(Action::ResizeWindowLeft(/*amount*/ 3))
+        .await
+        .unwrap();
+    harness.editor.test_create_panel(
+        "inspector",
+        PanelConfig {
+            side: PanelSide::Left,
+            width: 20,
+            ..PanelConfig::default()
+        },
+    );
+    let split_topology = harness.editor.test_session_snapshot().window_layout.root;
+    assert!(harness.editor.test_focus_panel("inspector"));
+
+    harness
+        .execute_action(Action::ResizeWindowRight(/*amount*/ 5))
+        .await
+        .unwrap();
+    assert_eq!(
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Left, 25)),
+    );
+
+    execute_window_chord(&mut harness, '=').await;
+
+    assert_eq!(
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Left, 20)),
+    );
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
+    assert_eq!(
+        harness.editor.test_session_snapshot().window_layout.root,
+        split_topology,
+        "resetting a pane must not rebalance unrelated editor splits",
+    );
+}
+
+#[tokio::test]
+async fn shifted_window_chords_move_every_panel_kind_to_all_four_edges() {
+    for source_backed in [false, true] {
         let buffer = Buffer::new(None, "first\nsecond\n".to_string());
         let mut harness = EditorHarness::with_config(buffer, default_key_config());
-        harness.editor.test_create_panel(
-            "inspector",
-            PanelConfig {
-                side,
-                width: initial_size,
-                ..PanelConfig::default()
-            },
-        );
+        let config = PanelConfig {
+            side: PanelSide::Left,
+            width: 24,
+            title: Some("Inspector".to_string()),
+            ..PanelConfig::default()
+        };
+        if source_backed {
+            harness.editor.test_create_text_panel("inspector", config);
+        } else {
+            harness.editor.test_create_panel("inspector", config);
+        }
         assert!(harness.editor.test_focus_panel("inspector"));
 
-        execute_window_chord(&mut harness, 'r').await;
-        for key in grow_keys {
-            execute_unmodified_key(&mut harness, key).await;
-        }
-        assert_eq!(
-            harness.editor.test_panel_layout("inspector"),
-            Some((side, initial_size + 2)),
-            "moving the {side:?} panel's divider outward should grow it",
-        );
+        for (key, side, expected_size) in [
+            ('K', PanelSide::Top, 7),
+            ('J', PanelSide::Bottom, 7),
+            ('L', PanelSide::Right, 24),
+            ('H', PanelSide::Left, 24),
+        ] {
+            execute_window_chord(&mut harness, key).await;
 
-        for key in shrink_keys {
-            execute_unmodified_key(&mut harness, key).await;
+            assert_eq!(
+                harness.editor.test_panel_layout("inspector"),
+                Some((side, expected_size)),
+                "both row and source-backed panes should move with Ctrl-w {key}",
+            );
+            assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
+            let (_, editor_size) = harness.editor.test_active_window_bounds().unwrap();
+            assert!(editor_size.0 >= 10);
+            assert!(editor_size.1 >= 5);
         }
-        assert_eq!(
-            harness.editor.test_panel_layout("inspector"),
-            Some((side, initial_size)),
-            "moving the {side:?} panel's divider inward should shrink it",
-        );
     }
 }
 
 #[tokio::test]
-async fn pane_resize_mode_resizes_editor_splits_with_hjkl_and_arrows() {
-    for (split_action, grow_keys, shrink_keys, vertical) in [
-        (
-            Action::SplitVertical,
-            [KeyCode::Char('h'), KeyCode::Left],
-            [KeyCode::Char('l'), KeyCode::Right],
-            true,
-        ),
-        (
-            Action::SplitHorizontal,
-            [KeyCode::Char('k'), KeyCode::Up],
-            [KeyCode::Char('j'), KeyCode::Down],
-            false,
-        ),
-    ] {
-        let buffer = Buffer::new(None, "first\nsecond\nthird\n".to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-        harness.execute_action(split_action).await.unwrap();
-        let (initial_position, initial_size) = harness.editor.test_active_window_bounds().unwrap();
-        let initial_dimension = if vertical {
-            initial_size.0
-        } else {
-            initial_size.1
-        };
+async fn moved_panels_restore_their_independent_width_and_height() {
+    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    harness.editor.test_create_text_panel(
+        "inspector",
+        PanelConfig {
+            side: PanelSide::Left,
+            width: 24,
+            ..PanelConfig::default()
+        },
+    );
+    assert!(harness.editor.test_focus_panel("inspector"));
 
-        execute_window_chord(&mut harness, 'r').await;
-        assert!(harness.statusline_row().contains("RESIZE"));
+    execute_window_chord(&mut harness, 'K').await;
+    execute_window_chord(&mut harness, '+').await;
+    execute_window_chord(&mut harness, '+').await;
+    assert_eq!(
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Top, 9)),
+    );
 
-        for key in grow_keys {
-            execute_unmodified_key(&mut harness, key).await;
-        }
-        let (grown_position, grown_size) = harness.editor.test_active_window_bounds().unwrap();
-        let grown_dimension = if vertical { grown_size.0 } else { grown_size.1 };
-        assert_eq!(grown_dimension, initial_dimension + 2);
-        if vertical {
-            assert_eq!(grown_position.x, initial_position.x - 2);
-        } else {
-            assert_eq!(grown_position.y, initial_position.y - 2);
-        }
+    execute_window_chord(&mut harness, 'L').await;
+    execute_window_chord(&mut harness, '>').await;
+    assert_eq!(
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Right, 25)),
+    );
 
-        for key in shrink_keys {
-            execute_unmodified_key(&mut harness, key).await;
-        }
-        assert_eq!(
-            harness.editor.test_active_window_bounds().unwrap().1,
-            initial_size
-        );
+    execute_window_chord(&mut harness, 'J').await;
+    assert_eq!(
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Bottom, 9)),
+    );
 
-        execute_unmodified_key(&mut harness, KeyCode::Enter).await;
-        let statusline = harness.statusline_row();
-        assert!(!statusline.contains("RESIZE"), "{statusline:?}");
-        assert!(statusline.contains("NORMAL"), "{statusline:?}");
-    }
+    execute_window_chord(&mut harness, 'H').await;
+    assert_eq!(
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Left, 25)),
+    );
 }
 
 #[tokio::test]
-async fn focused_agent_composer_can_enter_and_exit_pane_resize_mode() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.editor.test_create_text_panel(
-        "agent",
+async fn docked_pane_resizing_preserves_minimum_editor_dimensions() {
+    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
+    let mut vertical = EditorHarness::with_config_and_size(
+        buffer,
+        default_key_config(),
+        /*width*/ 40,
+        /*height*/ 14,
+    );
+    vertical.editor.test_create_panel(
+        "inspector",
         PanelConfig {
-            side: PanelSide::Right,
-            width: 20,
-            title: Some("Agent".to_string()),
-            composer: Some(TextPanelComposerConfig {
-                placeholder: "Ask".to_string(),
-                rows: 2,
-            }),
+            side: PanelSide::Left,
+            width: 15,
+            ..PanelConfig::default()
+        },
+    );
+    assert!(vertical.editor.test_focus_panel("inspector"));
+
+    vertical
+        .execute_action(Action::ResizeWindowRight(/*amount*/ usize::MAX))
+        .await
+        .unwrap();
+    assert_eq!(
+        vertical.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Left, 29)),
+    );
+    let (_, vertical_editor_size) = vertical.editor.test_active_window_bounds().unwrap();
+    assert_eq!(vertical_editor_size.0, 10);
+
+    vertical
+        .execute_action(Action::ResizeWindowLeft(/*amount*/ usize::MAX))
+        .await
+        .unwrap();
+    assert_eq!(
+        vertical.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Left, 12)),
+    );
+
+    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
+    let mut horizontal = EditorHarness::with_config_and_size(
+        buffer,
+        default_key_config(),
+        /*width*/ 40,
+        /*height*/ 14,
+    );
+    horizontal.editor.test_create_panel(
+        "inspector",
+        PanelConfig {
+            side: PanelSide::Bottom,
+            width: 4,
+            ..PanelConfig::default()
+        },
+    );
+    assert!(horizontal.editor.test_focus_panel("inspector"));
+
+    horizontal
+        .execute_action(Action::ResizeWindowDown(/*amount*/ usize::MAX))
+        .await
+        .unwrap();
+    assert_eq!(
+        horizontal.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Bottom, 6)),
+    );
+    let (_, horizontal_editor_size) = horizontal.editor.test_active_window_bounds().unwrap();
+    assert_eq!(horizontal_editor_size.1, 5);
+
+    horizontal
+        .execute_action(Action::ResizeWindowUp(/*amount*/ usize::MAX))
+        .await
+        .unwrap();
+    assert_eq!(
+        horizontal.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Bottom, 4)),
+    );
+}
+
+#[tokio::test]
+async fn terminal_resize_preserves_independent_docked_pane_preferences() {
+    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    harness.editor.test_create_text_panel(
+        "inspector",
+        PanelConfig {
+            side: PanelSide::Left,
+            width: 24,
             ..PanelConfig::default()
         },
     );
-    assert_eq!(harness.editor.test_focused_panel_id(), None);
-    let initial_editor_size = harness.editor.test_active_window_bounds().unwrap().1;
-
-    execute_window_chord(&mut harness, 'r').await;
-    execute_unmodified_key(&mut harness, KeyCode::Char('h')).await;
-    assert_eq!(
-        harness.editor.test_panel_layout("agent"),
-        Some((PanelSide::Right, 21)),
-        "moving the shared divider left should work while the editor is focused",
-    );
-    let resized_editor_size = harness.editor.test_active_window_bounds().unwrap().1;
-    assert_eq!(resized_editor_size.0, initial_editor_size.0 - 1,);
-    execute_unmodified_key(&mut harness, KeyCode::Right).await;
+    assert!(harness.editor.test_focus_panel("inspector"));
+    execute_window_chord(&mut harness, 'J').await;
+    execute_window_chord(&mut harness, '+').await;
+    execute_window_chord(&mut harness, '+').await;
     assert_eq!(
-        harness.editor.test_panel_layout("agent"),
-        Some((PanelSide::Right, 20))
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Bottom, 9)),
     );
-    execute_unmodified_key(&mut harness, KeyCode::Enter).await;
-
-    assert!(harness.editor.test_focus_text_panel_composer("agent"));
 
-    execute_window_chord(&mut harness, 'r').await;
-    assert!(harness.statusline_row().contains("RESIZE"));
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
+    harness
+        .execute_event(Event::Resize(/*columns*/ 60, /*rows*/ 20))
+        .await
+        .unwrap();
 
-    for key in [KeyCode::Char('h'), KeyCode::Left] {
-        execute_unmodified_key(&mut harness, key).await;
-    }
     assert_eq!(
-        harness.editor.test_panel_layout("agent"),
-        Some((PanelSide::Right, 22))
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Bottom, 9)),
     );
+    let (_, editor_size) = harness.editor.test_active_window_bounds().unwrap();
+    assert_eq!(editor_size, (60, 8));
 
-    for key in [KeyCode::Char('j'), KeyCode::Down, KeyCode::Char('x')] {
-        execute_unmodified_key(&mut harness, key).await;
-    }
+    execute_window_chord(&mut harness, 'L').await;
     assert_eq!(
-        harness.editor.test_panel_layout("agent"),
-        Some((PanelSide::Right, 22)),
-        "orthogonal and unsupported keys should leave the pane unchanged",
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Right, 24)),
     );
-    assert!(harness.statusline_row().contains("RESIZE"));
 
-    for key in [KeyCode::Char('l'), KeyCode::Right] {
-        execute_unmodified_key(&mut harness, key).await;
-    }
+    execute_window_chord(&mut harness, 'K').await;
     assert_eq!(
-        harness.editor.test_panel_layout("agent"),
-        Some((PanelSide::Right, 20))
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Top, 9)),
     );
+}
 
-    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
-    assert!(!harness.statusline_row().contains("RESIZE"));
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
+#[tokio::test]
+async fn directional_window_chords_focus_and_leave_all_four_pane_edges() {
+    for (side, enter, leave, initial_size) in [
+        (PanelSide::Left, 'h', 'l', 20),
+        (PanelSide::Right, 'l', 'h', 20),
+        (PanelSide::Top, 'k', 'j', 6),
+        (PanelSide::Bottom, 'j', 'k', 6),
+    ] {
+        let buffer = Buffer::new(None, "first\nsecond\n".to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
+        harness.editor.test_create_panel(
+            "inspector",
+            PanelConfig {
+                side,
+                width: initial_size,
+                ..PanelConfig::default()
+            },
+        );
 
-    execute_unmodified_key(&mut harness, KeyCode::Char('l')).await;
-    assert_eq!(
-        harness.editor.test_panel_layout("agent"),
-        Some((PanelSide::Right, 20)),
-        "keys should return to the focused composer after leaving resize mode",
-    );
+        execute_window_chord(&mut harness, enter).await;
+        assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
+
+        execute_window_chord(&mut harness, leave).await;
+        assert_eq!(harness.editor.test_focused_panel_id(), None);
+    }
 }
 
 #[tokio::test]
-async fn pane_resize_mode_cancels_when_terminal_focus_is_lost() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
+async fn window_cycle_includes_top_left_editor_right_and_bottom_panes() {
+    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.execute_action(Action::SplitVertical).await.unwrap();
-
-    execute_window_chord(&mut harness, 'r').await;
-    assert!(harness.statusline_row().contains("RESIZE"));
+    for (id, side, width) in [
+        ("top", PanelSide::Top, 4),
+        ("left", PanelSide::Left, 12),
+        ("right", PanelSide::Right, 12),
+        ("bottom", PanelSide::Bottom, 4),
+    ] {
+        harness.editor.test_create_panel(
+            id,
+            PanelConfig {
+                side,
+                width,
+                ..PanelConfig::default()
+            },
+        );
+    }
 
-    harness.execute_event(Event::FocusLost).await.unwrap();
-    assert!(!harness.statusline_row().contains("RESIZE"));
+    for expected in [
+        Some("right"),
+        Some("bottom"),
+        Some("top"),
+        Some("left"),
+        None,
+    ] {
+        execute_window_chord(&mut harness, 'w').await;
+        assert_eq!(harness.editor.test_focused_panel_id(), expected);
+    }
 }
 
 #[tokio::test]
-async fn pane_resize_mode_cancels_when_terminal_geometry_changes() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.execute_action(Action::SplitVertical).await.unwrap();
+async fn shifted_window_chords_move_nested_splits_to_each_outer_edge() {
+    for (key, expected_position, expected_size) in [
+        ('H', (0, 0), (39, 22)),
+        ('J', (0, 11), (80, 11)),
+        ('K', (0, 0), (80, 10)),
+        ('L', (40, 0), (40, 22)),
+    ] {
+        let contents = (0..40)
+            .map(|line| format!("line {line:02}\n"))
+            .collect::<String>();
+        let buffer = Buffer::new(None, contents);
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
+        harness.execute_action(Action::SplitVertical).await.unwrap();
+        harness
+            .execute_action(Action::SplitHorizontal)
+            .await
+            .unwrap();
+        harness.set_viewport_cursor(2, 3, 3);
+        let cursor = harness.cursor_position();
 
-    execute_window_chord(&mut harness, 'r').await;
-    assert!(harness.statusline_row().contains("RESIZE"));
+        harness
+            .execute_event(Event::Key(KeyEvent::new(
+                KeyCode::Char('w'),
+                KeyModifiers::CONTROL,
+            )))
+            .await
+            .unwrap();
+        assert!(harness.is_waiting_for_key_sequence());
+        harness
+            .execute_event(Event::Key(KeyEvent::new(
+                KeyCode::Char(key),
+                KeyModifiers::SHIFT,
+            )))
+            .await
+            .unwrap();
 
-    harness.execute_event(Event::Resize(100, 30)).await.unwrap();
-    assert!(!harness.statusline_row().contains("RESIZE"));
+        let (position, size) = harness.editor.test_active_window_bounds().unwrap();
+        assert_eq!((position.x, position.y), expected_position);
+        assert_eq!(size, expected_size);
+        assert_eq!(harness.window_count(), 3);
+        assert_eq!(harness.cursor_position(), cursor);
+        assert_eq!(harness.viewport_top(), 2);
+        assert!(!harness.is_waiting_for_key_sequence());
+
+        let snapshot = harness.editor.test_session_snapshot();
+        match (key, snapshot.window_layout.root) {
+            ('H', SplitSnapshot::Vertical { left, .. }) => {
+                assert!(matches!(left.as_ref(), SplitSnapshot::Window { .. }));
+            }
+            ('L', SplitSnapshot::Vertical { right, .. }) => {
+                assert!(matches!(right.as_ref(), SplitSnapshot::Window { .. }));
+            }
+            ('K', SplitSnapshot::Horizontal { top, .. }) => {
+                assert!(matches!(top.as_ref(), SplitSnapshot::Window { .. }));
+            }
+            ('J', SplitSnapshot::Horizontal { bottom, .. }) => {
+                assert!(matches!(bottom.as_ref(), SplitSnapshot::Window { .. }));
+            }
+            _ => panic!("shifted window chord did not create the expected outer split"),
+        }
+    }
 }
 
 #[tokio::test]
-async fn vim_resize_chords_grow_and_shrink_either_editor_split_by_one_cell() {
-    for (split_action, grow, shrink, first_window_action, vertical) in [
-        (
-            Action::SplitVertical,
-            '>',
-            '<',
-            Action::MoveWindowLeft,
-            true,
-        ),
-        (
-            Action::SplitHorizontal,
-            '+',
-            '-',
-            Action::MoveWindowUp,
-            false,
-        ),
+async fn lowercase_window_chords_preserve_split_topology_and_move_focus() {
+    for (key, preparation) in [
+        ('h', Some(Action::MoveWindowRight)),
+        ('j', Some(Action::MoveWindowUp)),
+        ('k', None),
+        ('l', None),
     ] {
-        for first_window in [false, true] {
-            let buffer = Buffer::new(None, "first\nsecond\nthird\n".to_string());
-            let mut harness = EditorHarness::with_config(buffer, default_key_config());
-            harness.execute_action(split_action.clone()).await.unwrap();
-            if first_window {
-                harness
-                    .execute_action(first_window_action.clone())
-                    .await
-                    .unwrap();
-            }
-            let (_, initial_size) = harness.editor.test_active_window_bounds().unwrap();
-            let initial_dimension = if vertical {
-                initial_size.0
-            } else {
-                initial_size.1
-            };
+        let buffer = Buffer::new(None, "first\nsecond\nthird\n".to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
+        harness.execute_action(Action::SplitVertical).await.unwrap();
+        harness
+            .execute_action(Action::SplitHorizontal)
+            .await
+            .unwrap();
+        harness
+            .execute_action(Action::MoveWindowLeft)
+            .await
+            .unwrap();
+        harness
+            .execute_action(Action::SplitHorizontal)
+            .await
+            .unwrap();
+        if let Some(action) = preparation {
+            harness.execute_action(action).await.unwrap();
+        }
+        let before = harness.editor.test_session_snapshot().window_layout;
 
-            execute_window_chord(&mut harness, grow).await;
-            let (_, grown_size) = harness.editor.test_active_window_bounds().unwrap();
-            let grown_dimension = if vertical { grown_size.0 } else { grown_size.1 };
-            assert_eq!(grown_dimension, initial_dimension + 1);
+        harness
+            .execute_event(Event::Key(KeyEvent::new(
+                KeyCode::Char('w'),
+                KeyModifiers::CONTROL,
+            )))
+            .await
+            .unwrap();
+        harness
+            .execute_event(Event::Key(KeyEvent::new(
+                KeyCode::Char(key),
+                KeyModifiers::NONE,
+            )))
+            .await
+            .unwrap();
 
-            execute_window_chord(&mut harness, shrink).await;
-            let (_, restored_size) = harness.editor.test_active_window_bounds().unwrap();
-            assert_eq!(restored_size, initial_size);
-        }
+        let after = harness.editor.test_session_snapshot().window_layout;
+        assert_eq!(after.root, before.root);
+        assert_ne!(after.active_window_id, before.active_window_id);
+        assert_eq!(harness.window_count(), 4);
     }
 }
 
 #[tokio::test]
-async fn counted_vim_resize_moves_editor_split_by_the_requested_cells() {
+async fn moving_a_window_to_an_edge_preserves_both_side_panels() {
     let buffer = Buffer::new(None, "first\nsecond\nthird\n".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
     harness.execute_action(Action::SplitVertical).await.unwrap();
-    let (_, initial_size) = harness.editor.test_active_window_bounds().unwrap();
-
     harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('5'),
-            KeyModifiers::NONE,
-        )))
+        .execute_action(Action::SplitHorizontal)
         .await
         .unwrap();
-    execute_window_chord(&mut harness, '>').await;
-
-    let (_, resized) = harness.editor.test_active_window_bounds().unwrap();
-    assert_eq!(resized.0, initial_size.0 + 5);
-    assert_eq!(resized.1, initial_size.1);
-}
-
-#[tokio::test]
-async fn focused_panel_window_resize_chords_honor_vim_counts() {
-    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    add_tree_panel(&mut harness);
     harness.editor.test_create_panel(
-        "inspector",
+        "right",
         PanelConfig {
-            side: PanelSide::Left,
+            side: PanelSide::Right,
             width: 20,
-            ..PanelConfig::default()
+            title: None,
+            composer: None,
+            surface: None,
+            border: None,
+            header_actions: Vec::new(),
         },
     );
-    assert!(harness.editor.test_focus_panel("inspector"));
 
     harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('5'),
-            KeyModifiers::NONE,
-        )))
+        .execute_action(Action::MoveWindowToLeft)
         .await
         .unwrap();
-    execute_window_chord(&mut harness, '>').await;
-
-    assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Left, 25)),
-    );
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
-}
+    let (left_position, left_size) = harness.editor.test_active_window_bounds().unwrap();
+    assert_eq!(left_position.x, 21);
+    assert_eq!(left_position.y, 0);
+    assert_eq!(left_size.1, 22);
+    assert!(left_position.x + left_size.0 <= 59);
 
-#[tokio::test]
-async fn ctrl_w_equals_resets_a_focused_panel_without_balancing_editor_splits() {
-    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.execute_action(Action::SplitVertical).await.unwrap();
     harness
-        .execute_action(Action::ResizeWindowLeft(/*amount*/ 3))
+        .execute_action(Action::MoveWindowToRight)
         .await
         .unwrap();
-    harness.editor.test_create_panel(
-        "inspector",
-        PanelConfig {
-            side: PanelSide::Left,
-            width: 20,
-            ..PanelConfig::default()
-        },
-    );
-    let split_topology = harness.editor.test_session_snapshot().window_layout.root;
-    assert!(harness.editor.test_focus_panel("inspector"));
+    let (right_position, right_size) = harness.editor.test_active_window_bounds().unwrap();
+    assert_eq!(right_position.y, 0);
+    assert_eq!(right_size.1, 22);
+    assert_eq!(right_position.x + right_size.0, 59);
+    assert_eq!(harness.window_count(), 3);
+}
 
-    harness
-        .execute_action(Action::ResizeWindowRight(/*amount*/ 5))
-        .await
-        .unwrap();
-    assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Left, 25)),
-    );
+#[tokio::test]
+async fn moving_a_single_window_to_an_edge_is_silent() {
+    for action in [
+        Action::MoveWindowToLeft,
+        Action::MoveWindowToBottom,
+        Action::MoveWindowToTop,
+        Action::MoveWindowToRight,
+    ] {
+        let mut harness = EditorHarness::with_content("abcdef");
+        let before = harness.editor.test_session_snapshot().window_layout;
 
-    execute_window_chord(&mut harness, '=').await;
+        harness.execute_action(action).await.unwrap();
 
-    assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Left, 20)),
-    );
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
-    assert_eq!(
-        harness.editor.test_session_snapshot().window_layout.root,
-        split_topology,
-        "resetting a pane must not rebalance unrelated editor splits",
-    );
+        assert_eq!(harness.editor.test_session_snapshot().window_layout, before);
+        assert_eq!(harness.last_error(), None);
+        assert_eq!(harness.window_count(), 1);
+    }
 }
 
 #[tokio::test]
-async fn shifted_window_chords_move_every_panel_kind_to_all_four_edges() {
-    for source_backed in [false, true] {
-        let buffer = Buffer::new(None, "first\nsecond\n".to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-        let config = PanelConfig {
-            side: PanelSide::Left,
-            width: 24,
-            title: Some("Inspector".to_string()),
-            ..PanelConfig::default()
-        };
-        if source_backed {
-            harness.editor.test_create_text_panel("inspector", config);
-        } else {
-            harness.editor.test_create_panel("inspector", config);
-        }
-        assert!(harness.editor.test_focus_panel("inspector"));
-
-        for (key, side, expected_size) in [
-            ('K', PanelSide::Top, 7),
-            ('J', PanelSide::Bottom, 7),
-            ('L', PanelSide::Right, 24),
-            ('H', PanelSide::Left, 24),
-        ] {
-            execute_window_chord(&mut harness, key).await;
+async fn directional_window_boundaries_report_no_op() {
+    let mut harness = EditorHarness::with_content("abcdef");
 
-            assert_eq!(
-                harness.editor.test_panel_layout("inspector"),
-                Some((side, expected_size)),
-                "both row and source-backed panes should move with Ctrl-w {key}",
-            );
-            assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
-            let (_, editor_size) = harness.editor.test_active_window_bounds().unwrap();
-            assert!(editor_size.0 >= 10);
-            assert!(editor_size.1 >= 5);
-        }
+    for (action, message) in [
+        (Action::MoveWindowUp, "no window above"),
+        (Action::MoveWindowDown, "no window below"),
+        (Action::MoveWindowLeft, "no window to the left"),
+        (Action::MoveWindowRight, "no window to the right"),
+    ] {
+        harness.execute_action(action).await.unwrap();
+        assert!(harness.commandline_row().contains(message));
     }
 }
 
 #[tokio::test]
-async fn moved_panels_restore_their_independent_width_and_height() {
-    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.editor.test_create_text_panel(
-        "inspector",
+async fn window_cycle_uses_left_windows_right_visual_groups() {
+    let mut harness = EditorHarness::with_content("abcdef");
+    add_tree_panel(&mut harness);
+    harness.editor.test_create_panel(
+        "right",
         PanelConfig {
-            side: PanelSide::Left,
-            width: 24,
-            ..PanelConfig::default()
+            side: PanelSide::Right,
+            width: 20,
+            title: None,
+            composer: None,
+            surface: None,
+            border: None,
+            header_actions: Vec::new(),
         },
     );
-    assert!(harness.editor.test_focus_panel("inspector"));
-
-    execute_window_chord(&mut harness, 'K').await;
-    execute_window_chord(&mut harness, '+').await;
-    execute_window_chord(&mut harness, '+').await;
-    assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Top, 9)),
-    );
+    harness.execute_action(Action::SplitVertical).await.unwrap();
+    assert_eq!(harness.active_window_id(), 1);
 
-    execute_window_chord(&mut harness, 'L').await;
-    execute_window_chord(&mut harness, '>').await;
-    assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Right, 25)),
-    );
+    harness.execute_action(Action::NextWindow).await.unwrap();
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("right"));
 
-    execute_window_chord(&mut harness, 'J').await;
-    assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Bottom, 9)),
-    );
+    harness.execute_action(Action::NextWindow).await.unwrap();
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
 
-    execute_window_chord(&mut harness, 'H').await;
-    assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Left, 25)),
-    );
+    harness.execute_action(Action::NextWindow).await.unwrap();
+    assert_eq!(harness.editor.test_focused_panel_id(), None);
+    assert_eq!(harness.active_window_id(), 0);
 }
 
 #[tokio::test]
-async fn docked_pane_resizing_preserves_minimum_editor_dimensions() {
-    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
-    let mut vertical = EditorHarness::with_config_and_size(
-        buffer,
-        default_key_config(),
-        /*width*/ 40,
-        /*height*/ 14,
-    );
-    vertical.editor.test_create_panel(
-        "inspector",
-        PanelConfig {
-            side: PanelSide::Left,
-            width: 15,
-            ..PanelConfig::default()
-        },
-    );
-    assert!(vertical.editor.test_focus_panel("inspector"));
+async fn focused_panel_routes_ctrl_w_w_into_focus_cycle() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    add_tree_panel(&mut harness);
+    assert!(harness.editor.test_focus_panel("tree"));
 
-    vertical
-        .execute_action(Action::ResizeWindowRight(/*amount*/ usize::MAX))
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('w'),
+            KeyModifiers::CONTROL,
+        )))
         .await
         .unwrap();
-    assert_eq!(
-        vertical.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Left, 29)),
-    );
-    let (_, vertical_editor_size) = vertical.editor.test_active_window_bounds().unwrap();
-    assert_eq!(vertical_editor_size.0, 10);
-
-    vertical
-        .execute_action(Action::ResizeWindowLeft(/*amount*/ usize::MAX))
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('w'),
+            KeyModifiers::NONE,
+        )))
         .await
         .unwrap();
-    assert_eq!(
-        vertical.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Left, 12)),
-    );
 
-    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
-    let mut horizontal = EditorHarness::with_config_and_size(
-        buffer,
-        default_key_config(),
-        /*width*/ 40,
-        /*height*/ 14,
-    );
-    horizontal.editor.test_create_panel(
-        "inspector",
+    assert_eq!(harness.editor.test_focused_panel_id(), None);
+}
+
+#[tokio::test]
+async fn focused_agent_composer_routes_ctrl_w_w_into_focus_cycle() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    harness.editor.test_create_text_panel(
+        "agent",
         PanelConfig {
-            side: PanelSide::Bottom,
-            width: 4,
+            side: PanelSide::Right,
+            width: 40,
+            title: Some("Agent".to_string()),
+            composer: Some(TextPanelComposerConfig {
+                placeholder: "Ask".to_string(),
+                rows: 2,
+            }),
             ..PanelConfig::default()
         },
     );
-    assert!(horizontal.editor.test_focus_panel("inspector"));
+    assert!(harness.editor.test_focus_text_panel_composer("agent"));
 
-    horizontal
-        .execute_action(Action::ResizeWindowDown(/*amount*/ usize::MAX))
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('w'),
+            KeyModifiers::CONTROL,
+        )))
         .await
         .unwrap();
-    assert_eq!(
-        horizontal.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Bottom, 6)),
-    );
-    let (_, horizontal_editor_size) = horizontal.editor.test_active_window_bounds().unwrap();
-    assert_eq!(horizontal_editor_size.1, 5);
+    assert!(harness.is_waiting_for_key_sequence());
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
 
-    horizontal
-        .execute_action(Action::ResizeWindowUp(/*amount*/ usize::MAX))
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('w'),
+            KeyModifiers::NONE,
+        )))
         .await
         .unwrap();
-    assert_eq!(
-        horizontal.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Bottom, 4)),
-    );
+
+    assert!(!harness.is_waiting_for_key_sequence());
+    assert_eq!(harness.editor.test_focused_panel_id(), None);
 }
 
 #[tokio::test]
-async fn terminal_resize_preserves_independent_docked_pane_preferences() {
-    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
+async fn ctrl_w_w_focuses_agent_composer_and_makes_cursor_visible() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
     harness.editor.test_create_text_panel(
-        "inspector",
+        "agent",
         PanelConfig {
-            side: PanelSide::Left,
-            width: 24,
-            ..PanelConfig::default()
+            side: PanelSide::Right,
+            width: 40,
+            title: Some("Agent".to_string()),
+            composer: Some(TextPanelComposerConfig {
+                placeholder: "Ask".to_string(),
+                rows: 2,
+            }),
+            surface: None,
+            border: None,
+            header_actions: Vec::new(),
         },
     );
-    assert!(harness.editor.test_focus_panel("inspector"));
-    execute_window_chord(&mut harness, 'J').await;
-    execute_window_chord(&mut harness, '+').await;
-    execute_window_chord(&mut harness, '+').await;
-    assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Bottom, 9)),
-    );
+    let editor_cursor = harness.render_cursor_position();
 
     harness
-        .execute_event(Event::Resize(/*columns*/ 60, /*rows*/ 20))
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('w'),
+            KeyModifiers::CONTROL,
+        )))
+        .await
+        .unwrap();
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('w'),
+            KeyModifiers::NONE,
+        )))
         .await
         .unwrap();
 
-    assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Bottom, 9)),
-    );
-    let (_, editor_size) = harness.editor.test_active_window_bounds().unwrap();
-    assert_eq!(editor_size, (60, 8));
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
+    let composer_cursor = harness.render_cursor_position();
+    assert!(composer_cursor.is_some());
+    assert_ne!(composer_cursor, editor_cursor);
 
-    execute_window_chord(&mut harness, 'L').await;
-    assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Right, 24)),
-    );
+    let action = harness
+        .editor
+        .test_handle_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('x'),
+            KeyModifiers::NONE,
+        )))
+        .unwrap();
+    assert!(matches!(
+        action,
+        Some(KeyAction::Multiple(actions))
+            if actions.iter().any(|action| matches!(
+                action,
+                Action::NotifyPlugins(name, payload)
+                    if name == "panel:event:agent" && payload["action"] == "composer_input"
+            ))
+    ));
 
-    execute_window_chord(&mut harness, 'K').await;
+    let action = harness
+        .editor
+        .test_handle_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+        .unwrap();
+    assert!(matches!(
+        action,
+        Some(KeyAction::Multiple(actions))
+            if actions.iter().any(|action| matches!(
+                action,
+                Action::NotifyPlugins(name, payload)
+                    if name == "panel:event:agent" && payload["action"] == "composer_input"
+            ))
+    ));
+    assert!(harness.render_cursor_position().is_some());
+
+    let action = harness
+        .editor
+        .test_handle_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+        .unwrap();
+    assert!(matches!(
+        action,
+        Some(KeyAction::Multiple(actions))
+            if actions.iter().any(|action| matches!(
+                action,
+                Action::NotifyPlugins(name, payload)
+                    if name == "panel:event:agent" && payload["action"] == "composer_blur"
+            ))
+    ));
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
+    assert!(harness.render_cursor_position().is_some());
+}
+
+#[tokio::test]
+async fn mouse_click_inside_panel_focuses_and_selects_row() {
+    let mut harness = EditorHarness::with_content("abcdef");
+    add_tree_panel(&mut harness);
+
+    harness
+        .execute_event(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 1,
+            row: 2,
+            modifiers: KeyModifiers::NONE,
+        }))
+        .await
+        .unwrap();
+
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
     assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Top, 9)),
+        harness.editor.test_focused_panel_selected_index("tree"),
+        Some(2)
     );
+    assert_eq!(harness.render_cursor_position(), None);
+}
+
+#[test]
+fn mouse_double_click_inside_panel_activates_selected_row() {
+    let mut harness = EditorHarness::with_content("abcdef");
+    add_tree_panel(&mut harness);
+
+    for expected_action in ["select", "activate"] {
+        let action = harness
+            .editor
+            .test_handle_event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 1,
+                row: 2,
+                modifiers: KeyModifiers::NONE,
+            }))
+            .unwrap();
+
+        assert!(matches!(
+            action,
+            Some(KeyAction::Multiple(actions))
+                if actions.iter().any(|action| matches!(
+                    action,
+                    Action::NotifyPlugins(name, payload)
+                        if name == "panel:event:tree"
+                            && payload["action"] == expected_action
+                            && payload["selected_index"] == 2
+                ))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn mouse_click_in_editor_clears_panel_focus() {
+    let mut harness = EditorHarness::with_content("abcdef");
+    add_tree_panel(&mut harness);
+    assert!(harness.editor.test_focus_panel("tree"));
+
+    harness
+        .execute_event(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 25,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        }))
+        .await
+        .unwrap();
+
+    assert_eq!(harness.editor.test_focused_panel_id(), None);
+    assert!(harness.render_cursor_position().is_some());
 }
 
 #[tokio::test]
-async fn directional_window_chords_focus_and_leave_all_four_pane_edges() {
-    for (side, enter, leave, initial_size) in [
-        (PanelSide::Left, 'h', 'l', 20),
-        (PanelSide::Right, 'l', 'h', 20),
-        (PanelSide::Top, 'k', 'j', 6),
-        (PanelSide::Bottom, 'j', 'k', 6),
+async fn pane_divider_highlight_follows_press_drag_and_release_on_all_edges() {
+    for (side, initial_size, start, end, expected_glyph) in [
+        (PanelSide::Left, 20, (20, 4), (25, 4), '│'),
+        (PanelSide::Right, 20, (59, 4), (54, 4), '│'),
+        (PanelSide::Top, 6, (12, 6), (12, 9), '─'),
+        (PanelSide::Bottom, 6, (12, 15), (12, 12), '─'),
     ] {
         let buffer = Buffer::new(None, "first\nsecond\n".to_string());
         let mut harness = EditorHarness::with_config(buffer, default_key_config());
@@ -7722,1204 +9107,1000 @@ async fn directional_window_chords_focus_and_leave_all_four_pane_edges() {
                 ..PanelConfig::default()
             },
         );
+        assert!(harness.editor.test_focus_panel("inspector"));
 
-        execute_window_chord(&mut harness, enter).await;
-        assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
-
-        execute_window_chord(&mut harness, leave).await;
-        assert_eq!(harness.editor.test_focused_panel_id(), None);
-    }
-}
-
-#[tokio::test]
-async fn window_cycle_includes_top_left_editor_right_and_bottom_panes() {
-    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    for (id, side, width) in [
-        ("top", PanelSide::Top, 4),
-        ("left", PanelSide::Left, 12),
-        ("right", PanelSide::Right, 12),
-        ("bottom", PanelSide::Bottom, 4),
-    ] {
-        harness.editor.test_create_panel(
-            id,
-            PanelConfig {
-                side,
-                width,
-                ..PanelConfig::default()
-            },
+        assert_eq!(
+            harness
+                .render_row(usize::from(start.1))
+                .unwrap()
+                .chars()
+                .nth(usize::from(start.0)),
+            Some(' '),
         );
-    }
-
-    for expected in [
-        Some("right"),
-        Some("bottom"),
-        Some("top"),
-        Some("left"),
-        None,
-    ] {
-        execute_window_chord(&mut harness, 'w').await;
-        assert_eq!(harness.editor.test_focused_panel_id(), expected);
-    }
-}
-
-#[tokio::test]
-async fn shifted_window_chords_move_nested_splits_to_each_outer_edge() {
-    for (key, expected_position, expected_size) in [
-        ('H', (0, 0), (39, 22)),
-        ('J', (0, 11), (80, 11)),
-        ('K', (0, 0), (80, 10)),
-        ('L', (40, 0), (40, 22)),
-    ] {
-        let contents = (0..40)
-            .map(|line| format!("line {line:02}\n"))
-            .collect::<String>();
-        let buffer = Buffer::new(None, contents);
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-        harness.execute_action(Action::SplitVertical).await.unwrap();
-        harness
-            .execute_action(Action::SplitHorizontal)
-            .await
-            .unwrap();
-        harness.set_viewport_cursor(2, 3, 3);
-        let cursor = harness.cursor_position();
 
         harness
-            .execute_event(Event::Key(KeyEvent::new(
-                KeyCode::Char('w'),
-                KeyModifiers::CONTROL,
-            )))
-            .await
-            .unwrap();
-        assert!(harness.is_waiting_for_key_sequence());
-        harness
-            .execute_event(Event::Key(KeyEvent::new(
-                KeyCode::Char(key),
-                KeyModifiers::SHIFT,
-            )))
+            .execute_event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: start.0,
+                row: start.1,
+                modifiers: KeyModifiers::NONE,
+            }))
             .await
             .unwrap();
 
-        let (position, size) = harness.editor.test_active_window_bounds().unwrap();
-        assert_eq!((position.x, position.y), expected_position);
-        assert_eq!(size, expected_size);
-        assert_eq!(harness.window_count(), 3);
-        assert_eq!(harness.cursor_position(), cursor);
-        assert_eq!(harness.viewport_top(), 2);
-        assert!(!harness.is_waiting_for_key_sequence());
-
-        let snapshot = harness.editor.test_session_snapshot();
-        match (key, snapshot.window_layout.root) {
-            ('H', SplitSnapshot::Vertical { left, .. }) => {
-                assert!(matches!(left.as_ref(), SplitSnapshot::Window { .. }));
-            }
-            ('L', SplitSnapshot::Vertical { right, .. }) => {
-                assert!(matches!(right.as_ref(), SplitSnapshot::Window { .. }));
-            }
-            ('K', SplitSnapshot::Horizontal { top, .. }) => {
-                assert!(matches!(top.as_ref(), SplitSnapshot::Window { .. }));
-            }
-            ('J', SplitSnapshot::Horizontal { bottom, .. }) => {
-                assert!(matches!(bottom.as_ref(), SplitSnapshot::Window { .. }));
-            }
-            _ => panic!("shifted window chord did not create the expected outer split"),
-        }
-    }
-}
+        assert_eq!(
+            harness
+                .render_row(usize::from(start.1))
+                .unwrap()
+                .chars()
+                .nth(usize::from(start.0)),
+            Some(expected_glyph),
+            "the {side:?} divider should highlight immediately on mouse-down",
+        );
 
-#[tokio::test]
-async fn lowercase_window_chords_preserve_split_topology_and_move_focus() {
-    for (key, preparation) in [
-        ('h', Some(Action::MoveWindowRight)),
-        ('j', Some(Action::MoveWindowUp)),
-        ('k', None),
-        ('l', None),
-    ] {
-        let buffer = Buffer::new(None, "first\nsecond\nthird\n".to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-        harness.execute_action(Action::SplitVertical).await.unwrap();
-        harness
-            .execute_action(Action::SplitHorizontal)
-            .await
-            .unwrap();
-        harness
-            .execute_action(Action::MoveWindowLeft)
-            .await
-            .unwrap();
         harness
-            .execute_action(Action::SplitHorizontal)
+            .execute_event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Drag(MouseButton::Left),
+                column: end.0,
+                row: end.1,
+                modifiers: KeyModifiers::NONE,
+            }))
             .await
             .unwrap();
-        if let Some(action) = preparation {
-            harness.execute_action(action).await.unwrap();
-        }
-        let before = harness.editor.test_session_snapshot().window_layout;
+
+        assert_eq!(
+            harness
+                .render_row(usize::from(end.1))
+                .unwrap()
+                .chars()
+                .nth(usize::from(end.0)),
+            Some(expected_glyph),
+            "the {side:?} highlight should follow its moved divider",
+        );
 
         harness
-            .execute_event(Event::Key(KeyEvent::new(
-                KeyCode::Char('w'),
-                KeyModifiers::CONTROL,
-            )))
-            .await
-            .unwrap();
-        harness
-            .execute_event(Event::Key(KeyEvent::new(
-                KeyCode::Char(key),
-                KeyModifiers::NONE,
-            )))
+            .execute_event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Up(MouseButton::Left),
+                column: end.0,
+                row: end.1,
+                modifiers: KeyModifiers::NONE,
+            }))
             .await
             .unwrap();
 
-        let after = harness.editor.test_session_snapshot().window_layout;
-        assert_eq!(after.root, before.root);
-        assert_ne!(after.active_window_id, before.active_window_id);
-        assert_eq!(harness.window_count(), 4);
+        assert_eq!(
+            harness
+                .render_row(usize::from(end.1))
+                .unwrap()
+                .chars()
+                .nth(usize::from(end.0)),
+            Some(' '),
+            "the {side:?} divider should restore its idle appearance on mouse-up",
+        );
+        assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
     }
 }
 
 #[tokio::test]
-async fn moving_a_window_to_an_edge_preserves_both_side_panels() {
-    let buffer = Buffer::new(None, "first\nsecond\nthird\n".to_string());
+async fn escape_cancels_a_pane_divider_drag_without_stealing_focus() {
+    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.execute_action(Action::SplitVertical).await.unwrap();
-    harness
-        .execute_action(Action::SplitHorizontal)
-        .await
-        .unwrap();
-    add_tree_panel(&mut harness);
     harness.editor.test_create_panel(
-        "right",
+        "inspector",
         PanelConfig {
-            side: PanelSide::Right,
+            side: PanelSide::Left,
             width: 20,
-            title: None,
-            composer: None,
-            surface: None,
-            border: None,
-            header_actions: Vec::new(),
+            ..PanelConfig::default()
         },
     );
+    assert!(harness.editor.test_focus_panel("inspector"));
 
     harness
-        .execute_action(Action::MoveWindowToLeft)
+        .execute_event(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 20,
+            row: 4,
+            modifiers: KeyModifiers::NONE,
+        }))
         .await
         .unwrap();
-    let (left_position, left_size) = harness.editor.test_active_window_bounds().unwrap();
-    assert_eq!(left_position.x, 21);
-    assert_eq!(left_position.y, 0);
-    assert_eq!(left_size.1, 22);
-    assert!(left_position.x + left_size.0 <= 59);
+    assert_eq!(harness.render_row(4).unwrap().chars().nth(20), Some('│'));
 
     harness
-        .execute_action(Action::MoveWindowToRight)
+        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
         .await
         .unwrap();
-    let (right_position, right_size) = harness.editor.test_active_window_bounds().unwrap();
-    assert_eq!(right_position.y, 0);
-    assert_eq!(right_size.1, 22);
-    assert_eq!(right_position.x + right_size.0, 59);
-    assert_eq!(harness.window_count(), 3);
+
+    assert_eq!(harness.render_row(4).unwrap().chars().nth(20), Some(' '));
+    assert_eq!(
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Left, 20)),
+    );
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
+
+    let passive_drag = harness
+        .editor
+        .test_handle_event(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Drag(MouseButton::Left),
+            column: 25,
+            row: 4,
+            modifiers: KeyModifiers::NONE,
+        }))
+        .unwrap();
+    assert_eq!(passive_drag, None);
+    assert_eq!(
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Left, 20)),
+    );
 }
 
 #[tokio::test]
-async fn moving_a_single_window_to_an_edge_is_silent() {
-    for action in [
-        Action::MoveWindowToLeft,
-        Action::MoveWindowToBottom,
-        Action::MoveWindowToTop,
-        Action::MoveWindowToRight,
+async fn mouse_drag_resizes_all_four_docked_pane_edges_without_stealing_focus() {
+    for (side, initial_size, start, end, expected_size) in [
+        (PanelSide::Left, 20, (20, 4), (25, 4), 25),
+        (PanelSide::Right, 20, (59, 4), (54, 4), 25),
+        (PanelSide::Top, 6, (12, 6), (12, 9), 9),
+        (PanelSide::Bottom, 6, (12, 15), (12, 12), 9),
     ] {
-        let mut harness = EditorHarness::with_content("abcdef");
-        let before = harness.editor.test_session_snapshot().window_layout;
-
-        harness.execute_action(action).await.unwrap();
-
-        assert_eq!(harness.editor.test_session_snapshot().window_layout, before);
-        assert_eq!(harness.last_error(), None);
-        assert_eq!(harness.window_count(), 1);
-    }
-}
+        let buffer = Buffer::new(None, "first\nsecond\n".to_string());
+        let mut harness = EditorHarness::with_config(buffer, default_key_config());
+        harness.editor.test_create_panel(
+            "inspector",
+            PanelConfig {
+                side,
+                width: initial_size,
+                ..PanelConfig::default()
+            },
+        );
+        assert!(harness.editor.test_focus_panel("inspector"));
 
-#[tokio::test]
-async fn directional_window_boundaries_report_no_op() {
-    let mut harness = EditorHarness::with_content("abcdef");
+        drag_window_divider(&mut harness, start, end).await;
 
-    for (action, message) in [
-        (Action::MoveWindowUp, "no window above"),
-        (Action::MoveWindowDown, "no window below"),
-        (Action::MoveWindowLeft, "no window to the left"),
-        (Action::MoveWindowRight, "no window to the right"),
-    ] {
-        harness.execute_action(action).await.unwrap();
-        assert!(harness.commandline_row().contains(message));
+        assert_eq!(
+            harness.editor.test_panel_layout("inspector"),
+            Some((side, expected_size)),
+            "dragging the actual {side:?} divider should resize the focused pane",
+        );
+        assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
     }
 }
 
 #[tokio::test]
-async fn window_cycle_uses_left_windows_right_visual_groups() {
-    let mut harness = EditorHarness::with_content("abcdef");
-    add_tree_panel(&mut harness);
+async fn pane_divider_drag_stays_captured_after_the_pointer_enters_the_editor() {
+    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
     harness.editor.test_create_panel(
-        "right",
+        "inspector",
         PanelConfig {
-            side: PanelSide::Right,
+            side: PanelSide::Left,
             width: 20,
-            title: None,
-            composer: None,
-            surface: None,
-            border: None,
-            header_actions: Vec::new(),
+            ..PanelConfig::default()
         },
     );
-    harness.execute_action(Action::SplitVertical).await.unwrap();
-    assert_eq!(harness.active_window_id(), 1);
+    assert!(harness.editor.test_focus_panel("inspector"));
 
-    harness.execute_action(Action::NextWindow).await.unwrap();
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("right"));
+    for (kind, column) in [
+        (MouseEventKind::Down(MouseButton::Left), 20),
+        (MouseEventKind::Drag(MouseButton::Left), 34),
+    ] {
+        harness
+            .execute_event(Event::Mouse(MouseEvent {
+                kind,
+                column,
+                row: 3,
+                modifiers: KeyModifiers::NONE,
+            }))
+            .await
+            .unwrap();
+    }
 
-    harness.execute_action(Action::NextWindow).await.unwrap();
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
+    assert_eq!(
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Left, 34)),
+    );
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
 
-    harness.execute_action(Action::NextWindow).await.unwrap();
-    assert_eq!(harness.editor.test_focused_panel_id(), None);
-    assert_eq!(harness.active_window_id(), 0);
+    harness
+        .execute_event(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Up(MouseButton::Left),
+            column: 34,
+            row: 3,
+            modifiers: KeyModifiers::NONE,
+        }))
+        .await
+        .unwrap();
+
+    let passive_drag = harness
+        .editor
+        .test_handle_event(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Drag(MouseButton::Left),
+            column: 45,
+            row: 3,
+            modifiers: KeyModifiers::NONE,
+        }))
+        .unwrap();
+
+    assert_eq!(passive_drag, None);
+    assert_eq!(
+        harness.editor.test_panel_layout("inspector"),
+        Some((PanelSide::Left, 34)),
+    );
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
 }
 
 #[tokio::test]
-async fn focused_panel_routes_ctrl_w_w_into_focus_cycle() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
+async fn mouse_drag_resizes_a_vertical_editor_split_without_moving_the_cursor() {
+    let buffer = Buffer::new(None, "first\nsecond\nthird\n".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    add_tree_panel(&mut harness);
-    assert!(harness.editor.test_focus_panel("tree"));
+    harness.execute_action(Action::SplitVertical).await.unwrap();
+    let active_window_id = harness.active_window_id();
+    let cursor = harness.cursor_position();
 
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('w'),
-            KeyModifiers::CONTROL,
-        )))
-        .await
-        .unwrap();
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('w'),
-            KeyModifiers::NONE,
-        )))
-        .await
-        .unwrap();
+    drag_window_divider(&mut harness, (39, 4), (53, 4)).await;
 
+    let (position, size) = harness.editor.test_active_window_bounds().unwrap();
+    assert_eq!((position.x, position.y), (54, 0));
+    assert_eq!(size, (26, 22));
+    assert_eq!(harness.active_window_id(), active_window_id);
+    assert_eq!(harness.cursor_position(), cursor);
     assert_eq!(harness.editor.test_focused_panel_id(), None);
 }
 
 #[tokio::test]
-async fn focused_agent_composer_routes_ctrl_w_w_into_focus_cycle() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
+async fn mouse_drag_resizes_a_horizontal_editor_split_without_moving_the_cursor() {
+    let buffer = Buffer::new(None, "first\nsecond\nthird\n".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.editor.test_create_text_panel(
-        "agent",
-        PanelConfig {
-            side: PanelSide::Right,
-            width: 40,
-            title: Some("Agent".to_string()),
-            composer: Some(TextPanelComposerConfig {
-                placeholder: "Ask".to_string(),
-                rows: 2,
-            }),
-            ..PanelConfig::default()
-        },
-    );
-    assert!(harness.editor.test_focus_text_panel_composer("agent"));
-
     harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('w'),
-            KeyModifiers::CONTROL,
-        )))
+        .execute_action(Action::SplitHorizontal)
         .await
         .unwrap();
-    assert!(harness.is_waiting_for_key_sequence());
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
+    let active_window_id = harness.active_window_id();
+    let cursor = harness.cursor_position();
 
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('w'),
-            KeyModifiers::NONE,
-        )))
-        .await
-        .unwrap();
+    drag_window_divider(&mut harness, (8, 10), (8, 14)).await;
 
-    assert!(!harness.is_waiting_for_key_sequence());
+    let (position, size) = harness.editor.test_active_window_bounds().unwrap();
+    assert_eq!((position.x, position.y), (0, 15));
+    assert_eq!(size, (80, 7));
+    assert_eq!(harness.active_window_id(), active_window_id);
+    assert_eq!(harness.cursor_position(), cursor);
     assert_eq!(harness.editor.test_focused_panel_id(), None);
 }
 
 #[tokio::test]
-async fn ctrl_w_w_focuses_agent_composer_and_makes_cursor_visible() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
+async fn mouse_drag_preserves_a_focused_text_composer_and_its_draft() {
+    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
     harness.editor.test_create_text_panel(
         "agent",
         PanelConfig {
             side: PanelSide::Right,
-            width: 40,
+            width: 24,
             title: Some("Agent".to_string()),
             composer: Some(TextPanelComposerConfig {
                 placeholder: "Ask".to_string(),
                 rows: 2,
             }),
-            surface: None,
-            border: None,
-            header_actions: Vec::new(),
+            ..PanelConfig::default()
         },
-    );
-    let editor_cursor = harness.render_cursor_position();
-
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('w'),
-            KeyModifiers::CONTROL,
-        )))
-        .await
-        .unwrap();
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('w'),
-            KeyModifiers::NONE,
-        )))
-        .await
-        .unwrap();
-
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
-    let composer_cursor = harness.render_cursor_position();
-    assert!(composer_cursor.is_some());
-    assert_ne!(composer_cursor, editor_cursor);
-
-    let action = harness
-        .editor
-        .test_handle_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('x'),
-            KeyModifiers::NONE,
-        )))
-        .unwrap();
-    assert!(matches!(
-        action,
-        Some(KeyAction::Multiple(actions))
-            if actions.iter().any(|action| matches!(
-                action,
-                Action::NotifyPlugins(name, payload)
-                    if name == "panel:event:agent" && payload["action"] == "composer_input"
-            ))
-    ));
-
-    let action = harness
+    );
+    assert!(harness.editor.test_focus_text_panel_composer("agent"));
+    harness
         .editor
-        .test_handle_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+        .test_handle_event(Event::Paste("keep this follow-up".to_string()))
         .unwrap();
-    assert!(matches!(
-        action,
-        Some(KeyAction::Multiple(actions))
-            if actions.iter().any(|action| matches!(
-                action,
-                Action::NotifyPlugins(name, payload)
-                    if name == "panel:event:agent" && payload["action"] == "composer_input"
-            ))
-    ));
+
+    drag_window_divider(&mut harness, (55, 4), (50, 4)).await;
+
+    assert_eq!(
+        harness.editor.test_panel_layout("agent"),
+        Some((PanelSide::Right, 29)),
+    );
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
     assert!(harness.render_cursor_position().is_some());
 
-    let action = harness
+    let submitted = harness
         .editor
-        .test_handle_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+        .test_handle_event(Event::Key(KeyEvent::new(
+            KeyCode::Enter,
+            KeyModifiers::CONTROL,
+        )))
         .unwrap();
     assert!(matches!(
-        action,
+        submitted,
         Some(KeyAction::Multiple(actions))
             if actions.iter().any(|action| matches!(
                 action,
                 Action::NotifyPlugins(name, payload)
-                    if name == "panel:event:agent" && payload["action"] == "composer_blur"
+                    if name == "panel:event:agent"
+                        && payload["action"] == "submit"
+                        && payload["text"] == "keep this follow-up"
             ))
     ));
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
-    assert!(harness.render_cursor_position().is_some());
 }
 
-#[tokio::test]
-async fn mouse_click_inside_panel_focuses_and_selects_row() {
-    let mut harness = EditorHarness::with_content("abcdef");
+#[test]
+fn mouse_wheel_scrolls_hovered_row_panel_without_taking_focus() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config_and_size(buffer, default_key_config(), 80, 10);
     add_tree_panel(&mut harness);
+    harness
+        .editor
+        .test_update_panel("tree", numbered_tree_rows(30));
+    assert_eq!(harness.editor.test_focused_panel_id(), None);
+
+    let down = Event::Mouse(MouseEvent {
+        kind: MouseEventKind::ScrollDown,
+        column: 1,
+        row: 2,
+        modifiers: KeyModifiers::NONE,
+    });
+    harness.editor.test_handle_event(down.clone()).unwrap();
+    assert!(harness.render_row(0).unwrap().starts_with("row-03.rs"));
+    assert_eq!(harness.editor.test_focused_panel_id(), None);
+    assert_eq!(
+        harness.editor.test_focused_panel_selected_index("tree"),
+        Some(6)
+    );
+
+    harness.editor.test_handle_event(down).unwrap();
+    assert!(harness.render_row(0).unwrap().starts_with("row-06.rs"));
+    assert_eq!(
+        harness.editor.test_focused_panel_selected_index("tree"),
+        Some(9)
+    );
 
     harness
-        .execute_event(Event::Mouse(MouseEvent {
-            kind: MouseEventKind::Down(MouseButton::Left),
+        .editor
+        .test_handle_event(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::ScrollUp,
             column: 1,
             row: 2,
             modifiers: KeyModifiers::NONE,
         }))
-        .await
         .unwrap();
-
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
+    assert!(harness.render_row(0).unwrap().starts_with("row-03.rs"));
     assert_eq!(
         harness.editor.test_focused_panel_selected_index("tree"),
-        Some(2)
+        Some(7)
     );
-    assert_eq!(harness.render_cursor_position(), None);
 }
 
 #[test]
-fn mouse_double_click_inside_panel_activates_selected_row() {
-    let mut harness = EditorHarness::with_content("abcdef");
+fn mouse_wheel_over_editor_preserves_focused_row_panel() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
     add_tree_panel(&mut harness);
+    assert!(harness.editor.test_focus_panel("tree"));
 
-    for expected_action in ["select", "activate"] {
-        let action = harness
-            .editor
-            .test_handle_event(Event::Mouse(MouseEvent {
-                kind: MouseEventKind::Down(MouseButton::Left),
-                column: 1,
-                row: 2,
-                modifiers: KeyModifiers::NONE,
-            }))
-            .unwrap();
+    let action = harness
+        .editor
+        .test_handle_event(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::ScrollDown,
+            column: 30,
+            row: 2,
+            modifiers: KeyModifiers::NONE,
+        }))
+        .unwrap();
 
-        assert!(matches!(
-            action,
-            Some(KeyAction::Multiple(actions))
-                if actions.iter().any(|action| matches!(
-                    action,
-                    Action::NotifyPlugins(name, payload)
-                        if name == "panel:event:tree"
-                            && payload["action"] == expected_action
-                            && payload["selected_index"] == 2
-                ))
-        ));
-    }
+    assert_eq!(action, Some(KeyAction::Single(Action::ScrollDown)));
+    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
 }
 
-#[tokio::test]
-async fn mouse_click_in_editor_clears_panel_focus() {
-    let mut harness = EditorHarness::with_content("abcdef");
+#[test]
+fn zero_mouse_scroll_lines_does_not_move_hovered_row_panel() {
+    let mut config = default_key_config();
+    config.mouse_scroll_lines = Some(0);
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config_and_size(buffer, config, 80, 10);
     add_tree_panel(&mut harness);
-    assert!(harness.editor.test_focus_panel("tree"));
-
     harness
-        .execute_event(Event::Mouse(MouseEvent {
-            kind: MouseEventKind::Down(MouseButton::Left),
-            column: 25,
-            row: 0,
+        .editor
+        .test_update_panel("tree", numbered_tree_rows(30));
+
+    let action = harness
+        .editor
+        .test_handle_event(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::ScrollDown,
+            column: 1,
+            row: 2,
             modifiers: KeyModifiers::NONE,
         }))
-        .await
         .unwrap();
 
+    assert!(matches!(action, Some(KeyAction::Multiple(_))));
+    assert!(harness.render_row(0).unwrap().starts_with("row-00.rs"));
+    assert_eq!(
+        harness.editor.test_focused_panel_selected_index("tree"),
+        Some(0)
+    );
     assert_eq!(harness.editor.test_focused_panel_id(), None);
-    assert!(harness.render_cursor_position().is_some());
 }
 
-#[tokio::test]
-async fn pane_divider_highlight_follows_press_drag_and_release_on_all_edges() {
-    for (side, initial_size, start, end, expected_glyph) in [
-        (PanelSide::Left, 20, (20, 4), (25, 4), '│'),
-        (PanelSide::Right, 20, (59, 4), (54, 4), '│'),
-        (PanelSide::Top, 6, (12, 6), (12, 9), '─'),
-        (PanelSide::Bottom, 6, (12, 15), (12, 12), '─'),
-    ] {
-        let buffer = Buffer::new(None, "first\nsecond\n".to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-        harness.editor.test_create_panel(
-            "inspector",
-            PanelConfig {
-                side,
-                width: initial_size,
-                ..PanelConfig::default()
-            },
-        );
-        assert!(harness.editor.test_focus_panel("inspector"));
-
-        assert_eq!(
-            harness
-                .render_row(usize::from(start.1))
-                .unwrap()
-                .chars()
-                .nth(usize::from(start.0)),
-            Some(' '),
-        );
-
-        harness
-            .execute_event(Event::Mouse(MouseEvent {
-                kind: MouseEventKind::Down(MouseButton::Left),
-                column: start.0,
-                row: start.1,
-                modifiers: KeyModifiers::NONE,
-            }))
-            .await
-            .unwrap();
-
-        assert_eq!(
-            harness
-                .render_row(usize::from(start.1))
-                .unwrap()
-                .chars()
-                .nth(usize::from(start.0)),
-            Some(expected_glyph),
-            "the {side:?} divider should highlight immediately on mouse-down",
-        );
-
-        harness
-            .execute_event(Event::Mouse(MouseEvent {
-                kind: MouseEventKind::Drag(MouseButton::Left),
-                column: end.0,
-                row: end.1,
-                modifiers: KeyModifiers::NONE,
-            }))
-            .await
-            .unwrap();
-
-        assert_eq!(
-            harness
-                .render_row(usize::from(end.1))
-                .unwrap()
-                .chars()
-                .nth(usize::from(end.0)),
-            Some(expected_glyph),
-            "the {side:?} highlight should follow its moved divider",
-        );
+#[test]
+fn passive_mouse_events_over_editor_do_not_clear_focused_agent_composer() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
+    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+    harness.editor.test_create_text_panel(
+        "agent",
+        PanelConfig {
+            side: PanelSide::Right,
+            width: 40,
+            title: Some("Agent".to_string()),
+            composer: Some(TextPanelComposerConfig {
+                placeholder: "Ask".to_string(),
+                rows: 2,
+            }),
+            surface: None,
+            border: None,
+            header_actions: Vec::new(),
+        },
+    );
+    assert!(harness.editor.test_focus_text_panel_composer("agent"));
+    let cursor = harness.render_cursor_position();
 
-        harness
-            .execute_event(Event::Mouse(MouseEvent {
-                kind: MouseEventKind::Up(MouseButton::Left),
-                column: end.0,
-                row: end.1,
+    for kind in [
+        MouseEventKind::Moved,
+        MouseEventKind::Up(MouseButton::Left),
+        MouseEventKind::Drag(MouseButton::Left),
+    ] {
+        let action = harness
+            .editor
+            .test_handle_event(Event::Mouse(MouseEvent {
+                kind,
+                column: 10,
+                row: 1,
                 modifiers: KeyModifiers::NONE,
             }))
-            .await
             .unwrap();
 
-        assert_eq!(
-            harness
-                .render_row(usize::from(end.1))
-                .unwrap()
-                .chars()
-                .nth(usize::from(end.0)),
-            Some(' '),
-            "the {side:?} divider should restore its idle appearance on mouse-up",
-        );
-        assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
+        assert_eq!(action, None);
+        assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
+        assert_eq!(harness.render_cursor_position(), cursor);
     }
 }
 
 #[tokio::test]
-async fn escape_cancels_a_pane_divider_drag_without_stealing_focus() {
-    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
+async fn only_window_hides_auxiliary_panels_and_preserves_agent_draft() {
+    let buffer = Buffer::new(None, "abcdef".to_string());
     let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.editor.test_create_panel(
-        "inspector",
+    add_tree_panel(&mut harness);
+    harness.editor.test_create_text_panel(
+        "agent",
         PanelConfig {
-            side: PanelSide::Left,
-            width: 20,
-            ..PanelConfig::default()
+            side: PanelSide::Right,
+            width: 24,
+            title: Some("Agent".to_string()),
+            composer: Some(TextPanelComposerConfig {
+                placeholder: "Ask".to_string(),
+                rows: 2,
+            }),
+            surface: None,
+            border: None,
+            header_actions: Vec::new(),
         },
     );
-    assert!(harness.editor.test_focus_panel("inspector"));
-
+    assert!(harness.editor.test_focus_text_panel_composer("agent"));
     harness
-        .execute_event(Event::Mouse(MouseEvent {
-            kind: MouseEventKind::Down(MouseButton::Left),
-            column: 20,
-            row: 4,
-            modifiers: KeyModifiers::NONE,
-        }))
+        .editor
+        .test_handle_event(Event::Paste("keep this follow-up".to_string()))
+        .unwrap();
+    harness.execute_action(Action::SplitVertical).await.unwrap();
+    harness
+        .execute_action(Action::PreviousWindow)
         .await
         .unwrap();
-    assert_eq!(harness.render_row(4).unwrap().chars().nth(20), Some('│'));
+    assert_eq!(harness.editor.test_focused_panel_id(), None);
 
     harness
-        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('w'),
+            KeyModifiers::CONTROL,
+        )))
         .await
         .unwrap();
-
-    assert_eq!(harness.render_row(4).unwrap().chars().nth(20), Some(' '));
-    assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Left, 20)),
-    );
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
-
-    let passive_drag = harness
-        .editor
-        .test_handle_event(Event::Mouse(MouseEvent {
-            kind: MouseEventKind::Drag(MouseButton::Left),
-            column: 25,
-            row: 4,
-            modifiers: KeyModifiers::NONE,
-        }))
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('o'),
+            KeyModifiers::NONE,
+        )))
+        .await
         .unwrap();
-    assert_eq!(passive_drag, None);
+
+    assert_eq!(harness.window_count(), 1);
+    assert_eq!(harness.editor.test_focused_panel_id(), None);
+    assert!(!harness.editor.test_focus_panel("tree"));
+    assert!(!harness.editor.test_focus_text_panel_composer("agent"));
     assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Left, 20)),
+        harness.editor.test_active_window_bounds(),
+        Some((red::editor::Point::new(0, 0), (80, 22)))
     );
-}
-
-#[tokio::test]
-async fn mouse_drag_resizes_all_four_docked_pane_edges_without_stealing_focus() {
-    for (side, initial_size, start, end, expected_size) in [
-        (PanelSide::Left, 20, (20, 4), (25, 4), 25),
-        (PanelSide::Right, 20, (59, 4), (54, 4), 25),
-        (PanelSide::Top, 6, (12, 6), (12, 9), 9),
-        (PanelSide::Bottom, 6, (12, 15), (12, 12), 9),
-    ] {
-        let buffer = Buffer::new(None, "first\nsecond\n".to_string());
-        let mut harness = EditorHarness::with_config(buffer, default_key_config());
-        harness.editor.test_create_panel(
-            "inspector",
-            PanelConfig {
-                side,
-                width: initial_size,
-                ..PanelConfig::default()
-            },
-        );
-        assert!(harness.editor.test_focus_panel("inspector"));
 
-        drag_window_divider(&mut harness, start, end).await;
-
-        assert_eq!(
-            harness.editor.test_panel_layout("inspector"),
-            Some((side, expected_size)),
-            "dragging the actual {side:?} divider should resize the focused pane",
-        );
-        assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
-    }
+    assert!(harness.editor.test_set_panel_visible("agent", true));
+    assert!(harness.editor.test_focus_text_panel_composer("agent"));
+    assert!((0..24).any(|row| {
+        harness
+            .editor
+            .test_render_row(row)
+            .unwrap()
+            .contains("keep this follow-up")
+    }));
 }
 
 #[tokio::test]
-async fn pane_divider_drag_stays_captured_after_the_pointer_enters_the_editor() {
-    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.editor.test_create_panel(
-        "inspector",
-        PanelConfig {
-            side: PanelSide::Left,
-            width: 20,
-            ..PanelConfig::default()
-        },
-    );
-    assert!(harness.editor.test_focus_panel("inspector"));
-
-    for (kind, column) in [
-        (MouseEventKind::Down(MouseButton::Left), 20),
-        (MouseEventKind::Drag(MouseButton::Left), 34),
-    ] {
-        harness
-            .execute_event(Event::Mouse(MouseEvent {
-                kind,
-                column,
-                row: 3,
-                modifiers: KeyModifiers::NONE,
-            }))
-            .await
-            .unwrap();
-    }
+async fn test_dirty_isolated_per_buffer() {
+    let lsp = Box::new(MockLsp) as Box<dyn LspClient + Send>;
+    let config = Config::default();
+    let theme = Theme::default();
+    let buffers = vec![
+        Buffer::new(None, "one".to_string()),
+        Buffer::new(None, "two".to_string()),
+    ];
+    let mut editor = Editor::test_with_size(lsp, 80, 24, config, theme, buffers).unwrap();
+    editor.test_disable_terminal_output();
+    let mut harness = EditorHarness { editor };
 
-    assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Left, 34)),
-    );
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
+    harness
+        .execute_action(Action::DeleteCharAtCursorPos)
+        .await
+        .unwrap();
+    assert!(harness.is_dirty());
 
+    harness.execute_action(Action::NextBuffer).await.unwrap();
+    assert!(!harness.is_dirty());
     harness
-        .execute_event(Event::Mouse(MouseEvent {
-            kind: MouseEventKind::Up(MouseButton::Left),
-            column: 34,
-            row: 3,
-            modifiers: KeyModifiers::NONE,
-        }))
+        .execute_action(Action::DeleteCharAtCursorPos)
         .await
         .unwrap();
+    assert!(harness.is_dirty());
+    harness.execute_action(Action::Undo).await.unwrap();
+    assert!(!harness.is_dirty());
 
-    let passive_drag = harness
-        .editor
-        .test_handle_event(Event::Mouse(MouseEvent {
-            kind: MouseEventKind::Drag(MouseButton::Left),
-            column: 45,
-            row: 3,
-            modifiers: KeyModifiers::NONE,
-        }))
+    harness
+        .execute_action(Action::PreviousBuffer)
+        .await
         .unwrap();
+    assert!(harness.is_dirty());
+    harness.execute_action(Action::Undo).await.unwrap();
+    assert!(!harness.is_dirty());
+}
 
-    assert_eq!(passive_drag, None);
-    assert_eq!(
-        harness.editor.test_panel_layout("inspector"),
-        Some((PanelSide::Left, 34)),
-    );
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("inspector"));
+#[tokio::test]
+async fn test_paste() {
+    let mut harness = EditorHarness::with_content("Hello World");
+
+    // Delete a word (should be yanked to clipboard)
+    harness.execute_action(Action::DeleteWord).await.unwrap();
+    harness.assert_buffer_contents("World");
+
+    // Move to end and paste with 'p'
+    harness.execute_action(Action::MoveToLineEnd).await.unwrap();
+    harness.execute_action(Action::Paste).await.unwrap();
+    // This depends on clipboard/register implementation
+    // For now, let's just verify it doesn't crash
 }
 
 #[tokio::test]
-async fn mouse_drag_resizes_a_vertical_editor_split_without_moving_the_cursor() {
-    let buffer = Buffer::new(None, "first\nsecond\nthird\n".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.execute_action(Action::SplitVertical).await.unwrap();
-    let active_window_id = harness.active_window_id();
-    let cursor = harness.cursor_position();
+async fn test_yank_and_paste() {
+    let mut harness = EditorHarness::with_content("Line 1\nLine 2\nLine 3");
 
-    drag_window_divider(&mut harness, (39, 4), (53, 4)).await;
+    // Yank action exists
+    harness.execute_action(Action::Yank).await.unwrap();
 
-    let (position, size) = harness.editor.test_active_window_bounds().unwrap();
-    assert_eq!((position.x, position.y), (54, 0));
-    assert_eq!(size, (26, 22));
-    assert_eq!(harness.active_window_id(), active_window_id);
-    assert_eq!(harness.cursor_position(), cursor);
-    assert_eq!(harness.editor.test_focused_panel_id(), None);
+    // Move down and paste
+    harness.execute_action(Action::MoveDown).await.unwrap();
+    harness.execute_action(Action::Paste).await.unwrap();
+    // This depends on clipboard/register implementation
 }
 
 #[tokio::test]
-async fn mouse_drag_resizes_a_horizontal_editor_split_without_moving_the_cursor() {
-    let buffer = Buffer::new(None, "first\nsecond\nthird\n".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
+async fn test_direct_open_line_below_groups_insert_undo() {
+    let mut harness = EditorHarness::with_content("Line 1\nLine 2");
+
+    harness
+        .execute_action(Action::InsertLineBelowCursor)
+        .await
+        .unwrap();
+    harness.type_text("New line").await.unwrap();
     harness
-        .execute_action(Action::SplitHorizontal)
+        .execute_action(Action::EnterMode(Mode::Normal))
         .await
         .unwrap();
-    let active_window_id = harness.active_window_id();
-    let cursor = harness.cursor_position();
 
-    drag_window_divider(&mut harness, (8, 10), (8, 14)).await;
-
-    let (position, size) = harness.editor.test_active_window_bounds().unwrap();
-    assert_eq!((position.x, position.y), (0, 15));
-    assert_eq!(size, (80, 7));
-    assert_eq!(harness.active_window_id(), active_window_id);
-    assert_eq!(harness.cursor_position(), cursor);
-    assert_eq!(harness.editor.test_focused_panel_id(), None);
+    harness.assert_buffer_contents("Line 1\nNew line\nLine 2");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("Line 1\nLine 2");
 }
 
 #[tokio::test]
-async fn mouse_drag_preserves_a_focused_text_composer_and_its_draft() {
-    let buffer = Buffer::new(None, "first\nsecond\n".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.editor.test_create_text_panel(
-        "agent",
-        PanelConfig {
-            side: PanelSide::Right,
-            width: 24,
-            title: Some("Agent".to_string()),
-            composer: Some(TextPanelComposerConfig {
-                placeholder: "Ask".to_string(),
-                rows: 2,
-            }),
-            ..PanelConfig::default()
-        },
-    );
-    assert!(harness.editor.test_focus_text_panel_composer("agent"));
+async fn test_editing_empty_buffer() {
+    let mut harness = EditorHarness::new();
+
+    // Enter insert mode in empty buffer
     harness
-        .editor
-        .test_handle_event(Event::Paste("keep this follow-up".to_string()))
+        .execute_action(Action::EnterMode(Mode::Insert))
+        .await
         .unwrap();
+    harness.type_text("First line").await.unwrap();
+    harness.assert_buffer_contents("First line\n");
 
-    drag_window_divider(&mut harness, (55, 4), (50, 4)).await;
-
-    assert_eq!(
-        harness.editor.test_panel_layout("agent"),
-        Some((PanelSide::Right, 29)),
-    );
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
-    assert!(harness.render_cursor_position().is_some());
-
-    let submitted = harness
-        .editor
-        .test_handle_event(Event::Key(KeyEvent::new(
-            KeyCode::Enter,
-            KeyModifiers::CONTROL,
-        )))
+    // Exit and create new line below
+    harness
+        .execute_action(Action::EnterMode(Mode::Normal))
+        .await
         .unwrap();
-    assert!(matches!(
-        submitted,
-        Some(KeyAction::Multiple(actions))
-            if actions.iter().any(|action| matches!(
-                action,
-                Action::NotifyPlugins(name, payload)
-                    if name == "panel:event:agent"
-                        && payload["action"] == "submit"
-                        && payload["text"] == "keep this follow-up"
-            ))
-    ));
-}
-
-#[test]
-fn mouse_wheel_scrolls_hovered_row_panel_without_taking_focus() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut harness = EditorHarness::with_config_and_size(buffer, default_key_config(), 80, 10);
-    add_tree_panel(&mut harness);
     harness
-        .editor
-        .test_update_panel("tree", numbered_tree_rows(30));
-    assert_eq!(harness.editor.test_focused_panel_id(), None);
+        .execute_action(Action::InsertLineBelowCursor)
+        .await
+        .unwrap();
+    harness.type_text("Second line").await.unwrap();
+    harness.assert_buffer_contents("First line\nSecond line\n");
+}
 
-    let down = Event::Mouse(MouseEvent {
-        kind: MouseEventKind::ScrollDown,
-        column: 1,
-        row: 2,
-        modifiers: KeyModifiers::NONE,
-    });
-    harness.editor.test_handle_event(down.clone()).unwrap();
-    assert!(harness.render_row(0).unwrap().starts_with("row-03.rs"));
-    assert_eq!(harness.editor.test_focused_panel_id(), None);
-    assert_eq!(
-        harness.editor.test_focused_panel_selected_index("tree"),
-        Some(6)
-    );
+#[tokio::test]
+async fn test_delete_at_end_of_file() {
+    let mut harness = EditorHarness::with_content("Line 1\nLine 2");
 
-    harness.editor.test_handle_event(down).unwrap();
-    assert!(harness.render_row(0).unwrap().starts_with("row-06.rs"));
-    assert_eq!(
-        harness.editor.test_focused_panel_selected_index("tree"),
-        Some(9)
+    // Move to last line
+    harness.execute_action(Action::MoveToBottom).await.unwrap();
+    println!(
+        "After MoveToBottom: cursor at {:?}",
+        harness.cursor_position()
     );
+    println!("Current line: {:?}", harness.current_line());
 
+    // Try to delete line at end of file
     harness
-        .editor
-        .test_handle_event(Event::Mouse(MouseEvent {
-            kind: MouseEventKind::ScrollUp,
-            column: 1,
-            row: 2,
-            modifiers: KeyModifiers::NONE,
-        }))
+        .execute_action(Action::DeleteCurrentLine)
+        .await
         .unwrap();
-    assert!(harness.render_row(0).unwrap().starts_with("row-03.rs"));
-    assert_eq!(
-        harness.editor.test_focused_panel_selected_index("tree"),
-        Some(7)
-    );
+    println!("After delete: {:?}", harness.buffer_contents());
+    harness.assert_buffer_contents("Line 1\n");
 }
 
-#[test]
-fn mouse_wheel_over_editor_preserves_focused_row_panel() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    add_tree_panel(&mut harness);
-    assert!(harness.editor.test_focus_panel("tree"));
-
-    let action = harness
-        .editor
-        .test_handle_event(Event::Mouse(MouseEvent {
-            kind: MouseEventKind::ScrollDown,
-            column: 30,
-            row: 2,
-            modifiers: KeyModifiers::NONE,
-        }))
-        .unwrap();
+#[tokio::test]
+async fn test_change_to_end_of_line() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "Hello World Test".to_string()),
+        default_key_config(),
+    );
 
-    assert_eq!(action, Some(KeyAction::Single(Action::ScrollDown)));
-    assert_eq!(harness.editor.test_focused_panel_id(), Some("tree"));
+    type_normal_keys(&mut harness, "wC").await;
+    harness.assert_mode(Mode::Insert);
+    harness.type_text("Universe").await.unwrap();
+    harness.assert_buffer_contents("Hello Universe");
 }
 
-#[test]
-fn zero_mouse_scroll_lines_does_not_move_hovered_row_panel() {
-    let mut config = default_key_config();
-    config.mouse_scroll_lines = Some(0);
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut harness = EditorHarness::with_config_and_size(buffer, config, 80, 10);
-    add_tree_panel(&mut harness);
-    harness
-        .editor
-        .test_update_panel("tree", numbered_tree_rows(30));
+#[tokio::test]
+async fn vim_parity_counted_word_operators_stop_at_blank_line_boundaries() {
+    for (contents, keys, expected, cursor) in [
+        (
+            "alpha beta\n\nnext line",
+            "wd2w",
+            "alpha \nnext line",
+            (5, 0),
+        ),
+        (
+            "alpha beta\n\nnext line",
+            "w2dw",
+            "alpha \nnext line",
+            (5, 0),
+        ),
+        ("alpha beta\n\nnext line", "wd3w", "alpha line", (6, 0)),
+        ("\n    next line", "dw", "    next line", (4, 0)),
+        ("\n    next line", "cwX", "X\n    next line", (0, 0)),
+        (
+            "alpha beta\n\nnext line",
+            "wy2wp",
+            "alpha bbeta\neta\n\nnext line",
+            (7, 0),
+        ),
+    ] {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, contents.to_string()),
+            default_key_config(),
+        );
 
-    let action = harness
-        .editor
-        .test_handle_event(Event::Mouse(MouseEvent {
-            kind: MouseEventKind::ScrollDown,
-            column: 1,
-            row: 2,
-            modifiers: KeyModifiers::NONE,
-        }))
-        .unwrap();
+        type_normal_keys(&mut harness, keys).await;
+        if harness.is_insert() {
+            command_key(&mut harness, KeyCode::Esc).await;
+        }
 
-    assert!(matches!(action, Some(KeyAction::Multiple(_))));
-    assert!(harness.render_row(0).unwrap().starts_with("row-00.rs"));
-    assert_eq!(
-        harness.editor.test_focused_panel_selected_index("tree"),
-        Some(0)
-    );
-    assert_eq!(harness.editor.test_focused_panel_id(), None);
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(cursor.0, cursor.1);
+        harness.assert_mode(Mode::Normal);
+    }
 }
 
-#[test]
-fn passive_mouse_events_over_editor_do_not_clear_focused_agent_composer() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    harness.editor.test_create_text_panel(
-        "agent",
-        PanelConfig {
-            side: PanelSide::Right,
-            width: 40,
-            title: Some("Agent".to_string()),
-            composer: Some(TextPanelComposerConfig {
-                placeholder: "Ask".to_string(),
-                rows: 2,
-            }),
-            surface: None,
-            border: None,
-            header_actions: Vec::new(),
-        },
-    );
-    assert!(harness.editor.test_focus_text_panel_composer("agent"));
-    let cursor = harness.render_cursor_position();
-
-    for kind in [
-        MouseEventKind::Moved,
-        MouseEventKind::Up(MouseButton::Left),
-        MouseEventKind::Drag(MouseButton::Left),
+#[tokio::test]
+async fn vim_parity_big_word_operators_and_text_objects_match_neovim() {
+    for (contents, keys, expected, cursor) in [
+        ("foo.bar baz", "dW", "baz", (0, 0)),
+        ("foo.bar baz qux", "d2W", "qux", (0, 0)),
+        ("foo.bar baz", "cWX", "X baz", (0, 0)),
+        ("foo.bar baz", "yWp", "ffoo.bar oo.bar baz", (8, 0)),
+        ("foo.bar baz", "diW", " baz", (0, 0)),
+        ("foo.bar baz", "daW", "baz", (0, 0)),
+        ("α.β γδ", "dW", "γδ", (0, 0)),
+        ("foo.bar\n\n baz qux", "d2W", " baz qux", (1, 0)),
     ] {
-        let action = harness
-            .editor
-            .test_handle_event(Event::Mouse(MouseEvent {
-                kind,
-                column: 10,
-                row: 1,
-                modifiers: KeyModifiers::NONE,
-            }))
-            .unwrap();
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, contents.to_string()),
+            default_key_config(),
+        );
 
-        assert_eq!(action, None);
-        assert_eq!(harness.editor.test_focused_panel_id(), Some("agent"));
-        assert_eq!(harness.render_cursor_position(), cursor);
+        type_normal_keys(&mut harness, keys).await;
+        if harness.is_insert() {
+            command_key(&mut harness, KeyCode::Esc).await;
+        }
+
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(cursor.0, cursor.1);
+        harness.assert_mode(Mode::Normal);
     }
 }
 
 #[tokio::test]
-async fn only_window_hides_auxiliary_panels_and_preserves_agent_draft() {
-    let buffer = Buffer::new(None, "abcdef".to_string());
-    let mut harness = EditorHarness::with_config(buffer, default_key_config());
-    add_tree_panel(&mut harness);
-    harness.editor.test_create_text_panel(
-        "agent",
-        PanelConfig {
-            side: PanelSide::Right,
-            width: 24,
-            title: Some("Agent".to_string()),
-            composer: Some(TextPanelComposerConfig {
-                placeholder: "Ask".to_string(),
-                rows: 2,
-            }),
-            surface: None,
-            border: None,
-            header_actions: Vec::new(),
-        },
-    );
-    assert!(harness.editor.test_focus_text_panel_composer("agent"));
-    harness
-        .editor
-        .test_handle_event(Event::Paste("keep this follow-up".to_string()))
-        .unwrap();
-    harness.execute_action(Action::SplitVertical).await.unwrap();
-    harness
-        .execute_action(Action::PreviousWindow)
-        .await
-        .unwrap();
-    assert_eq!(harness.editor.test_focused_panel_id(), None);
-
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('w'),
-            KeyModifiers::CONTROL,
-        )))
-        .await
-        .unwrap();
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('o'),
-            KeyModifiers::NONE,
-        )))
-        .await
-        .unwrap();
+async fn vim_parity_character_operators_accept_horizontal_motions_and_counts() {
+    for (contents, keys, expected, cursor) in [
+        ("alpha", "dl", "lpha", (0, 0)),
+        ("alpha", "ldh", "lpha", (0, 0)),
+        ("alpha", "d2l", "pha", (0, 0)),
+        ("alpha", "d99l", "", (0, 0)),
+        ("alpha", "3ld99h", "ha", (0, 0)),
+        ("alpha", "clX", "Xlpha", (0, 0)),
+        ("alpha", "ylp", "aalpha", (1, 0)),
+        ("αβγ", "dl", "βγ", (0, 0)),
+        ("αβγ", "ldh", "βγ", (0, 0)),
+        ("αβγ", "d2l", "γ", (0, 0)),
+    ] {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, contents.to_string()),
+            default_key_config(),
+        );
 
-    assert_eq!(harness.window_count(), 1);
-    assert_eq!(harness.editor.test_focused_panel_id(), None);
-    assert!(!harness.editor.test_focus_panel("tree"));
-    assert!(!harness.editor.test_focus_text_panel_composer("agent"));
-    assert_eq!(
-        harness.editor.test_active_window_bounds(),
-        Some((red::editor::Point::new(0, 0), (80, 22)))
-    );
+        type_normal_keys(&mut harness, keys).await;
+        if harness.is_insert() {
+            command_key(&mut harness, KeyCode::Esc).await;
+        }
 
-    assert!(harness.editor.test_set_panel_visible("agent", true));
-    assert!(harness.editor.test_focus_text_panel_composer("agent"));
-    assert!((0..24).any(|row| {
-        harness
-            .editor
-            .test_render_row(row)
-            .unwrap()
-            .contains("keep this follow-up")
-    }));
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(cursor.0, cursor.1);
+        harness.assert_mode(Mode::Normal);
+    }
 }
 
 #[tokio::test]
-async fn test_dirty_isolated_per_buffer() {
-    let lsp = Box::new(MockLsp) as Box<dyn LspClient + Send>;
-    let config = Config::default();
-    let theme = Theme::default();
-    let buffers = vec![
-        Buffer::new(None, "one".to_string()),
-        Buffer::new(None, "two".to_string()),
-    ];
-    let mut editor = Editor::test_with_size(lsp, 80, 24, config, theme, buffers).unwrap();
-    editor.test_disable_terminal_output();
-    let mut harness = EditorHarness { editor };
-
-    harness
-        .execute_action(Action::DeleteCharAtCursorPos)
-        .await
-        .unwrap();
-    assert!(harness.is_dirty());
+async fn vim_parity_end_word_operators_include_the_final_buffer_character() {
+    for (contents, keys, expected, cursor) in [
+        ("alpha beta", "de", " beta", (0, 0)),
+        ("alpha", "$de", "alph", (3, 0)),
+        ("alpha", "$dE", "alph", (3, 0)),
+        ("x", "de", "", (0, 0)),
+        ("alpha", "$ceX", "alphX", (4, 0)),
+    ] {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, contents.to_string()),
+            default_key_config(),
+        );
 
-    harness.execute_action(Action::NextBuffer).await.unwrap();
-    assert!(!harness.is_dirty());
-    harness
-        .execute_action(Action::DeleteCharAtCursorPos)
-        .await
-        .unwrap();
-    assert!(harness.is_dirty());
-    harness.execute_action(Action::Undo).await.unwrap();
-    assert!(!harness.is_dirty());
+        type_normal_keys(&mut harness, keys).await;
+        if harness.is_insert() {
+            command_key(&mut harness, KeyCode::Esc).await;
+        }
 
-    harness
-        .execute_action(Action::PreviousBuffer)
-        .await
-        .unwrap();
-    assert!(harness.is_dirty());
-    harness.execute_action(Action::Undo).await.unwrap();
-    assert!(!harness.is_dirty());
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(cursor.0, cursor.1);
+        harness.assert_mode(Mode::Normal);
+    }
 }
 
 #[tokio::test]
-async fn test_paste() {
-    let mut harness = EditorHarness::with_content("Hello World");
+async fn vim_parity_linewise_operators_accept_file_boundary_motions() {
+    for (contents, keys, expected, cursor) in [
+        ("one\ntwo\nthree", "dG", "", (0, 0)),
+        ("one\ntwo\nthree", "jdG", "one", (0, 0)),
+        ("one\ntwo\nthree", "jdgg", "three", (0, 0)),
+        ("one\ntwo\nthree\nfour", "2dG", "three\nfour", (0, 0)),
+        ("one\ntwo\nthree\nfour", "d2G", "three\nfour", (0, 0)),
+        ("one\ntwo\nthree\nfour", "jjd2gg", "one\nfour", (0, 1)),
+        (
+            "one\ntwo\nthree",
+            "yGp",
+            "one\none\ntwo\nthree\ntwo\nthree",
+            (0, 1),
+        ),
+        ("one\ntwo\nthree", "cGX", "X", (0, 0)),
+    ] {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, contents.to_string()),
+            default_key_config(),
+        );
 
-    // Delete a word (should be yanked to clipboard)
-    harness.execute_action(Action::DeleteWord).await.unwrap();
-    harness.assert_buffer_contents("World");
+        type_normal_keys(&mut harness, keys).await;
+        if harness.is_insert() {
+            command_key(&mut harness, KeyCode::Esc).await;
+        }
 
-    // Move to end and paste with 'p'
-    harness.execute_action(Action::MoveToLineEnd).await.unwrap();
-    harness.execute_action(Action::Paste).await.unwrap();
-    // This depends on clipboard/register implementation
-    // For now, let's just verify it doesn't crash
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(cursor.0, cursor.1);
+        harness.assert_mode(Mode::Normal);
+    }
 }
 
 #[tokio::test]
-async fn test_yank_and_paste() {
-    let mut harness = EditorHarness::with_content("Line 1\nLine 2\nLine 3");
+async fn vim_parity_operators_accept_previous_word_end_g_motions() {
+    for (contents, keys, expected, cursor) in [
+        ("alpha beta gamma", "wdge", "alpheta gamma", (4, 0)),
+        ("alpha.beta gamma", "wdgE", "beta gamma", (0, 0)),
+    ] {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, contents.to_string()),
+            default_key_config(),
+        );
 
-    // Yank action exists
-    harness.execute_action(Action::Yank).await.unwrap();
+        type_normal_keys(&mut harness, keys).await;
 
-    // Move down and paste
-    harness.execute_action(Action::MoveDown).await.unwrap();
-    harness.execute_action(Action::Paste).await.unwrap();
-    // This depends on clipboard/register implementation
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(cursor.0, cursor.1);
+        harness.assert_mode(Mode::Normal);
+    }
 }
 
 #[tokio::test]
-async fn test_direct_open_line_below_groups_insert_undo() {
-    let mut harness = EditorHarness::with_content("Line 1\nLine 2");
-
-    harness
-        .execute_action(Action::InsertLineBelowCursor)
-        .await
-        .unwrap();
-    harness.type_text("New line").await.unwrap();
-    harness
-        .execute_action(Action::EnterMode(Mode::Normal))
-        .await
-        .unwrap();
+async fn vim_parity_charwise_paste_places_the_cursor_on_the_last_inserted_grapheme() {
+    for (contents, keys, expected, cursor) in [
+        ("alpha beta", "ywp", "aalpha lpha beta", (6, 0)),
+        (
+            "alpha beta\nnext line",
+            "wywp",
+            "alpha bbetaeta\nnext line",
+            (10, 0),
+        ),
+        ("alpha", "ylp", "aalpha", (1, 0)),
+        ("αβ γδ", "ywp", "ααβ β γδ", (3, 0)),
+        (
+            "a\u{301}bc next",
+            "ywp",
+            "a\u{301}a\u{301}bc bc next",
+            (4, 0),
+        ),
+        ("alpha beta", "ywP", "alpha alpha beta", (5, 0)),
+    ] {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, contents.to_string()),
+            default_key_config(),
+        );
 
-    harness.assert_buffer_contents("Line 1\nNew line\nLine 2");
-    harness.execute_action(Action::Undo).await.unwrap();
-    harness.assert_buffer_contents("Line 1\nLine 2");
+        type_normal_keys(&mut harness, keys).await;
+
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(cursor.0, cursor.1);
+        harness.assert_mode(Mode::Normal);
+    }
 }
 
 #[tokio::test]
-async fn test_editing_empty_buffer() {
-    let mut harness = EditorHarness::new();
+async fn vim_parity_paragraph_operators_preserve_exclusive_and_linewise_ranges() {
+    for (contents, keys, expected, cursor) in [
+        ("alpha\n\nbeta", "d}", "\nbeta", (0, 0)),
+        ("alpha\n\nbeta", "2ld}", "al\n\nbeta", (1, 0)),
+        ("alpha\n\nbeta\n\ngamma", "d2}", "\ngamma", (0, 0)),
+        ("alpha\n\nbeta\n\ngamma", "2d}", "\ngamma", (0, 0)),
+        ("alpha\n   \nbeta\n\ngamma", "d}", "\ngamma", (0, 0)),
+        ("alpha\nbeta", "d}", "", (0, 0)),
+        ("alpha\n\nbeta", "y}p", "alpha\nalpha\n\nbeta", (0, 1)),
+        ("alpha\n\nbeta", "v}x", "beta", (0, 0)),
+        ("one\n\ntwo\n\nthree", "Gd{", "one\n\ntwo\nthree", (0, 3)),
+        ("one\n\ntwo\n\nthree", "d}.", "\nthree", (0, 0)),
+    ] {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, contents.to_string()),
+            default_key_config(),
+        );
 
-    // Enter insert mode in empty buffer
-    harness
-        .execute_action(Action::EnterMode(Mode::Insert))
-        .await
-        .unwrap();
-    harness.type_text("First line").await.unwrap();
-    harness.assert_buffer_contents("First line\n");
+        type_normal_keys(&mut harness, keys).await;
 
-    // Exit and create new line below
-    harness
-        .execute_action(Action::EnterMode(Mode::Normal))
-        .await
-        .unwrap();
-    harness
-        .execute_action(Action::InsertLineBelowCursor)
-        .await
-        .unwrap();
-    harness.type_text("Second line").await.unwrap();
-    harness.assert_buffer_contents("First line\nSecond line\n");
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(cursor.0, cursor.1);
+        harness.assert_mode(Mode::Normal);
+    }
 }
 
 #[tokio::test]
-async fn test_delete_at_end_of_file() {
-    let mut harness = EditorHarness::with_content("Line 1\nLine 2");
+async fn vim_parity_sentence_operators_and_objects_preserve_sentence_whitespace() {
+    for (contents, keys, expected, cursor) in [
+        ("One.  Two! Three? End", "d)", "Two! Three? End", (0, 0)),
+        ("One.  Two! Three? End", "d2)", "Three? End", (0, 0)),
+        ("One.  Two! Three? End", "2d)", "Three? End", (0, 0)),
+        ("One.  Two! Three? End", ")d(", "Two! Three? End", (0, 0)),
+        ("One.  Two! Three? End", "dis", "  Two! Three? End", (0, 0)),
+        ("One.  Two! Three? End", "das", "Two! Three? End", (0, 0)),
+        ("One.  Two! Three? End", "d2is", "Two! Three? End", (0, 0)),
+        ("One.  Two! Three? End", "d3is", " Three? End", (0, 0)),
+        ("One.  Two! Three? End", "d2as", "Three? End", (0, 0)),
+        (
+            "One.  Two! Three? End",
+            "4ldis",
+            "One.Two! Three? End",
+            (4, 0),
+        ),
+        ("One.  Two! Three? End", "4ldas", "One. Three? End", (4, 0)),
+        ("One.  Two! Three? End", "visx", "  Two! Three? End", (0, 0)),
+        ("One.  Two! Three? End", "vasx", "Two! Three? End", (0, 0)),
+        ("One.  Two! Three? End", "v2isx", "Two! Three? End", (0, 0)),
+        ("One. Two! Last", "v)x", "wo! Last", (0, 0)),
+        ("One.)\"  Two!", "dis", "  Two!", (0, 0)),
+        ("One.\nTwo", "dis", "Two", (0, 0)),
+        ("One.\n\nTwo", "d2as", "Two", (0, 0)),
+        (
+            "Olá! 👨‍👩‍👧 e\u{301}lan. Fim",
+            "das",
+            "👨‍👩‍👧 e\u{301}lan. Fim",
+            (0, 0),
+        ),
+        ("One.  Two! Three? End", "das.", "Three? End", (0, 0)),
+    ] {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, contents.to_string()),
+            default_key_config(),
+        );
 
-    // Move to last line
-    harness.execute_action(Action::MoveToBottom).await.unwrap();
-    println!(
-        "After MoveToBottom: cursor at {:?}",
-        harness.cursor_position()
-    );
-    println!("Current line: {:?}", harness.current_line());
+        type_normal_keys(&mut harness, keys).await;
 
-    // Try to delete line at end of file
-    harness
-        .execute_action(Action::DeleteCurrentLine)
-        .await
-        .unwrap();
-    println!("After delete: {:?}", harness.buffer_contents());
-    harness.assert_buffer_contents("Line 1\n");
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(cursor.0, cursor.1);
+        harness.assert_mode(Mode::Normal);
+    }
 }
 
 #[tokio::test]
-async fn test_change_to_end_of_line() {
+async fn sentence_changes_remain_one_undoable_transaction() {
+    let contents = "One.  Two!";
     let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "Hello World Test".to_string()),
+        Buffer::new(None, contents.to_string()),
         default_key_config(),
     );
 
-    type_normal_keys(&mut harness, "wC").await;
-    harness.assert_mode(Mode::Insert);
-    harness.type_text("Universe").await.unwrap();
-    harness.assert_buffer_contents("Hello Universe");
+    type_normal_keys(&mut harness, "cisnew").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("new  Two!");
+
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents(contents);
+    harness.assert_mode(Mode::Normal);
 }
 
 #[tokio::test]
-async fn vim_parity_counted_word_operators_stop_at_blank_line_boundaries() {
+async fn vim_parity_paragraph_text_objects_preserve_inner_blank_line_semantics() {
     for (contents, keys, expected, cursor) in [
         (
-            "alpha beta\n\nnext line",
-            "wd2w",
-            "alpha \nnext line",
-            (5, 0),
+            "one paragraph\nstill one\n\nnext paragraph",
+            "dip",
+            "\nnext paragraph",
+            (0, 0),
         ),
         (
-            "alpha beta\n\nnext line",
-            "w2dw",
-            "alpha \nnext line",
-            (5, 0),
+            "one paragraph\nstill one\n\nnext paragraph",
+            "dap",
+            "next paragraph",
+            (0, 0),
         ),
-        ("alpha beta\n\nnext line", "wd3w", "alpha line", (6, 0)),
-        ("\n    next line", "dw", "    next line", (4, 0)),
-        ("\n    next line", "cwX", "X\n    next line", (0, 0)),
         (
-            "alpha beta\n\nnext line",
-            "wy2wp",
-            "alpha bbeta\neta\n\nnext line",
-            (7, 0),
+            "one\n\nsecond line\nsecond end\n\nthird",
+            "jjdip",
+            "one\n\n\nthird",
+            (0, 2),
+        ),
+        (
+            "one\n\nsecond line\nsecond end\n\nthird",
+            "jjdap",
+            "one\n\nthird",
+            (0, 2),
         ),
+        ("one\n\n\nnext", "jdip", "one\nnext", (0, 1)),
+        ("one\n\n\nnext", "jdap", "one", (0, 0)),
+        ("one\nsecond", "dip", "", (0, 0)),
     ] {
         let mut harness = EditorHarness::with_config(
             Buffer::new(None, contents.to_string()),
@@ -8927,9 +10108,6 @@ async fn vim_parity_counted_word_operators_stop_at_blank_line_boundaries() {
         );
 
         type_normal_keys(&mut harness, keys).await;
-        if harness.is_insert() {
-            command_key(&mut harness, KeyCode::Esc).await;
-        }
 
         harness.assert_buffer_contents(expected);
         harness.assert_cursor_at(cursor.0, cursor.1);
@@ -8938,46 +10116,84 @@ async fn vim_parity_counted_word_operators_stop_at_blank_line_boundaries() {
 }
 
 #[tokio::test]
-async fn vim_parity_big_word_operators_and_text_objects_match_neovim() {
-    for (contents, keys, expected, cursor) in [
-        ("foo.bar baz", "dW", "baz", (0, 0)),
-        ("foo.bar baz qux", "d2W", "qux", (0, 0)),
-        ("foo.bar baz", "cWX", "X baz", (0, 0)),
-        ("foo.bar baz", "yWp", "ffoo.bar oo.bar baz", (8, 0)),
-        ("foo.bar baz", "diW", " baz", (0, 0)),
-        ("foo.bar baz", "daW", "baz", (0, 0)),
-        ("α.β γδ", "dW", "γδ", (0, 0)),
-        ("foo.bar\n\n baz qux", "d2W", " baz qux", (1, 0)),
+async fn vim_parity_visual_big_word_and_paragraph_objects_match_operator_objects() {
+    for (contents, keys, expected) in [
+        ("foo.bar baz", "viWx", " baz"),
+        (
+            "one paragraph\nstill one\n\nnext paragraph",
+            "vipx",
+            "\nnext paragraph",
+        ),
     ] {
         let mut harness = EditorHarness::with_config(
             Buffer::new(None, contents.to_string()),
             default_key_config(),
         );
 
+        type_normal_keys(&mut harness, keys).await;
+
+        harness.assert_buffer_contents(expected);
+        harness.assert_cursor_at(0, 0);
+        harness.assert_mode(Mode::Normal);
+    }
+}
+
+#[tokio::test]
+async fn vim_parity_default_character_search_repeat_honors_counts() {
+    for (contents, keys, expected_x) in [("foo.bar.baz", "f.;", 7), ("foo.bar.baz.qux", "f.2;", 11)]
+    {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, contents.to_string()),
+            default_key_config(),
+        );
+
+        type_normal_keys(&mut harness, keys).await;
+
+        harness.assert_buffer_contents(contents);
+        harness.assert_cursor_at(expected_x, 0);
+        harness.assert_mode(Mode::Normal);
+    }
+}
+
+#[tokio::test]
+async fn vim_editing_shortcuts_honor_counts_and_register_kinds() {
+    let cases = [
+        ("one two\nthree four\nfive", "w2D", "one \nfive"),
+        ("one two\nthree four\nfive", "w2CX", "one X\nfive"),
+        ("  one two\nnext", "SX", "  X\nnext"),
+        ("one two", "w2sX", "one Xo"),
+        ("one two", "wX", "onetwo"),
+        ("one two", "wY$p", "one twotwo"),
+        ("abc", "xp", "bac"),
+        ("abc", "xuU", "bc"),
+    ];
+
+    for (contents, keys, expected) in cases {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, contents.to_string()),
+            line_end_delete_key_config(),
+        );
+
         type_normal_keys(&mut harness, keys).await;
         if harness.is_insert() {
             command_key(&mut harness, KeyCode::Esc).await;
         }
 
         harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(cursor.0, cursor.1);
-        harness.assert_mode(Mode::Normal);
     }
 }
 
 #[tokio::test]
-async fn vim_parity_character_operators_accept_horizontal_motions_and_counts() {
-    for (contents, keys, expected, cursor) in [
-        ("alpha", "dl", "lpha", (0, 0)),
-        ("alpha", "ldh", "lpha", (0, 0)),
-        ("alpha", "d2l", "pha", (0, 0)),
-        ("alpha", "d99l", "", (0, 0)),
-        ("alpha", "3ld99h", "ha", (0, 0)),
-        ("alpha", "clX", "Xlpha", (0, 0)),
-        ("alpha", "ylp", "aalpha", (1, 0)),
-        ("αβγ", "dl", "βγ", (0, 0)),
-        ("αβγ", "ldh", "βγ", (0, 0)),
-        ("αβγ", "d2l", "γ", (0, 0)),
+async fn vim_operator_motions_cover_line_edges_backward_words_and_vertical_lines() {
+    for (contents, keys, expected) in [
+        ("one two three", "wd$", "one "),
+        ("one two three", "wdb", "two three"),
+        ("one\ntwo\nthree", "dj", "three"),
+        ("one\ntwo\nthree", "jdk", "three"),
+        ("one two three", "cwX", "X two three"),
+        ("one two three four", "c2wX", "X three four"),
+        ("one two", "wcwX", "one X"),
+        ("  one\n    two\nthree", "cjX", "  X\nthree"),
     ] {
         let mut harness = EditorHarness::with_config(
             Buffer::new(None, contents.to_string()),
@@ -8990,107 +10206,86 @@ async fn vim_parity_character_operators_accept_horizontal_motions_and_counts() {
         }
 
         harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(cursor.0, cursor.1);
-        harness.assert_mode(Mode::Normal);
     }
 }
 
 #[tokio::test]
-async fn vim_parity_end_word_operators_include_the_final_buffer_character() {
-    for (contents, keys, expected, cursor) in [
-        ("alpha beta", "de", " beta", (0, 0)),
-        ("alpha", "$de", "alph", (3, 0)),
-        ("alpha", "$dE", "alph", (3, 0)),
-        ("x", "de", "", (0, 0)),
-        ("alpha", "$ceX", "alphX", (4, 0)),
-    ] {
+async fn vim_line_end_changes_repeat_at_the_new_cursor() {
+    for (keys, expected) in [("wCX", "one X\nthree X"), ("wD", "one \nthree ")] {
         let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
-            default_key_config(),
+            Buffer::new(None, "one two\nthree four".to_string()),
+            line_end_delete_key_config(),
         );
-
         type_normal_keys(&mut harness, keys).await;
         if harness.is_insert() {
             command_key(&mut harness, KeyCode::Esc).await;
         }
-
+        type_normal_keys(&mut harness, "jw.").await;
+        if harness.is_insert() {
+            command_key(&mut harness, KeyCode::Esc).await;
+        }
         harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(cursor.0, cursor.1);
-        harness.assert_mode(Mode::Normal);
     }
 }
 
 #[tokio::test]
-async fn vim_parity_linewise_operators_accept_file_boundary_motions() {
-    for (contents, keys, expected, cursor) in [
-        ("one\ntwo\nthree", "dG", "", (0, 0)),
-        ("one\ntwo\nthree", "jdG", "one", (0, 0)),
-        ("one\ntwo\nthree", "jdgg", "three", (0, 0)),
-        ("one\ntwo\nthree\nfour", "2dG", "three\nfour", (0, 0)),
-        ("one\ntwo\nthree\nfour", "d2G", "three\nfour", (0, 0)),
-        ("one\ntwo\nthree\nfour", "jjd2gg", "one\nfour", (0, 1)),
-        (
-            "one\ntwo\nthree",
-            "yGp",
-            "one\none\ntwo\nthree\ntwo\nthree",
-            (0, 1),
-        ),
-        ("one\ntwo\nthree", "cGX", "X", (0, 0)),
+async fn vim_backward_character_and_end_word_motions_work_in_normal_and_operator_modes() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "alpha.beta.gamma".to_string()),
+        default_key_config(),
+    );
+    type_normal_keys(&mut harness, "$F.").await;
+    harness.assert_cursor_at(10, 0);
+
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "alpha.beta.gamma".to_string()),
+        default_key_config(),
+    );
+    type_normal_keys(&mut harness, "$T.").await;
+    harness.assert_cursor_at(11, 0);
+
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "alpha.beta.gamma".to_string()),
+        default_key_config(),
+    );
+    type_normal_keys(&mut harness, "f.f.,").await;
+    harness.assert_cursor_at(5, 0);
+
+    for (contents, keys, expected) in [
+        ("alpha.beta.gamma", "$dF.", "alpha.betaa"),
+        ("alpha.beta.gamma", "$dT.", "alpha.beta.a"),
+        ("alpha beta gamma", "de", " beta gamma"),
+        ("alpha beta gamma", "wdb", "beta gamma"),
     ] {
         let mut harness = EditorHarness::with_config(
             Buffer::new(None, contents.to_string()),
             default_key_config(),
         );
-
         type_normal_keys(&mut harness, keys).await;
-        if harness.is_insert() {
-            command_key(&mut harness, KeyCode::Esc).await;
-        }
-
         harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(cursor.0, cursor.1);
-        harness.assert_mode(Mode::Normal);
     }
-}
 
-#[tokio::test]
-async fn vim_parity_operators_accept_previous_word_end_g_motions() {
-    for (contents, keys, expected, cursor) in [
-        ("alpha beta gamma", "wdge", "alpheta gamma", (4, 0)),
-        ("alpha.beta gamma", "wdgE", "beta gamma", (0, 0)),
-    ] {
+    for (keys, cursor) in [("e", 4), ("E", 9), ("$ge", 9), ("$gE", 9), ("$B", 11)] {
         let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
+            Buffer::new(None, "alpha.beta gamma".to_string()),
             default_key_config(),
         );
-
         type_normal_keys(&mut harness, keys).await;
-
-        harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(cursor.0, cursor.1);
-        harness.assert_mode(Mode::Normal);
+        harness.assert_cursor_at(cursor, 0);
     }
 }
 
 #[tokio::test]
-async fn vim_parity_charwise_paste_places_the_cursor_on_the_last_inserted_grapheme() {
-    for (contents, keys, expected, cursor) in [
-        ("alpha beta", "ywp", "aalpha lpha beta", (6, 0)),
-        (
-            "alpha beta\nnext line",
-            "wywp",
-            "alpha bbetaeta\nnext line",
-            (10, 0),
-        ),
-        ("alpha", "ylp", "aalpha", (1, 0)),
-        ("αβ γδ", "ywp", "ααβ β γδ", (3, 0)),
-        (
-            "a\u{301}bc next",
-            "ywp",
-            "a\u{301}a\u{301}bc bc next",
-            (4, 0),
-        ),
-        ("alpha beta", "ywP", "alpha alpha beta", (5, 0)),
+async fn vim_case_changes_and_visual_replace_are_transactional() {
+    for (contents, keys, expected) in [
+        ("alpha beta", "~", "Alpha beta"),
+        ("alpha beta", "gUiw", "ALPHA beta"),
+        ("ALPHA beta", "guiw", "alpha beta"),
+        ("aLpHa beta", "g~iw", "AlPhA beta"),
+        ("alpha beta", "viwU", "ALPHA beta"),
+        ("ALPHA beta", "viwu", "alpha beta"),
+        ("aLpHa beta", "viw~", "AlPhA beta"),
+        ("alpha beta", "viwrX", "XXXXX beta"),
     ] {
         let mut harness = EditorHarness::with_config(
             Buffer::new(None, contents.to_string()),
@@ -9100,607 +10295,1114 @@ async fn vim_parity_charwise_paste_places_the_cursor_on_the_last_inserted_graphe
         type_normal_keys(&mut harness, keys).await;
 
         harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(cursor.0, cursor.1);
         harness.assert_mode(Mode::Normal);
+        type_normal_keys(&mut harness, "u").await;
+        harness.assert_buffer_contents(contents);
     }
 }
 
 #[tokio::test]
-async fn vim_parity_paragraph_operators_preserve_exclusive_and_linewise_ranges() {
-    for (contents, keys, expected, cursor) in [
-        ("alpha\n\nbeta", "d}", "\nbeta", (0, 0)),
-        ("alpha\n\nbeta", "2ld}", "al\n\nbeta", (1, 0)),
-        ("alpha\n\nbeta\n\ngamma", "d2}", "\ngamma", (0, 0)),
-        ("alpha\n\nbeta\n\ngamma", "2d}", "\ngamma", (0, 0)),
-        ("alpha\n   \nbeta\n\ngamma", "d}", "\ngamma", (0, 0)),
-        ("alpha\nbeta", "d}", "", (0, 0)),
-        ("alpha\n\nbeta", "y}p", "alpha\nalpha\n\nbeta", (0, 1)),
-        ("alpha\n\nbeta", "v}x", "beta", (0, 0)),
-        ("one\n\ntwo\n\nthree", "Gd{", "one\n\ntwo\nthree", (0, 3)),
-        ("one\n\ntwo\n\nthree", "d}.", "\nthree", (0, 0)),
-    ] {
+async fn vim_word_and_character_repeat_actions_use_the_default_keymap() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "alpha.beta gamma".to_string()),
+        default_key_config(),
+    );
+    type_normal_keys(&mut harness, "W").await;
+    harness.assert_cursor_at(11, 0);
+
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "alpha.beta.gamma".to_string()),
+        default_key_config(),
+    );
+    type_normal_keys(&mut harness, "f.;").await;
+    harness.assert_cursor_at(10, 0);
+}
+
+#[tokio::test]
+async fn visual_replace_accepts_a_shifted_terminal_key_event() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "alpha beta".to_string()),
+        default_key_config(),
+    );
+    type_normal_keys(&mut harness, "viwr").await;
+
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('X'),
+            KeyModifiers::SHIFT,
+        )))
+        .await
+        .unwrap();
+
+    harness.assert_buffer_contents("XXXXX beta");
+    harness.assert_mode(Mode::Normal);
+}
+
+#[tokio::test]
+async fn visual_multiline_and_block_replace_and_case_changes_preserve_line_breaks() {
+    let cases = [
+        ("vjrX", "XXXXX\nXeta\ngamma"),
+        ("VjrX", "XXXXX\nXXXX\ngamma"),
+        ("VjU", "ALPHA\nBETA\ngamma"),
+    ];
+    for (keys, expected) in cases {
         let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
+            Buffer::new(None, "alpha\nbeta\ngamma".to_string()),
             default_key_config(),
         );
-
         type_normal_keys(&mut harness, keys).await;
+        harness.assert_buffer_contents(expected);
+    }
 
+    for (suffix, expected) in [
+        ("jlrX", "XXpha\nXXta\ngamma"),
+        ("jlU", "ALpha\nBEta\ngamma"),
+    ] {
+        let mut harness = EditorHarness::with_config(
+            Buffer::new(None, "alpha\nbeta\ngamma".to_string()),
+            default_key_config(),
+        );
+        harness
+            .execute_event(Event::Key(KeyEvent::new(
+                KeyCode::Char('v'),
+                KeyModifiers::CONTROL,
+            )))
+            .await
+            .unwrap();
+        type_normal_keys(&mut harness, suffix).await;
         harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(cursor.0, cursor.1);
-        harness.assert_mode(Mode::Normal);
     }
 }
 
 #[tokio::test]
-async fn vim_parity_sentence_operators_and_objects_preserve_sentence_whitespace() {
-    for (contents, keys, expected, cursor) in [
-        ("One.  Two! Three? End", "d)", "Two! Three? End", (0, 0)),
-        ("One.  Two! Three? End", "d2)", "Three? End", (0, 0)),
-        ("One.  Two! Three? End", "2d)", "Three? End", (0, 0)),
-        ("One.  Two! Three? End", ")d(", "Two! Three? End", (0, 0)),
-        ("One.  Two! Three? End", "dis", "  Two! Three? End", (0, 0)),
-        ("One.  Two! Three? End", "das", "Two! Three? End", (0, 0)),
-        ("One.  Two! Three? End", "d2is", "Two! Three? End", (0, 0)),
-        ("One.  Two! Three? End", "d3is", " Three? End", (0, 0)),
-        ("One.  Two! Three? End", "d2as", "Three? End", (0, 0)),
-        (
-            "One.  Two! Three? End",
-            "4ldis",
-            "One.Two! Three? End",
-            (4, 0),
-        ),
-        ("One.  Two! Three? End", "4ldas", "One. Three? End", (4, 0)),
-        ("One.  Two! Three? End", "visx", "  Two! Three? End", (0, 0)),
-        ("One.  Two! Three? End", "vasx", "Two! Three? End", (0, 0)),
-        ("One.  Two! Three? End", "v2isx", "Two! Three? End", (0, 0)),
-        ("One. Two! Last", "v)x", "wo! Last", (0, 0)),
-        ("One.)\"  Two!", "dis", "  Two!", (0, 0)),
-        ("One.\nTwo", "dis", "Two", (0, 0)),
-        ("One.\n\nTwo", "d2as", "Two", (0, 0)),
-        (
-            "Olá! 👨‍👩‍👧 e\u{301}lan. Fim",
-            "das",
-            "👨‍👩‍👧 e\u{301}lan. Fim",
-            (0, 0),
+async fn vim_half_page_keys_move_the_cursor_by_half_a_viewport() {
+    let contents = (0..40)
+        .map(|line| format!("line-{line:02}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut harness = EditorHarness::with_config_and_size(
+        Buffer::new(None, contents),
+        default_key_config(),
+        80,
+        12,
+    );
+
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('d'),
+            KeyModifiers::CONTROL,
+        )))
+        .await
+        .unwrap();
+    assert_eq!(harness.buffer_line(), 5);
+
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('u'),
+            KeyModifiers::CONTROL,
+        )))
+        .await
+        .unwrap();
+    assert_eq!(harness.buffer_line(), 0);
+}
+
+#[tokio::test]
+async fn structural_text_objects_support_inner_outer_and_linewise_operators() {
+    let contents = "fn first() {\n    alpha();\n    beta();\n}\nfn second() {}\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(Some("sample.rs".to_string()), contents.to_string()),
+        default_key_config(),
+    );
+
+    type_normal_keys(&mut harness, "dif").await;
+    harness.assert_buffer_contents("fn first() {\n    \n}\nfn second() {}\n");
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents(contents);
+
+    type_normal_keys(&mut harness, "daf").await;
+    harness.assert_buffer_contents("fn second() {}\n");
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents(contents);
+
+    type_normal_keys(&mut harness, "yaf]fP").await;
+    harness.assert_buffer_contents(
+        "fn first() {\n    alpha();\n    beta();\n}\nfn first() {\n    alpha();\n    beta();\n}\nfn second() {}\n",
+    );
+}
+
+#[tokio::test]
+async fn structural_comment_objects_synthesize_rust_inner_capture() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(
+            Some("sample.rs".to_string()),
+            "let café = 1; // comentário\n".to_string(),
         ),
-        ("One.  Two! Three? End", "das.", "Three? End", (0, 0)),
-    ] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
-            default_key_config(),
-        );
+        default_key_config(),
+    );
+    harness
+        .execute_action(Action::SetCursor(18, 0))
+        .await
+        .unwrap();
 
-        type_normal_keys(&mut harness, keys).await;
+    type_normal_keys(&mut harness, "dik").await;
 
-        harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(cursor.0, cursor.1);
-        harness.assert_mode(Mode::Normal);
-    }
+    harness.assert_buffer_contents("let café = 1; // \n");
 }
 
 #[tokio::test]
-async fn sentence_changes_remain_one_undoable_transaction() {
-    let contents = "One.  Two!";
+async fn structural_function_objects_support_change_and_case_operators() {
+    let contents = "fn first() {\n    alpha();\n}\n";
     let mut harness = EditorHarness::with_config(
-        Buffer::new(None, contents.to_string()),
+        Buffer::new(Some("sample.rs".to_string()), contents.to_string()),
         default_key_config(),
     );
 
-    type_normal_keys(&mut harness, "cisnew").await;
-    command_key(&mut harness, KeyCode::Esc).await;
-    harness.assert_buffer_contents("new  Two!");
+    type_normal_keys(&mut harness, "gUif").await;
+    harness.assert_buffer_contents("fn first() {\n    ALPHA();\n}\n");
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents(contents);
 
+    type_normal_keys(&mut harness, "cifupdated();").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("fn first() {\n    updated();\n}\n");
     type_normal_keys(&mut harness, "u").await;
     harness.assert_buffer_contents(contents);
-    harness.assert_mode(Mode::Normal);
 }
 
 #[tokio::test]
-async fn vim_parity_paragraph_text_objects_preserve_inner_blank_line_semantics() {
-    for (contents, keys, expected, cursor) in [
-        (
-            "one paragraph\nstill one\n\nnext paragraph",
-            "dip",
-            "\nnext paragraph",
-            (0, 0),
-        ),
-        (
-            "one paragraph\nstill one\n\nnext paragraph",
-            "dap",
-            "next paragraph",
-            (0, 0),
-        ),
-        (
-            "one\n\nsecond line\nsecond end\n\nthird",
-            "jjdip",
-            "one\n\n\nthird",
-            (0, 2),
-        ),
-        (
-            "one\n\nsecond line\nsecond end\n\nthird",
-            "jjdap",
-            "one\n\nthird",
-            (0, 2),
-        ),
-        ("one\n\n\nnext", "jdip", "one\nnext", (0, 1)),
-        ("one\n\n\nnext", "jdap", "one", (0, 0)),
-        ("one\nsecond", "dip", "", (0, 0)),
-    ] {
+async fn structural_operator_motions_accept_prefix_and_motion_counts() {
+    let contents = "// header\nfn first() {}\nfn second() {}\nfn third() {}\n";
+    for keys in ["d2]f", "2d]f"] {
         let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
+            Buffer::new(Some("sample.rs".to_string()), contents.to_string()),
             default_key_config(),
         );
 
         type_normal_keys(&mut harness, keys).await;
 
-        harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(cursor.0, cursor.1);
-        harness.assert_mode(Mode::Normal);
+        harness.assert_buffer_contents("fn second() {}\nfn third() {}\n");
+        type_normal_keys(&mut harness, "u").await;
+        harness.assert_buffer_contents(contents);
     }
 }
 
 #[tokio::test]
-async fn vim_parity_visual_big_word_and_paragraph_objects_match_operator_objects() {
-    for (contents, keys, expected) in [
-        ("foo.bar baz", "viWx", " baz"),
-        (
-            "one paragraph\nstill one\n\nnext paragraph",
-            "vipx",
-            "\nnext paragraph",
-        ),
-    ] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
-            default_key_config(),
-        );
+async fn structural_visual_function_selection_is_linewise() {
+    let contents = "fn first() {\n    alpha();\n}\nfn second() {}\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(Some("sample.rs".to_string()), contents.to_string()),
+        default_key_config(),
+    );
 
-        type_normal_keys(&mut harness, keys).await;
+    type_normal_keys(&mut harness, "vaf").await;
 
-        harness.assert_buffer_contents(expected);
-        harness.assert_cursor_at(0, 0);
-        harness.assert_mode(Mode::Normal);
-    }
+    harness.assert_mode(Mode::VisualLine);
+    assert_eq!(harness.selection(), Some((0, 0, 0, 2)));
+    type_normal_keys(&mut harness, "y").await;
+    harness.assert_mode(Mode::Normal);
+    type_normal_keys(&mut harness, "]fP").await;
+    harness.assert_buffer_contents(
+        "fn first() {\n    alpha();\n}\nfn first() {\n    alpha();\n}\nfn second() {}\n",
+    );
 }
 
 #[tokio::test]
-async fn vim_parity_default_character_search_repeat_honors_counts() {
-    for (contents, keys, expected_x) in [("foo.bar.baz", "f.;", 7), ("foo.bar.baz.qux", "f.2;", 11)]
-    {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
-            default_key_config(),
-        );
+async fn structural_argument_conditional_and_assignment_objects_with_argument_motions() {
+    let contents = "fn call(alpha: i32, beta: i32) {\n    let total = alpha + beta;\n    if total > 0 {\n        go(total);\n    }\n}\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(Some("sample.rs".to_string()), contents.to_string()),
+        default_key_config(),
+    );
+    harness
+        .execute_action(Action::SetCursor(8, 0))
+        .await
+        .unwrap();
 
-        type_normal_keys(&mut harness, keys).await;
+    type_normal_keys(&mut harness, "]a").await;
+    assert_eq!(harness.cursor_position(), (20, 0));
+    type_normal_keys(&mut harness, "[a").await;
+    assert_eq!(harness.cursor_position(), (8, 0));
 
-        harness.assert_buffer_contents(contents);
-        harness.assert_cursor_at(expected_x, 0);
-        harness.assert_mode(Mode::Normal);
-    }
+    type_normal_keys(&mut harness, "]adia").await;
+    harness.assert_buffer_contents(
+        "fn call(alpha: i32, ) {\n    let total = alpha + beta;\n    if total > 0 {\n        go(total);\n    }\n}\n",
+    );
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents(contents);
+
+    type_normal_keys(&mut harness, "jwwwciR0").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents(
+        "fn call(alpha: i32, beta: i32) {\n    let total = 0;\n    if total > 0 {\n        go(total);\n    }\n}\n",
+    );
+    type_normal_keys(&mut harness, "u").await;
+
+    type_normal_keys(&mut harness, "jjdao").await;
+    harness.assert_buffer_contents(
+        "fn call(alpha: i32, beta: i32) {\n    let total = alpha + beta;\n    \n}\n",
+    );
 }
 
 #[tokio::test]
-async fn vim_editing_shortcuts_honor_counts_and_register_kinds() {
-    let cases = [
-        ("one two\nthree four\nfive", "w2D", "one \nfive"),
-        ("one two\nthree four\nfive", "w2CX", "one X\nfive"),
-        ("  one two\nnext", "SX", "  X\nnext"),
-        ("one two", "w2sX", "one Xo"),
-        ("one two", "wX", "onetwo"),
-        ("one two", "wY$p", "one twotwo"),
-        ("abc", "xp", "bac"),
-        ("abc", "xuU", "bc"),
-    ];
+async fn indentation_and_entire_objects_work_without_a_grammar() {
+    let contents = "root\n    one\n\n    two\nnext\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(Some("notes.txt".to_string()), contents.to_string()),
+        default_key_config(),
+    );
+    harness
+        .execute_action(Action::SetCursor(0, 1))
+        .await
+        .unwrap();
 
-    for (contents, keys, expected) in cases {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
-            line_end_delete_key_config(),
-        );
+    type_normal_keys(&mut harness, "dii").await;
+    harness.assert_buffer_contents("root\nnext\n");
+    type_normal_keys(&mut harness, "u").await;
+    type_normal_keys(&mut harness, "dai").await;
+    harness.assert_buffer_contents("next\n");
+    type_normal_keys(&mut harness, "u").await;
+    type_normal_keys(&mut harness, "daI").await;
+    harness.assert_buffer_contents("");
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents(contents);
 
-        type_normal_keys(&mut harness, keys).await;
-        if harness.is_insert() {
-            command_key(&mut harness, KeyCode::Esc).await;
-        }
+    harness
+        .execute_action(Action::SetCursor(0, 3))
+        .await
+        .unwrap();
+    type_normal_keys(&mut harness, "vii").await;
+    harness.assert_mode(Mode::VisualLine);
+    assert_eq!(harness.selection(), Some((0, 1, 0, 3)));
+    command_key(&mut harness, KeyCode::Esc).await;
 
-        harness.assert_buffer_contents(expected);
-    }
+    type_normal_keys(&mut harness, "yaeGp").await;
+    harness.assert_buffer_contents(&format!("{contents}{contents}"));
 }
 
 #[tokio::test]
-async fn vim_operator_motions_cover_line_edges_backward_words_and_vertical_lines() {
-    for (contents, keys, expected) in [
-        ("one two three", "wd$", "one "),
-        ("one two three", "wdb", "two three"),
-        ("one\ntwo\nthree", "dj", "three"),
-        ("one\ntwo\nthree", "jdk", "three"),
-        ("one two three", "cwX", "X two three"),
-        ("one two three four", "c2wX", "X three four"),
-        ("one two", "wcwX", "one X"),
-        ("  one\n    two\nthree", "cjX", "  X\nthree"),
-    ] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
-            default_key_config(),
-        );
-
-        type_normal_keys(&mut harness, keys).await;
-        if harness.is_insert() {
-            command_key(&mut harness, KeyCode::Esc).await;
-        }
+async fn parameter_swaps_preserve_separators_undo_and_dot_repeat() {
+    let original = "fn call(alpha: i32, beta: i32, gamma: i32) {}\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(Some("sample.rs".to_string()), original.to_string()),
+        default_key_config(),
+    );
+    harness
+        .execute_action(Action::SetCursor(8, 0))
+        .await
+        .unwrap();
 
-        harness.assert_buffer_contents(expected);
-    }
+    type_normal_keys(&mut harness, " ]a").await;
+    harness.assert_buffer_contents("fn call(beta: i32, alpha: i32, gamma: i32) {}\n");
+    type_normal_keys(&mut harness, ".").await;
+    harness.assert_buffer_contents("fn call(beta: i32, gamma: i32, alpha: i32) {}\n");
+    type_normal_keys(&mut harness, "uu").await;
+    harness.assert_buffer_contents(original);
 }
 
 #[tokio::test]
-async fn vim_line_end_changes_repeat_at_the_new_cursor() {
-    for (keys, expected) in [("wCX", "one X\nthree X"), ("wD", "one \nthree ")] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, "one two\nthree four".to_string()),
-            line_end_delete_key_config(),
-        );
-        type_normal_keys(&mut harness, keys).await;
-        if harness.is_insert() {
-            command_key(&mut harness, KeyCode::Esc).await;
-        }
-        type_normal_keys(&mut harness, "jw.").await;
-        if harness.is_insert() {
-            command_key(&mut harness, KeyCode::Esc).await;
-        }
-        harness.assert_buffer_contents(expected);
-    }
+async fn parameter_swaps_record_and_replay_inside_macros() {
+    let original = "fn call(alpha: i32, beta: i32, gamma: i32) {}\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(Some("sample.rs".to_string()), original.to_string()),
+        default_key_config(),
+    );
+    harness
+        .execute_action(Action::SetCursor(8, 0))
+        .await
+        .unwrap();
+
+    type_normal_keys(&mut harness, "qa ]aq@a").await;
+
+    harness.assert_buffer_contents("fn call(beta: i32, gamma: i32, alpha: i32) {}\n");
+    type_normal_keys(&mut harness, "uu").await;
+    harness.assert_buffer_contents(original);
 }
 
 #[tokio::test]
-async fn vim_backward_character_and_end_word_motions_work_in_normal_and_operator_modes() {
+async fn parameter_swaps_do_not_cross_argument_containers() {
+    let original = "fn first(alpha: i32) {}\nfn second(beta: i32) {}\n";
     let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "alpha.beta.gamma".to_string()),
+        Buffer::new(Some("sample.rs".to_string()), original.to_string()),
         default_key_config(),
     );
-    type_normal_keys(&mut harness, "$F.").await;
-    harness.assert_cursor_at(10, 0);
+    harness
+        .execute_action(Action::SetCursor(9, 0))
+        .await
+        .unwrap();
+
+    type_normal_keys(&mut harness, " ]a").await;
+
+    harness.assert_buffer_contents(original);
+    assert_eq!(harness.last_error(), Some("adjacent text object not found"));
+}
 
+#[tokio::test]
+async fn function_swaps_preserve_scope_multiline_ranges_and_jump_history() {
+    let original = "fn first() {\n    one();\n}\nfn second() { two(); }\n";
     let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "alpha.beta.gamma".to_string()),
+        Buffer::new(Some("sample.rs".to_string()), original.to_string()),
         default_key_config(),
     );
-    type_normal_keys(&mut harness, "$T.").await;
-    harness.assert_cursor_at(11, 0);
 
+    type_normal_keys(&mut harness, " ]m").await;
+    harness.assert_buffer_contents("fn second() { two(); }\nfn first() {\n    one();\n}\n");
+    harness.assert_cursor_at(0, 1);
+    harness.execute_action(Action::JumpBack).await.unwrap();
+    harness.assert_cursor_at(0, 0);
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents(original);
+}
+
+async fn press_modified_key(harness: &mut EditorHarness, key: KeyCode, modifiers: KeyModifiers) {
+    harness
+        .execute_event(Event::Key(KeyEvent::new(key, modifiers)))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn ctrl_n_and_ctrl_p_complete_keywords_as_part_of_the_insert() {
+    let original = "alpha alpine\n";
     let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "alpha.beta.gamma".to_string()),
+        Buffer::new(None, original.to_string()),
         default_key_config(),
     );
-    type_normal_keys(&mut harness, "f.f.,").await;
-    harness.assert_cursor_at(5, 0);
+    type_normal_keys(&mut harness, "A al").await;
+    press_modified_key(&mut harness, KeyCode::Char('n'), KeyModifiers::CONTROL).await;
+    execute_unmodified_key(&mut harness, KeyCode::Tab).await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("alpha alpine alpha\n");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents(original);
 
-    for (contents, keys, expected) in [
-        ("alpha.beta.gamma", "$dF.", "alpha.betaa"),
-        ("alpha.beta.gamma", "$dT.", "alpha.beta.a"),
-        ("alpha beta gamma", "de", " beta gamma"),
-        ("alpha beta gamma", "wdb", "beta gamma"),
-    ] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
-            default_key_config(),
-        );
-        type_normal_keys(&mut harness, keys).await;
-        harness.assert_buffer_contents(expected);
-    }
+    type_normal_keys(&mut harness, "A alp").await;
+    press_modified_key(&mut harness, KeyCode::Char('p'), KeyModifiers::CONTROL).await;
+    type_normal_keys(&mut harness, "i").await;
+    execute_unmodified_key(&mut harness, KeyCode::Tab).await;
+    type_normal_keys(&mut harness, "!").await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("alpha alpine alpine!\n");
 
-    for (keys, cursor) in [("e", 4), ("E", 9), ("$ge", 9), ("$gE", 9), ("$B", 11)] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, "alpha.beta gamma".to_string()),
-            default_key_config(),
-        );
-        type_normal_keys(&mut harness, keys).await;
-        harness.assert_cursor_at(cursor, 0);
-    }
+    // A nearer match does not change what `.` inserts.
+    harness
+        .execute_action(Action::Command("s/$/ alpinist/".to_string()))
+        .await
+        .unwrap();
+    type_normal_keys(&mut harness, ".").await;
+    harness.assert_buffer_contents("alpha alpine alpine! alpinist alpine!\n");
 }
 
 #[tokio::test]
-async fn vim_case_changes_and_visual_replace_are_transactional() {
-    for (contents, keys, expected) in [
-        ("alpha beta", "~", "Alpha beta"),
-        ("alpha beta", "gUiw", "ALPHA beta"),
-        ("ALPHA beta", "guiw", "alpha beta"),
-        ("aLpHa beta", "g~iw", "AlPhA beta"),
-        ("alpha beta", "viwU", "ALPHA beta"),
-        ("ALPHA beta", "viwu", "alpha beta"),
-        ("aLpHa beta", "viw~", "AlPhA beta"),
-        ("alpha beta", "viwrX", "XXXXX beta"),
-    ] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, contents.to_string()),
-            default_key_config(),
-        );
+async fn ctrl_x_completes_lines_and_file_names_without_a_language_server() {
+    let root = env::temp_dir().join(format!("red-insert-completion-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(root.join("nested")).unwrap();
+    fs::write(root.join("notes.md"), "").unwrap();
+    fs::write(root.join("nested").join("inner.txt"), "").unwrap();
+    let file = root.join("main.txt").to_string_lossy().to_string();
+    let original = "    let value = compute();\nlet other = 1;\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(Some(file), original.to_string()),
+        default_key_config(),
+    );
 
-        type_normal_keys(&mut harness, keys).await;
+    type_normal_keys(&mut harness, "Go  let v").await;
+    press_modified_key(&mut harness, KeyCode::Char('x'), KeyModifiers::CONTROL).await;
+    press_modified_key(&mut harness, KeyCode::Char('l'), KeyModifiers::CONTROL).await;
+    execute_unmodified_key(&mut harness, KeyCode::Enter).await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents(
+        "    let value = compute();\nlet other = 1;\n  let value = compute();\n",
+    );
 
-        harness.assert_buffer_contents(expected);
-        harness.assert_mode(Mode::Normal);
-        type_normal_keys(&mut harness, "u").await;
-        harness.assert_buffer_contents(contents);
-    }
+    type_normal_keys(&mut harness, "o./ne").await;
+    press_modified_key(&mut harness, KeyCode::Char('x'), KeyModifiers::CONTROL).await;
+    press_modified_key(&mut harness, KeyCode::Char('f'), KeyModifiers::CONTROL).await;
+    execute_unmodified_key(&mut harness, KeyCode::Tab).await;
+    press_modified_key(&mut harness, KeyCode::Char('x'), KeyModifiers::CONTROL).await;
+    press_modified_key(&mut harness, KeyCode::Char('f'), KeyModifiers::CONTROL).await;
+    execute_unmodified_key(&mut harness, KeyCode::Tab).await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    let contents = harness.buffer_contents();
+    assert_eq!(
+        contents.lines().last().unwrap().trim(),
+        "./nested/inner.txt"
+    );
+    harness.execute_action(Action::Undo).await.unwrap();
+    assert!(harness.buffer_contents().ends_with("compute();\n"));
+
+    type_normal_keys(&mut harness, "A zz").await;
+    press_modified_key(&mut harness, KeyCode::Char('x'), KeyModifiers::CONTROL).await;
+    press_modified_key(&mut harness, KeyCode::Char('f'), KeyModifiers::CONTROL).await;
+    assert_eq!(harness.last_error(), Some("no matching files"));
+    press_modified_key(&mut harness, KeyCode::Char('x'), KeyModifiers::CONTROL).await;
+    press_modified_key(&mut harness, KeyCode::Char('o'), KeyModifiers::CONTROL).await;
+    assert_eq!(
+        harness.last_error(),
+        Some("no language server provides completion for this buffer")
+    );
+    fs::remove_dir_all(root).unwrap();
 }
 
 #[tokio::test]
-async fn vim_word_and_character_repeat_actions_use_the_default_keymap() {
+async fn ctrl_r_inserts_registers_as_typed_or_literally_within_the_insert() {
     let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "alpha.beta gamma".to_string()),
+        Buffer::new(None, "word other\n".to_string()),
         default_key_config(),
     );
-    type_normal_keys(&mut harness, "W").await;
-    harness.assert_cursor_at(11, 0);
+    type_normal_keys(&mut harness, "\"ayiwA ").await;
+    press_modified_key(&mut harness, KeyCode::Char('r'), KeyModifiers::CONTROL).await;
+    type_normal_keys(&mut harness, "a!").await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("word other word!\n");
 
+    // `.` repeats the inserted text, not the register that has changed since.
+    type_normal_keys(&mut harness, "0w\"ayiw.").await;
+    harness.assert_buffer_contents("word other word! word!\n");
+
+    let original = "    x\ny\n";
     let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "alpha.beta.gamma".to_string()),
+        Buffer::new(None, original.to_string()),
         default_key_config(),
     );
-    type_normal_keys(&mut harness, "f.;").await;
-    harness.assert_cursor_at(10, 0);
+    type_normal_keys(&mut harness, "$vj0\"ayA").await;
+    press_modified_key(&mut harness, KeyCode::Char('r'), KeyModifiers::CONTROL).await;
+    type_normal_keys(&mut harness, "a").await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("    xx\n    y\ny\n");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents(original);
+
+    type_normal_keys(&mut harness, "ggA").await;
+    press_modified_key(&mut harness, KeyCode::Char('r'), KeyModifiers::CONTROL).await;
+    press_modified_key(&mut harness, KeyCode::Char('r'), KeyModifiers::CONTROL).await;
+    type_normal_keys(&mut harness, "a").await;
+    press_modified_key(&mut harness, KeyCode::Char('r'), KeyModifiers::CONTROL).await;
+    type_normal_keys(&mut harness, "z").await;
+    assert_eq!(harness.last_error(), Some("register z is empty"));
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("    xx\ny\ny\n");
 }
 
 #[tokio::test]
-async fn visual_replace_accepts_a_shifted_terminal_key_event() {
+async fn ctrl_o_runs_one_normal_command_and_resumes_insert() {
     let mut harness = EditorHarness::with_config(
-        Buffer::new(None, "alpha beta".to_string()),
+        Buffer::new(None, "one\ntwo\n".to_string()),
         default_key_config(),
     );
-    type_normal_keys(&mut harness, "viwr").await;
-
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('X'),
-            KeyModifiers::SHIFT,
-        )))
-        .await
-        .unwrap();
+    type_normal_keys(&mut harness, "A x").await;
+    press_modified_key(&mut harness, KeyCode::Char('o'), KeyModifiers::CONTROL).await;
+    assert_eq!(harness.mode(), Mode::Normal);
+    type_normal_keys(&mut harness, "0").await;
+    assert_eq!(harness.mode(), Mode::Insert);
+    type_normal_keys(&mut harness, ">").await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents(">one x\ntwo\n");
+    type_normal_keys(&mut harness, "j.").await;
+    harness.assert_buffer_contents(">one x\n>two x\n");
 
-    harness.assert_buffer_contents("XXXXX beta");
-    harness.assert_mode(Mode::Normal);
+    // A command that leaves the cursor alone resumes past the end of the line.
+    type_normal_keys(&mut harness, "A!").await;
+    press_modified_key(&mut harness, KeyCode::Char('o'), KeyModifiers::CONTROL).await;
+    type_normal_keys(&mut harness, "zz?").await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents(">one x\n>two x!?\n");
+    type_normal_keys(&mut harness, "k.").await;
+    harness.assert_buffer_contents(">one x!?\n>two x!?\n");
 }
 
 #[tokio::test]
-async fn visual_multiline_and_block_replace_and_case_changes_preserve_line_breaks() {
-    let cases = [
-        ("vjrX", "XXXXX\nXeta\ngamma"),
-        ("VjrX", "XXXXX\nXXXX\ngamma"),
-        ("VjU", "ALPHA\nBETA\ngamma"),
-    ];
-    for (keys, expected) in cases {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, "alpha\nbeta\ngamma".to_string()),
-            default_key_config(),
-        );
-        type_normal_keys(&mut harness, keys).await;
-        harness.assert_buffer_contents(expected);
-    }
-
-    for (suffix, expected) in [
-        ("jlrX", "XXpha\nXXta\ngamma"),
-        ("jlU", "ALpha\nBEta\ngamma"),
-    ] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(None, "alpha\nbeta\ngamma".to_string()),
-            default_key_config(),
-        );
-        harness
-            .execute_event(Event::Key(KeyEvent::new(
-                KeyCode::Char('v'),
-                KeyModifiers::CONTROL,
-            )))
-            .await
-            .unwrap();
-        type_normal_keys(&mut harness, suffix).await;
-        harness.assert_buffer_contents(expected);
-    }
+async fn ctrl_w_and_ctrl_u_delete_back_to_the_insert_start() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "  foo.bar baz\nnext word\n".to_string()),
+        default_key_config(),
+    );
+    type_normal_keys(&mut harness, "A qux").await;
+    press_modified_key(&mut harness, KeyCode::Char('w'), KeyModifiers::CONTROL).await;
+    harness.assert_buffer_contents("  foo.bar baz \nnext word\n");
+    press_modified_key(&mut harness, KeyCode::Char('w'), KeyModifiers::CONTROL).await;
+    harness.assert_buffer_contents("  foo.bar baz\nnext word\n");
+    press_modified_key(&mut harness, KeyCode::Char('w'), KeyModifiers::CONTROL).await;
+    press_modified_key(&mut harness, KeyCode::Char('w'), KeyModifiers::CONTROL).await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("  foo.\nnext word\n");
+    type_normal_keys(&mut harness, "j.").await;
+    harness.assert_buffer_contents("  foo.\n\n");
+
+    type_normal_keys(&mut harness, "kA end").await;
+    press_modified_key(&mut harness, KeyCode::Char('u'), KeyModifiers::CONTROL).await;
+    harness.assert_buffer_contents("  foo.\n\n");
+    press_modified_key(&mut harness, KeyCode::Char('u'), KeyModifiers::CONTROL).await;
+    harness.assert_buffer_contents("  \n\n");
+    press_modified_key(&mut harness, KeyCode::Char('u'), KeyModifiers::CONTROL).await;
+    type_normal_keys(&mut harness, "x").await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("x\n\n");
 }
 
 #[tokio::test]
-async fn vim_half_page_keys_move_the_cursor_by_half_a_viewport() {
-    let contents = (0..40)
-        .map(|line| format!("line-{line:02}"))
-        .collect::<Vec<_>>()
-        .join("\n");
-    let mut harness = EditorHarness::with_config_and_size(
-        Buffer::new(None, contents),
+async fn ctrl_t_and_ctrl_d_shift_the_indent_by_the_shift_width() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "  value\nother\n".to_string()),
         default_key_config(),
-        80,
-        12,
     );
+    type_normal_keys(&mut harness, "A").await;
+    press_modified_key(&mut harness, KeyCode::Char('t'), KeyModifiers::CONTROL).await;
+    harness.assert_buffer_contents("    value\nother\n");
+    press_modified_key(&mut harness, KeyCode::Char('t'), KeyModifiers::CONTROL).await;
+    press_modified_key(&mut harness, KeyCode::Char('d'), KeyModifiers::CONTROL).await;
+    type_normal_keys(&mut harness, "!").await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("    value!\nother\n");
+    type_normal_keys(&mut harness, "j.").await;
+    harness.assert_buffer_contents("    value!\n    other!\n");
+}
 
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('d'),
-            KeyModifiers::CONTROL,
-        )))
-        .await
-        .unwrap();
-    assert_eq!(harness.buffer_line(), 5);
+#[tokio::test]
+async fn ys_and_visual_s_add_delimiters_tags_and_function_calls() {
+    let original = "hello world\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, original.to_string()),
+        default_key_config(),
+    );
+    for (keys, expected) in [
+        ("ysiw)", "(hello) world\n"),
+        ("ysiw(", "( hello ) world\n"),
+        ("ysw\"", "\"hello\" world\n"),
+        ("yss]", "[hello world]\n"),
+        ("ysiw<em>", "<em>hello</em> world\n"),
+        (
+            "wysiwtspan class=\"x\">",
+            "hello <span class=\"x\">world</span>\n",
+        ),
+        ("vllS'", "'hel'lo world\n"),
+        ("VS{", "{\nhello world\n}\n"),
+    ] {
+        type_normal_keys(&mut harness, keys).await;
+        harness.assert_buffer_contents(expected);
+        harness.execute_action(Action::Undo).await.unwrap();
+        harness.assert_buffer_contents(original);
+        type_normal_keys(&mut harness, "0").await;
+    }
 
-    harness
-        .execute_event(Event::Key(KeyEvent::new(
-            KeyCode::Char('u'),
-            KeyModifiers::CONTROL,
-        )))
-        .await
-        .unwrap();
-    assert_eq!(harness.buffer_line(), 0);
+    type_normal_keys(&mut harness, "ysiwFprint").await;
+    execute_unmodified_key(&mut harness, KeyCode::Enter).await;
+    harness.assert_buffer_contents("print( hello ) world\n");
+    harness.execute_action(Action::Undo).await.unwrap();
+
+    type_normal_keys(&mut harness, "ysiw*").await;
+    type_normal_keys(&mut harness, "W.").await;
+    harness.assert_buffer_contents("*hello* *world*\n");
+    harness.execute_action(Action::Undo).await.unwrap();
+    harness.assert_buffer_contents("*hello* world\n");
+
+    type_normal_keys(&mut harness, "ysiwx").await;
+    assert_eq!(harness.last_error(), Some("invalid surround delimiter"));
+    harness.assert_buffer_contents("*hello* world\n");
 }
 
 #[tokio::test]
-async fn structural_text_objects_support_inner_outer_and_linewise_operators() {
-    let contents = "fn first() {\n    alpha();\n    beta();\n}\nfn second() {}\n";
+async fn cs_and_ds_change_and_delete_surrounding_delimiters() {
+    let mut config = default_key_config();
+    config
+        .matchit
+        .pairs
+        .push(["«".to_string(), "»".to_string()]);
     let mut harness = EditorHarness::with_config(
-        Buffer::new(Some("sample.rs".to_string()), contents.to_string()),
-        default_key_config(),
+        Buffer::new(
+            None,
+            "x = ( hi );\nsay \"a\" and \"b\"\n<p><b>bold</b></p>\nv = outer(inner(1), «q»)\n"
+                .to_string(),
+        ),
+        config,
     );
 
-    type_normal_keys(&mut harness, "dif").await;
-    harness.assert_buffer_contents("fn first() {\n    \n}\nfn second() {}\n");
-    type_normal_keys(&mut harness, "u").await;
-    harness.assert_buffer_contents(contents);
+    type_normal_keys(&mut harness, "fhcs(]").await;
+    harness.assert_buffer_contents(
+        "x = [hi];\nsay \"a\" and \"b\"\n<p><b>bold</b></p>\nv = outer(inner(1), «q»)\n",
+    );
+    type_normal_keys(&mut harness, "ds]").await;
+    type_normal_keys(&mut harness, "jfads\"").await;
+    type_normal_keys(&mut harness, "fb.").await;
+    harness.assert_buffer_contents(
+        "x = hi;\nsay a and b\n<p><b>bold</b></p>\nv = outer(inner(1), «q»)\n",
+    );
 
-    type_normal_keys(&mut harness, "daf").await;
-    harness.assert_buffer_contents("fn second() {}\n");
-    type_normal_keys(&mut harness, "u").await;
-    harness.assert_buffer_contents(contents);
+    type_normal_keys(&mut harness, "jfodst").await;
+    harness.assert_buffer_contents("x = hi;\nsay a and b\n<p>bold</p>\nv = outer(inner(1), «q»)\n");
+    type_normal_keys(&mut harness, "cst<div class=\"x\">").await;
+    harness.assert_buffer_contents(
+        "x = hi;\nsay a and b\n<div class=\"x\">bold</div>\nv = outer(inner(1), «q»)\n",
+    );
 
-    type_normal_keys(&mut harness, "yaf]fP").await;
+    type_normal_keys(&mut harness, "jf1dsf").await;
     harness.assert_buffer_contents(
-        "fn first() {\n    alpha();\n    beta();\n}\nfn first() {\n    alpha();\n    beta();\n}\nfn second() {}\n",
+        "x = hi;\nsay a and b\n<div class=\"x\">bold</div>\nv = outer(1, «q»)\n",
     );
+    type_normal_keys(&mut harness, "fqcs»)").await;
+    type_normal_keys(&mut harness, "csbfmax").await;
+    execute_unmodified_key(&mut harness, KeyCode::Enter).await;
+    harness.assert_buffer_contents(
+        "x = hi;\nsay a and b\n<div class=\"x\">bold</div>\nv = outer(1, max(q))\n",
+    );
+
+    type_normal_keys(&mut harness, "ggds(").await;
+    assert_eq!(harness.last_error(), Some("no surrounding ( found"));
 }
 
 #[tokio::test]
-async fn structural_comment_objects_synthesize_rust_inner_capture() {
+async fn cursors_added_below_insert_together_and_undo_as_one_step() {
+    let original = "one\ntwo\nthree\n";
     let mut harness = EditorHarness::with_config(
-        Buffer::new(
-            Some("sample.rs".to_string()),
-            "let café = 1; // comentário\n".to_string(),
-        ),
+        Buffer::new(None, original.to_string()),
         default_key_config(),
     );
-    harness
-        .execute_action(Action::SetCursor(18, 0))
-        .await
-        .unwrap();
 
-    type_normal_keys(&mut harness, "dik").await;
+    press_modified_key(&mut harness, KeyCode::Down, KeyModifiers::CONTROL).await;
+    press_modified_key(&mut harness, KeyCode::Down, KeyModifiers::CONTROL).await;
+    assert_eq!(harness.last_error(), Some("3 cursors"));
+    harness.assert_cursor_at(0, 2);
 
-    harness.assert_buffer_contents("let café = 1; // \n");
+    type_normal_keys(&mut harness, "A!?").await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("one!?\ntwo!?\nthree!?\n");
+    harness.assert_cursor_at(6, 2);
+
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents(original);
+    type_normal_keys(&mut harness, "U").await;
+    harness.assert_buffer_contents("one!?\ntwo!?\nthree!?\n");
+
+    type_normal_keys(&mut harness, "0x").await;
+    harness.assert_buffer_contents("ne!?\nwo!?\nhree!?\n");
+
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    type_normal_keys(&mut harness, "x").await;
+    harness.assert_buffer_contents("ne!?\nwo!?\nree!?\n");
 }
 
 #[tokio::test]
-async fn structural_function_objects_support_change_and_case_operators() {
-    let contents = "fn first() {\n    alpha();\n}\n";
+async fn operators_and_paste_apply_at_every_cursor() {
     let mut harness = EditorHarness::with_config(
-        Buffer::new(Some("sample.rs".to_string()), contents.to_string()),
+        Buffer::new(None, "foo bar\nfoo bar\n".to_string()),
         default_key_config(),
     );
 
-    type_normal_keys(&mut harness, "gUif").await;
-    harness.assert_buffer_contents("fn first() {\n    ALPHA();\n}\n");
-    type_normal_keys(&mut harness, "u").await;
-    harness.assert_buffer_contents(contents);
+    press_modified_key(&mut harness, KeyCode::Down, KeyModifiers::CONTROL).await;
+    type_normal_keys(&mut harness, "dw").await;
+    harness.assert_buffer_contents("bar\nbar\n");
 
-    type_normal_keys(&mut harness, "cifupdated();").await;
-    command_key(&mut harness, KeyCode::Esc).await;
-    harness.assert_buffer_contents("fn first() {\n    updated();\n}\n");
+    type_normal_keys(&mut harness, "$p").await;
+    harness.assert_buffer_contents("barfoo \nbarfoo \n");
     type_normal_keys(&mut harness, "u").await;
-    harness.assert_buffer_contents(contents);
+    harness.assert_buffer_contents("bar\nbar\n");
 }
 
 #[tokio::test]
-async fn structural_operator_motions_accept_prefix_and_motion_counts() {
-    let contents = "// header\nfn first() {}\nfn second() {}\nfn third() {}\n";
-    for keys in ["d2]f", "2d]f"] {
-        let mut harness = EditorHarness::with_config(
-            Buffer::new(Some("sample.rs".to_string()), contents.to_string()),
-            default_key_config(),
-        );
+async fn next_match_cursors_change_whole_word_occurrences() {
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, "let foo = foo_bar + foo;\n".to_string()),
+        default_key_config(),
+    );
 
-        type_normal_keys(&mut harness, keys).await;
+    type_normal_keys(&mut harness, "w").await;
+    press_modified_key(&mut harness, KeyCode::Char('n'), KeyModifiers::CONTROL).await;
+    harness.assert_mode(Mode::Visual);
+    press_modified_key(&mut harness, KeyCode::Char('n'), KeyModifiers::CONTROL).await;
+    assert_eq!(harness.last_error(), Some("2 cursors"));
+    press_modified_key(&mut harness, KeyCode::Char('n'), KeyModifiers::CONTROL).await;
+    assert_eq!(harness.last_error(), Some("no more matches"));
 
-        harness.assert_buffer_contents("fn second() {}\nfn third() {}\n");
-        type_normal_keys(&mut harness, "u").await;
-        harness.assert_buffer_contents(contents);
-    }
+    type_normal_keys(&mut harness, "cvalue").await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("let value = foo_bar + value;\n");
 }
 
 #[tokio::test]
-async fn structural_visual_function_selection_is_linewise() {
-    let contents = "fn first() {\n    alpha();\n}\nfn second() {}\n";
+async fn visual_selection_splits_into_one_selection_per_line() {
     let mut harness = EditorHarness::with_config(
-        Buffer::new(Some("sample.rs".to_string()), contents.to_string()),
+        Buffer::new(None, "alpha\nbeta\ngamma\n".to_string()),
         default_key_config(),
     );
 
-    type_normal_keys(&mut harness, "vaf").await;
-
-    harness.assert_mode(Mode::VisualLine);
-    assert_eq!(harness.selection(), Some((0, 0, 0, 2)));
-    type_normal_keys(&mut harness, "y").await;
+    type_normal_keys(&mut harness, "lvjj").await;
+    press_modified_key(&mut harness, KeyCode::Char('s'), KeyModifiers::ALT).await;
+    assert_eq!(harness.last_error(), Some("3 cursors"));
+    harness.assert_mode(Mode::Visual);
+    type_normal_keys(&mut harness, "U").await;
+    harness.assert_buffer_contents("aLPHA\nBETA\nGAmma\n");
     harness.assert_mode(Mode::Normal);
-    type_normal_keys(&mut harness, "]fP").await;
-    harness.assert_buffer_contents(
-        "fn first() {\n    alpha();\n}\nfn first() {\n    alpha();\n}\nfn second() {}\n",
-    );
 }
 
 #[tokio::test]
-async fn parameter_swaps_preserve_separators_undo_and_dot_repeat() {
-    let original = "fn call(alpha: i32, beta: i32, gamma: i32) {}\n";
+async fn select_matches_command_selects_every_match_in_its_range() {
     let mut harness = EditorHarness::with_config(
-        Buffer::new(Some("sample.rs".to_string()), original.to_string()),
+        Buffer::new(None, "a1 b22\nc333\nd4\n".to_string()),
         default_key_config(),
     );
+
     harness
-        .execute_action(Action::SetCursor(8, 0))
+        .execute_action(Action::Command("1,2SelectMatches \\d+".to_string()))
         .await
         .unwrap();
+    assert_eq!(harness.last_error(), Some("3 cursors"));
+    harness.assert_mode(Mode::Visual);
+    harness.assert_cursor_at(1, 0);
+    type_normal_keys(&mut harness, "c#").await;
+    execute_unmodified_key(&mut harness, KeyCode::Esc).await;
+    harness.assert_buffer_contents("a# b#\nc#\nd4\n");
 
-    type_normal_keys(&mut harness, " ]a").await;
-    harness.assert_buffer_contents("fn call(beta: i32, alpha: i32, gamma: i32) {}\n");
-    type_normal_keys(&mut harness, ".").await;
-    harness.assert_buffer_contents("fn call(beta: i32, gamma: i32, alpha: i32) {}\n");
-    type_normal_keys(&mut harness, "uu").await;
-    harness.assert_buffer_contents(original);
+    harness
+        .execute_action(Action::Command("SelectMatches x".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(harness.last_error(), Some("pattern not found: x"));
 }
 
 #[tokio::test]
-async fn parameter_swaps_record_and_replay_inside_macros() {
-    let original = "fn call(alpha: i32, beta: i32, gamma: i32) {}\n";
+async fn secondary_cursors_render_in_every_window_showing_the_buffer() {
     let mut harness = EditorHarness::with_config(
-        Buffer::new(Some("sample.rs".to_string()), original.to_string()),
+        Buffer::new(None, "first line\nsecond line\n".to_string()),
         default_key_config(),
     );
+
+    type_normal_keys(&mut harness, "w").await;
+    press_modified_key(&mut harness, KeyCode::Down, KeyModifiers::CONTROL).await;
+    execute_window_chord(&mut harness, 'v').await;
+    assert_eq!(harness.window_count(), 2);
+
+    let row = harness.render_row(0).unwrap().chars().collect::<Vec<_>>();
+    let cursors = (0..row.len())
+        .filter(|x| row[*x..].starts_with(&['l', 'i', 'n', 'e']))
+        .collect::<Vec<_>>();
+    assert_eq!(cursors.len(), 2);
+    for x in cursors {
+        assert_ne!(
+            harness.render_cell_bg(x, 0).unwrap(),
+            harness.render_cell_bg(x + 1, 0).unwrap()
+        );
+    }
+}
+
+fn harness_with_files(files: &[(&PathBuf, &str)]) -> EditorHarness {
+    let buffers = files
+        .iter()
+        .map(|(path, contents)| {
+            Buffer::new(
+                Some(path.to_string_lossy().into_owned()),
+                contents.to_string(),
+            )
+        })
+        .collect();
+    let mut editor = Editor::test_with_size(
+        Box::new(MockLsp),
+        /*width*/ 80,
+        /*height*/ 24,
+        Config::default(),
+        Theme::default(),
+        buffers,
+    )
+    .unwrap();
+    editor.test_disable_terminal_output();
+    EditorHarness { editor }
+}
+
+/// Starts the editor the way `red file…` does: the files are loaded as buffers
+/// and handed to the editor as its startup files.
+async fn startup_harness(files: &[String]) -> EditorHarness {
+    let buffers = red::buffer::load_startup_buffers(files).await.unwrap();
+    let config = Config {
+        startup_files: files.to_vec(),
+        ..Config::default()
+    };
+    let mut editor = Editor::test_with_size(
+        Box::new(MockLsp),
+        /*width*/ 80,
+        /*height*/ 24,
+        config,
+        Theme::default(),
+        buffers,
+    )
+    .unwrap();
+    editor.test_disable_terminal_output();
+    EditorHarness { editor }
+}
+
+async fn run_ex(harness: &mut EditorHarness, command: &str) {
+    harness
+        .execute_action(Action::Command(command.to_string()))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn startup_files_seed_the_argument_list() {
+    let directory = tempfile::tempdir().unwrap();
+    let first = directory.path().join("a.txt");
+    let second = directory.path().join("b.txt");
+    fs::write(&first, "a\n").unwrap();
+    fs::write(&second, "b\n").unwrap();
+    let files = [&first, &second].map(|path| path.to_string_lossy().into_owned());
+    let mut harness = startup_harness(&files).await;
+
+    run_ex(&mut harness, "args").await;
+    assert_eq!(
+        harness.last_error(),
+        Some(format!("[{}] {}", files[0], files[1]).as_str())
+    );
+    run_ex(&mut harness, "n").await;
+    assert_eq!(harness.current_buffer_index(), 1);
+    assert_eq!(harness.buffer_names().len(), 2);
+    run_ex(&mut harness, "args").await;
+    assert_eq!(
+        harness.last_error(),
+        Some(format!("{} [{}]", files[0], files[1]).as_str())
+    );
+    run_ex(&mut harness, "next").await;
+    assert_eq!(harness.last_error(), Some("cannot go beyond last file"));
+}
+
+#[tokio::test]
+async fn argdelete_removes_matching_entries_and_the_current_one() {
+    // The names are never written, so they need not exist.
+    let files = ["a.rs", "b.rs", "notes.txt", "c.rs"].map(str::to_string);
+    let mut harness = startup_harness(&files).await;
+
+    run_ex(&mut harness, "argdelete x*").await;
+    assert_eq!(harness.last_error(), Some("no match: x*"));
+    run_ex(&mut harness, "args").await;
+    assert_eq!(harness.last_error(), Some("[a.rs] b.rs notes.txt c.rs"));
+
+    run_ex(&mut harness, "argd b.* c?rs").await;
+    run_ex(&mut harness, "args").await;
+    assert_eq!(harness.last_error(), Some("[a.rs] notes.txt"));
+
+    run_ex(&mut harness, "argdelete %").await;
+    run_ex(&mut harness, "args").await;
+    assert_eq!(harness.last_error(), Some("[notes.txt]"));
+
+    run_ex(&mut harness, "argdelete").await;
+    assert_eq!(harness.last_error(), Some("argdelete requires a pattern"));
+}
+
+fn quickfix_item(path: &Path, line: usize, text: &str) -> QuickfixItem {
+    QuickfixItem {
+        location: PluginLocation {
+            path: path.to_string_lossy().into_owned(),
+            line,
+            column: 0,
+            column_encoding: LocationColumnEncoding::Utf8Byte,
+        },
+        text: text.to_string(),
+    }
+}
+
+async fn set_quickfix_list(
+    harness: &mut EditorHarness,
+    title: &str,
+    items: Vec<QuickfixItem>,
+    location: bool,
+) {
     harness
-        .execute_action(Action::SetCursor(8, 0))
+        .execute_action(Action::SetQuickfixList(QuickfixListSpec {
+            title: title.to_string(),
+            items,
+            location,
+            open: false,
+        }))
         .await
         .unwrap();
+}
 
-    type_normal_keys(&mut harness, "qa ]aq@a").await;
+fn screen_text(harness: &mut EditorHarness) -> String {
+    (0..24)
+        .map(|row| harness.render_row(row).unwrap())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-    harness.assert_buffer_contents("fn call(beta: i32, gamma: i32, alpha: i32) {}\n");
-    type_normal_keys(&mut harness, "uu").await;
-    harness.assert_buffer_contents(original);
+#[tokio::test]
+async fn cdo_runs_its_command_at_each_quickfix_entry_and_updates() {
+    let directory = tempfile::tempdir().unwrap();
+    let first = directory.path().join("a.txt");
+    let second = directory.path().join("b.txt");
+    fs::write(&first, "foo\nfoo\n").unwrap();
+    fs::write(&second, "bar foo\n").unwrap();
+    let mut harness = EditorHarness::with_content("scratch\n");
+    let items = vec![quickfix_item(&first, 1, ""), quickfix_item(&second, 0, "")];
+    set_quickfix_list(&mut harness, "matches", items, false).await;
+
+    run_ex(&mut harness, "cdo s/foo/baz/ | update").await;
+
+    assert_eq!(fs::read_to_string(&first).unwrap(), "foo\nbaz\n");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "bar baz\n");
 }
 
 #[tokio::test]
-async fn parameter_swaps_do_not_cross_argument_containers() {
-    let original = "fn first(alpha: i32) {}\nfn second(beta: i32) {}\n";
+async fn bufdo_update_writes_only_the_buffers_it_changed() {
+    let directory = tempfile::tempdir().unwrap();
+    let first = directory.path().join("a.txt");
+    let second = directory.path().join("b.txt");
+    fs::write(&first, "x\n").unwrap();
+    // The buffer differs from the disk, so a write of it would show.
+    fs::write(&second, "on disk\n").unwrap();
+    let mut harness = harness_with_files(&[(&first, "x\n"), (&second, "y\n")]);
+
+    run_ex(&mut harness, "bufdo s/x/z/ | update").await;
+
+    assert_eq!(fs::read_to_string(&first).unwrap(), "z\n");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "on disk\n");
+    assert!(!harness.is_dirty());
+}
+
+#[tokio::test]
+async fn quickfix_commands_walk_the_list_its_panel_and_its_history() {
+    let directory = tempfile::tempdir().unwrap();
+    let first = directory.path().join("a.txt");
+    let second = directory.path().join("b.txt");
+    fs::write(&first, "one\ntwo\nthree\n").unwrap();
+    fs::write(&second, "four\n").unwrap();
     let mut harness = EditorHarness::with_config(
-        Buffer::new(Some("sample.rs".to_string()), original.to_string()),
+        Buffer::new(None, "scratch\n".to_string()),
         default_key_config(),
     );
-    harness
-        .execute_action(Action::SetCursor(9, 0))
-        .await
-        .unwrap();
+    let items = vec![
+        quickfix_item(&first, 1, "first"),
+        quickfix_item(&first, 2, "second"),
+        quickfix_item(&second, 0, "third"),
+    ];
+    set_quickfix_list(&mut harness, "build", items, false).await;
 
-    type_normal_keys(&mut harness, " ]a").await;
+    run_ex(&mut harness, "cc").await;
+    assert_eq!(harness.last_error(), Some("(1 of 3) first"));
+    assert_eq!(harness.buffer_line(), 1);
+    run_ex(&mut harness, "cnext").await;
+    assert_eq!(harness.last_error(), Some("(2 of 3) second"));
+    assert_eq!(harness.buffer_line(), 2);
+    run_ex(&mut harness, "cn").await;
+    assert_eq!(harness.last_error(), Some("(3 of 3) third"));
+    assert!(harness
+        .buffer_names()
+        .iter()
+        .any(|name| name.ends_with("b.txt")));
+    run_ex(&mut harness, "cnext").await;
+    assert_eq!(harness.last_error(), Some("no more items"));
+    run_ex(&mut harness, "cprev").await;
+    assert_eq!(harness.last_error(), Some("(2 of 3) second"));
+    assert_eq!(harness.buffer_line(), 2);
+
+    run_ex(&mut harness, "copen").await;
+    let screen = screen_text(&mut harness);
+    assert!(screen.contains("Quickfix List: build"), "{screen}");
+    assert!(screen.contains(":3:1 second"), "{screen}");
+
+    // Setting more than ten lists drops the oldest.
+    for number in 1..=11 {
+        let items = vec![quickfix_item(&second, 0, &format!("entry {number}"))];
+        set_quickfix_list(&mut harness, &format!("list {number}"), items, false).await;
+    }
+    run_ex(&mut harness, "colder 20").await;
+    assert_eq!(
+        harness.last_error(),
+        Some("quickfix list 1 of 10; 1 entry: list 2")
+    );
+    assert!(screen_text(&mut harness).contains("Quickfix List: list 2"));
+    run_ex(&mut harness, "colder").await;
+    assert_eq!(
+        harness.last_error(),
+        Some("at bottom of quickfix list stack")
+    );
+    run_ex(&mut harness, "cnewer 2").await;
+    assert_eq!(
+        harness.last_error(),
+        Some("quickfix list 3 of 10; 1 entry: list 4")
+    );
+    run_ex(&mut harness, "cnewer 20").await;
+    assert_eq!(
+        harness.last_error(),
+        Some("quickfix list 10 of 10; 1 entry: list 11")
+    );
 
-    harness.assert_buffer_contents(original);
-    assert_eq!(harness.last_error(), Some("adjacent text object not found"));
+    run_ex(&mut harness, "cclose").await;
+    assert!(!screen_text(&mut harness).contains("Quickfix List"));
 }
 
 #[tokio::test]
-async fn function_swaps_preserve_scope_multiline_ranges_and_jump_history() {
-    let original = "fn first() {\n    one();\n}\nfn second() { two(); }\n";
+async fn location_lists_belong_to_the_window_that_set_them() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("a.txt");
+    fs::write(&path, "one\ntwo\n").unwrap();
     let mut harness = EditorHarness::with_config(
-        Buffer::new(Some("sample.rs".to_string()), original.to_string()),
+        Buffer::new(None, "scratch\n".to_string()),
         default_key_config(),
     );
+    execute_window_chord(&mut harness, 'v').await;
+    let owner = harness.active_window_id();
+    let items = vec![quickfix_item(&path, 1, "in the owner")];
+    set_quickfix_list(&mut harness, "local", items, true).await;
+    let items = vec![quickfix_item(&path, 0, "shared")];
+    set_quickfix_list(&mut harness, "global", items, false).await;
+
+    execute_window_chord(&mut harness, 'w').await;
+    assert_ne!(harness.active_window_id(), owner);
+    run_ex(&mut harness, "ll").await;
+    assert_eq!(harness.last_error(), Some("no location list"));
+    run_ex(&mut harness, "lopen").await;
+    assert_eq!(harness.last_error(), Some("no location list"));
+    run_ex(&mut harness, "cc").await;
+    assert_eq!(harness.last_error(), Some("(1 of 1) shared"));
+
+    execute_window_chord(&mut harness, 'w').await;
+    assert_eq!(harness.active_window_id(), owner);
+    run_ex(&mut harness, "ll").await;
+    assert_eq!(harness.last_error(), Some("(1 of 1) in the owner"));
+    assert_eq!(harness.buffer_line(), 1);
+    run_ex(&mut harness, "lopen").await;
+    let screen = screen_text(&mut harness);
+    assert!(screen.contains("Location List: local"), "{screen}");
+    assert!(screen.contains(":2:1 in the owner"), "{screen}");
+}
 
-    type_normal_keys(&mut harness, " ]m").await;
-    harness.assert_buffer_contents("fn second() { two(); }\nfn first() {\n    one();\n}\n");
-    harness.assert_cursor_at(0, 1);
-    harness.execute_action(Action::JumpBack).await.unwrap();
-    harness.assert_cursor_at(0, 0);
-    type_normal_keys(&mut harness, "u").await;
-    harness.assert_buffer_contents(original);
+#[tokio::test]
+async fn quickfix_entries_stay_on_their_text_as_lines_change_above_them() {
+    let directory = tempfile::tempdir().unwrap();
+    let loaded = directory.path().join("a.txt");
+    let unloaded = directory.path().join("b.txt");
+    fs::write(&loaded, "one\ntwo\nthree\n").unwrap();
+    fs::write(&unloaded, "four\nfive\n").unwrap();
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(
+            Some(loaded.to_string_lossy().into_owned()),
+            "one\ntwo\nthree\n".to_string(),
+        ),
+        default_key_config(),
+    );
+    let items = vec![
+        quickfix_item(&loaded, 2, "at three"),
+        quickfix_item(&unloaded, 1, "at five"),
+    ];
+    set_quickfix_list(&mut harness, "", items, false).await;
+
+    type_normal_keys(&mut harness, "ggOnew").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    type_normal_keys(&mut harness, "Oother").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    run_ex(&mut harness, "cc").await;
+    assert_eq!(harness.buffer_line(), 4);
+    assert_eq!(harness.current_line().as_deref(), Some("three\n"));
+    run_ex(&mut harness, "copen").await;
+    assert!(screen_text(&mut harness).contains(":5:1 at three"));
+    run_ex(&mut harness, "cclose").await;
+
+    type_normal_keys(&mut harness, "ggdd").await;
+    run_ex(&mut harness, "cc").await;
+    assert_eq!(harness.buffer_line(), 3);
+
+    // An entry in a file that was not open binds to it when it is opened.
+    run_ex(&mut harness, &format!("e {}", unloaded.display())).await;
+    type_normal_keys(&mut harness, "ggOzero").await;
+    command_key(&mut harness, KeyCode::Esc).await;
+    run_ex(&mut harness, "cn").await;
+    assert_eq!(harness.last_error(), Some("(2 of 2) at five"));
+    assert_eq!(harness.buffer_line(), 2);
+    assert_eq!(harness.current_line().as_deref(), Some("five\n"));
 }
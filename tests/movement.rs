@@ -5,7 +5,7 @@ use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, Mouse
 use red::{
     buffer::Buffer,
     color::Color,
-    config::{Config, KeyAction},
+    config::{Config, KeyAction, SearchDialect},
     editor::{Action, Editor, Mode, SearchDirection},
     theme::Style,
 };
@@ -1195,6 +1195,49 @@ async fn search_uses_rust_regex_and_case_options() {
     harness.assert_cursor_at(0, 2);
 }
 
+#[tokio::test]
+async fn search_uses_vim_patterns_when_the_dialect_is_vim() {
+    let mut config = Config::default();
+    config.search.dialect = SearchDialect::Vim;
+    config.search.ignorecase = true;
+    config.search.smartcase = true;
+    let buffer = Buffer::new(None, "start\nfoobar foo\nFoo(12) x=3".to_string());
+    let mut harness = EditorHarness::with_config(buffer, config);
+
+    for (pattern, x, y) in [
+        (r"\<foo\>", 7, 1),
+        (r"\v(ba)+r", 3, 1),
+        (r"foo\zsbar", 3, 1),
+        (r"\CFoo(\zs\d\{2}", 4, 2),
+        (r"\Vx=3", 8, 2),
+        (r"\S\+\ze=", 8, 2),
+    ] {
+        harness.execute_action(Action::MoveToTop).await.unwrap();
+        harness
+            .execute_action(Action::EnterSearch(SearchDirection::Forward))
+            .await
+            .unwrap();
+        type_normal_keys(&mut harness, pattern).await;
+        harness.assert_cursor_at(x, y);
+        harness
+            .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+            .await
+            .unwrap();
+    }
+
+    harness
+        .execute_action(Action::EnterSearch(SearchDirection::Forward))
+        .await
+        .unwrap();
+    type_normal_keys(&mut harness, r"foo\(bar\)\@=").await;
+    assert_eq!(
+        harness.last_error(),
+        Some(
+            r"invalid search pattern: lookahead \@= is not supported; end the match with \ze instead at column 11"
+        )
+    );
+}
+
 #[tokio::test]
 async fn search_preview_and_highlight_handle_wide_prefix_text() {
     let mut harness = EditorHarness::with_content("👋 alpha\nplain alpha");
@@ -2394,3 +2437,134 @@ async fn structural_motions_respect_disabled_syntax_and_no_wrap_boundaries() {
     harness.assert_cursor_at(0, 0);
     assert_eq!(harness.last_error(), Some("text object not found"));
 }
+
+fn folded_row_shown(harness: &mut EditorHarness, placeholder: &str) -> bool {
+    (0..6).any(|y| harness.render_row(y).unwrap().contains(placeholder))
+}
+
+#[tokio::test]
+async fn manual_folds_open_close_and_count_as_one_row() {
+    let contents = "one\ntwo\nthree\nfour\nfive\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, contents.to_string()),
+        default_key_config(),
+    );
+
+    type_normal_keys(&mut harness, "jzfj").await;
+    assert_eq!(harness.buffer_line(), 1);
+    assert!(folded_row_shown(&mut harness, "+--  2 lines: two"));
+
+    type_normal_keys(&mut harness, "j").await;
+    assert_eq!(harness.buffer_line(), 3);
+    type_normal_keys(&mut harness, "k").await;
+    assert_eq!(harness.buffer_line(), 1);
+
+    type_normal_keys(&mut harness, "zoj").await;
+    assert_eq!(harness.buffer_line(), 2);
+    assert!(!folded_row_shown(&mut harness, "+--"));
+
+    type_normal_keys(&mut harness, "zc").await;
+    assert_eq!(harness.buffer_line(), 1);
+    type_normal_keys(&mut harness, "zajzM").await;
+    assert_eq!(harness.buffer_line(), 1);
+    type_normal_keys(&mut harness, "zRjj").await;
+    assert_eq!(harness.buffer_line(), 3);
+
+    type_normal_keys(&mut harness, "kzdzc").await;
+    assert_eq!(harness.last_error(), Some("no fold found"));
+}
+
+#[tokio::test]
+async fn searches_open_folds_and_fold_motions_skip_between_folds() {
+    let contents = "alpha\nbeta\nalpha\ngamma\ndelta\nepsilon\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, contents.to_string()),
+        default_key_config(),
+    );
+
+    type_normal_keys(&mut harness, "jzfjGkzfjgg*").await;
+    assert_eq!(harness.buffer_line(), 2);
+    type_normal_keys(&mut harness, "k").await;
+    assert_eq!(harness.buffer_line(), 1);
+
+    type_normal_keys(&mut harness, "ggzj").await;
+    assert_eq!(harness.buffer_line(), 1);
+    type_normal_keys(&mut harness, "zj").await;
+    assert_eq!(harness.buffer_line(), 4);
+    type_normal_keys(&mut harness, "zk").await;
+    assert_eq!(harness.buffer_line(), 2);
+}
+
+#[tokio::test]
+async fn folds_follow_inserted_lines() {
+    let contents = "a\nb\nc\nd\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, contents.to_string()),
+        default_key_config(),
+    );
+
+    type_normal_keys(&mut harness, "jzfjggOnew").await;
+    harness
+        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+        .await
+        .unwrap();
+    type_normal_keys(&mut harness, "jjj").await;
+
+    assert_eq!(harness.buffer_line(), 4);
+    assert!(folded_row_shown(&mut harness, "+--  2 lines: b"));
+}
+
+#[tokio::test]
+async fn folds_follow_undo_and_redo_of_inserted_lines() {
+    let contents = "a\nb\nc\nd\ne\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(None, contents.to_string()),
+        default_key_config(),
+    );
+
+    type_normal_keys(&mut harness, "jzfjggOnew").await;
+    harness
+        .execute_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+        .await
+        .unwrap();
+
+    type_normal_keys(&mut harness, "u").await;
+    harness.assert_buffer_contents(contents);
+    type_normal_keys(&mut harness, "ggj").await;
+    assert_eq!(harness.buffer_line(), 1);
+    type_normal_keys(&mut harness, "j").await;
+    assert_eq!(harness.buffer_line(), 3);
+    assert!(folded_row_shown(&mut harness, "+--  2 lines: b"));
+
+    harness
+        .execute_event(Event::Key(KeyEvent::new(
+            KeyCode::Char('r'),
+            KeyModifiers::CONTROL,
+        )))
+        .await
+        .unwrap();
+    harness.assert_buffer_contents(&format!("new\n{contents}"));
+    type_normal_keys(&mut harness, "ggjj").await;
+    assert_eq!(harness.buffer_line(), 2);
+    type_normal_keys(&mut harness, "j").await;
+    assert_eq!(harness.buffer_line(), 4);
+    assert!(folded_row_shown(&mut harness, "+--  2 lines: b"));
+}
+
+#[tokio::test]
+async fn close_all_folds_uses_tree_sitter_ranges_without_a_language_server() {
+    let contents = "fn first() {\n    one();\n}\n\nfn second() {\n    two();\n}\n";
+    let mut harness = EditorHarness::with_config(
+        Buffer::new(Some("sample.rs".to_string()), contents.to_string()),
+        default_key_config(),
+    );
+
+    type_normal_keys(&mut harness, "zMj").await;
+    assert_eq!(harness.buffer_line(), 3);
+    type_normal_keys(&mut harness, "j").await;
+    assert_eq!(harness.buffer_line(), 4);
+    assert!(folded_row_shown(
+        &mut harness,
+        "+--  3 lines: fn second() {"
+    ));
+}
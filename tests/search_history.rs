@@ -4,7 +4,7 @@ use common::{EditorHarness, MockLsp};
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use red::{
     buffer::Buffer,
-    config::Config,
+    config::{Config, SearchDialect},
     editor::{Action, Editor, Mode},
     preferences::PreferencesStore,
     theme::Theme,
@@ -248,3 +248,17 @@ async fn submitted_searches_persist_but_cancelled_and_empty_drafts_do_not() {
         assert_eq!(reopened.commandline_text(), expected);
     }
 }
+
+#[tokio::test]
+async fn star_searches_follow_the_dialect_and_only_vim_enters_the_history() {
+    for (dialect, expected) in [(SearchDialect::Rust, ""), (SearchDialect::Vim, r"\<foo\>")] {
+        let mut config = default_config();
+        config.search.dialect = dialect;
+        let mut harness = with_preferences("foo foobar foo", config, PreferencesStore::in_memory());
+        key(&mut harness, KeyCode::Char('*')).await;
+        harness.assert_cursor_at(if dialect == SearchDialect::Vim { 11 } else { 4 }, 0);
+        start(&mut harness, '/').await;
+        key(&mut harness, KeyCode::Up).await;
+        assert_eq!(harness.commandline_text(), expected);
+    }
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use common::EditorHarness;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use red::{
+    buffer::Buffer,
+    config::Config,
+    editor::{Action, Point},
+    plugin::PanelConfig,
+};
+
+fn harness() -> EditorHarness {
+    let config: Config = toml::from_str(include_str!("../default_config.toml")).unwrap();
+    EditorHarness::with_config(Buffer::new(None, "first page\n".to_string()), config)
+}
+
+async fn keys(harness: &mut EditorHarness, keys: &str) {
+    for character in keys.chars() {
+        harness
+            .execute_event(Event::Key(KeyEvent::new(
+                KeyCode::Char(character),
+                KeyModifiers::NONE,
+            )))
+            .await
+            .unwrap();
+    }
+}
+
+async fn command(harness: &mut EditorHarness, command: &str) {
+    harness
+        .execute_action(Action::Command(command.to_string()))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn tab_pages_keep_separate_window_layouts() {
+    let mut editor = harness();
+    editor.execute_action(Action::SplitVertical).await.unwrap();
+    assert_eq!(editor.editor.test_window_count(), 2);
+    assert!(!editor.render_row(0).unwrap().contains("[No Name]"));
+
+    command(&mut editor, "tabnew").await;
+    assert_eq!(editor.editor.test_tab_page_count(), 2);
+    assert_eq!(editor.editor.test_current_tab_page(), 1);
+    assert_eq!(editor.editor.test_window_count(), 1);
+    assert_eq!(editor.buffer_contents(), "\n");
+    assert_eq!(
+        editor.editor.test_active_window_bounds(),
+        Some((Point::new(0, 1), (80, 21)))
+    );
+    let tab_line = editor.render_row(0).unwrap();
+    assert!(
+        tab_line.starts_with(" 2 [No Name]  [No Name] "),
+        "{tab_line}"
+    );
+
+    keys(&mut editor, "gt").await;
+    assert_eq!(editor.editor.test_current_tab_page(), 0);
+    assert_eq!(editor.editor.test_window_count(), 2);
+    assert_eq!(editor.buffer_contents(), "first page\n");
+    keys(&mut editor, "gT").await;
+    assert_eq!(editor.editor.test_current_tab_page(), 1);
+
+    command(&mut editor, "tabclose").await;
+    assert_eq!(editor.editor.test_tab_page_count(), 1);
+    assert_eq!(editor.editor.test_window_count(), 2);
+    assert_eq!(editor.buffer_names().len(), 2);
+    assert!(editor.render_row(0).unwrap().contains("first page"));
+    command(&mut editor, "tabclose").await;
+    assert_eq!(editor.last_error(), Some("cannot close the last tab page"));
+}
+
+#[tokio::test]
+async fn counts_moves_and_tabonly_reorder_pages() {
+    let mut editor = harness();
+    command(&mut editor, "tabnew").await;
+    command(&mut editor, "tabnew").await;
+    assert_eq!(editor.editor.test_current_tab_page(), 2);
+
+    keys(&mut editor, "1gt").await;
+    assert_eq!(editor.editor.test_current_tab_page(), 0);
+    assert_eq!(editor.buffer_contents(), "first page\n");
+    keys(&mut editor, "gT").await;
+    assert_eq!(editor.editor.test_current_tab_page(), 2);
+
+    keys(&mut editor, "1gt").await;
+    command(&mut editor, "tabmove").await;
+    assert_eq!(editor.editor.test_current_tab_page(), 2);
+    assert_eq!(editor.buffer_contents(), "first page\n");
+    command(&mut editor, "tabmove -1").await;
+    assert_eq!(editor.editor.test_current_tab_page(), 1);
+    command(&mut editor, "tabmove 0").await;
+    assert_eq!(editor.editor.test_current_tab_page(), 0);
+
+    command(&mut editor, "tabonly").await;
+    assert_eq!(editor.editor.test_tab_page_count(), 1);
+    assert_eq!(editor.buffer_contents(), "first page\n");
+    assert_eq!(
+        editor.editor.test_active_window_bounds(),
+        Some((Point::new(0, 0), (80, 22)))
+    );
+}
+
+#[tokio::test]
+async fn closing_the_last_window_of_a_page_closes_the_page() {
+    let mut editor = harness();
+    command(&mut editor, "tabnew").await;
+    editor.execute_action(Action::CloseWindow).await.unwrap();
+    assert_eq!(editor.editor.test_tab_page_count(), 1);
+    assert_eq!(editor.buffer_contents(), "first page\n");
+}
+
+#[tokio::test]
+async fn docked_panels_stay_below_the_tab_line_on_every_page() {
+    let mut editor = harness();
+    editor.editor.test_create_panel(
+        "tree",
+        PanelConfig {
+            title: Some("tree-marker".to_string()),
+            width: 20,
+            ..PanelConfig::default()
+        },
+    );
+    editor.execute_action(Action::Refresh).await.unwrap();
+    assert!(editor.render_row(0).unwrap().contains("tree-marker"));
+
+    command(&mut editor, "tabnew").await;
+    assert!(!editor.render_row(0).unwrap().contains("tree-marker"));
+    assert!(editor.render_row(1).unwrap().contains("tree-marker"));
+    let (position, _) = editor.editor.test_active_window_bounds().unwrap();
+    assert_eq!(position.y, 1);
+    assert!(position.x >= 20);
+
+    keys(&mut editor, "gt").await;
+    assert!(editor.render_row(1).unwrap().contains("tree-marker"));
+    assert!(editor.editor.test_active_window_bounds().unwrap().0.x >= 20);
+}